@@ -1,7 +1,7 @@
 use super::{
     inputs::{deserialize_inputs, CommandInputParameter, CommandLineBinding},
     outputs::{deserialize_outputs, CommandOutputParameter},
-    requirements::{deserialize_requirements, Requirement},
+    requirements::{deserialize_requirements, InlineJavascriptRequirement, LoadListingRequirement, Requirement},
     types::CWLType,
 };
 use core::fmt;
@@ -12,7 +12,7 @@ use std::fmt::Display;
 /// non-interactive program which is invoked on some input, produces output, and then terminates.
 ///
 /// Reference: [CWL CommandLineTool Specification](https://www.commonwl.org/v1.2/CommandLineTool.html)
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CommandLineTool {
     pub class: String,
@@ -130,6 +130,39 @@ impl CommandLineTool {
         }
     }
 
+    /// Checks whether a DockerRequirement is given in requirements or hints
+    pub fn has_docker_requirement(&self) -> bool {
+        self.requirements
+            .iter()
+            .chain(self.hints.iter())
+            .flatten()
+            .any(|req| matches!(req, Requirement::DockerRequirement(_)))
+    }
+
+    /// Returns the InlineJavascriptRequirement if given in requirements or hints
+    pub fn get_inline_javascript_requirement(&self) -> Option<&InlineJavascriptRequirement> {
+        self.requirements
+            .iter()
+            .chain(self.hints.iter())
+            .flatten()
+            .find_map(|req| match req {
+                Requirement::InlineJavascriptRequirement(js_req) => Some(js_req),
+                _ => None,
+            })
+    }
+
+    /// Returns the LoadListingRequirement if given in requirements or hints
+    pub fn get_load_listing_requirement(&self) -> Option<&LoadListingRequirement> {
+        self.requirements
+            .iter()
+            .chain(self.hints.iter())
+            .flatten()
+            .find_map(|req| match req {
+                Requirement::LoadListingRequirement(listing_req) => Some(listing_req),
+                _ => None,
+            })
+    }
+
     pub fn get_error_code(&self) -> i32 {
         if let Some(code) = &self.permanent_fail_codes {
             code[0]
@@ -223,6 +256,7 @@ baseCommand:
             .with_inputs(vec![CommandInputParameter {
                 id: "la".to_string(),
                 type_: CWLType::Boolean,
+                doc: None,
                 input_binding: Some(CommandLineBinding {
                     prefix: Some("-la".to_string()),
                     position: None,
@@ -231,6 +265,9 @@ baseCommand:
                 }),
                 default: None,
                 format: None,
+                load_contents: None,
+                load_listing: None,
+                secondary_files: None,
             }])
             .with_outputs(vec![]);
         let result = serde_yaml::to_string(&tool);
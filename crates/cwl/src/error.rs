@@ -0,0 +1,95 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Errors occurring while loading CWL documents
+#[derive(Debug, Error)]
+pub enum CWLError {
+    #[error("Could not read {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// Invalid YAML or a document not matching the CWL schema, the location is only known for YAML syntax errors
+    #[error("Could not parse {}: {message}", describe_location(.path.as_deref(), *.line, *.column))]
+    Parse {
+        path: Option<PathBuf>,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// A field not defined by the CWL specification, only reported in strict parse mode
+    #[error("Unknown field `{field}` in {context}")]
+    UnknownField { field: String, context: String },
+    #[error("Unknown class `{found}`, expected {expected}")]
+    UnknownClass { found: String, expected: String },
+    #[error("CWL version {0} is not supported")]
+    UnsupportedVersion(String),
+    /// A document of a YAML stream was addressed by an index or id that does not exist
+    #[error("Document `{0}` not found in YAML stream")]
+    UnknownDocument(String),
+    #[error("YAML stream contains {0} documents, select one by index or id using `<file>#<index|id>`")]
+    AmbiguousDocument(usize),
+}
+
+impl CWLError {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        CWLError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// Sets the path of the document a parse error occurred in, other errors are returned unchanged
+    pub fn with_path(self, document: impl AsRef<Path>) -> Self {
+        match self {
+            CWLError::Parse { line, column, message, .. } => CWLError::Parse {
+                path: Some(document.as_ref().to_path_buf()),
+                line,
+                column,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for CWLError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let location = error.location();
+        CWLError::Parse {
+            path: None,
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            message: error.to_string(),
+        }
+    }
+}
+
+fn describe_location(path: Option<&Path>, line: Option<usize>, column: Option<usize>) -> String {
+    let document = path.map_or("CWL document".to_string(), |p| format!("{p:?}"));
+    match (line, column) {
+        (Some(line), Some(column)) => format!("{document} at line {line}, column {column}"),
+        (Some(line), None) => format!("{document} at line {line}"),
+        _ => document,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clt::CommandLineTool;
+
+    #[test]
+    fn test_parse_error_location() {
+        let error: CWLError = serde_yaml::from_str::<CommandLineTool>("class: CommandLineTool\ninputs: [\n").unwrap_err().into();
+        let CWLError::Parse { line, column, .. } = &error else {
+            panic!("expected parse error, got {error:?}");
+        };
+        assert!(line.is_some() && column.is_some());
+        assert!(error.with_path("tool.cwl").to_string().starts_with("Could not parse \"tool.cwl\" at line "));
+    }
+}
@@ -1,7 +1,7 @@
 use serde_yaml::{Mapping, Value};
 use std::{collections::HashMap, error::Error};
 
-const HASH_BANG: &str = "#!/usr/bin/env cwl-runner\n\n";
+const HASH_BANG: &str = "#!/usr/bin/env cwl-runner";
 const HASH_BANG_PRE: &str = "#!/usr/bin/env ";
 const KEYS_WITH_NEWLINES: [&str; 7] = ["inputs", "outputs", "steps", "requirements", "hints", "baseCommand", "$schemas"];
 
@@ -18,6 +18,19 @@ pub fn format_cwl(raw_cwl: &str) -> Result<String, Box<dyn Error>> {
     Ok(format!("{}{}", comment, formatted_cwl))
 }
 
+/// Whether the document uses YAML anchors, aliases or merge keys. Aliases are expanded when a document is serialized
+/// again, merge keys are kept but the merged mapping is written inline.
+pub fn has_anchors(raw_cwl: &str) -> bool {
+    raw_cwl.lines().any(|line| {
+        let line = line.trim_start().trim_start_matches("- ");
+        line.starts_with("<<:")
+            || line.starts_with(['&', '*'])
+            || line
+                .split_once(": ")
+                .is_some_and(|(_, value)| value.trim_start().starts_with(['&', '*']))
+    })
+}
+
 fn format_node(cwl: &Value) -> Value {
     match cwl {
         Value::Mapping(map) => {
@@ -113,6 +126,7 @@ fn get_key_order() -> HashMap<&'static str, Vec<&'static str>> {
             "source",
             "outputSource",
             "linkMerge",
+            "pickValue",
         ],
     );
 
@@ -161,6 +175,7 @@ fn get_key_order() -> HashMap<&'static str, Vec<&'static str>> {
     key_order_dict
 }
 
+/// Keeps the comments at the top of the document, which are always followed by a single empty line
 fn add_leading_comment(raw_cwl: &str) -> String {
     let mut top_comment = Vec::new();
 
@@ -168,18 +183,24 @@ fn add_leading_comment(raw_cwl: &str) -> String {
         for line in raw_cwl.lines() {
             let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
-                top_comment.push(line.to_string());
+                top_comment.push(line);
             } else {
                 break;
             }
         }
     }
+    while top_comment.last().is_some_and(|line| line.is_empty()) {
+        top_comment.pop();
+    }
 
-    if top_comment.is_empty() || !top_comment[0].starts_with(HASH_BANG_PRE) {
-        top_comment.insert(0, HASH_BANG.to_string());
+    if !top_comment.first().is_some_and(|line| line.starts_with(HASH_BANG_PRE)) {
+        if !top_comment.is_empty() {
+            top_comment.insert(0, "");
+        }
+        top_comment.insert(0, HASH_BANG);
     }
 
-    top_comment.join("\n")
+    format!("{}\n\n", top_comment.join("\n"))
 }
 
 fn add_space_between_main_sections(raw_cwl: &str) -> String {
@@ -202,3 +223,28 @@ fn add_space_between_main_sections(raw_cwl: &str) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_anchors() {
+        assert!(has_anchors("inputs:\n  first: &input\n    type: string\n  second: *input\n"));
+        assert!(has_anchors("inputs:\n  second:\n    <<: *input\n"));
+        assert!(has_anchors("baseCommand:\n- *command\n"));
+        assert!(!has_anchors("outputs:\n  out:\n    outputBinding:\n      glob: '*.txt'\n"));
+    }
+
+    #[test]
+    fn test_format_cwl_is_idempotent() {
+        let raw = "# copyright\ninputs: []\nclass: CommandLineTool\nbaseCommand: echo\noutputs: []\n";
+        let formatted = format_cwl(raw).unwrap();
+        assert!(formatted.starts_with("#!/usr/bin/env cwl-runner\n\n# copyright\n\nclass: CommandLineTool\n"));
+        assert_eq!(format_cwl(&formatted).unwrap(), formatted);
+
+        let formatted = format_cwl("class: Workflow").unwrap();
+        assert_eq!(formatted, "#!/usr/bin/env cwl-runner\n\nclass: Workflow\n");
+        assert_eq!(format_cwl(&formatted).unwrap(), formatted);
+    }
+}
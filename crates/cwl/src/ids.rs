@@ -0,0 +1,203 @@
+use serde_yaml::{Mapping, Value};
+
+/// Normalizes the identifiers of a CWL document in place following the schema salad rules. Identifiers like
+/// `#main/step1/out` or `file:///wf.cwl#main/input` written by `cwltool --pack` or other tools are shortened relative
+/// to the process or step they are defined in, so that `#main/input` becomes `input` and a source `#main/step1/out`
+/// becomes `step1/out`. Documents containing a `$graph` have all of their processes normalized.
+pub fn normalize_ids(document: &mut Value) {
+    if let Some(Value::Sequence(graph)) = document.get_mut("$graph") {
+        for process in graph {
+            normalize_process(process);
+        }
+    } else {
+        normalize_process(document);
+    }
+}
+
+/// Shortens an identifier relative to the given scope. Absolute identifiers are reduced to their fragment first.
+pub fn shorten_id(id: &str, scope: &str) -> String {
+    let fragment = id.split_once('#').map_or(id, |(_, fragment)| fragment);
+    match fragment.strip_prefix(scope).and_then(|rest| rest.strip_prefix('/')) {
+        Some(rest) if !scope.is_empty() => rest.to_string(),
+        _ => fragment.to_string(),
+    }
+}
+
+/// The scope entries of a process or step are named relative to
+fn get_scope(id: Option<&str>, parent_scope: &str) -> String {
+    match id {
+        Some(id) if id.contains('#') => shorten_id(id, ""),
+        Some(id) if !parent_scope.is_empty() => format!("{parent_scope}/{id}"),
+        Some(id) => id.to_string(),
+        None => parent_scope.to_string(),
+    }
+}
+
+fn normalize_process(process: &mut Value) {
+    let scope = get_scope(process.get("id").and_then(Value::as_str), "");
+    for key in ["inputs", "outputs"] {
+        if let Some(entries) = process.get_mut(key) {
+            normalize_entries(entries, &scope);
+        }
+    }
+    if let Some(Value::Sequence(outputs)) = process.get_mut("outputs") {
+        for output in outputs {
+            shorten_references(output.get_mut("outputSource"), &scope);
+        }
+    } else if let Some(Value::Mapping(outputs)) = process.get_mut("outputs") {
+        for output in outputs.values_mut() {
+            shorten_references(output.get_mut("outputSource"), &scope);
+        }
+    }
+
+    let Some(steps) = process.get_mut("steps") else {
+        return;
+    };
+    //step ids are needed to know the scope of their inputs and outputs, so they are kept in their original form first
+    let steps: Vec<(String, &mut Value)> = match steps {
+        Value::Sequence(steps) => steps
+            .iter_mut()
+            .map(|step| (step.get("id").and_then(Value::as_str).unwrap_or_default().to_string(), step))
+            .collect(),
+        Value::Mapping(steps) => steps.iter_mut().map(|(id, step)| (id.as_str().unwrap_or_default().to_string(), step)).collect(),
+        _ => vec![],
+    };
+    for (id, step) in steps {
+        normalize_step(step, &get_scope(Some(&id), &scope), &scope);
+    }
+    if let Some(steps) = process.get_mut("steps") {
+        normalize_entries(steps, &scope);
+    }
+}
+
+fn normalize_step(step: &mut Value, step_scope: &str, scope: &str) {
+    if let Some(inputs) = step.get_mut("in") {
+        normalize_entries(inputs, step_scope);
+        //cwltool writes step inputs as list, which is turned into the map form used by s4n
+        if let Value::Sequence(items) = inputs {
+            let mut map = Mapping::new();
+            for mut item in items.drain(..) {
+                if let Value::Mapping(entry) = &mut item {
+                    if let Some(id) = entry.remove("id") {
+                        map.insert(id, item);
+                    }
+                }
+            }
+            *inputs = Value::Mapping(map);
+        }
+        if let Value::Mapping(inputs) = inputs {
+            for input in inputs.values_mut() {
+                match input {
+                    Value::Mapping(_) => shorten_references(input.get_mut("source"), scope),
+                    _ => shorten_references(Some(input), scope),
+                }
+            }
+        }
+    }
+    if let Some(Value::Sequence(outputs)) = step.get_mut("out") {
+        for output in outputs {
+            //outputs can be given as `{id: out}` as well
+            if let Some(id) = output.get("id").cloned() {
+                *output = id;
+            }
+            shorten_references(Some(output), step_scope);
+        }
+    }
+    shorten_references(step.get_mut("scatter"), step_scope);
+    if let Some(run) = step.get_mut("run").filter(|run| run.is_mapping()) {
+        normalize_process(run);
+    }
+}
+
+/// Shortens the ids of a list of entries given either as sequence of mappings with an `id` or as mapping from id to entry
+fn normalize_entries(entries: &mut Value, scope: &str) {
+    match entries {
+        Value::Sequence(items) => {
+            for item in items {
+                if let Some(Value::String(id)) = item.get_mut("id") {
+                    *id = shorten_id(id, scope);
+                }
+            }
+        }
+        Value::Mapping(items) => {
+            *items = std::mem::take(items)
+                .into_iter()
+                .map(|(id, item)| match id {
+                    Value::String(id) => (Value::String(shorten_id(&id, scope)), item),
+                    id => (id, item),
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+/// Shortens a single reference or a list of references like `source`, `outputSource` or `scatter`
+fn shorten_references(references: Option<&mut Value>, scope: &str) {
+    match references {
+        Some(Value::String(reference)) => *reference = shorten_id(reference, scope),
+        Some(Value::Sequence(references)) => {
+            for reference in references {
+                shorten_references(Some(reference), scope);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wf::Workflow;
+
+    #[test]
+    fn test_shorten_id() {
+        assert_eq!(shorten_id("#main/step1/out", "main"), "step1/out");
+        assert_eq!(shorten_id("file:///data/wf.cwl#main/input", "main"), "input");
+        assert_eq!(shorten_id("#step1/in", "step1"), "in");
+        assert_eq!(shorten_id("#other/input", "main"), "other/input");
+        assert_eq!(shorten_id("input", "main"), "input");
+    }
+
+    #[test]
+    fn test_normalize_ids() {
+        let mut document: Value = serde_yaml::from_str(
+            r##"
+class: Workflow
+cwlVersion: v1.2
+id: "#main"
+inputs:
+- id: "#main/message"
+  type: string
+outputs:
+  "#main/result":
+    type: File
+    outputSource: "#main/echo/out"
+steps:
+- id: "#main/echo"
+  run: "#echo.cwl"
+  in:
+  - id: "#main/echo/text"
+    source: "#main/message"
+  out:
+  - id: "#main/echo/out"
+  scatter: "#main/echo/text"
+"##,
+        )
+        .unwrap();
+        normalize_ids(&mut document);
+        let workflow: Workflow = serde_yaml::from_value(document.clone()).unwrap();
+        assert_eq!(workflow.inputs[0].id, "message");
+        assert_eq!(workflow.outputs[0].id, "result");
+        assert_eq!(workflow.outputs[0].output_source, "echo/out");
+        assert_eq!(workflow.steps[0].id, "echo");
+        assert_eq!(workflow.steps[0].out, vec!["out".to_string()]);
+        assert_eq!(workflow.steps[0].get_scatter_inputs(), vec!["text".to_string()]);
+        assert!(workflow.has_step_input("message"));
+
+        //normalizing is idempotent
+        let normalized = document.clone();
+        normalize_ids(&mut document);
+        assert_eq!(document, normalized);
+    }
+}
@@ -1,6 +1,8 @@
 use super::{
     deserialize::Identifiable,
+    requirements::LoadListing,
     types::{CWLType, DefaultValue},
+    wf::{LinkMerge, PickValue, Source},
 };
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml::Value;
@@ -12,11 +14,21 @@ pub struct CommandInputParameter {
     pub id: String,
     pub type_: CWLType,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<DefaultValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub input_binding: Option<CommandLineBinding>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_contents: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_listing: Option<LoadListing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_secondary_files")]
+    #[serde(default)]
+    pub secondary_files: Option<Vec<SecondaryFile>>,
 }
 
 impl CommandInputParameter {
@@ -39,6 +51,11 @@ impl CommandInputParameter {
         self.input_binding = Some(binding);
         self
     }
+
+    pub fn with_doc(mut self, doc: &str) -> Self {
+        self.doc = Some(doc.to_string());
+        self
+    }
 }
 
 impl Identifiable for CommandInputParameter {
@@ -115,13 +132,89 @@ where
     Ok(parameters)
 }
 
+/// File expected next to a primary file like an index, given as pattern e.g. `.bai` or `^.dict` or as expression
+///
+/// Reference: [CWL SecondaryFileSchema](https://www.commonwl.org/v1.2/CommandLineTool.html#SecondaryFileSchema)
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
+pub enum SecondaryFile {
+    Pattern(String),
+    Schema(SecondaryFileSchema),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SecondaryFileSchema {
+    pub pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+impl SecondaryFile {
+    pub fn pattern(&self) -> &str {
+        match self {
+            SecondaryFile::Pattern(pattern) => pattern,
+            SecondaryFile::Schema(schema) => &schema.pattern,
+        }
+    }
+
+    /// Secondary files of inputs are required unless stated otherwise
+    pub fn is_required(&self) -> bool {
+        match self {
+            SecondaryFile::Pattern(_) => true,
+            SecondaryFile::Schema(schema) => schema.required.unwrap_or(true),
+        }
+    }
+}
+
+/// Accepts a single secondary file or a list of them
+pub fn deserialize_secondary_files<'de, D>(deserializer: D) -> Result<Option<Vec<SecondaryFile>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<Value> = Deserialize::deserialize(deserializer)?;
+    match value {
+        None => Ok(None),
+        Some(Value::Sequence(seq)) => seq
+            .into_iter()
+            .map(|item| serde_yaml::from_value(item).map_err(serde::de::Error::custom))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        Some(value) => Ok(Some(vec![serde_yaml::from_value(value).map_err(serde::de::Error::custom)?])),
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
 pub enum WorkflowStepInput {
     String(String),
     Parameter(WorkflowStepInputParameter),
 }
 
+impl<'de> Deserialize<'de> for WorkflowStepInput {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum StepInput {
+            String(String),
+            Sources(Vec<String>),
+            Parameter(WorkflowStepInputParameter),
+        }
+
+        Ok(match StepInput::deserialize(deserializer)? {
+            StepInput::String(source) => WorkflowStepInput::String(source),
+            //multiple sources can be given as list instead of using `source`
+            StepInput::Sources(sources) => WorkflowStepInput::Parameter(WorkflowStepInputParameter {
+                source: Some(Source::Multiple(sources)),
+                ..Default::default()
+            }),
+            StepInput::Parameter(parameter) => WorkflowStepInput::Parameter(parameter),
+        })
+    }
+}
+
 impl Default for WorkflowStepInput {
     fn default() -> Self {
         WorkflowStepInput::String(String::default())
@@ -132,9 +225,15 @@ impl Default for WorkflowStepInput {
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowStepInputParameter {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub source: Option<String>,
+    pub source: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_merge: Option<LinkMerge>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<DefaultValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pick_value: Option<PickValue>,
 }
 
 #[cfg(test)]
@@ -149,6 +248,21 @@ mod tests {
         assert_eq!(input.id(), "test");
     }
 
+    #[test]
+    pub fn test_secondary_files() {
+        let input: CommandInputParameter = serde_yaml::from_str("type: File\nsecondaryFiles: .bai").unwrap();
+        assert_eq!(input.secondary_files, Some(vec![SecondaryFile::Pattern(".bai".to_string())]));
+
+        let input: CommandInputParameter =
+            serde_yaml::from_str("type: File\nsecondaryFiles:\n- ^.dict\n- pattern: .fai\n  required: false").unwrap();
+        let secondary_files = input.secondary_files.unwrap();
+        assert_eq!(secondary_files.len(), 2);
+        assert_eq!(secondary_files[0].pattern(), "^.dict");
+        assert!(secondary_files[0].is_required());
+        assert_eq!(secondary_files[1].pattern(), ".fai");
+        assert!(!secondary_files[1].is_required());
+    }
+
     #[test]
     pub fn test_workflow_step_input_default() {
         let input = WorkflowStepInput::default();
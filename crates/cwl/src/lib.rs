@@ -1,37 +1,128 @@
 use clt::CommandLineTool;
-use std::{error::Error, fmt::Debug, fs, path::Path};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_yaml::Value;
+use error::CWLError;
+use ids::normalize_ids;
+use strict::check_parse_mode;
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+};
+use version::upgrade_to_v12;
 use wf::Workflow;
 
 pub mod clt;
 pub mod deserialize;
+pub mod error;
 pub mod format;
+pub mod ids;
 pub mod inputs;
+pub mod location;
 pub mod outputs;
+pub mod packed;
 pub mod requirements;
+pub mod strict;
 pub mod types;
+pub mod version;
 pub mod wf;
 
-/// Loads a CWL CommandLineTool from disk and parses given YAML
-pub fn load_tool<P: AsRef<Path> + Debug>(filename: P) -> Result<CommandLineTool, Box<dyn Error>> {
-    let path = filename.as_ref();
-    if !path.exists() {
-        return Err(format!("❌ Tool {:?} does not exist.", filename).into());
-    }
-    let contents = fs::read_to_string(path)?;
-    let tool: CommandLineTool = serde_yaml::from_str(&contents).map_err(|e| format!("❌ Could not read CommandLineTool {:?}: {}", filename, e))?;
-
-    Ok(tool)
+/// Loads a CWL CommandLineTool from disk and parses given YAML. A document of a YAML stream can be addressed using
+/// `file.cwl#<index|id>`.
+pub fn load_tool<P: AsRef<Path> + Debug>(filename: P) -> Result<CommandLineTool, CWLError> {
+    load_document(filename.as_ref(), "CommandLineTool")
 }
 
 /// Loads a CWL Workflow from disk and parses given YAML
-pub fn load_workflow<P: AsRef<Path> + Debug>(filename: P) -> Result<Workflow, Box<dyn Error>> {
+pub fn load_workflow<P: AsRef<Path> + Debug>(filename: P) -> Result<Workflow, CWLError> {
+    load_document(filename.as_ref(), "Workflow")
+}
+
+/// Parses a CWL document from YAML. Documents written against CWL v1.0 or v1.1 are upgraded to v1.2 on the fly and
+/// identifiers are normalized, see [`normalize_ids`]. Unknown fields are rejected in strict [`strict::ParseMode`].
+pub fn parse_cwl<T: DeserializeOwned>(contents: &str) -> Result<T, CWLError> {
+    let mut document = parse_yaml(contents)?;
+    upgrade_to_v12(&mut document)?;
+    normalize_ids(&mut document);
+    check_parse_mode(&document)?;
+    Ok(serde_yaml::from_value(document)?)
+}
+
+/// Parses YAML resolving anchors and aliases. Merge keys (`<<: *defaults`) are applied, so that the merged entries
+/// are part of the mapping they are merged into. Streams are only accepted if they contain a single document.
+pub fn parse_yaml(contents: &str) -> Result<Value, CWLError> {
+    select_document(parse_yaml_stream(contents)?, None)
+}
+
+/// Parses all documents of a YAML stream, documents are separated by `---`
+pub fn parse_yaml_stream(contents: &str) -> Result<Vec<Value>, CWLError> {
+    let mut documents = vec![];
+    for deserializer in serde_yaml::Deserializer::from_str(contents) {
+        let mut document = Value::deserialize(deserializer)?;
+        document.apply_merge()?;
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// Loads all documents of a YAML stream from disk
+pub fn load_documents<P: AsRef<Path>>(filename: P) -> Result<Vec<Value>, CWLError> {
     let path = filename.as_ref();
-    if !path.exists() {
-        return Err(format!("❌ Workflow {:?} does not exist, yet!", filename).into());
+    let contents = fs::read_to_string(path).map_err(|e| CWLError::io(path, e))?;
+    parse_yaml_stream(&contents).map_err(|e| e.with_path(path))
+}
+
+/// Selects a document of a stream by its index or `id`. Without selector the stream must contain a single document.
+pub fn select_document(mut documents: Vec<Value>, selector: Option<&str>) -> Result<Value, CWLError> {
+    let Some(selector) = selector else {
+        return match documents.len() {
+            1 => Ok(documents.remove(0)),
+            //an empty file is an empty document
+            0 => Ok(Value::Null),
+            n => Err(CWLError::AmbiguousDocument(n)),
+        };
+    };
+    let index = match selector.parse::<usize>() {
+        Ok(index) => Some(index).filter(|i| *i < documents.len()),
+        Err(_) => documents
+            .iter()
+            .position(|d| d.get("id").and_then(Value::as_str).map(|id| id.trim_start_matches('#')) == Some(selector.trim_start_matches('#'))),
+    };
+    index.map(|i| documents.remove(i)).ok_or_else(|| CWLError::UnknownDocument(selector.to_string()))
+}
+
+/// Splits a reference like `tools.cwl#echo` into the file and the selector of the document. Paths of existing files
+/// are not split, even if they contain a `#`.
+pub fn split_document_reference<P: AsRef<Path>>(reference: P) -> (PathBuf, Option<String>) {
+    let reference = reference.as_ref();
+    if reference.exists() {
+        return (reference.to_path_buf(), None);
+    }
+    match reference.to_string_lossy().rsplit_once('#') {
+        Some((path, selector)) if !selector.is_empty() => (PathBuf::from(path), Some(selector.to_string())),
+        _ => (reference.to_path_buf(), None),
     }
-    let contents = fs::read_to_string(path)?;
-    let workflow: Workflow = serde_yaml::from_str(&contents).map_err(|e| format!("❌ Could not read Workflow {:?}: {}", filename, e))?;
-    Ok(workflow)
+}
+
+fn load_document<T: DeserializeOwned>(reference: &Path, class: &str) -> Result<T, CWLError> {
+    let (path, selector) = split_document_reference(reference);
+    let mut documents = load_documents(&path)?;
+    //without selector the only document of the requested class is used
+    if selector.is_none() && documents.len() > 1 {
+        documents.retain(|d| d.get("class").and_then(Value::as_str) == Some(class));
+    }
+    let mut document = select_document(documents, selector.as_deref())?;
+    let found = document.get("class").and_then(Value::as_str).unwrap_or_default();
+    if found != class {
+        return Err(CWLError::UnknownClass {
+            found: found.to_string(),
+            expected: class.to_string(),
+        });
+    }
+    upgrade_to_v12(&mut document)?;
+    normalize_ids(&mut document);
+    check_parse_mode(&document)?;
+    serde_yaml::from_value(document).map_err(|e| CWLError::from(e).with_path(&path))
 }
 
 #[cfg(test)]
@@ -43,6 +134,7 @@ mod tests {
     #[case("../../tests/test_data/default.cwl")]
     #[case("../../tests/test_data/echo.cwl")]
     #[case("../../tests/test_data/mkdir.cwl")]
+    #[case("../../tests/test_data/cat_v1_0.cwl")]
     #[case("../../tests/test_data/hello_world/workflows/calculation/calculation.cwl")]
     #[case("../../tests/test_data/hello_world/workflows/plot/plot.cwl")]
 
@@ -51,6 +143,16 @@ mod tests {
         assert!(tool.is_ok());
     } 
 
+    #[test]
+    fn test_load_tool_v1_0() {
+        let tool = load_tool("../../tests/test_data/cat_v1_0.cwl").unwrap();
+        assert_eq!(tool.cwl_version, "v1.2");
+        //listing requirement was given as cwltool hint and must not be added again
+        let requirements = tool.requirements.unwrap();
+        assert!(!requirements.iter().any(|r| matches!(r, requirements::Requirement::LoadListingRequirement(_))));
+        assert!(requirements.iter().any(|r| matches!(r, requirements::Requirement::NetworkAccess(_))));
+    }
+
     #[test]
     #[should_panic]
     fn test_load_tool_fails() {
@@ -75,4 +177,47 @@ mod tests {
     fn test_load_wf_fails() {
         let _ = load_workflow("this is not valid").unwrap();
     }
+
+    #[test]
+    fn test_parse_cwl_merge_keys() {
+        let contents = "class: CommandLineTool
+cwlVersion: v1.2
+baseCommand: echo
+inputs:
+  first: &input
+    type: string
+    default: hello
+    inputBinding: {position: 1}
+  second:
+    <<: *input
+    inputBinding: {position: 2}
+outputs: []
+";
+        let tool: CommandLineTool = parse_cwl(contents).unwrap();
+        assert_eq!(tool.inputs[1].id, "second");
+        assert_eq!(tool.inputs[1].default, tool.inputs[0].default);
+        assert_eq!(tool.inputs[1].input_binding.as_ref().unwrap().position, Some(2));
+    }
+
+    #[test]
+    fn test_load_from_stream() {
+        let path = Path::new("../../tests/test_data/stream.cwl");
+        assert_eq!(load_documents(path).unwrap().len(), 3);
+        let reference = |selector: &str| format!("{}#{selector}", path.to_string_lossy());
+        assert_eq!(load_tool(reference("1")).unwrap().base_command, clt::Command::Single("ls".to_string()));
+        assert_eq!(load_tool(reference("echo")).unwrap().base_command, clt::Command::Single("echo".to_string()));
+        assert!(load_workflow(path).is_ok());
+        assert!(matches!(load_tool(path), Err(CWLError::AmbiguousDocument(2))));
+        assert!(matches!(load_tool(reference("3")), Err(CWLError::UnknownDocument(_))));
+        assert!(matches!(parse_yaml(&fs::read_to_string(path).unwrap()), Err(CWLError::AmbiguousDocument(3))));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(load_tool("this is not valid"), Err(CWLError::Io { .. })));
+        assert!(matches!(
+            load_tool("../../tests/test_data/mkdir_wf.cwl"),
+            Err(CWLError::UnknownClass { found, .. }) if found == "Workflow"
+        ));
+    }
 }
@@ -0,0 +1,211 @@
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_yaml::Value;
+use std::fmt;
+
+/// Message of the error raised when the searched node is reached, the YAML parser marks it with its location
+const FOUND: &str = "s4n: node found";
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Returns the line of a node of a YAML document taken from the marks of the YAML parser. The path consists of mapping
+/// keys, items of sequences are addressed by their `id` or by their index, e.g. `["steps", "align", "in", "reads"]`
+/// finds the key `reads` whether steps are given as mapping or as sequence. Mapping entries are located at their key.
+pub fn find_line(contents: &str, path: &[&str]) -> Option<usize> {
+    let document: Value = serde_yaml::from_str(contents).ok()?;
+    let segments = resolve_path(&document, path)?;
+    let deserializer = serde_yaml::Deserializer::from_str(contents);
+    match (Locator { path: &segments }).deserialize(deserializer) {
+        Err(e) if e.to_string().contains(FOUND) => e.location().map(|location| location.line()),
+        _ => None,
+    }
+}
+
+/// Translates ids of sequence items into their index
+fn resolve_path(document: &Value, path: &[&str]) -> Option<Vec<Segment>> {
+    let mut node = document;
+    let mut segments = vec![];
+    for name in path {
+        match node {
+            Value::Mapping(mapping) => {
+                node = mapping.get(*name)?;
+                segments.push(Segment::Key(name.to_string()));
+            }
+            Value::Sequence(items) => {
+                let index = items
+                    .iter()
+                    .position(|item| item.get("id").and_then(Value::as_str).is_some_and(|id| id.trim_start_matches('#') == *name))
+                    .or_else(|| name.parse().ok())?;
+                node = items.get(index)?;
+                segments.push(Segment::Index(index));
+            }
+            _ => return None,
+        }
+    }
+    Some(segments)
+}
+
+/// Walks down the path and fails once the node at its end is reached
+struct Locator<'a> {
+    path: &'a [Segment],
+}
+
+impl<'de> DeserializeSeed<'de> for Locator<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Locator<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any YAML value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        self.visit_unit()
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        self.visit_unit()
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        self.visit_unit()
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        self.visit_unit()
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        self.visit_unit()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        if self.path.is_empty() {
+            return Err(E::custom(FOUND));
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let (key, rest) = match self.path.split_first() {
+            None => return Err(de::Error::custom(FOUND)),
+            Some((Segment::Key(key), rest)) => (Some(key.as_str()), rest),
+            Some((Segment::Index(_), _)) => (None, &[][..]),
+        };
+        while let Some(is_match) = map.next_key_seed(KeyMatcher { key, is_last: rest.is_empty() })? {
+            if is_match {
+                map.next_value_seed(Locator { path: rest })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let (index, rest) = match self.path.split_first() {
+            None => return Err(de::Error::custom(FOUND)),
+            Some((Segment::Index(index), rest)) => (Some(*index), rest),
+            Some((Segment::Key(_), _)) => (None, &[][..]),
+        };
+        let mut i = 0;
+        loop {
+            let item = if Some(i) == index {
+                seq.next_element_seed(Locator { path: rest })?
+            } else {
+                seq.next_element::<IgnoredAny>()?.map(|_| ())
+            };
+            if item.is_none() {
+                return Ok(());
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Checks whether a mapping key is the next one of the path, the last key of the path is the searched node
+struct KeyMatcher<'a> {
+    key: Option<&'a str>,
+    is_last: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for KeyMatcher<'_> {
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyMatcher<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a mapping key")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<bool, E> {
+        let is_match = Some(value) == self.key;
+        if is_match && self.is_last {
+            return Err(E::custom(FOUND));
+        }
+        Ok(is_match)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<bool, E> {
+        Ok(false)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = "class: Workflow
+# steps: are listed below
+inputs:
+  name: string
+outputs: []
+steps:
+- id: greet
+  run: echo.cwl
+  in:
+    message: name
+  out: [out, err]
+";
+
+    #[test]
+    fn test_find_line() {
+        assert_eq!(find_line(WORKFLOW, &["class"]), Some(1));
+        assert_eq!(find_line(WORKFLOW, &["steps"]), Some(6));
+        assert_eq!(find_line(WORKFLOW, &["steps", "greet"]), Some(7));
+        assert_eq!(find_line(WORKFLOW, &["steps", "greet", "in", "message"]), Some(10));
+        assert_eq!(find_line(WORKFLOW, &["steps", "0", "out", "1"]), Some(11));
+        assert_eq!(find_line(WORKFLOW, &["steps", "greet", "in", "missing"]), None);
+        assert_eq!(find_line("inputs: [\n", &["inputs"]), None);
+    }
+}
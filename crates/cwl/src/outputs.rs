@@ -1,8 +1,12 @@
-use super::{deserialize::Identifiable, types::CWLType};
+use super::{
+    deserialize::Identifiable,
+    types::CWLType,
+    wf::{LinkMerge, PickValue, Source},
+};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml::Value;
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CommandOutputParameter {
     #[serde(default)]
@@ -80,10 +84,15 @@ where
 }
 
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CommandOutputBinding {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub glob: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_contents: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_eval: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
@@ -92,7 +101,11 @@ pub struct WorkflowOutputParameter {
     #[serde(default)]
     pub id: String,
     pub type_: CWLType,
-    pub output_source: String,
+    pub output_source: Source,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_merge: Option<LinkMerge>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pick_value: Option<PickValue>,
 }
 
 impl WorkflowOutputParameter {
@@ -0,0 +1,241 @@
+use crate::{
+    parse_yaml,
+    version::{upgrade_to_v12, CWL_VERSION},
+};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Id the main process of a packed document is referenced by
+pub const MAIN_ID: &str = "#main";
+
+/// A packed CWL document which contains all processes of a workflow in its `$graph`.
+/// The entrypoint is given the id `#main`, steps reference their tools using `run: "#<id>"`.
+///
+/// Reference: [CWL Packed Documents](https://www.commonwl.org/v1.2/Workflow.html#Packed_documents)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackedCWL {
+    pub cwl_version: String,
+    #[serde(rename = "$graph")]
+    pub graph: Vec<Value>,
+}
+
+impl PackedCWL {
+    /// Returns the process with the given id, e.g. `#main`
+    pub fn get_process(&self, id: &str) -> Option<&Value> {
+        self.graph.iter().find(|p| p.get("id").and_then(|i| i.as_str()) == Some(id))
+    }
+}
+
+impl Display for PackedCWL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_yaml::to_string(self) {
+            Ok(yaml) => write!(f, "{}", yaml),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// Packs the workflow at the given path into a single `$graph` document. All step `run` references are inlined
+/// recursively, `$import` and `$include` directives are resolved and relative file locations are rewritten to be
+/// relative to the main workflow.
+pub fn pack_workflow<P: AsRef<Path>>(path: P) -> Result<PackedCWL, Box<dyn Error>> {
+    let path = path.as_ref();
+    let base_dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+    let base_dir = base_dir.canonicalize().unwrap_or(base_dir);
+
+    let mut packer = Packer {
+        base_dir,
+        ids: HashMap::new(),
+        graph: vec![],
+    };
+    let main = packer.pack_process(path, MAIN_ID.to_string())?;
+    packer.graph.push(main);
+
+    Ok(PackedCWL {
+        cwl_version: CWL_VERSION.to_string(),
+        graph: packer.graph,
+    })
+}
+
+struct Packer {
+    base_dir: PathBuf,
+    /// maps canonical paths of already packed processes to their ids
+    ids: HashMap<PathBuf, String>,
+    graph: Vec<Value>,
+}
+
+impl Packer {
+    fn pack_process(&mut self, path: &Path, id: String) -> Result<Value, Box<dyn Error>> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("❌ Could not read {:?}: {}", path, e))?;
+        let mut document = parse_yaml(&contents).map_err(|e| e.with_path(path))?;
+        upgrade_to_v12(&mut document)?;
+
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        resolve_directives(&mut document, dir)?;
+        rewrite_locations(&mut document, &dir.canonicalize()?, &self.base_dir);
+
+        let Value::Mapping(map) = &mut document else {
+            return Err(format!("❌ {:?} is not a valid CWL document", path).into());
+        };
+        map.remove("cwlVersion");
+        map.insert(Value::String("id".to_string()), Value::String(id));
+
+        if let Some(steps) = map.get_mut("steps") {
+            let steps: Vec<&mut Value> = match steps {
+                Value::Sequence(seq) => seq.iter_mut().collect(),
+                Value::Mapping(map) => map.values_mut().collect(),
+                _ => vec![],
+            };
+            for step in steps {
+                if let Some(Value::String(run)) = step.get("run") {
+                    let run_id = self.pack_reference(&dir.join(run))?;
+                    step["run"] = Value::String(run_id);
+                }
+            }
+        }
+        Ok(document)
+    }
+
+    /// Packs a referenced process once and returns its id
+    fn pack_reference(&mut self, path: &Path) -> Result<String, Box<dyn Error>> {
+        let canonical = path.canonicalize().map_err(|e| format!("❌ Could not find {:?}: {}", path, e))?;
+        if let Some(id) = self.ids.get(&canonical) {
+            return Ok(id.clone());
+        }
+
+        let filename = path.file_name().map(|f| f.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut id = format!("#{filename}");
+        let mut counter = 1;
+        while id == MAIN_ID || self.ids.values().any(|i| *i == id) {
+            counter += 1;
+            id = format!("#{filename}_{counter}");
+        }
+        self.ids.insert(canonical, id.clone());
+
+        let process = self.pack_process(path, id.clone())?;
+        self.graph.push(process);
+        Ok(id)
+    }
+}
+
+/// Replaces `$import` with the referenced YAML document and `$include` with the contents of the referenced file
+fn resolve_directives(value: &mut Value, dir: &Path) -> Result<(), Box<dyn Error>> {
+    match value {
+        Value::Mapping(map) => {
+            if map.len() == 1 {
+                if let Some(Value::String(file)) = map.get("$include") {
+                    let path = dir.join(file);
+                    *value = Value::String(fs::read_to_string(&path).map_err(|e| format!("❌ Could not include {:?}: {}", path, e))?);
+                    return Ok(());
+                }
+                if let Some(Value::String(file)) = map.get("$import") {
+                    let path = dir.join(file);
+                    let contents = fs::read_to_string(&path).map_err(|e| format!("❌ Could not import {:?}: {}", path, e))?;
+                    *value = serde_yaml::from_str(&contents)?;
+                    return resolve_directives(value, path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")));
+                }
+            }
+            for (_, v) in map.iter_mut() {
+                resolve_directives(v, dir)?;
+            }
+        }
+        Value::Sequence(seq) => {
+            for v in seq {
+                resolve_directives(v, dir)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Rewrites relative locations of Files and Directories to be relative to the main workflow
+fn rewrite_locations(value: &mut Value, dir: &Path, base_dir: &Path) {
+    match value {
+        Value::Mapping(map) => {
+            let is_path_item = matches!(map.get("class").and_then(|c| c.as_str()), Some("File") | Some("Directory"));
+            if is_path_item {
+                for key in ["location", "path"] {
+                    if let Some(Value::String(location)) = map.get_mut(key) {
+                        if !location.contains("://") && !location.starts_with('$') && Path::new(location).is_relative() {
+                            let absolute = dir.join(&location);
+                            if let Some(relative) = pathdiff::diff_paths(&absolute, base_dir) {
+                                *location = relative.to_string_lossy().replace('\\', "/");
+                            }
+                        }
+                    }
+                }
+            }
+            for (_, v) in map.iter_mut() {
+                rewrite_locations(v, dir, base_dir);
+            }
+        }
+        Value::Sequence(seq) => {
+            for v in seq {
+                rewrite_locations(v, dir, base_dir);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_workflow() {
+        let packed = pack_workflow("../../tests/test_data/hello_world/workflows/main/main.cwl").unwrap();
+        assert_eq!(packed.cwl_version, "v1.2");
+        assert_eq!(packed.graph.len(), 3);
+
+        let main = packed.get_process(MAIN_ID).unwrap();
+        assert!(main.get("cwlVersion").is_none());
+        let steps = main["steps"].as_sequence().unwrap();
+        for step in steps {
+            let run = step["run"].as_str().unwrap();
+            assert!(run.starts_with('#'));
+            assert!(packed.get_process(run).is_some());
+        }
+
+        //included scripts are inlined
+        let calculation = packed.get_process("#calculation.cwl").unwrap();
+        let yaml = serde_yaml::to_string(calculation).unwrap();
+        assert!(!yaml.contains("$include"));
+        assert!(yaml.contains("import pandas"));
+    }
+
+    #[test]
+    fn test_pack_commandlinetool() {
+        //a single tool is packed as the only process of the graph, so that engines run it as `#main` like a workflow
+        let packed = pack_workflow("../../tests/test_data/hello_world/workflows/calculation/calculation.cwl").unwrap();
+        assert_eq!(packed.graph.len(), 1);
+        let main = packed.get_process(MAIN_ID).unwrap();
+        assert_eq!(main["class"], Value::String("CommandLineTool".to_string()));
+        assert!(!serde_yaml::to_string(main).unwrap().contains("$include"));
+    }
+
+    #[test]
+    fn test_pack_workflow_roundtrip() {
+        let packed = pack_workflow("../../tests/test_data/mkdir_wf.cwl").unwrap();
+        let yaml = packed.to_string();
+        let parsed: PackedCWL = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, packed);
+        assert_eq!(parsed.get_process(MAIN_ID).unwrap()["steps"][0]["run"], Value::String("#mkdir.cwl".to_string()));
+    }
+
+    #[test]
+    fn test_rewrite_locations() {
+        let mut value: Value = serde_yaml::from_str("default:\n  class: File\n  location: data.csv").unwrap();
+        rewrite_locations(&mut value, Path::new("workflows/tool"), Path::new("workflows/main"));
+        assert_eq!(value["default"]["location"], Value::String("../tool/data.csv".to_string()));
+    }
+}
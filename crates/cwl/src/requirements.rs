@@ -3,7 +3,10 @@ use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml::{Mapping, Value};
 use std::{fs, path::MAIN_SEPARATOR_STR};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// Namespace of the `s4n:` extensions, documents using them need to declare it in their `$namespaces`
+pub const S4N_NAMESPACE: &str = "https://github.com/fairagro/m4.4_sciwin_client#";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "class")]
 pub enum Requirement {
     InitialWorkDirRequirement(InitialWorkDirRequirement),
@@ -11,14 +14,49 @@ pub enum Requirement {
     ResourceRequirement(ResourceRequirement),
     EnvVarRequirement(EnvVarRequirement),
     ShellCommandRequirement,
+    NetworkAccess(NetworkAccess),
+    LoadListingRequirement(LoadListingRequirement),
+    ToolTimeLimit(ToolTimeLimit),
+    WorkReuse(WorkReuse),
+    InplaceUpdateRequirement(InplaceUpdateRequirement),
+    InlineJavascriptRequirement(InlineJavascriptRequirement),
+    SoftwareRequirement(SoftwareRequirement),
+    #[serde(rename = "s4n:UnpackArchive")]
+    UnpackArchive(UnpackArchive),
+    #[serde(rename = "s4n:CondaEnvironment")]
+    CondaEnvironment(CondaEnvironment),
+    #[serde(rename = "s4n:RetryPolicy")]
+    RetryPolicy(RetryPolicy),
     //as dummys, not used at this point
-    SoftwareRequirement,
-    NetworkAccess,
-    InlineJavascriptRequirement,
     MultipleInputFeatureRequirement,
     SubworkflowFeatureRequirement,
     StepInputExpressionRequirement,
-    ToolTimeLimit,
+    ScatterFeatureRequirement,
+    /// Vendor extensions like `sbg:` or `arv:` hints are kept as they are, so that they survive saving a document
+    #[serde(untagged)]
+    Extension(Mapping),
+}
+
+impl Requirement {
+    /// Whether both requirements are of the same class, extensions are compared by their `class` field
+    pub fn is_same_class(&self, other: &Requirement) -> bool {
+        match (self, other) {
+            (Requirement::Extension(a), Requirement::Extension(b)) => a.get("class") == b.get("class"),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// Only namespaced classes are accepted as extensions, otherwise typos or invalid fields of known requirements
+/// would go unnoticed
+fn check_extension<E: serde::de::Error>(requirement: Requirement) -> Result<Requirement, E> {
+    if let Requirement::Extension(map) = &requirement {
+        let class = map.get("class").and_then(Value::as_str).unwrap_or_default();
+        if !class.contains(':') {
+            return Err(E::custom(format!("Invalid or unknown requirement `{class}`")));
+        }
+    }
+    Ok(requirement)
 }
 
 pub fn deserialize_requirements<'de, D>(deserializer: D) -> Result<Option<Vec<Requirement>>, D::Error>
@@ -37,7 +75,7 @@ where
             .into_iter()
             .map(|item| {
                 let param: Requirement = serde_yaml::from_value(item).map_err(serde::de::Error::custom)?;
-                Ok(param)
+                check_extension(param)
             })
             .collect::<Result<Vec<_>, _>>()?,
         Value::Mapping(map) => map
@@ -57,7 +95,7 @@ where
                     }
                 };
                 let param: Requirement = serde_yaml::from_value(Value::Mapping(new_map)).map_err(serde::de::Error::custom)?;
-                Ok(param)
+                check_extension(param)
             })
             .collect::<Result<Vec<_>, _>>()?,
         _ => return Err(serde::de::Error::custom("Expected sequence or mapping for outputs")),
@@ -66,6 +104,37 @@ where
     Ok(Some(parameters))
 }
 
+/// Adds the requirements and hints of an enclosing workflow to those of a process. Requirements of the process itself
+/// take precedence over inherited ones, while inherited requirements override hints of the same class.
+///
+/// Reference: [CWL Requirements and hints](https://www.commonwl.org/v1.2/Workflow.html#Requirements_and_hints)
+pub fn inherit_requirements(
+    requirements: &mut Option<Vec<Requirement>>,
+    hints: &mut Option<Vec<Requirement>>,
+    parent_requirements: Option<&Vec<Requirement>>,
+    parent_hints: Option<&Vec<Requirement>>,
+) {
+    for requirement in parent_requirements.into_iter().flatten() {
+        let own = requirements.get_or_insert_with(Vec::new);
+        if !own.iter().any(|r| r.is_same_class(requirement)) {
+            own.push(requirement.clone());
+        }
+        if let Some(hints) = hints {
+            hints.retain(|h| !h.is_same_class(requirement));
+        }
+    }
+    for hint in parent_hints.into_iter().flatten() {
+        let required = requirements.iter().flatten().any(|r| r.is_same_class(hint));
+        let own = hints.get_or_insert_with(Vec::new);
+        if !required && !own.iter().any(|h| h.is_same_class(hint)) {
+            own.push(hint.clone());
+        }
+    }
+    if hints.as_ref().is_some_and(Vec::is_empty) {
+        *hints = None;
+    }
+}
+
 fn get_entry_name(script_name: &str, input: &str) -> String {
     // Read the content of the script file
     let script_content = match fs::read_to_string(script_name) {
@@ -95,7 +164,7 @@ fn get_entry_name(script_name: &str, input: &str) -> String {
     input.trim_start_matches(|c: char| !c.is_alphabetic()).to_string()
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct InitialWorkDirRequirement {
     pub listing: Vec<Listing>,
@@ -107,6 +176,7 @@ impl InitialWorkDirRequirement {
             listing: vec![Listing {
                 entryname: filename.to_string(),
                 entry: Entry::from_file(filename),
+                writable: false,
             }],
         }
     }
@@ -121,6 +191,7 @@ impl InitialWorkDirRequirement {
                         .unwrap()
                         .to_string(),
                     entry: Entry::from_file(filename),
+                    writable: false,
                 })
                 .collect(),
         }
@@ -130,12 +201,13 @@ impl InitialWorkDirRequirement {
             listing: vec![Listing {
                 entryname: entryname.to_string(),
                 entry: Entry::Source(contents.to_string()),
+                writable: false,
             }],
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum DockerRequirement {
     DockerPull(String),
@@ -160,7 +232,7 @@ impl DockerRequirement {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ResourceRequirement {
     pub cores_min: Option<i32>,
@@ -173,12 +245,136 @@ pub struct ResourceRequirement {
     pub outdir_max: Option<i32>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct EnvVarRequirement {
     pub env_def: EnviromentDefs,
 }
 
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineJavascriptRequirement {
+    /// Javascript code that is made available to all expressions, e.g. function definitions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expression_lib: Option<Vec<String>>,
+}
+
+/// s4n specific hint to unpack `.tar`, `.tar.gz`, `.tgz` or `.gz` archives created by a tool into Directory outputs
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UnpackArchive {
+    /// ids of the outputs to unpack
+    pub outputs: Vec<String>,
+    /// whether the archive is written to the output directory next to the unpacked directory
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keep_archive: bool,
+}
+
+/// Software packages that need to be installed for the tool to run
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct SoftwareRequirement {
+    #[serde(default)]
+    pub packages: Vec<SoftwarePackage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct SoftwarePackage {
+    pub package: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Vec<String>>,
+    /// IRIs identifying the package, e.g. in a package registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub specs: Option<Vec<String>>,
+}
+
+/// Conda environment file the tool can be run in if no container is used
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CondaEnvironment {
+    /// path to the `environment.yml` relative to the tool
+    pub env_file: String,
+}
+
+/// s4n specific hint to rerun failing workflow steps and to continue the workflow if they still fail
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// how often a step is run at most, including the first attempt
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// seconds to wait before the first retry, doubled for every further retry
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub backoff: f64,
+    /// whether the workflow stops or continues with all steps not depending on a step that failed
+    #[serde(default)]
+    pub on_error: OnError,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: default_max_attempts(),
+            backoff: 0.0,
+            on_error: OnError::default(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    #[default]
+    Stop,
+    Continue,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkAccess {
+    pub network_access: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadListing {
+    NoListing,
+    ShallowListing,
+    DeepListing,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadListingRequirement {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_listing: Option<LoadListing>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ToolTimeLimit {
+    /// Time limit in seconds or an expression evaluating to it
+    pub timelimit: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkReuse {
+    pub enable_reuse: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InplaceUpdateRequirement {
+    pub inplace_update: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,6 +386,70 @@ mod tests {
         assert_eq!(req.listing[0].entryname, "../../tests/test_data/echo.py".to_string());
     }
 
+    #[test]
+    pub fn test_inherit_requirements() {
+        let mut requirements = Some(vec![Requirement::NetworkAccess(NetworkAccess { network_access: false })]);
+        let mut hints = Some(vec![Requirement::ShellCommandRequirement]);
+        let parent_requirements = vec![
+            Requirement::NetworkAccess(NetworkAccess { network_access: true }),
+            Requirement::ShellCommandRequirement,
+        ];
+        let parent_hints = vec![Requirement::ShellCommandRequirement, Requirement::ScatterFeatureRequirement];
+        inherit_requirements(&mut requirements, &mut hints, Some(&parent_requirements), Some(&parent_hints));
+
+        //own requirement is kept, inherited requirement replaces the hint
+        assert_eq!(
+            requirements,
+            Some(vec![
+                Requirement::NetworkAccess(NetworkAccess { network_access: false }),
+                Requirement::ShellCommandRequirement
+            ])
+        );
+        assert_eq!(hints, Some(vec![Requirement::ScatterFeatureRequirement]));
+    }
+
+    #[test]
+    pub fn test_vendor_extension_hints() {
+        let yaml = "class: CommandLineTool
+cwlVersion: v1.2
+baseCommand: echo
+inputs: []
+outputs: []
+hints:
+- class: sbg:SaveLogs
+  value: '*.log'
+- class: NetworkAccess
+  networkAccess: true
+";
+        let tool: crate::clt::CommandLineTool = serde_yaml::from_str(yaml).unwrap();
+        let hints = tool.hints.as_ref().unwrap();
+        assert!(matches!(&hints[0], Requirement::Extension(map) if map.get("value") == Some(&Value::from("*.log"))));
+        assert!(matches!(hints[1], Requirement::NetworkAccess(_)));
+        //extensions are written back unchanged
+        let saved = serde_yaml::to_string(&tool).unwrap();
+        assert!(saved.contains("- class: sbg:SaveLogs\n  value: '*.log'\n"));
+
+        let hints: Mapping = serde_yaml::from_str("hints:\n  arv:RuntimeConstraints:\n    keep_cache: 512\n  MyRequirement: {}\n").unwrap();
+        let result: Result<Vec<Requirement>, _> = deserialize_requirements(hints["hints"].clone()).map(Option::unwrap_or_default);
+        assert!(result.unwrap_err().to_string().contains("Invalid or unknown requirement `MyRequirement`"));
+    }
+
+    #[test]
+    pub fn test_retry_policy() {
+        let hints: Mapping = serde_yaml::from_str("hints:\n  s4n:RetryPolicy:\n    maxAttempts: 3\n    onError: continue\n").unwrap();
+        let hints = deserialize_requirements(hints["hints"].clone()).unwrap().unwrap();
+        assert_eq!(
+            hints,
+            vec![Requirement::RetryPolicy(RetryPolicy {
+                max_attempts: 3,
+                backoff: 0.0,
+                on_error: OnError::Continue
+            })]
+        );
+        let saved = serde_yaml::to_string(&hints).unwrap();
+        assert_eq!(saved, "- class: s4n:RetryPolicy\n  maxAttempts: 3\n  onError: continue\n");
+    }
+
     #[test]
     pub fn test_initial_workdir_requirement_multiple() {
         let req = InitialWorkDirRequirement::from_files(
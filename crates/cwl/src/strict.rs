@@ -0,0 +1,203 @@
+use crate::error::CWLError;
+use serde_yaml::{Mapping, Value};
+use std::cell::RefCell;
+
+pub const TOOL_FIELDS: [&str; 18] = [
+    "class", "cwlVersion", "id", "label", "doc", "intent", "baseCommand", "arguments", "inputs", "outputs", "requirements", "hints", "stdin",
+    "stdout", "stderr", "successCodes", "permanentFailCodes", "temporaryFailCodes",
+];
+pub const WORKFLOW_FIELDS: [&str; 11] = [
+    "class", "cwlVersion", "id", "label", "doc", "intent", "inputs", "outputs", "steps", "requirements", "hints",
+];
+pub const STEP_FIELDS: [&str; 11] = ["id", "label", "doc", "run", "in", "out", "scatter", "scatterMethod", "when", "requirements", "hints"];
+const INPUT_FIELDS: [&str; 11] = [
+    "id", "label", "doc", "type", "default", "inputBinding", "secondaryFiles", "streamable", "format", "loadContents", "loadListing",
+];
+const TOOL_OUTPUT_FIELDS: [&str; 8] = ["id", "label", "doc", "type", "outputBinding", "secondaryFiles", "streamable", "format"];
+const WORKFLOW_OUTPUT_FIELDS: [&str; 10] = [
+    "id", "label", "doc", "type", "outputSource", "linkMerge", "pickValue", "secondaryFiles", "streamable", "format",
+];
+const INPUT_BINDING_FIELDS: [&str; 7] = ["loadContents", "position", "prefix", "separate", "itemSeparator", "valueFrom", "shellQuote"];
+const OUTPUT_BINDING_FIELDS: [&str; 4] = ["glob", "loadContents", "loadListing", "outputEval"];
+const STEP_INPUT_FIELDS: [&str; 9] = ["id", "source", "linkMerge", "pickValue", "loadContents", "loadListing", "label", "default", "valueFrom"];
+
+/// How fields unknown to the CWL specification are handled when loading documents
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ParseMode {
+    /// Unknown fields are ignored, e.g. for execution
+    #[default]
+    Permissive,
+    /// Unknown fields are errors, e.g. for validation
+    Strict,
+}
+
+thread_local!(static PARSE_MODE: RefCell<ParseMode> = const { RefCell::new(ParseMode::Permissive) });
+
+/// Sets how unknown fields are handled by [`crate::load_tool`], [`crate::load_workflow`] and [`crate::parse_cwl`]
+pub fn set_parse_mode(mode: ParseMode) {
+    PARSE_MODE.with(|parse_mode| *parse_mode.borrow_mut() = mode);
+}
+
+pub fn parse_mode() -> ParseMode {
+    PARSE_MODE.with(|parse_mode| *parse_mode.borrow())
+}
+
+/// A field not defined by the CWL specification, `context` describes where it was found, e.g. ``input `file` ``. The
+/// path leads to the field, items of sequences are given by their id or index, see [`crate::location::find_line`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownField {
+    pub field: String,
+    pub context: String,
+    pub path: Vec<String>,
+}
+
+/// Fails with the first unknown field of the document if documents are parsed in strict mode
+pub fn check_parse_mode(document: &Value) -> Result<(), CWLError> {
+    if parse_mode() == ParseMode::Permissive {
+        return Ok(());
+    }
+    match find_unknown_fields(document).into_iter().next() {
+        Some(UnknownField { field, context, .. }) => Err(CWLError::UnknownField { field, context }),
+        None => Ok(()),
+    }
+}
+
+/// Finds all fields of a CommandLineTool or Workflow which are not part of the CWL specification, including fields of
+/// inputs, outputs, bindings and steps. Namespaced extensions like `s:author` and directives like `$namespaces` are
+/// allowed everywhere.
+pub fn find_unknown_fields(document: &Value) -> Vec<UnknownField> {
+    find_unknown_fields_at(document, &[])
+}
+
+fn find_unknown_fields_at(document: &Value, path: &[String]) -> Vec<UnknownField> {
+    let mut unknown = vec![];
+    let at = |segments: &[&str]| path.iter().cloned().chain(segments.iter().map(|s| s.to_string())).collect::<Vec<_>>();
+    let Some(mapping) = document.as_mapping() else {
+        return unknown;
+    };
+    let (class, fields, output_fields) = match mapping.get("class").and_then(Value::as_str) {
+        Some("CommandLineTool") => ("CommandLineTool", &TOOL_FIELDS[..], &TOOL_OUTPUT_FIELDS[..]),
+        Some("Workflow") => ("Workflow", &WORKFLOW_FIELDS[..], &WORKFLOW_OUTPUT_FIELDS[..]),
+        _ => return unknown,
+    };
+    check_mapping(mapping, fields, class, &at(&[]), &mut unknown);
+
+    for (id, input) in get_entries(mapping.get("inputs")) {
+        let context = format!("input `{id}`");
+        check_mapping(input, &INPUT_FIELDS, &context, &at(&["inputs", &id]), &mut unknown);
+        if let Some(binding) = input.get("inputBinding").and_then(Value::as_mapping) {
+            let path = at(&["inputs", &id, "inputBinding"]);
+            check_mapping(binding, &INPUT_BINDING_FIELDS, &format!("inputBinding of {context}"), &path, &mut unknown);
+        }
+    }
+    for (id, output) in get_entries(mapping.get("outputs")) {
+        let context = format!("output `{id}`");
+        check_mapping(output, output_fields, &context, &at(&["outputs", &id]), &mut unknown);
+        if let Some(binding) = output.get("outputBinding").and_then(Value::as_mapping) {
+            let path = at(&["outputs", &id, "outputBinding"]);
+            check_mapping(binding, &OUTPUT_BINDING_FIELDS, &format!("outputBinding of {context}"), &path, &mut unknown);
+        }
+    }
+    if let Some(Value::Sequence(arguments)) = mapping.get("arguments") {
+        for (i, argument) in arguments.iter().enumerate() {
+            if let Some(binding) = argument.as_mapping() {
+                check_mapping(binding, &INPUT_BINDING_FIELDS, &format!("argument {i}"), &at(&["arguments", &i.to_string()]), &mut unknown);
+            }
+        }
+    }
+    for (id, step) in get_entries(mapping.get("steps")) {
+        check_mapping(step, &STEP_FIELDS, &format!("step `{id}`"), &at(&["steps", &id]), &mut unknown);
+        for (input_id, input) in get_entries(step.get("in")) {
+            let path = at(&["steps", &id, "in", &input_id]);
+            check_mapping(input, &STEP_INPUT_FIELDS, &format!("input `{input_id}` of step `{id}`"), &path, &mut unknown);
+        }
+        //inline processes are checked as well
+        if let Some(run) = step.get("run").filter(|run| run.is_mapping()) {
+            unknown.extend(find_unknown_fields_at(run, &at(&["steps", &id, "run"])));
+        }
+    }
+    unknown
+}
+
+fn check_mapping(mapping: &Mapping, allowed: &[&str], context: &str, path: &[String], unknown: &mut Vec<UnknownField>) {
+    for key in mapping.keys().filter_map(Value::as_str) {
+        if !allowed.contains(&key) && !key.contains(':') && !key.starts_with('$') {
+            unknown.push(UnknownField {
+                field: key.to_string(),
+                context: context.to_string(),
+                path: path.iter().cloned().chain([key.to_string()]).collect(),
+            });
+        }
+    }
+}
+
+/// Entries given as sequence of mappings with `id` or as mapping from id to entry, shorthand entries like
+/// `input: string` have no fields to check
+fn get_entries(value: Option<&Value>) -> Vec<(String, &Mapping)> {
+    match value {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| {
+                let item = item.as_mapping()?;
+                let id = item.get("id").and_then(Value::as_str).unwrap_or_default();
+                Some((id.to_string(), item))
+            })
+            .collect(),
+        Some(Value::Mapping(items)) => items
+            .iter()
+            .filter_map(|(id, item)| Some((id.as_str()?.to_string(), item.as_mapping()?)))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clt::CommandLineTool, parse_cwl};
+
+    const TOOL: &str = "class: CommandLineTool
+cwlVersion: v1.2
+baseCommand: echo
+s:author: someone
+inputs:
+  message:
+    type: string
+    inputBinding: {position: 1, prefx: -m}
+  count: int
+outputs:
+- id: out
+  type: stdout
+  colour: blue
+";
+
+    #[test]
+    fn test_find_unknown_fields() {
+        let document: Value = serde_yaml::from_str(TOOL).unwrap();
+        let unknown = find_unknown_fields(&document);
+        assert_eq!(
+            unknown,
+            vec![
+                UnknownField {
+                    field: "prefx".to_string(),
+                    context: "inputBinding of input `message`".to_string(),
+                    path: vec!["inputs".to_string(), "message".to_string(), "inputBinding".to_string(), "prefx".to_string()],
+                },
+                UnknownField {
+                    field: "colour".to_string(),
+                    context: "output `out`".to_string(),
+                    path: vec!["outputs".to_string(), "out".to_string(), "colour".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert!(parse_cwl::<CommandLineTool>(TOOL).is_ok());
+        set_parse_mode(ParseMode::Strict);
+        let result = parse_cwl::<CommandLineTool>(TOOL);
+        set_parse_mode(ParseMode::Permissive);
+        assert!(matches!(result, Err(CWLError::UnknownField { field, .. }) if field == "prefx"));
+    }
+}
@@ -1,9 +1,8 @@
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_yaml::Value;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Default, PartialEq, Clone)]
 pub enum CWLType {
     #[default]
     Null,
@@ -13,14 +12,91 @@ pub enum CWLType {
     Float,
     Double,
     String,
-    #[serde(rename = "File")]
     File,
-    #[serde(rename = "Directory")]
     Directory,
-    #[serde(rename = "Any")]
     Any,
     Stdout,
     Stderr,
+    /// Array of the given item type, written as `File[]` or `{type: array, items: File}`
+    Array(Box<CWLType>),
+}
+
+impl CWLType {
+    pub fn is_array(&self) -> bool {
+        matches!(self, CWLType::Array(_))
+    }
+}
+
+impl fmt::Display for CWLType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CWLType::Null => write!(f, "null"),
+            CWLType::Boolean => write!(f, "boolean"),
+            CWLType::Int => write!(f, "int"),
+            CWLType::Long => write!(f, "long"),
+            CWLType::Float => write!(f, "float"),
+            CWLType::Double => write!(f, "double"),
+            CWLType::String => write!(f, "string"),
+            CWLType::File => write!(f, "File"),
+            CWLType::Directory => write!(f, "Directory"),
+            CWLType::Any => write!(f, "Any"),
+            CWLType::Stdout => write!(f, "stdout"),
+            CWLType::Stderr => write!(f, "stderr"),
+            CWLType::Array(items) => write!(f, "{items}[]"),
+        }
+    }
+}
+
+impl FromStr for CWLType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(items) = s.strip_suffix("[]") {
+            return Ok(CWLType::Array(Box::new(items.parse()?)));
+        }
+        match s {
+            "null" => Ok(CWLType::Null),
+            "boolean" => Ok(CWLType::Boolean),
+            "int" => Ok(CWLType::Int),
+            "long" => Ok(CWLType::Long),
+            "float" => Ok(CWLType::Float),
+            "double" => Ok(CWLType::Double),
+            "string" => Ok(CWLType::String),
+            "File" => Ok(CWLType::File),
+            "Directory" => Ok(CWLType::Directory),
+            "Any" => Ok(CWLType::Any),
+            "stdout" => Ok(CWLType::Stdout),
+            "stderr" => Ok(CWLType::Stderr),
+            _ => Err(format!("unknown CWL type `{s}`")),
+        }
+    }
+}
+
+impl Serialize for CWLType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for CWLType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+        match &value {
+            Value::String(type_str) => type_str.parse().map_err(serde::de::Error::custom),
+            Value::Mapping(map) if map.get("type").and_then(Value::as_str) == Some("array") => {
+                let items = map.get("items").ok_or_else(|| serde::de::Error::custom("array type is missing `items`"))?;
+                let items: CWLType = serde_yaml::from_value(items.clone()).map_err(serde::de::Error::custom)?;
+                Ok(CWLType::Array(Box::new(items)))
+            }
+            _ => Err(serde::de::Error::custom(format!("unsupported CWL type: {value:?}"))),
+        }
+    }
 }
 
 #[derive(Serialize, Debug, PartialEq, Clone)]
@@ -43,6 +119,11 @@ impl DefaultValue {
         }
     }
 
+    /// Converts the value back into its YAML representation, e.g. to be used as array item
+    pub fn to_value(&self) -> Value {
+        serde_yaml::to_value(self).unwrap_or_default()
+    }
+
     pub fn has_matching_type(&self, cwl_type: &CWLType) -> bool {
         matches!(
             (self, cwl_type),
@@ -190,13 +271,15 @@ pub enum EnviromentDefs {
     Map(HashMap<String, String>),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Listing {
     pub entryname: String,
     pub entry: Entry,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub writable: bool,
 }
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(untagged)]
 pub enum Entry {
     Source(String),
@@ -249,6 +332,9 @@ pub enum OutputItem {
     OutputFile(OutputFile),
     OutputDirectory(OutputDirectory),
     OutputString(String),
+    OutputArray(Vec<OutputItem>),
+    /// Output of a step that was skipped as its `when` condition was false
+    OutputNull,
 }
 
 impl OutputItem {
@@ -257,6 +343,8 @@ impl OutputItem {
             OutputItem::OutputFile(output_file) => DefaultValue::File(File::from_location(&output_file.path)),
             OutputItem::OutputDirectory(output_directory) => DefaultValue::Directory(Directory::from_location(&output_directory.path)),
             OutputItem::OutputString(output_string) => DefaultValue::Any(Value::String(output_string.to_string())),
+            OutputItem::OutputArray(items) => DefaultValue::Any(Value::Sequence(items.iter().map(|i| i.to_default_value().to_value()).collect())),
+            OutputItem::OutputNull => DefaultValue::Any(Value::Null),
         }
     }
 }
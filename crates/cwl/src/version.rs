@@ -0,0 +1,227 @@
+use serde_yaml::{Mapping, Value};
+use crate::error::CWLError;
+
+/// The CWL version documents are created in and upgraded to
+pub const CWL_VERSION: &str = "v1.2";
+
+/// Requirements and hints introduced as cwltool extensions in v1.0 that became part of the standard in v1.1
+const V1_0_EXTENSIONS: [(&str, &str); 5] = [
+    ("LoadListingRequirement", "LoadListingRequirement"),
+    ("InplaceUpdateRequirement", "InplaceUpdateRequirement"),
+    ("TimeLimit", "ToolTimeLimit"),
+    ("WorkReuse", "WorkReuse"),
+    ("NetworkAccess", "NetworkAccess"),
+];
+const CWLTOOL_PREFIXES: [&str; 2] = ["cwltool:", "http://commonwl.org/cwltool#"];
+
+/// Returns the `cwlVersion` of a CWL document if given
+pub fn get_cwl_version(document: &Value) -> Option<&str> {
+    document.get("cwlVersion").and_then(|v| v.as_str())
+}
+
+/// Upgrades a CWL document written against v1.0 or v1.1 to v1.2 in place. Deprecated fields are mapped to their
+/// successors and defaults that changed between versions are made explicit, so that the document keeps its meaning.
+/// Returns whether the document has been changed.
+pub fn upgrade_to_v12(document: &mut Value) -> Result<bool, CWLError> {
+    let version = match get_cwl_version(document) {
+        Some(version) => version.to_string(),
+        None => return Ok(false),
+    };
+
+    if version.starts_with("v1.0") {
+        upgrade_v1_0_to_v1_1(document);
+    } else if !version.starts_with("v1.1") {
+        if version.starts_with(CWL_VERSION) {
+            return Ok(false);
+        }
+        return Err(CWLError::UnsupportedVersion(version));
+    }
+
+    if let Value::Mapping(map) = document {
+        map.insert(Value::String("cwlVersion".to_string()), Value::String(CWL_VERSION.to_string()));
+    }
+    Ok(true)
+}
+
+fn upgrade_v1_0_to_v1_1(document: &mut Value) {
+    rename_extensions(document, "requirements");
+    rename_extensions(document, "hints");
+
+    let class = document.get("class").and_then(|c| c.as_str()).unwrap_or_default().to_string();
+    match class.as_str() {
+        "CommandLineTool" => {
+            move_up_load_contents(document);
+            //network access was allowed by default and directory listings were loaded deeply in v1.0
+            if !has_requirement(document, "NetworkAccess") {
+                add_requirement(document, "NetworkAccess", "networkAccess", Value::Bool(true));
+            }
+            if !has_requirement(document, "LoadListingRequirement") {
+                add_requirement(document, "LoadListingRequirement", "loadListing", Value::String("deep_listing".to_string()));
+            }
+        }
+        "ExpressionTool" => move_up_load_contents(document),
+        "Workflow" => {
+            if let Some(Value::Sequence(steps)) = document.get_mut("steps") {
+                for step in steps {
+                    if let Some(run) = step.get_mut("run").filter(|r| r.is_mapping()) {
+                        upgrade_v1_0_to_v1_1(run);
+                    }
+                }
+            } else if let Some(Value::Mapping(steps)) = document.get_mut("steps") {
+                for (_, step) in steps.iter_mut() {
+                    if let Some(run) = step.get_mut("run").filter(|r| r.is_mapping()) {
+                        upgrade_v1_0_to_v1_1(run);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn get_class(requirement: &Value) -> Option<&str> {
+    requirement.get("class").and_then(|c| c.as_str())
+}
+
+fn upgrade_class(class: &str) -> Option<&'static str> {
+    let stripped = CWLTOOL_PREFIXES.iter().find_map(|prefix| class.strip_prefix(prefix))?;
+    V1_0_EXTENSIONS.iter().find(|(old, _)| *old == stripped).map(|(_, new)| *new)
+}
+
+/// Renames cwltool extensions to their standardized counterparts, e.g. `cwltool:TimeLimit` becomes `ToolTimeLimit`
+fn rename_extensions(document: &mut Value, key: &str) {
+    match document.get_mut(key) {
+        Some(Value::Sequence(requirements)) => {
+            for requirement in requirements {
+                if let Some(new_class) = get_class(requirement).and_then(upgrade_class) {
+                    requirement["class"] = Value::String(new_class.to_string());
+                }
+            }
+        }
+        Some(Value::Mapping(requirements)) => {
+            let renamed: Mapping = std::mem::take(requirements)
+                .into_iter()
+                .map(|(class, value)| match class.as_str().and_then(upgrade_class) {
+                    Some(new_class) => (Value::String(new_class.to_string()), value),
+                    None => (class, value),
+                })
+                .collect();
+            *requirements = renamed;
+        }
+        _ => {}
+    }
+}
+
+fn has_requirement(document: &Value, class: &str) -> bool {
+    ["requirements", "hints"].iter().any(|key| match document.get(key) {
+        Some(Value::Sequence(requirements)) => requirements.iter().any(|r| get_class(r) == Some(class)),
+        Some(Value::Mapping(requirements)) => requirements.contains_key(class),
+        _ => false,
+    })
+}
+
+fn add_requirement(document: &mut Value, class: &str, field: &str, value: Value) {
+    let Value::Mapping(map) = document else {
+        return;
+    };
+    let requirements = map
+        .entry(Value::String("requirements".to_string()))
+        .or_insert_with(|| Value::Sequence(vec![]));
+    match requirements {
+        Value::Sequence(requirements) => {
+            let mut requirement = Mapping::new();
+            requirement.insert(Value::String("class".to_string()), Value::String(class.to_string()));
+            requirement.insert(Value::String(field.to_string()), value);
+            requirements.push(Value::Mapping(requirement));
+        }
+        Value::Mapping(requirements) => {
+            let mut requirement = Mapping::new();
+            requirement.insert(Value::String(field.to_string()), value);
+            requirements.insert(Value::String(class.to_string()), Value::Mapping(requirement));
+        }
+        _ => {}
+    }
+}
+
+/// `loadContents` moved from `inputBinding` to the input parameter itself in v1.1
+fn move_up_load_contents(document: &mut Value) {
+    let move_up = |input: &mut Value| {
+        let load_contents = input
+            .get_mut("inputBinding")
+            .and_then(|b| b.as_mapping_mut())
+            .and_then(|b| b.remove("loadContents"));
+        if let (Some(load_contents), Value::Mapping(input)) = (load_contents, input) {
+            input.insert(Value::String("loadContents".to_string()), load_contents);
+        }
+    };
+    match document.get_mut("inputs") {
+        Some(Value::Sequence(inputs)) => inputs.iter_mut().for_each(move_up),
+        Some(Value::Mapping(inputs)) => inputs.iter_mut().for_each(|(_, input)| move_up(input)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clt::CommandLineTool, requirements::Requirement, wf::Workflow};
+
+    #[test]
+    fn test_upgrade_tool_v1_0() {
+        let cwl = r#"
+cwlVersion: v1.0
+class: CommandLineTool
+hints:
+  cwltool:TimeLimit:
+    timelimit: 60
+inputs:
+  file:
+    type: File
+    inputBinding:
+      loadContents: true
+      position: 1
+outputs: []
+baseCommand: cat
+"#;
+        let mut document: Value = serde_yaml::from_str(cwl).unwrap();
+        assert!(upgrade_to_v12(&mut document).unwrap());
+        assert_eq!(get_cwl_version(&document), Some("v1.2"));
+        assert_eq!(document["inputs"]["file"]["loadContents"], Value::Bool(true));
+        assert!(document["inputs"]["file"]["inputBinding"].get("loadContents").is_none());
+
+        let tool: CommandLineTool = serde_yaml::from_value(document).unwrap();
+        let requirements = tool.requirements.unwrap();
+        assert!(requirements.contains(&Requirement::NetworkAccess(crate::requirements::NetworkAccess { network_access: true })));
+        assert!(requirements.iter().any(|r| matches!(r, Requirement::LoadListingRequirement(_))));
+        assert!(matches!(tool.hints.unwrap()[0], Requirement::ToolTimeLimit(_)));
+    }
+
+    #[test]
+    fn test_upgrade_workflow_v1_1() {
+        let cwl = r#"
+cwlVersion: v1.1
+class: Workflow
+inputs: []
+outputs: []
+steps: []
+"#;
+        let mut document: Value = serde_yaml::from_str(cwl).unwrap();
+        assert!(upgrade_to_v12(&mut document).unwrap());
+        let workflow: Workflow = serde_yaml::from_value(document).unwrap();
+        assert_eq!(workflow.cwl_version, CWL_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_v1_2_unchanged() {
+        let mut document: Value = serde_yaml::from_str("cwlVersion: v1.2\nclass: CommandLineTool").unwrap();
+        let expected = document.clone();
+        assert!(!upgrade_to_v12(&mut document).unwrap());
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn test_upgrade_unsupported() {
+        let mut document: Value = serde_yaml::from_str("cwlVersion: draft-3\nclass: CommandLineTool").unwrap();
+        assert!(upgrade_to_v12(&mut document).is_err());
+    }
+}
@@ -5,7 +5,10 @@ use super::{
     requirements::{deserialize_requirements, Requirement},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+};
 
 /// Represents a CWL Workflow, a  process characterized by multiple subprocess steps,
 /// where step outputs are connected to the inputs of downstream steps to form a
@@ -71,12 +74,9 @@ impl Workflow {
 
     pub fn has_step_input(&self, id: &str) -> bool {
         self.steps.iter().any(|step| {
-            step.in_.clone().into_values().any(|val| {
-                let src = match val {
-                    WorkflowStepInput::String(str) => str,
-                    WorkflowStepInput::Parameter(par) => par.source.unwrap_or_default(),
-                };
-                src == id
+            step.in_.values().any(|val| match val {
+                WorkflowStepInput::String(str) => str == id,
+                WorkflowStepInput::Parameter(par) => par.source.as_ref().is_some_and(|source| source.ids().contains(&id)),
             })
         })
     }
@@ -106,21 +106,16 @@ impl Workflow {
             in_degree.entry(step.id.clone()).or_insert(0);
 
             for input in step.in_.values() {
-                let parts: Vec<&str> = match input {
-                    WorkflowStepInput::String(string) => string.split('/').collect(),
-                    WorkflowStepInput::Parameter(parameter) => {
-                        if let Some(source) = &parameter.source {
-                            source.split('/').collect()
-                        } else {
-                            vec![]
-                        }
-                    }
+                let sources = match input {
+                    WorkflowStepInput::String(string) => vec![string.as_str()],
+                    WorkflowStepInput::Parameter(parameter) => parameter.source.as_ref().map(Source::ids).unwrap_or_default(),
                 };
 
-                if parts.len() == 2 {
-                    let dependency = parts[0];
-                    graph.entry(dependency.to_string()).or_default().push(step.id.clone());
-                    *in_degree.entry(step.id.clone()).or_insert(0) += 1;
+                for source in sources {
+                    if let [dependency, _] = source.split('/').collect::<Vec<_>>()[..] {
+                        graph.entry(dependency.to_string()).or_default().push(step.id.clone());
+                        *in_degree.entry(step.id.clone()).or_insert(0) += 1;
+                    }
                 }
             }
         }
@@ -158,7 +153,153 @@ pub struct WorkflowStep {
     pub run: String,
     pub in_: HashMap<String, WorkflowStepInput>,
     pub out: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scatter: Option<Scatter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scatter_method: Option<ScatterMethod>,
+    /// Condition the step is only run if it evaluates to `true`, otherwise its outputs are `null`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Name of the section of the workflow the step belongs to, steps of the same group are drawn together in graphs
+    #[serde(rename = "s4n:group", skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl WorkflowStep {
+    /// Returns the ids of all steps whose outputs are connected to the inputs of the step
+    pub fn get_source_steps(&self) -> Vec<String> {
+        let mut steps = self
+            .in_
+            .values()
+            .flat_map(|input| match input {
+                WorkflowStepInput::String(source) => vec![source.as_str()],
+                WorkflowStepInput::Parameter(parameter) => parameter.source.as_ref().map(Source::ids).unwrap_or_default(),
+            })
+            .filter_map(|source| source.split_once('/').map(|(step, _)| step.to_string()))
+            .collect::<Vec<_>>();
+        steps.sort();
+        steps.dedup();
+        steps
+    }
+
+    /// Returns the ids of all inputs the step is scattered over
+    pub fn get_scatter_inputs(&self) -> Vec<String> {
+        match &self.scatter {
+            Some(Scatter::Single(input)) => vec![input.clone()],
+            Some(Scatter::Multiple(inputs)) => inputs.clone(),
+            None => vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Scatter {
+    Single(String),
+    Multiple(Vec<String>),
 }
+
+/// Defines how multiple scattered inputs are combined.
+///
+/// Reference: [CWL Scatter](https://www.commonwl.org/v1.2/Workflow.html#WorkflowStep)
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+pub enum ScatterMethod {
+    #[default]
+    #[serde(rename = "dotproduct")]
+    DotProduct,
+    #[serde(rename = "nested_crossproduct")]
+    NestedCrossProduct,
+    #[serde(rename = "flat_crossproduct")]
+    FlatCrossProduct,
+}
+/// One or multiple sources connected to a step input or workflow output. Multiple sources are merged as defined by
+/// `linkMerge` and need a `MultipleInputFeatureRequirement`.
+///
+/// Reference: [CWL WorkflowStepInput](https://www.commonwl.org/v1.2/Workflow.html#WorkflowStepInput)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Source {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::Single(String::default())
+    }
+}
+
+impl Source {
+    /// Returns the ids of all connected sources
+    pub fn ids(&self) -> Vec<&str> {
+        match self {
+            Source::Single(source) => vec![source.as_str()],
+            Source::Multiple(sources) => sources.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn is_multiple(&self) -> bool {
+        matches!(self, Source::Multiple(_))
+    }
+}
+
+impl From<String> for Source {
+    fn from(source: String) -> Self {
+        Source::Single(source)
+    }
+}
+
+impl From<&str> for Source {
+    fn from(source: &str) -> Self {
+        Source::Single(source.to_string())
+    }
+}
+
+impl PartialEq<str> for Source {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Source::Single(source) if source == other)
+    }
+}
+
+impl PartialEq<&str> for Source {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ids().join(", "))
+    }
+}
+
+/// Defines how the values of multiple sources are combined.
+///
+/// Reference: [CWL WorkflowStepInput](https://www.commonwl.org/v1.2/Workflow.html#WorkflowStepInput)
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMerge {
+    /// one array item per source
+    #[default]
+    MergeNested,
+    /// arrays of the sources are concatenated, single values are appended
+    MergeFlattened,
+}
+
+/// Defines how `null` values of conditional steps are removed from the values of a source.
+///
+/// Reference: [CWL Conditional Execution](https://www.commonwl.org/v1.2/Workflow.html#Conditional_execution_(Optional))
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PickValue {
+    /// the first value that is not `null`, fails if all values are `null`
+    FirstNonNull,
+    /// the single value that is not `null`, fails if none or several values are not `null`
+    TheOnlyNonNull,
+    /// all values that are not `null`, as array
+    AllNonNull,
+}
+
 impl Identifiable for WorkflowStep {
     fn id(&self) -> &str {
         &self.id
@@ -174,6 +315,79 @@ mod tests {
     use super::*;
     use std::fs;
 
+    #[test]
+    fn test_scatter_step() {
+        let yaml = r"
+id: echo
+run: echo.cwl
+in:
+  message: messages
+  prefix: prefixes
+out: [out]
+scatter: [message, prefix]
+scatterMethod: flat_crossproduct";
+        let step: WorkflowStep = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(step.get_scatter_inputs(), vec!["message", "prefix"]);
+        assert_eq!(step.scatter_method, Some(ScatterMethod::FlatCrossProduct));
+
+        let step: WorkflowStep = serde_yaml::from_str("run: echo.cwl\nin: {}\nout: []\nscatter: message").unwrap();
+        assert_eq!(step.get_scatter_inputs(), vec!["message"]);
+        assert_eq!(step.scatter_method, None);
+    }
+
+    #[test]
+    fn test_conditional_step() {
+        let yaml = r"
+run: echo.cwl
+in:
+  message:
+    source: first/out
+    pickValue: first_non_null
+out: [out]
+when: $(inputs.message !== null)";
+        let step: WorkflowStep = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(step.when, Some("$(inputs.message !== null)".to_string()));
+        let WorkflowStepInput::Parameter(parameter) = &step.in_["message"] else {
+            panic!("message is not a parameter");
+        };
+        assert_eq!(parameter.pick_value, Some(PickValue::FirstNonNull));
+        assert_eq!(serde_yaml::to_string(&PickValue::TheOnlyNonNull).unwrap().trim(), "the_only_non_null");
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        let yaml = r"
+run: gather.cwl
+in:
+  files: [a/out, b/out]
+  merged:
+    source: [a/out, b/out]
+    linkMerge: merge_flattened
+  single: c/out
+out: [out]";
+        let step: WorkflowStep = serde_yaml::from_str(yaml).unwrap();
+        let WorkflowStepInput::Parameter(files) = &step.in_["files"] else {
+            panic!("files is not a parameter");
+        };
+        let source = files.source.as_ref().unwrap();
+        assert!(source.is_multiple());
+        assert_eq!(source.ids(), vec!["a/out", "b/out"]);
+        let WorkflowStepInput::Parameter(merged) = &step.in_["merged"] else {
+            panic!("merged is not a parameter");
+        };
+        assert_eq!(merged.link_merge, Some(LinkMerge::MergeFlattened));
+        assert_eq!(step.in_["single"], WorkflowStepInput::String("c/out".to_string()));
+        assert_eq!(serde_yaml::to_string(&LinkMerge::MergeNested).unwrap().trim(), "merge_nested");
+    }
+
+    #[test]
+    fn test_step_group() {
+        let yaml = "run: clean.cwl\nin: {}\nout: []\ns4n:group: preprocessing\n";
+        let step: WorkflowStep = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(step.group, Some("preprocessing".to_string()));
+        assert!(serde_yaml::to_string(&step).unwrap().contains("s4n:group: preprocessing\n"));
+    }
+
     #[test]
     fn test_identifyable() {
         let mut input = WorkflowStep::default();
@@ -0,0 +1,186 @@
+use crate::{
+    commands::fmt::find_cwl_files,
+    container::get_image_digest,
+    datasets::{load_manifest, DATASET_MANIFEST},
+    environments::lock_environments,
+    io::get_workflows_folder,
+};
+use chrono::Utc;
+use cwl::{format::format_cwl, load_tool, load_workflow, packed::pack_workflow, requirements::DockerRequirement, requirements::Requirement};
+use git2::{build::CheckoutBuilder, Repository};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Name of the file describing the contents of a bundle
+pub const BUNDLE_METADATA: &str = "bundle.json";
+/// Folder the contents of an imported bundle are kept in besides the repository, relative to the project root
+pub const BUNDLE_DIR: &str = ".s4n/bundle";
+
+/// Folders within the bundle
+const REPOSITORY_DIR: &str = "repository";
+const PACKED_DIR: &str = "packed";
+const ENVIRONMENTS_DIR: &str = "environments";
+
+/// Description of a reproducibility bundle, written to [`BUNDLE_METADATA`]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct BundleMetadata {
+    pub name: String,
+    /// Commit of the project the bundle was created from
+    pub commit: String,
+    pub created: String,
+    /// Packed workflows within the `packed` folder
+    pub workflows: Vec<String>,
+    pub images: Vec<PinnedImage>,
+    /// Lockfiles within the `environments` folder
+    pub environments: Vec<String>,
+    /// Names of the datasets registered in the bundled manifest
+    pub datasets: Vec<String>,
+}
+
+/// Container image of a tool together with the digest it resolved to when the bundle was created
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PinnedImage {
+    /// Tool using the image, relative to the project root
+    pub tool: String,
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl PinnedImage {
+    /// Reference to the image pinned by its digest if known, e.g. `python@sha256:...`
+    pub fn reference(&self) -> String {
+        match &self.digest {
+            Some(digest) if !self.image.contains('@') => {
+                let name = self
+                    .image
+                    .rsplit_once(':')
+                    .filter(|(_, tag)| !tag.contains('/'))
+                    .map_or(self.image.as_str(), |(name, _)| name);
+                format!("{name}@{digest}")
+            }
+            _ => self.image.clone(),
+        }
+    }
+}
+
+/// Writes the git repository of the project, all workflows packed into single files, the images of all tools pinned to
+/// their digests, the dataset manifest and lockfiles of the managed environments into a zstd compressed tar archive
+pub fn create_bundle(project_dir: &Path, archive: &Path) -> Result<BundleMetadata, Box<dyn Error>> {
+    let repo = Repository::open(project_dir)?;
+    let commit = repo.head()?.peel_to_commit()?.id().to_string();
+    let mut metadata = BundleMetadata {
+        name: project_dir.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        commit,
+        created: Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    let mut builder = tar::Builder::new(zstd::Encoder::new(File::create(archive)?, 0)?);
+    builder.append_dir_all(REPOSITORY_DIR, repo.path())?;
+
+    for file in find_cwl_files(project_dir.join(get_workflows_folder())) {
+        let relative = file.strip_prefix(project_dir)?.to_string_lossy().replace('\\', "/");
+        if load_workflow(&file).is_ok() {
+            let name = format!("{}.cwl", file.file_stem().unwrap_or_default().to_string_lossy());
+            let packed = format_cwl(&pack_workflow(&file)?.to_string())?;
+            append_bytes(&mut builder, &format!("{PACKED_DIR}/{name}"), packed.as_bytes())?;
+            metadata.workflows.push(name);
+        } else if let Ok(tool) = load_tool(&file) {
+            let image = tool.requirements.iter().chain(tool.hints.iter()).flatten().find_map(|r| match r {
+                Requirement::DockerRequirement(DockerRequirement::DockerPull(image)) => Some(image.clone()),
+                Requirement::DockerRequirement(DockerRequirement::DockerFile { docker_image_id, .. }) => Some(docker_image_id.clone()),
+                _ => None,
+            });
+            if let Some(image) = image {
+                metadata.images.push(PinnedImage {
+                    tool: relative,
+                    digest: get_image_digest(&image),
+                    image,
+                });
+            }
+        }
+    }
+
+    let manifest = project_dir.join(DATASET_MANIFEST);
+    if manifest.exists() {
+        metadata.datasets = load_manifest(project_dir)?.into_keys().collect();
+        append_bytes(&mut builder, DATASET_MANIFEST, &fs::read(&manifest)?)?;
+    }
+
+    for (name, contents) in lock_environments(project_dir)? {
+        append_bytes(&mut builder, &format!("{ENVIRONMENTS_DIR}/{name}"), contents.as_bytes())?;
+        metadata.environments.push(name);
+    }
+
+    append_bytes(&mut builder, BUNDLE_METADATA, serde_json::to_string_pretty(&metadata)?.as_bytes())?;
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(metadata)
+}
+
+/// Restores a bundle into the given directory, which must not exist or be empty. The repository is checked out at
+/// the bundled commit, all other contents of the bundle are kept in [`BUNDLE_DIR`].
+pub fn import_bundle(archive: &Path, directory: &Path) -> Result<BundleMetadata, Box<dyn Error>> {
+    if directory.exists() && fs::read_dir(directory)?.next().is_some() {
+        return Err(format!("❌ Directory {:?} is not empty", directory).into());
+    }
+    let file = File::open(archive).map_err(|e| format!("❌ Could not open bundle {:?}: {}", archive, e))?;
+    let bundle_dir = directory.join(BUNDLE_DIR);
+    fs::create_dir_all(&bundle_dir)?;
+    tar::Archive::new(zstd::Decoder::new(file)?).unpack(&bundle_dir)?;
+    fs::write(bundle_dir.join(".gitignore"), "*")?;
+
+    let metadata: BundleMetadata = serde_json::from_str(
+        &fs::read_to_string(bundle_dir.join(BUNDLE_METADATA))
+            .map_err(|_| format!("❌ {:?} is not a bundle, {BUNDLE_METADATA} is missing", archive))?,
+    )?;
+    fs::rename(bundle_dir.join(REPOSITORY_DIR), directory.join(".git"))?;
+    let repo = Repository::open(directory)?;
+    repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+    Ok(metadata)
+}
+
+/// Paths of the environment lockfiles of a bundle imported into the given project
+pub fn get_bundled_environments(project_dir: &Path, metadata: &BundleMetadata) -> Vec<PathBuf> {
+    metadata
+        .environments
+        .iter()
+        .map(|name| project_dir.join(BUNDLE_DIR).join(ENVIRONMENTS_DIR).join(name))
+        .collect()
+}
+
+fn append_bytes<W: Write>(builder: &mut tar::Builder<W>, path: &str, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_image_reference() {
+        let image = |image: &str, digest: Option<&str>| PinnedImage {
+            tool: "workflows/tool/tool.cwl".to_string(),
+            image: image.to_string(),
+            digest: digest.map(str::to_string),
+        };
+        assert_eq!(image("python:3.12", Some("sha256:abc")).reference(), "python@sha256:abc");
+        assert_eq!(
+            image("localhost:5000/tool", Some("sha256:abc")).reference(),
+            "localhost:5000/tool@sha256:abc"
+        );
+        assert_eq!(image("python:3.12", None).reference(), "python:3.12");
+        assert_eq!(image("python@sha256:abc", Some("sha256:abc")).reference(), "python@sha256:abc");
+    }
+}
@@ -1,9 +1,17 @@
 use std::{error::Error, io};
 
 use crate::commands::{
+    bundle::{ExportCommands, ImportCommands},
+    data::DataCommands,
     execute::ExecuteCommands,
+    fmt::FmtArgs,
     init::InitArgs,
+    inputs::InputsCommands,
+    package::PackageCommands,
+    report::ReportCommands,
+    sbom::SbomArgs,
     tool::{CreateToolArgs, ToolCommands},
+    validate::ValidateArgs,
     workflow::WorkflowCommands,
     annotate::AnnotateCommands
 };
@@ -40,13 +48,54 @@ pub enum Commands {
         /// Name of the tool or workflow to annotate
         #[arg(value_name = "TOOL_NAME", required = false)]
         tool_name: Option<String>,
+        /// Only use cached ontology terms instead of querying the terminology service
+        #[arg(long = "offline", global = true)]
+        offline: bool,
     },
 
+    #[command(about = "Registers and lists datasets used by the project")]
+    Data {
+        #[command(subcommand)]
+        command: DataCommands,
+    },
+    #[command(about = "Creates job files with the inputs for runs of workflows and tools")]
+    Inputs {
+        #[command(subcommand)]
+        command: InputsCommands,
+    },
     #[command(about = "Execution of CWL Files locally or on remote servers", visible_alias = "ex")]
     Execute {
         #[command(subcommand)]
         command: ExecuteCommands,
     },
+    #[command(about = "Packs and publishes the project's workflows")]
+    Package {
+        #[command(subcommand)]
+        command: PackageCommands,
+    },
+    #[command(about = "Exports the project for use elsewhere")]
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+    #[command(about = "Imports projects exported by s4n export")]
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
+    #[command(about = "Creates reports about workflows, e.g. for publications")]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    #[command(about = "Creates a software bill of materials of the images and packages used by a workflow")]
+    Sbom(SbomArgs),
+    #[command(about = "Validates a CWL CommandLineTool or Workflow without executing it")]
+    Validate(ValidateArgs),
+    #[command(about = "Formats CWL files of the project in a consistent style")]
+    Fmt(FmtArgs),
+    #[command(about = "Opens a terminal user interface to browse, run and follow workflows")]
+    Tui,
     Sync,
     #[command(about = "Generate shell completions")]
     Completions{
@@ -1,10 +1,20 @@
+use crate::{
+    ontology::{search_terms, set_offline, Term},
+    units::{to_unit_iri, Quantity, UO_PREFIX},
+    wizard::print_diff,
+};
+use calamine::{open_workbook_auto, Reader};
 use clap::{Args, Subcommand};
 use colored::*;
-use cwl::format::format_cwl;
+use cwl::{
+    format::{format_cwl, has_anchors},
+    requirements::S4N_NAMESPACE,
+};
 use dialoguer::Select;
-use log::error;
+use log::{error, info, warn};
+use serde::Deserialize;
 use serde_yaml::{Mapping, Value};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fs::File;
 use std::io::BufRead;
@@ -13,6 +23,7 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::{env, fs, path::Path};
 use tokio::runtime::Builder;
+use walkdir::WalkDir;
 
 const REST_URL_TS: &str = "https://ts4nfdi-api-gateway.prod.km.k8s.zbmed.de/api-gateway/search?query=";
 const SCHEMAORG_NAMESPACE: &str = "https://schema.org/";
@@ -21,8 +32,9 @@ const ARC_NAMESPACE: &str = "https://github.com/nfdi4plants/ARC_ontology";
 const ARC_SCHEMA: &str = "https://raw.githubusercontent.com/nfdi4plants/ARC_ontology/main/ARC_v2.0.owl";
 const MAX_RECOMMENDATIONS: usize = 10;
 
-pub fn handle_annotation_command(command: &Option<AnnotateCommands>, tool_name: &Option<String>) -> Result<(), Box<dyn Error>> {
+pub fn handle_annotation_command(command: &Option<AnnotateCommands>, tool_name: &Option<String>, offline: bool) -> Result<(), Box<dyn Error>> {
     let runtime = Builder::new_current_thread().enable_all().build()?;
+    set_offline(offline);
 
     if let Some(subcommand) = command {
         runtime.block_on(handle_annotate_commands(subcommand))?;
@@ -62,8 +74,10 @@ pub async fn handle_annotate_commands(command: &AnnotateCommands) -> Result<(),
         }
         AnnotateCommands::Performer(args) => annotate_performer(args).await,
         AnnotateCommands::Process(args) => annotate_process_step(args).await,
+        AnnotateCommands::Input(args) => annotate_input(args).await,
         AnnotateCommands::Container { cwl_name, container } => annotate_container(cwl_name, container),
         AnnotateCommands::Custom { cwl_name, field, value } => annotate_field(cwl_name, field, value),
+        AnnotateCommands::FromFile(args) => annotate_from_file(args),
     }
 }
 
@@ -119,6 +133,9 @@ pub enum AnnotateCommands {
     #[command(about = "Annotates a process arc ontolology")]
     Process(AnnotateProcessArgs),
 
+    #[command(about = "Annotates unit and allowed range of a numeric input (schema.org, UO ontology)")]
+    Input(AnnotateInputArgs),
+
     #[command(about = "Annotates container information of a tool or workflow")]
     Container {
         #[arg(help = "Name of the CWL file")]
@@ -135,6 +152,8 @@ pub enum AnnotateCommands {
         #[arg(help = "Value for the field")]
         value: String,
     },
+    #[command(about = "Annotates all CWL files in the workflows folder from a metadata YAML file or ISA investigation")]
+    FromFile(AnnotateFromFileArgs),
 }
 
 #[derive(Args, Debug)]
@@ -172,6 +191,61 @@ pub struct PerformerArgs {
     pub role: Option<String>,
 }
 
+/// Arguments for annotate input command
+#[derive(Args, Debug)]
+pub struct AnnotateInputArgs {
+    #[arg(help = "Name of the CWL file")]
+    pub cwl_name: String,
+
+    #[arg(help = "Input to annotate")]
+    pub input: String,
+
+    #[arg(short = 'u', long = "unit", help = "Unit of the input, a UO term like UO:0000027 or a name to search in the UO ontology")]
+    pub unit: Option<String>,
+
+    #[arg(long = "min", allow_hyphen_values = true, help = "Smallest allowed value")]
+    pub min: Option<f64>,
+
+    #[arg(long = "max", allow_hyphen_values = true, help = "Largest allowed value")]
+    pub max: Option<f64>,
+}
+
+/// Arguments for annotate from-file command
+#[derive(Args, Debug)]
+pub struct AnnotateFromFileArgs {
+    #[arg(default_value = "metadata.yml", help = "Metadata YAML file or ISA investigation (.csv, .xlsx)")]
+    pub file: String,
+}
+
+/// Project metadata applied to all CWL files by the annotate from-file command
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ProjectMetadata {
+    pub license: Option<String>,
+    pub authors: Vec<MetadataPerson>,
+    pub contributors: Vec<MetadataPerson>,
+    pub performers: Vec<MetadataPerformer>,
+    pub container: Option<String>,
+    pub custom: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct MetadataPerson {
+    pub name: String,
+    pub mail: Option<String>,
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct MetadataPerformer {
+    pub first_name: String,
+    pub last_name: String,
+    pub mail: Option<String>,
+    pub affiliation: Option<String>,
+    pub role: Option<String>,
+    pub role_accession: Option<String>,
+}
+
 /// Arguments for annotate process command
 #[derive(Args, Debug)]
 pub struct AnnotateProcessArgs {
@@ -225,12 +299,14 @@ pub fn annotate_person(args: &PersonArgs, role: &str) -> Result<(), Box<dyn Erro
 
         // Check if the selected role (author or contributor) exists and is a sequence, then add new person
         if let Some(Value::Sequence(ref mut persons)) = mapping.get_mut(role_key) {
-            // Check if the person already exists
+            // Check if the person already exists, persons without identifier are matched by name
             let person_exists = persons.iter().any(|person| {
                 if let Value::Mapping(ref existing_person) = person {
-                    if let Some(Value::String(ref id)) = existing_person.get(Value::String("s:identifier".to_string())) {
-                        return id == &args.id.clone().unwrap_or_default();
-                    }
+                    let (key, value) = match args.id {
+                        Some(ref id) => ("s:identifier", id),
+                        None => ("s:name", &args.name),
+                    };
+                    return existing_person.get(Value::String(key.to_string())) == Some(&Value::String(value.clone()));
                 }
                 false
             });
@@ -305,6 +381,20 @@ pub fn annotate_container(cwl_name: &str, container_value: &str) -> Result<(), B
 }
 
 pub async fn annotate_performer(args: &PerformerArgs) -> Result<(), Box<dyn Error>> {
+    // Look up the role in the terminology service
+    let role = match args.role {
+        Some(ref role) => {
+            let mut role_mapping = Mapping::new();
+            role_mapping.insert(Value::String("class".to_string()), Value::String("arc:role".to_string()));
+            Some(process_annotation_with_mapping(role, role_mapping, false).await?)
+        }
+        None => None,
+    };
+    add_performer(args, role)
+}
+
+/// Adds a performer with an already annotated role, performers are identified by their email or name if no email is given
+fn add_performer(args: &PerformerArgs, role: Option<Mapping>) -> Result<(), Box<dyn Error>> {
     // Ensure ARC namespace and schema are defined
     annotate(&args.cwl_name, "$schemas", None, Some(ARC_SCHEMA))?;
     annotate(&args.cwl_name, "$namespaces", Some("arc"), Some(ARC_NAMESPACE))?;
@@ -334,14 +424,7 @@ pub async fn annotate_performer(args: &PerformerArgs) -> Result<(), Box<dyn Erro
     }
 
     // Handle role information
-    if let Some(ref role) = args.role {
-        let mut role_mapping = Mapping::new();
-        role_mapping.insert(Value::String("class".to_string()), Value::String("arc:role".to_string()));
-
-        // Process role annotations
-        let annotation_value = process_annotation_with_mapping(role, role_mapping.clone(), false).await?;
-        role_mapping.extend(annotation_value);
-
+    if let Some(role_mapping) = role {
         // Add the role to the performer info
         let has_role_key = Value::String("arc:has role".to_string());
         match performer_info.get_mut(&has_role_key) {
@@ -356,12 +439,14 @@ pub async fn annotate_performer(args: &PerformerArgs) -> Result<(), Box<dyn Erro
     let performer_key = Value::String("arc:performer".to_string());
     match mapping.get_mut(&performer_key) {
         Some(Value::Sequence(performers)) => {
-            // Check if the performer already exists based on email match
+            // Check if the performer already exists based on email match or name if there is no email
             let performer_exists = performers.iter().any(|performer| {
                 if let Value::Mapping(existing_performer) = performer {
-                    args.mail
-                        .as_ref()
-                        .is_some_and(|mail| existing_performer.get(Value::String("arc:email".to_string())) == Some(&Value::String(mail.clone())))
+                    let has = |key: &str, value: &str| existing_performer.get(Value::String(key.to_string())) == Some(&Value::String(value.to_string()));
+                    match args.mail {
+                        Some(ref mail) => has("arc:email", mail),
+                        None => has("arc:first name", &args.first_name) && has("arc:last name", &args.last_name),
+                    }
                 } else {
                     false
                 }
@@ -430,9 +515,32 @@ pub fn annotate(name: &str, namespace_key: &str, key: Option<&str>, value: Optio
     write_updated_yaml(name, &yaml)
 }
 
+/// Declares the `s4n` namespace if the document uses extensions like `s4n:RetryPolicy` or `s4n:group`
+pub fn annotate_s4n_namespace(cwl_name: &str) -> Result<(), Box<dyn Error>> {
+    if uses_s4n_extensions(&parse_cwl(cwl_name)?) {
+        annotate(cwl_name, "$namespaces", Some("s4n"), Some(S4N_NAMESPACE))?;
+    }
+    Ok(())
+}
+
+/// Extensions are used as field names or, in lists of requirements, as `class`
+fn uses_s4n_extensions(value: &Value) -> bool {
+    let is_extension = |value: &Value| value.as_str().is_some_and(|s| s.starts_with("s4n:"));
+    match value {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(key, value)| is_extension(key) || (key.as_str() == Some("class") && is_extension(value)) || uses_s4n_extensions(value)),
+        Value::Sequence(items) => items.iter().any(uses_s4n_extensions),
+        _ => false,
+    }
+}
+
 /// Helper function to write updated YAML to a file.
 pub fn write_updated_yaml(name: &str, yaml: &Value) -> Result<(), Box<dyn Error>> {
     let path = get_filename(name)?;
+    if fs::read_to_string(&path).is_ok_and(|contents| has_anchors(&contents)) {
+        warn!("Anchors and aliases in {} are expanded when saving the annotations", path);
+    }
 
     // Convert the YAML content to a string and write it to the file
     let yaml_str = serde_yaml::to_string(&yaml).map_err(|e| format!("Failed to serialize YAML: {e}"))?;
@@ -445,7 +553,8 @@ pub fn write_updated_yaml(name: &str, yaml: &Value) -> Result<(), Box<dyn Error>
 }
 
 pub fn annotate_field(cwl_name: &str, field: &str, value: &str) -> Result<(), Box<dyn Error>> {
-    if field == "s:license" {
+    //schema.org fields like `s:license` need the namespace to be declared
+    if field.starts_with("s:") {
         annotate(cwl_name, "$namespaces", Some("s"), Some(SCHEMAORG_NAMESPACE))?;
         annotate(cwl_name, "$schemas", None, Some(SCHEMAORG_SCHEMA))?;
     }
@@ -472,6 +581,90 @@ pub fn annotate_field(cwl_name: &str, field: &str, value: &str) -> Result<(), Bo
     write_updated_yaml(cwl_name, &yaml)
 }
 
+/// Annotates an input with its unit as `s:unitCode` and `s:unitText` and its allowed range as `s:minValue` and
+/// `s:maxValue`. Units not given as UO term are searched using the terminology service.
+pub async fn annotate_input(args: &AnnotateInputArgs) -> Result<(), Box<dyn Error>> {
+    if args.unit.is_none() && args.min.is_none() && args.max.is_none() {
+        return Err("❌ Please provide a unit, a minimum or a maximum to annotate".into());
+    }
+    if let (Some(min), Some(max)) = (args.min, args.max) {
+        if min > max {
+            return Err(format!("❌ Minimum {min} is larger than maximum {max}").into());
+        }
+    }
+    let (unit_code, unit_text) = match &args.unit {
+        Some(unit) => resolve_unit(unit).await?,
+        None => (None, None),
+    };
+
+    annotate(&args.cwl_name, "$namespaces", Some("s"), Some(SCHEMAORG_NAMESPACE))?;
+    annotate(&args.cwl_name, "$schemas", None, Some(SCHEMAORG_SCHEMA))?;
+    let mut yaml = parse_cwl(&args.cwl_name)?;
+    let input = find_input(&mut yaml, &args.input).ok_or(format!("❌ Input {} does not exist in {}", args.input, args.cwl_name))?;
+    //inputs written in short form like `threshold: float` need to become a mapping
+    if !input.is_mapping() {
+        let mut mapping = Mapping::new();
+        mapping.insert(Value::from("type"), input.clone());
+        *input = Value::Mapping(mapping);
+    }
+    let input = input.as_mapping_mut().expect("input is a mapping");
+    let fields = [
+        ("s:unitCode", unit_code.map(Value::from)),
+        ("s:unitText", unit_text.map(Value::from)),
+        ("s:minValue", args.min.map(to_number)),
+        ("s:maxValue", args.max.map(to_number)),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            input.insert(Value::from(key), value);
+        }
+    }
+    let quantity = Quantity::from_input(&Value::Mapping(input.clone())).unwrap_or_default();
+    write_updated_yaml(&args.cwl_name, &yaml)?;
+    info!("📏 Annotated input {} of {} with {}", args.input, args.cwl_name, quantity);
+    Ok(())
+}
+
+/// Finds the IRI and label of a unit. Units given as UO term or IRI are used as they are, names are searched in the UO
+/// ontology and kept as text if no term is selected.
+async fn resolve_unit(unit: &str) -> Result<(Option<String>, Option<String>), Box<dyn Error>> {
+    if let Some(iri) = to_unit_iri(unit) {
+        return Ok((Some(iri), None));
+    }
+    let terms = search_terms(unit, || fetch_terms(unit, MAX_RECOMMENDATIONS)).await?;
+    let terms = terms.into_iter().filter(|(_, _, iri)| iri.starts_with(UO_PREFIX)).collect::<Vec<_>>();
+    if let Some((label, _, iri)) = terms.iter().find(|(label, _, _)| label.eq_ignore_ascii_case(unit)) {
+        return Ok((Some(iri.clone()), Some(label.clone())));
+    }
+    if terms.is_empty() {
+        warn!("No UO term found for '{}', annotating it as text", unit);
+        return Ok((None, Some(unit.to_string())));
+    }
+    match select_annotation(&terms.into_iter().collect(), unit.to_string())? {
+        (label, _, iri) if iri != "N/A" => Ok((Some(iri), Some(label))),
+        (label, _, _) => Ok((None, Some(label))),
+    }
+}
+
+fn find_input<'a>(yaml: &'a mut Value, id: &str) -> Option<&'a mut Value> {
+    match yaml.get_mut("inputs")? {
+        Value::Sequence(inputs) => inputs
+            .iter_mut()
+            .find(|input| input.get("id").and_then(Value::as_str).is_some_and(|i| i.trim_start_matches('#') == id)),
+        Value::Mapping(inputs) => inputs.get_mut(id),
+        _ => None,
+    }
+}
+
+/// Whole numbers are written without fraction
+fn to_number(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
+    }
+}
+
 pub fn parse_cwl(name: &str) -> Result<Value, Box<dyn std::error::Error>> {
     let path = Path::new(name);
 
@@ -630,6 +823,152 @@ pub async fn annotate_process_step(args: &AnnotateProcessArgs) -> Result<(), Box
     write_updated_yaml(&args.cwl_name, &yaml)
 }
 
+pub fn annotate_from_file(args: &AnnotateFromFileArgs) -> Result<(), Box<dyn Error>> {
+    let metadata = load_project_metadata(&args.file)?;
+    let files = WalkDir::new("workflows")
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "cwl"))
+        .map(|e| e.path().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err("❌ No CWL files found in workflows folder".into());
+    }
+
+    for file in &files {
+        let before = fs::read_to_string(file)?;
+        apply_project_metadata(file, &metadata).map_err(|e| format!("❌ Could not annotate {}: {}", file, e))?;
+        let after = fs::read_to_string(file)?;
+        if before == after {
+            info!("✔️  {} is already annotated", file);
+        } else {
+            print_diff(file, &before, &after);
+        }
+    }
+    info!("🏷️  Annotated {} CWL files from {}", files.len(), args.file);
+    Ok(())
+}
+
+/// Loads project metadata from a YAML file or an ISA investigation as CSV or Excel file, depending on the file extension
+pub fn load_project_metadata(path: &str) -> Result<ProjectMetadata, Box<dyn Error>> {
+    if !Path::new(path).exists() {
+        return Err(format!("❌ Metadata file {} does not exist", path).into());
+    }
+    let lowercase = path.to_lowercase();
+    if lowercase.ends_with(".csv") || lowercase.ends_with(".xlsx") {
+        Ok(metadata_from_isa(&read_isa_rows(path)?))
+    } else {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&contents).map_err(|e| format!("❌ Could not read metadata file {}: {}", path, e))?)
+    }
+}
+
+/// Rows of an ISA investigation keyed by their lowercase field name without section prefix, e.g. `person last name`
+/// for `Investigation Person Last Name`
+pub type IsaRows = HashMap<String, Vec<String>>;
+
+/// Reads an ISA investigation from an Excel file or an ISA-style CSV file, where every row starts with a field name
+/// followed by its values. If a field occurs multiple times, e.g. for studies, the first occurrence is used.
+pub fn read_isa_rows(path: &str) -> Result<IsaRows, Box<dyn Error>> {
+    let records: Vec<Vec<String>> = if path.to_lowercase().ends_with(".xlsx") {
+        let mut workbook = open_workbook_auto(path)?;
+        let sheet = workbook.worksheet_range_at(0).ok_or(format!("❌ {} does not contain any sheets", path))??;
+        sheet.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+    } else {
+        let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_path(path)?;
+        reader
+            .records()
+            .map(|record| record.map(|r| r.iter().map(String::from).collect()))
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut rows = IsaRows::new();
+    for record in records {
+        let mut fields = record.iter().map(|f| f.trim());
+        let Some(key) = fields.next().filter(|k| !k.is_empty()) else {
+            continue;
+        };
+        let key = key.trim_start_matches("Investigation ").trim_start_matches("Study ").to_lowercase();
+        rows.entry(key).or_insert_with(|| fields.map(String::from).collect());
+    }
+    Ok(rows)
+}
+
+/// Contacts of an ISA investigation are annotated as authors and performers
+pub fn metadata_from_isa(rows: &IsaRows) -> ProjectMetadata {
+    let value = |key: &str, i: usize| rows.get(key).and_then(|values| values.get(i)).filter(|v| !v.is_empty()).cloned();
+
+    let mut metadata = ProjectMetadata {
+        license: value("license", 0),
+        ..Default::default()
+    };
+    for i in 0..rows.get("person last name").map_or(0, Vec::len) {
+        let (Some(first_name), Some(last_name)) = (value("person first name", i), value("person last name", i)) else {
+            continue;
+        };
+        metadata.authors.push(MetadataPerson {
+            name: format!("{first_name} {last_name}"),
+            mail: value("person email", i),
+            id: value("comment[orcid]", i),
+        });
+        metadata.performers.push(MetadataPerformer {
+            first_name,
+            last_name,
+            mail: value("person email", i),
+            affiliation: value("person affiliation", i),
+            role: value("person roles", i),
+            role_accession: value("person roles term accession number", i),
+        });
+    }
+    metadata
+}
+
+/// Applies all annotations of the project metadata to a CWL file. Roles are annotated as given, no terminology
+/// service is queried.
+pub fn apply_project_metadata(cwl_name: &str, metadata: &ProjectMetadata) -> Result<(), Box<dyn Error>> {
+    if let Some(license) = &metadata.license {
+        annotate_field(cwl_name, "s:license", license)?;
+    }
+    for (persons, role) in [(&metadata.authors, "author"), (&metadata.contributors, "contributor")] {
+        for person in persons {
+            let args = PersonArgs {
+                cwl_name: cwl_name.to_string(),
+                name: person.name.clone(),
+                mail: person.mail.clone(),
+                id: person.id.clone(),
+            };
+            annotate_person(&args, role)?;
+        }
+    }
+    for performer in &metadata.performers {
+        let args = PerformerArgs {
+            cwl_name: cwl_name.to_string(),
+            first_name: performer.first_name.clone(),
+            last_name: performer.last_name.clone(),
+            mail: performer.mail.clone(),
+            affiliation: performer.affiliation.clone(),
+            role: performer.role.clone(),
+        };
+        let role = performer.role.as_ref().map(|role| {
+            let mut role_mapping = Mapping::new();
+            role_mapping.insert(Value::String("class".to_string()), Value::String("arc:role".to_string()));
+            if let Some(accession) = &performer.role_accession {
+                role_mapping.insert(Value::String("arc:term accession".to_string()), Value::String(accession.clone()));
+            }
+            role_mapping.insert(Value::String("arc:annotation value".to_string()), Value::String(role.clone()));
+            role_mapping
+        });
+        add_performer(&args, role)?;
+    }
+    if let Some(container) = &metadata.container {
+        annotate_container(cwl_name, container)?;
+    }
+    for (field, value) in &metadata.custom {
+        annotate_field(cwl_name, field, value)?;
+    }
+    Ok(())
+}
+
 pub async fn process_annotation_with_mapping(value: &str, mut parameter_name: Mapping, complete: bool) -> Result<Mapping, Box<dyn Error>> {
     match ts_recommendations(value, MAX_RECOMMENDATIONS).await {
         Ok((annotation_value, source_ref, term_accession)) => {
@@ -694,6 +1033,16 @@ pub fn select_annotation(recommendations: &HashSet<(String, String, String)>, te
 }
 
 pub async fn ts_recommendations(search_term: &str, max_recommendations: usize) -> Result<(String, String, String), Box<dyn Error>> {
+    let terms = search_terms(search_term, || fetch_terms(search_term, max_recommendations)).await?;
+    if terms.is_empty() {
+        println!("No valid annotations found.");
+    }
+    let recommendations: HashSet<(String, String, String)> = terms.into_iter().collect();
+    select_annotation(&recommendations, search_term.to_string())
+}
+
+/// Queries the terminology service for terms matching the search term
+async fn fetch_terms(search_term: &str, max_recommendations: usize) -> Result<Vec<Term>, Box<dyn Error>> {
     let client = reqwest::Client::new();
     let query = format!("{}{}", REST_URL_TS, search_term);
     // GET request
@@ -701,20 +1050,18 @@ pub async fn ts_recommendations(search_term: &str, max_recommendations: usize) -
 
     let ts_json: serde_json::Value = response.json().await?;
 
-    let mut recommendations: HashSet<(String, String, String)> = HashSet::new();
+    let mut recommendations: Vec<Term> = vec![];
     // Iterate over annotations
     if let Some(results) = ts_json.as_array() {
         for result in results {
             let id = result["iri"].as_str().unwrap_or("").trim_matches('"').to_string();
             let label = result["label"].as_str().unwrap_or("").trim_matches('"').to_string();
             let ontology = result["ontology"].as_str().unwrap_or("").trim_matches('"').to_string();
-            if recommendations.len() < max_recommendations {
-                recommendations.insert((label, ontology, id));
+            let term = (label, ontology, id);
+            if recommendations.len() < max_recommendations && !recommendations.contains(&term) {
+                recommendations.push(term);
             }
         }
-    } else {
-        println!("No valid annotations found.");
     }
-
-    select_annotation(&recommendations, search_term.to_string())
+    Ok(recommendations)
 }
@@ -0,0 +1,134 @@
+use crate::{
+    bundle::{create_bundle, get_bundled_environments, import_bundle, BUNDLE_DIR},
+    environments::{is_micromamba_available, restore_environment},
+    repo::get_modified_files,
+    secrets::{check_secrets, SecretScanner},
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use git2::Repository;
+use log::{info, warn};
+use std::{env, error::Error, path::PathBuf};
+
+const BUNDLE_EXTENSION: &str = ".tar.zst";
+
+pub fn handle_export_commands(subcommand: &ExportCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        ExportCommands::Bundle(args) => export_bundle(args),
+    }
+}
+
+pub fn handle_import_commands(subcommand: &ImportCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        ImportCommands::Bundle(args) => import(args),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommands {
+    #[command(
+        about = "Exports the project as reproducibility bundle containing the repository, packed workflows, pinned images, the dataset manifest and environment lockfiles"
+    )]
+    Bundle(ExportBundleArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommands {
+    #[command(about = "Restores a project from a reproducibility bundle")]
+    Bundle(ImportBundleArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ExportBundleArgs {
+    #[arg(short = 'o', long = "output", help = "Path of the created bundle [default: <PROJECT>.tar.zst]")]
+    pub output: Option<PathBuf>,
+    #[arg(long = "allow-secrets", help = "Exports the project even if it contains possible passwords, tokens or credentials")]
+    pub allow_secrets: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportBundleArgs {
+    #[arg(help = "Path of the bundle")]
+    pub bundle: PathBuf,
+    #[arg(help = "Directory to restore the project into [default: name of the bundle]")]
+    pub directory: Option<PathBuf>,
+    #[arg(long = "no-environments", help = "Do not recreate the environments from the bundled lockfiles")]
+    pub no_environments: bool,
+}
+
+pub fn export_bundle(args: &ExportBundleArgs) -> Result<(), Box<dyn Error>> {
+    let cwd = env::current_dir()?;
+    let repo = Repository::open(&cwd)?;
+    if get_modified_files(&repo).iter().any(|file| !file.ends_with(BUNDLE_EXTENSION)) {
+        return Err("❌ Project has uncommitted changes, please commit them before exporting".into());
+    }
+    //the bundle contains the repository, so secrets can not be redacted
+    let files = repo
+        .index()?
+        .iter()
+        .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+        .collect::<Vec<_>>();
+    check_secrets("Project", &SecretScanner::load(&cwd)?.scan_files(&cwd, &files), args.allow_secrets, false)?;
+
+    let name = cwd.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let output = args.output.clone().unwrap_or_else(|| PathBuf::from(format!("{name}{BUNDLE_EXTENSION}")));
+
+    let metadata = create_bundle(&cwd, &output)?;
+    for image in metadata.images.iter().filter(|i| i.digest.is_none()) {
+        warn!(
+            "Could not determine the digest of image {} used by {}, it is bundled unpinned",
+            image.image, image.tool
+        );
+    }
+    info!(
+        "📦 Exported commit {} with {} workflows, {} images, {} datasets and {} environments to {}",
+        &metadata.commit[..7],
+        metadata.workflows.len(),
+        metadata.images.len(),
+        metadata.datasets.len(),
+        metadata.environments.len(),
+        output.to_string_lossy().green().bold()
+    );
+    Ok(())
+}
+
+pub fn import(args: &ImportBundleArgs) -> Result<(), Box<dyn Error>> {
+    let directory = args.directory.clone().unwrap_or_else(|| {
+        let name = args.bundle.file_name().unwrap_or_default().to_string_lossy();
+        PathBuf::from(name.strip_suffix(BUNDLE_EXTENSION).unwrap_or(&name))
+    });
+    let metadata = import_bundle(&args.bundle, &directory)?;
+    info!(
+        "📂 Restored {} at commit {} into {}",
+        metadata.name,
+        &metadata.commit[..7],
+        directory.to_string_lossy().green().bold()
+    );
+
+    if !metadata.images.is_empty() {
+        info!("🐳 Tools use the following images:");
+        for image in &metadata.images {
+            info!("\t- {}: {}", image.tool, image.reference());
+        }
+    }
+    if !args.no_environments {
+        for lockfile in get_bundled_environments(&directory, &metadata) {
+            let conda = lockfile.extension().is_some_and(|e| e == "lock");
+            if conda && !is_micromamba_available() {
+                warn!(
+                    "micromamba is not available, environment {:?} is not restored!",
+                    lockfile.file_stem().unwrap_or_default()
+                );
+                continue;
+            }
+            if let Err(e) = restore_environment(&directory, &lockfile) {
+                warn!("Could not restore environment {:?}: {e}", lockfile.file_stem().unwrap_or_default());
+            }
+        }
+    }
+    info!(
+        "📄 Packed workflows and lockfiles are kept in {}",
+        directory.join(BUNDLE_DIR).to_string_lossy()
+    );
+    Ok(())
+}
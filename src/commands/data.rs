@@ -0,0 +1,93 @@
+use crate::{
+    datasets::{check_dataset_name, create_dataset, get_dataset_name, load_manifest, save_manifest, DATASET_MANIFEST, DATASET_PREFIX},
+    repo::{commit, stage_file},
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use git2::Repository;
+use log::{info, warn};
+use prettytable::{row, Table};
+use std::{env, error::Error};
+
+pub fn handle_data_commands(subcommand: &DataCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        DataCommands::Add(args) => add_dataset(args),
+        DataCommands::List => list_datasets(),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DataCommands {
+    #[command(about = "Registers a local or remote dataset in the project")]
+    Add(AddDataArgs),
+    #[command(about = "Lists all registered datasets", visible_alias = "ls")]
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct AddDataArgs {
+    #[arg(help = "Path or URL of the dataset")]
+    pub location: String,
+    #[arg(short = 'n', long = "name", help = "A name to reference the dataset by, defaults to the filename")]
+    pub name: Option<String>,
+    #[arg(short = 'd', long = "doi", help = "DOI of the dataset")]
+    pub doi: Option<String>,
+    #[arg(short = 'l', long = "license", help = "License of the dataset")]
+    pub license: Option<String>,
+    #[arg(short = 'c', long = "checksum", help = "Expected checksum, e.g. sha1$...")]
+    pub checksum: Option<String>,
+    #[arg(short = 'f', long = "force", help = "Overwrites an existing dataset with the same name")]
+    pub force: bool,
+    #[arg(long = "no-commit", help = "Do not commit the updated manifest")]
+    pub no_commit: bool,
+}
+
+pub fn add_dataset(args: &AddDataArgs) -> Result<(), Box<dyn Error>> {
+    let cwd = env::current_dir()?;
+    let mut manifest = load_manifest(&cwd)?;
+
+    let name = args.name.clone().unwrap_or_else(|| get_dataset_name(&args.location));
+    check_dataset_name(&name)?;
+    if manifest.contains_key(&name) && !args.force {
+        return Err(format!("Dataset `{name}` already exists. Use --force to overwrite it.").into());
+    }
+
+    let dataset = create_dataset(&args.location, args.checksum.clone(), args.license.clone(), args.doi.clone())?;
+    if dataset.is_remote() && dataset.checksum.is_none() {
+        warn!("No checksum given for remote dataset `{name}`, integrity can not be verified!");
+    }
+    manifest.insert(name.clone(), dataset);
+    save_manifest(&cwd, &manifest)?;
+
+    info!("📦 Registered dataset {}, reference it using `location: {DATASET_PREFIX}{name}`", name.green().bold());
+
+    if !args.no_commit {
+        if let Ok(repo) = Repository::open(&cwd) {
+            stage_file(&repo, DATASET_MANIFEST)?;
+            commit(&repo, &format!("Registered dataset `{name}`"))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn list_datasets() -> Result<(), Box<dyn Error>> {
+    let manifest = load_manifest(env::current_dir()?)?;
+    if manifest.is_empty() {
+        info!("No datasets registered, yet. Use `s4n data add` to register one.");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_titles(row![bFg => "Name", "Location", "Checksum", "License", "DOI"]);
+    for (name, dataset) in &manifest {
+        table.add_row(row![
+            b -> name,
+            dataset.location,
+            dataset.checksum.as_deref().unwrap_or("-"),
+            dataset.license.as_deref().unwrap_or("-"),
+            dataset.doi.as_deref().unwrap_or("-")
+        ]);
+    }
+    table.printstd();
+    Ok(())
+}
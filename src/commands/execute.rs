@@ -1,182 +1,749 @@
 use crate::{
+    datasets::resolve_dataset_references,
     execution::{
-        runner::{run_commandlinetool, run_workflow},
+        backend::{wait_for_run, ExecutionBackend, LocalBackend, WesBackend},
+        cache::get_incremental_cache_dir,
+        events::{log_event, set_event_step, start_event_log, stop_event_log},
+        notify::{notify, RunNotification},
+        provenance::{abort_provenance, finish_provenance, start_provenance},
+        resources::{format_bytes, format_duration, format_usage_table, take_usages},
+        runner::{
+            run_commandlinetool, run_workflow, set_cache_dir, set_disk_quota, set_dry_run, set_job_retry_policy, set_parallel, set_retries,
+            set_sandbox, set_simulate,
+        },
+        runs::{get_run_key, load_runs, set_run_revision, RunRevision},
+        staging::{set_staging_strategy, StagingStrategy},
         util::preprocess_cwl,
     },
     io::join_path_string,
+    job::load_document,
+    log::{set_log_format, LogFormat},
     parser::guess_type,
+    random::{set_seed, temp_dir},
+    repo::{checkout_revision, get_repository_path},
+    units::check_job_ranges,
+    wes::{get_unsupported_requirements, RunState, WesClient, WesConfig},
 };
 use clap::{Args, Subcommand, ValueEnum};
 use cwl::{
     clt::CommandLineTool,
+    error::CWLError,
+    ids::normalize_ids,
     types::{CWLType, DefaultValue, Directory, File, PathItem},
+    parse_yaml_stream,
+    requirements::{deserialize_requirements, Requirement, RetryPolicy},
+    select_document, split_document_reference,
+    version::{upgrade_to_v12, CWL_VERSION},
     wf::Workflow,
 };
-use log::info;
+use dialoguer::Confirm;
+use git2::Repository;
+use log::{info, warn};
+use serde_json::json;
 use serde_yaml::Value;
 use std::{
     collections::HashMap,
+    env,
     error::Error,
     fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
     process::Command,
+    time::{Duration, Instant},
 };
+use tempfile::TempDir;
 
 pub fn handle_execute_commands(subcommand: &ExecuteCommands) -> Result<(), Box<dyn Error>> {
     match subcommand {
         ExecuteCommands::Local(args) => execute_local(args),
+        ExecuteCommands::Remote(args) => execute_remote(args),
     }
 }
 
 #[derive(Debug, Subcommand)]
 pub enum ExecuteCommands {
-    #[command(about = "Runs CWL files locally using a custom runner or cwltool", visible_alias = "l")]
+    #[command(about = "Runs CWL files locally using a custom runner, cwltool or toil", visible_alias = "l")]
     Local(LocalExecuteArgs),
+    #[command(about = "Runs CWL files on a GA4GH Workflow Execution Service (WES) server", visible_alias = "r")]
+    Remote(RemoteExecuteArgs),
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct LocalExecuteArgs {
-    #[arg(value_enum, default_value_t = Runner::Custom, short = 'r', long = "runner", help="Choose your cwl runner implementation")]
+    #[arg(
+        value_enum,
+        default_value_t = Runner::Custom,
+        short = 'r',
+        long = "runner",
+        visible_alias = "engine",
+        help = "Choose your cwl runner implementation, cwltool and toil need to be installed"
+    )]
     pub runner: Runner,
     #[arg(long = "outdir", help = "A path to output resulting files to")]
     pub out_dir: Option<String>,
     #[arg(long = "quiet", help = "Runner does not print to stdout")]
     pub is_quiet: bool,
+    #[arg(long = "simulate", help = "Runs without executing commands, creating empty placeholder outputs instead")]
+    pub simulate: bool,
+    #[arg(
+        long = "dry-run",
+        help = "Prints resolved commands, staged files, environment and expected outputs of every step without running anything"
+    )]
+    pub dry_run: bool,
+    #[arg(long = "sandbox", help = "Runs commands of tools without DockerRequirement in a bubblewrap sandbox (Linux only)")]
+    pub sandbox: bool,
+    #[arg(
+        long = "disk-quota",
+        value_name = "MiB",
+        requires = "sandbox",
+        help = "Kills sandboxed commands writing more than the given number of MiB to their working directory"
+    )]
+    pub disk_quota: Option<u64>,
+    #[arg(
+        long = "retries",
+        value_name = "N",
+        help = "Reruns failing workflow steps up to N times, steps declaring an s4n:RetryPolicy use their own"
+    )]
+    pub retries: Option<u32>,
+    #[arg(
+        long = "parallel",
+        value_name = "CORES",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Number of cores the jobs of scattered steps may use at the same time, defaults to all cores"
+    )]
+    pub parallel: Option<usize>,
+    #[arg(long = "provenance", help = "Records provenance and writes a CWLProv research object to the given directory")]
+    pub provenance: Option<PathBuf>,
+    #[arg(long = "cachedir", help = "Reuses results of previous runs with the same tool and inputs stored in the given directory")]
+    pub cache_dir: Option<PathBuf>,
+    #[arg(
+        long = "incremental",
+        help = "Only runs steps whose tool or inputs changed since the last run, unchanged results are reused from the project's cache"
+    )]
+    pub incremental: bool,
+    #[arg(
+        value_enum,
+        default_value_t = StagingStrategy::Copy,
+        long = "staging",
+        help = "How input files are placed into the working directory, links fall back to copies across file systems. Linked inputs must be treated as read only, tools that may modify their inputs always get copies"
+    )]
+    pub staging: StagingStrategy,
+    #[arg(
+        value_enum,
+        default_value_t = LogFormat::Text,
+        long = "log-format",
+        help = "Format of log messages, using json structured events of the run are written to run.log.jsonl in the output directory"
+    )]
+    pub log_format: LogFormat,
+    #[arg(
+        long = "seed",
+        help = "Makes run ids and generated names reproducible and passes the seed to tools using the S4N_SEED environment variable"
+    )]
+    pub seed: Option<u64>,
+    #[arg(
+        long = "notify-webhook",
+        value_name = "URL",
+        help = "Posts the status and duration of the run as JSON to the given URL when it finishes"
+    )]
+    pub notify_webhook: Option<String>,
+    #[arg(
+        long = "notify-command",
+        value_name = "COMMAND",
+        help = "Runs a shell command when the run finishes, the status and duration are passed as S4N_RUN_STATUS and S4N_RUN_DURATION"
+    )]
+    pub notify_command: Option<String>,
     #[arg(help = "CWL File to execute")]
     pub file: PathBuf,
     #[arg(trailing_var_arg = true, help = "Other arguments provided to cwl file", allow_hyphen_values = true)]
     pub args: Vec<String>,
 }
 
-#[derive(ValueEnum, Debug, Clone)]
+#[derive(Args, Debug, Default)]
+pub struct RemoteExecuteArgs {
+    #[arg(long = "server", visible_alias = "endpoint", help = "URL of the WES API, e.g. https://wes.example.org/ga4gh/wes/v1")]
+    pub server: String,
+    #[arg(
+        long = "token",
+        help = "Bearer token used for authentication, read from the WES_TOKEN environment variable or the project's .s4n/wes.yml if not given"
+    )]
+    pub token: Option<String>,
+    #[arg(long = "outdir", help = "A path to download resulting files to")]
+    pub out_dir: Option<String>,
+    #[arg(long = "interval", default_value_t = 10, help = "Seconds to wait between status checks of the run")]
+    pub interval: u64,
+    #[arg(long = "timeout", value_name = "SECONDS", help = "Cancels the run if it did not finish within the given number of seconds")]
+    pub timeout: Option<u64>,
+    #[arg(short = 'y', long = "yes", help = "Submits the run without asking for confirmation")]
+    pub yes: bool,
+    #[arg(help = "CWL File to execute")]
+    pub file: PathBuf,
+    #[arg(help = "YAML or JSON file containing the inputs")]
+    pub input_file: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Default)]
 pub enum Runner {
     #[clap(name = "cwltool")]
     CWLTool,
+    #[clap(name = "toil")]
+    Toil,
+    #[default]
     Custom,
 }
 
+impl Runner {
+    /// Executable of external engines, `None` for the custom runner
+    pub fn executable(&self) -> Option<&'static str> {
+        match self {
+            Runner::CWLTool => Some("cwltool"),
+            Runner::Toil => Some("toil-cwl-runner"),
+            Runner::Custom => None,
+        }
+    }
+}
+
 pub fn execute_local(args: &LocalExecuteArgs) -> Result<(), Box<dyn Error>> {
-    match args.runner {
-        Runner::CWLTool => {
-            if !args.is_quiet {
-                eprintln!("💻 Executing {:?} using cwltool.", &args.file);
-            }
-            let mut cmd = Command::new("cwltool");
+    let clock = Instant::now();
+    let result = run_local(args);
+    if args.notify_webhook.is_some() || args.notify_command.is_some() {
+        let notification = RunNotification::new(&args.file, &result, clock.elapsed());
+        notify(&notification, args.notify_webhook.as_deref(), args.notify_command.as_deref());
+    }
+    result
+}
 
-            //handle args
-            if args.is_quiet {
-                cmd.arg("--quiet");
-            }
-            if let Some(outdir) = &args.out_dir {
-                cmd.arg("--outdir").arg(outdir);
-            }
+fn run_local(args: &LocalExecuteArgs) -> Result<(), Box<dyn Error>> {
+    set_log_format(args.log_format);
+    //incremental runs use a cache inside of the project unless another one is given
+    let cache_dir = match (&args.cache_dir, args.incremental) {
+        (Some(cache_dir), _) => Some(cache_dir.clone()),
+        (None, true) => Some(get_incremental_cache_dir(env::current_dir()?)?),
+        (None, false) => None,
+    };
+    if args.incremental && !args.is_quiet {
+        info!("♻️  Running incrementally, results of unchanged steps are reused from {:?}", cache_dir.as_ref().unwrap());
+    }
+    //the checkout of a previous revision is kept until the run is finished
+    let checkout = checkout_file_revision(&args.file)?;
+    let cwl_file = checkout.as_ref().map(|checkout| &checkout.file).unwrap_or(&args.file);
 
-            cmd.arg(&args.file).args(&args.args);
-            let output = &cmd.output()?;
-            if !output.stdout.is_empty() {
-                println!("{}", String::from_utf8_lossy(&output.stdout));
-            }
-            if !output.stderr.is_empty() {
-                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+    //both local backends finish runs while they are scheduled
+    let mut backend: Box<dyn ExecutionBackend + '_> = match args.runner {
+        Runner::CWLTool | Runner::Toil => Box::new(LocalBackend::new(|file: &Path, job_args: &[String]| {
+            execute_external(args, file, job_args, cache_dir.as_deref())
+        })),
+        Runner::Custom => Box::new(LocalBackend::new(|file: &Path, job_args: &[String]| {
+            run_custom(args, file, job_args, checkout.as_ref().map(|checkout| &checkout.revision), cache_dir.as_deref())
+        })),
+    };
+    backend.schedule_run(cwl_file, &args.args).map(|_| ())
+}
+
+/// Runs a CWL document using the custom runner and returns its output object
+fn run_custom(
+    args: &LocalExecuteArgs,
+    cwl_file: &Path,
+    job_args: &[String],
+    revision: Option<&RunRevision>,
+    cache_dir: Option<&Path>,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    if !args.is_quiet {
+        info!(
+            "💻 Executing {:?} using SciWIn's custom runner. Use `--runner cwltool` to use reference runner (if installed). 
+⚠️  The internal runner currently is for testing purposes only and does not support containerization, yet!",
+            cwl_file
+        );
+    }
+
+    //gather inputs, a document of a YAML stream can be addressed using `file.cwl#<index|id>`
+    let (file, selector) = split_document_reference(cwl_file);
+    let contents = fs::read_to_string(&file).map_err(|e| format!("Could not load File {:?}: {}", file, e))?;
+    let mut inputs: Option<HashMap<String, DefaultValue>> = None;
+    let is_file_input = job_args.len() == 1 && !&job_args[0].starts_with("-");
+
+    //check for yaml input
+    match job_args.len() {
+        // is input.yml file
+        1 => {
+            let input = &job_args[0];
+            if is_file_input {
+                let yaml = fs::read_to_string(input).map_err(|e| format!("Could not load File {}: {}", input, e))?;
+                inputs = Some(serde_yaml::from_str(&yaml).map_err(|e| format!("Could not read input file: {}", e))?);
             }
-            Ok(())
         }
-        Runner::Custom => {
-            if !args.is_quiet {
-                info!(
-                    "💻 Executing {:?} using SciWIn's custom runner. Use `--runner cwltool` to use reference runner (if installed). 
-⚠️  The internal runner currently is for testing purposes only and does not support containerization, yet!",
-                    &args.file
-                );
+        //arguments given as commandline inputs
+        n if n > 1 => {
+            inputs = Some(HashMap::new());
+            let map = inputs.as_mut().unwrap();
+            let mut i = 0;
+            while i < job_args.len() {
+                if job_args[i].starts_with("-") {
+                    let key = job_args[i].trim_start_matches("--").to_string();
+                    let raw_value = &job_args[i + 1];
+                    let value = match guess_type(raw_value) {
+                        CWLType::File => DefaultValue::File(File::from_location(raw_value)),
+                        CWLType::Directory => DefaultValue::Directory(Directory::from_location(raw_value)),
+                        _ => serde_yaml::from_str(&job_args[i + 1])?,
+                    };
+                    map.insert(key, value);
+                    i += 1;
+                }
+                i += 1;
             }
+        }
+        //ignore and use without args
+        _ => {}
+    }
 
-            //gather inputs
-            let contents = fs::read_to_string(&args.file).map_err(|e| format!("Could not load File {:?}: {}", args.file, e))?;
-            let mut inputs: Option<HashMap<String, DefaultValue>> = None;
-            let is_file_input = args.args.len() == 1 && !&args.args[0].starts_with("-");
-
-            //check for yaml input
-            match args.args.len() {
-                // is input.yml file
-                1 => {
-                    let input = &args.args[0];
-                    if is_file_input {
-                        let yaml = fs::read_to_string(input).map_err(|e| format!("Could not load File {}: {}", input, e))?;
-                        inputs = Some(serde_yaml::from_str(&yaml).map_err(|e| format!("Could not read input file: {}", e))?);
+    fn correct_path<T: PathItem>(item: &mut T, path_prefix: &Path) {
+        let location = item.location().clone();
+        item.set_location(join_path_string(path_prefix, &location));
+        if let Some(secondary_files) = item.secondary_files_mut() {
+            for sec_file in secondary_files {
+                match sec_file {
+                    DefaultValue::File(file) => {
+                        file.set_location(join_path_string(path_prefix, &file.location));
                     }
+                    DefaultValue::Directory(directory) => directory.set_location(join_path_string(path_prefix, &directory.location)),
+                    DefaultValue::Any(_) => (),
                 }
-                //arguments given as commandline inputs
-                n if n > 1 => {
-                    inputs = Some(HashMap::new());
-                    let map = inputs.as_mut().unwrap();
-                    let mut i = 0;
-                    while i < args.args.len() {
-                        if args.args[i].starts_with("-") {
-                            let key = args.args[i].trim_start_matches("--").to_string();
-                            let raw_value = &args.args[i + 1];
-                            let value = match guess_type(raw_value) {
-                                CWLType::File => DefaultValue::File(File::from_location(raw_value)),
-                                CWLType::Directory => DefaultValue::Directory(Directory::from_location(raw_value)),
-                                _ => serde_yaml::from_str(&args.args[i + 1])?,
-                            };
-                            map.insert(key, value);
-                            i += 1;
-                        }
-                        i += 1;
-                    }
-                }
-                //ignore and use without args
-                _ => {}
             }
+        }
+    }
+
+    let job_retry_policy = match &mut inputs {
+        Some(inputs) => take_job_retry_policy(inputs)?,
+        None => None,
+    };
 
-            fn correct_path<T: PathItem>(item: &mut T, path_prefix: &Path) {
-                let location = item.location().clone();
-                item.set_location(join_path_string(path_prefix, &location));
-                if let Some(secondary_files) = item.secondary_files_mut() {
-                    for sec_file in secondary_files {
-                        match sec_file {
-                            DefaultValue::File(file) => {
-                                file.set_location(join_path_string(path_prefix, &file.location));
-                            }
-                            DefaultValue::Directory(directory) => directory.set_location(join_path_string(path_prefix, &directory.location)),
-                            DefaultValue::Any(_) => (),
+    //make paths relative to calling object
+    if let Some(inputs) = &mut inputs {
+        resolve_dataset_references(inputs, env::current_dir()?)?;
+        let path_prefix = if is_file_input {
+            Path::new(&job_args[0]).parent().unwrap()
+        } else {
+            Path::new(".")
+        };
+        for value in inputs.values_mut() {
+            match value {
+                DefaultValue::File(file) => correct_path(file, path_prefix),
+                DefaultValue::Directory(directory) => correct_path(directory, path_prefix),
+                DefaultValue::Any(Value::Sequence(items)) => {
+                    //arrays of files or directories
+                    for item in items {
+                        let is_path_item = matches!(item.get("class").and_then(|c| c.as_str()), Some("File") | Some("Directory"));
+                        if let (true, Some(Value::String(location))) = (is_path_item, item.get_mut("location")) {
+                            *location = join_path_string(path_prefix, location);
                         }
                     }
                 }
+                DefaultValue::Any(_) => (),
             }
+        }
+    }
 
-            //make paths relative to calling object
-            if let Some(inputs) = &mut inputs {
-                let path_prefix = if is_file_input {
-                    Path::new(&args.args[0]).parent().unwrap()
-                } else {
-                    Path::new(".")
-                };
-                for value in inputs.values_mut() {
-                    match value {
-                        DefaultValue::File(file) => correct_path(file, path_prefix),
-                        DefaultValue::Directory(directory) => correct_path(directory, path_prefix),
-                        DefaultValue::Any(_) => (),
-                    }
-                }
+    //preprocess cwl import statements
+    let preprocessed_contents = preprocess_cwl(&contents, &file);
+
+    let documents = parse_yaml_stream(&preprocessed_contents).map_err(|e| e.with_path(&file))?;
+    let mut cwl_yaml = select_document(documents, selector.as_deref())?;
+    upgrade_to_v12(&mut cwl_yaml)?;
+    normalize_ids(&mut cwl_yaml);
+    let is_workflow = match cwl_yaml.get("class").and_then(|c| c.as_str()) {
+        Some("Workflow") => true,
+        Some("CommandLineTool") => false,
+        class => {
+            return Err(CWLError::UnknownClass {
+                found: class.unwrap_or_default().to_string(),
+                expected: "CommandLineTool or Workflow".to_string(),
             }
+            .into())
+        }
+    };
 
-            //preprocess cwl import statements
-            let preprocessed_contents = preprocess_cwl(&contents, &args.file);
+    let job_values = inputs.iter().flatten().map(|(id, value)| (id.clone(), value.to_value())).collect();
+    check_job_ranges(&cwl_yaml, file.parent().unwrap_or(Path::new(".")), &job_values)?;
+
+    if args.dry_run {
+        info!("📋 Dry run, commands will not be run and nothing is written to the output directory!");
+    } else if args.simulate {
+        info!("🧪 Simulating execution, commands will not be run and outputs are empty placeholders!");
+    }
+    //placeholder outputs of a dry run are written to a directory that is thrown away afterwards
+    let dry_run_dir = if args.dry_run { Some(tempfile::tempdir()?) } else { None };
+    let out_dir = match &dry_run_dir {
+        Some(dir) => Some(dir.path().to_string_lossy().into_owned()),
+        None => args.out_dir.clone(),
+    };
+    let simulate = args.simulate || args.dry_run;
+
+    //keep inputs for the provenance record, they are consumed by the runner
+    let job = inputs.clone().unwrap_or_default();
+    set_seed(args.seed);
+    set_run_revision(revision.cloned());
+    if let Some(bundle) = &args.provenance {
+        start_provenance(bundle)?;
+    }
+    let clock = Instant::now();
+    if args.log_format == LogFormat::Json {
+        start_event_log(&env::current_dir()?.join(args.out_dir.as_deref().unwrap_or(".")))?;
+        log_event("run_started", json!({ "file": file, "inputs": job }));
+    }
+    set_cache_dir(cache_dir.map(|dir| env::current_dir().unwrap_or_default().join(dir)));
+    set_staging_strategy(args.staging);
 
-            let cwl_yaml: Value = serde_yaml::from_str(&preprocessed_contents).map_err(|e| format!("Could not load YAML: {}", e))?;
-            let class = cwl_yaml.get("class").expect("Could not get class");
-            let is_workflow = class == "Workflow";
+    let result = if !is_workflow {
+        let mut tool: CommandLineTool = serde_yaml::from_value(cwl_yaml).map_err(|e| CWLError::from(e).with_path(&file))?;
+        set_simulate(simulate);
+        set_dry_run(args.dry_run);
+        set_sandbox(args.sandbox);
+        set_disk_quota(args.disk_quota.map(|quota| quota * 1024 * 1024));
+        run_commandlinetool(&mut tool, inputs, Some(&file), out_dir)
+    } else {
+        let mut workflow: Workflow = serde_yaml::from_value(cwl_yaml).map_err(|e| CWLError::from(e).with_path(&file))?;
+        set_simulate(simulate);
+        set_dry_run(args.dry_run);
+        set_sandbox(args.sandbox);
+        set_disk_quota(args.disk_quota.map(|quota| quota * 1024 * 1024));
+        set_retries(args.retries);
+        set_job_retry_policy(job_retry_policy);
+        set_parallel(args.parallel);
+        run_workflow(&mut workflow, inputs, Some(&file), out_dir)
+    };
+    set_simulate(false);
+    set_dry_run(false);
+    set_sandbox(false);
+    set_disk_quota(None);
+    set_retries(None);
+    set_job_retry_policy(None);
+    set_parallel(None);
+    set_cache_dir(None);
+    set_staging_strategy(StagingStrategy::Copy);
 
-            if !is_workflow {
-                let mut tool: CommandLineTool = serde_yaml::from_value(cwl_yaml).map_err(|e| format!("Could not load CommandLineTool: {}", e))?;
-                run_commandlinetool(&mut tool, inputs, Some(&args.file), args.out_dir.clone())?;
-            } else {
-                let mut workflow: Workflow = serde_yaml::from_value(cwl_yaml).map_err(|e| format!("Could not load Workflow: {}", e))?;
-                run_workflow(&mut workflow, inputs, Some(&args.file), args.out_dir.clone())?;
+    //failed runs keep the step they failed in
+    match &result {
+        Ok(outputs) => {
+            set_event_step(None);
+            log_event("run_finished", json!({ "status": "success", "duration_ms": clock.elapsed().as_millis(), "outputs": outputs }));
+        }
+        Err(e) => log_event("run_finished", json!({ "status": "failed", "duration_ms": clock.elapsed().as_millis(), "error": e.to_string() })),
+    }
+    stop_event_log();
+
+    let usages = take_usages();
+    if result.is_ok() && !usages.is_empty() && !args.is_quiet && args.log_format == LogFormat::Text {
+        info!("📊 Resource usage of the run:\n{}", format_usage_table(&usages));
+    }
+
+    let result = match (result, &args.provenance) {
+        (Ok(outputs), Some(_)) => finish_provenance(&file, &job, &outputs).map(|_| outputs),
+        (result, Some(_)) => {
+            abort_provenance();
+            result
+        }
+        (result, None) => result,
+    };
+    set_seed(None);
+    set_run_revision(None);
+    Ok(serde_json::to_value(result?)?)
+}
+
+/// Delegates the run to cwltool or toil and prints the output object they return
+fn execute_external(
+    args: &LocalExecuteArgs,
+    file: &Path,
+    job_args: &[String],
+    cache_dir: Option<&Path>,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    //ranges are s4n annotations the engines do not know about, so a given job file is checked beforehand
+    if let [job_file] = job_args {
+        if !job_file.starts_with('-') && !file.to_string_lossy().contains('#') {
+            let job = serde_yaml::from_str(&fs::read_to_string(job_file)?)?;
+            check_job_ranges(&load_document(file)?, file.parent().unwrap_or(Path::new(".")), &job)?;
+        }
+    }
+    let mut cmd = get_engine_command(args, file, job_args, cache_dir)?;
+    let engine = cmd.get_program().to_string_lossy().into_owned();
+    if !args.is_quiet {
+        eprintln!("💻 Executing {:?} using {}.", file, engine);
+    }
+    let clock = Instant::now();
+    if args.log_format == LogFormat::Json {
+        start_event_log(&env::current_dir()?.join(args.out_dir.as_deref().unwrap_or(".")))?;
+        log_event("run_started", json!({ "file": file, "engine": engine }));
+    }
+
+    let output = cmd.output().map_err(|e| format!("❌ Could not run {}, is it installed? {}", engine, e));
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            log_event("run_finished", json!({ "status": "failed", "error": e }));
+            stop_event_log();
+            return Err(e.into());
+        }
+    };
+    if !output.stderr.is_empty() && !args.is_quiet {
+        eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    //both engines print the output object as JSON, anything else is passed through as is
+    let outputs = serde_json::from_slice::<serde_json::Value>(&output.stdout).ok();
+    match &outputs {
+        Some(outputs) => println!("{}", serde_json::to_string_pretty(outputs)?),
+        None if !output.stdout.is_empty() => println!("{}", String::from_utf8_lossy(&output.stdout)),
+        None => {}
+    }
+
+    let duration = clock.elapsed().as_millis();
+    if !output.status.success() {
+        log_event("run_finished", json!({ "status": "failed", "duration_ms": duration, "exit_code": output.status.code() }));
+        stop_event_log();
+        return Err(format!("❌ {} failed with {}", engine, output.status).into());
+    }
+    log_event("run_finished", json!({ "status": "success", "duration_ms": duration, "outputs": outputs }));
+    stop_event_log();
+    Ok(outputs.unwrap_or_default())
+}
+
+/// Builds the command line of an external engine, flags of the custom runner are translated where the engine supports
+/// them and rejected otherwise
+fn get_engine_command(args: &LocalExecuteArgs, file: &Path, job_args: &[String], cache_dir: Option<&Path>) -> Result<Command, Box<dyn Error>> {
+    let engine = args.runner.executable().ok_or("The custom runner is not an external engine")?;
+    let unsupported = |feature: &str| format!("{} is not supported by {}, use the custom runner!", feature, engine);
+    if args.simulate || args.dry_run {
+        return Err(unsupported("Simulation").into());
+    }
+    if args.sandbox {
+        return Err(unsupported("Sandboxing").into());
+    }
+    if args.staging != StagingStrategy::Copy {
+        return Err(unsupported("Choosing a staging strategy").into());
+    }
+    if args.seed.is_some() {
+        return Err(unsupported("Seeding").into());
+    }
+
+    let mut cmd = Command::new(engine);
+    if args.is_quiet {
+        cmd.arg("--quiet");
+    }
+    if let Some(outdir) = &args.out_dir {
+        cmd.arg("--outdir").arg(outdir);
+    }
+    match args.runner {
+        Runner::CWLTool => {
+            if let Some(cache_dir) = cache_dir {
+                cmd.arg("--cachedir").arg(cache_dir);
             }
+            if let Some(provenance) = &args.provenance {
+                cmd.arg("--provenance").arg(provenance);
+            }
+            if args.retries.is_some() {
+                return Err(unsupported("Retrying steps").into());
+            }
+            //cwltool limits parallel jobs by their resource requirements only
+            if args.parallel.is_some_and(|cores| cores > 1) {
+                cmd.arg("--parallel");
+            }
+        }
+        _ => {
+            if let Some(retries) = args.retries {
+                cmd.arg("--retryCount").arg(retries.to_string());
+            }
+            if let Some(cores) = args.parallel {
+                cmd.arg("--maxCores").arg(cores.to_string());
+            }
+            if cache_dir.is_some() {
+                return Err(unsupported("Caching").into());
+            }
+            if args.provenance.is_some() {
+                return Err(unsupported("Recording provenance").into());
+            }
+        }
+    }
+    cmd.arg(file).args(job_args);
+    Ok(cmd)
+}
+
+/// Removes `cwl:requirements` from the inputs of a job and returns the `s4n:RetryPolicy` given in them. Other
+/// requirements are not supported in job files yet and are ignored.
+fn take_job_retry_policy(inputs: &mut HashMap<String, DefaultValue>) -> Result<Option<RetryPolicy>, Box<dyn Error>> {
+    let Some(requirements) = inputs.remove("cwl:requirements") else {
+        return Ok(None);
+    };
+    let requirements = deserialize_requirements(serde_yaml::to_value(requirements)?)
+        .map_err(|e| format!("Could not read cwl:requirements of input file: {}", e))?
+        .unwrap_or_default();
+    let mut policy = None;
+    for requirement in requirements {
+        match requirement {
+            Requirement::RetryPolicy(retry_policy) => policy = Some(retry_policy),
+            requirement => {
+                let value = serde_yaml::to_value(&requirement)?;
+                let class = value.get("class").and_then(Value::as_str).unwrap_or_default();
+                warn!("{} in cwl:requirements of the input file is not supported and ignored", class);
+            }
+        }
+    }
+    Ok(policy)
+}
+
+/// A previous revision of the project checked out for a run, the directory is deleted when dropped
+struct Checkout {
+    _dir: TempDir,
+    file: PathBuf,
+    revision: RunRevision,
+}
 
-            Ok(())
+/// Checks out the revision of a reference like `workflows/main/main.cwl@HEAD~1` or `main.cwl@3f2a1b` into a temporary
+/// directory and returns the path of the file in it, references to files of the working tree are left alone
+fn checkout_file_revision(reference: &Path) -> Result<Option<Checkout>, Box<dyn Error>> {
+    if reference.exists() {
+        return Ok(None);
+    }
+    let reference = reference.to_string_lossy();
+    let Some((path, revision)) = reference.rsplit_once('@').filter(|(path, revision)| !path.is_empty() && !revision.is_empty()) else {
+        return Ok(None);
+    };
+    let (file, selector) = split_document_reference(path);
+    let repo = Repository::discover(env::current_dir()?).map_err(|e| format!("❌ Could not open repository: {}", e))?;
+    let relative = get_repository_path(&repo, &file).ok_or(format!("❌ {:?} is not part of the repository", file))?;
+
+    let dir = temp_dir()?;
+    let commit = checkout_revision(&repo, revision, dir.path()).map_err(|e| format!("❌ Could not check out revision {}: {}", revision, e))?;
+    let checked_out = dir.path().join(&relative);
+    if !checked_out.exists() {
+        return Err(format!("❌ {:?} does not exist in revision {}", file, revision).into());
+    }
+    info!("🕰️  Using {:?} as of commit {}", file, &commit[..7]);
+    let checked_out = match selector {
+        Some(selector) => PathBuf::from(format!("{}#{}", checked_out.to_string_lossy(), selector)),
+        None => checked_out,
+    };
+    Ok(Some(Checkout {
+        _dir: dir,
+        file: checked_out,
+        revision: RunRevision { file, commit },
+    }))
+}
+
+/// Environment variable the token for WES servers is read from
+pub const WES_TOKEN_VAR: &str = "WES_TOKEN";
+
+pub fn execute_remote(args: &RemoteExecuteArgs) -> Result<(), Box<dyn Error>> {
+    let token = match args.token.clone().or(env::var(WES_TOKEN_VAR).ok()) {
+        Some(token) => Some(token),
+        None => WesConfig::load(&env::current_dir()?)?.token(&args.server),
+    };
+    let mut backend = WesBackend::new(WesClient::new(&args.server, token)?);
+    let job_args: Vec<String> = args.input_file.iter().map(|file| file.to_string_lossy().into_owned()).collect();
+
+    //nothing is uploaded unless the server can run the packed workflow, servers not implementing the service info are tried anyway
+    let versions = backend.client().get_cwl_versions();
+    let submission = backend.prepare(&args.file, &job_args)?;
+    let version = submission.workflow.get("cwlVersion").and_then(|v| v.as_str()).unwrap_or(CWL_VERSION).to_string();
+    match versions {
+        Ok(versions) if versions.is_empty() => return Err(format!("❌ {} does not run CWL workflows", args.server).into()),
+        Ok(versions) if !versions.contains(&version) => {
+            return Err(format!("❌ {} supports CWL {} only, but {:?} is submitted as {}", args.server, versions.join(", "), args.file, version).into())
         }
+        Ok(_) => {}
+        Err(e) => warn!("Could not check the workflow types supported by {}: {}", args.server, e),
+    }
+    let unsupported = get_unsupported_requirements(&submission.workflow);
+    if !unsupported.is_empty() {
+        return Err(format!(
+            "❌ {:?} requires {} which are not part of the CWL standard and not supported by {}, use them as hints instead",
+            args.file,
+            unsupported.join(", "),
+            args.server
+        )
+        .into());
+    }
+
+    info!("📦 Uploading {} in {} attached files", format_bytes(submission.size()), submission.attachments.len() + 1);
+    info!("🧮 {} steps to run", submission.count_steps());
+    let attachments = submission.attachments.len();
+    let root = env::current_dir()?;
+    match load_runs(&root)?.get(&get_run_key(&args.file, &root)).filter(|run| run.success).and_then(|run| run.duration()) {
+        Some(duration) => info!("⏱️  Expected runtime: {} (last local run)", format_duration(duration.as_millis() as u64)),
+        None => info!("⏱️  Expected runtime: unknown, the workflow has not been run successfully before"),
+    }
+    if !args.yes {
+        if !io::stdin().is_terminal() {
+            return Err("❌ Can not ask for confirmation, use --yes to submit the run anyway".into());
+        }
+        if !Confirm::new().with_prompt(format!("Submit the run to {}?", args.server)).default(true).interact()? {
+            info!("Nothing has been submitted");
+            return Ok(());
+        }
+    }
+
+    let run_id = backend.schedule_run(&args.file, &job_args)?;
+    info!("🚀 Submitted {:?} with {} attached files to {} as run {}", args.file, attachments, args.server, run_id);
+
+    let state = wait_for_run(&backend, &run_id, Duration::from_secs(args.interval), args.timeout.map(Duration::from_secs))?;
+    if state != RunState::Complete {
+        let logs = backend.logs(&run_id)?;
+        let hint = if logs.is_empty() { String::new() } else { format!(", see {logs} for details") };
+        return Err(format!("❌ Run {} finished with state {}{}", run_id, state, hint).into());
+    }
+
+    let out_dir = args.out_dir.as_ref().map(PathBuf::from).unwrap_or(env::current_dir()?);
+    let outputs = backend.download_results(&run_id, &out_dir)?;
+    println!("{}", serde_json::to_string_pretty(&outputs)?);
+    info!("✔️  Workflow {:?} executed successfully on {}!", args.file, args.server);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_args(cmd: &Command) -> Vec<String> {
+        cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn test_get_engine_command() {
+        let mut args = LocalExecuteArgs {
+            runner: Runner::CWLTool,
+            is_quiet: true,
+            out_dir: Some("out".to_string()),
+            provenance: Some(PathBuf::from("ro")),
+            args: vec!["job.yml".to_string()],
+            ..Default::default()
+        };
+        let cmd = get_engine_command(&args, Path::new("main.cwl"), &args.args, Some(Path::new("cache"))).unwrap();
+        assert_eq!(cmd.get_program(), "cwltool");
+        assert_eq!(
+            get_args(&cmd),
+            vec!["--quiet", "--outdir", "out", "--cachedir", "cache", "--provenance", "ro", "main.cwl", "job.yml"]
+        );
+
+        args.runner = Runner::Toil;
+        assert!(get_engine_command(&args, Path::new("main.cwl"), &args.args, None).is_err());
+        args.provenance = None;
+        let cmd = get_engine_command(&args, Path::new("main.cwl"), &args.args, None).unwrap();
+        assert_eq!(cmd.get_program(), "toil-cwl-runner");
+        assert_eq!(get_args(&cmd), vec!["--quiet", "--outdir", "out", "main.cwl", "job.yml"]);
+        args.retries = Some(2);
+        let cmd = get_engine_command(&args, Path::new("main.cwl"), &args.args, None).unwrap();
+        assert_eq!(get_args(&cmd), vec!["--quiet", "--outdir", "out", "--retryCount", "2", "main.cwl", "job.yml"]);
+        args.parallel = Some(4);
+        let cmd = get_engine_command(&args, Path::new("main.cwl"), &args.args, None).unwrap();
+        assert_eq!(
+            get_args(&cmd),
+            vec!["--quiet", "--outdir", "out", "--retryCount", "2", "--maxCores", "4", "main.cwl", "job.yml"]
+        );
+        args.retries = None;
+        args.runner = Runner::CWLTool;
+        let cmd = get_engine_command(&args, Path::new("main.cwl"), &args.args, None).unwrap();
+        assert_eq!(get_args(&cmd), vec!["--quiet", "--outdir", "out", "--parallel", "main.cwl", "job.yml"]);
+        args.runner = Runner::Toil;
+
+        args.seed = Some(1);
+        assert!(get_engine_command(&args, Path::new("main.cwl"), &args.args, None).is_err());
+        args.runner = Runner::Custom;
+        assert!(get_engine_command(&args, Path::new("main.cwl"), &args.args, None).is_err());
     }
 }
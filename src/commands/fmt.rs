@@ -0,0 +1,87 @@
+use crate::wizard::print_diff;
+use clap::Args;
+use colored::Colorize;
+use cwl::format::{format_cwl, has_anchors};
+use log::{info, warn};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    #[arg(help = "CWL files to format, all CWL files of the project are formatted if none are given")]
+    pub files: Vec<PathBuf>,
+    #[arg(long = "check", help = "Only checks whether files are formatted and shows the changes that would be made")]
+    pub check: bool,
+}
+
+pub fn format_files(args: &FmtArgs) -> Result<(), Box<dyn Error>> {
+    let files = if args.files.is_empty() { find_cwl_files(".") } else { args.files.clone() };
+
+    let mut unformatted = vec![];
+    for file in &files {
+        let contents = fs::read_to_string(file).map_err(|e| format!("❌ Could not read {:?}: {}", file, e))?;
+        let formatted = format_cwl(&contents).map_err(|e| format!("❌ Could not format {:?}: {}", file, e))?;
+        if formatted == contents {
+            continue;
+        }
+        let name = file.to_string_lossy();
+        if args.check {
+            print_diff(&name, &contents, &formatted);
+        } else {
+            if has_inner_comments(&contents) {
+                warn!("Comments inside of {} are not kept by the formatter", name);
+            }
+            if has_anchors(&contents) {
+                warn!("Anchors and aliases in {} are expanded by the formatter", name);
+            }
+            fs::write(file, &formatted)?;
+            info!("📝 Formatted {}", name.green().bold());
+        }
+        unformatted.push(file);
+    }
+
+    if args.check && !unformatted.is_empty() {
+        return Err(format!("❌ {} of {} file(s) are not formatted, run `s4n fmt` to format them", unformatted.len(), files.len()).into());
+    }
+    if unformatted.is_empty() {
+        info!("✔️  All {} file(s) are formatted", files.len());
+    }
+    Ok(())
+}
+
+/// Collects all CWL files below the given folder, hidden folders like `.git` and `.s4n` are skipped
+pub fn find_cwl_files(folder: impl AsRef<Path>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(folder)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "cwl"))
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Only the comments at the top of a file survive formatting
+fn has_inner_comments(contents: &str) -> bool {
+    contents
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty() || line.starts_with('#'))
+        .any(|line| line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_inner_comments() {
+        assert!(!has_inner_comments("#!/usr/bin/env cwl-runner\n# comment\n\nclass: Workflow\n"));
+        assert!(has_inner_comments("class: Workflow\n# comment\ninputs: []\n"));
+    }
+}
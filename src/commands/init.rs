@@ -1,13 +1,18 @@
-use crate::repo::{commit, get_modified_files, initial_commit, stage_all};
+use crate::{
+    commands::annotate::{apply_project_metadata, metadata_from_isa, read_isa_rows, IsaRows, ProjectMetadata},
+    repo::{commit, get_modified_files, initial_commit, stage_all, stage_file},
+};
 use clap::Args;
 use git2::Repository;
-use log::{error, info};
+use log::{error, info, warn};
 use rust_xlsxwriter::Workbook;
 use std::{
+    collections::HashSet,
     env,
     fs::{self, File},
     path::{Path, PathBuf},
 };
+use walkdir::WalkDir;
 
 #[derive(Args, Debug)]
 pub struct InitArgs {
@@ -15,10 +20,20 @@ pub struct InitArgs {
     project: Option<String>,
     #[arg(short = 'a', long = "arc", help = "Option to create basic arc folder structure")]
     arc: bool,
+    #[arg(
+        long = "from-arc",
+        conflicts_with = "arc",
+        help = "Imports an existing ARC by registering its CWL files and annotating them with the ISA metadata"
+    )]
+    from_arc: bool,
 }
 
 pub fn handle_init_command(args: &InitArgs) -> Result<(), Box<dyn std::error::Error>> {
-    init_s4n(args.project.clone(), args.arc).map_err(|e| format!("Could not init {}", e))?;
+    if args.from_arc {
+        import_arc(args.project.as_deref()).map_err(|e| format!("Could not import ARC {}", e))?;
+    } else {
+        init_s4n(args.project.clone(), args.arc).map_err(|e| format!("Could not init {}", e))?;
+    }
     Ok(())
 }
 
@@ -214,3 +229,145 @@ pub fn create_investigation_excel_file(directory: &str) -> Result<(), Box<dyn st
 
     Ok(())
 }
+
+/// Name of the project file listing the CWL files of a project
+pub const PROJECT_FILE: &str = "workflow.toml";
+
+/// Investigation files as named by the ARC specification and by `s4n init --arc`
+const ARC_INVESTIGATION_FILES: [&str; 2] = ["isa.investigation.xlsx", "isa_investigation.xlsx"];
+const ARC_FOLDERS: [&str; 4] = ["studies", "assays", "workflows", "runs"];
+
+/// Turns an existing ARC into an s4n project. CWL files in `workflows` and `runs` are registered in the project file and
+/// annotated with the contacts of the investigation. Existing files and the git history are kept, changes are committed
+/// on top of it.
+pub fn import_arc(folder: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let base_dir = folder.map(PathBuf::from).unwrap_or(env::current_dir()?);
+    let investigation = ARC_INVESTIGATION_FILES.iter().map(|f| base_dir.join(f)).find(|f| f.is_file());
+    let folders = ARC_FOLDERS.iter().filter(|f| base_dir.join(f).is_dir()).count();
+    if investigation.is_none() && folders < 2 {
+        return Err(format!("❌ {} does not contain an ARC, no investigation file or ARC folders found", base_dir.display()).into());
+    }
+
+    let repo = if is_git_repo(folder) {
+        Repository::open(&base_dir)?
+    } else {
+        init_git_repo(folder)?
+    };
+    let workflows_dir = base_dir.join("workflows");
+    if !workflows_dir.exists() {
+        fs::create_dir_all(&workflows_dir)?;
+        File::create(workflows_dir.join(".gitkeep"))?;
+    }
+
+    let cwl_files = find_arc_cwl_files(&base_dir);
+    let rows = match &investigation {
+        Some(file) => read_isa_rows(&file.to_string_lossy())?,
+        None => IsaRows::new(),
+    };
+
+    let mut changed = vec![];
+    let project_file = base_dir.join(PROJECT_FILE);
+    if project_file.exists() {
+        warn!("{} already exists, keeping it", PROJECT_FILE);
+    } else {
+        let name = base_dir.canonicalize()?.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        fs::write(&project_file, create_project_file(&name, &rows, &cwl_files))?;
+        changed.push(PathBuf::from(PROJECT_FILE));
+    }
+
+    let metadata = metadata_from_isa(&rows);
+    if metadata != ProjectMetadata::default() {
+        for (path, _) in &cwl_files {
+            let file = base_dir.join(path);
+            let before = fs::read_to_string(&file)?;
+            apply_project_metadata(&file.canonicalize()?.to_string_lossy(), &metadata).map_err(|e| format!("❌ Could not annotate {}: {}", path.display(), e))?;
+            if fs::read_to_string(&file)? != before {
+                changed.push(path.clone());
+            }
+        }
+        info!("🏷️  Annotated {} CWL files with the contacts of the investigation", cwl_files.len());
+    }
+
+    if changed.is_empty() {
+        error!("Nothing to commit");
+    } else {
+        for path in &changed {
+            stage_file(&repo, &path.to_string_lossy().replace('\\', "/"))?;
+        }
+        if repo.head().is_ok() {
+            commit(&repo, "Imported ARC using `s4n init --from-arc`")?;
+        } else {
+            initial_commit(&repo)?;
+        }
+    }
+
+    info!("📂 Imported ARC {} with {} CWL files:", base_dir.display(), cwl_files.len());
+    for (path, class) in &cwl_files {
+        info!("  ├── {} ({})", path.display(), class);
+    }
+    Ok(())
+}
+
+/// CWL files of the `workflows` and `runs` folders of an ARC together with their class, paths are relative to the ARC
+fn find_arc_cwl_files(base_dir: &Path) -> Vec<(PathBuf, String)> {
+    let mut files = vec![];
+    for folder in ["workflows", "runs"] {
+        for entry in WalkDir::new(base_dir.join(folder)).sort_by_file_name().into_iter().filter_map(Result::ok) {
+            let path = entry.path();
+            if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "cwl") {
+                continue;
+            }
+            let class = fs::read_to_string(path)
+                .ok()
+                .and_then(|contents| serde_yaml::from_str::<serde_yaml::Value>(&contents).ok())
+                .and_then(|yaml| yaml.get("class").and_then(|c| c.as_str()).map(String::from))
+                .unwrap_or("Unknown".to_string());
+            if let Ok(relative) = path.strip_prefix(base_dir) {
+                files.push((relative.to_path_buf(), class));
+            }
+        }
+    }
+    files
+}
+
+/// Project file with the investigation's title and description and all CWL files grouped by their class
+fn create_project_file(name: &str, rows: &IsaRows, cwl_files: &[(PathBuf, String)]) -> String {
+    let value = |key: &str| rows.get(key).and_then(|v| v.first()).filter(|v| !v.is_empty()).cloned();
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+
+    let mut toml = String::from("[workflow]\n");
+    toml.push_str(&format!("name = {}\n", quote(&value("identifier").unwrap_or(name.to_string()))));
+    toml.push_str("version = \"0.1.0\"\n");
+    if let Some(title) = value("title") {
+        toml.push_str(&format!("title = {}\n", quote(&title)));
+    }
+    if let Some(description) = value("description") {
+        toml.push_str(&format!("description = {}\n", quote(&description)));
+    }
+
+    for (section, class) in [("tools", "CommandLineTool"), ("workflows", "Workflow")] {
+        toml.push_str(&format!("\n[{section}]\n"));
+        let mut names = HashSet::new();
+        for (path, _) in cwl_files.iter().filter(|(_, c)| c == class) {
+            let path = path.to_string_lossy().replace('\\', "/");
+            //names have to be unique, e.g. for `workflows/fastqc/workflow.cwl` and `runs/fastqc/run.cwl`
+            let mut name = get_registered_name(&path);
+            if !names.insert(name.clone()) {
+                name = path.trim_end_matches(".cwl").to_string();
+            }
+            toml.push_str(&format!("{} = {}\n", quote(&name), quote(&path)));
+        }
+    }
+    toml
+}
+
+/// Files named like their folder or `workflow.cwl` and `run.cwl` as used by ARCs are named after the folder, e.g.
+/// `runs/fastqc/run.cwl` is registered as `fastqc`, other files by their path e.g. `workflows/fastqc/trim`.
+fn get_registered_name(path: &str) -> String {
+    let path = path.trim_end_matches(".cwl");
+    let mut parts = path.rsplitn(3, '/');
+    match (parts.next(), parts.next()) {
+        (Some(stem), Some(folder)) if stem == folder || stem == "workflow" || stem == "run" => folder.to_string(),
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    commands::workflow::get_workflow_filename,
+    io::create_and_write_file_forced,
+    job::{create_jobs_from_table, create_sweep, get_missing_inputs, load_document, read_table},
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use log::{info, warn};
+use slugify::slugify;
+use std::{collections::HashSet, error::Error, fs, path::Path, path::PathBuf};
+
+pub fn handle_inputs_commands(subcommand: &InputsCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        InputsCommands::FromTable(args) => create_jobs_from_file(args),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InputsCommands {
+    #[command(about = "Creates job files from the rows of a CSV file or spreadsheet")]
+    FromTable(FromTableArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct FromTableArgs {
+    #[arg(help = "CSV file or Excel or OpenDocument spreadsheet with one run per row and one input per column")]
+    pub table: PathBuf,
+    #[arg(short = 's', long = "sheet", help = "Name of the sheet to read, defaults to the first sheet")]
+    pub sheet: Option<String>,
+    #[arg(
+        short = 'w',
+        long = "workflow",
+        help = "Name of the workflow or path to a CWL file to match the columns against its inputs"
+    )]
+    pub workflow: Option<String>,
+    #[arg(short = 'n', long = "name-column", help = "Column to name the job files by, rows are numbered otherwise")]
+    pub name_column: Option<String>,
+    #[arg(short = 'o', long = "outdir", default_value = "jobs", help = "Folder to write the job files to")]
+    pub outdir: PathBuf,
+    #[arg(long = "sweep", help = "Writes a single job with an array of the values of all rows for every input")]
+    pub sweep: bool,
+    #[arg(short = 'f', long = "force", help = "Overwrites existing job files")]
+    pub force: bool,
+}
+
+pub fn create_jobs_from_file(args: &FromTableArgs) -> Result<(), Box<dyn Error>> {
+    let mut table = read_table(&args.table, args.sheet.as_deref())?;
+    let names = match &args.name_column {
+        Some(column) => Some(
+            table
+                .take_column(column)
+                .ok_or(format!("❌ {:?} does not contain a column named {column}", args.table))?,
+        ),
+        None => None,
+    };
+
+    let document = match &args.workflow {
+        Some(workflow) => {
+            let filename = get_workflow_filename(workflow);
+            let document = load_document(Path::new(&filename))?;
+            let folder = Path::new(&filename).parent().unwrap_or(Path::new("."));
+            let missing = get_missing_inputs(&document, folder, &table.columns);
+            if !missing.is_empty() {
+                warn!(
+                    "Inputs {} of {filename} are not part of the table and have no default",
+                    missing.join(", ")
+                );
+            }
+            Some(document)
+        }
+        None => None,
+    };
+
+    fs::create_dir_all(&args.outdir)?;
+    let jobs = create_jobs_from_table(&table, document.as_ref(), &args.outdir)?;
+    if jobs.is_empty() {
+        return Err(format!("❌ {:?} does not contain any rows below its header", args.table).into());
+    }
+
+    let stem = args.table.file_stem().unwrap_or_default().to_string_lossy();
+    let files = if args.sweep {
+        let header = format!(
+            "# Sweep over the {} rows of {}, scatter over these inputs using scatterMethod: dotproduct\n",
+            jobs.len(),
+            args.table.to_string_lossy()
+        );
+        vec![(
+            args.outdir.join(format!("{stem}.yml")),
+            header + &serde_yaml::to_string(&create_sweep(&jobs))?,
+        )]
+    } else {
+        let mut files = vec![];
+        let mut seen = HashSet::new();
+        for (i, job) in jobs.iter().enumerate() {
+            let name = names
+                .as_ref()
+                .and_then(|names| names.get(i))
+                .filter(|name| !name.is_empty())
+                .map(|name| slugify!(name, separator = "_"))
+                .unwrap_or_else(|| format!("{stem}_{}", i + 1));
+            if !seen.insert(name.clone()) {
+                return Err(format!("❌ Multiple rows are named {name}").into());
+            }
+            files.push((args.outdir.join(format!("{name}.yml")), serde_yaml::to_string(job)?));
+        }
+        files
+    };
+
+    if let Some((path, _)) = files.iter().find(|(path, _)| path.exists() && !args.force) {
+        return Err(format!("❌ Job file {:?} already exists. Use --force to overwrite it.", path).into());
+    }
+    for (path, contents) in &files {
+        create_and_write_file_forced(path, contents).map_err(|e| format!("❌ Could not write job file {:?}: {}", path, e))?;
+    }
+
+    if args.sweep {
+        info!(
+            "📝 Created sweep over {} rows in {}",
+            jobs.len(),
+            files[0].0.to_string_lossy().green().bold()
+        );
+    } else {
+        info!("📝 Created {} job files in {}", files.len(), args.outdir.to_string_lossy().green().bold());
+    }
+    Ok(())
+}
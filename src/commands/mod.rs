@@ -1,6 +1,14 @@
 pub mod annotate;
+pub mod bundle;
+pub mod data;
 pub mod execute;
+pub mod fmt;
 pub mod init;
+pub mod inputs;
+pub mod package;
+pub mod report;
+pub mod sbom;
 pub mod sync;
 pub mod tool;
+pub mod validate;
 pub mod workflow;
@@ -0,0 +1,140 @@
+use crate::{
+    commands::annotate::annotate_field,
+    package::{tag_release, upload_to_workflowhub, upload_to_zenodo, Package, WORKFLOWHUB_TOKEN_VAR, ZENODO_TOKEN_VAR},
+    repo::{commit, get_modified_files, stage_file},
+    secrets::{check_secrets, SecretScanner},
+};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use cwl::{format::format_cwl, packed::pack_workflow};
+use serde_yaml::Value;
+use git2::Repository;
+use log::info;
+use std::{env, error::Error, fs, path::PathBuf};
+use tempfile::tempdir;
+
+pub fn handle_package_commands(subcommand: &PackageCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        PackageCommands::Publish(args) => publish(args),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PackageCommands {
+    #[command(about = "Packs the project's workflows as Workflow RO-Crate, tags the release and optionally uploads it to WorkflowHub or Zenodo")]
+    Publish(PublishArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PublishArgs {
+    #[arg(help = "Version of the release, the git tag is named v<VERSION>")]
+    pub version: String,
+    #[arg(
+        short = 'w',
+        long = "workflow",
+        help = "Main workflow of the package, required if the project contains multiple workflows"
+    )]
+    pub workflow: Option<String>,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Path of the created archive [default: <PROJECT>-<VERSION>.crate.zip]"
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(long = "no-tag", help = "Do not tag the release in git")]
+    pub no_tag: bool,
+    #[arg(
+        long = "workflowhub",
+        value_name = "URL",
+        requires = "project_id",
+        help = "Uploads the package to a WorkflowHub instance, e.g. https://workflowhub.eu, using the token in WORKFLOWHUB_API_TOKEN"
+    )]
+    pub workflowhub: Option<String>,
+    #[arg(long = "project-id", help = "Id of the WorkflowHub project to upload the package to")]
+    pub project_id: Option<String>,
+    #[arg(
+        long = "zenodo",
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = "https://zenodo.org",
+        help = "Publishes the package on Zenodo using the token in ZENODO_ACCESS_TOKEN and records the DOI in the workflow"
+    )]
+    pub zenodo: Option<String>,
+    #[arg(long = "allow-secrets", help = "Publishes the package even if it contains possible passwords, tokens or credentials")]
+    pub allow_secrets: bool,
+    #[arg(
+        long = "redact-secrets",
+        conflicts_with = "allow_secrets",
+        help = "Replaces possible passwords, tokens or credentials with REDACTED in the published files"
+    )]
+    pub redact_secrets: bool,
+}
+
+pub fn publish(args: &PublishArgs) -> Result<(), Box<dyn Error>> {
+    let cwd = env::current_dir()?;
+    let repo = Repository::open(&cwd)?;
+    //archives of earlier releases are not part of the project
+    if get_modified_files(&repo).iter().any(|file| !file.ends_with(".crate.zip")) {
+        return Err("❌ Project has uncommitted changes, please commit them before publishing".into());
+    }
+    let get_token = |url: &Option<String>, var: &str| match url {
+        Some(_) => env::var(var).map(Some).map_err(|_| format!("❌ {var} is not set")),
+        None => Ok(None),
+    };
+    let token = get_token(&args.workflowhub, WORKFLOWHUB_TOKEN_VAR)?;
+    let zenodo_token = get_token(&args.zenodo, ZENODO_TOKEN_VAR)?;
+
+    let name = cwd.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let version = args.version.trim_start_matches('v');
+    let package = Package::from_project(&cwd, &name, version, args.workflow.as_deref())?;
+    let archive = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{name}-{version}.crate.zip")));
+    let scanner = SecretScanner::load(&cwd)?;
+    if !args.redact_secrets {
+        check_secrets("Package", &scanner.scan_files(&cwd, &package.files), args.allow_secrets, true)?;
+    }
+    package.write_crate(&cwd, &archive, args.redact_secrets.then_some(&scanner))?;
+    info!(
+        "📦 Packed {} files with main workflow {} into {}",
+        package.files.len(),
+        package.main_workflow.to_string_lossy(),
+        archive.to_string_lossy().green().bold()
+    );
+
+    if !args.no_tag {
+        let tag = format!("v{version}");
+        tag_release(&repo, &tag, &format!("Release {version}"))?;
+        info!("🏷️  Tagged release {}", tag.green().bold());
+    }
+
+    if let (Some(url), Some(token), Some(project_id)) = (&args.workflowhub, token, &args.project_id) {
+        let location = upload_to_workflowhub(url, &token, project_id, &archive)?;
+        info!("🚀 Published package to {}", location.green().bold());
+    }
+
+    if let (Some(url), Some(token)) = (&args.zenodo, zenodo_token) {
+        //the deposition contains the crate, the workflow packed into a single file and the README
+        let dir = tempdir()?;
+        let workflow_name = package.main_workflow.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let packed = dir.path().join(format!("{workflow_name}.packed.cwl"));
+        let mut document: Value = serde_yaml::from_str(&pack_workflow(&package.main_workflow)?.to_string())?;
+        if args.redact_secrets {
+            scanner.redact(&mut document);
+        }
+        fs::write(&packed, format_cwl(&serde_yaml::to_string(&document)?)?)?;
+        let mut files = vec![archive.clone(), packed];
+        files.extend(package.files.iter().filter(|f| f.as_os_str() == "README.md").cloned());
+
+        let doi = upload_to_zenodo(url, &token, &package, &files)?;
+        info!("🚀 Published package on Zenodo with DOI {}", doi.green().bold());
+
+        let workflow = package.main_workflow.to_string_lossy();
+        annotate_field(&workflow, "s:identifier", &format!("https://doi.org/{doi}"))?;
+        stage_file(&repo, &workflow)?;
+        commit(&repo, &format!("Recorded DOI of release {version}"))?;
+        info!("📄 Recorded DOI in {}", workflow.green().bold());
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::{commands::workflow::get_workflow_filename, report::create_publication_report};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use log::info;
+use std::{error::Error, fs, path::Path, path::PathBuf};
+
+pub fn handle_report_commands(subcommand: &ReportCommands) -> Result<(), Box<dyn Error>> {
+    match subcommand {
+        ReportCommands::Publication(args) => create_publication(args),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReportCommands {
+    #[command(about = "Creates a figure, a methods paragraph and a software citation list of a workflow for a manuscript")]
+    Publication(PublicationReportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PublicationReportArgs {
+    #[arg(help = "Name of the workflow or path to its CWL file")]
+    pub workflow: String,
+    #[arg(short = 'o', long = "outdir", default_value = "publication", help = "Folder to write the report to")]
+    pub outdir: PathBuf,
+}
+
+pub fn create_publication(args: &PublicationReportArgs) -> Result<(), Box<dyn Error>> {
+    let filename = get_workflow_filename(&args.workflow);
+    let report = create_publication_report(Path::new(&filename))?;
+
+    fs::create_dir_all(&args.outdir)?;
+    let figure = args.outdir.join("figure.svg");
+    fs::write(&figure, &report.figure).map_err(|e| format!("❌ Could not write figure to {:?}: {}", figure, e))?;
+    let markdown = args.outdir.join("publication.md");
+    fs::write(&markdown, report.to_markdown("figure.svg")).map_err(|e| format!("❌ Could not write report to {:?}: {}", markdown, e))?;
+
+    info!(
+        "📰 Created publication report of {} citing {} references in {}",
+        filename,
+        report.references.len(),
+        args.outdir.to_string_lossy().green().bold()
+    );
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::sbom::{collect_components, to_cyclonedx, to_spdx};
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use log::info;
+use std::{error::Error, fs, path::PathBuf};
+
+#[derive(Args, Debug)]
+pub struct SbomArgs {
+    #[arg(help = "CWL Workflow or CommandLineTool to create the SBOM for")]
+    pub file: PathBuf,
+    #[arg(short = 'f', long = "format", value_enum, default_value_t = SbomFormat::CycloneDX, help = "Format of the SBOM")]
+    pub format: SbomFormat,
+    #[arg(short = 'o', long = "output", help = "File to write the SBOM to instead of printing it")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SbomFormat {
+    #[value(name = "cyclonedx")]
+    CycloneDX,
+    #[value(name = "spdx")]
+    Spdx,
+}
+
+pub fn create_sbom(args: &SbomArgs) -> Result<(), Box<dyn Error>> {
+    if !args.file.exists() {
+        return Err(format!("❌ File {:?} does not exist", args.file).into());
+    }
+    let components = collect_components(&args.file)?;
+    let name = args.file.file_stem().unwrap_or_default().to_string_lossy();
+    let sbom = match args.format {
+        SbomFormat::CycloneDX => to_cyclonedx(&name, &components),
+        SbomFormat::Spdx => to_spdx(&name, &components),
+    };
+    let sbom = serde_json::to_string_pretty(&sbom)?;
+
+    match &args.output {
+        Some(output) => {
+            fs::write(output, sbom)?;
+            info!(
+                "📋 Written SBOM listing {} components of {name} to {}",
+                components.len(),
+                output.to_string_lossy().green().bold()
+            );
+        }
+        None => println!("{sbom}"),
+    }
+    Ok(())
+}
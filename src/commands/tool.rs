@@ -1,22 +1,30 @@
 use crate::{
-    cwl::{highlight_cwl, Saveable},
+    commands::annotate::annotate,
+    container::{detect_dependencies, get_image_platforms, get_image_tag, get_native_platform, ImageBuild, AUTO_CONTAINER},
+    commands::{fmt::find_cwl_files, workflow::print_history},
+    cwl::{highlight_cwl, resolve_filename, Saveable},
+    environments::{deactivate, detect_environment, export_conda_environment, get_software_packages},
     execution::runner::{run_command, run_commandlinetool},
-    io::{create_and_write_file, get_qualified_filename},
+    io::{create_and_write_file, create_and_write_file_forced, get_filename_without_extension, get_qualified_filename, get_workflows_folder},
+    notebook::Notebook,
     parser::{self, post_process_cwl},
     repo::{commit, get_modified_files, stage_file},
+    secrets::{check_secrets, SecretScanner},
     print_list,
+    wizard::{confirm_tool, prompt_tool_args},
 };
 use clap::{Args, Subcommand};
 use colored::Colorize;
 use cwl::{
     format::format_cwl,
-    requirements::{DockerRequirement, Requirement},
+    load_tool,
+    requirements::{CondaEnvironment, DockerRequirement, Requirement, SoftwareRequirement, S4N_NAMESPACE},
 };
 use git2::Repository;
 use log::{error, info, warn};
 use prettytable::{Cell, Row, Table};
 use serde_yaml::Value;
-use std::{env, error::Error, fs, fs::remove_file, path::Path, path::PathBuf};
+use std::{env, error::Error, fs, fs::remove_file, path::Path, path::PathBuf, process::Command as SystemCommand};
 use walkdir::WalkDir;
 
 pub fn handle_tool_commands(subcommand: &ToolCommands) -> Result<(), Box<dyn Error>> {
@@ -24,6 +32,11 @@ pub fn handle_tool_commands(subcommand: &ToolCommands) -> Result<(), Box<dyn Err
         ToolCommands::Create(args) => create_tool(args),
         ToolCommands::List(args) => list_tools(args),
         ToolCommands::Remove(args) => remove_tool(args),
+        ToolCommands::Migrate(args) => migrate_tools(args),
+        ToolCommands::Build(args) => build_tool_image(args),
+        ToolCommands::Env { command } => match command {
+            ToolEnvCommands::Capture(args) => capture_environment(args),
+        },
     }
 }
 
@@ -35,16 +48,31 @@ pub enum ToolCommands {
     List(ListToolArgs),
     #[command(about = "Remove a tool, e.g. s4n tool rm toolname", visible_alias = "rm")]
     Remove(RemoveToolArgs),
+    #[command(about = "Rewrites `$include` entries of binary files into File inputs, e.g. for tools created by older versions")]
+    Migrate(MigrateToolArgs),
+    #[command(about = "Builds the container image of a tool from its Dockerfile and pins its content-addressed tag in the tool")]
+    Build(BuildToolArgs),
+    #[command(about = "Manages the software environment of a tool")]
+    Env {
+        #[command(subcommand)]
+        command: ToolEnvCommands,
+    },
 }
 
-#[derive(Args, Debug)]
+#[derive(Debug, Subcommand)]
+pub enum ToolEnvCommands {
+    #[command(about = "Exports the active conda environment next to a tool and references it as SoftwareRequirement hint")]
+    Capture(CaptureEnvArgs),
+}
+
+#[derive(Args, Debug, Default, Clone)]
 pub struct CreateToolArgs {
     #[arg(short = 'n', long = "name", help = "A name to be used for this tool")]
     pub name: Option<String>,
     #[arg(
         short = 'c',
         long = "container-image",
-        help = "An image to pull from e.g. docker hub or path to a Dockerfile"
+        help = "An image to pull from e.g. docker hub, path to a Dockerfile or `auto` to generate a Dockerfile from the script's dependencies"
     )]
     pub container_image: Option<String>,
     #[arg(short = 't', long = "container-tag", help = "The tag for the container when using a Dockerfile")]
@@ -58,6 +86,30 @@ pub struct CreateToolArgs {
     pub no_run: bool,
     #[arg(long = "clean", help = "Deletes created outputs after usage")]
     pub is_clean: bool,
+    #[arg(long = "project-env", help = "Runs the command inside the project's venv or renv environment instead of the one on PATH")]
+    pub project_env: bool,
+    #[arg(long = "allow-secrets", help = "Saves the tool even if its command line contains possible passwords, tokens or credentials")]
+    pub allow_secrets: bool,
+    #[arg(
+        long = "redact-secrets",
+        conflicts_with = "allow_secrets",
+        help = "Replaces possible passwords, tokens or credentials with REDACTED in the saved tool and commit message"
+    )]
+    pub redact_secrets: bool,
+    #[arg(long = "interactive", help = "Asks for the command, inputs, outputs, container and resources step by step")]
+    pub interactive: bool,
+    #[arg(
+        long = "from-help",
+        conflicts_with = "inputs",
+        help = "Generates the inputs from the --help output of an argparse or click script instead of running an example call"
+    )]
+    pub from_help: bool,
+    #[arg(
+        long = "from-notebook",
+        conflicts_with_all = ["command", "from_help", "inputs", "interactive"],
+        help = "Creates a tool running a Jupyter notebook with papermill, the variables of the cell tagged `parameters` become inputs"
+    )]
+    pub from_notebook: Option<String>,
     #[arg(short = 'i', long = "inputs", help = "Force values to be considered as an input.", value_delimiter = ' ')]
     pub inputs: Option<Vec<String>>,
     #[arg(
@@ -77,10 +129,43 @@ pub struct RemoveToolArgs {
     pub tool_names: Vec<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct CaptureEnvArgs {
+    #[arg(help = "Name of the tool to capture the environment for")]
+    pub tool_name: String,
+    #[arg(long = "no-commit", help = "Do not commit the environment file and updated tool")]
+    pub no_commit: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct BuildToolArgs {
+    #[arg(help = "Name of the tool to build the image for")]
+    pub tool_name: String,
+    #[arg(long = "force", help = "Builds the image even if an image with the same tag exists")]
+    pub force: bool,
+    #[arg(
+        long = "platform",
+        help = "Platform to build the image for, e.g. linux/amd64 to use emulation on Apple Silicon [default: platform of the host]"
+    )]
+    pub platform: Option<String>,
+    #[arg(long = "no-commit", help = "Do not commit the updated tool")]
+    pub no_commit: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct MigrateToolArgs {
+    #[arg(help = "Name of the tool to migrate, all tools of the project are migrated if not given")]
+    pub tool_name: Option<String>,
+    #[arg(long = "no-commit", help = "Do not commit the migrated tools")]
+    pub no_commit: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct ListToolArgs {
     #[arg(short = 'a', long = "all", help = "Outputs the tools with inputs and outputs")]
     pub list_all: bool,
+    #[arg(long = "history", value_name = "TOOL", conflicts_with = "list_all", help = "Shows the commits which changed the given tool")]
+    pub history: Option<String>,
 }
 
 pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
@@ -89,9 +174,6 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
     if !args.is_raw {
         info!("📂 The current working directory is {}", cwd.to_str().unwrap().green().bold());
     }
-    let inputs = args.inputs.as_deref().unwrap_or(&[]);
-    let outputs = args.outputs.as_deref().unwrap_or(&[]);
-
     let repo = Repository::open(&cwd).map_err(|e| format!("Could not find git repository at {:?}: {}", cwd, e))?;
     let modified = get_modified_files(&repo);
     if !modified.is_empty() {
@@ -100,19 +182,54 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
         return Err("Uncommitted changes detected".into());
     }
 
+    // Ask for everything else in interactive mode
+    let answers;
+    let (args, resources) = if args.interactive {
+        answers = prompt_tool_args(args)?;
+        (&answers.0, answers.1.clone())
+    } else {
+        (args, None)
+    };
+    let inputs = args.inputs.as_deref().unwrap_or(&[]);
+    let outputs = args.outputs.as_deref().unwrap_or(&[]);
+
+    // Notebooks are run using papermill
+    let notebook = args.from_notebook.as_deref().map(Notebook::from_file).transpose()?;
+    let command = match &notebook {
+        Some(notebook) => vec!["papermill".to_string(), notebook.path.clone(), notebook.get_executed_name()],
+        None => args.command.clone(),
+    };
+
     // Parse input string
-    if args.command.is_empty() {
+    if command.is_empty() {
         return Err("No commandline string given!".into());
     }
 
-    let mut cwl = parser::parse_command_line(
-        args.command.iter().map(|s| s.as_str()).collect(),
-        if inputs.is_empty() {
-            None
-        } else {
-            Some(inputs.iter().map(|s| s.as_str()).collect())
-        },
-    );
+    let mut cwl = if let Some(notebook) = &notebook {
+        if !args.is_raw {
+            info!(
+                "📓 Creating tool for notebook {} with parameters {:?}",
+                notebook.path,
+                notebook.get_parameters().iter().map(|p| &p.name).collect::<Vec<_>>()
+            );
+        }
+        notebook.to_tool()
+    } else if args.from_help {
+        let help = get_help_text(&args.command, args.project_env)?;
+        if !args.is_raw {
+            info!("📖 Generating inputs from the help text of `{}`", args.command.join(" "));
+        }
+        parser::parse_help_text(&args.command.iter().map(|s| s.as_str()).collect::<Vec<_>>(), &help)
+    } else {
+        parser::parse_command_line(
+            args.command.iter().map(|s| s.as_str()).collect(),
+            if inputs.is_empty() {
+                None
+            } else {
+                Some(inputs.iter().map(|s| s.as_str()).collect())
+            },
+        )
+    };
 
     // Handle outputs
     if !outputs.is_empty() {
@@ -120,7 +237,24 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
     }
 
     // Handle container requirements
-    if let Some(container) = &args.container_image {
+    let mut dockerfile = None;
+    if args.container_image.as_deref() == Some(AUTO_CONTAINER) || notebook.is_some() && args.container_image.is_none() {
+        //generate Dockerfile next to the CWL file and reference it as hint, notebooks always get one including papermill
+        let dependencies = match &notebook {
+            Some(notebook) => notebook.get_dependencies(),
+            None => detect_dependencies(&command).ok_or("❌ Could not detect a Python or R script to generate a Dockerfile for")?,
+        };
+        let path = get_qualified_filename(&cwl.base_command, args.name.clone());
+        let dockerfile_path = Path::new(&path).with_file_name("Dockerfile").to_string_lossy().into_owned();
+        let image_id = args.container_tag.clone().unwrap_or_else(|| format!("sciwin-{}", get_filename_without_extension(&path).unwrap_or_default()));
+        if !args.is_raw {
+            info!("🐳 Detected dependencies: {:?}", dependencies.packages);
+        }
+
+        let hint = Requirement::DockerRequirement(DockerRequirement::from_file(&dockerfile_path, &image_id));
+        cwl.hints.get_or_insert_with(Vec::new).push(hint);
+        dockerfile = Some((dockerfile_path, dependencies.to_dockerfile()));
+    } else if let Some(container) = &args.container_image {
         let requirement = if container.contains("Dockerfile") {
             let image_id = if let Some(tag) = &args.container_tag {
                 tag
@@ -140,18 +274,60 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    // Only run if not prohibited
-    if !args.no_run {
-        // Execute command
-        if inputs.is_empty() && outputs.is_empty() {
-            if run_command(&cwl, None).is_err() {
-                return Err(format!("Could not execute command: `{}`!", args.command.join(" ")).into());
+    // Handle resource requirements
+    if let Some(resources) = resources {
+        cwl.requirements.get_or_insert_with(Vec::new).push(Requirement::ResourceRequirement(resources));
+    }
+
+    // Secrets given on the command line end up in the defaults of the tool, so they are checked before running
+    let scanner = SecretScanner::load(&cwd)?;
+    if !args.redact_secrets {
+        check_secrets("Command line", &scanner.scan(&serde_yaml::to_value(&cwl)?), args.allow_secrets, true)?;
+    }
+    let redact = |yaml: String| -> Result<String, Box<dyn Error>> {
+        if !args.redact_secrets {
+            return Ok(yaml);
+        }
+        let mut document: Value = serde_yaml::from_str(&yaml)?;
+        let findings = scanner.redact(&mut document);
+        if findings.is_empty() {
+            return Ok(yaml);
+        }
+        info!("🔒 Replaced {} possible secrets with REDACTED", findings.len());
+        Ok(serde_yaml::to_string(&document)?)
+    };
+
+    // Only run if not prohibited, tools generated from help texts have no example values to run with
+    if !args.no_run && !args.from_help {
+        // Use isolated environment of project if requested
+        let mut previous_vars = None;
+        if args.project_env {
+            if let Some(environment) = detect_environment(&command, &cwd) {
+                environment.prepare()?;
+                info!("🔒 Running command inside project environment {:?}", environment);
+                previous_vars = Some(environment.activate());
+            } else {
+                warn!("No venv, requirements.txt or renv.lock found, running command with the current environment!");
             }
+        }
+
+        // Execute command
+        let result = if notebook.is_some() {
+            //parameters are not passed, so the notebook runs with its own values
+            let mut probe = cwl.clone();
+            probe.arguments = None;
+            run_command(&probe, None).map(|_| ()).map_err(|_| format!("Could not execute notebook using `{}`!", command.join(" ")).into())
+        } else if inputs.is_empty() && outputs.is_empty() {
+            run_command(&cwl, None).map(|_| ()).map_err(|_| format!("Could not execute command: `{}`!", command.join(" ")).into())
         } else {
             let path = get_qualified_filename(&cwl.base_command, args.name.clone());
             let path_buf = PathBuf::from(path.clone());
-            run_commandlinetool(&mut cwl, None, Some(&path_buf), None)?;
+            run_commandlinetool(&mut cwl, None, Some(&path_buf), None).map(|_| ())
+        };
+        if let Some(previous_vars) = previous_vars {
+            deactivate(previous_vars);
         }
+        result?;
 
         // Check files that changed
         let files = get_modified_files(&repo);
@@ -181,6 +357,13 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
         if outputs.is_empty() {
             cwl = cwl.with_outputs(parser::get_outputs(files));
         }
+    } else if args.from_help {
+        if outputs.is_empty() {
+            warn!("Outputs can not be determined from the help text, add them using `-o`!");
+        }
+    } else if let Some(notebook) = notebook.as_ref().filter(|_| outputs.is_empty()) {
+        warn!("User requested no run, only the executed notebook is added as output!");
+        cwl = cwl.with_outputs(parser::get_outputs(vec![notebook.get_executed_name()]));
     } else {
         warn!("User requested no run, could not determine outputs!");
     }
@@ -190,24 +373,39 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
     // Generate YAML
     if !args.is_raw {
         let path = get_qualified_filename(&cwl.base_command, args.name.clone());
-        let mut yaml = cwl.save(&path);
+        let mut yaml = redact(cwl.save(&path))?;
 
         //format
         yaml = format_cwl(&yaml)?;
 
+        if args.interactive && !confirm_tool(&path, &yaml)? {
+            warn!("Tool {} was not saved!", path);
+            return Ok(());
+        }
+
+        //write generated Dockerfile, this is done after execution to not detect it as output
+        if let Some((dockerfile_path, contents)) = &dockerfile {
+            create_and_write_file(dockerfile_path, contents)?;
+            info!("🐳 Created Dockerfile {}", dockerfile_path.green().bold());
+            if !args.no_commit {
+                stage_file(&repo, dockerfile_path)?;
+            }
+        }
+
         match create_and_write_file(path.as_str(), yaml.as_str()) {
             Ok(_) => {
                 info!("\n📄 Created CWL file {}", path.green().bold());
                 if !args.no_commit {
                     stage_file(&repo, path.as_str()).unwrap();
-                    commit(&repo, format!("Execution of `{}`", args.command.join(" ").as_str()).as_str()).unwrap();
+                    let command = if args.redact_secrets { scanner.redact_command(&command) } else { command };
+                    commit(&repo, format!("Execution of `{}`", command.join(" ").as_str()).as_str()).unwrap();
                 }
                 Ok(())
             }
             Err(e) => Err(Box::new(e)),
         }
     } else {
-        let mut yaml = serde_yaml::to_string(&cwl)?;
+        let mut yaml = redact(serde_yaml::to_string(&cwl)?)?;
         yaml = format_cwl(&yaml)?;
         highlight_cwl(yaml.as_str());
 
@@ -215,7 +413,33 @@ pub fn create_tool(args: &CreateToolArgs) -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// Runs the command with `--help` and returns what it printed, scripts using argparse or click list all of their
+/// options there
+fn get_help_text(command: &[String], project_env: bool) -> Result<String, Box<dyn Error>> {
+    let mut previous_vars = None;
+    if project_env {
+        if let Some(environment) = detect_environment(command, &env::current_dir()?) {
+            environment.prepare()?;
+            previous_vars = Some(environment.activate());
+        }
+    }
+    let output = SystemCommand::new(&command[0]).args(&command[1..]).arg("--help").output();
+    if let Some(previous_vars) = previous_vars {
+        deactivate(previous_vars);
+    }
+    let output = output.map_err(|e| format!("❌ Could not run `{} --help`: {}", command.join(" "), e))?;
+    let help = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    let help = String::from_utf8_lossy(&help).into_owned();
+    if !output.status.success() || help.trim().is_empty() {
+        return Err(format!("❌ `{} --help` did not print a help text", command.join(" ")).into());
+    }
+    Ok(help)
+}
+
 pub fn list_tools(args: &ListToolArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(tool) = &args.history {
+        return print_history(tool);
+    }
     // Print the current working directory
     let cwd = env::current_dir()?;
     info!("📂 Scanning for tools in: {}", cwd.to_str().unwrap_or("Invalid UTF-8").blue().bold());
@@ -289,6 +513,173 @@ pub fn list_tools(args: &ListToolArgs) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+pub fn capture_environment(args: &CaptureEnvArgs) -> Result<(), Box<dyn Error>> {
+    let tool_name = args.tool_name.trim_end_matches(".cwl");
+    let path = PathBuf::from(resolve_filename(tool_name));
+    if !path.exists() {
+        return Err(format!("❌ Tool {:?} does not exist.", path).into());
+    }
+
+    let environment = export_conda_environment(tool_name)?;
+    let packages = get_software_packages(&environment)?;
+    let environment_path = path.with_file_name("environment.yml");
+    create_and_write_file_forced(&environment_path, &environment)?;
+    info!("🐍 Exported conda environment with {} packages to {:?}", packages.len(), environment_path);
+
+    //the tool is edited as YAML to keep annotations which are not part of the CommandLineTool struct
+    let mut yaml: Value = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+    set_hint(&mut yaml, Requirement::SoftwareRequirement(SoftwareRequirement { packages }))?;
+    set_hint(
+        &mut yaml,
+        Requirement::CondaEnvironment(CondaEnvironment {
+            env_file: "environment.yml".to_string(),
+        }),
+    )?;
+    create_and_write_file_forced(&path, &format_cwl(&serde_yaml::to_string(&yaml)?)?)?;
+    annotate(&path.to_string_lossy(), "$namespaces", Some("s4n"), Some(S4N_NAMESPACE))?;
+    info!("📄 Added SoftwareRequirement hint to {}", path.to_string_lossy().green().bold());
+
+    if !args.no_commit {
+        let repo = Repository::open(env::current_dir()?)?;
+        stage_file(&repo, &environment_path.to_string_lossy())?;
+        stage_file(&repo, &path.to_string_lossy())?;
+        commit(&repo, &format!("Captured conda environment of `{tool_name}`"))?;
+    }
+    Ok(())
+}
+
+pub fn build_tool_image(args: &BuildToolArgs) -> Result<(), Box<dyn Error>> {
+    let tool_name = args.tool_name.trim_end_matches(".cwl");
+    let path = PathBuf::from(resolve_filename(tool_name));
+    if !path.exists() {
+        return Err(format!("❌ Tool {:?} does not exist.", path).into());
+    }
+    let tool = load_tool(&path)?;
+    let mut build = ImageBuild::from_tool(&tool, &path)?.ok_or(format!("❌ Tool {tool_name} does not reference a Dockerfile"))?;
+    build.platform = args.platform.clone();
+
+    //images without a variant for the platform fail with cryptic errors like `exec format error` when run
+    let native = get_native_platform();
+    let platform = args.platform.as_deref().unwrap_or(&native);
+    if platform != native {
+        info!("🐳 Building image for {platform} using emulation");
+    }
+    for image in build.get_base_images() {
+        if let Some(platforms) = get_image_platforms(&image).filter(|p| !p.is_empty() && !p.iter().any(|p| p == platform)) {
+            warn!("Image {image} does not provide {platform}, only {}. Use --platform to select one of them", platforms.join(", "));
+        }
+    }
+
+    let cwd = env::current_dir()?;
+    let project = cwd.file_name().unwrap_or_default().to_string_lossy();
+    let tag = get_image_tag(&project, &path.file_stem().unwrap_or_default().to_string_lossy(), &build.get_hash());
+    if build.build(&tag, args.force)? {
+        info!("🐳 Built image {}", tag.green().bold());
+    } else {
+        info!("🐳 Image {} is up to date", tag.green().bold());
+    }
+
+    //runners only build the Dockerfile if no image named like dockerImageId exists, so pinning the tag reuses the image
+    let mut yaml: Value = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+    if !set_docker_image_id(&mut yaml, &tag) {
+        return Ok(());
+    }
+    create_and_write_file_forced(&path, &format_cwl(&serde_yaml::to_string(&yaml)?)?)?;
+    info!("📄 Pinned image tag in {}", path.to_string_lossy().green().bold());
+    if !args.no_commit {
+        let repo = Repository::open(cwd)?;
+        stage_file(&repo, &path.to_string_lossy())?;
+        commit(&repo, &format!("Pinned image of `{tool_name}` to {tag}"))?;
+    }
+    Ok(())
+}
+
+/// Sets the `dockerImageId` of Dockerfile based DockerRequirements in requirements and hints, returns whether it changed
+fn set_docker_image_id(document: &mut Value, tag: &str) -> bool {
+    let mut changed = false;
+    for key in ["requirements", "hints"] {
+        let requirement = match document.get_mut(key) {
+            Some(Value::Sequence(items)) => items.iter_mut().find(|i| i.get("class").and_then(Value::as_str) == Some("DockerRequirement")),
+            Some(Value::Mapping(items)) => items.get_mut("DockerRequirement"),
+            _ => None,
+        };
+        if let Some(Value::Mapping(requirement)) = requirement {
+            if requirement.contains_key("dockerFile") && requirement.get("dockerImageId").and_then(Value::as_str) != Some(tag) {
+                requirement.insert(Value::String("dockerImageId".to_string()), Value::String(tag.to_string()));
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+pub fn migrate_tools(args: &MigrateToolArgs) -> Result<(), Box<dyn Error>> {
+    let paths = match &args.tool_name {
+        Some(name) => vec![PathBuf::from(resolve_filename(name.trim_end_matches(".cwl")))],
+        None => find_cwl_files(get_workflows_folder())
+            .into_iter()
+            .filter(|path| load_tool(path).is_ok())
+            .collect(),
+    };
+
+    let mut migrated = vec![];
+    for path in paths {
+        let mut tool = load_tool(&path)?;
+        let added = parser::stage_binary_files(&mut tool, path.parent().unwrap_or(Path::new(".")));
+        if added.is_empty() {
+            continue;
+        }
+        //the tool is edited as YAML to keep annotations which are not part of the CommandLineTool struct
+        let mut yaml: Value = serde_yaml::from_str(&fs::read_to_string(&path)?)?;
+        let mapping = yaml.as_mapping_mut().ok_or("❌ CWL document is not a mapping")?;
+        mapping.insert(Value::from("inputs"), serde_yaml::to_value(&tool.inputs)?);
+        for (key, requirements) in [("requirements", &tool.requirements), ("hints", &tool.hints)] {
+            if let Some(requirements) = requirements {
+                mapping.insert(Value::from(key), serde_yaml::to_value(requirements)?);
+            }
+        }
+        create_and_write_file_forced(&path, &format_cwl(&serde_yaml::to_string(&yaml)?)?)?;
+        info!("📄 Staged binary files of {} using the inputs {}", path.to_string_lossy().green().bold(), added.join(", "));
+        migrated.push(path);
+    }
+
+    if migrated.is_empty() {
+        info!("✔️  No tools need to be migrated");
+    } else if !args.no_commit {
+        let repo = Repository::open(env::current_dir()?)?;
+        for path in &migrated {
+            stage_file(&repo, &path.to_string_lossy())?;
+        }
+        commit(&repo, "Migrated binary `$include` entries to File inputs")?;
+    }
+    Ok(())
+}
+
+/// Adds a hint to a CWL document given as YAML, replacing any existing hint of the same class
+fn set_hint(document: &mut Value, hint: Requirement) -> Result<(), Box<dyn Error>> {
+    let mut hint = serde_yaml::to_value(hint)?;
+    let class = hint.get("class").cloned().unwrap_or_default();
+    let mapping = document.as_mapping_mut().ok_or("❌ CWL document is not a mapping")?;
+    let hints = mapping
+        .entry(Value::String("hints".to_string()))
+        .or_insert_with(|| Value::Sequence(vec![]));
+
+    match hints {
+        Value::Sequence(items) => {
+            items.retain(|item| item.get("class") != Some(&class));
+            items.push(hint);
+        }
+        Value::Mapping(items) => {
+            if let Some(hint) = hint.as_mapping_mut() {
+                hint.remove("class");
+            }
+            items.insert(class, hint);
+        }
+        _ => *hints = Value::Sequence(vec![hint]),
+    }
+    Ok(())
+}
+
 pub fn remove_tool(args: &RemoveToolArgs) -> Result<(), Box<dyn std::error::Error>> {
     let cwd = env::current_dir()?;
     let repo = Repository::open(cwd)?;
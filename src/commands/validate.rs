@@ -0,0 +1,553 @@
+use crate::execution::util::preprocess_cwl;
+use clap::Args;
+use colored::Colorize;
+use cwl::{
+    clt::CommandLineTool,
+    error::CWLError,
+    inputs::WorkflowStepInput,
+    load_tool,
+    location::find_line,
+    parse_yaml,
+    strict::{find_unknown_fields, parse_mode, set_parse_mode, ParseMode, STEP_FIELDS, TOOL_FIELDS, WORKFLOW_FIELDS},
+    types::CWLType,
+    version::upgrade_to_v12,
+    wf::{LinkMerge, PickValue, ScatterMethod, Source, Workflow},
+};
+use log::{error, info, warn};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    #[arg(help = "CWL File to validate")]
+    pub file: PathBuf,
+    #[arg(long = "strict", help = "Also reports unknown fields of inputs, outputs, bindings and step inputs and of the tools used by steps")]
+    pub strict: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single finding of the validation, located at a line of the validated file if possible
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{line}: {}", self.message),
+            None => write!(f, " {}", self.message),
+        }
+    }
+}
+
+pub fn validate(args: &ValidateArgs) -> Result<(), Box<dyn Error>> {
+    set_parse_mode(if args.strict { ParseMode::Strict } else { ParseMode::Permissive });
+    let diagnostics = validate_file(&args.file);
+    set_parse_mode(ParseMode::Permissive);
+    let diagnostics = diagnostics?;
+    let file = args.file.to_string_lossy();
+    for diagnostic in &diagnostics {
+        match diagnostic.severity {
+            Severity::Error => error!("{file}:{diagnostic}"),
+            Severity::Warning => warn!("{file}:{diagnostic}"),
+        }
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(format!("❌ {file} is not valid, found {errors} error(s)").into());
+    }
+    info!("✔️  {} is valid!", file.green().bold());
+    Ok(())
+}
+
+/// Checks a CommandLineTool or Workflow without executing it. Structural problems like unknown or missing fields are
+/// reported first, connections and types of workflow steps are only checked if the document could be loaded. In strict
+/// [`ParseMode`] unknown fields are searched in the whole document and in the tools used by steps.
+pub fn validate_file<P: AsRef<Path>>(path: P) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| format!("❌ Could not read {:?}: {}", path, e))?;
+    let mut validator = Validator {
+        contents: &contents,
+        diagnostics: vec![],
+    };
+
+    let mut document = match parse_yaml(&preprocess_cwl(&contents, path)) {
+        Ok(document) => document,
+        Err(e) => {
+            let line = if let CWLError::Parse { line, .. } = &e { *line } else { None };
+            validator.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line,
+                message: format!("invalid YAML: {e}"),
+            });
+            return Ok(validator.diagnostics);
+        }
+    };
+    if let Err(e) = upgrade_to_v12(&mut document) {
+        validator.error(validator.locate(&["cwlVersion"]), e.to_string());
+        return Ok(validator.diagnostics);
+    }
+    let Some(mapping) = document.as_mapping() else {
+        validator.error(None, "document needs to be a mapping".to_string());
+        return Ok(validator.diagnostics);
+    };
+
+    match mapping.get("class").and_then(|c| c.as_str()) {
+        Some("CommandLineTool") => {
+            validator.check_fields(mapping, &TOOL_FIELDS, &["class", "cwlVersion", "inputs", "outputs"], "CommandLineTool", &[]);
+            if !mapping.contains_key("baseCommand") && !mapping.contains_key("arguments") {
+                validator.error(None, "CommandLineTool needs a `baseCommand` or `arguments`".to_string());
+            }
+            if validator.is_valid() {
+                if let Err(e) = serde_yaml::from_value::<CommandLineTool>(document.clone()) {
+                    validator.error(None, format!("could not load CommandLineTool: {e}"));
+                }
+            }
+        }
+        Some("Workflow") => {
+            validator.check_fields(mapping, &WORKFLOW_FIELDS, &["class", "cwlVersion", "inputs", "outputs", "steps"], "Workflow", &[]);
+            validator.check_steps(mapping);
+            if validator.is_valid() {
+                match serde_yaml::from_value::<Workflow>(document.clone()) {
+                    Ok(workflow) => validator.check_workflow(&workflow, path.parent().unwrap_or(Path::new("."))),
+                    Err(e) => validator.error(None, format!("could not load Workflow: {e}")),
+                }
+            }
+        }
+        Some(class) => validator.error(validator.locate(&["class"]), format!("unsupported class `{class}`")),
+        None => validator.error(None, "missing required field `class`".to_string()),
+    }
+    if parse_mode() == ParseMode::Strict {
+        for unknown in find_unknown_fields(&document) {
+            let message = format!("unknown field `{}` in {}", unknown.field, unknown.context);
+            if !validator.diagnostics.iter().any(|d| d.message == message) {
+                let path = unknown.path.iter().map(String::as_str).collect::<Vec<_>>();
+                validator.error(validator.locate(&path), message);
+            }
+        }
+    }
+    Ok(validator.diagnostics)
+}
+
+struct Validator<'a> {
+    contents: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, line: Option<usize>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            line,
+            message,
+        });
+    }
+
+    fn warning(&mut self, line: Option<usize>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            line,
+            message,
+        });
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// Returns the line of the node at the given path of the validated file, see [`find_line`]
+    fn locate(&self, path: &[&str]) -> Option<usize> {
+        find_line(self.contents, path)
+    }
+
+    fn check_fields(&mut self, mapping: &Mapping, allowed: &[&str], required: &[&str], context: &str, path: &[&str]) {
+        for key in mapping.keys().filter_map(|k| k.as_str()) {
+            //namespaced extensions and directives like `$namespaces` are allowed everywhere
+            if !allowed.contains(&key) && !key.contains(':') && !key.starts_with('$') {
+                self.error(self.locate(&[path, &[key]].concat()), format!("unknown field `{key}` in {context}"));
+            }
+        }
+        //missing fields of the document itself have no line
+        let line = (!path.is_empty()).then(|| self.locate(path)).flatten();
+        for key in required {
+            if !mapping.contains_key(*key) {
+                self.error(line, format!("missing required field `{key}` in {context}"));
+            }
+        }
+    }
+
+    fn check_steps(&mut self, mapping: &Mapping) {
+        let steps: Vec<(String, &Mapping)> = match mapping.get("steps") {
+            Some(Value::Sequence(steps)) => steps
+                .iter()
+                .filter_map(|s| s.as_mapping())
+                .map(|s| (s.get("id").and_then(|i| i.as_str()).unwrap_or_default().to_string(), s))
+                .collect(),
+            Some(Value::Mapping(steps)) => steps
+                .iter()
+                .filter_map(|(id, s)| Some((id.as_str()?.to_string(), s.as_mapping()?)))
+                .collect(),
+            _ => return,
+        };
+        for (id, step) in steps {
+            let mut required = vec!["run", "in", "out"];
+            if mapping.get("steps").is_some_and(|s| s.is_sequence()) {
+                required.push("id");
+            }
+            self.check_fields(step, &STEP_FIELDS, &required, &format!("step `{id}`"), &["steps", &id]);
+        }
+    }
+
+    fn check_workflow(&mut self, workflow: &Workflow, workflow_folder: &Path) {
+        if let Err(e) = workflow.sort_steps() {
+            self.error(None, e);
+        }
+
+        //load tools of all steps to know about their inputs and outputs
+        let mut tools = HashMap::new();
+        for step in &workflow.steps {
+            match load_tool(workflow_folder.join(&step.run)) {
+                Ok(tool) => {
+                    tools.insert(step.id.clone(), tool);
+                }
+                Err(e) => self.error(
+                    self.locate(&["steps", &step.id, "run"]),
+                    format!("step `{}`: {}", step.id, e.to_string().trim_start_matches("❌ ")),
+                ),
+            }
+        }
+
+        for step in &workflow.steps {
+            let Some(tool) = tools.get(&step.id) else {
+                continue;
+            };
+            let scatter_inputs = step.get_scatter_inputs();
+            for (i, output) in step.out.iter().enumerate() {
+                if !tool.outputs.iter().any(|o| o.id == *output) {
+                    self.error(self.locate(&["steps", &step.id, "out", &i.to_string()]), format!("step `{}` has no output `{output}`", step.id));
+                }
+            }
+
+            for (id, input) in &step.in_ {
+                let line = self.locate(&["steps", &step.id, "in", id]);
+                let source = match input {
+                    WorkflowStepInput::String(source) => Some(Source::from(source.as_str())),
+                    WorkflowStepInput::Parameter(parameter) => parameter.source.clone(),
+                };
+                let Some(parameter) = tool.inputs.iter().find(|i| i.id == *id) else {
+                    self.error(line, format!("step `{}` has no input `{id}`", step.id));
+                    //sources of unknown inputs still need to exist
+                    for source_id in source.iter().flat_map(|s| s.ids()) {
+                        self.resolve_source(workflow, &tools, source_id, line);
+                    }
+                    continue;
+                };
+                let (link_merge, pick_value, has_value_from) = match input {
+                    WorkflowStepInput::String(_) => (None, None, false),
+                    WorkflowStepInput::Parameter(parameter) => (parameter.link_merge, parameter.pick_value, parameter.value_from.is_some()),
+                };
+                let Some(source) = source else {
+                    continue;
+                };
+                //the type of values selected with valueFrom is only known at runtime
+                if has_value_from {
+                    for source_id in source.ids() {
+                        self.resolve_source(workflow, &tools, source_id, line);
+                    }
+                    continue;
+                }
+                //scattered inputs receive the items of an array
+                let target_type = if scatter_inputs.contains(id) {
+                    CWLType::Array(Box::new(parameter.type_.clone()))
+                } else {
+                    parameter.type_.clone()
+                };
+                let target = format!("`{}/{id}`", step.id);
+                self.check_source(workflow, &tools, (&source, link_merge, pick_value), &target_type, &target, line);
+            }
+
+            for input in &tool.inputs {
+                if !step.in_.contains_key(&input.id) && input.default.is_none() && input.type_ != CWLType::Null {
+                    self.warning(
+                        self.locate(&["steps", &step.id]),
+                        format!("input `{}` of step `{}` is not connected and has no default", input.id, step.id),
+                    );
+                }
+            }
+        }
+
+        for output in &workflow.outputs {
+            let target = format!("output `{}`", output.id);
+            let connection = (&output.output_source, output.link_merge, output.pick_value);
+            let line = self.locate(&["outputs", &output.id, "outputSource"]).or_else(|| self.locate(&["outputs", &output.id]));
+            self.check_source(workflow, &tools, connection, &output.type_, &target, line);
+        }
+    }
+
+    /// Checks that all sources exist and their values fit the type of the input or output they are connected to, after
+    /// merging them as defined by `linkMerge` and removing `null` values using `pickValue`
+    fn check_source(
+        &mut self,
+        workflow: &Workflow,
+        tools: &HashMap<String, CommandLineTool>,
+        (source, link_merge, pick_value): (&Source, Option<LinkMerge>, Option<PickValue>),
+        target_type: &CWLType,
+        target: &str,
+        line: Option<usize>,
+    ) {
+        let source_types = source.ids().into_iter().map(|id| self.resolve_source(workflow, tools, id, line)).collect::<Vec<_>>();
+        let Some(source_types) = source_types.into_iter().collect::<Option<Vec<_>>>() else {
+            return;
+        };
+        let merged = source.is_multiple() || link_merge.is_some();
+        if !is_merge_compatible(&source_types, target_type, merged.then(|| link_merge.unwrap_or_default()), pick_value) {
+            let source_type = source_types.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+            self.error(
+                line,
+                format!("type mismatch: `{source}` of type {source_type} is connected to {target} of type {target_type}"),
+            );
+        }
+    }
+
+    /// Returns the type of a workflow input or step output referenced by a `source`, reports dangling connections
+    fn resolve_source(
+        &mut self,
+        workflow: &Workflow,
+        tools: &HashMap<String, CommandLineTool>,
+        source: &str,
+        line: Option<usize>,
+    ) -> Option<CWLType> {
+        match source.split_once('/') {
+            Some((step_id, output_id)) => {
+                let Some(step) = workflow.get_step(step_id) else {
+                    self.error(line, format!("`{source}` references step `{step_id}` which does not exist"));
+                    return None;
+                };
+                if !step.out.iter().any(|o| o == output_id) {
+                    self.error(line, format!("`{source}` references output `{output_id}` which is not listed in `out` of step `{step_id}`"));
+                    return None;
+                }
+                let output = tools.get(step_id)?.outputs.iter().find(|o| o.id == output_id)?;
+                let mut type_ = match &output.type_ {
+                    CWLType::Stdout | CWLType::Stderr => CWLType::File,
+                    type_ => type_.clone(),
+                };
+                //outputs of scattered steps are gathered into arrays, nested ones for each scattered input
+                let depth = match (step.get_scatter_inputs().len(), step.scatter_method.unwrap_or_default()) {
+                    (0, _) => 0,
+                    (n, ScatterMethod::NestedCrossProduct) => n,
+                    _ => 1,
+                };
+                for _ in 0..depth {
+                    type_ = CWLType::Array(Box::new(type_));
+                }
+                Some(type_)
+            }
+            None => match workflow.inputs.iter().find(|i| i.id == source) {
+                Some(input) => Some(input.type_.clone()),
+                None => {
+                    self.error(line, format!("`{source}` references workflow input which does not exist"));
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Whether a value of type `source` can be passed to a parameter of type `target`
+pub fn is_compatible(source: &CWLType, target: &CWLType) -> bool {
+    match (source, target) {
+        (CWLType::Any, _) | (_, CWLType::Any) => true,
+        (CWLType::Array(source), CWLType::Array(target)) => is_compatible(source, target),
+        //numbers can be widened
+        (CWLType::Int, CWLType::Long | CWLType::Float | CWLType::Double) => true,
+        (CWLType::Long | CWLType::Float, CWLType::Double) => true,
+        (CWLType::Stdout | CWLType::Stderr, CWLType::File) => true,
+        (source, target) => source == target,
+    }
+}
+
+/// Whether the values of sources with the given types fit the target type. Values of multiple sources are merged into
+/// an array using `link_merge`, `pick_value` selects single values or removes `null` values.
+pub fn is_merge_compatible(source_types: &[CWLType], target: &CWLType, link_merge: Option<LinkMerge>, pick_value: Option<PickValue>) -> bool {
+    let item_of = |type_: &CWLType| match type_ {
+        CWLType::Array(item) => *item.clone(),
+        type_ => type_.clone(),
+    };
+    let (items, is_array) = match link_merge {
+        Some(LinkMerge::MergeNested) => (source_types.to_vec(), true),
+        Some(LinkMerge::MergeFlattened) => (source_types.iter().map(item_of).collect(), true),
+        None => (source_types.to_vec(), false),
+    };
+    let (items, is_array) = match pick_value {
+        Some(PickValue::FirstNonNull | PickValue::TheOnlyNonNull) if is_array => (items, false),
+        Some(PickValue::FirstNonNull | PickValue::TheOnlyNonNull) => (items.iter().map(item_of).collect(), false),
+        _ => (items, is_array),
+    };
+    items.into_iter().all(|item| match is_array {
+        true => is_compatible(&CWLType::Array(Box::new(item)), target),
+        false => is_compatible(&item, target),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_is_compatible() {
+        assert!(is_compatible(&CWLType::File, &CWLType::File));
+        assert!(is_compatible(&CWLType::Int, &CWLType::Double));
+        assert!(is_compatible(&CWLType::Array(Box::new(CWLType::File)), &CWLType::Array(Box::new(CWLType::Any))));
+        assert!(!is_compatible(&CWLType::File, &CWLType::String));
+        assert!(!is_compatible(&CWLType::Array(Box::new(CWLType::File)), &CWLType::File));
+    }
+
+    #[test]
+    fn test_is_merge_compatible() {
+        let files = CWLType::Array(Box::new(CWLType::File));
+        assert!(is_merge_compatible(&[CWLType::File, CWLType::File], &files, Some(LinkMerge::MergeNested), None));
+        assert!(!is_merge_compatible(&[files.clone(), CWLType::File], &files, Some(LinkMerge::MergeNested), None));
+        assert!(is_merge_compatible(&[files.clone(), CWLType::File], &files, Some(LinkMerge::MergeFlattened), None));
+        assert!(is_merge_compatible(&[CWLType::File, CWLType::File], &CWLType::File, Some(LinkMerge::MergeNested), Some(PickValue::FirstNonNull)));
+        assert!(is_merge_compatible(std::slice::from_ref(&files), &CWLType::File, None, Some(PickValue::TheOnlyNonNull)));
+        assert!(is_merge_compatible(std::slice::from_ref(&files), &files, None, Some(PickValue::AllNonNull)));
+        assert!(!is_merge_compatible(&[CWLType::File, CWLType::String], &files, Some(LinkMerge::MergeNested), None));
+    }
+
+    #[test]
+    fn test_validate_unknown_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tool.cwl");
+        fs::write(&path, "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs: []\nbasecommand: ls\n").unwrap();
+
+        let diagnostics = validate_file(&path).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert!(diagnostics[0].message.contains("unknown field `basecommand`"));
+        assert!(diagnostics[1].message.contains("missing required field `outputs`"));
+    }
+
+    #[test]
+    fn test_validate_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tool.cwl");
+        fs::write(
+            &path,
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs:\n  message:\n    type: string\n    inputBinding: {positon: 1}\noutputs: []\n",
+        )
+        .unwrap();
+        assert!(validate_file(&path).unwrap().is_empty());
+
+        set_parse_mode(ParseMode::Strict);
+        let diagnostics = validate_file(&path);
+        set_parse_mode(ParseMode::Permissive);
+        let diagnostics = diagnostics.unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(7));
+        assert_eq!(diagnostics[0].message, "unknown field `positon` in inputBinding of input `message`");
+    }
+
+    #[test]
+    fn test_validate_invalid_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tool.cwl");
+        fs::write(&path, "class: CommandLineTool\ninputs: [\n").unwrap();
+
+        let diagnostics = validate_file(&path).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].line.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn test_validate_workflow() {
+        assert!(validate_file("tests/test_data/scatter_wf.cwl").unwrap().is_empty());
+
+        let diagnostics = validate_file("tests/test_data/mkdir_wf.cwl").unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(13));
+        assert!(diagnostics[0].message.contains("`mkdir/out` of type Directory"));
+    }
+
+    #[test]
+    fn test_validate_dangling_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy("tests/test_data/echo.cwl", dir.path().join("echo.cwl")).unwrap();
+        let path = dir.path().join("wf.cwl");
+        fs::write(
+            &path,
+            r"class: Workflow
+cwlVersion: v1.2
+inputs:
+  file: File
+outputs:
+  out:
+    type: File
+    outputSource: echo/missing
+steps:
+  echo:
+    run: echo.cwl
+    in:
+      test: files
+    out: [results]
+",
+        )
+        .unwrap();
+
+        let diagnostics = validate_file(&path).unwrap();
+        let messages = diagnostics.iter().map(|d| d.message.as_str()).collect::<Vec<_>>();
+        assert!(messages.contains(&"`files` references workflow input which does not exist"));
+        assert!(messages.contains(&"`echo/missing` references output `missing` which is not listed in `out` of step `echo`"));
+        assert_eq!(diagnostics.iter().find(|d| d.message.contains("echo/missing")).unwrap().line, Some(8));
+    }
+
+    #[test]
+    fn test_validate_unknown_step_input() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::copy("tests/test_data/echo.cwl", dir.path().join("echo.cwl")).unwrap();
+        let path = dir.path().join("wf.cwl");
+        fs::write(
+            &path,
+            r"class: Workflow
+cwlVersion: v1.2
+# g: nope is not connected, see below
+inputs:
+  file: File
+outputs: []
+steps:
+- id: s1
+  run: echo.cwl
+  in:
+    test: file
+    g: nope
+  out: []
+",
+        )
+        .unwrap();
+
+        //lines are those of the nodes, not of the first text matching them
+        let diagnostics = validate_file(&path).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "step `s1` has no input `g`");
+        assert_eq!(diagnostics[0].line, Some(12));
+        assert_eq!(diagnostics[1].message, "`nope` references workflow input which does not exist");
+        assert_eq!(diagnostics[1].line, Some(12));
+    }
+}
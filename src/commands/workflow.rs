@@ -1,33 +1,59 @@
 use crate::{
-    cwl::Connectable,
-    io::{create_and_write_file, get_workflows_folder},
-    repo::{commit, stage_file},
+    commands::{annotate::annotate_s4n_namespace, validate::is_compatible},
+    cwl::{parse_connection, resolve_filename, Connectable},
+    diff::diff_workflow_files,
+    execution::runs::{get_run_key, load_runs, WorkflowRun},
+    io::{create_and_write_file, create_and_write_file_forced, get_workflows_folder},
+    job::create_job_template,
+    repo::{commit, get_file_history, get_repository_path, stage_file},
+    secrets::{check_secrets, SecretScanner},
+    print_list,
+    visualize::{render_workflow, Renderer},
 };
+use chrono::{DateTime, Local};
 use clap::{Args, Subcommand};
 use colored::Colorize;
 use cwl::{
-    format::format_cwl,
+    clt::CommandLineTool,
+    format::{format_cwl, has_anchors},
     inputs::WorkflowStepInput,
+    packed::pack_workflow,
     wf::Workflow,
     {load_tool, load_workflow},
 };
+use dialoguer::Select;
+use flate2::{write::GzEncoder, Compression};
 use git2::Repository;
-use log::{error, info};
+use log::{error, info, warn};
 use prettytable::{row, Cell, Row, Table};
 use serde_yaml::Value;
 use std::path::PathBuf;
-use std::{env, error::Error, fs, io::Write, path::Path, vec};
+use std::{
+    collections::HashSet,
+    env,
+    error::Error,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    vec,
+};
 use walkdir::WalkDir;
 
 pub fn handle_workflow_commands(command: &WorkflowCommands) -> Result<(), Box<dyn Error>> {
     match command {
         WorkflowCommands::Create(args) => create_workflow(args),
+        WorkflowCommands::Compose(args) => compose_workflow(args),
         WorkflowCommands::Connect(args) => connect_workflow_nodes(args),
         WorkflowCommands::Disconnect(args) => disconnect_workflow_nodes(args),
         WorkflowCommands::Save(args) => save_workflow(args),
         WorkflowCommands::Status(args) => get_workflow_status(args),
         WorkflowCommands::List(args) => list_workflows(args),
         WorkflowCommands::Remove(args) => remove_workflow(args),
+        WorkflowCommands::Pack(args) => pack(args),
+        WorkflowCommands::Visualize(args) => visualize(args),
+        WorkflowCommands::Diff(args) => diff(args),
+        WorkflowCommands::Inputs(args) => create_inputs_template(args),
+        WorkflowCommands::Gc(args) => collect_garbage(args),
     }
 }
 
@@ -35,18 +61,30 @@ pub fn handle_workflow_commands(command: &WorkflowCommands) -> Result<(), Box<dy
 pub enum WorkflowCommands {
     #[command(about = "Creates a blank workflow")]
     Create(CreateWorkflowArgs),
+    #[command(about = "Creates a workflow from the given tools and connects them by matching names")]
+    Compose(ComposeWorkflowArgs),
     #[command(about = "Connects a workflow node")]
     Connect(ConnectWorkflowArgs),
     #[command(about = "Disconnects a workflow node")]
     Disconnect(ConnectWorkflowArgs),
     #[command(about = "Saves a workflow")]
-    Save(CreateWorkflowArgs),
-    #[command(about = "Shows socket status of workflow")]
-    Status(CreateWorkflowArgs),
+    Save(SaveWorkflowArgs),
+    #[command(about = "Shows socket status of a workflow or the execution state of all workflows")]
+    Status(StatusWorkflowArgs),
     #[command(about = "List all workflows", visible_alias = "ls")]
     List(ListWorkflowArgs),
     #[command(about = "Remove a workflow", visible_alias = "rm")]
     Remove(RemoveWorkflowArgs),
+    #[command(about = "Packs a workflow and all of its steps into a single file")]
+    Pack(PackWorkflowArgs),
+    #[command(about = "Renders the workflow graph as Mermaid, Graphviz DOT or SVG")]
+    Visualize(VisualizeWorkflowArgs),
+    #[command(about = "Compares two versions of a workflow structurally")]
+    Diff(DiffWorkflowArgs),
+    #[command(about = "Creates a job file template for the inputs of a workflow")]
+    Inputs(InputsWorkflowArgs),
+    #[command(about = "Finds files in the workflows folder no tool or workflow uses and deletes or archives them")]
+    Gc(GcWorkflowArgs),
 }
 
 #[derive(Args, Debug)]
@@ -57,10 +95,67 @@ pub struct CreateWorkflowArgs {
     pub force: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct SaveWorkflowArgs {
+    #[arg(help = "Name of the workflow to save")]
+    pub name: String,
+    #[arg(long = "allow-secrets", help = "Saves the workflow even if it contains possible passwords, tokens or credentials")]
+    pub allow_secrets: bool,
+    #[arg(
+        long = "redact-secrets",
+        conflicts_with = "allow_secrets",
+        help = "Replaces possible passwords, tokens or credentials with REDACTED before saving"
+    )]
+    pub redact_secrets: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ComposeWorkflowArgs {
+    #[arg(help = "A name to be used for this workflow")]
+    pub name: String,
+    #[arg(
+        short = 's',
+        long = "steps",
+        value_delimiter = ',',
+        required = true,
+        help = "Tools to add as steps in order of execution, e.g. calculation,plot"
+    )]
+    pub steps: Vec<String>,
+    #[arg(
+        short = 'a',
+        long = "auto-connect",
+        help = "Connects inputs to outputs of previous steps with the same name, remaining inputs and outputs become workflow inputs and outputs"
+    )]
+    pub auto_connect: bool,
+    #[arg(short = 'f', long = "force", help = "Overwrites existing workflow")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct GcWorkflowArgs {
+    #[arg(short = 'd', long = "delete", help = "Deletes orphaned files without asking")]
+    pub delete: bool,
+    #[arg(
+        short = 'a',
+        long = "archive",
+        conflicts_with = "delete",
+        help = "Moves orphaned files into an archive in .s4n/archive without asking"
+    )]
+    pub archive: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StatusWorkflowArgs {
+    #[arg(help = "Name of the workflow, shows the execution state of all workflows of the project if not given")]
+    pub name: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct ListWorkflowArgs {
     #[arg(short = 'a', long = "all", help = "Outputs the tools with inputs and outputs")]
     pub list_all: bool,
+    #[arg(long = "history", value_name = "WORKFLOW", conflicts_with = "list_all", help = "Shows the commits which changed the given workflow")]
+    pub history: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -69,6 +164,44 @@ pub struct RemoveWorkflowArgs {
     pub rm_workflow: Vec<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct PackWorkflowArgs {
+    #[arg(help = "Name of the workflow or path to its CWL file")]
+    pub workflow: String,
+    #[arg(short = 'o', long = "output", help = "File to write the packed workflow to, prints to stdout if not given")]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct VisualizeWorkflowArgs {
+    #[arg(help = "Name of the workflow or path to its CWL file")]
+    pub workflow: String,
+    #[arg(short = 'r', long = "renderer", value_enum, default_value_t = Renderer::Mermaid, help = "Format of the graph")]
+    pub renderer: Renderer,
+    #[arg(short = 'o', long = "output", help = "File to write the graph to, prints to stdout if not given")]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffWorkflowArgs {
+    #[arg(help = "Name of the old workflow or path to its CWL file")]
+    pub old: String,
+    #[arg(help = "Name of the new workflow or path to its CWL file")]
+    pub new: String,
+    #[arg(long = "json", help = "Prints the changes as JSON instead of a tree")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct InputsWorkflowArgs {
+    #[arg(help = "Name of the workflow or path to its CWL file")]
+    pub workflow: String,
+    #[arg(long = "fill-defaults", help = "Uses the defaults of the workflow and its tools as values")]
+    pub fill_defaults: bool,
+    #[arg(short = 'o', long = "output", help = "File to write the job template to, prints to stdout if not given")]
+    pub output: Option<String>,
+}
+
 pub fn create_workflow(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Error>> {
     let wf = Workflow::default();
 
@@ -91,13 +224,82 @@ pub fn create_workflow(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
+pub fn compose_workflow(args: &ComposeWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let filename = format!("{}{}/{}.cwl", get_workflows_folder(), args.name, args.name);
+    if Path::new(&filename).exists() && !args.force {
+        return Err(format!("❌ Workflow {} already exists, use --force to overwrite it", filename).into());
+    }
+
+    let mut workflow = Workflow::default();
+    let mut tools = vec![];
+    for name in &args.steps {
+        let tool = load_tool(resolve_filename(name)).map_err(|e| format!("❌ Could not load tool {}: {}", name, e))?;
+        workflow.add_new_step_if_not_exists(name, &tool);
+        tools.push((name.as_str(), tool));
+    }
+    if args.auto_connect {
+        auto_connect(&mut workflow, &tools)?;
+    }
+
+    let yaml = format_cwl(&serde_yaml::to_string(&workflow)?)?;
+    create_and_write_file_forced(&filename, &yaml).map_err(|e| format!("❌ Could not create workflow {} at {}: {}", args.name, filename, e))?;
+    info!("📄 Composed Workflow {} from {} steps", filename, tools.len());
+    Ok(())
+}
+
+/// Connects each input of a step to the output of the closest previous step having the same name and a compatible
+/// type. All other inputs become workflow inputs and outputs not used by any step become workflow outputs.
+fn auto_connect(workflow: &mut Workflow, tools: &[(&str, CommandLineTool)]) -> Result<(), Box<dyn Error>> {
+    let mut used_outputs = HashSet::new();
+    for (i, (name, tool)) in tools.iter().enumerate() {
+        for input in &tool.inputs {
+            let source = tools[..i].iter().rev().find_map(|(previous, previous_tool)| {
+                previous_tool
+                    .outputs
+                    .iter()
+                    .find(|o| o.id == input.id && is_compatible(&o.type_, &input.type_))
+                    .map(|o| format!("{previous}/{}", o.id))
+            });
+            if let Some(source) = source {
+                if let Some(step) = workflow.steps.iter_mut().find(|s| s.id == *name) {
+                    step.in_.insert(input.id.clone(), WorkflowStepInput::String(source.clone()));
+                }
+                info!("🔗 Connected {} to {}/{}", source, name, input.id);
+                used_outputs.insert(source);
+            } else {
+                workflow.add_input_connection(&input.id, &format!("{name}/{}", input.id))?;
+            }
+        }
+    }
+
+    for (name, tool) in tools {
+        for output in &tool.outputs {
+            let source = format!("{name}/{}", output.id);
+            if used_outputs.contains(&source) {
+                continue;
+            }
+            let id = if workflow.has_output(&output.id) {
+                format!("{name}_{}", output.id)
+            } else {
+                output.id.clone()
+            };
+            workflow.add_output_connection(&source, &id)?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Args, Debug)]
 pub struct ConnectWorkflowArgs {
     #[arg(help = "Name of the workflow name to be altered")]
     pub name: String,
-    #[arg(short = 'f', long = "from", help = "Starting Node: [tool]/[output]")]
+    #[arg(
+        short = 'f',
+        long = "from",
+        help = "Starting Node: [tool]/[output], [tool]/[output]/[field], [tool]/[output][index] or @inputs/[input]"
+    )]
     pub from: String,
-    #[arg(short = 't', long = "to", help = "Ending Node: [tool]/[input]")]
+    #[arg(short = 't', long = "to", help = "Ending Node: [tool]/[input] or @outputs/[output]")]
     pub to: String,
 }
 
@@ -106,24 +308,18 @@ pub fn connect_workflow_nodes(args: &ConnectWorkflowArgs) -> Result<(), Box<dyn
     let filename = format!("{}{}/{}.cwl", get_workflows_folder(), args.name, args.name);
     let mut workflow = load_workflow(&filename)?;
 
-    let from_parts = args.from.split('/').collect::<Vec<_>>();
-    let to_parts = args.to.split('/').collect::<Vec<_>>();
-    if from_parts[0] == "@inputs" {
-        workflow.add_input_connection(from_parts[1], &args.to)?;
-    } else if to_parts[0] == "@outputs" {
-        workflow.add_output_connection(&args.from, to_parts[1])?;
+    let (from, to) = parse_connection(&args.from, &args.to)?;
+    if from.is_inputs() && to.is_outputs() {
+        workflow.add_input_output_connection(&from.slot, &to.slot)?;
+    } else if from.is_inputs() {
+        workflow.add_input_connection(&from.slot, &args.to)?;
+    } else if to.is_outputs() {
+        workflow.add_output_connection(&args.from, &to.slot)?;
     } else {
         workflow.add_step_connection(&args.from, &args.to)?;
     }
 
-    //save workflow
-    let mut yaml = serde_yaml::to_string(&workflow)?;
-    yaml = format_cwl(&yaml)?;
-    let mut file = fs::File::create(&filename)?;
-    file.write_all(yaml.as_bytes())?;
-    info!("✔️  Updated Workflow {}!", filename);
-
-    Ok(())
+    update_workflow_file(&filename, &workflow)
 }
 
 pub fn disconnect_workflow_nodes(args: &ConnectWorkflowArgs) -> Result<(), Box<dyn Error>> {
@@ -131,30 +327,49 @@ pub fn disconnect_workflow_nodes(args: &ConnectWorkflowArgs) -> Result<(), Box<d
     let filename = format!("{}{}/{}.cwl", get_workflows_folder(), args.name, args.name);
     let mut workflow = load_workflow(&filename)?;
 
-    let from_parts = args.from.split('/').collect::<Vec<_>>();
-    let to_parts = args.to.split('/').collect::<Vec<_>>();
-
-    if from_parts[0] == "@inputs" {
-        workflow.remove_input_connection(from_parts[1], &args.to)?;
-    } else if to_parts[0] == "@outputs" {
-        workflow.remove_output_connection(&args.from, to_parts[1])?;
+    let (from, to) = parse_connection(&args.from, &args.to)?;
+    if from.is_inputs() && to.is_outputs() {
+        workflow.remove_input_output_connection(&from.slot, &to.slot)?;
+    } else if from.is_inputs() {
+        workflow.remove_input_connection(&from.slot, &args.to)?;
+    } else if to.is_outputs() {
+        workflow.remove_output_connection(&args.from, &to.slot)?;
     } else {
         workflow.remove_step_connection(&args.from, &args.to)?;
     }
 
-    // save workflow
-    let mut yaml = serde_yaml::to_string(&workflow)?;
-    yaml = format_cwl(&yaml)?;
-    let mut file = fs::File::create(&filename)?;
+    update_workflow_file(&filename, &workflow)
+}
+
+/// Writes a modified workflow back to its file, YAML anchors can not be kept as the workflow is serialized again
+fn update_workflow_file(filename: &str, workflow: &Workflow) -> Result<(), Box<dyn Error>> {
+    if fs::read_to_string(filename).is_ok_and(|contents| has_anchors(&contents)) {
+        warn!("Anchors and aliases in {} are expanded when saving the workflow", filename);
+    }
+    let yaml = format_cwl(&serde_yaml::to_string(workflow)?)?;
+    let mut file = fs::File::create(filename)?;
     file.write_all(yaml.as_bytes())?;
+    //`$namespaces` is not part of the Workflow struct and is declared again for extensions like `s4n:group`
+    annotate_s4n_namespace(filename)?;
     info!("✔️  Updated Workflow {}!", filename);
 
     Ok(())
 }
 
-pub fn save_workflow(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Error>> {
+pub fn save_workflow(args: &SaveWorkflowArgs) -> Result<(), Box<dyn Error>> {
     //get workflow
     let filename = format!("{}{}/{}.cwl", get_workflows_folder(), args.name, args.name);
+    let scanner = SecretScanner::load(Path::new("."))?;
+    if args.redact_secrets {
+        let mut document: Value = serde_yaml::from_str(&fs::read_to_string(&filename)?)?;
+        let findings = scanner.redact(&mut document);
+        if !findings.is_empty() {
+            create_and_write_file_forced(&filename, &format_cwl(&serde_yaml::to_string(&document)?)?)?;
+            info!("🔒 Replaced {} possible secrets with REDACTED", findings.len());
+        }
+    } else {
+        check_secrets(&filename, &scanner.scan_file(Path::new(&filename))?, args.allow_secrets, true)?;
+    }
     let repo = Repository::open(".")?;
     stage_file(&repo, &filename)?;
     let msg = &format!("✅ Saved workflow {}", args.name);
@@ -163,8 +378,11 @@ pub fn save_workflow(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub fn get_workflow_status(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Error>> {
-    let filename = format!("{}{}/{}.cwl", get_workflows_folder(), args.name, args.name);
+pub fn get_workflow_status(args: &StatusWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let Some(name) = &args.name else {
+        return get_project_status();
+    };
+    let filename = format!("{}{}/{}.cwl", get_workflows_folder(), name, name);
     let path = Path::new(&filename).parent().unwrap_or(Path::new("."));
     let workflow = load_workflow(&filename)?;
 
@@ -194,7 +412,7 @@ pub fn get_workflow_status(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Erro
         .outputs
         .iter()
         .map(|output| {
-            if workflow.has_step_output(&output.output_source) {
+            if output.output_source.ids().into_iter().all(|source| workflow.has_step_output(source)) {
                 format!("✅    {}", output.id)
             } else {
                 format!("❌    {}", output.id)
@@ -228,16 +446,8 @@ pub fn get_workflow_status(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Erro
             .outputs
             .iter()
             .map(|output| {
-                if workflow.steps.iter().any(|s| {
-                    s.in_.clone().into_values().any(|v| {
-                        let src = match v {
-                            WorkflowStepInput::String(str) => str,
-                            WorkflowStepInput::Parameter(par) => par.source.unwrap_or_default(),
-                        };
-                        src == format!("{}/{}", step.id, output.id)
-                    })
-                }) || workflow.outputs.iter().any(|o| o.output_source == format!("{}/{}", step.id, output.id))
-                {
+                let source = format!("{}/{}", step.id, output.id);
+                if workflow.has_step_input(&source) || workflow.outputs.iter().any(|o| o.output_source.ids().contains(&source.as_str())) {
                     format!("✅    {}", output.id)
                 } else {
                     format!("❌    {}", output.id)
@@ -252,10 +462,199 @@ pub fn get_workflow_status(args: &CreateWorkflowArgs) -> Result<(), Box<dyn Erro
 
     info!("✅ : connected - 🔘 : tool default - ❌ : no connection");
 
+    let root = env::current_dir()?;
+    if let Some(run) = load_runs(&root)?.get(&get_run_key(Path::new(&filename), &root)) {
+        info!("Last run of Workflow {}", filename.green().bold());
+        let mut table = Table::new();
+        table.set_titles(row![bFg => "Workflow", "Last run", "Result", "Inputs", "Outputs"]);
+        add_run_rows(&mut table, name, run, Path::new(&filename));
+        table.printstd();
+    }
+
+    Ok(())
+}
+
+/// Shows when the workflows of the project were run last, with which inputs and whether their outputs are outdated
+pub fn get_project_status() -> Result<(), Box<dyn Error>> {
+    let root = env::current_dir()?;
+    let runs = load_runs(&root)?;
+    info!("📂 Execution state of workflows in: {}", root.to_string_lossy().blue().bold());
+
+    let mut table = Table::new();
+    table.set_titles(row![bFg => "Workflow", "Last run", "Result", "Inputs", "Outputs"]);
+    let mut files = WalkDir::new(root.join(get_workflows_folder()))
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cwl"))
+        .filter(|path| load_workflow(path).is_ok())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    for file in files {
+        let name = file.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        match runs.get(&get_run_key(&file, &root)) {
+            Some(run) => add_run_rows(&mut table, &name, run, &file),
+            None => {
+                table.add_row(row![b -> name, "-", "🔘 never run", "", ""]);
+            }
+        }
+    }
+    table.printstd();
+
+    info!("⚠️ : inputs or workflow changed after the last run");
     Ok(())
 }
 
+fn add_run_rows(table: &mut Table, name: &str, run: &WorkflowRun, workflow_file: &Path) {
+    let result = if run.success { "✅ succeeded" } else { "❌ failed" };
+    let outputs = match (run.success, run.is_stale(workflow_file)) {
+        (false, _) => "-",
+        (true, true) => "⚠️ stale",
+        (true, false) => "✅ up to date",
+    };
+    table.add_row(row![b -> name, format_timestamp(&run.finished), result, format_inputs(&run.inputs), outputs]);
+    for (step, step_run) in &run.steps {
+        let outputs = if step_run.is_stale() { "⚠️ stale" } else { "✅ up to date" };
+        table.add_row(row![format!("  ↳ {step}"), format_timestamp(&step_run.finished), "", format_inputs(&step_run.inputs), outputs]);
+    }
+}
+
+fn format_inputs(inputs: &serde_json::Map<String, serde_json::Value>) -> String {
+    let mut inputs = inputs
+        .iter()
+        .map(|(key, value)| format!("{key}: {}", format_input_value(value)))
+        .collect::<Vec<_>>();
+    inputs.sort();
+    inputs.join("\n")
+}
+
+/// Files and directories are shown by their location only
+fn format_input_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(items) => items.iter().map(format_input_value).collect::<Vec<_>>().join(", "),
+        serde_json::Value::String(value) => value.clone(),
+        _ => match value.get("location").and_then(|l| l.as_str()) {
+            Some(location) => location.to_string(),
+            None => value.to_string(),
+        },
+    }
+}
+
+fn format_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(timestamp.to_string())
+}
+
+pub fn pack(args: &PackWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let filename = get_workflow_filename(&args.workflow);
+    let packed = pack_workflow(&filename)?;
+    let yaml = format_cwl(&packed.to_string())?;
+
+    if let Some(output) = &args.output {
+        fs::write(output, yaml).map_err(|e| format!("❌ Could not write packed workflow to {}: {}", output, e))?;
+        info!("📦 Packed workflow {} into {}", filename, output.green().bold());
+    } else {
+        println!("{yaml}");
+    }
+    Ok(())
+}
+
+pub fn visualize(args: &VisualizeWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let filename = get_workflow_filename(&args.workflow);
+    let graph = render_workflow(&filename, args.renderer)?;
+
+    if let Some(output) = &args.output {
+        fs::write(output, graph).map_err(|e| format!("❌ Could not write graph to {}: {}", output, e))?;
+        info!("📊 Rendered workflow {} into {}", filename, output.green().bold());
+    } else {
+        print!("{graph}");
+    }
+    Ok(())
+}
+
+pub fn diff(args: &DiffWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let diff = diff_workflow_files(get_workflow_filename(&args.old), get_workflow_filename(&args.new))?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        print!("{}", diff.to_tree());
+    }
+    Ok(())
+}
+
+pub fn create_inputs_template(args: &InputsWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let filename = get_workflow_filename(&args.workflow);
+    let job_folder = match &args.output {
+        Some(output) => Path::new(output).parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")),
+        None => Path::new("."),
+    };
+    let template = create_job_template(&filename, args.fill_defaults, job_folder)?;
+
+    if let Some(output) = &args.output {
+        create_and_write_file(output, &template).map_err(|e| format!("❌ Could not write job template to {}: {}", output, e))?;
+        info!("📝 Created job template for {} in {}", filename, output.green().bold());
+    } else {
+        print!("{template}");
+    }
+    Ok(())
+}
+
+/// Shows the commits which changed a tool or workflow, newest first, together with the last run of it recorded in the
+/// run-state database
+pub fn print_history(name: &str) -> Result<(), Box<dyn Error>> {
+    let filename = get_workflow_filename(name);
+    let root = env::current_dir()?;
+    let repo = Repository::discover(&root).map_err(|e| format!("❌ Could not open repository: {}", e))?;
+    let path = get_repository_path(&repo, Path::new(&filename)).ok_or(format!("❌ {} is not part of the repository", filename))?;
+    let history = get_file_history(&repo, &path)?;
+    if history.is_empty() {
+        return Err(format!("❌ {} has not been committed yet", filename).into());
+    }
+    info!("📜 History of {}", filename.green().bold());
+
+    let run = load_runs(&root)?.remove(&get_run_key(Path::new(&filename), &root));
+    let run_commit = run.as_ref().and_then(|run| run.commit.as_deref());
+    let format_run = |run: &WorkflowRun| {
+        let result = if run.success { "✅" } else { "❌" };
+        format!("{result} {}", format_timestamp(&run.finished))
+    };
+
+    let mut table = Table::new();
+    table.set_titles(row![bFg => "Commit", "Date", "Author", "Message", "Last run"]);
+    if let (Some(run), Some(commit)) = (&run, run_commit.filter(|c| c.ends_with("-dirty"))) {
+        let commit = commit.trim_end_matches("-dirty");
+        table.add_row(row![b -> format!("{}+", &commit[..commit.len().min(7)]), "", "", "uncommitted changes", format_run(run)]);
+    }
+    for revision in &history {
+        let date = DateTime::from_timestamp(revision.time, 0)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let last_run = match &run {
+            Some(run) if run_commit == Some(revision.id.as_str()) => format_run(run),
+            _ => String::new(),
+        };
+        table.add_row(row![b -> &revision.id[..7], date, &revision.author, &revision.summary, last_run]);
+    }
+    table.printstd();
+    info!("▶️  Previous versions can be run using `s4n execute local {}@<commit>`", filename);
+    Ok(())
+}
+
+/// Accepts either the name of a workflow in the workflows folder or a path to a CWL file
+pub fn get_workflow_filename(workflow: &str) -> String {
+    if workflow.ends_with(".cwl") {
+        workflow.to_string()
+    } else {
+        format!("{}{}/{}.cwl", get_workflows_folder(), workflow, workflow)
+    }
+}
+
 pub fn list_workflows(args: &ListWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(workflow) = &args.history {
+        return print_history(workflow);
+    }
     // Print the current working directory
     let cwd = env::current_dir()?;
     info!("📂 Scanning for workflows in: {}", cwd.to_str().unwrap_or("Invalid UTF-8").blue().bold());
@@ -398,3 +797,137 @@ pub fn remove_workflow(args: &RemoveWorkflowArgs) -> Result<(), Box<dyn Error>>
     }
     Ok(())
 }
+
+/// Folder orphaned files are archived in, relative to the project root
+pub const GC_ARCHIVE: &str = ".s4n/archive";
+
+const GC_OPTIONS: [&str; 3] = ["Keep them", "Delete them", "Archive them in .s4n/archive"];
+
+pub fn collect_garbage(args: &GcWorkflowArgs) -> Result<(), Box<dyn Error>> {
+    let folder = get_workflows_folder();
+    let orphans = find_orphans(Path::new(&folder))?;
+    if orphans.is_empty() {
+        info!("✨ No orphaned files found in {}", folder);
+        return Ok(());
+    }
+    info!("🔎 Found {} orphaned files and directories:", orphans.len());
+    print_list(&orphans.iter().map(|o| o.to_string_lossy().into_owned()).collect());
+
+    let choice = if args.delete {
+        1
+    } else if args.archive {
+        2
+    } else if io::stdin().is_terminal() {
+        Select::new().with_prompt("What should be done with them?").items(&GC_OPTIONS).default(0).interact()?
+    } else {
+        info!("Use --delete or --archive to remove them");
+        return Ok(());
+    };
+
+    match choice {
+        1 => {}
+        2 => {
+            let archive = archive_files(&orphans)?;
+            info!("📦 Archived orphaned files to {}", archive.display());
+        }
+        _ => return Ok(()),
+    }
+    for orphan in &orphans {
+        if orphan.is_dir() {
+            fs::remove_dir_all(orphan)?;
+        } else {
+            fs::remove_file(orphan)?;
+        }
+    }
+    info!("🗑️  Removed {} orphaned files and directories", orphans.len());
+    Ok(())
+}
+
+/// Finds CWL files and directories in the workflows folder which are neither a tool or workflow at the usual location
+/// `<name>/<name>.cwl` nor used as step by one of them. Directories are only reported as a whole, if they are located
+/// directly in the workflows folder and contain nothing in use.
+pub fn find_orphans(folder: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !folder.is_dir() {
+        return Ok(vec![]);
+    }
+    let root = folder.canonicalize()?;
+
+    let mut queue = vec![];
+    for entry in fs::read_dir(&root)? {
+        let path = entry?.path();
+        let file = path.join(format!("{}.cwl", path.file_name().unwrap_or_default().to_string_lossy()));
+        if path.is_dir() && file.is_file() {
+            queue.push(file);
+        }
+    }
+    //steps of nested workflows are in use as well
+    let mut used = HashSet::new();
+    while let Some(file) = queue.pop() {
+        if !used.insert(file.clone()) {
+            continue;
+        }
+        let dir = file.parent().unwrap_or(&root).to_path_buf();
+        queue.extend(get_step_runs(&file).iter().filter_map(|run| dir.join(run).canonicalize().ok()));
+    }
+
+    let mut orphans = vec![];
+    let mut walker = WalkDir::new(&root).min_depth(1).sort_by_file_name().into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        let path = entry.path();
+        let orphaned = if entry.file_type().is_dir() {
+            entry.depth() == 1 && !used.iter().any(|u| u.starts_with(path))
+        } else {
+            path.extension().is_some_and(|ext| ext == "cwl") && !used.contains(path)
+        };
+        if orphaned {
+            orphans.push(folder.join(path.strip_prefix(&root)?));
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+/// Paths of the files run by the steps of a workflow, inline processes are skipped
+fn get_step_runs(file: &Path) -> Vec<String> {
+    let Some(yaml) = fs::read_to_string(file).ok().and_then(|c| serde_yaml::from_str::<Value>(&c).ok()) else {
+        return vec![];
+    };
+    if yaml.get("class").and_then(|c| c.as_str()) != Some("Workflow") {
+        return vec![];
+    }
+    let steps = match yaml.get("steps") {
+        Some(Value::Sequence(steps)) => steps.iter().collect::<Vec<_>>(),
+        Some(Value::Mapping(steps)) => steps.values().collect(),
+        _ => vec![],
+    };
+    steps
+        .iter()
+        .filter_map(|step| step.get("run").and_then(|r| r.as_str()))
+        .map(String::from)
+        .collect()
+}
+
+/// Stores the given files and directories in a new archive in the project's archive folder
+fn archive_files(paths: &[PathBuf]) -> Result<PathBuf, Box<dyn Error>> {
+    let folder = Path::new(GC_ARCHIVE);
+    fs::create_dir_all(folder)?;
+    let ignore = folder.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(ignore, "*")?;
+    }
+
+    let archive = folder.join(format!("workflows-{}.tar.gz", Local::now().format("%Y%m%d-%H%M%S")));
+    let mut builder = tar::Builder::new(GzEncoder::new(fs::File::create(&archive)?, Compression::default()));
+    for path in paths {
+        if path.is_dir() {
+            builder.append_dir_all(path, path)?;
+        } else {
+            builder.append_path(path)?;
+        }
+    }
+    builder.into_inner()?.finish()?;
+    Ok(archive)
+}
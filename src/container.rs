@@ -0,0 +1,514 @@
+use cwl::{
+    clt::CommandLineTool,
+    requirements::{DockerRequirement, Requirement},
+    types::Entry,
+};
+use fancy_regex::Regex;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::BTreeSet,
+    env,
+    error::Error,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use walkdir::WalkDir;
+
+/// Value of `--container-image` that requests a generated Dockerfile
+pub const AUTO_CONTAINER: &str = "auto";
+
+/// Environment variable selecting the container engine used to build images, e.g. `podman`
+pub const CONTAINER_ENGINE_VAR: &str = "S4N_CONTAINER_ENGINE";
+
+const PYTHON_IMAGE: &str = "python:3.12-slim";
+const R_IMAGE: &str = "rocker/r-ver:4.4.1";
+
+/// Python modules shipped with the interpreter which do not need to be installed
+const PYTHON_STDLIB: [&str; 72] = [
+    "__future__", "abc", "argparse", "array", "ast", "asyncio", "base64", "bisect", "calendar", "cmath", "collections", "concurrent",
+    "configparser", "contextlib", "copy", "csv", "ctypes", "dataclasses", "datetime", "decimal", "difflib", "enum", "errno",
+    "fileinput", "fnmatch", "fractions", "functools", "getopt", "getpass", "glob", "gzip", "hashlib", "heapq", "hmac", "html",
+    "http", "importlib", "inspect", "io", "itertools", "json", "logging", "math", "multiprocessing", "operator", "os", "pathlib",
+    "pickle", "platform", "pprint", "queue", "random", "re", "shlex", "shutil", "signal", "socket", "sqlite3", "statistics",
+    "string", "struct", "subprocess", "sys", "tarfile", "tempfile", "textwrap", "threading", "time", "typing", "unittest", "urllib",
+    "zipfile",
+];
+
+/// Python modules whose package name on PyPI differs from the import name
+const PYTHON_PACKAGE_NAMES: [(&str, &str); 7] = [
+    ("sklearn", "scikit-learn"),
+    ("cv2", "opencv-python"),
+    ("PIL", "pillow"),
+    ("yaml", "pyyaml"),
+    ("bs4", "beautifulsoup4"),
+    ("skimage", "scikit-image"),
+    ("Bio", "biopython"),
+];
+
+/// R packages that are part of every R installation
+const R_BASE_PACKAGES: [&str; 14] = [
+    "base", "compiler", "datasets", "graphics", "grDevices", "grid", "methods", "parallel", "splines", "stats", "stats4", "tcltk",
+    "tools", "utils",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptLanguage {
+    Python,
+    R,
+}
+
+/// Packages a script needs to be installed in order to run
+#[derive(Debug, PartialEq)]
+pub struct ScriptDependencies {
+    pub language: ScriptLanguage,
+    pub packages: BTreeSet<String>,
+}
+
+impl ScriptDependencies {
+    /// Creates the contents of a Dockerfile installing all dependencies on top of a language specific base image
+    pub fn to_dockerfile(&self) -> String {
+        let packages = self.packages.iter().cloned().collect::<Vec<_>>();
+        match self.language {
+            ScriptLanguage::Python => {
+                let mut dockerfile = format!("FROM {PYTHON_IMAGE}\n");
+                if !packages.is_empty() {
+                    dockerfile.push_str(&format!("RUN pip install --no-cache-dir {}\n", packages.join(" ")));
+                }
+                dockerfile
+            }
+            ScriptLanguage::R => {
+                let mut dockerfile = format!("FROM {R_IMAGE}\n");
+                if !packages.is_empty() {
+                    let packages = packages.iter().map(|p| format!("'{p}'")).collect::<Vec<_>>().join(", ");
+                    dockerfile.push_str(&format!("RUN R -e \"install.packages(c({packages}), repos='https://cloud.r-project.org')\"\n"));
+                }
+                dockerfile
+            }
+        }
+    }
+}
+
+/// Detects the script called by a command line like `python script.py` or `Rscript script.R` and collects its
+/// dependencies. Python projects providing a `requirements.txt` next to the script or in the working directory use it
+/// instead of scanning imports.
+pub fn detect_dependencies(command: &[String]) -> Option<ScriptDependencies> {
+    let script = command.iter().map(PathBuf::from).find(|p| p.is_file() && get_language(p).is_some())?;
+    let language = get_language(&script)?;
+    let contents = fs::read_to_string(&script).ok()?;
+    let script_dir = script.parent().unwrap_or(Path::new("."));
+
+    let packages = match language {
+        ScriptLanguage::Python => {
+            let requirements = [script_dir.join("requirements.txt"), PathBuf::from("requirements.txt")]
+                .into_iter()
+                .find(|p| p.is_file());
+            match requirements {
+                Some(file) => parse_requirements_txt(&fs::read_to_string(file).ok()?),
+                None => get_python_imports(&contents)
+                    .into_iter()
+                    //local modules are part of the project
+                    .filter(|module| !script_dir.join(format!("{module}.py")).exists() && !script_dir.join(module).is_dir())
+                    .collect(),
+            }
+        }
+        ScriptLanguage::R => get_r_packages(&contents),
+    };
+    Some(ScriptDependencies { language, packages })
+}
+
+fn get_language(path: &Path) -> Option<ScriptLanguage> {
+    match path.extension()?.to_str()? {
+        "py" => Some(ScriptLanguage::Python),
+        "R" | "r" => Some(ScriptLanguage::R),
+        _ => None,
+    }
+}
+
+/// Collects the PyPI packages of all non standard library imports
+pub fn get_python_imports(contents: &str) -> BTreeSet<String> {
+    let import_re = Regex::new(r"(?m)^\s*import\s+([\w\.]+(?:\s*,\s*[\w\.]+)*)").unwrap();
+    let from_re = Regex::new(r"(?m)^\s*from\s+([\w\.]+)\s+import").unwrap();
+
+    let mut modules = vec![];
+    for caps in import_re.captures_iter(contents).flatten() {
+        modules.extend(caps[1].split(',').map(|m| m.trim().to_string()));
+    }
+    for caps in from_re.captures_iter(contents).flatten() {
+        modules.push(caps[1].to_string());
+    }
+
+    modules
+        .iter()
+        //relative imports and submodules
+        .filter_map(|m| m.split('.').next().filter(|m| !m.is_empty()))
+        .filter(|m| !PYTHON_STDLIB.contains(m))
+        .map(|m| {
+            PYTHON_PACKAGE_NAMES
+                .iter()
+                .find(|(module, _)| module == &m)
+                .map(|(_, package)| package.to_string())
+                .unwrap_or_else(|| m.to_string())
+        })
+        .collect()
+}
+
+/// Collects all packages loaded using `library`, `require` or `package::function` calls
+pub fn get_r_packages(contents: &str) -> BTreeSet<String> {
+    let library_re = Regex::new(r#"(?:library|require|requireNamespace)\(\s*["']?([\w\.]+)["']?"#).unwrap();
+    let namespace_re = Regex::new(r"([A-Za-z][\w\.]*):::?").unwrap();
+
+    library_re
+        .captures_iter(contents)
+        .chain(namespace_re.captures_iter(contents))
+        .flatten()
+        .map(|caps| caps[1].to_string())
+        .filter(|p| !R_BASE_PACKAGES.contains(&p.as_str()))
+        .collect()
+}
+
+fn parse_requirements_txt(contents: &str) -> BTreeSet<String> {
+    contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('-'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Dockerfile of a tool and the directory it is built in
+#[derive(Debug, PartialEq)]
+pub struct ImageBuild {
+    pub dockerfile: String,
+    pub context: PathBuf,
+    /// Platform to build for like `linux/amd64`, the platform of the host is used if not set
+    pub platform: Option<String>,
+}
+
+impl ImageBuild {
+    /// Gets the Dockerfile a DockerRequirement of the tool refers to. Included Dockerfiles are resolved relative to the
+    /// tool file and built in their own directory, inline ones in the directory of the tool.
+    pub fn from_tool(tool: &CommandLineTool, tool_path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        let tool_dir = tool_path.parent().unwrap_or(Path::new("."));
+        let entry = tool.requirements.iter().chain(tool.hints.iter()).flatten().find_map(|r| match r {
+            Requirement::DockerRequirement(DockerRequirement::DockerFile { docker_file, .. }) => Some(docker_file),
+            _ => None,
+        });
+        Ok(match entry {
+            Some(Entry::Include(include)) => {
+                let path = tool_dir.join(&include.include);
+                let dockerfile = fs::read_to_string(&path).map_err(|e| format!("❌ Could not read Dockerfile {path:?}: {e}"))?;
+                let context = path.parent().unwrap_or(tool_dir).to_path_buf();
+                Some(ImageBuild {
+                    dockerfile,
+                    context,
+                    platform: None,
+                })
+            }
+            Some(Entry::Source(dockerfile)) => Some(ImageBuild {
+                dockerfile: dockerfile.clone(),
+                context: tool_dir.to_path_buf(),
+                platform: None,
+            }),
+            None => None,
+        })
+    }
+
+    /// Hashes the Dockerfile together with all files it copies from the build context and the platform, so the hash
+    /// only changes if the image would
+    pub fn get_hash(&self) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(self.dockerfile.as_bytes());
+        if let Some(platform) = &self.platform {
+            hasher.update(platform.as_bytes());
+        }
+        for source in get_copy_sources(&self.dockerfile) {
+            let path = self.context.join(&source);
+            let mut files = WalkDir::new(&path)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect::<Vec<_>>();
+            files.sort();
+            for file in files {
+                let name = file.strip_prefix(&self.context).unwrap_or(&file).to_string_lossy().replace('\\', "/");
+                hasher.update(name.as_bytes());
+                if let Ok(contents) = fs::read(&file) {
+                    hasher.update(&contents);
+                }
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Builds the image unless an image with the given tag already exists, returns whether it was built
+    pub fn build(&self, tag: &str, force: bool) -> Result<bool, Box<dyn Error>> {
+        if !force && image_exists(tag) {
+            return Ok(false);
+        }
+        let engine = get_container_engine();
+        let mut command = Command::new(&engine);
+        command.args(["build", "--tag", tag, "--file", "-"]);
+        if let Some(platform) = &self.platform {
+            command.args(["--platform", platform]);
+        }
+        let mut child = command
+            .arg(&self.context)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("❌ Could not start {engine}: {e}"))?;
+        child.stdin.take().ok_or("❌ Could not pass Dockerfile")?.write_all(self.dockerfile.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("❌ Building image {tag} failed with {status}").into());
+        }
+        Ok(true)
+    }
+
+    /// Images the Dockerfile builds upon, stages of the Dockerfile itself and `scratch` are left out
+    pub fn get_base_images(&self) -> Vec<String> {
+        let mut images = vec![];
+        let mut stages = vec!["scratch".to_string()];
+        for line in self.dockerfile.lines().map(str::trim) {
+            let mut parts = line.split_whitespace();
+            if !parts.next().is_some_and(|i| i.eq_ignore_ascii_case("FROM")) {
+                continue;
+            }
+            let mut parts = parts.filter(|p| !p.starts_with("--"));
+            let Some(image) = parts.next() else {
+                continue;
+            };
+            if !stages.iter().any(|s| s.eq_ignore_ascii_case(image)) && !images.contains(&image.to_string()) {
+                images.push(image.to_string());
+            }
+            if parts.next().is_some_and(|p| p.eq_ignore_ascii_case("AS")) {
+                stages.extend(parts.next().map(str::to_string));
+            }
+        }
+        images
+    }
+}
+
+/// Platform of the host as used by container images, containers always run Linux, on macOS and Windows inside a
+/// virtual machine
+pub fn get_native_platform() -> String {
+    let arch = match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        arch => arch,
+    };
+    format!("linux/{arch}")
+}
+
+/// Platforms an image provides according to its manifest in the registry, `None` if they can not be determined, e.g.
+/// for single platform images or without network access
+pub fn get_image_platforms(image: &str) -> Option<Vec<String>> {
+    let output = Command::new(get_container_engine())
+        .args(["manifest", "inspect", image])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let manifest: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let platforms = manifest
+        .get("manifests")?
+        .as_array()?
+        .iter()
+        .filter_map(|m| {
+            let platform = m.get("platform")?;
+            let os = platform.get("os")?.as_str()?;
+            let arch = platform.get("architecture")?.as_str()?;
+            //attestations are listed with an unknown platform
+            (os != "unknown").then(|| format!("{os}/{arch}"))
+        })
+        .collect::<Vec<_>>();
+    Some(platforms)
+}
+
+/// Creates the tag of a tool image as `<project>/<tool>:<hash>` using the first 12 characters of the hash
+pub fn get_image_tag(project: &str, tool: &str, hash: &str) -> String {
+    let sanitize = |name: &str| {
+        name.to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '-' })
+            .collect::<String>()
+            .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+            .to_string()
+    };
+    format!("{}/{}:{}", sanitize(project), sanitize(tool), &hash[..hash.len().min(12)])
+}
+
+pub fn get_container_engine() -> String {
+    env::var(CONTAINER_ENGINE_VAR).unwrap_or_else(|_| "docker".to_string())
+}
+
+pub fn image_exists(tag: &str) -> bool {
+    Command::new(get_container_engine())
+        .args(["image", "inspect", tag])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Registry digest of a pulled image, `None` for images only built locally, if the image does not exist or the container
+/// engine is not available
+pub fn get_image_digest(image: &str) -> Option<String> {
+    let output = Command::new(get_container_engine())
+        .args(["image", "inspect", image])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_image_digest(&serde_json::from_slice(&output.stdout).ok()?)
+}
+
+fn parse_image_digest(inspect: &serde_json::Value) -> Option<String> {
+    let digest = inspect.get(0)?.get("RepoDigests")?.get(0)?.as_str()?;
+    digest.split_once('@').map(|(_, digest)| digest.to_string())
+}
+
+/// Sources of `COPY` and `ADD` instructions relative to the build context, copies from other stages and URLs are skipped
+fn get_copy_sources(dockerfile: &str) -> Vec<String> {
+    let mut sources = vec![];
+    for line in dockerfile.lines().map(str::trim) {
+        let Some((instruction, arguments)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        if !instruction.eq_ignore_ascii_case("COPY") && !instruction.eq_ignore_ascii_case("ADD") {
+            continue;
+        }
+        let arguments = arguments.trim();
+        let mut parts = if arguments.starts_with('[') {
+            serde_json::from_str::<Vec<String>>(arguments).unwrap_or_default()
+        } else {
+            arguments.split_whitespace().map(str::to_string).collect()
+        };
+        if parts.iter().any(|p| p.starts_with("--from")) {
+            continue;
+        }
+        parts.retain(|p| !p.starts_with("--"));
+        parts.pop();
+        sources.extend(parts.into_iter().filter(|p| !p.contains("://")));
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_get_python_imports() {
+        let script = r"
+import os, sys
+import numpy as np
+import matplotlib.pyplot as plt
+from sklearn.linear_model import LinearRegression
+from . import helpers
+    import yaml
+";
+        let packages = get_python_imports(script);
+        assert_eq!(
+            packages.into_iter().collect::<Vec<_>>(),
+            vec!["matplotlib", "numpy", "pyyaml", "scikit-learn"]
+        );
+    }
+
+    #[test]
+    fn test_get_r_packages() {
+        let script = r#"
+library(dplyr)
+require("ggplot2")
+library(stats)
+data <- readr::read_csv("data.csv")
+"#;
+        let packages = get_r_packages(script);
+        assert_eq!(packages.into_iter().collect::<Vec<_>>(), vec!["dplyr", "ggplot2", "readr"]);
+    }
+
+    #[test]
+    fn test_to_dockerfile() {
+        let dependencies = ScriptDependencies {
+            language: ScriptLanguage::Python,
+            packages: BTreeSet::from(["pandas".to_string(), "numpy".to_string()]),
+        };
+        assert_eq!(
+            dependencies.to_dockerfile(),
+            "FROM python:3.12-slim\nRUN pip install --no-cache-dir numpy pandas\n"
+        );
+
+        let dependencies = ScriptDependencies {
+            language: ScriptLanguage::R,
+            packages: BTreeSet::from(["dplyr".to_string()]),
+        };
+        assert!(dependencies.to_dockerfile().contains("install.packages(c('dplyr')"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_detect_dependencies() {
+        let command = vec!["python".to_string(), "tests/test_data/echo.py".to_string()];
+        let dependencies = detect_dependencies(&command).unwrap();
+        assert_eq!(dependencies.language, ScriptLanguage::Python);
+        assert!(dependencies.packages.is_empty());
+
+        assert!(detect_dependencies(&["echo".to_string(), "Hello".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_get_image_tag() {
+        assert_eq!(get_image_tag("My Project", "calculation", "0123456789abcdef"), "my-project/calculation:0123456789ab");
+    }
+
+    #[test]
+    fn test_parse_image_digest() {
+        let pulled = serde_json::json!([{"Id": "sha256:1111", "RepoDigests": ["python@sha256:2222"]}]);
+        assert_eq!(parse_image_digest(&pulled), Some("sha256:2222".to_string()));
+        let built = serde_json::json!([{"Id": "sha256:1111", "RepoDigests": []}]);
+        assert_eq!(parse_image_digest(&built), None);
+        assert_eq!(parse_image_digest(&serde_json::json!([])), None);
+    }
+
+    #[test]
+    fn test_image_build_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("requirements.txt"), "pandas\n").unwrap();
+        fs::write(dir.path().join("tool.cwl"), "class: CommandLineTool\n").unwrap();
+        let mut build = ImageBuild {
+            dockerfile: "FROM python:3.12-slim\nCOPY --chown=1000 requirements.txt /tmp/\nCOPY --from=base /opt /opt\n".to_string(),
+            context: dir.path().to_path_buf(),
+            platform: None,
+        };
+        assert_eq!(get_copy_sources(&build.dockerfile), vec!["requirements.txt"]);
+
+        let hash = build.get_hash();
+        fs::write(dir.path().join("tool.cwl"), "class: CommandLineTool\nbaseCommand: echo\n").unwrap();
+        assert_eq!(build.get_hash(), hash);
+        fs::write(dir.path().join("requirements.txt"), "pandas\nnumpy\n").unwrap();
+        assert_ne!(build.get_hash(), hash);
+
+        let hash = build.get_hash();
+        build.platform = Some("linux/amd64".to_string());
+        assert_ne!(build.get_hash(), hash);
+    }
+
+    #[test]
+    fn test_get_base_images() {
+        let build = ImageBuild {
+            dockerfile: "FROM --platform=$BUILDPLATFORM rust:1.84 AS builder\nRUN cargo build\nFROM builder AS test\nFROM debian:bookworm-slim\nCOPY --from=builder /app /app\nFROM scratch\n".to_string(),
+            context: PathBuf::from("."),
+            platform: None,
+        };
+        assert_eq!(build.get_base_images(), vec!["rust:1.84", "debian:bookworm-slim"]);
+        assert!(get_native_platform().starts_with("linux/"));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::io::{get_workflows_folder, resolve_path};
 use cwl::{
     clt::CommandLineTool,
-    inputs::{CommandInputParameter, WorkflowStepInput},
+    inputs::{CommandInputParameter, WorkflowStepInput, WorkflowStepInputParameter},
     load_tool,
     outputs::WorkflowOutputParameter,
     requirements::{DockerRequirement, Requirement},
@@ -18,6 +18,8 @@ pub trait Connectable {
     fn add_step_connection(&mut self, from: &str, to: &str) -> Result<(), Box<dyn Error>>;
     fn add_output_connection(&mut self, from: &str, to_output: &str) -> Result<(), Box<dyn Error>>;
     fn add_input_connection(&mut self, from_input: &str, to: &str) -> Result<(), Box<dyn Error>>;
+    fn add_input_output_connection(&mut self, from_input: &str, to_output: &str) -> Result<(), Box<dyn Error>>;
+    fn remove_input_output_connection(&mut self, from_input: &str, to_output: &str) -> Result<(), Box<dyn Error>>;
     fn add_new_step_if_not_exists(&mut self, name: &str, tool: &CommandLineTool);
     fn remove_step_connection(&mut self, from: &str, to: &str) -> Result<(), Box<dyn Error>>;
 }
@@ -38,7 +40,7 @@ impl Saveable for CommandLineTool {
             }
         }
 
-        if let Some(requirements) = &mut self.requirements {
+        for requirements in [&mut self.requirements, &mut self.hints].into_iter().flatten() {
             for requirement in requirements {
                 if let Requirement::DockerRequirement(docker) = requirement {
                     if let DockerRequirement::DockerFile {
@@ -69,6 +71,7 @@ impl Connectable for Workflow {
                 run: format!("../{name}/{name}.cwl"),
                 in_: HashMap::new(),
                 out: tool.get_output_ids(),
+                ..Default::default()
             };
             self.steps.push(workflow_step);
 
@@ -82,7 +85,11 @@ impl Connectable for Workflow {
 
         let to_filename = resolve_filename(to_parts[0]);
         let to_tool: CommandLineTool = load_tool(&to_filename)?;
-        let to_slot = to_tool.inputs.iter().find(|i| i.id == to_parts[1]).expect("No slot");
+        let to_slot = to_tool
+            .inputs
+            .iter()
+            .find(|i| i.id == to_parts[1])
+            .ok_or_else(|| slot_not_found(to_parts[0], "input", to_parts[1], to_tool.inputs.iter().map(|i| i.id.clone())))?;
 
         //register input
         if !self.has_input(from_input) {
@@ -110,7 +117,11 @@ impl Connectable for Workflow {
 
         let from_filename = resolve_filename(from_parts[0]);
         let from_tool: CommandLineTool = load_tool(&from_filename)?;
-        let from_slot = from_tool.outputs.iter().find(|i| i.id == from_parts[1]).expect("No slot");
+        let from_slot = from_tool
+            .outputs
+            .iter()
+            .find(|i| i.id == from_parts[1])
+            .ok_or_else(|| slot_not_found(from_parts[0], "output", from_parts[1], from_tool.get_output_ids()))?;
 
         if !self.has_output(to_output) {
             self.outputs.push(WorkflowOutputParameter::default().with_id(to_output).clone());
@@ -118,78 +129,121 @@ impl Connectable for Workflow {
 
         let output = self.outputs.iter_mut().find(|o| o.id == to_output).unwrap();
         output.type_.clone_from(&from_slot.type_);
-        output.output_source.clone_from(&from.to_string());
+        output.output_source = from.into();
 
         info!("➕ Added or updated connection from {from} to outputs.{to_output} in workflow!");
 
         Ok(())
     }
 
+    /// Connects a workflow input directly to a workflow output. The input has to exist already, its type is used for the output.
+    fn add_input_output_connection(&mut self, from_input: &str, to_output: &str) -> Result<(), Box<dyn Error>> {
+        let input = self
+            .inputs
+            .iter()
+            .find(|i| i.id == from_input)
+            .ok_or_else(|| slot_not_found("Workflow", "input", from_input, self.inputs.iter().map(|i| i.id.clone())))?;
+        let type_ = input.type_.clone();
+
+        if !self.has_output(to_output) {
+            self.outputs.push(WorkflowOutputParameter::default().with_id(to_output).clone());
+        }
+        let output = self.outputs.iter_mut().find(|o| o.id == to_output).unwrap();
+        output.type_ = type_;
+        output.output_source = from_input.into();
+
+        info!("➕ Added or updated connection from inputs.{from_input} to outputs.{to_output} in workflow!");
+        Ok(())
+    }
+
     /// Adds a connection between two a CommandLineToos. The tools will be registered as step if registered not already.
+    /// Sources selecting a field or array item are connected using `valueFrom`.
     fn add_step_connection(&mut self, from: &str, to: &str) -> Result<(), Box<dyn Error>> {
+        let from = ConnectionNode::parse(from)?;
+        let to = ConnectionNode::parse(to)?;
         //handle from
-        let from_parts = from.split('/').collect::<Vec<_>>();
         //check if step already exists and create if not
-        if !self.has_step(from_parts[0]) {
-            let from_filename = resolve_filename(from_parts[0]);
+        if !self.has_step(&from.step) {
+            let from_filename = resolve_filename(&from.step);
             let from_tool: CommandLineTool = load_tool(&from_filename)?;
             let from_outputs = from_tool.get_output_ids();
-            if !from_outputs.contains(&from_parts[1].to_string()) {
-                return Err(format!(
-                    "Tool {} does not have output `{}`. Cannot not create node from {} in Workflow!",
-                    from_parts[0], from_parts[1], from_filename
-                )
-                .into());
+            if !from_outputs.contains(&from.slot) {
+                return Err(slot_not_found(&from.step, "output", &from.slot, from_outputs));
             }
 
             //create step
-            self.add_new_step_if_not_exists(from_parts[0], &from_tool);
+            self.add_new_step_if_not_exists(&from.step, &from_tool);
         } else {
-            info!("🔗 Found step {} in workflow. Not changing that!", from_parts[0]);
+            let from_outputs = self.get_step(&from.step).map(|s| s.out.clone()).unwrap_or_default();
+            if !from_outputs.contains(&from.slot) {
+                return Err(slot_not_found(&from.step, "output", &from.slot, from_outputs));
+            }
+            info!("🔗 Found step {} in workflow. Not changing that!", from.step);
         }
 
         //handle to
-        let to_parts = to.split('/').collect::<Vec<_>>();
-        //check if step exists
-        if !self.has_step(to_parts[0]) {
-            let to_filename = resolve_filename(to_parts[0]);
-            let to_tool: CommandLineTool = load_tool(&to_filename)?;
-
-            self.add_new_step_if_not_exists(to_parts[0], &to_tool);
+        //check if step exists, tools of existing steps are checked if possible
+        let to_filename = resolve_filename(&to.step);
+        let to_tool = if self.has_step(&to.step) { load_tool(&to_filename).ok() } else { Some(load_tool(&to_filename)?) };
+        if let Some(to_tool) = to_tool {
+            if !to_tool.inputs.iter().any(|i| i.id == to.slot) {
+                return Err(slot_not_found(&to.step, "input", &to.slot, to_tool.inputs.iter().map(|i| i.id.clone())));
+            }
+            self.add_new_step_if_not_exists(&to.step, &to_tool);
         }
 
-        let step = self.steps.iter_mut().find(|s| s.id == to_parts[0]).unwrap(); //safe here!
-        step.in_.insert(to_parts[1].to_string(), WorkflowStepInput::String(from.to_string()));
+        let input = match from.value_from() {
+            Some(value_from) => {
+                let requirements = self.requirements.get_or_insert_with(Vec::new);
+                if !requirements.iter().any(|r| matches!(r, Requirement::StepInputExpressionRequirement)) {
+                    requirements.push(Requirement::StepInputExpressionRequirement);
+                    info!("➕ Added StepInputExpressionRequirement to workflow");
+                }
+                WorkflowStepInput::Parameter(WorkflowStepInputParameter {
+                    source: Some(from.source().into()),
+                    value_from: Some(value_from),
+                    ..Default::default()
+                })
+            }
+            None => WorkflowStepInput::String(from.source()),
+        };
+        let step = self.steps.iter_mut().find(|s| s.id == to.step).unwrap(); //safe here!
+        step.in_.insert(to.slot, input);
 
         Ok(())
     }
 
     /// Removes a connection between two CommandLineTools by removing input from tool_y that is also output of tool_x.
     fn remove_step_connection(&mut self, from: &str, to: &str) -> Result<(), Box<dyn Error>> {
-        let from_parts = from.split('/').collect::<Vec<_>>();
-        let to_parts = to.split('/').collect::<Vec<_>>();
-        if from_parts.len() != 2 {
-            return Err(format!("Invalid '--from' format: {from}. Please use tool/parameter or @inputs/parameter.").into());
+        ConnectionNode::parse(from)?;
+        let to_node = ConnectionNode::parse(to)?;
+        if !self.has_step(&to_node.step) {
+            return Err(format!("Step {} not found!", to_node.step).into());
         }
-        if to_parts.len() != 2 {
-            return Err(format!("Invalid '--to' format: {to}. Please use tool/parameter or @outputs/parameter.").into());
-        }
-        if !self.has_step(to_parts[0]) {
-            return Err(format!("Step {} not found!", to_parts[0]).into());
-        }
-        let step = self.steps.iter_mut().find(|s| s.id == to_parts[0]);
+        let step = self.steps.iter_mut().find(|s| s.id == to_node.step);
         // If the step is found, try to remove the connection by removing input from tool_y that uses output of tool_x
         //Input is empty, change that?
         if let Some(step) = step {
-            if step.in_.remove(to_parts[1]).is_some() {
+            if step.in_.remove(&to_node.slot).is_some() {
                 info!("🔗 Successfully disconnected {from} from {to}");
             } else {
                 warn!("No connection found between {from} and {to}. Nothing to disconnect.");
             }
             Ok(())
         } else {
-            Err(format!("Failed to find step {} in workflow!", to_parts[0]).into())
+            Err(format!("Failed to find step {} in workflow!", to_node.step).into())
+        }
+    }
+
+    /// Removes a workflow output that is directly connected to a workflow input.
+    fn remove_input_output_connection(&mut self, from_input: &str, to_output: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(index) = self.outputs.iter().position(|o| o.id == to_output && o.output_source == from_input) {
+            self.outputs.remove(index);
+            info!("➖ Removed connection from inputs.{from_input} to outputs.{to_output} from workflow!");
+        } else {
+            warn!("No connection found between inputs.{from_input} and outputs.{to_output}. Nothing to disconnect.");
         }
+        Ok(())
     }
 
     /// Removes an input from inputs and removes it from CommandLineTool input.
@@ -244,6 +298,112 @@ impl Connectable for Workflow {
     }
 }
 
+/// Pseudo step used to address workflow inputs in connections, e.g. `@inputs/population`
+pub const INPUTS_NODE: &str = "@inputs";
+/// Pseudo step used to address workflow outputs in connections, e.g. `@outputs/results`
+pub const OUTPUTS_NODE: &str = "@outputs";
+
+/// One end of a connection like `calculation/results`. Step outputs can be narrowed down to fields of records and
+/// items of arrays, e.g. `calculation/results/name` or `calculation/results[0]`.
+#[derive(Debug, PartialEq)]
+pub struct ConnectionNode {
+    pub step: String,
+    pub slot: String,
+    /// Selected part of the slot as parameter reference suffix, e.g. `[0].name`
+    pub selector: Option<String>,
+}
+
+impl ConnectionNode {
+    pub fn parse(node: &str) -> Result<Self, Box<dyn Error>> {
+        let invalid = || format!("Invalid node `{node}`, please use tool/parameter, tool/parameter/field, tool/parameter[index], @inputs/parameter or @outputs/parameter");
+        let mut parts = node.split('/');
+        let (Some(step), Some(slot)) = (parts.next(), parts.next()) else {
+            return Err(invalid().into());
+        };
+        let (slot, indices) = split_indices(slot).ok_or_else(invalid)?;
+        if step.is_empty() || slot.is_empty() {
+            return Err(invalid().into());
+        }
+        let mut selector = indices.to_string();
+        for field in parts {
+            let (field, indices) = split_indices(field).filter(|(f, _)| is_field_name(f)).ok_or_else(invalid)?;
+            selector.push_str(&format!(".{field}{indices}"));
+        }
+        Ok(Self {
+            step: step.to_string(),
+            slot: slot.to_string(),
+            selector: Some(selector).filter(|s| !s.is_empty()),
+        })
+    }
+
+    pub fn is_inputs(&self) -> bool {
+        self.step == INPUTS_NODE
+    }
+
+    pub fn is_outputs(&self) -> bool {
+        self.step == OUTPUTS_NODE
+    }
+
+    /// Source as used in the workflow, which is the plain input name for workflow inputs
+    pub fn source(&self) -> String {
+        if self.is_inputs() {
+            self.slot.clone()
+        } else {
+            format!("{}/{}", self.step, self.slot)
+        }
+    }
+
+    /// Parameter reference selecting the field or item of the source, e.g. `$(self[0].name)`
+    pub fn value_from(&self) -> Option<String> {
+        self.selector.as_ref().map(|selector| format!("$(self{selector})"))
+    }
+}
+
+/// Parses both ends of a connection. Workflow inputs can only be used as source and workflow outputs only as target,
+/// fields and items can only be selected from step outputs connected to other steps.
+pub fn parse_connection(from: &str, to: &str) -> Result<(ConnectionNode, ConnectionNode), Box<dyn Error>> {
+    let from_node = ConnectionNode::parse(from)?;
+    let to_node = ConnectionNode::parse(to)?;
+    if from_node.is_outputs() {
+        return Err(format!("Workflow outputs can not be the source of a connection, use {from} as `--to` instead").into());
+    }
+    if to_node.is_inputs() {
+        return Err(format!("Workflow inputs can not be the target of a connection, use {to} as `--from` instead").into());
+    }
+    if to_node.selector.is_some() {
+        return Err(format!("Fields and items can only be selected on the source of a connection, not on {to}").into());
+    }
+    if from_node.selector.is_some() && (from_node.is_inputs() || to_node.is_outputs()) {
+        return Err(format!("Fields and items of {from} can only be connected to inputs of steps").into());
+    }
+    Ok((from_node, to_node))
+}
+
+/// Splits `name[0][1]` into the name and its indices `[0][1]`, None is returned for malformed indices
+fn split_indices(part: &str) -> Option<(&str, &str)> {
+    let (name, indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+    let mut rest = indices;
+    while !rest.is_empty() {
+        let (index, tail) = rest.strip_prefix('[')?.split_once(']')?;
+        if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        rest = tail;
+    }
+    Some((name, indices))
+}
+
+fn is_field_name(field: &str) -> bool {
+    !field.is_empty() && field.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Error for a missing input or output listing the ones available
+fn slot_not_found(step: &str, kind: &str, slot: &str, available: impl IntoIterator<Item = String>) -> Box<dyn Error> {
+    let available = available.into_iter().collect::<Vec<_>>();
+    let available = if available.is_empty() { "none".to_string() } else { available.join(", ") };
+    format!("{step} does not have {kind} `{slot}`, available {kind}s are: {available}").into()
+}
+
 /// Locates CWL File by name
 pub fn resolve_filename(cwl_filename: &str) -> String {
     format!("{}{}/{}.cwl", get_workflows_folder(), cwl_filename, cwl_filename)
@@ -290,6 +450,38 @@ mod tests {
         assert_eq!(filename, "workflows/my-tool/my-tool.cwl".to_string());
     }
 
+    #[test]
+    fn test_parse_connection_node() {
+        let node = ConnectionNode::parse("calculation/results").unwrap();
+        assert_eq!((node.source(), node.value_from()), ("calculation/results".to_string(), None));
+
+        let node = ConnectionNode::parse("calculation/results[0]/name").unwrap();
+        assert_eq!(node.slot, "results");
+        assert_eq!(node.value_from(), Some("$(self[0].name)".to_string()));
+        assert_eq!(
+            ConnectionNode::parse("calculation/stats/mean/value").unwrap().value_from(),
+            Some("$(self.mean.value)".to_string())
+        );
+
+        let node = ConnectionNode::parse("@inputs/population").unwrap();
+        assert!(node.is_inputs());
+        assert_eq!(node.source(), "population");
+
+        for invalid in ["calculation", "calculation/", "calculation/results[a]", "calculation/results[0", "calculation/results/"] {
+            assert!(ConnectionNode::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_parse_connection() {
+        assert!(parse_connection("@inputs/population", "@outputs/population").is_ok());
+        assert!(parse_connection("calculation/results[1]", "plot/results").is_ok());
+        assert!(parse_connection("@outputs/results", "plot/results").is_err());
+        assert!(parse_connection("calculation/results", "@inputs/results").is_err());
+        assert!(parse_connection("calculation/results", "plot/results[0]").is_err());
+        assert!(parse_connection("calculation/results[0]", "@outputs/results").is_err());
+    }
+
     #[test]
     pub fn test_cwl_save() {
         let inputs = vec![
@@ -328,6 +520,7 @@ mod tests {
             Requirement::InitialWorkDirRequirement(InitialWorkDirRequirement {
                 listing: vec![Listing {
                     entry: Entry::from_file(&os_path("../../test/script.py")),
+                    writable: false,
                     entryname: "test/script.py".to_string()
                 }]
             })
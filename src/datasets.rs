@@ -0,0 +1,372 @@
+use crate::io::{get_file_checksum, get_filename_without_extension};
+use cwl::types::DefaultValue;
+use serde::{Deserialize, Serialize};
+use log::info;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Name of the manifest file datasets are registered in, relative to the project root
+pub const DATASET_MANIFEST: &str = "datasets.yml";
+/// Prefix used in job files to reference a registered dataset by its name, e.g. `location: dataset:population`
+pub const DATASET_PREFIX: &str = "dataset:";
+/// Folder remote datasets are downloaded to, relative to the project root
+pub const DATASET_CACHE: &str = ".s4n/cache/datasets";
+
+const S3_SCHEME: &str = "s3://";
+const DATAONE_SCHEME: &str = "dataone://";
+const DATAONE_OBJECT_URL: &str = "https://cn.dataone.org/cn/v2/object/";
+
+/// A dataset registered within a project
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Dataset {
+    pub location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doi: Option<String>,
+}
+
+impl Dataset {
+    pub fn is_remote(&self) -> bool {
+        is_url(&self.location)
+    }
+}
+
+/// Collection of registered datasets, keyed by name
+pub type DatasetManifest = BTreeMap<String, Dataset>;
+
+pub fn is_url(location: &str) -> bool {
+    location.contains("://")
+}
+
+/// Derives a dataset name from its location, e.g. `data/population.csv` becomes `population`
+pub fn get_dataset_name(location: &str) -> String {
+    let location = location.trim_end_matches('/');
+    let last = location.rsplit('/').next().unwrap_or(location);
+    get_filename_without_extension(last).unwrap_or_else(|| last.to_string())
+}
+
+/// Names are used as folder names in the dataset cache and must not point outside of it
+pub fn check_dataset_name(name: &str) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("Invalid dataset name `{name}`, names must not be empty or contain path separators or `..`").into());
+    }
+    Ok(())
+}
+
+/// Creates the manifest entry for a local file or remote location. Checksums of local files are calculated,
+/// for remote locations a given checksum is used.
+pub fn create_dataset(
+    location: &str,
+    checksum: Option<String>,
+    license: Option<String>,
+    doi: Option<String>,
+) -> Result<Dataset, Box<dyn Error>> {
+    let checksum = if is_url(location) {
+        checksum
+    } else {
+        let path = Path::new(location);
+        if !path.exists() {
+            return Err(format!("Dataset {location} does not exist!").into());
+        }
+        if path.is_file() {
+            let calculated = format!("sha1${}", get_file_checksum(path)?);
+            if let Some(given) = checksum {
+                if given != calculated {
+                    return Err(format!("Checksum mismatch for {location}: expected {given}, got {calculated}").into());
+                }
+            }
+            Some(calculated)
+        } else {
+            None
+        }
+    };
+
+    Ok(Dataset {
+        location: location.to_string(),
+        checksum,
+        license,
+        doi,
+    })
+}
+
+/// Loads the dataset manifest located in the given project folder. A missing manifest results in an empty one.
+pub fn load_manifest<P: AsRef<Path>>(project_dir: P) -> Result<DatasetManifest, Box<dyn Error>> {
+    let path = project_dir.as_ref().join(DATASET_MANIFEST);
+    if !path.exists() {
+        return Ok(DatasetManifest::new());
+    }
+    let contents = fs::read_to_string(&path)?;
+    let manifest = serde_yaml::from_str::<Option<DatasetManifest>>(&contents).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+    Ok(manifest.unwrap_or_default())
+}
+
+pub fn save_manifest<P: AsRef<Path>>(project_dir: P, manifest: &DatasetManifest) -> Result<PathBuf, Box<dyn Error>> {
+    let path = project_dir.as_ref().join(DATASET_MANIFEST);
+    fs::write(&path, serde_yaml::to_string(manifest)?)?;
+    Ok(path)
+}
+
+/// Replaces references like `dataset:population` in File or Directory values with the location of the registered dataset.
+pub fn resolve_dataset_references<P: AsRef<Path>>(inputs: &mut HashMap<String, DefaultValue>, project_dir: P) -> Result<(), Box<dyn Error>> {
+    let mut manifest = None;
+    for value in inputs.values_mut() {
+        let location = match value {
+            DefaultValue::File(file) => &mut file.location,
+            DefaultValue::Directory(directory) => &mut directory.location,
+            DefaultValue::Any(_) => continue,
+        };
+        if let Some(name) = location.strip_prefix(DATASET_PREFIX) {
+            if manifest.is_none() {
+                manifest = Some(load_manifest(&project_dir)?);
+            }
+            let dataset = manifest
+                .as_ref()
+                .and_then(|m| m.get(name))
+                .ok_or_else(|| format!("Dataset `{name}` is not registered in {DATASET_MANIFEST}"))?;
+            let path = if dataset.is_remote() {
+                fetch_dataset(name, dataset, &project_dir)?
+            } else {
+                project_dir.as_ref().join(&dataset.location)
+            };
+            *location = path.to_string_lossy().into_owned();
+        }
+    }
+    Ok(())
+}
+
+/// Translates dataset locations to a downloadable URL. Supports `http(s)://`, `s3://bucket/key` and `dataone://<pid>`.
+pub fn get_download_url(location: &str) -> String {
+    if let Some(path) = location.strip_prefix(S3_SCHEME) {
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        format!("https://{bucket}.s3.amazonaws.com/{key}")
+    } else if let Some(pid) = location.strip_prefix(DATAONE_SCHEME) {
+        format!("{DATAONE_OBJECT_URL}{}", urlencoding::encode(pid))
+    } else {
+        location.to_string()
+    }
+}
+
+/// Returns the path a remote dataset is cached at
+pub fn get_cached_dataset_path<P: AsRef<Path>>(project_dir: P, name: &str, dataset: &Dataset) -> Result<PathBuf, Box<dyn Error>> {
+    check_dataset_name(name)?;
+    let filename = dataset
+        .location
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|f| check_dataset_name(f).is_ok() && !f.contains(':'))
+        .unwrap_or(name);
+    Ok(project_dir.as_ref().join(DATASET_CACHE).join(name).join(filename))
+}
+
+/// Writes to a file while calculating the checksum of everything written
+struct ChecksumWriter {
+    file: File,
+    hasher: Sha1,
+}
+
+impl Write for ChecksumWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn verify_checksum(path: &Path, checksum: &Option<String>) -> Result<bool, Box<dyn Error>> {
+    match checksum {
+        Some(expected) => Ok(*expected == format!("sha1${}", get_file_checksum(path)?)),
+        None => Ok(true),
+    }
+}
+
+/// Downloads a remote dataset into the project's dataset cache unless a valid copy is already present
+/// and returns the path of the cached file. Downloads are verified against the registered checksum.
+pub fn fetch_dataset<P: AsRef<Path>>(name: &str, dataset: &Dataset, project_dir: P) -> Result<PathBuf, Box<dyn Error>> {
+    let path = get_cached_dataset_path(&project_dir, name, dataset)?;
+    if path.exists() && verify_checksum(&path, &dataset.checksum)? {
+        return Ok(path);
+    }
+
+    let cache_dir = project_dir.as_ref().join(DATASET_CACHE);
+    fs::create_dir_all(path.parent().unwrap())?;
+    let ignore = cache_dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(ignore, "*\n")?;
+    }
+
+    let url = get_download_url(&dataset.location);
+    info!("⬇️  Downloading dataset {name} from {url}");
+    //the download is streamed to disk and checksummed on the way
+    let mut response = reqwest::blocking::get(&url)?.error_for_status()?;
+    let tmp_path = path.with_extension("part");
+    let mut writer = ChecksumWriter {
+        file: File::create(&tmp_path)?,
+        hasher: Sha1::new(),
+    };
+    response.copy_to(&mut writer)?;
+    let ChecksumWriter { mut file, hasher } = writer;
+    file.flush()?;
+    drop(file);
+    let checksum = format!("sha1${:x}", hasher.finalize());
+
+    if dataset.checksum.as_ref().is_some_and(|expected| *expected != checksum) {
+        fs::remove_file(&tmp_path)?;
+        return Err(format!(
+            "Checksum mismatch for dataset `{name}` downloaded from {url}: expected {}",
+            dataset.checksum.as_deref().unwrap_or_default()
+        )
+        .into());
+    }
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cwl::types::File;
+    use serial_test::serial;
+    use std::{io::Read, net::TcpListener, thread};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_get_dataset_name() {
+        assert_eq!(get_dataset_name("data/population.csv"), "population");
+        assert_eq!(get_dataset_name("https://example.com/files/speakers.csv"), "speakers");
+        assert_eq!(get_dataset_name("data/raw/"), "raw");
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_dataset_local() {
+        let dataset = create_dataset("tests/test_data/file.txt", None, Some("MIT".to_string()), None).unwrap();
+        assert_eq!(dataset.checksum, Some("sha1$2c3cafa4db3f3e1e51b3dff4303502dbe42b7a89".to_string()));
+        assert_eq!(dataset.license, Some("MIT".to_string()));
+        assert!(!dataset.is_remote());
+    }
+
+    #[test]
+    #[serial]
+    fn test_create_dataset_checksum_mismatch() {
+        let result = create_dataset("tests/test_data/file.txt", Some("sha1$nope".to_string()), None, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_resolve_dataset_references() {
+        let dir = tempdir().unwrap();
+        let mut manifest = DatasetManifest::new();
+        manifest.insert("population".to_string(), Dataset {
+            location: "data/population.csv".to_string(),
+            ..Default::default()
+        });
+        save_manifest(dir.path(), &manifest).unwrap();
+
+        let mut inputs = HashMap::from([(
+            "population".to_string(),
+            DefaultValue::File(File::from_location(&"dataset:population".to_string())),
+        )]);
+        resolve_dataset_references(&mut inputs, dir.path()).unwrap();
+
+        let expected = dir.path().join("data/population.csv").to_string_lossy().into_owned();
+        assert_eq!(inputs["population"].as_value_string(), expected);
+    }
+
+    #[test]
+    fn test_check_dataset_name() {
+        assert!(check_dataset_name("population").is_ok());
+        assert!(check_dataset_name("population.v2").is_ok());
+        for name in ["", ".", "..", "../../x", "a/b", "a\\b"] {
+            assert!(check_dataset_name(name).is_err(), "{name}");
+        }
+
+        //names and filenames of locations stay inside of the cache
+        let dataset = Dataset {
+            location: "https://example.com/files/..".to_string(),
+            ..Default::default()
+        };
+        assert!(get_cached_dataset_path("project", "../../x", &dataset).is_err());
+        assert_eq!(
+            get_cached_dataset_path("project", "files", &dataset).unwrap(),
+            Path::new("project").join(DATASET_CACHE).join("files/files")
+        );
+    }
+
+    #[test]
+    fn test_get_download_url() {
+        assert_eq!(get_download_url("https://example.com/data.csv"), "https://example.com/data.csv");
+        assert_eq!(get_download_url("s3://my-bucket/path/data.csv"), "https://my-bucket.s3.amazonaws.com/path/data.csv");
+        assert_eq!(
+            get_download_url("dataone://doi:10.5063/F1Z899CZ"),
+            "https://cn.dataone.org/cn/v2/object/doi%3A10.5063%2FF1Z899CZ"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_fetch_dataset_cached() {
+        let dir = tempdir().unwrap();
+        let dataset = Dataset {
+            location: "https://example.com/files/file.txt".to_string(),
+            checksum: Some("sha1$2c3cafa4db3f3e1e51b3dff4303502dbe42b7a89".to_string()),
+            ..Default::default()
+        };
+        let cached = get_cached_dataset_path(dir.path(), "file", &dataset).unwrap();
+        assert_eq!(cached, dir.path().join(DATASET_CACHE).join("file/file.txt"));
+
+        //valid copy in cache is used without downloading
+        fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        fs::copy("tests/test_data/file.txt", &cached).unwrap();
+        assert_eq!(fetch_dataset("file", &dataset, dir.path()).unwrap(), cached);
+    }
+
+    #[test]
+    fn test_fetch_dataset_download() {
+        let contents = fs::read("tests/test_data/file.txt").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/files/file.txt", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).unwrap();
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", contents.len());
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&contents).unwrap();
+            }
+        });
+
+        let dir = tempdir().unwrap();
+        let mut dataset = Dataset {
+            location: url,
+            checksum: Some("sha1$2c3cafa4db3f3e1e51b3dff4303502dbe42b7a89".to_string()),
+            ..Default::default()
+        };
+        let path = fetch_dataset("file", &dataset, dir.path()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), fs::read("tests/test_data/file.txt").unwrap());
+        assert!(!path.with_extension("part").exists());
+
+        //downloads not matching the registered checksum are discarded
+        fs::remove_file(&path).unwrap();
+        dataset.checksum = Some("sha1$nope".to_string());
+        assert!(fetch_dataset("file", &dataset, dir.path()).is_err());
+        assert!(!path.exists() && !path.with_extension("part").exists());
+        server.join().unwrap();
+    }
+}
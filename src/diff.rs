@@ -0,0 +1,338 @@
+use colored::Colorize;
+use cwl::{inputs::WorkflowStepInput, load_workflow, requirements::Requirement, wf::Workflow};
+use serde::Serialize;
+use std::{collections::HashMap, error::Error, fmt::Write, path::Path};
+
+/// Kind of a change between two versions of a workflow
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A changed part of a workflow identified by `id`, modified parts carry the old and new value or the changes of
+/// their own parts, e.g. the connections of a step
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+}
+
+impl Change {
+    fn added(id: &str, value: String) -> Self {
+        Change {
+            kind: ChangeKind::Added,
+            id: id.to_string(),
+            old: None,
+            new: Some(value),
+            changes: vec![],
+        }
+    }
+
+    fn removed(id: &str, value: String) -> Self {
+        Change {
+            kind: ChangeKind::Removed,
+            id: id.to_string(),
+            old: Some(value),
+            new: None,
+            changes: vec![],
+        }
+    }
+
+    fn modified(id: &str, old: String, new: String) -> Self {
+        Change {
+            kind: ChangeKind::Modified,
+            id: id.to_string(),
+            old: Some(old),
+            new: Some(new),
+            changes: vec![],
+        }
+    }
+}
+
+/// Structural differences between two versions of a workflow, ignoring formatting, comments and the order of entries
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct WorkflowDiff {
+    pub inputs: Vec<Change>,
+    pub outputs: Vec<Change>,
+    pub steps: Vec<Change>,
+    pub requirements: Vec<Change>,
+    pub hints: Vec<Change>,
+}
+
+impl WorkflowDiff {
+    pub fn is_empty(&self) -> bool {
+        self.sections().iter().all(|(_, changes)| changes.is_empty())
+    }
+
+    fn sections(&self) -> [(&str, &Vec<Change>); 5] {
+        [
+            ("inputs", &self.inputs),
+            ("outputs", &self.outputs),
+            ("steps", &self.steps),
+            ("requirements", &self.requirements),
+            ("hints", &self.hints),
+        ]
+    }
+
+    /// Renders the changes as colored tree, added parts are prefixed with `+`, removed ones with `-` and modified ones
+    /// with `~`
+    pub fn to_tree(&self) -> String {
+        if self.is_empty() {
+            return "No structural changes\n".to_string();
+        }
+        let mut tree = String::new();
+        for (name, changes) in self.sections().into_iter().filter(|(_, changes)| !changes.is_empty()) {
+            let _ = writeln!(tree, "{}", name.bold());
+            write_changes(&mut tree, changes, "");
+        }
+        tree
+    }
+}
+
+fn write_changes(tree: &mut String, changes: &[Change], indent: &str) {
+    for (i, change) in changes.iter().enumerate() {
+        let last = i == changes.len() - 1;
+        let branch = if last { "└── " } else { "├── " };
+        let text = match (&change.old, &change.new) {
+            (Some(old), Some(new)) => format!("~ {}: {old} → {new}", change.id),
+            (_, Some(value)) | (Some(value), None) if !value.is_empty() => format!("{} {}: {value}", symbol(change.kind), change.id),
+            _ => format!("{} {}", symbol(change.kind), change.id),
+        };
+        let text = match change.kind {
+            ChangeKind::Added => text.green(),
+            ChangeKind::Removed => text.red(),
+            ChangeKind::Modified => text.yellow(),
+        };
+        let _ = writeln!(tree, "{indent}{branch}{text}");
+        let child_indent = format!("{indent}{}", if last { "    " } else { "│   " });
+        write_changes(tree, &change.changes, &child_indent);
+    }
+}
+
+fn symbol(kind: ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Added => "+",
+        ChangeKind::Removed => "-",
+        ChangeKind::Modified => "~",
+    }
+}
+
+/// Loads both workflows and compares them structurally
+pub fn diff_workflow_files<P: AsRef<Path>>(old: P, new: P) -> Result<WorkflowDiff, Box<dyn Error>> {
+    let old_workflow = load_workflow(old.as_ref())?;
+    let new_workflow = load_workflow(new.as_ref())?;
+    Ok(diff_workflows(&old_workflow, &new_workflow))
+}
+
+/// Compares inputs, outputs, steps including their connections, requirements and hints of two workflows
+pub fn diff_workflows(old: &Workflow, new: &Workflow) -> WorkflowDiff {
+    let inputs = |workflow: &Workflow| {
+        workflow
+            .inputs
+            .iter()
+            .map(|i| (i.id.clone(), describe_input(&i.type_, &i.default)))
+            .collect::<Vec<_>>()
+    };
+    let outputs = |workflow: &Workflow| {
+        workflow
+            .outputs
+            .iter()
+            .map(|o| (o.id.clone(), format!("{} ← {}", o.type_, o.output_source)))
+            .collect::<Vec<_>>()
+    };
+
+    let mut steps = vec![];
+    for step in &new.steps {
+        let Some(old_step) = old.steps.iter().find(|s| s.id == step.id) else {
+            steps.push(Change::added(&step.id, step.run.clone()));
+            continue;
+        };
+        let mut changes = diff_entries(&[("run".to_string(), old_step.run.clone())], &[("run".to_string(), step.run.clone())]);
+        changes.extend(diff_entries(&get_connections(&old_step.in_), &get_connections(&step.in_)));
+        changes.extend(diff_entries(
+            &old_step.out.iter().map(|o| (format!("out/{o}"), String::new())).collect::<Vec<_>>(),
+            &step.out.iter().map(|o| (format!("out/{o}"), String::new())).collect::<Vec<_>>(),
+        ));
+        changes.extend(diff_entries(
+            &[("scatter".to_string(), old_step.get_scatter_inputs().join(", "))],
+            &[("scatter".to_string(), step.get_scatter_inputs().join(", "))],
+        ));
+        if !changes.is_empty() {
+            steps.push(Change {
+                kind: ChangeKind::Modified,
+                id: step.id.clone(),
+                old: None,
+                new: None,
+                changes,
+            });
+        }
+    }
+    for step in old.steps.iter().filter(|s| !new.has_step(&s.id)) {
+        steps.push(Change::removed(&step.id, step.run.clone()));
+    }
+
+    WorkflowDiff {
+        inputs: diff_entries(&inputs(old), &inputs(new)),
+        outputs: diff_entries(&outputs(old), &outputs(new)),
+        steps,
+        requirements: diff_entries(&describe_requirements(&old.requirements), &describe_requirements(&new.requirements)),
+        hints: diff_entries(&describe_requirements(&old.hints), &describe_requirements(&new.hints)),
+    }
+}
+
+/// Compares entries by id, added and modified entries are listed in the order of the new version followed by the
+/// removed ones
+fn diff_entries(old: &[(String, String)], new: &[(String, String)]) -> Vec<Change> {
+    let old_map = old.iter().cloned().collect::<HashMap<_, _>>();
+    let mut changes = vec![];
+    for (id, value) in new {
+        match old_map.get(id) {
+            None => changes.push(Change::added(id, value.clone())),
+            Some(old_value) if old_value != value => changes.push(Change::modified(id, old_value.clone(), value.clone())),
+            _ => {}
+        }
+    }
+    for (id, value) in old.iter().filter(|(id, _)| !new.iter().any(|(new_id, _)| new_id == id)) {
+        changes.push(Change::removed(id, value.clone()));
+    }
+    changes
+}
+
+fn describe_input<T: std::fmt::Display, D: Serialize>(type_: &T, default: &Option<D>) -> String {
+    match default.as_ref().and_then(|d| serde_json::to_string(d).ok()) {
+        Some(default) => format!("{type_} = {default}"),
+        None => type_.to_string(),
+    }
+}
+
+/// Connections of the inputs of a step as `in/<id>` with their source, sorted by id
+fn get_connections(inputs: &HashMap<String, WorkflowStepInput>) -> Vec<(String, String)> {
+    let mut connections = inputs
+        .iter()
+        .map(|(id, input)| {
+            let value = match input {
+                WorkflowStepInput::String(source) => format!("← {source}"),
+                WorkflowStepInput::Parameter(parameter) => match &parameter.source {
+                    Some(source) => format!("← {source}"),
+                    None => serde_json::to_string(parameter).unwrap_or_default(),
+                },
+            };
+            (format!("in/{id}"), value)
+        })
+        .collect::<Vec<_>>();
+    connections.sort();
+    connections
+}
+
+/// Requirements identified by their class with their fields as JSON
+fn describe_requirements(requirements: &Option<Vec<Requirement>>) -> Vec<(String, String)> {
+    requirements
+        .iter()
+        .flatten()
+        .filter_map(|requirement| {
+            let mut value = serde_json::to_value(requirement).ok()?;
+            let class = value.as_object_mut()?.remove("class")?.as_str()?.to_string();
+            Some((class, value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cwl::parse_cwl;
+
+    const OLD: &str = "class: Workflow
+cwlVersion: v1.2
+inputs:
+  pop: File
+  speakers: File
+outputs:
+  out:
+    type: File
+    outputSource: plot/results
+steps:
+  calculation:
+    run: calculation.cwl
+    in:
+      population: pop
+      speakers: speakers
+    out: [results]
+  plot:
+    run: plot.cwl
+    in:
+      results: calculation/results
+    out: [results]
+";
+
+    const NEW: &str = "class: Workflow
+cwlVersion: v1.2
+requirements:
+  NetworkAccess:
+    networkAccess: true
+inputs:
+  pop: Directory
+  speakers: File
+outputs:
+  out:
+    type: File
+    outputSource: calculation/results
+steps:
+  calculation:
+    run: calculation_v2.cwl
+    in:
+      population: pop
+      speakers: speakers
+      language:
+        default: en
+    out: [results]
+";
+
+    #[test]
+    fn test_diff_workflows() {
+        let old = parse_cwl::<Workflow>(OLD).unwrap();
+        let new = parse_cwl::<Workflow>(NEW).unwrap();
+        let diff = diff_workflows(&old, &new);
+
+        assert_eq!(diff.inputs, vec![Change::modified("pop", "File".to_string(), "Directory".to_string())]);
+        assert_eq!(
+            diff.outputs,
+            vec![Change::modified(
+                "out",
+                "File ← plot/results".to_string(),
+                "File ← calculation/results".to_string()
+            )]
+        );
+        assert_eq!(diff.steps.len(), 2);
+        assert_eq!(diff.steps[0].kind, ChangeKind::Modified);
+        assert_eq!(
+            diff.steps[0].changes,
+            vec![
+                Change::modified("run", "calculation.cwl".to_string(), "calculation_v2.cwl".to_string()),
+                Change::added("in/language", r#"{"default":"en"}"#.to_string()),
+            ]
+        );
+        assert_eq!(diff.steps[1], Change::removed("plot", "plot.cwl".to_string()));
+        assert_eq!(
+            diff.requirements,
+            vec![Change::added("NetworkAccess", r#"{"networkAccess":true}"#.to_string())]
+        );
+        assert!(diff.hints.is_empty());
+        assert!(diff_workflows(&old, &old).is_empty());
+
+        colored::control::set_override(false);
+        let tree = diff.to_tree();
+        colored::control::unset_override();
+        assert!(tree.contains("steps\n├── ~ calculation\n│   ├── ~ run: calculation.cwl → calculation_v2.cwl\n"));
+        assert!(tree.contains("└── - plot: plot.cwl\n"));
+    }
+}
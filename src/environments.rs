@@ -0,0 +1,531 @@
+use crate::io::create_and_write_file_forced;
+use cwl::{
+    clt::CommandLineTool,
+    requirements::{Requirement, SoftwarePackage},
+};
+use log::info;
+use serde_yaml::Value;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Directory managed environments are created in, ignored by git
+pub const ENVIRONMENT_DIR: &str = ".s4n/envs";
+
+/// Existing virtual environments of a project that are used as is
+const VENV_DIRECTORIES: [&str; 2] = [".venv", "venv"];
+
+/// Isolated Python or R environment of a project
+#[derive(Debug, PartialEq)]
+pub enum ProjectEnvironment {
+    /// virtual environment at the given path, created from `requirements.txt` if missing
+    Venv { path: PathBuf, requirements: Option<PathBuf> },
+    /// renv project described by the given `renv.lock`
+    Renv { lockfile: PathBuf },
+    /// conda environment at the given path, created by micromamba from the contents of an `environment.yml`
+    Conda { path: PathBuf, environment: String },
+}
+
+/// Detects the environment of the project in the given directory matching the interpreter of the command, e.g. a venv
+/// for `python script.py` or renv for `Rscript script.R`
+pub fn detect_environment(command: &[String], project_dir: &Path) -> Option<ProjectEnvironment> {
+    let interpreter = Path::new(command.first()?).file_stem()?.to_string_lossy().to_string();
+    if interpreter.starts_with("python") {
+        if let Some(path) = VENV_DIRECTORIES
+            .iter()
+            .map(|dir| project_dir.join(dir))
+            .find(|dir| dir.join("pyvenv.cfg").exists())
+        {
+            return Some(ProjectEnvironment::Venv { path, requirements: None });
+        }
+        let requirements = project_dir.join("requirements.txt");
+        if requirements.exists() {
+            return Some(ProjectEnvironment::Venv {
+                path: project_dir.join(ENVIRONMENT_DIR).join("venv"),
+                requirements: Some(requirements),
+            });
+        }
+    } else if interpreter == "Rscript" || interpreter == "R" {
+        let lockfile = project_dir.join("renv.lock");
+        if lockfile.exists() {
+            return Some(ProjectEnvironment::Renv { lockfile });
+        }
+    }
+    None
+}
+
+impl ProjectEnvironment {
+    /// Creates the environment if needed and installs all declared packages
+    pub fn prepare(&self) -> Result<(), Box<dyn Error>> {
+        match self {
+            ProjectEnvironment::Venv { path, requirements } => {
+                if path.join("pyvenv.cfg").exists() {
+                    return Ok(());
+                }
+                let Some(requirements) = requirements else {
+                    return Ok(());
+                };
+                ignore_environment_dir(path)?;
+                info!("🐍 Creating virtual environment {:?} from {:?}", path, requirements);
+                run(Command::new(get_python()).arg("-m").arg("venv").arg(path))?;
+                run(Command::new(get_venv_executable(path, "pip")).arg("install").arg("-r").arg(requirements))?;
+            }
+            ProjectEnvironment::Renv { lockfile } => {
+                let profile = get_renv_profile(lockfile);
+                ignore_environment_dir(&profile)?;
+                let project = lockfile.parent().unwrap_or(Path::new(".")).to_string_lossy().replace('\\', "/");
+                create_and_write_file_forced(&profile, &format!("renv::load(\"{project}\")\n"))?;
+                info!("📦 Restoring renv environment from {:?}", lockfile);
+                run(Command::new("Rscript")
+                    .arg("-e")
+                    .arg(format!("renv::restore(project = \"{project}\", prompt = FALSE)")))?;
+            }
+            ProjectEnvironment::Conda { path, environment } => {
+                if path.join("conda-meta").exists() {
+                    return Ok(());
+                }
+                ignore_environment_dir(path)?;
+                let file = path.with_extension("yml");
+                create_and_write_file_forced(&file, environment)?;
+                info!("🐍 Creating conda environment {:?} using micromamba", path);
+                run(Command::new(get_micromamba())
+                    .arg("create")
+                    .arg("--yes")
+                    .arg("--prefix")
+                    .arg(path)
+                    .arg("--file")
+                    .arg(&file))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the environment variables activating the environment and returns their previous values
+    pub fn activate(&self) -> HashMap<String, Option<OsString>> {
+        let mut previous = HashMap::new();
+        for (key, value) in self.variables() {
+            previous.insert(key.to_string(), env::var_os(key));
+            env::set_var(key, value);
+        }
+        previous
+    }
+
+    /// Environment variables activating the environment, commands can be run in it without changing the process
+    pub fn variables(&self) -> Vec<(&'static str, OsString)> {
+        match self {
+            ProjectEnvironment::Venv { path, .. } => {
+                let bin = get_venv_executable(path, "python").parent().map(Path::to_path_buf).unwrap_or_default();
+                let mut paths = vec![bin];
+                if let Some(path) = env::var_os("PATH") {
+                    paths.extend(env::split_paths(&path));
+                }
+                let joined = env::join_paths(paths).unwrap_or_default();
+                vec![("PATH", joined), ("VIRTUAL_ENV", path.clone().into_os_string())]
+            }
+            ProjectEnvironment::Renv { lockfile } => vec![("R_PROFILE_USER", get_renv_profile(lockfile).into_os_string())],
+            ProjectEnvironment::Conda { path, .. } => {
+                let mut paths = get_conda_bin_dirs(path);
+                if let Some(path) = env::var_os("PATH") {
+                    paths.extend(env::split_paths(&path));
+                }
+                let joined = env::join_paths(paths).unwrap_or_default();
+                vec![("PATH", joined), ("CONDA_PREFIX", path.clone().into_os_string())]
+            }
+        }
+    }
+}
+
+/// Restores environment variables changed by `ProjectEnvironment::activate`
+pub fn deactivate(previous: HashMap<String, Option<OsString>>) {
+    for (key, value) in previous {
+        match value {
+            Some(value) => env::set_var(key, value),
+            None => env::remove_var(key),
+        }
+    }
+}
+
+/// Gets the conda environment a tool runs in from its `s4n:CondaEnvironment` or `SoftwareRequirement`. Environments are
+/// created in the project directory and named after the hash of their specification, so tools declaring the same
+/// packages share one environment.
+pub fn get_software_environment(tool: &CommandLineTool, tool_dir: &Path, project_dir: &Path) -> Result<Option<ProjectEnvironment>, Box<dyn Error>> {
+    let requirements = tool.requirements.iter().chain(tool.hints.iter()).flatten().collect::<Vec<_>>();
+    let environment_file = requirements.iter().find_map(|r| match r {
+        Requirement::CondaEnvironment(conda) => Some(tool_dir.join(&conda.env_file)),
+        _ => None,
+    });
+    let environment = match environment_file {
+        Some(file) => fs::read_to_string(&file).map_err(|e| format!("❌ Could not read conda environment {:?}: {}", file, e))?,
+        None => match requirements.iter().find_map(|r| match r {
+            Requirement::SoftwareRequirement(software) if !software.packages.is_empty() => Some(&software.packages),
+            _ => None,
+        }) {
+            Some(packages) => to_environment_file(packages),
+            None => return Ok(None),
+        },
+    };
+
+    let mut hasher = Sha1::new();
+    hasher.update(environment.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    let path = project_dir.join(ENVIRONMENT_DIR).join(format!("conda-{}", &hash[..12]));
+    Ok(Some(ProjectEnvironment::Conda { path, environment }))
+}
+
+/// Checks whether micromamba can be run to create environments
+pub fn is_micromamba_available() -> bool {
+    Command::new(get_micromamba()).arg("--version").output().is_ok_and(|o| o.status.success())
+}
+
+/// Pins the packages of all environments managed in the project directory. Conda environments are exported as explicit
+/// lockfiles named `<environment>.lock`, virtual environments as `<environment>.txt` requirements. Returns the names and
+/// contents of the lockfiles.
+pub fn lock_environments(project_dir: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let Ok(entries) = fs::read_dir(project_dir.join(ENVIRONMENT_DIR)) else {
+        return Ok(vec![]);
+    };
+    let mut paths = entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir()).collect::<Vec<_>>();
+    paths.sort();
+
+    let mut lockfiles = vec![];
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let (command, extension) = if path.join("conda-meta").exists() {
+            let mut command = Command::new(get_micromamba());
+            command.args(["env", "export", "--explicit", "--prefix"]).arg(&path);
+            (command, "lock")
+        } else if path.join("pyvenv.cfg").exists() {
+            let mut command = Command::new(get_venv_executable(&path, "pip"));
+            command.arg("freeze");
+            (command, "txt")
+        } else {
+            continue;
+        };
+        lockfiles.push((format!("{name}.{extension}"), output(command)?));
+    }
+    Ok(lockfiles)
+}
+
+/// Recreates a managed environment of the project from a lockfile written by [`lock_environments`]
+pub fn restore_environment(project_dir: &Path, lockfile: &Path) -> Result<(), Box<dyn Error>> {
+    let name = lockfile.file_stem().ok_or("❌ Lockfile has no name")?;
+    let path = project_dir.join(ENVIRONMENT_DIR).join(name);
+    ignore_environment_dir(&path)?;
+    match lockfile.extension().and_then(|e| e.to_str()) {
+        Some("lock") => {
+            info!("🐍 Restoring conda environment {:?} using micromamba", path);
+            run(Command::new(get_micromamba())
+                .arg("create")
+                .arg("--yes")
+                .arg("--prefix")
+                .arg(&path)
+                .arg("--file")
+                .arg(lockfile))
+        }
+        Some("txt") => {
+            info!("🐍 Restoring virtual environment {:?}", path);
+            run(Command::new(get_python()).arg("-m").arg("venv").arg(&path))?;
+            run(Command::new(get_venv_executable(&path, "pip")).arg("install").arg("-r").arg(lockfile))
+        }
+        _ => Err(format!("❌ {:?} is not an environment lockfile", lockfile).into()),
+    }
+}
+
+/// Creates the contents of an `environment.yml` installing the given packages. Packages linking to PyPI are installed
+/// using pip, all others from conda-forge or the channel they link to on anaconda.org.
+fn to_environment_file(packages: &[SoftwarePackage]) -> String {
+    let mut conda = vec![];
+    let mut pip = vec![];
+    for package in packages {
+        let version = package.version.as_ref().and_then(|v| v.first());
+        let specs = package.specs.iter().flatten().collect::<Vec<_>>();
+        if specs.iter().any(|s| s.starts_with("https://pypi.org/")) {
+            pip.push(match version {
+                Some(version) => format!("{}=={version}", package.package),
+                None => package.package.clone(),
+            });
+            continue;
+        }
+        let channel = specs
+            .iter()
+            .find_map(|s| s.strip_prefix("https://anaconda.org/"))
+            .and_then(|s| s.split('/').next())
+            .filter(|c| *c != "conda-forge");
+        let name = match channel {
+            Some(channel) => format!("{channel}::{}", package.package),
+            None => package.package.clone(),
+        };
+        conda.push(match version {
+            Some(version) => format!("{name}={version}"),
+            None => name,
+        });
+    }
+
+    let mut environment = "channels:\n- conda-forge\ndependencies:\n".to_string();
+    for dependency in &conda {
+        environment.push_str(&format!("- {dependency}\n"));
+    }
+    if !pip.is_empty() {
+        if !conda.iter().any(|d| d == "pip" || d.starts_with("pip=")) {
+            environment.push_str("- pip\n");
+        }
+        environment.push_str("- pip:\n");
+        for dependency in &pip {
+            environment.push_str(&format!("  - {dependency}\n"));
+        }
+    }
+    environment
+}
+
+/// Exports the currently active conda environment as `environment.yml` contents with the given name. Build strings and
+/// the local prefix are left out to keep the file portable.
+pub fn export_conda_environment(name: &str) -> Result<String, Box<dyn Error>> {
+    //mamba and micromamba set their own variable and understand the same command
+    let conda = env::var_os("MAMBA_EXE").or(env::var_os("CONDA_EXE")).unwrap_or(OsString::from("conda"));
+    let mut command = Command::new(&conda);
+    command.arg("env").arg("export").arg("--no-builds");
+    if let Some(prefix) = env::var_os("CONDA_PREFIX") {
+        command.arg("--prefix").arg(prefix);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("❌ Could not run {:?}, is conda installed and the environment activated? {}", conda, e))?;
+    if !output.status.success() {
+        return Err(format!("❌ Exporting conda environment failed: {}", String::from_utf8_lossy(&output.stderr)).into());
+    }
+
+    let mut environment: Value = serde_yaml::from_slice(&output.stdout)?;
+    let mapping = environment.as_mapping_mut().ok_or("❌ Unexpected output of conda env export")?;
+    mapping.remove("prefix");
+    mapping.insert(Value::String("name".to_string()), Value::String(name.to_string()));
+    Ok(serde_yaml::to_string(&environment)?)
+}
+
+/// Lists all packages of an `environment.yml` together with their pinned versions and registry links, e.g. to be used
+/// in a `SoftwareRequirement`
+pub fn get_software_packages(environment: &str) -> Result<Vec<SoftwarePackage>, Box<dyn Error>> {
+    let environment: Value = serde_yaml::from_str(environment)?;
+    //packages from `defaults` are hosted in the anaconda channel, custom channel urls can not be linked
+    let channel = environment
+        .get("channels")
+        .and_then(|c| c.as_sequence())
+        .and_then(|c| c.first())
+        .and_then(|c| c.as_str())
+        .map(|c| if c == "defaults" { "anaconda" } else { c })
+        .filter(|c| !c.contains("://"));
+
+    let mut packages = vec![];
+    for dependency in environment.get("dependencies").and_then(|d| d.as_sequence()).into_iter().flatten() {
+        match dependency {
+            Value::String(spec) => {
+                let mut parts = spec.split('=');
+                let name = parts.next().unwrap_or_default();
+                //packages can be pinned to a channel using `channel::name`
+                let (channel, name) = name.split_once("::").map(|(c, n)| (Some(c), n)).unwrap_or((channel, name));
+                packages.push(SoftwarePackage {
+                    package: name.to_string(),
+                    version: parts.next().filter(|v| !v.is_empty()).map(|v| vec![v.to_string()]),
+                    specs: channel.map(|c| vec![format!("https://anaconda.org/{c}/{name}")]),
+                });
+            }
+            Value::Mapping(map) => {
+                for spec in map.get("pip").and_then(|p| p.as_sequence()).into_iter().flatten().filter_map(|p| p.as_str()) {
+                    let (name, version) = spec.split_once("==").map(|(n, v)| (n, Some(v))).unwrap_or((spec, None));
+                    packages.push(SoftwarePackage {
+                        package: name.trim().to_string(),
+                        version: version.map(|v| vec![v.trim().to_string()]),
+                        specs: Some(vec![format!("https://pypi.org/project/{}", name.trim())]),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(packages)
+}
+
+fn get_renv_profile(lockfile: &Path) -> PathBuf {
+    lockfile.parent().unwrap_or(Path::new(".")).join(ENVIRONMENT_DIR).join("renv.R")
+}
+
+fn get_venv_executable(venv: &Path, name: &str) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        venv.join("Scripts").join(format!("{name}.exe"))
+    } else {
+        venv.join("bin").join(name)
+    }
+}
+
+fn get_conda_bin_dirs(prefix: &Path) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        vec![prefix.to_path_buf(), prefix.join("Library").join("bin"), prefix.join("Scripts")]
+    } else {
+        vec![prefix.join("bin")]
+    }
+}
+
+/// micromamba sets its own variable when initialized in a shell, otherwise it needs to be on PATH
+fn get_micromamba() -> OsString {
+    env::var_os("MAMBA_EXE").unwrap_or(OsString::from("micromamba"))
+}
+
+fn get_python() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "python"
+    } else {
+        "python3"
+    }
+}
+
+/// Makes sure managed environments are not picked up as outputs or committed
+fn ignore_environment_dir(path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.ancestors().find(|p| p.ends_with(ENVIRONMENT_DIR)) {
+        fs::create_dir_all(dir)?;
+        create_and_write_file_forced(dir.join(".gitignore"), "*")?;
+    }
+    Ok(())
+}
+
+fn run(command: &mut Command) -> Result<(), Box<dyn Error>> {
+    let output = command.output().map_err(|e| format!("❌ Could not run {:?}: {}", command.get_program(), e))?;
+    if !output.status.success() {
+        return Err(format!("❌ Preparing environment failed: {}", String::from_utf8_lossy(&output.stderr)).into());
+    }
+    Ok(())
+}
+
+fn output(mut command: Command) -> Result<String, Box<dyn Error>> {
+    let output = command.output().map_err(|e| format!("❌ Could not run {:?}: {}", command.get_program(), e))?;
+    if !output.status.success() {
+        return Err(format!("❌ Locking environment failed: {}", String::from_utf8_lossy(&output.stderr)).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_environment() {
+        let dir = tempdir().unwrap();
+        let python = vec!["python".to_string(), "script.py".to_string()];
+        assert_eq!(detect_environment(&python, dir.path()), None);
+
+        fs::write(dir.path().join("requirements.txt"), "pandas").unwrap();
+        assert_eq!(
+            detect_environment(&python, dir.path()),
+            Some(ProjectEnvironment::Venv {
+                path: dir.path().join(ENVIRONMENT_DIR).join("venv"),
+                requirements: Some(dir.path().join("requirements.txt"))
+            })
+        );
+
+        //existing venv is preferred
+        fs::create_dir(dir.path().join(".venv")).unwrap();
+        fs::write(dir.path().join(".venv/pyvenv.cfg"), "").unwrap();
+        assert_eq!(
+            detect_environment(&python, dir.path()),
+            Some(ProjectEnvironment::Venv {
+                path: dir.path().join(".venv"),
+                requirements: None
+            })
+        );
+
+        let r = vec!["Rscript".to_string(), "script.R".to_string()];
+        assert_eq!(detect_environment(&r, dir.path()), None);
+        fs::write(dir.path().join("renv.lock"), "{}").unwrap();
+        assert!(matches!(detect_environment(&r, dir.path()), Some(ProjectEnvironment::Renv { .. })));
+    }
+
+    #[test]
+    fn test_get_software_packages() {
+        let environment = r"
+name: analysis
+channels:
+  - conda-forge
+  - defaults
+dependencies:
+  - numpy=1.26.4
+  - bioconda::samtools=1.21
+  - python
+  - pip:
+    - requests==2.32.3
+";
+        let packages = get_software_packages(environment).unwrap();
+        assert_eq!(packages.len(), 4);
+        assert_eq!(
+            packages[0],
+            SoftwarePackage {
+                package: "numpy".to_string(),
+                version: Some(vec!["1.26.4".to_string()]),
+                specs: Some(vec!["https://anaconda.org/conda-forge/numpy".to_string()]),
+            }
+        );
+        assert_eq!(packages[1].specs, Some(vec!["https://anaconda.org/bioconda/samtools".to_string()]));
+        assert_eq!(packages[2].version, None);
+        assert_eq!(packages[3].package, "requests");
+        assert_eq!(packages[3].specs, Some(vec!["https://pypi.org/project/requests".to_string()]));
+    }
+
+    #[test]
+    fn test_get_software_environment() {
+        let tool = cwl::parse_cwl::<CommandLineTool>(
+            r"
+class: CommandLineTool
+cwlVersion: v1.2
+baseCommand: samtools
+inputs: []
+outputs: []
+hints:
+- class: SoftwareRequirement
+  packages:
+  - package: samtools
+    version: ['1.21']
+    specs: ['https://anaconda.org/bioconda/samtools']
+  - package: numpy
+  - package: requests
+    version: ['2.32.3']
+    specs: ['https://pypi.org/project/requests']
+",
+        )
+        .unwrap();
+        let Some(ProjectEnvironment::Conda { path, environment }) = get_software_environment(&tool, Path::new("."), Path::new("/project")).unwrap() else {
+            panic!("Expected conda environment");
+        };
+        assert!(path.starts_with(Path::new("/project").join(ENVIRONMENT_DIR)));
+        assert_eq!(
+            environment,
+            "channels:\n- conda-forge\ndependencies:\n- bioconda::samtools=1.21\n- numpy\n- pip\n- pip:\n  - requests==2.32.3\n"
+        );
+        assert_eq!(get_software_packages(&environment).unwrap()[0].package, "samtools");
+
+        let tool = cwl::parse_cwl::<CommandLineTool>("class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs: []\noutputs: []\n").unwrap();
+        assert_eq!(get_software_environment(&tool, Path::new("."), Path::new("/project")).unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_activate_venv() {
+        let path = PathBuf::from("/project/.venv");
+        let environment = ProjectEnvironment::Venv { path, requirements: None };
+        let previous_path = env::var_os("PATH");
+
+        let previous = environment.activate();
+        let bin = get_venv_executable(Path::new("/project/.venv"), "python").parent().unwrap().to_path_buf();
+        assert_eq!(env::split_paths(&env::var_os("PATH").unwrap()).next(), Some(bin));
+        assert!(env::var_os("VIRTUAL_ENV").is_some());
+
+        deactivate(previous);
+        assert_eq!(env::var_os("PATH"), previous_path);
+    }
+}
@@ -1,3 +1,4 @@
+use cwl::error::CWLError;
 use std::{error::Error, fmt::Display};
 
 pub trait ExitCode {
@@ -23,3 +24,31 @@ impl ExitCode for CommandError {
         self.exit_code
     }
 }
+
+/// Documents that can not be read or parsed are reported like sysexits(3) `EX_NOINPUT` and `EX_DATAERR`, unsupported
+/// versions use the exit code CWL runners use for unsupported features.
+impl ExitCode for CWLError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CWLError::Io { .. } => 66,
+            CWLError::Parse { .. }
+            | CWLError::UnknownField { .. }
+            | CWLError::UnknownClass { .. }
+            | CWLError::UnknownDocument(_)
+            | CWLError::AmbiguousDocument(_) => 65,
+            CWLError::UnsupportedVersion(_) => 33,
+        }
+    }
+}
+
+/// Makes an error sendable to another thread. Errors carrying an exit code keep their type, others are passed on as
+/// their message.
+pub fn into_send_error(error: Box<dyn Error>) -> Box<dyn Error + Send + Sync> {
+    match error.downcast::<CommandError>() {
+        Ok(error) => error,
+        Err(error) => match error.downcast::<CWLError>() {
+            Ok(error) => error,
+            Err(error) => error.to_string().into(),
+        },
+    }
+}
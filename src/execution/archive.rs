@@ -0,0 +1,121 @@
+use crate::io::copy_file;
+use cwl::{
+    clt::CommandLineTool,
+    requirements::{Requirement, UnpackArchive},
+    types::CWLType,
+};
+use flate2::read::GzDecoder;
+use log::info;
+use std::{
+    error::Error,
+    fs::{self, File},
+    io,
+    path::Path,
+};
+
+const ARCHIVE_EXTENSIONS: [&str; 4] = [".tar.gz", ".tgz", ".tar", ".gz"];
+
+/// Returns the `s4n:UnpackArchive` hint if given in requirements or hints
+pub fn get_unpack_archive_hint(tool: &CommandLineTool) -> Option<&UnpackArchive> {
+    tool.requirements.iter().chain(tool.hints.iter()).flatten().find_map(|req| match req {
+        Requirement::UnpackArchive(hint) => Some(hint),
+        _ => None,
+    })
+}
+
+/// Unpacks the archives matched by the outputs listed in the `s4n:UnpackArchive` hint. The glob of those outputs is
+/// rewritten to point to the unpacked directory in the working directory of the tool afterwards.
+pub fn unpack_archive_outputs(tool: &mut CommandLineTool, working_dir: &Path, output_directory: &Path) -> Result<(), Box<dyn Error>> {
+    let Some(hint) = get_unpack_archive_hint(tool) else {
+        return Ok(());
+    };
+    let ids = hint.outputs.clone();
+    let keep_archive = hint.keep_archive;
+
+    for id in ids {
+        let output = tool
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == id)
+            .ok_or_else(|| format!("❌ Output `{id}` to unpack does not exist"))?;
+        if output.type_ != CWLType::Directory {
+            return Err(format!("❌ Output `{id}` needs to be of type Directory to be unpacked").into());
+        }
+        let Some(binding) = &mut output.output_binding else {
+            return Err(format!("❌ Output `{id}` needs a glob pointing to the archive").into());
+        };
+
+        let archive = binding.glob.clone();
+        let directory = get_unpacked_name(&archive).ok_or_else(|| format!("❌ {archive} is not a supported archive"))?;
+        unpack(&working_dir.join(&archive), &working_dir.join(&directory))?;
+        info!("📦 Unpacked {} into {}", archive, directory);
+
+        if keep_archive {
+            copy_file(working_dir.join(&archive), output_directory.join(&archive))?;
+        }
+        fs::remove_file(working_dir.join(&archive))?;
+        binding.glob = directory;
+    }
+    Ok(())
+}
+
+/// Returns the name of the directory an archive is unpacked into, e.g. `data` for `data.tar.gz`
+pub fn get_unpacked_name(archive: &str) -> Option<String> {
+    ARCHIVE_EXTENSIONS
+        .iter()
+        .find_map(|ext| archive.strip_suffix(ext))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+fn unpack(archive: &Path, directory: &Path) -> Result<(), Box<dyn Error>> {
+    let name = archive.to_string_lossy();
+    let file = File::open(archive).map_err(|e| format!("❌ Could not open archive {name}: {e}"))?;
+    fs::create_dir_all(directory)?;
+
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        tar::Archive::new(GzDecoder::new(file)).unpack(directory)?;
+    } else if name.ends_with(".tar") {
+        tar::Archive::new(file).unpack(directory)?;
+    } else {
+        //plain gzip contains a single file
+        let filename = archive.file_stem().unwrap_or_default();
+        let mut target = File::create(directory.join(filename))?;
+        io::copy(&mut GzDecoder::new(file), &mut target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_get_unpacked_name() {
+        assert_eq!(get_unpacked_name("data.tar.gz"), Some("data".to_string()));
+        assert_eq!(get_unpacked_name("data.tgz"), Some("data".to_string()));
+        assert_eq!(get_unpacked_name("results.csv.gz"), Some("results.csv".to_string()));
+        assert_eq!(get_unpacked_name("results.csv"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_unpack_tar_gz() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("data.tar.gz");
+
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(&archive).unwrap(), Compression::default()));
+        builder.append_path_with_name("tests/test_data/input.txt", "input.txt").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let target = dir.path().join("data");
+        unpack(&archive, &target).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.join("input.txt")).unwrap(),
+            fs::read_to_string("tests/test_data/input.txt").unwrap()
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::wes::{download_outputs, RunState, Submission, WesClient};
+use log::info;
+use serde_json::Value;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Engine CWL documents are run with, either in the local process or on a remote server. Inputs are given as arguments
+/// like on the command line, i.e. a job file or `--<input> <value>` pairs.
+pub trait ExecutionBackend {
+    /// Starts a run of a CWL document and returns the id it is tracked by
+    fn schedule_run(&mut self, file: &Path, args: &[String]) -> Result<String, Box<dyn Error>>;
+
+    /// Returns the current state of a run
+    fn check_status(&self, run_id: &str) -> Result<RunState, Box<dyn Error>>;
+
+    /// Makes the output files of a finished run available in the output directory and returns its output object
+    fn download_results(&self, run_id: &str, out_dir: &Path) -> Result<Value, Box<dyn Error>>;
+
+    /// Returns where to find the logs of a run, empty if they have been printed already
+    fn logs(&self, run_id: &str) -> Result<String, Box<dyn Error>>;
+
+    /// Stops a run that has not finished yet
+    fn cancel(&self, run_id: &str) -> Result<(), Box<dyn Error>>;
+
+    /// Waits between status checks, returns `false` if waiting was interrupted
+    fn wait(&self, duration: Duration) -> bool {
+        thread::sleep(duration);
+        true
+    }
+}
+
+/// Checks the state of a run in the given interval until it finished. Runs are canceled if they did not finish within
+/// the timeout or if waiting is interrupted.
+pub fn wait_for_run(backend: &dyn ExecutionBackend, run_id: &str, interval: Duration, timeout: Option<Duration>) -> Result<RunState, Box<dyn Error>> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut last_state = None;
+    loop {
+        let state = backend.check_status(run_id)?;
+        if last_state != Some(state) {
+            info!("⏳ Run {} is {}", run_id, state);
+            last_state = Some(state);
+        }
+        if state.is_finished() {
+            return Ok(state);
+        }
+        let mut interval = interval;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                backend.cancel(run_id)?;
+                let timeout = timeout.unwrap_or_default().as_secs();
+                return Err(format!("❌ Run {} did not finish within {} seconds and was canceled", run_id, timeout).into());
+            }
+            interval = interval.min(remaining);
+        }
+        if !backend.wait(interval) {
+            backend.cancel(run_id)?;
+            return Err(format!("❌ Run {} was canceled", run_id).into());
+        }
+    }
+}
+
+/// Runs documents in the local process using the custom runner or an external engine like cwltool. Runs finish while
+/// they are scheduled, failed runs are reported right away and there is nothing to cancel.
+pub struct LocalBackend<F> {
+    run: F,
+    outputs: Vec<Value>,
+}
+
+impl<F: FnMut(&Path, &[String]) -> Result<Value, Box<dyn Error>>> LocalBackend<F> {
+    /// Creates a backend executing runs using the given function, which returns the output object of a run
+    pub fn new(run: F) -> Self {
+        Self { run, outputs: vec![] }
+    }
+
+    fn get_outputs(&self, run_id: &str) -> Result<&Value, Box<dyn Error>> {
+        let outputs = run_id.parse::<usize>().ok().and_then(|index| self.outputs.get(index));
+        Ok(outputs.ok_or(format!("❌ Unknown run {}", run_id))?)
+    }
+}
+
+impl<F: FnMut(&Path, &[String]) -> Result<Value, Box<dyn Error>>> ExecutionBackend for LocalBackend<F> {
+    fn schedule_run(&mut self, file: &Path, args: &[String]) -> Result<String, Box<dyn Error>> {
+        let outputs = (self.run)(file, args)?;
+        self.outputs.push(outputs);
+        Ok((self.outputs.len() - 1).to_string())
+    }
+
+    fn check_status(&self, run_id: &str) -> Result<RunState, Box<dyn Error>> {
+        self.get_outputs(run_id).map(|_| RunState::Complete)
+    }
+
+    fn download_results(&self, run_id: &str, _out_dir: &Path) -> Result<Value, Box<dyn Error>> {
+        //local runs write their outputs to the output directory themselves
+        self.get_outputs(run_id).cloned()
+    }
+
+    fn logs(&self, run_id: &str) -> Result<String, Box<dyn Error>> {
+        self.get_outputs(run_id).map(|_| String::new())
+    }
+
+    fn cancel(&self, run_id: &str) -> Result<(), Box<dyn Error>> {
+        Err(format!("❌ Run {} already finished", run_id).into())
+    }
+}
+
+/// Runs documents on a GA4GH Workflow Execution Service (WES) server. The packed document is submitted together with
+/// the local files referenced by it or by its job file, which is the only argument supported.
+pub struct WesBackend {
+    client: WesClient,
+    prepared: Option<(PathBuf, Submission)>,
+}
+
+impl WesBackend {
+    pub fn new(client: WesClient) -> Self {
+        Self { client, prepared: None }
+    }
+
+    pub fn client(&self) -> &WesClient {
+        &self.client
+    }
+
+    /// Packs a run without uploading anything, so that it can be checked before it is scheduled
+    pub fn prepare(&mut self, file: &Path, args: &[String]) -> Result<&Submission, Box<dyn Error>> {
+        let submission = Submission::new(file, get_job_file(args)?)?;
+        Ok(&self.prepared.insert((file.to_path_buf(), submission)).1)
+    }
+}
+
+impl ExecutionBackend for WesBackend {
+    fn schedule_run(&mut self, file: &Path, args: &[String]) -> Result<String, Box<dyn Error>> {
+        let submission = match self.prepared.take() {
+            Some((prepared, submission)) if prepared == file => submission,
+            _ => Submission::new(file, get_job_file(args)?)?,
+        };
+        let workflow = submission.workflow.to_string();
+        let run_id = self
+            .client
+            .submit_run(&submission.workflow_name, &workflow, &submission.params, &submission.attachments)?;
+        //runs are canceled on the server if waiting is interrupted
+        self.client.catch_interrupts();
+        Ok(run_id)
+    }
+
+    fn check_status(&self, run_id: &str) -> Result<RunState, Box<dyn Error>> {
+        self.client.get_state(run_id)
+    }
+
+    fn download_results(&self, run_id: &str, out_dir: &Path) -> Result<Value, Box<dyn Error>> {
+        let log = self.client.get_run_log(run_id)?;
+        let mut outputs = log.get("outputs").cloned().unwrap_or(serde_json::json!({}));
+        download_outputs(&self.client, &mut outputs, out_dir)?;
+        Ok(outputs)
+    }
+
+    fn logs(&self, run_id: &str) -> Result<String, Box<dyn Error>> {
+        let log = self.client.get_run_log(run_id)?;
+        Ok(log.pointer("/run_log/stderr").and_then(|s| s.as_str()).unwrap_or_default().to_string())
+    }
+
+    fn cancel(&self, run_id: &str) -> Result<(), Box<dyn Error>> {
+        self.client.cancel_run(run_id)
+    }
+
+    fn wait(&self, duration: Duration) -> bool {
+        self.client.wait(duration)
+    }
+}
+
+/// Inputs of remote runs can only be given as job file
+fn get_job_file(args: &[String]) -> Result<Option<&Path>, Box<dyn Error>> {
+    match args {
+        [] => Ok(None),
+        [job_file] if !job_file.starts_with('-') => Ok(Some(Path::new(job_file))),
+        _ => Err("❌ Inputs of remote runs have to be given as YAML or JSON file".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_backend() {
+        let mut backend = LocalBackend::new(|file: &Path, args: &[String]| match args {
+            [] => Ok(serde_json::json!({ "file": file })),
+            _ => Err("invalid inputs".into()),
+        });
+        let run_id = backend.schedule_run(Path::new("main.cwl"), &[]).unwrap();
+        assert_eq!(wait_for_run(&backend, &run_id, Duration::ZERO, None).unwrap(), RunState::Complete);
+        assert_eq!(
+            backend.download_results(&run_id, Path::new(".")).unwrap(),
+            serde_json::json!({"file": "main.cwl"})
+        );
+        assert!(backend.cancel(&run_id).is_err());
+
+        //failed runs are reported when they are scheduled
+        assert!(backend.schedule_run(Path::new("main.cwl"), &["--message".to_string()]).is_err());
+        assert!(backend.check_status("1").is_err());
+    }
+
+    #[test]
+    fn test_get_job_file() {
+        assert_eq!(get_job_file(&[]).unwrap(), None);
+        assert_eq!(get_job_file(&["inputs.yml".to_string()]).unwrap(), Some(Path::new("inputs.yml")));
+        assert!(get_job_file(&["--message".to_string(), "hello".to_string()]).is_err());
+    }
+}
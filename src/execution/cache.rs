@@ -0,0 +1,199 @@
+use crate::io::{copy_dir, copy_file, create_and_write_file_forced};
+use cwl::{
+    clt::CommandLineTool,
+    types::{DefaultValue, OutputItem},
+};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+const OUTPUTS_FILE: &str = "outputs.json";
+const FILES_DIR: &str = "files";
+
+/// Cache used for incremental runs, relative to the project root
+pub const INCREMENTAL_CACHE: &str = ".s4n/cache/steps";
+
+/// Keys whose string values may point to files that need to be part of the hash
+const PATH_KEYS: [&str; 3] = ["location", "path", "$include"];
+
+/// Calculates the key a tool run is cached under. It is the hash of the tool description including its container image,
+/// all input values and the contents of every file or directory referenced by the tool or given as input.
+/// Relative locations of the tool are resolved against `tool_path`, those of inputs against the working directory.
+pub fn get_cache_key(tool: &CommandLineTool, input_values: Option<&HashMap<String, DefaultValue>>, tool_path: &Path) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha1::new();
+    update_hash(&mut hasher, &serde_json::to_value(tool)?, tool_path);
+    update_hash(&mut hasher, &serde_json::to_value(input_values)?, Path::new("."));
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Creates the cache used for incremental runs of the project, which is ignored by git
+pub fn get_incremental_cache_dir<P: AsRef<Path>>(project_dir: P) -> Result<PathBuf, Box<dyn Error>> {
+    let cache_dir = project_dir.as_ref().join(INCREMENTAL_CACHE);
+    fs::create_dir_all(&cache_dir)?;
+    let ignore = cache_dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(ignore, "*\n")?;
+    }
+    Ok(cache_dir)
+}
+
+/// Copies the outputs of a cached run to the output directory, returns `None` if there is no cache entry for the key
+pub fn load_cached_outputs(cache_dir: &Path, key: &str, output_directory: &Path) -> Result<Option<HashMap<String, OutputItem>>, Box<dyn Error>> {
+    let entry = cache_dir.join(key);
+    let Ok(contents) = fs::read_to_string(entry.join(OUTPUTS_FILE)) else {
+        return Ok(None);
+    };
+    let cached: HashMap<String, OutputItem> = serde_json::from_str(&contents)?;
+
+    let mut outputs = HashMap::new();
+    for (id, item) in cached {
+        copy_item(&item, &entry.join(FILES_DIR), output_directory)?;
+        let item = rebase(&item, Path::new(""), output_directory).ok_or("❌ Invalid cache entry")?;
+        outputs.insert(id, item);
+    }
+    Ok(Some(outputs))
+}
+
+/// Stores outputs of a run in the cache. Outputs located outside of the output directory can not be restored and
+/// prevent the run from being cached.
+pub fn store_cached_outputs(cache_dir: &Path, key: &str, outputs: &HashMap<String, OutputItem>, output_directory: &Path) -> Result<bool, Box<dyn Error>> {
+    let mut relative = HashMap::new();
+    for (id, item) in outputs {
+        let Some(item) = rebase(item, output_directory, Path::new("")) else {
+            return Ok(false);
+        };
+        relative.insert(id.clone(), item);
+    }
+
+    let entry = cache_dir.join(key);
+    if entry.exists() {
+        fs::remove_dir_all(&entry)?;
+    }
+    for item in relative.values() {
+        copy_item(item, output_directory, &entry.join(FILES_DIR))?;
+    }
+    //written last, so that incomplete entries are never used
+    create_and_write_file_forced(entry.join(OUTPUTS_FILE), &serde_json::to_string_pretty(&relative)?)?;
+    Ok(true)
+}
+
+fn update_hash(hasher: &mut Sha1, value: &Value, base: &Path) {
+    match value {
+        Value::Object(map) => {
+            let mut keys = map.keys().collect::<Vec<_>>();
+            keys.sort();
+            for key in keys {
+                hasher.update(key.as_bytes());
+                let value = &map[key];
+                if let (true, Some(location)) = (PATH_KEYS.contains(&key.as_str()), value.as_str()) {
+                    hash_path(hasher, &base.join(location.trim_start_matches("file://")));
+                }
+                update_hash(hasher, value, base);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| update_hash(hasher, item, base)),
+        value => hasher.update(value.to_string().as_bytes()),
+    }
+}
+
+fn hash_path(hasher: &mut Sha1, path: &Path) {
+    if path.is_file() {
+        if let Ok(contents) = fs::read(path) {
+            hasher.update(&contents);
+        }
+    } else if path.is_dir() {
+        for entry in WalkDir::new(path).sort_by_file_name().into_iter().flatten().filter(|e| e.file_type().is_file()) {
+            hasher.update(entry.path().strip_prefix(path).unwrap_or(entry.path()).to_string_lossy().as_bytes());
+            if let Ok(contents) = fs::read(entry.path()) {
+                hasher.update(&contents);
+            }
+        }
+    }
+}
+
+/// Moves the paths of an output from one base directory to another, returns `None` if it is not located in `from`
+fn rebase(item: &OutputItem, from: &Path, to: &Path) -> Option<OutputItem> {
+    let move_path = |path: &str| -> Option<PathBuf> { Some(to.join(Path::new(path).strip_prefix(from).ok()?)) };
+    Some(match item {
+        OutputItem::OutputFile(file) => {
+            let path = move_path(&file.path)?;
+            let mut file = file.clone();
+            file.path = path.to_string_lossy().into_owned();
+            file.location = format!("file://{}", file.path);
+            OutputItem::OutputFile(file)
+        }
+        OutputItem::OutputDirectory(dir) => {
+            let path = move_path(&dir.path)?;
+            let mut dir = dir.clone();
+            dir.path = path.to_string_lossy().into_owned();
+            dir.location = format!("file://{}", dir.path);
+            dir.listing = dir.listing.iter().map(|i| rebase(i, from, to)).collect::<Option<Vec<_>>>()?;
+            OutputItem::OutputDirectory(dir)
+        }
+        OutputItem::OutputString(str) => OutputItem::OutputString(str.clone()),
+        OutputItem::OutputNull => OutputItem::OutputNull,
+        OutputItem::OutputArray(items) => OutputItem::OutputArray(items.iter().map(|i| rebase(i, from, to)).collect::<Option<Vec<_>>>()?),
+    })
+}
+
+/// Copies the files of an output given with relative paths from one directory to another
+fn copy_item(item: &OutputItem, from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    match item {
+        OutputItem::OutputFile(file) => copy_file(from.join(&file.path), to.join(&file.path))?,
+        OutputItem::OutputDirectory(dir) => {
+            copy_dir(from.join(&dir.path), to.join(&dir.path))?;
+        }
+        OutputItem::OutputString(_) | OutputItem::OutputNull => {}
+        OutputItem::OutputArray(items) => items.iter().try_for_each(|i| copy_item(i, from, to))?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::util::get_file_metadata;
+    use cwl::types::File;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    #[test]
+    #[serial]
+    fn test_get_cache_key() {
+        let tool = CommandLineTool::default();
+        let inputs = HashMap::from([("file".to_string(), DefaultValue::File(File::from_location(&"tests/test_data/input.txt".to_string())))]);
+        let key = get_cache_key(&tool, Some(&inputs), Path::new(".")).unwrap();
+        assert_eq!(key, get_cache_key(&tool, Some(&inputs), Path::new(".")).unwrap());
+
+        //contents of files are part of the key
+        let other = HashMap::from([("file".to_string(), DefaultValue::File(File::from_location(&"tests/test_data/input2.txt".to_string())))]);
+        assert_ne!(key, get_cache_key(&tool, Some(&other), Path::new(".")).unwrap());
+        assert_ne!(key, get_cache_key(&tool, None, Path::new(".")).unwrap());
+    }
+
+    #[test]
+    fn test_store_and_load_cached_outputs() {
+        let cache = tempdir().unwrap();
+        let output_directory = tempdir().unwrap();
+        let file = output_directory.path().join("results.txt");
+        fs::write(&file, "Hello").unwrap();
+        let outputs = HashMap::from([("out".to_string(), OutputItem::OutputFile(get_file_metadata(&file, None)))]);
+
+        assert!(load_cached_outputs(cache.path(), "abc", output_directory.path()).unwrap().is_none());
+        assert!(store_cached_outputs(cache.path(), "abc", &outputs, output_directory.path()).unwrap());
+
+        let target = tempdir().unwrap();
+        let cached = load_cached_outputs(cache.path(), "abc", target.path()).unwrap().unwrap();
+        let OutputItem::OutputFile(cached_file) = &cached["out"] else {
+            panic!("Output is not a file")
+        };
+        assert_eq!(Path::new(&cached_file.path), target.path().join("results.txt"));
+        assert_eq!(fs::read_to_string(&cached_file.path).unwrap(), "Hello");
+    }
+}
@@ -0,0 +1,134 @@
+use crate::execution::expression::step_context;
+use cwl::{
+    types::{DefaultValue, OutputItem},
+    wf::{LinkMerge, PickValue},
+};
+use serde_json::Value;
+use std::{collections::HashMap, error::Error};
+
+/// Evaluates the `when` condition of a step against the values of its inputs, it has to evaluate to a boolean
+pub fn evaluate_condition(condition: &str, inputs: &HashMap<String, DefaultValue>, expression_lib: Vec<String>) -> Result<bool, Box<dyn Error>> {
+    match step_context(inputs, expression_lib)?.evaluate(condition, &Value::Null)? {
+        Value::Bool(value) => Ok(value),
+        value => Err(format!("❌ Condition {condition} evaluated to {value} instead of a boolean").into()),
+    }
+}
+
+/// Outputs of a step that was skipped, all of them are `null`
+pub fn skipped_outputs(output_ids: &[String]) -> HashMap<String, OutputItem> {
+    output_ids.iter().map(|id| (id.clone(), OutputItem::OutputNull)).collect()
+}
+
+/// Values of sources connected to inputs and outputs
+pub trait SourceValue: Sized {
+    fn is_null(&self) -> bool;
+    /// Items of an array, other values are a single item
+    fn into_items(self) -> Vec<Self>;
+    fn from_items(items: Vec<Self>) -> Self;
+}
+
+impl SourceValue for DefaultValue {
+    fn is_null(&self) -> bool {
+        matches!(self, DefaultValue::Any(serde_yaml::Value::Null))
+    }
+
+    fn into_items(self) -> Vec<Self> {
+        match self {
+            DefaultValue::Any(serde_yaml::Value::Sequence(items)) => items
+                .into_iter()
+                .map(|item| serde_yaml::from_value(item.clone()).unwrap_or(DefaultValue::Any(item)))
+                .collect(),
+            value => vec![value],
+        }
+    }
+
+    fn from_items(items: Vec<Self>) -> Self {
+        DefaultValue::Any(serde_yaml::Value::Sequence(items.iter().map(|i| i.to_value()).collect()))
+    }
+}
+
+impl SourceValue for OutputItem {
+    fn is_null(&self) -> bool {
+        matches!(self, OutputItem::OutputNull)
+    }
+
+    fn into_items(self) -> Vec<Self> {
+        match self {
+            OutputItem::OutputArray(items) => items,
+            value => vec![value],
+        }
+    }
+
+    fn from_items(items: Vec<Self>) -> Self {
+        OutputItem::OutputArray(items)
+    }
+}
+
+/// Combines the values of multiple sources into an array as requested by `linkMerge`
+pub fn link_merge<T: SourceValue>(values: Vec<T>, method: LinkMerge) -> T {
+    match method {
+        LinkMerge::MergeNested => T::from_items(values),
+        LinkMerge::MergeFlattened => T::from_items(values.into_iter().flat_map(T::into_items).collect()),
+    }
+}
+
+/// Removes the `null` values of a source as requested by `pickValue`, `id` is the input or output the source is
+/// connected to
+pub fn pick_value<T: SourceValue>(id: &str, values: Vec<T>, method: PickValue) -> Result<T, Box<dyn Error>> {
+    let mut values = values.into_iter().filter(|v| !v.is_null()).collect::<Vec<_>>();
+    match method {
+        PickValue::AllNonNull => Ok(T::from_items(values)),
+        PickValue::FirstNonNull if !values.is_empty() => Ok(values.remove(0)),
+        PickValue::FirstNonNull => Err(format!("❌ All values of {id} are null, but first_non_null requires one").into()),
+        PickValue::TheOnlyNonNull if values.len() == 1 => Ok(values.remove(0)),
+        PickValue::TheOnlyNonNull => Err(format!("❌ {} values of {id} are not null, but the_only_non_null requires exactly one", values.len()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> OutputItem {
+        OutputItem::OutputString(value.to_string())
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let inputs = HashMap::from([("run".to_string(), DefaultValue::Any(serde_yaml::Value::Bool(false)))]);
+        assert!(!evaluate_condition("$(inputs.run)", &inputs, vec![]).unwrap());
+        assert!(evaluate_condition("$(inputs.missing)", &inputs, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_pick_value() {
+        let values = || vec![OutputItem::OutputNull, string("a"), OutputItem::OutputNull, string("b")];
+        assert_eq!(pick_value("out", values(), PickValue::FirstNonNull).unwrap(), string("a"));
+        assert_eq!(
+            pick_value("out", values(), PickValue::AllNonNull).unwrap(),
+            OutputItem::OutputArray(vec![string("a"), string("b")])
+        );
+        assert!(pick_value("out", values(), PickValue::TheOnlyNonNull).is_err());
+        assert_eq!(pick_value("out", vec![OutputItem::OutputNull, string("a")], PickValue::TheOnlyNonNull).unwrap(), string("a"));
+        assert!(pick_value("out", vec![OutputItem::OutputNull], PickValue::FirstNonNull).is_err());
+        assert_eq!(pick_value("out", vec![OutputItem::OutputNull], PickValue::AllNonNull).unwrap(), OutputItem::OutputArray(vec![]));
+
+        let nested = || vec![OutputItem::OutputArray(vec![string("a"), string("b")]), OutputItem::OutputNull, string("c")];
+        assert_eq!(
+            link_merge(nested(), LinkMerge::MergeNested),
+            OutputItem::OutputArray(vec![OutputItem::OutputArray(vec![string("a"), string("b")]), OutputItem::OutputNull, string("c")])
+        );
+        let flattened = link_merge(nested(), LinkMerge::MergeFlattened);
+        assert_eq!(flattened, OutputItem::OutputArray(vec![string("a"), string("b"), OutputItem::OutputNull, string("c")]));
+        assert_eq!(
+            pick_value("out", flattened.into_items(), PickValue::AllNonNull).unwrap(),
+            OutputItem::OutputArray(vec![string("a"), string("b"), string("c")])
+        );
+
+        let value = DefaultValue::Any(serde_yaml::from_str("[null, 1]").unwrap());
+        assert_eq!(
+            pick_value("in", value.into_items(), PickValue::FirstNonNull).unwrap(),
+            DefaultValue::Any(serde_yaml::Value::from(1))
+        );
+    }
+}
@@ -3,42 +3,23 @@ use cwl::{
     requirements::{EnvVarRequirement, Requirement},
     types::EnviromentDefs,
 };
-use std::env;
 
-pub fn set_tool_environment_vars(tool: &CommandLineTool) -> Vec<String> {
-    let mut keys = vec![];
+/// Gets the environment variables a tool declares using `EnvVarRequirement`
+pub fn get_tool_environment_vars(tool: &CommandLineTool) -> Vec<(String, String)> {
+    let mut variables = vec![];
 
     for req in tool.requirements.iter().chain(tool.hints.iter()).flatten() {
         if let Requirement::EnvVarRequirement(env_defs) = req {
-            keys.extend(set_environment_vars(env_defs));
+            variables.extend(get_environment_vars(env_defs));
         }
     }
-    keys
+    variables
 }
 
-fn set_environment_vars(requirement: &EnvVarRequirement) -> Vec<String> {
-    let mut keys = vec![];
-
+fn get_environment_vars(requirement: &EnvVarRequirement) -> Vec<(String, String)> {
     match &requirement.env_def {
-        EnviromentDefs::Vec(vec) => {
-            for def in vec {
-                env::set_var(&def.env_name, &def.env_value);
-                keys.push(def.env_name.to_string());
-            }
-        }
-        EnviromentDefs::Map(map) => {
-            for (key, value) in map {
-                env::set_var(key, value);
-                keys.push(key.to_string());
-            }
-        }
-    }
-    keys
-}
-
-pub fn unset_environment_vars(keys: &[String]) {
-    for key in keys {
-        env::remove_var(key);
+        EnviromentDefs::Vec(vec) => vec.iter().map(|def| (def.env_name.to_string(), def.env_value.to_string())).collect(),
+        EnviromentDefs::Map(map) => map.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
     }
 }
 
@@ -46,15 +27,10 @@ pub fn unset_environment_vars(keys: &[String]) {
 mod tests {
     use super::*;
     use cwl::types::EnvironmentDef;
-    use serial_test::serial;
-    use std::{collections::HashMap, vec};
+    use std::{collections::HashMap, env, vec};
 
     #[test]
-    #[serial]
-    fn test_set_environment_vars() {
-        let mut current_vars = env::vars();
-        assert!(!current_vars.any(|v| v.0 == "MY_COOL_VAR"));
-
+    fn test_get_environment_vars() {
         let mut env_map = HashMap::new();
         env_map.insert("MY_COOL_VAR".to_string(), "my awesome value".to_string());
 
@@ -62,26 +38,16 @@ mod tests {
             env_def: EnviromentDefs::Map(env_map),
         };
 
-        let keys = set_environment_vars(&requirement);
-        assert_eq!(keys, vec!["MY_COOL_VAR"]);
-
-        //exists now!
-        let mut current_vars = env::vars();
-        assert!(current_vars.any(|v| v.0 == "MY_COOL_VAR"));
-
-        unset_environment_vars(&keys);
+        let variables = get_environment_vars(&requirement);
+        assert_eq!(variables, vec![("MY_COOL_VAR".to_string(), "my awesome value".to_string())]);
 
-        //gone again
+        //the process is left untouched
         let mut current_vars = env::vars();
         assert!(!current_vars.any(|v| v.0 == "MY_COOL_VAR"));
     }
 
     #[test]
-    #[serial]
-    fn test_set_environment_vars_envdef() {
-        let mut current_vars = env::vars();
-        assert!(!current_vars.any(|v| v.0 == "MY_COOL_VAR"));
-
+    fn test_get_environment_vars_envdef() {
         let env_def = vec![EnvironmentDef {
             env_name: "MY_COOL_VAR".to_string(),
             env_value: "my awesome value".to_string(),
@@ -91,22 +57,15 @@ mod tests {
             env_def: EnviromentDefs::Vec(env_def),
         };
 
-        let keys = set_environment_vars(&requirement);
-        assert_eq!(keys, vec!["MY_COOL_VAR"]);
-
-        //exists now!
-        let mut current_vars = env::vars();
-        assert!(current_vars.any(|v| v.0 == "MY_COOL_VAR"));
-
-        unset_environment_vars(&keys);
+        let variables = get_environment_vars(&requirement);
+        assert_eq!(variables, vec![("MY_COOL_VAR".to_string(), "my awesome value".to_string())]);
 
-        //gone again
         let mut current_vars = env::vars();
         assert!(!current_vars.any(|v| v.0 == "MY_COOL_VAR"));
     }
 
     #[test]
-    fn test_set_tool_environment_vars() {
+    fn test_get_tool_environment_vars() {
         let cwl = r#"class: CommandLineTool
 cwlVersion: v1.2
 inputs:
@@ -127,17 +86,9 @@ baseCommand: ["/bin/sh", "-c", "echo $TEST_ENV"]
 stdout: out"#;
         let tool = &serde_yaml::from_str(cwl).unwrap();
 
-        let mut current_vars = env::vars();
-        assert!(!current_vars.any(|v| v.0 == "TEST_ENV"));
-
-        set_tool_environment_vars(tool);
-
-        let mut current_vars = env::vars();
-        assert!(current_vars.any(|v| v.0 == "TEST_ENV"));
-
-        assert_eq!(env::var("TEST_ENV").unwrap(), "Hello World");
+        let variables = get_tool_environment_vars(tool);
+        assert_eq!(variables, vec![("TEST_ENV".to_string(), "Hello World".to_string())]);
 
-        env::remove_var("TEST_ENV");
         let mut current_vars = env::vars();
         assert!(!current_vars.any(|v| v.0 == "TEST_ENV"));
     }
@@ -0,0 +1,128 @@
+use crate::random::new_uuid;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::{
+    cell::RefCell,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+/// Name of the file structured events of a run are written to
+pub const EVENT_LOG_FILE: &str = "run.log.jsonl";
+
+/// Structured events of the current run, written as JSON lines to a file and printed to stderr
+#[derive(Debug)]
+struct EventLog {
+    file: PathBuf,
+    run_id: Uuid,
+    step: Option<String>,
+}
+
+thread_local!(static EVENT_LOG: RefCell<Option<EventLog>> = const { RefCell::new(None) });
+
+/// Starts writing structured events to `run.log.jsonl` in the given directory, an existing log is replaced
+pub fn start_event_log(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let file = dir.join(EVENT_LOG_FILE);
+    fs::write(&file, "")?;
+    EVENT_LOG.with(|log| {
+        *log.borrow_mut() = Some(EventLog {
+            file: file.clone(),
+            run_id: new_uuid(),
+            step: None,
+        })
+    });
+    Ok(file)
+}
+
+pub fn stop_event_log() {
+    EVENT_LOG.with(|log| *log.borrow_mut() = None);
+}
+
+/// File and run id of the current event log, used to continue the log in worker threads
+pub fn get_event_log() -> Option<(PathBuf, Uuid)> {
+    EVENT_LOG.with(|log| log.borrow().as_ref().map(|log| (log.file.clone(), log.run_id)))
+}
+
+/// Continues writing events to the log of a run started in another thread, `None` stops logging
+pub fn set_event_log(log: Option<(PathBuf, Uuid)>) {
+    EVENT_LOG.with(|event_log| *event_log.borrow_mut() = log.map(|(file, run_id)| EventLog { file, run_id, step: None }));
+}
+
+pub fn is_logging_events() -> bool {
+    EVENT_LOG.with(|log| log.borrow().is_some())
+}
+
+/// Sets the step following events belong to, `None` for events of the run itself
+pub fn set_event_step(step: Option<&str>) {
+    EVENT_LOG.with(|log| {
+        if let Some(log) = log.borrow_mut().as_mut() {
+            log.step = step.map(str::to_string);
+        }
+    });
+}
+
+/// Emits an event with the given fields, nothing happens if no event log has been started. Every event carries a
+/// timestamp, the id of the run and the name of the step it belongs to.
+pub fn log_event(event: &str, fields: Value) {
+    EVENT_LOG.with(|log| {
+        let log = log.borrow();
+        let Some(log) = log.as_ref() else {
+            return;
+        };
+        let mut entry = Map::new();
+        entry.insert("timestamp".to_string(), json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)));
+        entry.insert("run".to_string(), json!(log.run_id.to_string()));
+        entry.insert("event".to_string(), json!(event));
+        if let Some(step) = &log.step {
+            entry.insert("step".to_string(), json!(step));
+        }
+        if let Value::Object(fields) = fields {
+            entry.extend(fields);
+        }
+        let line = Value::Object(entry).to_string();
+        eprintln!("{line}");
+        //events are appended one by one, so the log is complete up to a crash
+        let written = OpenOptions::new()
+            .append(true)
+            .open(&log.file)
+            .and_then(|mut file| writeln!(file, "{line}"));
+        if let Err(e) = written {
+            eprintln!("Could not write event to {:?}: {}", log.file, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_log_event() {
+        log_event("ignored", json!({}));
+
+        let dir = tempdir().unwrap();
+        let file = start_event_log(dir.path()).unwrap();
+        log_event("run_started", json!({ "file": "main.cwl" }));
+        set_event_step(Some("calculation"));
+        log_event("step_finished", json!({ "duration_ms": 5 }));
+        stop_event_log();
+        log_event("ignored", json!({}));
+
+        let events = fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "run_started");
+        assert!(events[0].get("step").is_none());
+        assert_eq!(events[1]["step"], "calculation");
+        assert_eq!(events[1]["duration_ms"], 5);
+        assert_eq!(events[0]["run"], events[1]["run"]);
+    }
+}
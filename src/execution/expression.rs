@@ -0,0 +1,657 @@
+use crate::{
+    execution::util::{copy_output_dir, evaluate_input, get_file_metadata},
+    io::get_file_property,
+};
+use cwl::{
+    clt::{Argument, CommandLineTool},
+    outputs::CommandOutputParameter,
+    requirements::{LoadListing, Requirement},
+    types::{DefaultValue, Entry, EnviromentDefs, OutputItem},
+};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+/// Maximum number of bytes loaded into `contents` of a File
+const CONTENTS_LIMIT: u64 = 64 * 1024;
+
+/// A part of a string that may contain CWL expressions
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    /// parameter reference or javascript expression `$(...)`
+    Expression(&'a str),
+    /// javascript function body `${...}`
+    FunctionBody(&'a str),
+}
+
+/// Checks whether a string contains `$(...)` or `${...}` expressions
+pub fn has_expressions(text: &str) -> bool {
+    matches!(parse_segments(text), Ok(segments) if segments.iter().any(|s| !matches!(s, Segment::Text(_))))
+}
+
+/// Values expressions are evaluated against, `inputs` and `runtime` together with the `expressionLib`
+pub struct ExpressionContext {
+    inputs: Value,
+    runtime: Value,
+    expression_lib: Vec<String>,
+}
+
+impl ExpressionContext {
+    /// Builds the context for a tool run. Files of inputs with `loadContents` get their `contents` and Directories their
+    /// `listing` as requested by `loadListing` of the input or the tool's LoadListingRequirement.
+    pub fn new(tool: &CommandLineTool, input_values: Option<&HashMap<String, DefaultValue>>, runtime: &HashMap<String, String>) -> Result<Self, Box<dyn Error>> {
+        let default_listing = tool
+            .get_load_listing_requirement()
+            .and_then(|req| req.load_listing)
+            .unwrap_or(LoadListing::NoListing);
+        let mut inputs = Map::new();
+        for input in &tool.inputs {
+            let value = evaluate_input(input, input_values)
+                .ok()
+                .and_then(|v| serde_json::to_value(v.to_value()).ok())
+                .map(add_file_properties)
+                .unwrap_or(Value::Null);
+            let listing = input.load_listing.unwrap_or(default_listing);
+            let value = load_input(value, input.load_contents == Some(true), listing)?;
+            inputs.insert(input.id.clone(), value);
+        }
+
+        //numeric runtime values like cores and ram are exposed as numbers
+        let runtime = runtime
+            .iter()
+            .map(|(key, value)| {
+                let value = value.parse::<u64>().map(Value::from).unwrap_or_else(|_| Value::String(value.clone()));
+                (key.clone(), value)
+            })
+            .collect::<Map<_, _>>();
+
+        let expression_lib = tool
+            .get_inline_javascript_requirement()
+            .and_then(|req| req.expression_lib.clone())
+            .unwrap_or_default();
+
+        Ok(Self {
+            inputs: Value::Object(inputs),
+            runtime: Value::Object(runtime),
+            expression_lib,
+        })
+    }
+
+    /// Evaluates all expressions in the given text. If the text consists of a single expression its result is returned
+    /// as is, otherwise the results are interpolated into the text.
+    pub fn evaluate(&self, text: &str, self_: &Value) -> Result<Value, Box<dyn Error>> {
+        let segments = parse_segments(text)?;
+        if let [segment] = segments.as_slice() {
+            if !matches!(segment, Segment::Text(_)) {
+                return self.evaluate_segment(segment, self_);
+            }
+        }
+
+        let mut result = String::new();
+        for segment in &segments {
+            match self.evaluate_segment(segment, self_)? {
+                Value::String(str) => result.push_str(&str),
+                value => result.push_str(&value.to_string()),
+            }
+        }
+        Ok(Value::String(result))
+    }
+
+    /// Evaluates all expressions in the given text and returns the result as string
+    pub fn evaluate_to_string(&self, text: &str, self_: &Value) -> Result<String, Box<dyn Error>> {
+        Ok(match self.evaluate(text, self_)? {
+            Value::String(str) => str,
+            Value::Null => String::new(),
+            value => value.to_string(),
+        })
+    }
+
+    fn evaluate_segment(&self, segment: &Segment, self_: &Value) -> Result<Value, Box<dyn Error>> {
+        let code = match segment {
+            Segment::Text(text) => return Ok(Value::String(text.to_string())),
+            Segment::Expression(expression) => {
+                //parameter references do not need a javascript engine
+                if let Some(value) = self.resolve_reference(expression, self_) {
+                    return Ok(value);
+                }
+                format!("({expression})")
+            }
+            Segment::FunctionBody(body) => format!("(function(){{{body}}})()"),
+        };
+        let script = format!(
+            "var inputs = {};\nvar runtime = {};\nvar self = {};\n{}\n{}",
+            self.inputs,
+            self.runtime,
+            self_,
+            self.expression_lib.join("\n"),
+            code
+        );
+        eval_javascript(&script).map_err(|e| format!("❌ Could not evaluate expression {code}: {e}").into())
+    }
+
+    /// Resolves parameter references like `inputs.file.basename`, `self[0]` or `inputs['name']`, returns `None` for
+    /// anything else. Missing fields resolve to `null`.
+    fn resolve_reference(&self, expression: &str, self_: &Value) -> Option<Value> {
+        let expression = expression.trim();
+        let end = expression.find(['.', '[']).unwrap_or(expression.len());
+        let (root, mut rest) = expression.split_at(end);
+        let mut current = match root {
+            "inputs" => &self.inputs,
+            "runtime" => &self.runtime,
+            "self" => self_,
+            _ => return None,
+        };
+        while !rest.is_empty() {
+            let (next, tail) = if let Some(tail) = rest.strip_prefix('.') {
+                let end = tail.find(['.', '[']).unwrap_or(tail.len());
+                let (field, tail) = tail.split_at(end);
+                if field.is_empty() || !field.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return None;
+                }
+                (current.get(field), tail)
+            } else {
+                let (index, tail) = rest.strip_prefix('[')?.split_once(']')?;
+                let key = index.strip_prefix('\'').and_then(|k| k.strip_suffix('\''));
+                let key = key.or_else(|| index.strip_prefix('"').and_then(|k| k.strip_suffix('"')));
+                match key {
+                    Some(key) => (current.get(key), tail),
+                    None => (current.get(index.parse::<usize>().ok()?), tail),
+                }
+            };
+            match next {
+                Some(next) => current = next,
+                None if tail.is_empty() => return Some(Value::Null),
+                //accessing a field of undefined fails in javascript
+                None => return None,
+            }
+            rest = tail;
+        }
+        Some(current.clone())
+    }
+}
+
+/// Builds the context of a step's `when` condition, `inputs` are the values of the step inputs
+pub fn step_context(inputs: &HashMap<String, DefaultValue>, expression_lib: Vec<String>) -> Result<ExpressionContext, Box<dyn Error>> {
+    let inputs = inputs
+        .iter()
+        .map(|(id, value)| Ok((id.clone(), add_file_properties(serde_json::to_value(value.to_value())?))))
+        .collect::<Result<Map<_, _>, Box<dyn Error>>>()?;
+    Ok(ExpressionContext {
+        inputs: Value::Object(inputs),
+        runtime: Value::Object(Map::new()),
+        expression_lib,
+    })
+}
+
+#[cfg(feature = "javascript")]
+fn eval_javascript(script: &str) -> Result<Value, Box<dyn Error>> {
+    use boa_engine::{Context, Source};
+
+    let mut context = Context::default();
+    let result = context.eval(Source::from_bytes(script)).map_err(|e| e.to_string())?;
+    Ok(result.to_json(&mut context).map_err(|e| e.to_string())?.unwrap_or(Value::Null))
+}
+
+#[cfg(not(feature = "javascript"))]
+fn eval_javascript(_script: &str) -> Result<Value, Box<dyn Error>> {
+    Err("JavaScript expressions are only supported if s4n is built with the `javascript` feature".into())
+}
+
+/// Resolves a parameter reference to a part of `self` like `$(self[0].name)`, as used by `valueFrom` of step inputs to
+/// select fields and items of step outputs. No javascript is needed for these references.
+pub fn evaluate_self_reference(value: &DefaultValue, reference: &str) -> Result<DefaultValue, Box<dyn Error>> {
+    let unsupported = || format!("❌ valueFrom `{reference}` is not supported, only references like $(self[0].name) can be used");
+    let path = reference
+        .strip_prefix("$(self")
+        .and_then(|r| r.strip_suffix(')'))
+        .ok_or_else(unsupported)?;
+
+    let mut current = value.to_value();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (next, tail) = if let Some(tail) = rest.strip_prefix('.') {
+            let end = tail.find(['.', '[']).unwrap_or(tail.len());
+            let (field, tail) = tail.split_at(end);
+            (current.get(field).cloned(), tail)
+        } else if let Some((index, tail)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            let index = index.parse::<usize>().map_err(|_| unsupported())?;
+            (current.get(index).cloned(), tail)
+        } else {
+            return Err(unsupported().into());
+        };
+        current = next.ok_or_else(|| format!("❌ Could not resolve {reference}, {path} does not exist"))?;
+        rest = tail;
+    }
+    Ok(serde_yaml::from_value(current)?)
+}
+
+/// Evaluates javascript expressions in `arguments`, `valueFrom` of inputs, output globs, stdin, stdout, stderr,
+/// environment definitions and InitialWorkDirRequirement listings. Simple parameter references have already been
+/// replaced by `set_placeholder_values` at this point.
+pub fn evaluate_expressions(tool: &mut CommandLineTool, context: &ExpressionContext) -> Result<(), Box<dyn Error>> {
+    let eval = |text: &mut String| -> Result<(), Box<dyn Error>> {
+        if has_expressions(text) {
+            *text = context.evaluate_to_string(text, &Value::Null)?;
+        }
+        Ok(())
+    };
+
+    if let Some(args) = &mut tool.arguments {
+        for arg in args.iter_mut() {
+            match arg {
+                Argument::String(str) => eval(str)?,
+                Argument::Binding(binding) => {
+                    if let Some(value_from) = &mut binding.value_from {
+                        eval(value_from)?;
+                    }
+                }
+            }
+        }
+    }
+
+    //valueFrom of inputs is evaluated with self being the value of the input
+    for input in tool.inputs.iter_mut() {
+        if let Some(value_from) = input.input_binding.as_mut().and_then(|b| b.value_from.as_mut()) {
+            if has_expressions(value_from) {
+                let self_ = context.inputs.get(&input.id).cloned().unwrap_or(Value::Null);
+                *value_from = context.evaluate_to_string(value_from, &self_)?;
+            }
+        }
+    }
+
+    for output in tool.outputs.iter_mut() {
+        if let Some(binding) = &mut output.output_binding {
+            eval(&mut binding.glob)?;
+        }
+    }
+
+    for stream in [&mut tool.stdin, &mut tool.stdout, &mut tool.stderr].into_iter().flatten() {
+        eval(stream)?;
+    }
+
+    for requirement in tool.requirements.iter_mut().chain(tool.hints.iter_mut()).flatten() {
+        match requirement {
+            Requirement::EnvVarRequirement(env_req) => match &mut env_req.env_def {
+                EnviromentDefs::Vec(vec) => {
+                    for env_def in vec.iter_mut() {
+                        eval(&mut env_def.env_value)?;
+                    }
+                }
+                EnviromentDefs::Map(map) => {
+                    for value in map.values_mut() {
+                        eval(value)?;
+                    }
+                }
+            },
+            Requirement::InitialWorkDirRequirement(wd_req) => {
+                for listing in wd_req.listing.iter_mut() {
+                    eval(&mut listing.entryname)?;
+                    if let Entry::Source(src) = &mut listing.entry {
+                        eval(src)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates `outputEval` of all outputs having one. `self` is the list of files matched by the output's glob in the
+/// working directory of the tool.
+pub fn evaluate_output_expressions(
+    outputs: &[CommandOutputParameter],
+    context: &ExpressionContext,
+    working_dir: &Path,
+    initial_dir: &Path,
+) -> Result<HashMap<String, OutputItem>, Box<dyn Error>> {
+    let mut items = HashMap::new();
+    for output in outputs {
+        let Some(binding) = &output.output_binding else { continue };
+        let Some(output_eval) = &binding.output_eval else { continue };
+
+        let mut self_ = vec![];
+        let glob = working_dir.join(&binding.glob);
+        if !binding.glob.is_empty() && glob.exists() {
+            let location = glob.to_string_lossy();
+            let mut file = add_file_properties(serde_json::json!({"class": "File", "location": location}));
+            if glob.is_dir() {
+                file["class"] = Value::String("Directory".to_string());
+            } else if binding.load_contents == Some(true) {
+                file["contents"] = Value::String(load_contents(&location)?);
+            }
+            self_.push(file);
+        }
+
+        let value = context.evaluate(output_eval, &Value::Array(self_))?;
+        if let Some(item) = to_output_item(&value, output, working_dir, initial_dir)? {
+            items.insert(output.id.clone(), item);
+        }
+    }
+    Ok(items)
+}
+
+fn to_output_item(
+    value: &Value,
+    output: &CommandOutputParameter,
+    working_dir: &Path,
+    initial_dir: &Path,
+) -> Result<Option<OutputItem>, Box<dyn Error>> {
+    Ok(match value {
+        Value::Null => None,
+        Value::String(str) => Some(OutputItem::OutputString(str.clone())),
+        Value::Array(values) => Some(OutputItem::OutputArray(
+            values
+                .iter()
+                .filter_map(|v| to_output_item(v, output, working_dir, initial_dir).transpose())
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        Value::Object(map) => {
+            let location = map.get("path").or_else(|| map.get("location")).and_then(|l| l.as_str());
+            match (map.get("class").and_then(|c| c.as_str()), location) {
+                (Some("File"), Some(location)) => {
+                    let path = initial_dir.join(get_file_property(location, "basename"));
+                    let source = working_dir.join(location);
+                    fs::copy(&source, &path).map_err(|e| format!("Failed to copy file from {:?} to {:?}: {}", source, path, e))?;
+                    eprintln!("📜 Wrote output file: {:?}", path);
+                    Some(OutputItem::OutputFile(get_file_metadata(path, output.format.clone())))
+                }
+                (Some("Directory"), Some(location)) => {
+                    let path = initial_dir.join(get_file_property(location, "basename"));
+                    Some(OutputItem::OutputDirectory(copy_output_dir(working_dir.join(location), path)?))
+                }
+                _ => Some(OutputItem::OutputString(value.to_string())),
+            }
+        }
+        value => Some(OutputItem::OutputString(value.to_string())),
+    })
+}
+
+/// Adds `path`, `basename`, `nameroot`, `nameext`, `dirname` and `size` to Files and Directories
+pub fn add_file_properties(value: Value) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            let class = map.get("class").and_then(|c| c.as_str()).map(str::to_string);
+            let location = map.get("location").and_then(|l| l.as_str()).map(str::to_string);
+            if let (Some(class), Some(location)) = (class, location) {
+                if class == "File" || class == "Directory" {
+                    map.entry("path").or_insert(Value::String(location.clone()));
+                    map.entry("basename").or_insert(Value::String(get_file_property(&location, "basename")));
+                    map.entry("dirname").or_insert(Value::String(get_file_property(&location, "dirname")));
+                }
+                if class == "File" {
+                    let nameroot = Path::new(&location).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+                    map.entry("nameroot").or_insert(Value::String(nameroot));
+                    map.entry("nameext").or_insert(Value::String(get_file_property(&location, "nameext")));
+                    if let Ok(metadata) = fs::metadata(&location) {
+                        map.entry("size").or_insert(Value::from(metadata.len()));
+                    }
+                }
+            }
+            Value::Object(map.into_iter().map(|(k, v)| (k, add_file_properties(v))).collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(add_file_properties).collect()),
+        value => value,
+    }
+}
+
+/// Adds `contents` to Files and the `listing` to Directories of an input value
+fn load_input(value: Value, contents: bool, listing: LoadListing) -> Result<Value, Box<dyn Error>> {
+    Ok(match value {
+        Value::Object(mut map) => {
+            let location = map.get("location").and_then(|l| l.as_str()).map(str::to_string);
+            match (map.get("class").and_then(|c| c.as_str()), location) {
+                (Some("File"), Some(location)) if contents => {
+                    if fs::metadata(&location).is_ok_and(|m| m.len() > CONTENTS_LIMIT) {
+                        return Err(format!("❌ File {location} is larger than 64 KiB and can not be loaded using loadContents").into());
+                    }
+                    map.insert("contents".to_string(), Value::String(load_contents(&location)?));
+                }
+                (Some("Directory"), Some(location)) if listing != LoadListing::NoListing && !map.contains_key("listing") => {
+                    let listing = get_listing(Path::new(&location), listing == LoadListing::DeepListing)?;
+                    map.insert("listing".to_string(), Value::Array(listing));
+                }
+                _ => {}
+            }
+            Value::Object(map)
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(|v| load_input(v, contents, listing)).collect::<Result<_, _>>()?),
+        value => value,
+    })
+}
+
+/// Lists the contents of a directory as Files and Directories, the listing of subdirectories is only included if `deep`
+fn get_listing(path: &Path, deep: bool) -> Result<Vec<Value>, Box<dyn Error>> {
+    let mut entries = fs::read_dir(path)
+        .map_err(|e| format!("❌ Could not list directory {:?}: {}", path, e))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut listing = vec![];
+    for entry in entries {
+        let location = entry.path().to_string_lossy().into_owned();
+        if entry.path().is_dir() {
+            let mut dir = add_file_properties(serde_json::json!({"class": "Directory", "location": location}));
+            if deep {
+                dir["listing"] = Value::Array(get_listing(&entry.path(), deep)?);
+            }
+            listing.push(dir);
+        } else {
+            listing.push(add_file_properties(serde_json::json!({"class": "File", "location": location})));
+        }
+    }
+    Ok(listing)
+}
+
+fn load_contents(path: &str) -> Result<String, Box<dyn Error>> {
+    let mut buffer = vec![];
+    File::open(path)?.take(CONTENTS_LIMIT).read_to_end(&mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Splits a string into literal text, `$(...)` expressions and `${...}` function bodies. Brackets inside of
+/// javascript string literals are ignored, `\$(` escapes an expression.
+fn parse_segments(text: &str) -> Result<Vec<Segment<'_>>, Box<dyn Error>> {
+    let bytes = text.as_bytes();
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'$') {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == b'$' && matches!(bytes.get(i + 1), Some(b'(') | Some(b'{')) {
+            let (open, close) = if bytes[i + 1] == b'(' { (b'(', b')') } else { (b'{', b'}') };
+            let end = find_closing(bytes, i + 1, open, close).ok_or_else(|| format!("❌ Unterminated expression in `{text}`"))?;
+            if start < i {
+                segments.push(Segment::Text(&text[start..i]));
+            }
+            let inner = &text[i + 2..end];
+            segments.push(if open == b'(' { Segment::Expression(inner) } else { Segment::FunctionBody(inner) });
+            i = end + 1;
+            start = i;
+            continue;
+        }
+        i += 1;
+    }
+    if start < bytes.len() {
+        segments.push(Segment::Text(&text[start..]));
+    }
+    Ok(segments)
+}
+
+/// Returns the index of the bracket closing the one at `open_index`
+fn find_closing(bytes: &[u8], open_index: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<u8> = None;
+    let mut i = open_index;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if let Some(q) = quote {
+            if byte == b'\\' {
+                i += 1;
+            } else if byte == q {
+                quote = None;
+            }
+        } else if matches!(byte, b'"' | b'\'' | b'`') {
+            quote = Some(byte);
+        } else if byte == open {
+            depth += 1;
+        } else if byte == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cwl::types::File;
+    use serial_test::serial;
+
+    #[test]
+    fn test_parse_segments() {
+        let segments = parse_segments("out_$(inputs.name + \")\").txt ${ return 1; }").unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Text("out_"),
+                Segment::Expression("inputs.name + \")\""),
+                Segment::Text(".txt "),
+                Segment::FunctionBody(" return 1; "),
+            ]
+        );
+        assert_eq!(parse_segments("no \\$(expression)").unwrap(), vec![Segment::Text("no \\$(expression)")]);
+        assert!(parse_segments("$(inputs.name").is_err());
+    }
+
+    #[test]
+    fn test_has_expressions() {
+        assert!(has_expressions("$(inputs.file.nameroot).txt"));
+        assert!(has_expressions("${ return 42; }"));
+        assert!(!has_expressions("results.csv"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_context_load_contents_and_listing() {
+        let tool: CommandLineTool = serde_yaml::from_str(
+            r"
+class: CommandLineTool
+cwlVersion: v1.2
+requirements:
+- class: LoadListingRequirement
+  loadListing: deep_listing
+inputs:
+- id: file
+  type: File
+  loadContents: true
+  default: {class: File, location: tests/test_data/input.txt}
+- id: shallow
+  type: Directory
+  loadListing: shallow_listing
+  default: {class: Directory, location: tests/test_data}
+- id: deep
+  type: Directory
+  default: {class: Directory, location: tests/test_data/test_dir}
+outputs: []
+",
+        )
+        .unwrap();
+        let context = ExpressionContext::new(&tool, None, &HashMap::new()).unwrap();
+
+        assert_eq!(context.inputs["file"]["contents"], "Hello fellow CWL-enjoyers!");
+        let shallow = context.inputs["shallow"]["listing"].as_array().unwrap();
+        let test_dir = shallow.iter().find(|e| e["basename"] == "test_dir").unwrap();
+        assert_eq!(test_dir["class"], "Directory");
+        assert!(test_dir.get("listing").is_none());
+        //requirement applies to inputs without loadListing
+        let deep = context.inputs["deep"]["listing"].as_array().unwrap();
+        assert_eq!(deep.iter().map(|e| e["basename"].as_str().unwrap()).collect::<Vec<_>>(), vec!["file.txt", "input.txt"]);
+    }
+
+    #[test]
+    fn test_load_input_too_large() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.txt");
+        fs::write(&path, vec![b'a'; CONTENTS_LIMIT as usize + 1]).unwrap();
+        let value = serde_json::json!({"class": "File", "location": path.to_string_lossy()});
+        assert!(load_input(value.clone(), true, LoadListing::NoListing).is_err());
+        assert!(load_input(value, false, LoadListing::NoListing).is_ok());
+    }
+
+    #[cfg(feature = "javascript")]
+    fn context() -> ExpressionContext {
+        ExpressionContext {
+            inputs: serde_json::json!({"name": "world", "numbers": [1, 2, 3]}),
+            runtime: serde_json::json!({"cores": 4}),
+            expression_lib: vec!["function greet(name) { return 'Hello ' + name; }".to_string()],
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "javascript")]
+    fn test_evaluate_expression() {
+        let context = context();
+        assert_eq!(context.evaluate("$(inputs.numbers.length * runtime.cores)", &Value::Null).unwrap(), Value::from(12));
+        assert_eq!(context.evaluate_to_string("$(greet(inputs.name))!", &Value::Null).unwrap(), "Hello world!");
+        assert_eq!(
+            context
+                .evaluate("${ return inputs.numbers.map(function(n) { return n * 2; }); }", &Value::Null)
+                .unwrap(),
+            serde_json::json!([2, 4, 6])
+        );
+        assert_eq!(context.evaluate("$(self[0].basename)", &serde_json::json!([{"basename": "a.txt"}])).unwrap(), "a.txt");
+    }
+
+    #[test]
+    fn test_evaluate_self_reference() {
+        let value = DefaultValue::Any(serde_yaml::from_str("[{name: a.txt, size: 1}, {class: File, location: b.txt}]").unwrap());
+        assert_eq!(
+            evaluate_self_reference(&value, "$(self[0].name)").unwrap(),
+            DefaultValue::Any(serde_yaml::Value::String("a.txt".to_string()))
+        );
+        assert!(matches!(evaluate_self_reference(&value, "$(self[1])").unwrap(), DefaultValue::File(file) if file.location == "b.txt"));
+        assert!(evaluate_self_reference(&value, "$(self[2])").is_err());
+        assert!(evaluate_self_reference(&value, "$(self.length)").is_err());
+        assert!(evaluate_self_reference(&value, "$(inputs.name)").is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "javascript"))]
+    fn test_evaluate_expression_unsupported() {
+        let context = ExpressionContext {
+            inputs: Value::Null,
+            runtime: Value::Null,
+            expression_lib: vec![],
+        };
+        assert!(context.evaluate("$(1 + 1)", &Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_reference() {
+        let inputs = HashMap::from([
+            ("run".to_string(), DefaultValue::Any(serde_yaml::Value::Bool(true))),
+            ("file".to_string(), DefaultValue::File(File::from_location(&"data/input.txt".to_string()))),
+        ]);
+        let context = step_context(&inputs, vec![]).unwrap();
+        assert_eq!(context.evaluate("$(inputs.run)", &Value::Null).unwrap(), Value::Bool(true));
+        assert_eq!(context.evaluate("$(inputs['file'].basename)", &Value::Null).unwrap(), "input.txt");
+        assert_eq!(context.evaluate("$(inputs.missing)", &Value::Null).unwrap(), Value::Null);
+        assert_eq!(context.evaluate("$(self[1])", &serde_json::json!([1, 2])).unwrap(), Value::from(2));
+        assert_eq!(context.resolve_reference("inputs.missing.field", &Value::Null), None);
+        assert_eq!(context.resolve_reference("inputs.run === true", &Value::Null), None);
+    }
+}
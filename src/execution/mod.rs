@@ -1,5 +1,17 @@
+pub mod archive;
+pub mod backend;
+pub mod cache;
+pub mod conditional;
 pub mod environment;
+pub mod events;
+pub mod expression;
+pub mod notify;
+pub mod provenance;
+pub mod resources;
 pub mod runner;
+pub mod runs;
+pub mod sandbox;
+pub mod scatter;
 pub mod staging;
 pub mod util;
 pub mod validate;
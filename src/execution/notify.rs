@@ -0,0 +1,143 @@
+use crate::io::get_shell_command;
+use log::warn;
+use reqwest::Client;
+use serde::Serialize;
+use std::{error::Error, path::Path, time::Duration};
+use tokio::runtime::Builder;
+
+/// Outcome of a finished run as posted to webhooks
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RunNotification {
+    pub file: String,
+    /// Either `success` or `failed`
+    pub status: String,
+    pub duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Summary of the run, which chat services like Slack or Mattermost display as message
+    pub text: String,
+}
+
+impl RunNotification {
+    pub fn new(file: &Path, result: &Result<(), Box<dyn Error>>, duration: Duration) -> Self {
+        let file = file.to_string_lossy().into_owned();
+        let (status, text) = match result {
+            Ok(_) => ("success", format!("✔️ {file} finished successfully in {:.0?}", duration)),
+            Err(e) => ("failed", format!("❌ {file} failed after {:.0?}: {e}", duration)),
+        };
+        RunNotification {
+            status: status.to_string(),
+            duration_ms: duration.as_millis(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            text,
+            file,
+        }
+    }
+}
+
+/// Notifies about a finished run, failing hooks are reported but do not fail the run
+pub fn notify(notification: &RunNotification, webhook: Option<&str>, command: Option<&str>) {
+    if let Some(url) = webhook {
+        if let Err(e) = send_webhook(url, notification) {
+            warn!("Could not notify {}: {}", url, e);
+        }
+    }
+    if let Some(command) = command {
+        if let Err(e) = run_notify_command(command, notification) {
+            warn!("Notification command `{}` failed: {}", command, e);
+        }
+    }
+}
+
+/// Posts the notification as JSON to the given URL
+pub fn send_webhook(url: &str, notification: &RunNotification) -> Result<(), Box<dyn Error>> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    let request = Client::new().post(url).json(notification).timeout(Duration::from_secs(30));
+    runtime.block_on(async { request.send().await?.error_for_status() })?;
+    Ok(())
+}
+
+/// Runs a shell command, the outcome of the run is passed as `S4N_RUN_FILE`, `S4N_RUN_STATUS`, `S4N_RUN_DURATION` in
+/// seconds and `S4N_RUN_ERROR` environment variables
+pub fn run_notify_command(command: &str, notification: &RunNotification) -> Result<(), Box<dyn Error>> {
+    let status = get_shell_command()
+        .arg(command)
+        .env("S4N_RUN_FILE", &notification.file)
+        .env("S4N_RUN_STATUS", &notification.status)
+        .env("S4N_RUN_DURATION", format!("{:.1}", notification.duration_ms as f64 / 1000.0))
+        .env("S4N_RUN_ERROR", notification.error.as_deref().unwrap_or_default())
+        .status()?;
+    if !status.success() {
+        return Err(format!("exited with {}", status).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_run_notification() {
+        let notification = RunNotification::new(Path::new("main.cwl"), &Err("step failed".into()), Duration::from_millis(1500));
+        assert_eq!(notification.status, "failed");
+        assert_eq!(notification.duration_ms, 1500);
+        assert_eq!(notification.error.as_deref(), Some("step failed"));
+        assert!(notification.text.starts_with("❌ main.cwl failed after 2s"));
+    }
+
+    #[test]
+    fn test_send_webhook() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = vec![0; 65536];
+            let mut request = String::new();
+            //read until the announced body is complete
+            loop {
+                let n = stream.read(&mut buffer).unwrap();
+                request.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                let Some(end) = request.find("\r\n\r\n") else { continue };
+                let length = request
+                    .lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length || n == 0 {
+                    break;
+                }
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            request
+        });
+
+        let notification = RunNotification::new(Path::new("main.cwl"), &Ok(()), Duration::from_secs(3));
+        send_webhook(&url, &notification).unwrap();
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("POST /hook"));
+        let body: Value = serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(body["status"], "success");
+        assert_eq!(body["duration_ms"], 3000);
+        assert!(body.get("error").is_none());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_run_notify_command() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("status.txt");
+        let notification = RunNotification::new(Path::new("main.cwl"), &Ok(()), Duration::from_millis(2500));
+        let command = format!("echo \"$S4N_RUN_FILE $S4N_RUN_STATUS $S4N_RUN_DURATION\" > {}", file.to_string_lossy());
+        run_notify_command(&command, &notification).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "main.cwl success 2.5\n");
+        assert!(run_notify_command("exit 3", &notification).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+use super::resources::ResourceUsage;
+use crate::{
+    io::{create_and_write_file_forced, get_file_checksum},
+    random::new_uuid,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use cwl::{
+    packed::pack_workflow,
+    types::{DefaultValue, OutputItem},
+};
+use log::info;
+use serde_json::{json, Map, Value};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use uuid::Uuid;
+
+/// Interpreters whose version is recorded for the steps calling them
+const KNOWN_INTERPRETERS: [&str; 7] = ["python", "python3", "Rscript", "node", "perl", "julia", "java"];
+
+/// Everything recorded during a run that is needed to write the research object
+#[derive(Debug)]
+pub struct Provenance {
+    bundle: PathBuf,
+    run_id: Uuid,
+    started: DateTime<Utc>,
+    steps: Vec<StepRun>,
+}
+
+/// A single execution of a CommandLineTool, either standalone or as step of a workflow
+#[derive(Debug)]
+struct StepRun {
+    id: Uuid,
+    name: String,
+    started: DateTime<Utc>,
+    ended: Option<DateTime<Utc>>,
+    command: Option<String>,
+    tool_version: Option<String>,
+    resources: Option<ResourceUsage>,
+    inputs: Vec<(String, Value)>,
+    outputs: Vec<(String, Value)>,
+}
+
+thread_local!(static PROVENANCE: RefCell<Option<Provenance>> = const { RefCell::new(None) });
+
+/// Starts recording provenance, payload files are collected in the given bundle directory while the run proceeds
+pub fn start_provenance(bundle: &Path) -> Result<(), Box<dyn Error>> {
+    let bundle = env::current_dir()?.join(bundle);
+    if bundle.exists() && fs::read_dir(&bundle)?.next().is_some() {
+        return Err(format!("❌ Provenance directory {:?} is not empty", bundle).into());
+    }
+    fs::create_dir_all(bundle.join("data"))?;
+    PROVENANCE.with(|provenance| {
+        *provenance.borrow_mut() = Some(Provenance {
+            bundle,
+            run_id: new_uuid(),
+            started: Utc::now(),
+            steps: vec![],
+        })
+    });
+    Ok(())
+}
+
+pub fn is_recording() -> bool {
+    PROVENANCE.with(|provenance| provenance.borrow().is_some())
+}
+
+/// Records the start of a tool execution together with its input values
+pub fn record_step_start(name: &str, inputs: &HashMap<String, DefaultValue>) {
+    with_provenance(|provenance| {
+        let inputs = inputs.iter().map(|(id, value)| (id.clone(), provenance.add_value(&value.to_value()))).collect();
+        provenance.steps.push(StepRun {
+            id: new_uuid(),
+            name: name.to_string(),
+            started: Utc::now(),
+            ended: None,
+            command: None,
+            tool_version: None,
+            resources: None,
+            inputs,
+            outputs: vec![],
+        });
+    });
+}
+
+/// Records the exact command line of the currently running tool
+pub fn record_command(command: &Command) {
+    with_provenance(|provenance| {
+        if let Some(step) = provenance.steps.last_mut() {
+            let program = command.get_program().to_string_lossy().into_owned();
+            let args = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect::<Vec<_>>();
+            step.command = Some(shlex::try_join(std::iter::once(program.as_str()).chain(args.iter().map(String::as_str))).unwrap_or(program.clone()));
+            step.tool_version = get_tool_version(&program);
+        }
+    });
+}
+
+/// Records the resources used by the command of the currently running tool
+pub fn record_resources(usage: &ResourceUsage) {
+    with_provenance(|provenance| {
+        if let Some(step) = provenance.steps.last_mut() {
+            step.resources = Some(*usage);
+        }
+    });
+}
+
+/// Records the end of the currently running tool together with its outputs
+pub fn record_step_end(outputs: &HashMap<String, OutputItem>) {
+    with_provenance(|provenance| {
+        let outputs = outputs
+            .iter()
+            .map(|(id, item)| (id.clone(), provenance.add_output(item)))
+            .collect::<Vec<_>>();
+        if let Some(step) = provenance.steps.iter_mut().rev().find(|s| s.ended.is_none()) {
+            step.ended = Some(Utc::now());
+            step.outputs = outputs;
+        }
+    });
+}
+
+/// Stops recording and writes a CWLProv research object bundle for the given CWL file, job inputs and final outputs
+pub fn finish_provenance(
+    cwl_path: &Path,
+    inputs: &HashMap<String, DefaultValue>,
+    outputs: &HashMap<String, OutputItem>,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let provenance = PROVENANCE
+        .with(|provenance| provenance.borrow_mut().take())
+        .ok_or("❌ Provenance is not being recorded")?;
+    let bundle = provenance.bundle.clone();
+
+    let inputs = inputs
+        .iter()
+        .map(|(id, value)| (id.clone(), provenance.add_value(&value.to_value())))
+        .collect::<Vec<_>>();
+    let outputs = outputs.iter().map(|(id, item)| (id.clone(), provenance.add_output(item))).collect::<Vec<_>>();
+
+    //workflow description and job
+    let packed = pack_workflow(cwl_path)?;
+    create_and_write_file_forced(bundle.join("workflow/packed.cwl"), &packed.to_string())?;
+    let job = inputs.iter().cloned().collect::<Map<_, _>>();
+    create_and_write_file_forced(bundle.join("workflow/primary-job.json"), &serde_json::to_string_pretty(&job)?)?;
+
+    let document = provenance.to_prov_json(&inputs, &outputs);
+    create_and_write_file_forced(
+        bundle.join("metadata/provenance/primary.cwlprov.json"),
+        &serde_json::to_string_pretty(&document)?,
+    )?;
+    create_and_write_file_forced(bundle.join("metadata/manifest.json"), &serde_json::to_string_pretty(&provenance.manifest()?)?)?;
+
+    //bagit structure with checksums for payload and tag files
+    create_and_write_file_forced(bundle.join("bagit.txt"), "BagIt-Version: 0.97\nTag-File-Character-Encoding: UTF-8\n")?;
+    create_and_write_file_forced(bundle.join("manifest-sha1.txt"), &get_checksum_manifest(&bundle, |p| p.starts_with("data"))?)?;
+    create_and_write_file_forced(
+        bundle.join("tagmanifest-sha1.txt"),
+        &get_checksum_manifest(&bundle, |p| !p.starts_with("data") && !p.ends_with("tagmanifest-sha1.txt"))?,
+    )?;
+
+    info!("🔏 Provenance was written to {:?}", bundle);
+    Ok(bundle)
+}
+
+/// Stops recording without writing anything, e.g. if the execution failed
+pub fn abort_provenance() {
+    PROVENANCE.with(|provenance| provenance.borrow_mut().take());
+}
+
+fn with_provenance<F: FnOnce(&mut Provenance)>(f: F) {
+    PROVENANCE.with(|provenance| {
+        if let Some(provenance) = provenance.borrow_mut().as_mut() {
+            f(provenance)
+        }
+    });
+}
+
+impl Provenance {
+    /// Copies a file into the payload of the bundle and returns its content addressed location
+    fn add_file(&self, path: &Path) -> Option<String> {
+        let checksum = get_file_checksum(path).ok()?;
+        let target = self.bundle.join("data").join(&checksum[..2]).join(&checksum);
+        if !target.exists() {
+            fs::create_dir_all(target.parent()?).ok()?;
+            fs::copy(path, &target).ok()?;
+        }
+        Some(checksum)
+    }
+
+    /// Adds checksums and payload references to Files inside of a value
+    fn add_value(&self, value: &serde_yaml::Value) -> Value {
+        let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.annotate_files(&mut value);
+        value
+    }
+
+    fn add_output(&self, item: &OutputItem) -> Value {
+        let mut value = serde_json::to_value(item).unwrap_or(Value::Null);
+        self.annotate_files(&mut value);
+        value
+    }
+
+    fn annotate_files(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                if map.get("class").and_then(|c| c.as_str()) == Some("File") {
+                    let path = map.get("path").or_else(|| map.get("location")).and_then(|p| p.as_str()).map(|p| p.trim_start_matches("file://").to_string());
+                    if let Some(checksum) = path.and_then(|p| self.add_file(Path::new(&p))) {
+                        map.insert("checksum".to_string(), Value::String(format!("sha1${checksum}")));
+                        map.insert("location".to_string(), Value::String(format!("../data/{}/{checksum}", &checksum[..2])));
+                        map.remove("path");
+                    }
+                }
+                for value in map.values_mut() {
+                    self.annotate_files(value);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.annotate_files(v)),
+            _ => {}
+        }
+    }
+
+    /// Creates the PROV-JSON document describing the run
+    fn to_prov_json(&self, inputs: &[(String, Value)], outputs: &[(String, Value)]) -> Value {
+        let mut document = ProvDocument::default();
+        let agent = format!("id:{}", new_uuid());
+        document.insert("agent", &agent, json!({
+            "prov:type": qualified("prov:SoftwareAgent"),
+            "prov:label": format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        }));
+
+        let run = format!("id:{}", self.run_id);
+        document.insert("activity", &run, json!({
+            "prov:type": qualified("wfprov:WorkflowRun"),
+            "prov:label": "Run of workflow/packed.cwl#main",
+            "prov:startTime": format_time(&self.started),
+            "prov:endTime": format_time(&Utc::now()),
+        }));
+        document.relation("wasAssociatedWith", json!({"prov:activity": run, "prov:agent": agent}));
+        document.add_io(&run, "main", inputs, outputs);
+
+        for step in &self.steps {
+            let activity = format!("id:{}", step.id);
+            let mut attributes = json!({
+                "prov:type": qualified("wfprov:ProcessRun"),
+                "prov:label": format!("Run of workflow/packed.cwl#main/{}", step.name),
+                "prov:startTime": format_time(&step.started),
+            });
+            if let Some(ended) = &step.ended {
+                attributes["prov:endTime"] = Value::String(format_time(ended));
+            }
+            if let Some(command) = &step.command {
+                attributes["cwlprov:command"] = Value::String(command.clone());
+            }
+            if let Some(version) = &step.tool_version {
+                attributes["cwlprov:toolVersion"] = Value::String(version.clone());
+            }
+            if let Some(usage) = &step.resources {
+                attributes["s4n:wallTimeMs"] = json!(usage.wall_time_ms);
+                attributes["s4n:cpuTimeMs"] = json!(usage.cpu_time_ms);
+                attributes["s4n:peakMemoryBytes"] = json!(usage.peak_memory_bytes);
+                attributes["s4n:diskWrittenBytes"] = json!(usage.disk_written_bytes);
+            }
+            document.insert("activity", &activity, attributes);
+            document.relation("wasStartedBy", json!({"prov:activity": activity, "prov:starter": run}));
+            document.add_io(&activity, &format!("main/{}", step.name), &step.inputs, &step.outputs);
+        }
+        document.into_value()
+    }
+
+    /// Creates the Research Object manifest aggregating all files of the bundle
+    fn manifest(&self) -> Result<Value, Box<dyn Error>> {
+        let mut aggregates = vec![];
+        for path in get_files(&self.bundle)? {
+            let relative = path.strip_prefix(&self.bundle)?.to_string_lossy().replace('\\', "/");
+            if relative.starts_with("metadata/manifest.json") {
+                continue;
+            }
+            aggregates.push(json!({
+                "uri": format!("../{relative}"),
+                "bundledAs": {"uri": format!("urn:uuid:{}", new_uuid()), "folder": format!("/{}", relative.rsplit_once('/').map(|(f, _)| f).unwrap_or(""))},
+            }));
+        }
+        Ok(json!({
+            "@context": ["https://w3id.org/bundle/context"],
+            "id": "/",
+            "manifest": "manifest.json",
+            "createdOn": format_time(&self.started),
+            "createdBy": {"name": format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))},
+            "conformsTo": ["https://w3id.org/cwl/prov/0.6.0", "https://w3id.org/ro/crate/bagit"],
+            "aggregates": aggregates,
+            "annotations": [{
+                "about": format!("urn:uuid:{}", self.run_id),
+                "content": ["/metadata/provenance/primary.cwlprov.json"],
+                "oa:motivatedBy": {"@id": "http://www.w3.org/ns/prov#has_provenance"},
+            }],
+        }))
+    }
+}
+
+/// Helper to build PROV-JSON documents which group records by their type
+#[derive(Default)]
+struct ProvDocument {
+    records: Map<String, Value>,
+    counter: usize,
+}
+
+impl ProvDocument {
+    fn insert(&mut self, kind: &str, id: &str, attributes: Value) {
+        let records = self.records.entry(kind).or_insert_with(|| Value::Object(Map::new()));
+        records[id] = attributes;
+    }
+
+    fn relation(&mut self, kind: &str, attributes: Value) {
+        self.counter += 1;
+        let id = format!("_:id{}", self.counter);
+        self.insert(kind, &id, attributes);
+    }
+
+    /// Adds entities for all values and links them to the activity using `used` and `wasGeneratedBy`
+    fn add_io(&mut self, activity: &str, prefix: &str, inputs: &[(String, Value)], outputs: &[(String, Value)]) {
+        for (kind, values) in [("used", inputs), ("wasGeneratedBy", outputs)] {
+            for (id, value) in values {
+                let entity = self.add_entity(value);
+                self.relation(kind, json!({"prov:activity": activity, "prov:entity": entity, "prov:role": format!("wf:{prefix}/{id}")}));
+            }
+        }
+    }
+
+    fn add_entity(&mut self, value: &Value) -> String {
+        let checksum = value.get("checksum").and_then(|c| c.as_str()).and_then(|c| c.strip_prefix("sha1$"));
+        match checksum {
+            Some(checksum) => {
+                let id = format!("data:{checksum}");
+                let label = value.get("basename").cloned().unwrap_or(Value::Null);
+                self.insert("entity", &id, json!({"prov:type": qualified("wfprov:Artifact"), "prov:label": label}));
+                id
+            }
+            None => {
+                let id = format!("id:{}", new_uuid());
+                self.insert("entity", &id, json!({"prov:type": qualified("wfprov:Artifact"), "prov:value": value.to_string()}));
+                id
+            }
+        }
+    }
+
+    fn into_value(self) -> Value {
+        let mut document = Map::new();
+        document.insert(
+            "prefix".to_string(),
+            json!({
+                "wfprov": "http://purl.org/wf4ever/wfprov#",
+                "wfdesc": "http://purl.org/wf4ever/wfdesc#",
+                "cwlprov": "https://w3id.org/cwl/prov#",
+                "s4n": concat!(env!("CARGO_PKG_REPOSITORY"), "#"),
+                "prov": "http://www.w3.org/ns/prov#",
+                "id": "urn:uuid:",
+                "data": "urn:hash::sha1:",
+                "wf": "workflow/packed.cwl#",
+            }),
+        );
+        document.extend(self.records);
+        Value::Object(document)
+    }
+}
+
+fn qualified(name: &str) -> Value {
+    json!({"$": name, "type": "prov:QUALIFIED_NAME"})
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Asks well known interpreters for their version, other executables are not called to not cause side effects
+fn get_tool_version(program: &str) -> Option<String> {
+    let name = Path::new(program).file_stem()?.to_string_lossy().to_string();
+    if !KNOWN_INTERPRETERS.contains(&name.as_str()) {
+        return None;
+    }
+    let output = Command::new(program).arg("--version").output().ok()?;
+    //some tools print their version to stderr
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    String::from_utf8_lossy(&text).lines().next().map(|l| l.trim().to_string())
+}
+
+fn get_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = vec![];
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn get_checksum_manifest<F: Fn(&str) -> bool>(bundle: &Path, filter: F) -> Result<String, Box<dyn Error>> {
+    let mut manifest = String::new();
+    for path in get_files(bundle)? {
+        let relative = path.strip_prefix(bundle)?.to_string_lossy().replace('\\', "/");
+        if filter(&relative) {
+            manifest.push_str(&format!("{}  {}\n", get_file_checksum(&path)?, relative));
+        }
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prov_document() {
+        let mut document = ProvDocument::default();
+        document.insert("activity", "id:run", json!({"prov:label": "run"}));
+        let entity = document.add_entity(&json!({"class": "File", "basename": "a.txt", "checksum": "sha1$abc"}));
+        assert_eq!(entity, "data:abc");
+        document.add_io("id:run", "main", &[("file".to_string(), json!({"checksum": "sha1$abc"}))], &[]);
+
+        let value = document.into_value();
+        assert!(value["prefix"]["wfprov"].is_string());
+        assert_eq!(value["used"]["_:id1"]["prov:entity"], "data:abc");
+        assert_eq!(value["used"]["_:id1"]["prov:role"], "wf:main/file");
+    }
+
+    #[test]
+    fn test_get_tool_version_unknown() {
+        assert_eq!(get_tool_version("rm"), None);
+    }
+}
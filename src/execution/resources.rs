@@ -0,0 +1,260 @@
+use prettytable::{row, Table};
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use walkdir::WalkDir;
+
+/// Longest time between two samples of a running command, short commands are sampled more often
+const MAX_SAMPLE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Resources used by a command including all processes it started. CPU time, memory and disk usage are sampled while
+/// the command is running, so they are estimates for commands finishing in a few milliseconds.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub wall_time_ms: u64,
+    pub cpu_time_ms: u64,
+    pub peak_memory_bytes: u64,
+    pub disk_written_bytes: u64,
+}
+
+thread_local!(static USAGES: RefCell<Vec<(String, ResourceUsage)>> = const { RefCell::new(vec![]) });
+
+/// Remembers the resources used by a step for the summary of the run
+pub fn record_usage(step: &str, usage: ResourceUsage) {
+    USAGES.with(|usages| usages.borrow_mut().push((step.to_string(), usage)));
+}
+
+/// Returns the resources used by all steps recorded so far and starts over
+pub fn take_usages() -> Vec<(String, ResourceUsage)> {
+    USAGES.with(|usages| usages.take())
+}
+
+/// Maximum number of bytes a command may add to a directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskQuota {
+    pub directory: PathBuf,
+    pub bytes: u64,
+}
+
+/// Runs a command like [`Command::output`] while sampling the resources used by it and its child processes. Commands
+/// exceeding the given disk quota are killed.
+pub fn run_monitored(command: &mut Command, quota: Option<&DiskQuota>) -> io::Result<(Output, ResourceUsage)> {
+    let clock = Instant::now();
+    //files already in the directory, like staged inputs, do not count towards the quota
+    let initial_size = quota.map(|quota| get_directory_size(&quota.directory)).unwrap_or_default();
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    //pipes are read in the background, otherwise commands writing a lot of output would block
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let mut monitor = Monitor::new(Pid::from_u32(child.id()));
+    let mut interval = Duration::from_millis(5);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        monitor.sample();
+        if let Some(quota) = quota {
+            if get_directory_size(&quota.directory).saturating_sub(initial_size) > quota.bytes {
+                child.kill()?;
+                child.wait()?;
+                return Err(io::Error::other(format!(
+                    "command exceeded the disk quota of {} in {:?}",
+                    format_bytes(quota.bytes),
+                    quota.directory
+                )));
+            }
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(MAX_SAMPLE_INTERVAL);
+    };
+
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    let usage = ResourceUsage {
+        wall_time_ms: clock.elapsed().as_millis() as u64,
+        ..monitor.usage()
+    };
+    Ok((output, usage))
+}
+
+fn get_directory_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Accumulates the usage of a process tree over multiple samples
+struct Monitor {
+    system: System,
+    root: Pid,
+    peak_memory: u64,
+    /// CPU time and bytes written of every process seen, kept after the process ended
+    processes: HashMap<Pid, (f64, u64)>,
+    last_sample: Instant,
+}
+
+impl Monitor {
+    fn new(root: Pid) -> Self {
+        Monitor {
+            system: System::new(),
+            root,
+            peak_memory: 0,
+            processes: HashMap::new(),
+            last_sample: Instant::now(),
+        }
+    }
+
+    fn sample(&mut self) {
+        let refresh = ProcessRefreshKind::nothing().with_cpu().with_memory().with_disk_usage();
+        self.system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+        let elapsed = self.last_sample.elapsed().as_secs_f64();
+        self.last_sample = Instant::now();
+
+        let tree = self.get_process_tree();
+        let mut memory = 0;
+        for pid in tree {
+            let Some(process) = self.system.process(pid) else {
+                continue;
+            };
+            memory += process.memory();
+            let (cpu_time, written) = self.processes.entry(pid).or_default();
+            //the cpu usage is given in percent of a single core since the previous refresh
+            *cpu_time += f64::from(process.cpu_usage()) / 100.0 * elapsed;
+            *written = process.disk_usage().total_written_bytes;
+        }
+        self.peak_memory = self.peak_memory.max(memory);
+    }
+
+    /// The root process and all of its descendants
+    fn get_process_tree(&self) -> HashSet<Pid> {
+        let mut tree = HashSet::from([self.root]);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (pid, process) in self.system.processes() {
+                if !tree.contains(pid) && process.parent().is_some_and(|parent| tree.contains(&parent)) {
+                    tree.insert(*pid);
+                    changed = true;
+                }
+            }
+        }
+        tree
+    }
+
+    fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            wall_time_ms: 0,
+            cpu_time_ms: (self.processes.values().map(|(cpu_time, _)| cpu_time).sum::<f64>() * 1000.0) as u64,
+            peak_memory_bytes: self.peak_memory,
+            disk_written_bytes: self.processes.values().map(|(_, written)| written).sum(),
+        }
+    }
+}
+
+/// Creates a table listing the resources used by every step
+pub fn format_usage_table(usages: &[(String, ResourceUsage)]) -> String {
+    let mut table = Table::new();
+    table.set_titles(row![bFg => "Step", "Wall time", "CPU time", "Peak memory", "Disk written"]);
+    for (step, usage) in usages {
+        table.add_row(row![
+            b -> step,
+            format_duration(usage.wall_time_ms),
+            format_duration(usage.cpu_time_ms),
+            format_bytes(usage.peak_memory_bytes),
+            format_bytes(usage.disk_written_bytes)
+        ]);
+    }
+    table.to_string()
+}
+
+pub(crate) fn format_duration(ms: u64) -> String {
+    match ms {
+        0..1000 => format!("{ms}ms"),
+        1000..60_000 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m {}s", ms / 60_000, ms % 60_000 / 1000),
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_usage() {
+        assert_eq!(format_duration(250), "250ms");
+        assert_eq!(format_duration(1500), "1.5s");
+        assert_eq!(format_duration(125_000), "2m 5s");
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 + 512 * 1024), "3.5 MiB");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_monitored() {
+        let (output, usage) = run_monitored(Command::new("sh").args(["-c", "echo hello; sleep 0.3"]), None).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello\n");
+        assert!(usage.wall_time_ms >= 300);
+        assert!(usage.peak_memory_bytes > 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_monitored_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("input.txt"), vec![0; 4096]).unwrap();
+        let quota = DiskQuota {
+            directory: dir.path().to_path_buf(),
+            bytes: 2048,
+        };
+        let mut command = Command::new("sh");
+        command.current_dir(dir.path()).args(["-c", "head -c 1024 /dev/zero > small.bin"]);
+        assert!(run_monitored(&mut command, Some(&quota)).is_ok());
+
+        let mut command = Command::new("sh");
+        command.current_dir(dir.path()).args(["-c", "head -c 8192 /dev/zero > large.bin; sleep 5"]);
+        let error = run_monitored(&mut command, Some(&quota)).unwrap_err();
+        assert!(error.to_string().contains("disk quota of 2.0 KiB"));
+    }
+}
@@ -1,60 +1,388 @@
 use crate::{
+    environments::{get_software_environment, is_micromamba_available},
     error::CommandError,
     execution::{
-        environment::{set_tool_environment_vars, unset_environment_vars},
-        staging::{stage_required_files, unstage_files},
+        archive::unpack_archive_outputs,
+        cache::{get_cache_key, load_cached_outputs, store_cached_outputs},
+        conditional::{evaluate_condition, link_merge, pick_value, skipped_outputs, SourceValue},
+        environment::get_tool_environment_vars,
+        events::{get_event_log, log_event, set_event_log, set_event_step},
+        expression::{evaluate_expressions, evaluate_output_expressions, evaluate_self_reference, has_expressions, ExpressionContext},
+        provenance::{is_recording, record_command, record_resources, record_step_end, record_step_start},
+        resources::{record_usage, run_monitored, take_usages, DiskQuota, ResourceUsage},
+        runs::{
+            extend_step_runs, finish_run_tracking, get_tracking_directory, is_tracking, record_step_run, run_revision, set_run_revision,
+            set_tracking_directory, start_run_tracking, take_step_runs, RunRevision, StepRun, RUNS_FILE,
+        },
+        sandbox::sandbox_command,
+        scatter::{dispatch_jobs, gather_outputs, get_scatter_jobs},
+        staging::{set_staging_strategy, stage_required_files, staging_strategy, unstage_files, StagingStrategy},
         util::{copy_output_dir, evaluate_input, evaluate_input_as_string, evaluate_outputs, get_file_metadata, preprocess_cwl},
         validate::{rewire_paths, set_placeholder_values},
     },
-    io::{copy_dir, copy_file, create_and_write_file_forced, get_random_filename, get_shell_command, print_output, set_print_output},
+    io::{
+        copy_file, create_and_write_bytes_forced, create_and_write_file_forced, get_random_filename, get_shell_command, get_workflows_folder, print_output,
+        set_print_output,
+    },
+    ontology::{is_offline, set_offline},
+    random::{seed, set_job_seed, set_seed, temp_dir, SEED_VAR},
     {format_command, get_available_ram, get_processor_count},
 };
 use cwl::{
     clt::{Argument, Command, CommandLineTool},
-    inputs::{CommandLineBinding, WorkflowStepInput},
+    parse_cwl,
+    inputs::{CommandInputParameter, CommandLineBinding, WorkflowStepInput},
+    requirements::{inherit_requirements, OnError, Requirement, RetryPolicy},
+    strict::{parse_mode, set_parse_mode, ParseMode},
     types::{CWLType, DefaultValue, OutputItem},
     wf::Workflow,
 };
-use log::info;
+use log::{info, warn};
+use serde_json::json;
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
     env,
     error::Error,
+    ffi::OsString,
     fs::{self},
     path::{Path, PathBuf},
     process::Command as SystemCommand,
-    time::Instant,
+    sync::Mutex,
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
 };
-use tempfile::tempdir;
+use uuid::Uuid;
+
+thread_local!(static SIMULATE: RefCell<bool> = const { RefCell::new(false) });
+
+/// Enables simulation mode: commands are not executed, but empty placeholder outputs are created
+pub fn set_simulate(value: bool) {
+    SIMULATE.with(|simulate| {
+        *simulate.borrow_mut() = value;
+    });
+}
+
+pub fn is_simulate() -> bool {
+    SIMULATE.with(|simulate| *simulate.borrow())
+}
+
+thread_local!(static DRY_RUN: RefCell<bool> = const { RefCell::new(false) });
+
+/// Enables printing the resolved command, staged files, environment and expected outputs of every simulated tool run
+pub fn set_dry_run(value: bool) {
+    DRY_RUN.with(|dry_run| {
+        *dry_run.borrow_mut() = value;
+    });
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.with(|dry_run| *dry_run.borrow())
+}
+
+thread_local!(static SANDBOX: RefCell<bool> = const { RefCell::new(false) });
+
+/// Enables sandboxing: commands of tools without DockerRequirement are run using bubblewrap with only the working directory being writable
+pub fn set_sandbox(value: bool) {
+    SANDBOX.with(|sandbox| {
+        *sandbox.borrow_mut() = value;
+    });
+}
+
+pub fn is_sandbox() -> bool {
+    SANDBOX.with(|sandbox| *sandbox.borrow())
+}
+
+thread_local!(static DISK_QUOTA: RefCell<Option<u64>> = const { RefCell::new(None) });
+
+/// Sets the number of bytes sandboxed commands may write to their working directory before being killed
+pub fn set_disk_quota(value: Option<u64>) {
+    DISK_QUOTA.with(|disk_quota| {
+        *disk_quota.borrow_mut() = value;
+    });
+}
+
+pub fn disk_quota() -> Option<u64> {
+    DISK_QUOTA.with(|disk_quota| *disk_quota.borrow())
+}
+
+thread_local!(static RETRIES: RefCell<Option<u32>> = const { RefCell::new(None) });
+
+/// Sets how often failing workflow steps are rerun unless they declare an `s4n:RetryPolicy` of their own
+pub fn set_retries(value: Option<u32>) {
+    RETRIES.with(|retries| {
+        *retries.borrow_mut() = value;
+    });
+}
+
+pub fn retries() -> Option<u32> {
+    RETRIES.with(|retries| *retries.borrow())
+}
+
+thread_local!(static JOB_RETRY_POLICY: RefCell<Option<RetryPolicy>> = const { RefCell::new(None) });
+
+/// Sets the `s4n:RetryPolicy` given in `cwl:requirements` of the job file, which applies to all steps of the run
+pub fn set_job_retry_policy(value: Option<RetryPolicy>) {
+    JOB_RETRY_POLICY.with(|policy| {
+        *policy.borrow_mut() = value;
+    });
+}
+
+pub fn job_retry_policy() -> Option<RetryPolicy> {
+    JOB_RETRY_POLICY.with(|policy| policy.borrow().clone())
+}
+
+thread_local!(static LOG_FILE: RefCell<Option<PathBuf>> = const { RefCell::new(None) });
+
+/// Sets the path (without extension) stdout and stderr of the next commands are logged to as `.out` and `.err` files
+pub fn set_log_file(value: Option<PathBuf>) {
+    LOG_FILE.with(|log_file| {
+        *log_file.borrow_mut() = value;
+    });
+}
+
+pub fn log_file() -> Option<PathBuf> {
+    LOG_FILE.with(|log_file| log_file.borrow().clone())
+}
+
+thread_local!(static CACHE_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) });
+
+/// Sets the directory results of tool runs are cached in, no cache is used if `None`
+pub fn set_cache_dir(value: Option<PathBuf>) {
+    CACHE_DIR.with(|cache_dir| {
+        *cache_dir.borrow_mut() = value;
+    });
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.with(|cache_dir| cache_dir.borrow().clone())
+}
+
+thread_local!(static PARALLEL: RefCell<Option<usize>> = const { RefCell::new(None) });
+
+/// Sets the number of cores the jobs of scattered steps may use at the same time, `None` uses all available cores
+pub fn set_parallel(value: Option<usize>) {
+    PARALLEL.with(|parallel| {
+        *parallel.borrow_mut() = value;
+    });
+}
+
+pub fn parallel() -> Option<usize> {
+    PARALLEL.with(|parallel| *parallel.borrow())
+}
+
+/// Tools sharing a software environment must not create it at the same time
+static ENVIRONMENT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Resources used and steps run by a job of a worker thread
+type JobRecords = (usize, Vec<(String, ResourceUsage)>, BTreeMap<String, StepRun>);
+
+/// Settings and records of a run, which are kept per thread and handed over to the threads running scattered jobs
+struct RunContext {
+    thread: ThreadId,
+    simulate: bool,
+    dry_run: bool,
+    sandbox: bool,
+    disk_quota: Option<u64>,
+    retries: Option<u32>,
+    job_retry_policy: Option<RetryPolicy>,
+    cache_dir: Option<PathBuf>,
+    parallel: Option<usize>,
+    print_output: bool,
+    staging_strategy: StagingStrategy,
+    seed: Option<u64>,
+    event_log: Option<(PathBuf, Uuid)>,
+    tracking_directory: Option<PathBuf>,
+    revision: Option<RunRevision>,
+    parse_mode: ParseMode,
+    offline: bool,
+    records: Mutex<Vec<JobRecords>>,
+}
+
+impl RunContext {
+    fn capture() -> Self {
+        RunContext {
+            thread: thread::current().id(),
+            simulate: is_simulate(),
+            dry_run: is_dry_run(),
+            sandbox: is_sandbox(),
+            disk_quota: disk_quota(),
+            retries: retries(),
+            job_retry_policy: job_retry_policy(),
+            cache_dir: cache_dir(),
+            parallel: parallel(),
+            print_output: print_output(),
+            staging_strategy: staging_strategy(),
+            seed: seed(),
+            event_log: get_event_log(),
+            tracking_directory: get_tracking_directory(),
+            revision: run_revision(),
+            parse_mode: parse_mode(),
+            offline: is_offline(),
+            records: Mutex::new(vec![]),
+        }
+    }
+
+    /// Runs a job, jobs of worker threads get the settings of the run and record their resources and steps for the
+    /// run to collect them afterwards
+    fn run_job<T>(&self, index: usize, job: impl FnOnce() -> T) -> T {
+        if thread::current().id() == self.thread {
+            return job();
+        }
+        set_simulate(self.simulate);
+        set_dry_run(self.dry_run);
+        set_sandbox(self.sandbox);
+        set_disk_quota(self.disk_quota);
+        set_retries(self.retries);
+        set_job_retry_policy(self.job_retry_policy.clone());
+        set_cache_dir(self.cache_dir.clone());
+        set_parallel(self.parallel);
+        set_print_output(self.print_output);
+        set_staging_strategy(self.staging_strategy);
+        //names drawn by a job do not depend on the order jobs are run in
+        set_job_seed(self.seed, index as u64);
+        set_event_log(self.event_log.clone());
+        set_tracking_directory(self.tracking_directory.clone());
+        set_run_revision(self.revision.clone());
+        set_parse_mode(self.parse_mode);
+        set_offline(self.offline);
+
+        let result = job();
+        if let Ok(mut records) = self.records.lock() {
+            records.push((index, take_usages(), take_step_runs()));
+        }
+        set_seed(None);
+        set_event_log(None);
+        set_tracking_directory(None);
+        set_run_revision(None);
+        set_parse_mode(ParseMode::Permissive);
+        set_offline(false);
+        result
+    }
+
+    /// Adds the records of the jobs run by worker threads to the run in the order of the jobs
+    fn collect_records(self) {
+        let mut records = self.records.into_inner().unwrap_or_default();
+        records.sort_by_key(|(index, _, _)| *index);
+        for (_, usages, steps) in records {
+            for (step, usage) in usages {
+                record_usage(&step, usage);
+            }
+            extend_step_runs(steps);
+        }
+    }
+}
+
+/// Number of jobs of a step run at the same time, every job is given the cores its `ResourceRequirement` asks for.
+/// Jobs are run one after another while recording provenance, which follows the steps in the order they are run.
+fn get_parallel_jobs(process: &StepProcess) -> usize {
+    if is_recording() {
+        return 1;
+    }
+    let (requirements, hints) = match process {
+        StepProcess::Tool(tool) => (&tool.requirements, &tool.hints),
+        StepProcess::Workflow(workflow) => (&workflow.requirements, &workflow.hints),
+    };
+    let cores = requirements
+        .iter()
+        .chain(hints.iter())
+        .flatten()
+        .find_map(|r| match r {
+            Requirement::ResourceRequirement(resources) => resources.cores_min,
+            _ => None,
+        })
+        .unwrap_or(1)
+        .max(1) as usize;
+    (parallel().unwrap_or_else(get_processor_count) / cores).max(1)
+}
 
 pub fn run_workflow(
     workflow: &mut Workflow,
     input_values: Option<HashMap<String, DefaultValue>>,
     cwl_path: Option<&PathBuf>,
     out_dir: Option<String>,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<HashMap<String, OutputItem>, Box<dyn Error>> {
     let clock = Instant::now();
 
-    let sorted_step_ids = workflow.sort_steps()?;
-    let input_values = input_values.unwrap_or_default();
-
-    let dir = tempdir()?;
-    let tmp_path = dir.path().to_string_lossy().into_owned();
     let current = env::current_dir()?;
     let output_directory = if let Some(out) = out_dir {
         out
     } else {
         current.to_string_lossy().into_owned()
     };
+    let log_directory = current.join(&output_directory).join("logs");
 
-    let workflow_folder = cwl_path.unwrap().parent().unwrap_or(Path::new("."));
+    //runs of project workflows are remembered for `s4n workflow status`
+    let job = if !is_simulate() && current.join(get_workflows_folder()).exists() {
+        let job = input_values.clone().unwrap_or_default();
+        start_run_tracking(&log_directory, cwl_path.unwrap(), &job);
+        Some(job)
+    } else {
+        None
+    };
 
     //prevent tool from outputting
     set_print_output(false);
+    let output_values = execute_workflow(workflow, input_values, cwl_path.unwrap(), &output_directory, &log_directory);
+    set_print_output(true);
+    if let Some(job) = job {
+        if let Err(e) = finish_run_tracking(cwl_path.unwrap(), &job, output_values.is_ok()) {
+            warn!("Could not record run in {}: {}", RUNS_FILE, e);
+        }
+    }
+    let output_values = output_values?;
+
+    if log_directory.exists() {
+        info!("📝 Logs of all steps were written to {:?}", log_directory);
+    }
+    let json = serde_json::to_string_pretty(&output_values)?;
+    println!("{}", json);
+
+    info!(
+        "✔️  Workflow {:?} executed successfully in {:.0?}!",
+        &cwl_path.unwrap_or(&PathBuf::default()),
+        clock.elapsed()
+    );
+    Ok(output_values)
+}
+
+/// Runs all steps of a workflow and copies its outputs to the output directory. Steps running a nested workflow are
+/// executed recursively with an output directory of their own.
+fn execute_workflow(
+    workflow: &Workflow,
+    input_values: Option<HashMap<String, DefaultValue>>,
+    cwl_path: &Path,
+    output_directory: &str,
+    log_directory: &Path,
+) -> Result<HashMap<String, OutputItem>, Box<dyn Error>> {
+    let sorted_step_ids = workflow.sort_steps()?;
+    let input_values = input_values.unwrap_or_default();
+
+    let dir = temp_dir()?;
+    let tmp_path = dir.path().to_string_lossy().into_owned();
+    let workflow_folder = cwl_path.parent().unwrap_or(Path::new("."));
+
+    let expression_lib = workflow
+        .requirements
+        .iter()
+        .flatten()
+        .find_map(|r| match r {
+            Requirement::InlineJavascriptRequirement(req) => req.expression_lib.clone(),
+            _ => None,
+        })
+        .unwrap_or_default();
 
     let mut outputs: HashMap<String, OutputItem> = HashMap::new();
+    //steps that failed with `onError: continue` and the steps depending on them
+    let mut failed_steps: Vec<String> = vec![];
     for step_id in sorted_step_ids {
         if let Some(step) = workflow.get_step(&step_id) {
+            if let Some(dependency) = step.get_source_steps().into_iter().find(|s| failed_steps.contains(s)) {
+                warn!("Skipping step {} as it depends on step {} which did not succeed", step.id, dependency);
+                failed_steps.push(step.id.clone());
+                continue;
+            }
+            info!("▶️  Running step {}", step.id);
             let path = workflow_folder.join(step.run.clone());
             let file = fs::read_to_string(&path).map_err(|e| format!("Unable to find Step {} at {:?}: {}", step.id, path, e))?;
 
@@ -66,100 +394,309 @@ pub fn run_workflow(
                     WorkflowStepInput::String(in_string) => {
                         let parts: Vec<&str> = in_string.split('/').collect();
                         if parts.len() == 2 {
-                            step_inputs.insert(key.to_string(), outputs.get(in_string).unwrap().to_default_value());
+                            //null outputs of skipped steps leave the input unset, so the tool's default applies
+                            let value = outputs.get(in_string).unwrap().to_default_value();
+                            if !value.is_null() {
+                                step_inputs.insert(key.to_string(), value);
+                            }
                         } else if let Some(input) = workflow.inputs.iter().find(|i| i.id == *in_string) {
-                            let value = evaluate_input(input, &Some(input_values.clone()))?;
+                            let value = evaluate_input(input, Some(&input_values))?;
                             step_inputs.insert(key.to_string(), value.to_owned());
                         }
                     }
                     WorkflowStepInput::Parameter(parameter) => {
                         let source = parameter.source.clone().unwrap_or_default();
-                        let source_parts: Vec<&str> = source.split('/').collect();
-                        if source_parts.len() == 2 {
-                            //handle default
-                            if let Some(out_value) = outputs.get(&source) {
-                                step_inputs.insert(key.to_string(), out_value.to_default_value());
-                            } else if let Some(default) = &parameter.default {
-                                step_inputs.insert(key.to_string(), default.to_owned());
-                            }
-                        } else if let Some(default) = &parameter.default {
-                            step_inputs.insert(key.to_string(), default.to_owned());
+                        let mut values = vec![];
+                        for id in source.ids() {
+                            let value = if id.split('/').count() == 2 {
+                                outputs.get(id).map(|v| v.to_default_value())
+                            } else if let Some(input) = workflow.inputs.iter().find(|i| i.id == id) {
+                                Some(evaluate_input(input, Some(&input_values))?)
+                            } else {
+                                None
+                            };
+                            values.push(value);
                         }
-                        if let Some(input) = workflow.inputs.iter().find(|i| i.id == *source) {
-                            let value = evaluate_input(input, &Some(input_values.clone()))?;
-                            step_inputs.insert(key.to_string(), value.to_owned());
+                        //values of multiple sources are merged into an array, missing ones of failed steps become null
+                        let value = if source.is_multiple() || parameter.link_merge.is_some() {
+                            let values = values.into_iter().map(|v| v.unwrap_or(DefaultValue::Any(serde_yaml::Value::Null))).collect();
+                            Some(link_merge(values, parameter.link_merge.unwrap_or_default()))
+                        } else {
+                            values.into_iter().next().flatten()
+                        };
+                        let value = match (parameter.pick_value, value) {
+                            (Some(method), Some(value)) => Some(pick_value(&format!("{}/{}", step.id, key), value.into_items(), method)?),
+                            (_, value) => value,
+                        };
+                        //the default is used if there is no source or its value is null
+                        if let Some(value) = value.filter(|v| !v.is_null()).or_else(|| parameter.default.clone()) {
+                            step_inputs.insert(key.to_string(), value);
+                        }
+                        if let (Some(value_from), Some(value)) = (&parameter.value_from, step_inputs.get(key)) {
+                            let value = evaluate_self_reference(value, value_from).map_err(|e| format!("Could not evaluate input {} of step {}: {}", key, step.id, e))?;
+                            step_inputs.insert(key.to_string(), value);
                         }
                     }
                 }
             }
 
+            let condition = |inputs: &HashMap<String, DefaultValue>| match &step.when {
+                Some(when) => evaluate_condition(when, inputs, expression_lib.clone())
+                    .map_err(|e| format!("Could not evaluate the condition of step {}: {}", step.id, e)),
+                None => Ok(true),
+            };
+            if step.scatter.is_none() && !condition(&step_inputs)? {
+                info!("⏭️  Skipping step {} as its condition is false", step.id);
+                for (key, value) in skipped_outputs(&step.out) {
+                    outputs.insert(format!("{}/{}", step.id, key), value);
+                }
+                continue;
+            }
+
+            let recorded_inputs = is_tracking().then(|| step_inputs.clone());
             let preprocessed_file = preprocess_cwl(&file, &path);
-            let mut tool: CommandLineTool = serde_yaml::from_str(&preprocessed_file)?;
-            let tool_outputs = run_commandlinetool(&mut tool, Some(step_inputs), Some(&path), Some(tmp_path.clone()))?;
+            //the process is loaded once and shared by all jobs of the step
+            let process = load_step_process(workflow, &preprocessed_file, &path)?;
+            let nested = matches!(process, StepProcess::Workflow(_));
+            if nested && !workflow.requirements.iter().flatten().any(|r| matches!(r, Requirement::SubworkflowFeatureRequirement)) {
+                warn!("Step {} runs a nested workflow, but SubworkflowFeatureRequirement is not declared", step.id);
+            }
+            let policy = get_retry_policy(&process);
+            let scatter_inputs = step.get_scatter_inputs();
+            let result = if scatter_inputs.is_empty() {
+                //nested workflows get their own folder to not mix up their outputs with those of other steps
+                let step_dir = if nested {
+                    Path::new(&tmp_path).join(&step.id)
+                } else {
+                    PathBuf::from(&tmp_path)
+                };
+                run_with_retries(&policy, &step.id, || {
+                    run_step(&process, &path, step_inputs.clone(), &step_dir, &log_directory.join(&step.id))
+                })
+            } else {
+                let method = step.scatter_method.unwrap_or_default();
+                let jobs = get_scatter_jobs(&step_inputs, &scatter_inputs, method)?;
+                let lengths = jobs.lengths();
+                let parallel = get_parallel_jobs(&process);
+                info!(
+                    "🔀 Scattering step {} over {:?} into {} jobs, running up to {} at a time",
+                    step.id,
+                    scatter_inputs,
+                    jobs.len(),
+                    parallel
+                );
+                let context = RunContext::capture();
+                let result = dispatch_jobs(jobs, parallel, |i, job| {
+                    context.run_job(i, || {
+                        //conditions of scattered steps are evaluated per job, skipped jobs have null outputs
+                        if !condition(&job)? {
+                            info!("⏭️  Skipping job {} of step {} as its condition is false", i, step.id);
+                            return Ok(skipped_outputs(&step.out));
+                        }
+                        //every job gets its own output folder to not overwrite the results of others
+                        let job_dir = Path::new(&tmp_path).join(format!("{}_{i}", step.id));
+                        fs::create_dir_all(&job_dir)?;
+                        let log = log_directory.join(format!("{}_{i}", step.id));
+                        run_with_retries(&policy, &format!("{}_{i}", step.id), || {
+                            run_step(&process, &path, job.clone(), &job_dir, &log)
+                        })
+                    })
+                });
+                context.collect_records();
+                result.map(|job_outputs| gather_outputs(job_outputs, &step.out, &lengths, method))
+            };
+            let tool_outputs = match result {
+                Ok(tool_outputs) => tool_outputs,
+                Err(e) if policy.on_error == OnError::Continue => {
+                    warn!("Step {} failed, continuing with the steps not depending on it: {}", step.id, e);
+                    failed_steps.push(step.id.clone());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             for (key, value) in tool_outputs {
                 outputs.insert(format!("{}/{}", step.id, key), value);
             }
+            if let Some(inputs) = recorded_inputs {
+                record_step_run(&log_directory.join(&step.id), &inputs);
+            }
         } else {
             return Err(format!("Could not find step {}", step_id).into());
         }
     }
 
-    set_print_output(true);
+    if !failed_steps.is_empty() {
+        warn!("Steps {:?} did not succeed, their outputs are missing", failed_steps);
+    }
+
+    //workflow inputs connected to outputs are copied to the output directory
+    let input_to_output = |input: &CommandInputParameter| -> Result<OutputItem, Box<dyn Error>> {
+        let result = evaluate_input(input, Some(&input_values))?;
+        Ok(match &result {
+            DefaultValue::File(file) => {
+                let dest = format!("{}/{}", output_directory, file.location);
+                fs::copy(workflow_folder.join(&file.location), &dest).map_err(|e| format!("Could not copy file to {}: {}", dest, e))?;
+                OutputItem::OutputFile(get_file_metadata(Path::new(&dest).to_path_buf(), file.format.clone()))
+            }
+            DefaultValue::Directory(directory) => OutputItem::OutputDirectory(
+                copy_output_dir(
+                    workflow_folder.join(&directory.location),
+                    format!("{}/{}", output_directory, &directory.location),
+                )
+                .map_err(|e| format!("Could not provide output directory: {}", e))?,
+            ),
+            DefaultValue::Any(_) => OutputItem::OutputString(result.as_value_string()),
+        })
+    };
 
     let mut output_values = HashMap::new();
-    let input_values_ = Some(input_values);
     for output in &workflow.outputs {
         let source = &output.output_source;
-        if let Some(value) = &outputs.get(source) {
-            let value = match value {
-                OutputItem::OutputFile(file) => {
-                    let new_loc = Path::new(&file.path).to_string_lossy().replace(&tmp_path, &output_directory);
-                    copy_file(&file.path, &new_loc)?;
-                    let mut file = file.clone();
-                    file.path = new_loc.to_string();
-                    file.location = format!("file://{}", new_loc);
-                    OutputItem::OutputFile(file)
-                }
-                OutputItem::OutputDirectory(dir) => {
-                    let new_loc = Path::new(&dir.path).to_string_lossy().replace(&tmp_path, &output_directory);
-                    copy_dir(&dir.path, &new_loc)?;
-                    let mut dir = dir.clone();
-                    dir.path = new_loc.to_string();
-                    dir.location = format!("file://{}", new_loc);
-                    OutputItem::OutputDirectory(dir)
-                }
-                OutputItem::OutputString(str) => OutputItem::OutputString(str.to_string()),
-            };
-            output_values.insert(&output.id, value.clone());
-        } else if let Some(input) = workflow.inputs.iter().find(|i| i.id == *source) {
-            let result = evaluate_input(input, &input_values_)?;
-            let value = match &result {
-                DefaultValue::File(file) => {
-                    let dest = format!("{}/{}", output_directory, file.location);
-                    fs::copy(workflow_folder.join(&file.location), &dest).map_err(|e| format!("Could not copy file to {}: {}", dest, e))?;
-                    OutputItem::OutputFile(get_file_metadata(Path::new(&dest).to_path_buf(), file.format.clone()))
-                }
-                DefaultValue::Directory(directory) => OutputItem::OutputDirectory(
-                    copy_output_dir(
-                        workflow_folder.join(&directory.location),
-                        format!("{}/{}", &output_directory, &directory.location),
-                    )
-                    .map_err(|e| format!("Could not provide output directory: {}", e))?,
-                ),
-                DefaultValue::Any(_) => OutputItem::OutputString(result.as_value_string()),
+        let mut values = vec![];
+        for id in source.ids() {
+            let value = if let Some(value) = outputs.get(id) {
+                Some(relocate_output(value, &tmp_path, output_directory)?)
+            } else if let Some(input) = workflow.inputs.iter().find(|i| i.id == id) {
+                Some(input_to_output(input)?)
+            } else {
+                None
             };
-            output_values.insert(&output.id, value);
+            values.push(value);
+        }
+        let value = if source.is_multiple() || output.link_merge.is_some() {
+            let values = values.into_iter().map(|v| v.unwrap_or(OutputItem::OutputNull)).collect();
+            Some(link_merge(values, output.link_merge.unwrap_or_default()))
+        } else {
+            values.into_iter().next().flatten()
+        };
+        let value = match (output.pick_value, value) {
+            (Some(method), Some(value)) => Some(pick_value(&output.id, value.into_items(), method)?),
+            (_, value) => value,
+        };
+        if let Some(value) = value {
+            output_values.insert(output.id.clone(), value);
         }
     }
-    let json = serde_json::to_string_pretty(&output_values)?;
-    println!("{}", json);
+    Ok(output_values)
+}
 
-    info!(
-        "✔️  Workflow {:?} executed successfully in {:.0?}!",
-        &cwl_path.unwrap_or(&PathBuf::default()),
-        clock.elapsed()
-    );
-    Ok(())
+/// Process run by a workflow step
+enum StepProcess {
+    Tool(CommandLineTool),
+    Workflow(Workflow),
+}
+
+/// Loads the tool or nested workflow of a step. Requirements and hints of the enclosing workflow are passed down to it.
+fn load_step_process(workflow: &Workflow, contents: &str, path: &Path) -> Result<StepProcess, Box<dyn Error>> {
+    if is_workflow(contents) {
+        let mut subworkflow: Workflow = parse_cwl(contents).map_err(|e| e.with_path(path))?;
+        inherit_requirements(
+            &mut subworkflow.requirements,
+            &mut subworkflow.hints,
+            workflow.requirements.as_ref(),
+            workflow.hints.as_ref(),
+        );
+        Ok(StepProcess::Workflow(subworkflow))
+    } else {
+        let mut tool: CommandLineTool = parse_cwl(contents).map_err(|e| e.with_path(path))?;
+        inherit_requirements(&mut tool.requirements, &mut tool.hints, workflow.requirements.as_ref(), workflow.hints.as_ref());
+        Ok(StepProcess::Tool(tool))
+    }
+}
+
+/// Gets the retry policy of a step. A policy given in the job file applies to all steps, otherwise the one the process
+/// declares or inherits from the workflow is used, otherwise the number of retries given for the run.
+fn get_retry_policy(process: &StepProcess) -> RetryPolicy {
+    let (requirements, hints) = match process {
+        StepProcess::Tool(tool) => (&tool.requirements, &tool.hints),
+        StepProcess::Workflow(workflow) => (&workflow.requirements, &workflow.hints),
+    };
+    let declared = requirements.iter().chain(hints.iter()).flatten().find_map(|r| match r {
+        Requirement::RetryPolicy(policy) => Some(policy.clone()),
+        _ => None,
+    });
+    job_retry_policy().or(declared).unwrap_or_else(|| RetryPolicy {
+        max_attempts: retries().unwrap_or_default() + 1,
+        ..Default::default()
+    })
+}
+
+/// Runs a job of a step until it succeeds or the policy's maximum number of attempts is reached. The time waited
+/// before a retry starts at the policy's backoff and doubles with every attempt.
+fn run_with_retries<T>(policy: &RetryPolicy, name: &str, mut job: impl FnMut() -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+    let mut attempt = 1;
+    loop {
+        match job() {
+            Err(e) if attempt < policy.max_attempts => {
+                let delay = Duration::from_secs_f64(policy.backoff.max(0.0) * 2f64.powi(attempt as i32 - 1));
+                warn!(
+                    "Step {} failed in attempt {} of {}, retrying in {:.0?}: {}",
+                    name, attempt, policy.max_attempts, delay, e
+                );
+                log_event("step_retried", json!({ "attempt": attempt, "error": e.to_string() }));
+                thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs a single job of a workflow step, which is either a tool or a nested workflow
+fn run_step(
+    process: &StepProcess,
+    path: &PathBuf,
+    inputs: HashMap<String, DefaultValue>,
+    out_dir: &Path,
+    log: &Path,
+) -> Result<HashMap<String, OutputItem>, Box<dyn Error>> {
+    match process {
+        StepProcess::Workflow(subworkflow) => {
+            info!("🪆 Executing nested Workflow {:?} ...", path);
+            fs::create_dir_all(out_dir)?;
+            //logs of nested steps are grouped in a folder named after the step
+            execute_workflow(subworkflow, Some(inputs), path, &out_dir.to_string_lossy(), log)
+        }
+        StepProcess::Tool(tool) => {
+            //placeholders and expressions are replaced in the tool, so every job needs a copy of its own
+            let mut tool = tool.clone();
+            set_log_file(Some(log.to_path_buf()));
+            let outputs = run_commandlinetool(&mut tool, Some(inputs), Some(path), Some(out_dir.to_string_lossy().into_owned()));
+            set_log_file(None);
+            outputs
+        }
+    }
+}
+
+fn is_workflow(contents: &str) -> bool {
+    serde_yaml::from_str::<serde_yaml::Value>(contents).is_ok_and(|document| document.get("class").and_then(|c| c.as_str()) == Some("Workflow"))
+}
+
+/// Copies an output from the staging directory to the output directory
+fn relocate_output(value: &OutputItem, tmp_path: &str, output_directory: &str) -> Result<OutputItem, Box<dyn Error>> {
+    Ok(match value {
+        OutputItem::OutputFile(file) => {
+            let new_loc = Path::new(&file.path).to_string_lossy().replace(tmp_path, output_directory);
+            copy_file(&file.path, &new_loc)?;
+            let mut file = file.clone();
+            file.path = new_loc.to_string();
+            file.location = format!("file://{}", new_loc);
+            OutputItem::OutputFile(file)
+        }
+        OutputItem::OutputDirectory(dir) => {
+            //listing is captured again to point to the relocated files
+            let new_loc = Path::new(&dir.path).to_string_lossy().replace(tmp_path, output_directory);
+            OutputItem::OutputDirectory(copy_output_dir(&dir.path, &new_loc)?)
+        }
+        OutputItem::OutputString(str) => OutputItem::OutputString(str.to_string()),
+        OutputItem::OutputNull => OutputItem::OutputNull,
+        OutputItem::OutputArray(items) => OutputItem::OutputArray(
+            items
+                .iter()
+                .map(|item| relocate_output(item, tmp_path, output_directory))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+    })
 }
 
 pub fn run_commandlinetool(
@@ -173,8 +710,17 @@ pub fn run_commandlinetool(
     if !print_output() {
         info!("🚲 Executing CommandLineTool {:?} ...", cwl_path.unwrap_or(&PathBuf::default()));
     }
+    //steps of workflows are named after their log file
+    let step_name = log_file()
+        .or(cwl_path.and_then(|p| p.file_stem()).map(PathBuf::from))
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    record_step_start(&step_name, input_values.as_ref().unwrap_or(&HashMap::new()));
+    set_event_step(Some(&step_name));
+    log_event("step_started", json!({ "tool": cwl_path, "inputs": input_values }));
+
     //create staging directory
-    let dir = tempdir()?;
+    let dir = temp_dir()?;
     info!("📁 Created staging directory: {:?}", dir.path());
 
     //save reference to current working directory
@@ -188,6 +734,25 @@ pub fn run_commandlinetool(
         Path::new(".")
     };
 
+    //reuse outputs of a previous run with the same tool and inputs
+    let cache = match cache_dir() {
+        Some(cache_dir) if !is_simulate() => Some((get_cache_key(tool, input_values.as_ref(), tool_path)?, cache_dir)),
+        _ => None,
+    };
+    if let Some((key, cache_dir)) = &cache {
+        if let Some(outputs) = load_cached_outputs(cache_dir, key, output_directory)? {
+            info!("♻️  Reusing cached outputs of {:?} from {:?}", cwl_path.unwrap_or(&PathBuf::default()), cache_dir.join(key));
+            if print_output() {
+                let json = serde_json::to_string_pretty(&outputs)?;
+                println!("{}", json);
+            }
+            record_step_end(&outputs);
+            log_event("step_finished", json!({ "cached": true, "duration_ms": clock.elapsed().as_millis(), "outputs": outputs }));
+            set_event_step(None);
+            return Ok(outputs);
+        }
+    }
+
     //build runtime object
     let runtime = HashMap::from([
         (
@@ -202,45 +767,87 @@ pub fn run_commandlinetool(
 
     //replace inputs and runtime placeholders in tool with the actual values
     set_placeholder_values(tool, input_values.as_ref(), &runtime);
+    //evaluate remaining javascript expressions
+    let expression_context = ExpressionContext::new(tool, input_values.as_ref(), &runtime)?;
+    if cfg!(feature = "javascript") && tool.get_inline_javascript_requirement().is_some() {
+        evaluate_expressions(tool, &expression_context)?;
+    }
     //stage files listed in input default values, input values or initial work dir requirements
-    let staged_files = stage_required_files(tool, &input_values, tool_path, dir.path(), output_directory)?;
+    let staged_files = stage_required_files(tool, &input_values, &expression_context, tool_path, dir.path(), output_directory)?;
 
-    //change working directory to tmp folder, we will execute tool from root here
-    env::set_current_dir(dir.path())?;
-
-    //set environment variables
-    let environment_variables = set_tool_environment_vars(tool);
+    //tools declaring software packages run in a conda environment if micromamba is available
+    let software_environment = match get_software_environment(tool, tool_path, &current)? {
+        Some(environment) if !is_simulate() && is_micromamba_available() => Some(environment),
+        Some(_) if !is_simulate() => {
+            warn!("Tool declares software packages but micromamba is not available, running with the software on PATH");
+            None
+        }
+        _ => None,
+    };
 
     //rewire files in tool to staged ones
     let mut input_values = input_values;
-    rewire_paths(tool, &mut input_values, &staged_files, &output_directory.to_string_lossy());
-
-    //set required environment variables
-    let home_directory = env::var("HOME").unwrap_or_default();
-    let tmp_directory = env::temp_dir();
-    env::set_var("HOME", &runtime["outdir"]);
-    env::set_var("TMPDIR", &runtime["tmpdir"]);
-
-    //run the tool command)
-    run_command(tool, input_values).map_err(|e| CommandError {
-        message: format!("❌ Error in Tool execution: {}", e),
-        exit_code: tool.get_error_code(),
-    })?;
-    //reset required environment variables
-    env::set_var("HOME", home_directory);
-    env::set_var("TMPDIR", tmp_directory);
+    rewire_paths(tool, &mut input_values, &staged_files, &output_directory.to_string_lossy(), dir.path());
+
+    //the tool is run in the staging directory with its environment variables and the required HOME and TMPDIR, the
+    //process itself is left untouched so that jobs can run in parallel
+    let mut environment = vec![
+        ("HOME".to_string(), OsString::from(&runtime["outdir"])),
+        ("TMPDIR".to_string(), OsString::from(&runtime["tmpdir"])),
+    ];
+    environment.extend(
+        get_tool_environment_vars(tool)
+            .into_iter()
+            .filter(|(key, _)| key != "HOME" && key != "TMPDIR")
+            .map(|(key, value)| (key, OsString::from(value))),
+    );
+
+    //run the tool command or pretend to do so
+    if is_simulate() {
+        let command = build_command(tool, input_values)?;
+        if is_dry_run() {
+            print_execution_plan(tool, &step_name, &command, &environment, dir.path())?;
+        } else {
+            info!("🧪 Simulating Command: `{}`", format_command(&command));
+        }
+        create_placeholder_outputs(tool, dir.path())?;
+    } else {
+        if let Some(software_environment) = &software_environment {
+            let _lock = ENVIRONMENT_LOCK.lock().map_err(|e| e.to_string())?;
+            software_environment.prepare()?;
+            environment.extend(software_environment.variables().into_iter().map(|(key, value)| (key.to_string(), value)));
+        }
+        let usage = execute_command(tool, input_values, &staged_files, dir.path(), &environment).map_err(|e| CommandError {
+            message: format!("❌ Error in Tool execution: {}", e),
+            exit_code: tool.get_error_code(),
+        })?;
+        record_usage(&step_name, usage);
+        //unpack archives if requested by hint
+        unpack_archive_outputs(tool, dir.path(), output_directory)?;
+    }
 
     //remove staged files
     unstage_files(&staged_files, dir.path(), &tool.outputs)?;
 
     //evaluate output files
-    let outputs = evaluate_outputs(&tool.outputs, output_directory, &tool.stdout, &tool.stderr)?;
-
-    //unset environment variables
-    unset_environment_vars(&environment_variables);
+    let mut outputs = evaluate_outputs(&tool.outputs, dir.path(), output_directory, &tool.stdout, &tool.stderr)?;
+    if cfg!(feature = "javascript") {
+        outputs.extend(evaluate_output_expressions(&tool.outputs, &expression_context, dir.path(), output_directory)?);
+    }
+    if let Some((key, cache_dir)) = &cache {
+        if !store_cached_outputs(cache_dir, key, &outputs, output_directory)? {
+            warn!("Outputs are not located in the output directory and could not be cached");
+        }
+    }
+    if print_output() {
+        //print output metadata
+        let json = serde_json::to_string_pretty(&outputs)?;
+        println!("{}", json);
+    }
 
-    //come back to original directory
-    env::set_current_dir(current)?;
+    record_step_end(&outputs);
+    log_event("step_finished", json!({ "cached": false, "duration_ms": clock.elapsed().as_millis(), "outputs": outputs }));
+    set_event_step(None);
 
     info!(
         "✔️  CommandLineTool {:?} executed successfully in {:.0?}!",
@@ -250,54 +857,160 @@ pub fn run_commandlinetool(
     Ok(outputs)
 }
 
-pub fn run_command(tool: &CommandLineTool, input_values: Option<HashMap<String, DefaultValue>>) -> Result<(), Box<dyn Error>> {
+/// Runs the command of a tool and returns the resources it used
+pub fn run_command(tool: &CommandLineTool, input_values: Option<HashMap<String, DefaultValue>>) -> Result<ResourceUsage, Box<dyn Error>> {
+    execute_command(tool, input_values, &[], &env::current_dir()?, &[])
+}
+
+/// Runs the command of a tool in the given working directory with additional environment variables, staged files are
+/// read only for sandboxed commands
+fn execute_command(
+    tool: &CommandLineTool,
+    input_values: Option<HashMap<String, DefaultValue>>,
+    staged_files: &[String],
+    working_directory: &Path,
+    environment: &[(String, OsString)],
+) -> Result<ResourceUsage, Box<dyn Error>> {
     let mut command = build_command(tool, input_values)?;
+    command.current_dir(working_directory);
+    command.envs(environment.iter().map(|(key, value)| (key, value)));
+    record_command(&command);
+    let mut quota = None;
+    if is_sandbox() && !tool.has_docker_requirement() {
+        command = sandbox_command(&command, tool, working_directory, staged_files)?;
+        quota = disk_quota().map(|bytes| DiskQuota {
+            directory: working_directory.to_path_buf(),
+            bytes,
+        });
+    }
+    //stochastic tools can use the seed of the run to be reproducible
+    if let Some(seed) = seed() {
+        command.env(SEED_VAR, seed.to_string()).env("PYTHONHASHSEED", seed.to_string());
+    }
 
     //run
     info!("⏳ Executing Command: `{}`", format_command(&command));
-    let output = command.output()?;
+    let (output, usage) = run_monitored(&mut command, quota.as_ref())?;
+    record_resources(&usage);
+    log_event(
+        "command_finished",
+        json!({ "command": format_command(&command), "exit_code": output.status.code(), "duration_ms": usage.wall_time_ms, "resources": usage }),
+    );
+
+    //keep a copy of both streams for later debugging
+    if let Some(log_file) = log_file() {
+        if let Some(parent) = log_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(format!("{}.out", log_file.display()), &output.stdout)?;
+        fs::write(format!("{}.err", log_file.display()), &output.stderr)?;
+    }
 
-    //handle redirection of stdout
+    //handle redirection of stdout, bytes are written as they are to not corrupt binary outputs
     if !output.stdout.is_empty() {
-        let out = &String::from_utf8_lossy(&output.stdout);
         if let Some(stdout) = &tool.stdout {
-            create_and_write_file_forced(stdout, out)?;
+            create_and_write_bytes_forced(working_directory.join(stdout), &output.stdout)?;
         } else if tool.has_stdout_output() {
-            let output = tool.outputs.iter().filter(|o| matches!(o.type_, CWLType::Stdout)).collect::<Vec<_>>()[0];
-            let filename = if let Some(binding) = &output.output_binding {
+            let output_parameter = tool.outputs.iter().filter(|o| matches!(o.type_, CWLType::Stdout)).collect::<Vec<_>>()[0];
+            let filename = if let Some(binding) = &output_parameter.output_binding {
                 &binding.glob
             } else {
-                &get_random_filename(&format!("{}_stdout", output.id), "out")
+                &get_random_filename(&format!("{}_stdout", output_parameter.id), "out")
             };
-            create_and_write_file_forced(filename, out)?;
+            create_and_write_bytes_forced(working_directory.join(filename), &output.stdout)?;
         } else {
-            eprintln!("{}", out);
+            eprintln!("{}", String::from_utf8_lossy(&output.stdout));
         }
     }
     //handle redirection of stderr
     if !output.stderr.is_empty() {
-        let out = &String::from_utf8_lossy(&output.stderr);
         if let Some(stderr) = &tool.stderr {
-            create_and_write_file_forced(stderr, out)?;
+            create_and_write_bytes_forced(working_directory.join(stderr), &output.stderr)?;
         } else if tool.has_stderr_output() {
-            let output = tool.outputs.iter().filter(|o| matches!(o.type_, CWLType::Stderr)).collect::<Vec<_>>()[0];
-            let filename = if let Some(binding) = &output.output_binding {
+            let output_parameter = tool.outputs.iter().filter(|o| matches!(o.type_, CWLType::Stderr)).collect::<Vec<_>>()[0];
+            let filename = if let Some(binding) = &output_parameter.output_binding {
                 &binding.glob
             } else {
-                &get_random_filename(&format!("{}_stderr", output.id), "out")
+                &get_random_filename(&format!("{}_stderr", output_parameter.id), "out")
             };
-            create_and_write_file_forced(filename, out)?;
+            create_and_write_bytes_forced(working_directory.join(filename), &output.stderr)?;
         } else {
-            eprintln!("❌ {}", out);
+            eprintln!("❌ {}", String::from_utf8_lossy(&output.stderr));
         }
     }
 
     match output.status.success() {
-        true => Ok(()),
+        true => Ok(usage),
         false => Err(format!("command returned with code {:?}", output.status.code().unwrap_or(1)).into()),
     }
 }
 
+/// Prints everything needed to debug a tool run: the resolved command, the contents of the working directory after
+/// staging, environment variables and the outputs that are expected to be created
+fn print_execution_plan(
+    tool: &CommandLineTool,
+    step_name: &str,
+    command: &SystemCommand,
+    environment: &[(String, OsString)],
+    working_directory: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut plan = format!("📋 Execution plan of {step_name}\n");
+    plan.push_str(&format!("   Command: `{}`\n", format_command(command)));
+
+    plan.push_str(&format!("   Working directory {:?}:\n", working_directory));
+    for entry in walkdir::WalkDir::new(working_directory).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(working_directory)?.to_string_lossy().replace('\\', "/");
+        let suffix = if entry.file_type().is_dir() { "/" } else { "" };
+        plan.push_str(&format!("     - {relative}{suffix}\n"));
+    }
+
+    plan.push_str("   Environment:\n");
+    for (key, value) in environment {
+        plan.push_str(&format!("     {key}={}\n", value.to_string_lossy()));
+    }
+
+    plan.push_str("   Expected outputs:\n");
+    for output in &tool.outputs {
+        let location = match (&output.output_binding, &output.type_) {
+            (Some(binding), _) if !binding.glob.is_empty() => binding.glob.clone(),
+            (_, CWLType::Stdout) => tool.stdout.clone().unwrap_or("stdout".to_string()),
+            (_, CWLType::Stderr) => tool.stderr.clone().unwrap_or("stderr".to_string()),
+            _ => "evaluated from expression".to_string(),
+        };
+        plan.push_str(&format!("     - {} ({}): {location}\n", output.id, output.type_));
+    }
+    info!("{}", plan.trim_end());
+    Ok(())
+}
+
+/// Creates zero-byte files and empty directories for all outputs of a tool in its working directory, used to simulate
+/// execution
+fn create_placeholder_outputs(tool: &CommandLineTool, working_directory: &Path) -> Result<(), Box<dyn Error>> {
+    for output in &tool.outputs {
+        let name = if let Some(binding) = output.output_binding.as_ref().filter(|b| !b.glob.is_empty()) {
+            //make globs concrete
+            binding.glob.replace('*', "simulated").replace('?', "x")
+        } else {
+            match output.type_ {
+                CWLType::Stdout => tool.stdout.clone().unwrap_or_else(|| format!("{}_stdout.out", output.id)),
+                CWLType::Stderr => tool.stderr.clone().unwrap_or_else(|| format!("{}_stderr.out", output.id)),
+                _ => continue,
+            }
+        };
+        let name = working_directory.join(name);
+        match output.type_ {
+            CWLType::Directory => fs::create_dir_all(&name)?,
+            _ => {
+                if !name.exists() {
+                    create_and_write_file_forced(&name, "")?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn build_command(tool: &CommandLineTool, input_values: Option<HashMap<String, DefaultValue>>) -> Result<SystemCommand, Box<dyn Error>> {
     let mut args: Vec<String> = vec![];
 
@@ -340,7 +1053,26 @@ fn build_command(tool: &CommandLineTool, input_values: Option<HashMap<String, De
         if let Some(ref binding) = &input.input_binding {
             let mut binding = binding.clone();
             let position = binding.position.unwrap_or_default();
-            binding.value_from = Some(evaluate_input_as_string(input, &input_values)?);
+            //valueFrom replaces the value of the input, unless it still contains unevaluated expressions
+            match &binding.value_from {
+                Some(value_from) if !has_expressions(value_from) => {}
+                _ => {
+                    //items of arrays are added as separate arguments following the prefix, sorting is stable so they keep their order
+                    if let DefaultValue::Any(serde_yaml::Value::Sequence(items)) = evaluate_input(input, input_values.as_ref())? {
+                        for (j, item) in items.into_iter().enumerate() {
+                            let item = serde_yaml::from_value(item.clone()).unwrap_or(DefaultValue::Any(item));
+                            let prefix = if j == 0 { binding.prefix.clone() } else { None };
+                            bindings.push((position, i + index, CommandLineBinding {
+                                prefix,
+                                value_from: Some(item.as_value_string()),
+                                ..binding.clone()
+                            }));
+                        }
+                        continue;
+                    }
+                    binding.value_from = Some(evaluate_input_as_string(input, input_values.as_ref())?)
+                }
+            }
             bindings.push((position, i + index, binding))
         }
     }
@@ -405,6 +1137,44 @@ fn build_command(tool: &CommandLineTool, input_values: Option<HashMap<String, De
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_relocate_output_directory() {
+        let stage = tempdir().unwrap();
+        let tmp = tempdir().unwrap();
+        let out = tempdir().unwrap();
+        fs::create_dir_all(stage.path().join("nested")).unwrap();
+        fs::write(stage.path().join("b.txt"), "b").unwrap();
+        fs::write(stage.path().join("nested/a.txt"), "a").unwrap();
+        let dir = copy_output_dir(stage.path(), tmp.path().join("results")).unwrap();
+
+        let tmp_path = tmp.path().to_string_lossy();
+        let out_path = out.path().to_string_lossy();
+        let OutputItem::OutputDirectory(dir) = relocate_output(&OutputItem::OutputDirectory(dir), &tmp_path, &out_path).unwrap() else {
+            panic!("Output is not a directory")
+        };
+        assert_eq!(Path::new(&dir.path), out.path().join("results"));
+        assert_eq!(dir.listing.len(), 2);
+
+        //listing points to the relocated files, nested directories keep their structure
+        let OutputItem::OutputFile(file) = &dir.listing[0] else {
+            panic!("First item is not a file")
+        };
+        assert_eq!(file.basename, "b.txt");
+        assert_eq!(file.size, 1);
+        assert!(file.checksum.starts_with("sha1$"));
+        assert_eq!(Path::new(&file.path), out.path().join("results/b.txt"));
+        let OutputItem::OutputDirectory(nested) = &dir.listing[1] else {
+            panic!("Second item is not a directory")
+        };
+        assert_eq!(nested.basename, "nested");
+        let OutputItem::OutputFile(file) = &nested.listing[0] else {
+            panic!("Nested item is not a file")
+        };
+        assert_eq!(Path::new(&file.path), out.path().join("results/nested/a.txt"));
+        assert_eq!(fs::read_to_string(&file.path).unwrap(), "a");
+    }
 
     #[test]
     fn test_build_command() {
@@ -0,0 +1,391 @@
+use crate::{
+    io::{create_and_write_file_forced, get_file_checksum},
+    repo::{get_file_commit, get_repository_path},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use cwl::types::DefaultValue;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env,
+    error::Error,
+    fs,
+    mem,
+    path::{Path, PathBuf},
+};
+
+/// Location of the run-state database relative to the project root
+pub const RUNS_FILE: &str = ".s4n/runs.json";
+
+/// Last run of every workflow of a project, keyed by the path of the workflow file relative to the project root
+pub type RunDatabase = BTreeMap<String, WorkflowRun>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkflowRun {
+    pub started: String,
+    pub finished: String,
+    pub success: bool,
+    pub inputs: Map<String, Value>,
+    #[serde(default)]
+    pub steps: BTreeMap<String, StepRun>,
+    /// Commit the workflow file was taken from, suffixed with `-dirty` if it had uncommitted changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Checksum of the workflow file when the run was started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Checksums of the input files when the run was started, keyed by their location
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StepRun {
+    pub finished: String,
+    pub inputs: Map<String, Value>,
+    /// Checksums of the input files when the step finished, keyed by their location
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, String>,
+}
+
+impl WorkflowRun {
+    /// Outputs are stale if the workflow file or any of the input files changed since the run was started.
+    /// Contents are compared by checksum, modification times are only used for directories and runs recorded without checksums.
+    pub fn is_stale(&self, workflow_file: &Path) -> bool {
+        let workflow_changed = match &self.checksum {
+            Some(checksum) => is_changed(workflow_file, checksum),
+            None => is_modified_after(workflow_file, &self.started),
+        };
+        workflow_changed || has_changed_inputs(&self.inputs, &self.checksums, &self.started)
+    }
+
+    /// Time the run took, `None` if its timestamps cannot be read
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        let started = DateTime::parse_from_rfc3339(&self.started).ok()?;
+        let finished = DateTime::parse_from_rfc3339(&self.finished).ok()?;
+        (finished - started).to_std().ok()
+    }
+}
+
+impl StepRun {
+    pub fn is_stale(&self) -> bool {
+        has_changed_inputs(&self.inputs, &self.checksums, &self.finished)
+    }
+}
+
+/// Steps of the currently running workflow, nested steps are named after their path in the logs directory
+struct RunTracker {
+    log_directory: PathBuf,
+    started: DateTime<Utc>,
+    checksum: Option<String>,
+    checksums: BTreeMap<String, String>,
+    steps: BTreeMap<String, StepRun>,
+}
+
+thread_local!(static TRACKER: RefCell<Option<RunTracker>> = const { RefCell::new(None) });
+
+/// A workflow run from a historical revision, the workflow file is checked out outside of the project
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRevision {
+    /// Path of the workflow file in the project
+    pub file: PathBuf,
+    pub commit: String,
+}
+
+thread_local!(static REVISION: RefCell<Option<RunRevision>> = const { RefCell::new(None) });
+
+/// Sets the revision the next runs are recorded for, `None` records them for the workflow file itself
+pub fn set_run_revision(revision: Option<RunRevision>) {
+    REVISION.with(|r| *r.borrow_mut() = revision);
+}
+
+pub fn run_revision() -> Option<RunRevision> {
+    REVISION.with(|r| r.borrow().clone())
+}
+
+/// Starts tracking the steps of a workflow run, steps are identified by their log file in the given directory.
+/// The workflow file and the input files are checksummed before they are used by any step.
+pub fn start_run_tracking(log_directory: &Path, cwl_path: &Path, inputs: &HashMap<String, DefaultValue>) {
+    let checksum = get_checksum(cwl_path);
+    let checksums = get_checksums(&to_json_map(inputs));
+    TRACKER.with(|tracker| {
+        *tracker.borrow_mut() = Some(RunTracker {
+            log_directory: log_directory.to_path_buf(),
+            started: Utc::now(),
+            checksum,
+            checksums,
+            steps: BTreeMap::new(),
+        })
+    });
+}
+
+/// Log directory of the tracked run, used to continue tracking steps in worker threads
+pub fn get_tracking_directory() -> Option<PathBuf> {
+    TRACKER.with(|tracker| tracker.borrow().as_ref().map(|tracker| tracker.log_directory.clone()))
+}
+
+/// Tracks the steps run by a worker thread of a run tracked in another thread, `None` stops tracking
+pub fn set_tracking_directory(log_directory: Option<PathBuf>) {
+    TRACKER.with(|tracker| {
+        *tracker.borrow_mut() = log_directory.map(|log_directory| RunTracker {
+            log_directory,
+            started: Utc::now(),
+            checksum: None,
+            checksums: BTreeMap::new(),
+            steps: BTreeMap::new(),
+        })
+    });
+}
+
+/// Returns the steps recorded so far and starts over
+pub fn take_step_runs() -> BTreeMap<String, StepRun> {
+    TRACKER.with(|tracker| tracker.borrow_mut().as_mut().map(|tracker| mem::take(&mut tracker.steps)).unwrap_or_default())
+}
+
+/// Adds steps recorded by a worker thread to the tracked run
+pub fn extend_step_runs(steps: BTreeMap<String, StepRun>) {
+    TRACKER.with(|tracker| {
+        if let Some(tracker) = tracker.borrow_mut().as_mut() {
+            tracker.steps.extend(steps);
+        }
+    });
+}
+
+pub fn is_tracking() -> bool {
+    TRACKER.with(|tracker| tracker.borrow().is_some())
+}
+
+/// Records a successfully finished step together with the inputs it was run with
+pub fn record_step_run(log_file: &Path, inputs: &HashMap<String, DefaultValue>) {
+    TRACKER.with(|tracker| {
+        if let Some(tracker) = tracker.borrow_mut().as_mut() {
+            let name = log_file.strip_prefix(&tracker.log_directory).unwrap_or(log_file);
+            let name = name.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            let inputs = to_json_map(inputs);
+            tracker.steps.insert(
+                name,
+                StepRun {
+                    finished: now(),
+                    checksums: get_checksums(&inputs),
+                    inputs,
+                },
+            );
+        }
+    });
+}
+
+/// Stops tracking and stores the run of the given workflow in the run-state database of the current directory
+pub fn finish_run_tracking(cwl_path: &Path, inputs: &HashMap<String, DefaultValue>, success: bool) -> Result<(), Box<dyn Error>> {
+    let Some(tracker) = TRACKER.with(|tracker| tracker.borrow_mut().take()) else {
+        return Ok(());
+    };
+    let root = env::current_dir()?;
+    let (cwl_path, commit) = match run_revision() {
+        Some(RunRevision { file, commit }) => (file, Some(commit)),
+        None => (cwl_path.to_path_buf(), get_commit(cwl_path)),
+    };
+    let mut runs = load_runs(&root)?;
+    runs.insert(
+        get_run_key(&cwl_path, &root),
+        WorkflowRun {
+            started: tracker.started.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            finished: now(),
+            success,
+            inputs: to_json_map(inputs),
+            steps: tracker.steps,
+            commit,
+            checksum: tracker.checksum,
+            checksums: tracker.checksums,
+        },
+    );
+    create_and_write_file_forced(root.join(RUNS_FILE), &serde_json::to_string_pretty(&runs)?)?;
+
+    //the run state is local to each checkout and must not make the repository dirty
+    let ignore = root.join(".s4n/.gitignore");
+    let mut ignored = fs::read_to_string(&ignore).unwrap_or_default();
+    if !ignored.lines().any(|line| line == "runs.json") {
+        if !ignored.is_empty() && !ignored.ends_with('\n') {
+            ignored.push('\n');
+        }
+        fs::write(&ignore, ignored + "runs.json\n")?;
+    }
+    Ok(())
+}
+
+/// Loads the run-state database of the project at the given root, which is empty if nothing was run yet
+pub fn load_runs(root: &Path) -> Result<RunDatabase, Box<dyn Error>> {
+    let path = root.join(RUNS_FILE);
+    if !path.exists() {
+        return Ok(RunDatabase::new());
+    }
+    let contents = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&contents).map_err(|e| format!("❌ Could not read {}: {}", RUNS_FILE, e))?)
+}
+
+/// Commit of the repository containing the workflow file, `None` outside of repositories
+fn get_commit(cwl_path: &Path) -> Option<String> {
+    let repo = Repository::discover(fs::canonicalize(cwl_path).ok()?.parent()?).ok()?;
+    get_file_commit(&repo, &get_repository_path(&repo, cwl_path)?)
+}
+
+/// Workflows are identified by their path relative to the project root using forward slashes
+pub fn get_run_key(cwl_path: &Path, root: &Path) -> String {
+    let path = fs::canonicalize(cwl_path).unwrap_or(cwl_path.to_path_buf());
+    let root = fs::canonicalize(root).unwrap_or(root.to_path_buf());
+    let relative = path.strip_prefix(&root).unwrap_or(&path);
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// Collects the locations of all files and directories contained in the given input values
+pub fn get_input_locations(inputs: &Map<String, Value>) -> Vec<String> {
+    fn collect(value: &Value, locations: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                let is_path_item = matches!(map.get("class").and_then(|c| c.as_str()), Some("File") | Some("Directory"));
+                if let (true, Some(location)) = (is_path_item, map.get("location").or(map.get("path")).and_then(|l| l.as_str())) {
+                    locations.push(location.to_string());
+                }
+                map.values().for_each(|v| collect(v, locations));
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect(v, locations)),
+            _ => {}
+        }
+    }
+    let mut locations = vec![];
+    inputs.values().for_each(|v| collect(v, &mut locations));
+    locations
+}
+
+/// Checksums of all input files, directories and missing files are left out
+fn get_checksums(inputs: &Map<String, Value>) -> BTreeMap<String, String> {
+    get_input_locations(inputs)
+        .into_iter()
+        .filter_map(|location| get_checksum(Path::new(location.trim_start_matches("file://"))).map(|checksum| (location, checksum)))
+        .collect()
+}
+
+fn get_checksum(path: &Path) -> Option<String> {
+    path.is_file()
+        .then(|| get_file_checksum(path).ok())
+        .flatten()
+        .map(|checksum| format!("sha1${checksum}"))
+}
+
+/// Inputs without a checksum are compared by modification time. Files no longer present are ignored, e.g. intermediate results of steps
+fn has_changed_inputs(inputs: &Map<String, Value>, checksums: &BTreeMap<String, String>, timestamp: &str) -> bool {
+    get_input_locations(inputs).iter().any(|location| {
+        let path = Path::new(location.trim_start_matches("file://"));
+        match checksums.get(location) {
+            Some(checksum) => is_changed(path, checksum),
+            None => is_modified_after(path, timestamp),
+        }
+    })
+}
+
+fn is_changed(path: &Path, checksum: &str) -> bool {
+    path.exists() && get_checksum(path).as_deref() != Some(checksum)
+}
+
+fn is_modified_after(path: &Path, timestamp: &str) -> bool {
+    let Ok(timestamp) = DateTime::parse_from_rfc3339(timestamp) else {
+        return false;
+    };
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| DateTime::<Utc>::from(modified) > timestamp)
+}
+
+fn to_json_map(inputs: &HashMap<String, DefaultValue>) -> Map<String, Value> {
+    inputs
+        .iter()
+        .map(|(key, value)| (key.clone(), serde_json::to_value(value).unwrap_or_default()))
+        .collect()
+}
+
+/// Full precision is kept, files written within the same second as the run finished would be stale otherwise
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cwl::types::File;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_get_input_locations() {
+        let inputs = json!({
+            "data": {"class": "File", "location": "data/population.csv"},
+            "folders": [{"class": "Directory", "location": "data/raw"}],
+            "name": "population"
+        });
+        assert_eq!(
+            get_input_locations(inputs.as_object().unwrap()),
+            vec!["data/population.csv".to_string(), "data/raw".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_run_is_stale() {
+        let dir = tempdir().unwrap();
+        let workflow = dir.path().join("main.cwl");
+        let data = dir.path().join("data.csv");
+        fs::write(&workflow, "class: Workflow").unwrap();
+        fs::write(&data, "a,b").unwrap();
+
+        let inputs = to_json_map(&HashMap::from([(
+            "data".to_string(),
+            DefaultValue::File(File::from_location(&data.to_string_lossy().into_owned())),
+        )]));
+        let run = WorkflowRun {
+            started: now(),
+            finished: now(),
+            success: true,
+            checksums: get_checksums(&inputs),
+            inputs,
+            steps: BTreeMap::new(),
+            commit: None,
+            checksum: get_checksum(&workflow),
+        };
+        assert!(!run.is_stale(&workflow));
+
+        //rewriting the same contents does not outdate the run
+        fs::write(&data, "a,b").unwrap();
+        assert!(!run.is_stale(&workflow));
+
+        fs::write(&data, "a,b,c").unwrap();
+        assert!(run.is_stale(&workflow));
+    }
+
+    #[test]
+    fn test_run_duration() {
+        let mut run = WorkflowRun {
+            started: "2024-05-02T10:00:00Z".to_string(),
+            finished: "2024-05-02T10:01:30.5Z".to_string(),
+            success: true,
+            inputs: Map::new(),
+            steps: BTreeMap::new(),
+            commit: None,
+            checksum: None,
+            checksums: BTreeMap::new(),
+        };
+        assert_eq!(run.duration(), Some(std::time::Duration::from_millis(90_500)));
+        run.finished = "unknown".to_string();
+        assert_eq!(run.duration(), None);
+    }
+
+    #[test]
+    fn test_record_step_run() {
+        let logs = Path::new("out/logs");
+        start_run_tracking(logs, Path::new("main.cwl"), &HashMap::new());
+        record_step_run(&logs.join("inner").join("echo_1"), &HashMap::new());
+        let steps = TRACKER.with(|tracker| tracker.borrow_mut().take().unwrap().steps);
+        assert!(!is_tracking());
+        assert_eq!(steps.keys().collect::<Vec<_>>(), vec!["inner/echo_1"]);
+    }
+}
@@ -0,0 +1,102 @@
+use cwl::{clt::CommandLineTool, requirements::Requirement};
+use std::{error::Error, path::Path, process::Command as SystemCommand};
+
+const BWRAP: &str = "bwrap";
+
+/// Directories hidden from sandboxed commands
+const HIDDEN_DIRECTORIES: [&str; 2] = ["/home", "/root"];
+
+/// Wraps a command to be run inside a bubblewrap sandbox. The whole filesystem is mounted read only, home directories
+/// are hidden and only the working directory is writable except for the given staged files. Network access is only
+/// granted if the tool requests it using `NetworkAccess`.
+pub fn sandbox_command(
+    command: &SystemCommand,
+    tool: &CommandLineTool,
+    working_directory: &Path,
+    read_only: &[String],
+) -> Result<SystemCommand, Box<dyn Error>> {
+    if !cfg!(target_os = "linux") {
+        return Err("❌ Sandboxing is only supported on Linux".into());
+    }
+    if SystemCommand::new(BWRAP).arg("--version").output().is_err() {
+        return Err("❌ Sandboxing requires bubblewrap (bwrap) to be installed".into());
+    }
+
+    let mut sandboxed = SystemCommand::new(BWRAP);
+    //symbolic links point outside of the working directory, which is read only anyway
+    let read_only = read_only.iter().filter(|path| !Path::new(path).is_symlink()).cloned().collect::<Vec<_>>();
+    sandboxed.args(get_sandbox_args(working_directory, has_network_access(tool), &read_only));
+    sandboxed.arg(command.get_program());
+    sandboxed.args(command.get_args());
+    //bwrap passes its environment on to the command
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => sandboxed.env(key, value),
+            None => sandboxed.env_remove(key),
+        };
+    }
+    sandboxed.current_dir(working_directory);
+    Ok(sandboxed)
+}
+
+/// Builds the arguments passed to `bwrap` in front of the actual command
+pub fn get_sandbox_args(working_directory: &Path, network_access: bool, read_only: &[String]) -> Vec<String> {
+    let working_directory = working_directory.to_string_lossy().into_owned();
+    let mut args = vec!["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for dir in HIDDEN_DIRECTORIES {
+        args.extend(["--tmpfs".to_string(), dir.to_string()]);
+    }
+    //bound last, so that it is writable even if located in one of the hidden directories
+    args.extend(["--bind".to_string(), working_directory.clone(), working_directory.clone()]);
+    //staged inputs may be hard links sharing their data with the original files
+    for path in read_only {
+        args.extend(["--ro-bind".to_string(), path.clone(), path.clone()]);
+    }
+    args.extend(["--chdir".to_string(), working_directory]);
+    args.push("--unshare-pid".to_string());
+    if !network_access {
+        args.push("--unshare-net".to_string());
+    }
+    args.extend(["--die-with-parent".to_string(), "--".to_string()]);
+    args
+}
+
+fn has_network_access(tool: &CommandLineTool) -> bool {
+    tool.requirements
+        .iter()
+        .chain(tool.hints.iter())
+        .flatten()
+        .any(|req| matches!(req, Requirement::NetworkAccess(access) if access.network_access))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cwl::requirements::NetworkAccess;
+
+    #[test]
+    fn test_get_sandbox_args() {
+        let args = get_sandbox_args(Path::new("/tmp/.tmpABC"), false, &["/tmp/.tmpABC/input.txt".to_string()]);
+        assert_eq!(args[..3], ["--ro-bind", "/", "/"]);
+        assert!(args.windows(3).any(|w| w == ["--bind", "/tmp/.tmpABC", "/tmp/.tmpABC"]));
+        assert!(args.windows(2).any(|w| w == ["--tmpfs", "/home"]));
+        assert!(args.windows(3).any(|w| w == ["--ro-bind", "/tmp/.tmpABC/input.txt", "/tmp/.tmpABC/input.txt"]));
+        assert!(args.contains(&"--unshare-net".to_string()));
+        assert_eq!(args.last().unwrap(), "--");
+
+        let args = get_sandbox_args(Path::new("/tmp/.tmpABC"), true, &[]);
+        assert!(!args.contains(&"--unshare-net".to_string()));
+    }
+
+    #[test]
+    fn test_has_network_access() {
+        let tool = CommandLineTool::default();
+        assert!(!has_network_access(&tool));
+
+        let tool = tool.with_requirements(vec![Requirement::NetworkAccess(NetworkAccess { network_access: true })]);
+        assert!(has_network_access(&tool));
+    }
+}
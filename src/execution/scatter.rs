@@ -0,0 +1,358 @@
+use crate::error::into_send_error;
+use cwl::{
+    types::{DefaultValue, OutputItem},
+    wf::ScatterMethod,
+};
+use serde_yaml::Value;
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+/// Input values of a single job
+pub type JobInputs = HashMap<String, DefaultValue>;
+
+/// Number of jobs generated ahead of the one currently running
+const JOB_QUEUE_SIZE: usize = 16;
+
+/// Jobs of a scattered step, which are generated one by one when iterating instead of up front. This keeps the memory
+/// needed for large parameter scans bounded by the size of the scattered arrays instead of the number of jobs.
+#[derive(Debug)]
+pub struct ScatterJobs {
+    shared: JobInputs,
+    scatter_inputs: Vec<String>,
+    arrays: Vec<Vec<DefaultValue>>,
+    method: ScatterMethod,
+    next: usize,
+    count: usize,
+}
+
+impl ScatterJobs {
+    /// Lengths of the scattered arrays, which are needed to gather the outputs again
+    pub fn lengths(&self) -> Vec<usize> {
+        self.arrays.iter().map(Vec::len).collect()
+    }
+
+    /// Indices into the scattered arrays used by the job with the given number. Crossproducts vary the last scattered
+    /// input fastest.
+    fn get_indices(&self, job: usize) -> Vec<usize> {
+        match self.method {
+            ScatterMethod::DotProduct => vec![job; self.arrays.len()],
+            ScatterMethod::NestedCrossProduct | ScatterMethod::FlatCrossProduct => {
+                let mut rest = job;
+                let mut indices = self
+                    .arrays
+                    .iter()
+                    .rev()
+                    .map(|items| {
+                        let index = rest % items.len();
+                        rest /= items.len();
+                        index
+                    })
+                    .collect::<Vec<_>>();
+                indices.reverse();
+                indices
+            }
+        }
+    }
+}
+
+impl Iterator for ScatterJobs {
+    type Item = JobInputs;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        let indices = self.get_indices(self.next);
+        self.next += 1;
+        let mut job = self.shared.clone();
+        for ((id, items), index) in self.scatter_inputs.iter().zip(&self.arrays).zip(indices) {
+            job.insert(id.clone(), items[index].clone());
+        }
+        Some(job)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for ScatterJobs {}
+
+/// Splits the inputs of a scattered step into the inputs of the single jobs, see [`ScatterJobs`]
+pub fn get_scatter_jobs(step_inputs: &JobInputs, scatter_inputs: &[String], method: ScatterMethod) -> Result<ScatterJobs, Box<dyn Error>> {
+    let mut arrays = vec![];
+    for id in scatter_inputs {
+        let items = match step_inputs.get(id) {
+            Some(DefaultValue::Any(Value::Sequence(items))) => items
+                .iter()
+                .map(|item| serde_yaml::from_value::<DefaultValue>(item.clone()))
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(format!("Scattered input `{id}` is not an array").into()),
+            None => return Err(format!("Scattered input `{id}` has no value").into()),
+        };
+        arrays.push(items);
+    }
+    let lengths = arrays.iter().map(Vec::len).collect::<Vec<_>>();
+
+    let count = match method {
+        ScatterMethod::DotProduct => {
+            if lengths.windows(2).any(|w| w[0] != w[1]) {
+                return Err(format!("Scattered inputs {scatter_inputs:?} need to have the same length for dotproduct, got {lengths:?}").into());
+            }
+            lengths.first().copied().unwrap_or_default()
+        }
+        ScatterMethod::NestedCrossProduct | ScatterMethod::FlatCrossProduct => lengths.iter().product(),
+    };
+
+    //scattered arrays are left out of the inputs shared by all jobs, copying them for every job is quadratic
+    let shared = step_inputs
+        .iter()
+        .filter(|(id, _)| !scatter_inputs.contains(id))
+        .map(|(id, value)| (id.clone(), value.clone()))
+        .collect::<JobInputs>();
+    Ok(ScatterJobs {
+        shared,
+        scatter_inputs: scatter_inputs.to_vec(),
+        arrays,
+        method,
+        next: 0,
+        count,
+    })
+}
+
+/// Runs the jobs of a scattered step on up to `parallel` threads, a single one runs them one after another on the
+/// calling thread. Jobs are generated in the background and handed over through a bounded queue, so only a few of them
+/// exist at the same time. No more jobs are started once one failed. The outputs are returned in the order of the jobs.
+pub fn dispatch_jobs<F>(jobs: ScatterJobs, parallel: usize, run: F) -> Result<Vec<HashMap<String, OutputItem>>, Box<dyn Error>>
+where
+    F: Fn(usize, JobInputs) -> Result<HashMap<String, OutputItem>, Box<dyn Error>> + Sync,
+{
+    let workers = parallel.clamp(1, jobs.len().max(1));
+    let (sender, receiver) = mpsc::sync_channel(JOB_QUEUE_SIZE);
+    let producer = thread::spawn(move || {
+        for job in jobs {
+            //the receiver is gone if a job failed
+            if sender.send(job).is_err() {
+                break;
+            }
+        }
+    });
+    //workers take the next job from the queue until it is empty or dropped by a failed job
+    let queue = Mutex::new(Some(receiver.into_iter().enumerate()));
+    let worker = || {
+        let mut results = vec![];
+        loop {
+            let next = queue.lock().ok().and_then(|mut queue| queue.as_mut().and_then(Iterator::next));
+            let Some((i, job)) = next else {
+                break;
+            };
+            let result = run(i, job);
+            if result.is_err() {
+                if let Ok(mut queue) = queue.lock() {
+                    *queue = None;
+                }
+            }
+            results.push((i, result));
+        }
+        results
+    };
+    let mut results = if workers == 1 {
+        worker()
+    } else {
+        //errors are handed over to the calling thread keeping their exit code
+        let handles = thread::scope(|scope| {
+            let handles = (0..workers)
+                .map(|_| scope.spawn(|| worker().into_iter().map(|(i, result)| (i, result.map_err(into_send_error))).collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|handle| handle.join()).collect::<Result<Vec<_>, _>>()
+        });
+        handles
+            .map_err(|_| "A scattered job panicked")?
+            .into_iter()
+            .flatten()
+            .map(|(i, result)| (i, result.map_err(|e| e as Box<dyn Error>)))
+            .collect()
+    };
+    drop(queue);
+    let generated = producer.join();
+
+    results.sort_by_key(|(i, _)| *i);
+    let job_outputs = results.into_iter().map(|(_, result)| result).collect::<Result<Vec<_>, _>>()?;
+    generated.map_err(|_| "Generating scatter jobs failed")?;
+    Ok(job_outputs)
+}
+
+/// Gathers the outputs of all jobs of a scattered step into arrays. Nested crossproducts create one level of
+/// nesting per scattered input.
+pub fn gather_outputs(
+    job_outputs: Vec<HashMap<String, OutputItem>>,
+    output_ids: &[String],
+    lengths: &[usize],
+    method: ScatterMethod,
+) -> HashMap<String, OutputItem> {
+    let mut outputs = HashMap::new();
+    for id in output_ids {
+        let items = job_outputs.iter().filter_map(|o| o.get(id).cloned()).collect::<Vec<_>>();
+        let value = match method {
+            ScatterMethod::NestedCrossProduct if items.len() == job_outputs.len() => nest(items, lengths),
+            _ => OutputItem::OutputArray(items),
+        };
+        outputs.insert(id.clone(), value);
+    }
+    outputs
+}
+
+fn nest(items: Vec<OutputItem>, lengths: &[usize]) -> OutputItem {
+    if lengths.len() <= 1 {
+        return OutputItem::OutputArray(items);
+    }
+    let chunk_size = lengths[1..].iter().product::<usize>().max(1);
+    let mut chunks = vec![];
+    let mut items = items.into_iter();
+    for _ in 0..lengths[0] {
+        let chunk = items.by_ref().take(chunk_size).collect();
+        chunks.push(nest(chunk, &lengths[1..]));
+    }
+    OutputItem::OutputArray(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Barrier,
+        },
+        time::Duration,
+    };
+
+    fn strings(values: &[&str]) -> DefaultValue {
+        DefaultValue::Any(Value::Sequence(values.iter().map(|v| Value::String(v.to_string())).collect()))
+    }
+
+    fn inputs() -> HashMap<String, DefaultValue> {
+        HashMap::from([
+            ("a".to_string(), strings(&["one", "two"])),
+            ("b".to_string(), strings(&["x", "y", "z"])),
+            ("c".to_string(), DefaultValue::Any(Value::String("fixed".to_string()))),
+        ])
+    }
+
+    #[test]
+    fn test_scatter_dotproduct() {
+        let inputs = HashMap::from([("a".to_string(), strings(&["one", "two"])), ("b".to_string(), strings(&["x", "y"]))]);
+        let jobs = get_scatter_jobs(&inputs, &["a".to_string(), "b".to_string()], ScatterMethod::DotProduct).unwrap();
+        assert_eq!(jobs.lengths(), vec![2, 2]);
+        let jobs = jobs.collect::<Vec<_>>();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[1]["a"].as_value_string(), "two");
+        assert_eq!(jobs[1]["b"].as_value_string(), "y");
+    }
+
+    #[test]
+    fn test_scatter_dotproduct_length_mismatch() {
+        let result = get_scatter_jobs(&inputs(), &["a".to_string(), "b".to_string()], ScatterMethod::DotProduct);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_scatter_crossproduct() {
+        let jobs = get_scatter_jobs(&inputs(), &["a".to_string(), "b".to_string()], ScatterMethod::FlatCrossProduct).unwrap();
+        assert_eq!(jobs.lengths(), vec![2, 3]);
+        let jobs = jobs.collect::<Vec<_>>();
+        assert_eq!(jobs.len(), 6);
+        assert_eq!(jobs[4]["a"].as_value_string(), "two");
+        assert_eq!(jobs[4]["b"].as_value_string(), "y");
+        assert_eq!(jobs[4]["c"].as_value_string(), "fixed");
+    }
+
+    #[test]
+    fn test_dispatch_jobs() {
+        let inputs = HashMap::from([("a".to_string(), DefaultValue::Any(Value::Sequence((0..100).map(Value::from).collect())))]);
+        let jobs = get_scatter_jobs(&inputs, &["a".to_string()], ScatterMethod::DotProduct).unwrap();
+        let outputs = dispatch_jobs(jobs, 1, |i, job| {
+            assert_eq!(job["a"].as_value_string(), i.to_string());
+            Ok(HashMap::from([("out".to_string(), OutputItem::OutputString(i.to_string()))]))
+        })
+        .unwrap();
+        assert_eq!(outputs.len(), 100);
+        assert_eq!(outputs[42]["out"], OutputItem::OutputString("42".to_string()));
+
+        //jobs following a failed one are not run
+        let jobs = get_scatter_jobs(&inputs, &["a".to_string()], ScatterMethod::DotProduct).unwrap();
+        let runs = AtomicUsize::new(0);
+        let result = dispatch_jobs(jobs, 1, |i, _| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            if i == 3 {
+                Err("failed".into())
+            } else {
+                Ok(HashMap::new())
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_dispatch_jobs_parallel() {
+        let inputs = HashMap::from([("a".to_string(), DefaultValue::Any(Value::Sequence((0..40).map(Value::from).collect())))]);
+        let jobs = get_scatter_jobs(&inputs, &["a".to_string()], ScatterMethod::DotProduct).unwrap();
+        let running = AtomicUsize::new(0);
+        let most_running = AtomicUsize::new(0);
+        //every job waits for a second one, so this only finishes if jobs run at the same time
+        let barrier = Barrier::new(2);
+        let outputs = dispatch_jobs(jobs, 4, |i, job| {
+            assert_eq!(job["a"].as_value_string(), i.to_string());
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now_running, Ordering::SeqCst);
+            barrier.wait();
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(HashMap::from([("out".to_string(), OutputItem::OutputString(i.to_string()))]))
+        })
+        .unwrap();
+        //outputs keep the order of the jobs regardless of when they finished
+        assert_eq!(outputs.len(), 40);
+        assert!(outputs.iter().enumerate().all(|(i, o)| o["out"] == OutputItem::OutputString(i.to_string())));
+        let most_running = most_running.load(Ordering::SeqCst);
+        assert!(most_running > 1 && most_running <= 4);
+
+        let jobs = get_scatter_jobs(&inputs, &["a".to_string()], ScatterMethod::DotProduct).unwrap();
+        let runs = AtomicUsize::new(0);
+        let result = dispatch_jobs(jobs, 4, |i, _| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            if i == 3 {
+                Err(format!("job {i} failed").into())
+            } else {
+                thread::sleep(Duration::from_millis(5));
+                Ok(HashMap::new())
+            }
+        });
+        assert_eq!(result.unwrap_err().to_string(), "job 3 failed");
+        assert!(runs.load(Ordering::SeqCst) < 40);
+    }
+
+    #[test]
+    fn test_gather_outputs_nested() {
+        let job_outputs = (0..6)
+            .map(|i| HashMap::from([("out".to_string(), OutputItem::OutputString(i.to_string()))]))
+            .collect::<Vec<_>>();
+        let outputs = gather_outputs(job_outputs.clone(), &["out".to_string()], &[2, 3], ScatterMethod::NestedCrossProduct);
+        let OutputItem::OutputArray(rows) = &outputs["out"] else { panic!() };
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], OutputItem::OutputArray(vec![
+            OutputItem::OutputString("3".to_string()),
+            OutputItem::OutputString("4".to_string()),
+            OutputItem::OutputString("5".to_string()),
+        ]));
+
+        let outputs = gather_outputs(job_outputs, &["out".to_string()], &[2, 3], ScatterMethod::FlatCrossProduct);
+        let OutputItem::OutputArray(items) = &outputs["out"] else { panic!() };
+        assert_eq!(items.len(), 6);
+    }
+}
@@ -1,5 +1,9 @@
-use super::util::evaluate_input;
-use crate::io::{copy_dir, copy_file, create_and_write_file, make_relative_to};
+use super::{
+    expression::{add_file_properties, has_expressions, ExpressionContext},
+    util::evaluate_input,
+};
+use crate::io::{copy_dir, copy_file, create_and_write_file, get_file_property, make_relative_to};
+use clap::ValueEnum;
 use cwl::{
     clt::CommandLineTool,
     inputs::CommandInputParameter,
@@ -7,19 +11,50 @@ use cwl::{
     requirements::Requirement,
     types::{CWLType, DefaultValue, Entry},
 };
+use log::debug;
 use std::{
+    cell::RefCell,
     collections::HashMap,
     env,
     error::Error,
-    fs,
-    path::{Path, MAIN_SEPARATOR_STR},
+    fs, io,
+    path::{Path, PathBuf, MAIN_SEPARATOR_STR},
     vec,
 };
+use serde_json::Value;
 use urlencoding::decode;
 
+/// How input files and directories are placed into the working directory of a tool
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum StagingStrategy {
+    /// Copies inputs, always works but is slow for large files
+    #[default]
+    Copy,
+    /// Creates symbolic links pointing to the inputs
+    Symlink,
+    /// Creates hard links of the inputs, directories are recreated with their files being linked
+    Hardlink,
+    /// Uses hard links if possible, symbolic links otherwise
+    Auto,
+}
+
+thread_local!(static STAGING_STRATEGY: RefCell<StagingStrategy> = const { RefCell::new(StagingStrategy::Copy) });
+
+/// Sets how inputs are staged, staging falls back to copying if links can not be created, e.g. across file systems
+pub fn set_staging_strategy(value: StagingStrategy) {
+    STAGING_STRATEGY.with(|strategy| {
+        *strategy.borrow_mut() = value;
+    });
+}
+
+pub fn staging_strategy() -> StagingStrategy {
+    STAGING_STRATEGY.with(|strategy| *strategy.borrow())
+}
+
 pub fn stage_required_files<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
     tool: &CommandLineTool,
     input_values: &Option<HashMap<String, DefaultValue>>,
+    context: &ExpressionContext,
     tool_path: P,
     path: Q,
     out_dir: R,
@@ -28,18 +63,31 @@ pub fn stage_required_files<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
     //stage requirements
     staged_files.extend(stage_requirements(&tool.requirements, tool_path.as_ref(), path.as_ref())?);
 
+    //tools writing to their inputs would alter the original files through links, so their inputs are copied
+    let strategy = staging_strategy();
+    if strategy != StagingStrategy::Copy && may_modify_inputs(tool) {
+        debug!("Tool may modify its inputs, copying them instead of using {:?} staging", strategy);
+        set_staging_strategy(StagingStrategy::Copy);
+    }
+
     //stage inputs
-    staged_files.extend(stage_input_files(
-        &tool.inputs,
-        input_values,
-        tool_path.as_ref(),
-        path.as_ref(),
-        out_dir.as_ref(),
-    )?);
+    let staged_inputs = stage_input_files(&tool.inputs, input_values, context, tool_path.as_ref(), path.as_ref(), out_dir.as_ref());
+    set_staging_strategy(strategy);
+    staged_files.extend(staged_inputs?);
 
     Ok(staged_files)
 }
 
+/// Tools requiring `InplaceUpdateRequirement` or listing writable `InitialWorkDirRequirement` entries may write to
+/// their inputs
+pub fn may_modify_inputs(tool: &CommandLineTool) -> bool {
+    tool.requirements.iter().chain(tool.hints.iter()).flatten().any(|requirement| match requirement {
+        Requirement::InplaceUpdateRequirement(inplace) => inplace.inplace_update,
+        Requirement::InitialWorkDirRequirement(iwdr) => iwdr.listing.iter().any(|listing| listing.writable),
+        _ => false,
+    })
+}
+
 pub fn unstage_files(staged_files: &[String], tmp_dir: &Path, outputs: &[CommandOutputParameter]) -> Result<(), Box<dyn Error>> {
     for file in staged_files {
         let mut should_remove = true;
@@ -105,6 +153,7 @@ fn stage_requirements(requirements: &Option<Vec<Requirement>>, tool_path: &Path,
 fn stage_input_files(
     inputs: &[CommandInputParameter],
     input_values: &Option<HashMap<String, DefaultValue>>,
+    context: &ExpressionContext,
     tool_path: &Path,
     path: &Path,
     out_dir: &Path,
@@ -116,7 +165,7 @@ fn stage_input_files(
         if input.type_ != CWLType::File && input.type_ != CWLType::Directory {
             continue;
         }
-        let incoming_data = evaluate_input(input, input_values)?;
+        let incoming_data = evaluate_input(input, input_values.as_ref())?;
         let mut incoming_file = incoming_data.as_value_string();
         //decode special characters
         incoming_file = decode(&incoming_file).unwrap().to_string();
@@ -139,10 +188,23 @@ fn stage_input_files(
         let path_str = &into_path.to_string_lossy();
 
         if input.type_ == CWLType::File {
-            copy_file(&incoming_file, &into_path).map_err(|e| format!("Failed to copy file from {} to {}: {}", incoming_file, path_str, e))?;
+            stage_file(&incoming_file, &into_path).map_err(|e| format!("Failed to stage file from {} to {}: {}", incoming_file, path_str, e))?;
             staged_files.push(path_str.clone().into_owned());
+
+            //files matching secondaryFiles patterns are placed next to the primary file
+            for secondary_file in resolve_secondary_files(input, &incoming_path, context)? {
+                let into_path = into_path.with_file_name(secondary_file.file_name().unwrap_or_default());
+                if secondary_file.is_dir() {
+                    stage_dir(&secondary_file, &into_path)
+                        .map_err(|e| format!("Failed to stage directory from {:?} to {:?}: {}", secondary_file, into_path, e))?;
+                } else {
+                    stage_file(&secondary_file, &into_path)
+                        .map_err(|e| format!("Failed to stage file from {:?} to {:?}: {}", secondary_file, into_path, e))?;
+                }
+                staged_files.push(into_path.to_string_lossy().into_owned());
+            }
         } else if input.type_ == CWLType::Directory {
-            copy_dir(&incoming_file, &into_path).map_err(|e| format!("Failed to copy directory from {} to {}: {}", incoming_file, path_str, e))?;
+            stage_dir(&incoming_file, &into_path).map_err(|e| format!("Failed to stage directory from {} to {}: {}", incoming_file, path_str, e))?;
             staged_files.push(path_str.clone().into_owned());
         }
         staged_files.extend(stage_secondary_files(incoming_data, path)?);
@@ -162,13 +224,13 @@ fn stage_secondary_files(incoming_data: DefaultValue, path: &Path) -> Result<Vec
                 let path_str = &into_path.to_string_lossy();
                 match value {
                     DefaultValue::File(_) => {
-                        copy_file(&incoming_file, &into_path)
-                            .map_err(|e| format!("Failed to copy file from {} to {:?}: {}", incoming_file, into_path, e))?;
+                        stage_file(&incoming_file, &into_path)
+                            .map_err(|e| format!("Failed to stage file from {} to {:?}: {}", incoming_file, into_path, e))?;
                         staged_files.push(path_str.clone().into_owned());
                     }
                     DefaultValue::Directory(_) => {
-                        copy_dir(&incoming_file, &into_path)
-                            .map_err(|e| format!("Failed to copy directory from {} to {:?}: {}", incoming_file, into_path, e))?;
+                        stage_dir(&incoming_file, &into_path)
+                            .map_err(|e| format!("Failed to stage directory from {} to {:?}: {}", incoming_file, into_path, e))?;
                         staged_files.push(path_str.clone().into_owned());
                     }
                     _ => {}
@@ -179,6 +241,130 @@ fn stage_secondary_files(incoming_data: DefaultValue, path: &Path) -> Result<Vec
     Ok(staged_files)
 }
 
+/// Places a file at the given destination using the current staging strategy
+fn stage_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let strategy = staging_strategy();
+    if strategy != StagingStrategy::Copy {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        //writing through an existing link would alter the file it points to
+        if fs::symlink_metadata(to).is_ok_and(|m| !m.is_dir()) {
+            fs::remove_file(to)?;
+        }
+        if matches!(strategy, StagingStrategy::Hardlink | StagingStrategy::Auto) && fs::hard_link(from, to).is_ok() {
+            return Ok(());
+        }
+        if matches!(strategy, StagingStrategy::Symlink | StagingStrategy::Auto) && symlink(from, to, false).is_ok() {
+            return Ok(());
+        }
+        debug!("Could not link {:?}, copying instead", from);
+    }
+    copy_file(from, to)
+}
+
+/// Places a directory at the given destination using the current staging strategy. Directories can not be hard
+/// linked, so their structure is recreated and each file is staged on its own.
+fn stage_dir<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    match staging_strategy() {
+        StagingStrategy::Copy => copy_dir(from, to).map(|_| ()),
+        StagingStrategy::Symlink => {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if symlink(from, to, true).is_ok() {
+                return Ok(());
+            }
+            debug!("Could not link {:?}, copying instead", from);
+            copy_dir(from, to).map(|_| ())
+        }
+        StagingStrategy::Hardlink | StagingStrategy::Auto => {
+            fs::create_dir_all(to)?;
+            for entry in fs::read_dir(from)? {
+                let entry = entry?;
+                let dest = to.join(entry.file_name());
+                if entry.path().is_dir() {
+                    stage_dir(entry.path(), dest)?;
+                } else {
+                    stage_file(entry.path(), dest)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Links need absolute targets to stay valid from within the working directory
+fn symlink(from: &Path, to: &Path, is_dir: bool) -> io::Result<()> {
+    let target = fs::canonicalize(from)?;
+    #[cfg(unix)]
+    {
+        let _ = is_dir;
+        std::os::unix::fs::symlink(target, to)
+    }
+    #[cfg(windows)]
+    {
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, to)
+        } else {
+            std::os::windows::fs::symlink_file(target, to)
+        }
+    }
+}
+
+/// Resolves the `secondaryFiles` of a File input against the location of its primary file. Each leading `^` of a
+/// pattern removes one extension of the primary file, expressions are evaluated with `self` being the primary file.
+fn resolve_secondary_files(input: &CommandInputParameter, primary: &Path, context: &ExpressionContext) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = vec![];
+    let directory = primary.parent().unwrap_or(Path::new("."));
+    let basename = get_file_property(&primary.to_string_lossy(), "basename");
+    for secondary_file in input.secondary_files.iter().flatten() {
+        let pattern = secondary_file.pattern();
+        let names = if has_expressions(pattern) {
+            let self_ = add_file_properties(serde_json::json!({"class": "File", "location": primary.to_string_lossy()}));
+            match context.evaluate(pattern, &self_)? {
+                Value::Array(values) => values.iter().filter_map(get_secondary_location).collect(),
+                value => get_secondary_location(&value).into_iter().collect(),
+            }
+        } else {
+            vec![apply_pattern(&basename, pattern)]
+        };
+
+        for name in names {
+            let path = directory.join(&name);
+            if path.exists() {
+                files.push(path);
+            } else if secondary_file.is_required() {
+                return Err(format!("❌ Secondary file {:?} of input `{}` does not exist", path, input.id).into());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Expressions may return file names or File and Directory objects
+fn get_secondary_location(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.clone()),
+        Value::Object(map) => map.get("path").or(map.get("location")).and_then(|l| l.as_str()).map(str::to_string),
+        _ => None,
+    }
+}
+
+fn apply_pattern(basename: &str, pattern: &str) -> String {
+    let mut name = basename.to_string();
+    let mut pattern = pattern;
+    while let Some(rest) = pattern.strip_prefix('^') {
+        if let Some(index) = name.rfind('.') {
+            name.truncate(index);
+        }
+        pattern = rest;
+    }
+    name + pattern
+}
+
 fn handle_filename(value: &DefaultValue) -> String {
     let join_with_basename = |location: &str, basename: &Option<String>| {
         if let Some(basename) = basename {
@@ -199,6 +385,7 @@ fn handle_filename(value: &DefaultValue) -> String {
 mod tests {
     use super::*;
     use cwl::{
+        inputs::SecondaryFile,
         outputs::CommandOutputBinding,
         requirements::InitialWorkDirRequirement,
         types::{Directory, File},
@@ -207,6 +394,109 @@ mod tests {
     use std::{path::PathBuf, vec};
     use tempfile::tempdir;
 
+    fn context() -> ExpressionContext {
+        ExpressionContext::new(&CommandLineTool::default(), None, &HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_apply_pattern() {
+        assert_eq!(apply_pattern("reads.bam", ".bai"), "reads.bam.bai");
+        assert_eq!(apply_pattern("reference.fa", "^.dict"), "reference.dict");
+        assert_eq!(apply_pattern("reads.fastq.gz", "^^.idx"), "reads.idx");
+        assert_eq!(apply_pattern("reads", "^.bai"), "reads.bai");
+    }
+
+    #[test]
+    fn test_stage_input_files_secondary_patterns() {
+        let data = tempdir().unwrap();
+        for file in ["reference.fa", "reference.fa.fai", "reference.dict"] {
+            fs::write(data.path().join(file), file).unwrap();
+        }
+        let primary = data.path().join("reference.fa").to_string_lossy().into_owned();
+        let mut input = CommandInputParameter::default()
+            .with_id("reference")
+            .with_type(CWLType::File)
+            .with_default_value(DefaultValue::File(File::from_location(&primary)));
+        input.secondary_files = serde_yaml::from_str("- .fai\n- ^.dict\n- pattern: .amb\n  required: false").unwrap();
+
+        let tmp_dir = tempdir().unwrap();
+        let list = stage_input_files(&[input.clone()], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        assert_eq!(list.len(), 3);
+        let staged = Path::new(&list[0]);
+        assert_eq!(fs::read_to_string(staged.with_file_name("reference.fa.fai")).unwrap(), "reference.fa.fai");
+        assert_eq!(fs::read_to_string(staged.with_file_name("reference.dict")).unwrap(), "reference.dict");
+
+        //missing required secondary files are an error
+        input.secondary_files = Some(vec![SecondaryFile::Pattern(".amb".to_string())]);
+        let tmp_dir = tempdir().unwrap();
+        assert!(stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).is_err());
+    }
+
+    #[test]
+    fn test_stage_input_files_strategies() {
+        let data = tempdir().unwrap();
+        fs::create_dir(data.path().join("dir")).unwrap();
+        fs::write(data.path().join("dir/data.csv"), "a,b").unwrap();
+        fs::write(data.path().join("data.csv"), "a,b").unwrap();
+        let file = CommandInputParameter::default()
+            .with_id("file")
+            .with_type(CWLType::File)
+            .with_default_value(DefaultValue::File(File::from_location(&data.path().join("data.csv").to_string_lossy().into_owned())));
+        let dir = CommandInputParameter::default()
+            .with_id("dir")
+            .with_type(CWLType::Directory)
+            .with_default_value(DefaultValue::Directory(Directory::from_location(&data.path().join("dir").to_string_lossy().into_owned())));
+
+        for strategy in [StagingStrategy::Symlink, StagingStrategy::Hardlink, StagingStrategy::Auto] {
+            set_staging_strategy(strategy);
+            let tmp_dir = tempdir().unwrap();
+            let result = stage_input_files(&[file.clone(), dir.clone()], &None, &context(), Path::new("."), tmp_dir.path(), data.path());
+            set_staging_strategy(StagingStrategy::Copy);
+            let list = result.unwrap();
+            assert_eq!(list.len(), 2);
+            assert_eq!(fs::read_to_string(&list[0]).unwrap(), "a,b");
+            assert_eq!(fs::read_to_string(Path::new(&list[1]).join("data.csv")).unwrap(), "a,b");
+            let is_symlink = fs::symlink_metadata(&list[0]).unwrap().file_type().is_symlink();
+            assert_eq!(is_symlink, strategy == StagingStrategy::Symlink);
+
+            //removing staged links keeps the original data
+            unstage_files(&list, tmp_dir.path(), &[]).unwrap();
+            assert!(!Path::new(&list[1]).exists());
+            assert_eq!(fs::read_to_string(data.path().join("dir/data.csv")).unwrap(), "a,b");
+            assert_eq!(fs::read_to_string(data.path().join("data.csv")).unwrap(), "a,b");
+        }
+    }
+
+    #[test]
+    fn test_stage_required_files_inplace_update() {
+        let data = tempdir().unwrap();
+        fs::write(data.path().join("data.csv"), "a,b").unwrap();
+        let location = data.path().join("data.csv").to_string_lossy().into_owned();
+        let mut tool: CommandLineTool = serde_yaml::from_str(&format!(
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: touch\ninputs:\n  file:\n    type: File\n    default:\n      class: File\n      location: {location}\noutputs: []\n"
+        ))
+        .unwrap();
+        assert!(!may_modify_inputs(&tool));
+        tool.requirements = serde_yaml::from_str("- class: InplaceUpdateRequirement\n  inplaceUpdate: true").unwrap();
+        assert!(may_modify_inputs(&tool));
+        let writable: CommandLineTool = serde_yaml::from_str(
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: touch\ninputs: []\noutputs: []\nhints:\n  InitialWorkDirRequirement:\n    listing:\n      - entryname: data.csv\n        entry: $(inputs.file)\n        writable: true\n",
+        )
+        .unwrap();
+        assert!(may_modify_inputs(&writable));
+
+        //writing to the staged input must not alter the original file
+        set_staging_strategy(StagingStrategy::Hardlink);
+        let tmp_dir = tempdir().unwrap();
+        let result = stage_required_files(&tool, &None, &context(), Path::new("."), tmp_dir.path(), data.path());
+        let strategy = staging_strategy();
+        set_staging_strategy(StagingStrategy::Copy);
+        assert_eq!(strategy, StagingStrategy::Hardlink);
+        let list = result.unwrap();
+        fs::write(&list[0], "changed").unwrap();
+        assert_eq!(fs::read_to_string(data.path().join("data.csv")).unwrap(), "a,b");
+    }
+
     #[test]
     #[serial]
     fn test_stage_requirement() {
@@ -258,7 +548,7 @@ mod tests {
             .with_type(CWLType::Directory)
             .with_default_value(DefaultValue::Directory(Directory::from_location(&test_dir.to_string())));
 
-        let list = stage_input_files(&[input], &None, Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        let list = stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
 
         let expected_path = tmp_dir.path().join(test_dir);
 
@@ -279,7 +569,7 @@ mod tests {
             .with_type(CWLType::File)
             .with_default_value(DefaultValue::File(File::from_location(&test_dir.to_string())));
 
-        let list = stage_input_files(&[input], &None, Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        let list = stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
 
         let expected_path = tmp_dir.path().join(test_dir);
 
@@ -299,7 +589,7 @@ mod tests {
             .with_type(CWLType::File)
             .with_default_value(DefaultValue::File(File::from_location(&test_dir.to_string())));
 
-        let list = stage_input_files(&[input], &None, Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        let list = stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
 
         unstage_files(&list, tmp_dir.path(), &[]).unwrap();
         //file should be gone
@@ -318,7 +608,7 @@ mod tests {
             .with_type(CWLType::Directory)
             .with_default_value(DefaultValue::Directory(Directory::from_location(&test_dir.to_string())));
 
-        let list = stage_input_files(&[input], &None, Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        let list = stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
 
         unstage_files(&list, tmp_dir.path(), &[]).unwrap();
         //file should be gone
@@ -339,9 +629,10 @@ mod tests {
 
         let output = CommandOutputParameter::default().with_binding(CommandOutputBinding {
             glob: "tests/test_data/input.txt".to_string(),
+            ..Default::default()
         });
 
-        let list = stage_input_files(&[input], &None, Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
+        let list = stage_input_files(&[input], &None, &context(), Path::new("."), tmp_dir.path(), &PathBuf::from("")).unwrap();
 
         unstage_files(&list, tmp_dir.path(), &[output]).unwrap();
         //file should still be there
@@ -1,23 +1,23 @@
-use crate::io::{copy_file, get_file_checksum, get_file_size, get_first_file_with_prefix, print_output};
+use crate::io::{copy_file, get_file_checksum, get_file_size, get_first_file_with_prefix};
 use cwl::{
     inputs::CommandInputParameter,
     outputs::CommandOutputParameter,
     types::{CWLType, DefaultValue, OutputDirectory, OutputFile, OutputItem},
 };
 use fancy_regex::Regex;
-use std::{collections::HashMap, env, error::Error, fmt::Debug, fs, path::Path};
+use std::{collections::HashMap, error::Error, fmt::Debug, fs, path::Path};
 
 ///Either gets the default value for input or the provided one (preferred)
 pub fn evaluate_input_as_string(
     input: &CommandInputParameter,
-    input_values: &Option<HashMap<String, DefaultValue>>,
+    input_values: Option<&HashMap<String, DefaultValue>>,
 ) -> Result<String, Box<dyn Error>> {
     Ok(evaluate_input(input, input_values)?.as_value_string())
 }
 
 ///Either gets the default value for input or the provided one (preferred)
-pub fn evaluate_input(input: &CommandInputParameter, input_values: &Option<HashMap<String, DefaultValue>>) -> Result<DefaultValue, Box<dyn Error>> {
-    if let Some(ref values) = input_values {
+pub fn evaluate_input(input: &CommandInputParameter, input_values: Option<&HashMap<String, DefaultValue>>) -> Result<DefaultValue, Box<dyn Error>> {
+    if let Some(values) = input_values {
         if let Some(value) = values.get(&input.id) {
             if !value.has_matching_type(&input.type_) {
                 //change handling accordingly in utils on main branch!
@@ -37,9 +37,10 @@ pub fn evaluate_input(input: &CommandInputParameter, input_values: &Option<HashM
     Err(format!("Could not evaluate input: {}", input.id))?
 }
 
-///Copies back requested outputs and writes to commandline
+///Copies back requested outputs from the working directory of the tool and writes to commandline
 pub fn evaluate_outputs(
     tool_outputs: &Vec<CommandOutputParameter>,
+    working_dir: &Path,
     initial_dir: &Path,
     tool_stdout: &Option<String>,
     tool_stderr: &Option<String>,
@@ -47,10 +48,15 @@ pub fn evaluate_outputs(
     //copy back requested output
     let mut outputs: HashMap<String, OutputItem> = HashMap::new();
     for output in tool_outputs {
+        //outputs with outputEval are handled by the expression engine
+        if cfg!(feature = "javascript") && output.output_binding.as_ref().is_some_and(|b| b.output_eval.is_some()) {
+            continue;
+        }
         if output.type_ == CWLType::File || output.type_ == CWLType::Stdout || output.type_ == CWLType::Stderr {
             if let Some(binding) = &output.output_binding {
                 let path = &initial_dir.join(&binding.glob);
-                fs::copy(&binding.glob, path).map_err(|e| format!("Failed to copy file from {:?} to {:?}: {}", &binding.glob, path, e))?;
+                let source = working_dir.join(&binding.glob);
+                fs::copy(&source, path).map_err(|e| format!("Failed to copy file from {:?} to {:?}: {}", source, path, e))?;
                 eprintln!("📜 Wrote output file: {:?}", path);
                 outputs.insert(output.id.clone(), OutputItem::OutputFile(get_file_metadata(path, output.format.clone())));
             } else {
@@ -64,11 +70,12 @@ pub fn evaluate_outputs(
                             CWLType::Stderr => "_stderr",
                             _ => "",
                         };
-                        &get_first_file_with_prefix(".", &file_prefix).unwrap_or_default()
+                        &get_first_file_with_prefix(working_dir, &file_prefix).unwrap_or_default()
                     }
                 };
                 let path = &initial_dir.join(filename);
-                fs::copy(filename, path).map_err(|e| format!("Failed to copy file from {:?} to {:?}: {}", &filename, path, e))?;
+                let source = working_dir.join(filename);
+                fs::copy(&source, path).map_err(|e| format!("Failed to copy file from {:?} to {:?}: {}", source, path, e))?;
                 eprintln!("📜 Wrote output file: {:?}", path);
                 outputs.insert(output.id.clone(), OutputItem::OutputFile(get_file_metadata(path, output.format.clone())));
             }
@@ -77,8 +84,7 @@ pub fn evaluate_outputs(
                 let dir = if &binding.glob != "." {
                     &initial_dir.join(&binding.glob)
                 } else {
-                    let working_dir = env::current_dir()?;
-                    let raw_basename = working_dir.file_name().unwrap().to_string_lossy();
+                    let raw_basename = working_dir.file_name().unwrap_or_default().to_string_lossy();
                     let glob_name = if let Some(stripped) = raw_basename.strip_prefix(".") {
                         stripped.to_owned()
                     } else {
@@ -87,22 +93,17 @@ pub fn evaluate_outputs(
                     &initial_dir.join(&glob_name)
                 };
                 fs::create_dir_all(dir)?;
-                let out_dir = copy_output_dir(&binding.glob, dir.to_str().unwrap()).map_err(|e| format!("Failed to copy: {}", e))?;
+                let out_dir = copy_output_dir(working_dir.join(&binding.glob), dir.to_str().unwrap()).map_err(|e| format!("Failed to copy: {}", e))?;
                 outputs.insert(output.id.clone(), OutputItem::OutputDirectory(out_dir));
             }
         } else if output.type_ == CWLType::String {
             //string and has binding -> read file
             if let Some(binding) = &output.output_binding {
-                let contents = fs::read_to_string(&binding.glob)?;
+                let contents = fs::read_to_string(working_dir.join(&binding.glob))?;
                 outputs.insert(output.id.clone(), OutputItem::OutputString(contents));
             }
         }
     }
-    if print_output() {
-        //print output metadata
-        let json = serde_json::to_string_pretty(&outputs)?;
-        println!("{}", json);
-    }
     Ok(outputs)
 }
 
@@ -144,12 +145,15 @@ pub fn get_diretory_metadata<P: AsRef<Path>>(path: P) -> OutputDirectory {
     }
 }
 
+/// Copies a directory and captures its full listing, including the metadata of all contained files and directories
 pub fn copy_output_dir<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dest: Q) -> Result<OutputDirectory, std::io::Error> {
     fs::create_dir_all(&dest)?;
     let mut dir = get_diretory_metadata(&dest);
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
+    //sorted to get a stable listing
+    let mut entries = fs::read_dir(src)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
         let src_path = entry.path();
         let dest_path = dest.as_ref().join(entry.file_name());
         if src_path.is_dir() {
@@ -203,6 +207,7 @@ mod tests {
     };
     use serde_yaml::{value, Value};
     use serial_test::serial;
+    use std::env;
     use tempfile::tempdir;
 
     #[test]
@@ -214,7 +219,7 @@ mod tests {
         let mut values = HashMap::new();
         values.insert("test".to_string(), DefaultValue::Any(value::Value::String("Hello!".to_string())));
 
-        let evaluation = evaluate_input(&input, &Some(values.clone())).unwrap();
+        let evaluation = evaluate_input(&input, Some(&values)).unwrap();
 
         assert_eq!(evaluation, values["test"]);
     }
@@ -228,7 +233,7 @@ mod tests {
         let mut values = HashMap::new();
         values.insert("test".to_string(), DefaultValue::Any(value::Value::String("Hello!".to_string())));
 
-        let evaluation = evaluate_input_as_string(&input, &Some(values.clone())).unwrap();
+        let evaluation = evaluate_input_as_string(&input, Some(&values)).unwrap();
 
         assert_eq!(evaluation, values["test"].as_value_string());
     }
@@ -241,7 +246,7 @@ mod tests {
             .with_binding(CommandLineBinding::default().with_prefix(&"--arg".to_string()))
             .with_default_value(DefaultValue::Any(Value::String("Nice".to_string())));
         let values = HashMap::new();
-        let evaluation = evaluate_input_as_string(&input, &Some(values.clone())).unwrap();
+        let evaluation = evaluate_input_as_string(&input, Some(&values)).unwrap();
 
         assert_eq!(evaluation, "Nice".to_string());
     }
@@ -253,7 +258,7 @@ mod tests {
             .with_type(CWLType::String)
             .with_binding(CommandLineBinding::default().with_prefix(&"--arg".to_string()))
             .with_default_value(DefaultValue::Any(Value::String("Nice".to_string())));
-        let evaluation = evaluate_input_as_string(&input, &None).unwrap();
+        let evaluation = evaluate_input_as_string(&input, None).unwrap();
 
         assert_eq!(evaluation, "Nice".to_string());
     }
@@ -269,16 +274,14 @@ mod tests {
             .with_type(CWLType::File)
             .with_binding(CommandOutputBinding {
                 glob: "tests/test_data/file.txt".to_string(),
+                ..Default::default()
             });
 
         fs::create_dir_all(dir.path().join("tests/test_data")).expect("Could not create folders");
         fs::copy("tests/test_data/file.txt", dir.path().join("tests/test_data/file.txt")).expect("Unable to copy file");
-        env::set_current_dir(dir.path()).unwrap();
 
-        let result = evaluate_outputs(&vec![output], &current, &None, &None);
+        let result = evaluate_outputs(&vec![output], dir.path(), &current, &None, &None);
         assert!(result.is_ok());
-
-        env::set_current_dir(current).unwrap();
     }
 
     #[test]
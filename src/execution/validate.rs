@@ -1,13 +1,13 @@
-use crate::io::{get_file_property, make_relative_to};
+use crate::io::{get_file_property, is_binary_file, make_relative_to};
 use cwl::{
     clt::{Argument, Command, CommandLineTool},
     inputs::CommandInputParameter,
     requirements::Requirement,
-    types::{DefaultValue, Directory, Entry, EnviromentDefs, File},
+    types::{DefaultValue, Directory, Entry, EnviromentDefs, File, Include},
 };
 use fancy_regex::Regex;
 use pathdiff::diff_paths;
-use std::{collections::HashMap, env};
+use std::{collections::HashMap, path::Path};
 
 /// Replaces placeholders like $(inputs.test) or $(runtime.cpu) with its actual evaluated values
 pub fn set_placeholder_values(cwl: &mut CommandLineTool, input_values: Option<&HashMap<String, DefaultValue>>, runtime: &HashMap<String, String>) {
@@ -74,13 +74,19 @@ pub fn set_placeholder_values(cwl: &mut CommandLineTool, input_values: Option<&H
     }
 }
 
-pub fn rewire_paths(cwl: &mut CommandLineTool, input_values: &mut Option<HashMap<String, DefaultValue>>, staged_files: &[String], home_dir: &str) {
+pub fn rewire_paths(
+    cwl: &mut CommandLineTool,
+    input_values: &mut Option<HashMap<String, DefaultValue>>,
+    staged_files: &[String],
+    home_dir: &str,
+    working_dir: &Path,
+) {
     //rewire in inputs
     for input in cwl.inputs.iter_mut() {
         if let Some(default) = &mut input.default {
             let mut new_default = default.clone();
             for staged_file in staged_files {
-                new_default = rewire_default_value(new_default, staged_file, home_dir)
+                new_default = rewire_default_value(new_default, staged_file, home_dir, working_dir)
             }
             *default = new_default;
         }
@@ -90,7 +96,7 @@ pub fn rewire_paths(cwl: &mut CommandLineTool, input_values: &mut Option<HashMap
             if let Some(existing_value) = values.get(&input.id) {
                 let mut new_value = existing_value.clone();
                 for staged_file in staged_files {
-                    new_value = rewire_default_value(new_value.clone(), staged_file, home_dir);
+                    new_value = rewire_default_value(new_value.clone(), staged_file, home_dir, working_dir);
                 }
                 values.insert(input.id.clone(), new_value);
             }
@@ -98,11 +104,11 @@ pub fn rewire_paths(cwl: &mut CommandLineTool, input_values: &mut Option<HashMap
     }
 }
 
-fn rewire_default_value(value: DefaultValue, staged_file: &String, home_dir: &str) -> DefaultValue {
+fn rewire_default_value(value: DefaultValue, staged_file: &String, home_dir: &str, working_dir: &Path) -> DefaultValue {
     match value {
         DefaultValue::File(file) => {
             let location = make_relative_to(&file.location, home_dir).trim_start_matches("../");
-            let test = env::current_dir().unwrap().join(location);
+            let test = working_dir.join(location);
             if let Some(diff) = diff_paths(test, staged_file) {
                 if diff.to_str() == Some("") {
                     let new_location = staged_file;
@@ -116,7 +122,7 @@ fn rewire_default_value(value: DefaultValue, staged_file: &String, home_dir: &st
         }
         DefaultValue::Directory(directory) => {
             let location = make_relative_to(&directory.location, home_dir).trim_start_matches("../");
-            let test = env::current_dir().unwrap().join(location);
+            let test = working_dir.join(location);
             if let Some(diff) = diff_paths(test, staged_file) {
                 if diff.to_str() == Some("") {
                     let new_location = staged_file;
@@ -159,6 +165,11 @@ fn set_placeholder_values_requirements(
         if let Requirement::InitialWorkDirRequirement(wd_req) = requirement {
             for listing in wd_req.listing.iter_mut() {
                 listing.entryname = set_placeholder_values_in_string(&listing.entryname, input_values, runtime, inputs);
+                if let Entry::Source(src) = &listing.entry {
+                    if let Some(location) = get_binary_file_reference(src, input_values, inputs) {
+                        listing.entry = Entry::Include(Include { include: location });
+                    }
+                }
                 listing.entry = match &mut listing.entry {
                     Entry::Source(src) => {
                         *src = set_placeholder_values_in_string(src, input_values, runtime, inputs);
@@ -208,6 +219,18 @@ fn set_placeholder_values_in_string(
         .to_string()
 }
 
+/// Listing entries referencing a binary File input stage the file itself, as its contents can not be inlined as text
+fn get_binary_file_reference(text: &str, input_values: Option<&HashMap<String, DefaultValue>>, inputs: &[CommandInputParameter]) -> Option<String> {
+    let id = text.trim().strip_prefix("$(inputs.")?.strip_suffix(')')?;
+    let value = input_values
+        .and_then(|values| values.get(id))
+        .or_else(|| inputs.iter().find(|i| i.id == id).and_then(|i| i.default.as_ref()));
+    match value {
+        Some(DefaultValue::File(file)) if is_binary_file(&file.location) => Some(file.location.clone()),
+        _ => None,
+    }
+}
+
 /// Evaluate inputs and given parameters for given key
 fn get_input_value(
     key: &str,
@@ -1,5 +1,5 @@
+use crate::random::random_string;
 use cwl::clt::Command;
-use rand::{distributions::Alphanumeric, Rng};
 use sha1::{Digest, Sha1};
 use std::{
     cell::RefCell,
@@ -33,13 +33,17 @@ pub fn get_workflows_folder() -> String {
 }
 
 pub fn create_and_write_file<P: AsRef<Path>>(filename: P, contents: &str) -> Result<(), Error> {
-    create_and_write_file_internal(filename, contents, false)
+    create_and_write_file_internal(filename, contents.as_bytes(), false)
 }
 pub fn create_and_write_file_forced<P: AsRef<Path>>(filename: P, contents: &str) -> Result<(), Error> {
+    create_and_write_file_internal(filename, contents.as_bytes(), true)
+}
+/// Writes raw bytes, e.g. binary output of a tool, overwriting existing files
+pub fn create_and_write_bytes_forced<P: AsRef<Path>>(filename: P, contents: &[u8]) -> Result<(), Error> {
     create_and_write_file_internal(filename, contents, true)
 }
 
-fn create_and_write_file_internal<P: AsRef<Path>>(filename: P, contents: &str, overwrite: bool) -> Result<(), Error> {
+fn create_and_write_file_internal<P: AsRef<Path>>(filename: P, contents: &[u8], overwrite: bool) -> Result<(), Error> {
     let path = filename.as_ref();
 
     if let Some(parent) = path.parent() {
@@ -50,7 +54,7 @@ fn create_and_write_file_internal<P: AsRef<Path>>(filename: P, contents: &str, o
     } else {
         fs::File::create_new(filename)
     }?;
-    file.write_all(contents.as_bytes())?;
+    file.write_all(contents)?;
     Ok(())
 }
 
@@ -136,6 +140,19 @@ pub fn get_file_checksum<P: AsRef<Path>>(path: P) -> io::Result<String> {
     Ok(format!("{result:x}"))
 }
 
+/// Sniffs the beginning of a file, files containing null bytes or invalid UTF-8 are considered binary
+pub fn is_binary_file<P: AsRef<Path>>(path: P) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut buffer = Vec::new();
+    if file.take(8192).read_to_end(&mut buffer).is_err() {
+        return false;
+    }
+    //a multibyte character may be cut off at the end of the buffer
+    buffer.contains(&0) || std::str::from_utf8(&buffer).is_err_and(|e| e.error_len().is_some())
+}
+
 pub fn get_shell_command() -> SystemCommand {
     let shell = if cfg!(target_os = "windows") { "cmd" } else { "sh" };
     let param = if cfg!(target_os = "windows") { "/C" } else { "-c" };
@@ -169,8 +186,7 @@ pub fn join_path_string<P: AsRef<Path>>(path: P, location: &str) -> String {
 }
 
 pub fn get_random_filename(prefix: &str, extension: &str) -> String {
-    let rnd: String = rand::thread_rng().sample_iter(&Alphanumeric).take(10).map(char::from).collect();
-    format!("{prefix}_{rnd}.{extension}")
+    format!("{prefix}_{}.{extension}", random_string(10))
 }
 
 pub fn get_first_file_with_prefix<P: AsRef<Path>>(location: P, prefix: &str) -> Option<String> {
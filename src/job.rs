@@ -0,0 +1,606 @@
+use crate::units::{to_curie, Quantity};
+use calamine::{open_workbook_auto, Reader};
+use cwl::{ids::normalize_ids, parse_yaml, version::upgrade_to_v12};
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Kind of value an input expects, optional inputs are marked separately
+#[derive(Debug, Clone, PartialEq)]
+enum InputKind {
+    File,
+    Directory,
+    Scalar,
+    Enum(Vec<String>),
+    Array(Box<InputKind>),
+}
+
+#[derive(Debug, PartialEq)]
+struct InputType {
+    kind: InputKind,
+    optional: bool,
+    name: String,
+}
+
+/// Creates a commented job file skeleton for the inputs of the given workflow or tool. Types, defaults, allowed
+/// values, units and ranges are written as comments. Using `fill_defaults` the defaults of the workflow and, for inputs without default,
+/// of the connected tools are used as values. Paths are written relative to `job_folder`.
+pub fn create_job_template<P: AsRef<Path>>(path: P, fill_defaults: bool, job_folder: &Path) -> Result<String, Box<dyn Error>> {
+    let path = path.as_ref();
+    let document = load_document(path)?;
+    let folder = path.parent().unwrap_or(Path::new("."));
+
+    let mut template = format!("# Inputs of {}\n", path.to_string_lossy());
+    for (id, input) in get_entries(document.get("inputs")) {
+        let input_type = get_type(&input);
+        let default = input
+            .get("default")
+            .map(|default| (default.clone(), folder.to_path_buf()))
+            .or_else(|| get_tool_default(&document, folder, &id));
+
+        template.push('\n');
+        for key in ["label", "doc"] {
+            if let Some(text) = input.get(key).and_then(Value::as_str) {
+                template.push_str(&format!("# {}\n", text.lines().next().unwrap_or_default()));
+            }
+        }
+        let optional = if input_type.optional { " (optional)" } else { "" };
+        template.push_str(&format!("# type: {}{}\n", input_type.name, optional));
+        if let InputKind::Enum(symbols) = &input_type.kind {
+            template.push_str(&format!("# allowed values: {}\n", symbols.join(", ")));
+        }
+        let quantity = Quantity::from_input(&input).or_else(|| find_connected(&document, folder, &id, &|value, _| Quantity::from_input(value)));
+        if let Some(quantity) = quantity {
+            if let Some(unit) = quantity.unit() {
+                //the unit code is only added if the unit is not already shown as code
+                let code = quantity.unit_text.as_ref().and(quantity.unit_code.as_deref()).map(|code| format!(" ({})", to_curie(code)));
+                template.push_str(&format!("# unit: {unit}{}\n", code.unwrap_or_default()));
+            }
+            if let Some(range) = quantity.range() {
+                template.push_str(&format!("# range: {range}\n"));
+            }
+        }
+
+        let value = match default {
+            Some((default, default_folder)) => {
+                let default = relocate(&default, &default_folder, job_folder);
+                template.push_str(&format!("# default: {}\n", to_inline_yaml(&default)));
+                if fill_defaults {
+                    default
+                } else {
+                    create_stub(&input_type.kind)
+                }
+            }
+            None => create_stub(&input_type.kind),
+        };
+        template.push_str(&format_entry(&id, &value)?);
+    }
+    Ok(template)
+}
+
+pub fn load_document(path: &Path) -> Result<Value, Box<dyn Error>> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("❌ Could not read {:?}: {}", path, e))?;
+    let mut document = parse_yaml(&contents).map_err(|e| e.with_path(path))?;
+    upgrade_to_v12(&mut document)?;
+    normalize_ids(&mut document);
+    Ok(document)
+}
+
+/// Entries of a CWL list which can either be written as sequence of mappings with an `id` or as mapping from id to entry
+pub fn get_entries(value: Option<&Value>) -> Vec<(String, Value)> {
+    match value {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| Some((normalize_id(item.get("id")?.as_str()?), item.clone())))
+            .collect(),
+        Some(Value::Mapping(items)) => items.iter().filter_map(|(id, item)| Some((normalize_id(id.as_str()?), item.clone()))).collect(),
+        _ => vec![],
+    }
+}
+
+fn normalize_id(id: &str) -> String {
+    id.trim_start_matches('#').to_string()
+}
+
+/// Finds the default of a step input connected to the given workflow input, nested workflows are searched as well
+fn get_tool_default(document: &Value, folder: &Path, input: &str) -> Option<(Value, PathBuf)> {
+    find_connected(document, folder, input, &|value, folder| value.get("default").map(|default| (default.clone(), folder.to_path_buf())))
+}
+
+/// Searches the step inputs connected to the given workflow input and the inputs of the tools they run, nested workflows
+/// are searched as well. `get` is called with each of them and the folder of their document, its first result is returned.
+pub fn find_connected<T>(document: &Value, folder: &Path, input: &str, get: &dyn Fn(&Value, &Path) -> Option<T>) -> Option<T> {
+    for (_, step) in get_entries(document.get("steps")) {
+        for (step_input, connection) in get_entries(step.get("in")) {
+            let source = connection.get("source").unwrap_or(&connection);
+            let is_connected = match source {
+                Value::String(source) => normalize_id(source) == input,
+                Value::Sequence(sources) => sources.iter().any(|s| s.as_str().is_some_and(|s| normalize_id(s) == input)),
+                _ => false,
+            };
+            if !is_connected {
+                continue;
+            }
+            if let Some(value) = get(&connection, folder) {
+                return Some(value);
+            }
+            let Some(run) = step.get("run").and_then(Value::as_str) else {
+                continue;
+            };
+            let run_path = folder.join(run);
+            let Ok(run_document) = load_document(&run_path) else {
+                continue;
+            };
+            let run_folder = run_path.parent().unwrap_or(folder).to_path_buf();
+            let run_input = get_entries(run_document.get("inputs")).into_iter().find(|(id, _)| *id == step_input);
+            if let Some(value) = run_input.and_then(|(_, i)| get(&i, &run_folder)) {
+                return Some(value);
+            }
+            if let Some(value) = find_connected(&run_document, &run_folder, &step_input, get) {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+/// Type of an input, which is either written as `type` field or as the input itself
+fn get_type(input: &Value) -> InputType {
+    let type_value = if input.is_mapping() { input.get("type").cloned().unwrap_or_default() } else { input.clone() };
+    parse_type(&type_value)
+}
+
+fn parse_type(value: &Value) -> InputType {
+    match value {
+        Value::String(name) => {
+            if let Some(inner) = name.strip_suffix('?') {
+                return InputType {
+                    optional: true,
+                    ..parse_type(&Value::String(inner.to_string()))
+                };
+            }
+            let kind = match name.strip_suffix("[]") {
+                Some(items) => InputKind::Array(Box::new(parse_type(&Value::String(items.to_string())).kind)),
+                None => match name.as_str() {
+                    "File" => InputKind::File,
+                    "Directory" => InputKind::Directory,
+                    _ => InputKind::Scalar,
+                },
+            };
+            InputType {
+                kind,
+                optional: false,
+                name: name.clone(),
+            }
+        }
+        Value::Sequence(types) => {
+            let types = types.iter().filter(|t| t.as_str() != Some("null")).collect::<Vec<_>>();
+            let optional = types.len() < value.as_sequence().map_or(0, Vec::len);
+            if let [single] = types.as_slice() {
+                return InputType {
+                    optional: optional || parse_type(single).optional,
+                    ..parse_type(single)
+                };
+            }
+            InputType {
+                kind: InputKind::Scalar,
+                optional,
+                name: types.iter().map(|t| parse_type(t).name).collect::<Vec<_>>().join(" | "),
+            }
+        }
+        Value::Mapping(map) => match map.get("type").and_then(Value::as_str) {
+            Some("enum") => {
+                let symbols = map
+                    .get("symbols")
+                    .and_then(Value::as_sequence)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|s| s.rsplit(['/', '#']).next().unwrap_or(s).to_string())
+                    .collect();
+                InputType {
+                    kind: InputKind::Enum(symbols),
+                    optional: false,
+                    name: "enum".to_string(),
+                }
+            }
+            Some("array") => {
+                let items = parse_type(map.get("items").unwrap_or(&Value::Null));
+                InputType {
+                    kind: InputKind::Array(Box::new(items.kind)),
+                    optional: false,
+                    name: format!("{}[]", items.name),
+                }
+            }
+            Some(other) => InputType {
+                kind: InputKind::Scalar,
+                optional: false,
+                name: other.to_string(),
+            },
+            None => parse_type(&Value::Null),
+        },
+        _ => InputType {
+            kind: InputKind::Scalar,
+            optional: false,
+            name: "Any".to_string(),
+        },
+    }
+}
+
+/// Placeholder value for an input without default, files and directories get a `class` and an empty `location`
+fn create_stub(kind: &InputKind) -> Value {
+    match kind {
+        InputKind::File | InputKind::Directory => {
+            let class = if *kind == InputKind::File { "File" } else { "Directory" };
+            let mut stub = Mapping::new();
+            stub.insert(Value::from("class"), Value::from(class));
+            stub.insert(Value::from("location"), Value::Null);
+            Value::Mapping(stub)
+        }
+        InputKind::Array(items) => match items.as_ref() {
+            InputKind::File | InputKind::Directory => Value::Sequence(vec![create_stub(items)]),
+            _ => Value::Sequence(vec![]),
+        },
+        InputKind::Scalar | InputKind::Enum(_) => Value::Null,
+    }
+}
+
+/// Locations of files and directories are relative to the document they are defined in and need to be relative to the job file
+fn relocate(value: &Value, from: &Path, to: &Path) -> Value {
+    match value {
+        Value::Mapping(map) => {
+            let is_path_item = matches!(map.get("class").and_then(Value::as_str), Some("File") | Some("Directory"));
+            let mut relocated = Mapping::new();
+            for (key, item) in map {
+                let location = item.as_str().filter(|_| is_path_item && matches!(key.as_str(), Some("location") | Some("path")));
+                let item = match location {
+                    Some(location) if !location.contains("://") && Path::new(location).is_relative() => {
+                        Value::from(relative_path(&from.join(location), to))
+                    }
+                    _ => relocate(item, from, to),
+                };
+                relocated.insert(key.clone(), item);
+            }
+            Value::Mapping(relocated)
+        }
+        Value::Sequence(items) => Value::Sequence(items.iter().map(|i| relocate(i, from, to)).collect()),
+        _ => value.clone(),
+    }
+}
+
+fn relative_path(path: &Path, base: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let base = fs::canonicalize(base).unwrap_or(base.to_path_buf());
+    let relative = pathdiff::diff_paths(&path, &base).unwrap_or(path);
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+fn to_inline_yaml(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn format_entry(id: &str, value: &Value) -> Result<String, Box<dyn Error>> {
+    let mut entry = Mapping::new();
+    entry.insert(Value::from(id), value.clone());
+    Ok(serde_yaml::to_string(&entry)?.replace(": null\n", ":\n"))
+}
+
+/// Header and rows of a table, cells are kept as text
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Removes a column and returns its values
+    pub fn take_column(&mut self, name: &str) -> Option<Vec<String>> {
+        let index = self.columns.iter().position(|column| column == name)?;
+        self.columns.remove(index);
+        Some(
+            self.rows
+                .iter_mut()
+                .map(|row| if index < row.len() { row.remove(index) } else { String::new() })
+                .collect(),
+        )
+    }
+}
+
+/// Reads a CSV file or a sheet of an Excel or OpenDocument spreadsheet, the first sheet is used if none is given.
+/// The first row holds the column names, empty rows are skipped.
+pub fn read_table(path: &Path, sheet: Option<&str>) -> Result<Table, Box<dyn Error>> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    let records: Vec<Vec<String>> = if matches!(extension.as_str(), "xlsx" | "xlsm" | "xls" | "ods") {
+        let mut workbook = open_workbook_auto(path).map_err(|e| format!("❌ Could not open {:?}: {}", path, e))?;
+        let range = match sheet {
+            Some(name) => workbook.worksheet_range(name).map_err(|_| {
+                format!("❌ {:?} does not contain a sheet named {name}, available sheets are: {}", path, workbook.sheet_names().join(", "))
+            })?,
+            None => workbook.worksheet_range_at(0).ok_or(format!("❌ {:?} does not contain any sheets", path))??,
+        };
+        range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+    } else {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| format!("❌ Could not open {:?}: {}", path, e))?;
+        reader
+            .records()
+            .map(|record| record.map(|r| r.iter().map(String::from).collect()))
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut records = records
+        .into_iter()
+        .map(|record| record.iter().map(|cell| cell.trim().to_string()).collect::<Vec<_>>())
+        .filter(|record| record.iter().any(|cell| !cell.is_empty()));
+    let columns = records.next().ok_or(format!("❌ {:?} does not contain any rows", path))?;
+    Ok(Table {
+        columns,
+        rows: records.collect(),
+    })
+}
+
+/// Creates a job for every row of a table. Columns are matched to the inputs of the given workflow or tool by name and
+/// converted to their type: files and directories become `File` and `Directory` objects and items of arrays are separated
+/// by `;`. Without a document numbers and booleans are recognized and existing paths become files or directories.
+/// Paths are read relative to the working directory and written relative to `job_folder`, empty cells are left out.
+pub fn create_jobs_from_table(table: &Table, document: Option<&Value>, job_folder: &Path) -> Result<Vec<Mapping>, Box<dyn Error>> {
+    let types = document.map(|document| {
+        get_entries(document.get("inputs"))
+            .into_iter()
+            .map(|(id, input)| (id, get_type(&input)))
+            .collect::<HashMap<_, _>>()
+    });
+    if let Some(types) = &types {
+        let unknown = table.columns.iter().filter(|c| !c.is_empty() && !types.contains_key(*c)).cloned().collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            let mut inputs = types.keys().cloned().collect::<Vec<_>>();
+            inputs.sort();
+            return Err(format!(
+                "❌ Columns {} do not match any input, available inputs are: {}",
+                unknown.join(", "),
+                inputs.join(", ")
+            )
+            .into());
+        }
+    }
+
+    let jobs = table
+        .rows
+        .iter()
+        .map(|row| {
+            let mut job = Mapping::new();
+            for (column, cell) in table.columns.iter().zip(row).filter(|(column, cell)| !column.is_empty() && !cell.is_empty()) {
+                let value = match types.as_ref().and_then(|types| types.get(column)) {
+                    Some(input_type) => to_typed_value(cell, &input_type.kind, &input_type.name, job_folder),
+                    None => to_untyped_value(cell, job_folder),
+                };
+                job.insert(Value::from(column.as_str()), value);
+            }
+            job
+        })
+        .collect();
+    Ok(jobs)
+}
+
+/// Required inputs of a workflow or tool without default, which are not among the given columns
+pub fn get_missing_inputs(document: &Value, folder: &Path, columns: &[String]) -> Vec<String> {
+    get_entries(document.get("inputs"))
+        .into_iter()
+        .filter(|(id, input)| {
+            !columns.contains(id) && !get_type(input).optional && input.get("default").is_none() && get_tool_default(document, folder, id).is_none()
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Combines jobs into a single job where every input is an array with one item per job, `null` where a job does not
+/// set the input. Workflows can scatter over these inputs using the `dotproduct` method.
+pub fn create_sweep(jobs: &[Mapping]) -> Mapping {
+    let mut keys: Vec<&Value> = vec![];
+    for key in jobs.iter().flat_map(Mapping::keys) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys.into_iter()
+        .map(|key| {
+            let values = jobs.iter().map(|job| job.get(key).cloned().unwrap_or_default()).collect();
+            (key.clone(), Value::Sequence(values))
+        })
+        .collect()
+}
+
+fn to_typed_value(cell: &str, kind: &InputKind, name: &str, job_folder: &Path) -> Value {
+    match kind {
+        InputKind::File => to_path_item("File", cell, job_folder),
+        InputKind::Directory => to_path_item("Directory", cell, job_folder),
+        InputKind::Array(items) => Value::Sequence(
+            cell.split(';')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| to_typed_value(item, items, name.trim_end_matches("[]"), job_folder))
+                .collect(),
+        ),
+        InputKind::Enum(_) => Value::from(cell),
+        InputKind::Scalar if name == "string" => Value::from(cell),
+        InputKind::Scalar => parse_scalar(cell),
+    }
+}
+
+fn to_untyped_value(cell: &str, job_folder: &Path) -> Value {
+    let value = parse_scalar(cell);
+    let path = Path::new(cell);
+    if !value.is_string() {
+        value
+    } else if path.is_file() {
+        to_path_item("File", cell, job_folder)
+    } else if path.is_dir() {
+        to_path_item("Directory", cell, job_folder)
+    } else {
+        value
+    }
+}
+
+/// Numbers and booleans are converted, everything else is kept as text
+fn parse_scalar(cell: &str) -> Value {
+    if let Ok(number) = cell.parse::<i64>() {
+        return Value::from(number);
+    }
+    if let Some(number) = cell.parse::<f64>().ok().filter(|n| n.is_finite()) {
+        return Value::from(number);
+    }
+    match cell.to_lowercase().as_str() {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::from(cell),
+    }
+}
+
+fn to_path_item(class: &str, location: &str, job_folder: &Path) -> Value {
+    let location = if location.contains("://") || Path::new(location).is_absolute() {
+        location.to_string()
+    } else {
+        let cwd = env::current_dir().unwrap_or_default();
+        relative_path(&cwd.join(location), &cwd.join(job_folder))
+    };
+    let mut item = Mapping::new();
+    item.insert(Value::from("class"), Value::from(class));
+    item.insert(Value::from("location"), Value::from(location));
+    Value::Mapping(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_type() {
+        let parse = |yaml: &str| parse_type(&serde_yaml::from_str(yaml).unwrap());
+        assert_eq!(parse("File?").kind, InputKind::File);
+        assert!(parse("File?").optional);
+        assert!(parse("[\"null\", string]").optional);
+        assert_eq!(parse("{type: array, items: File}").kind, InputKind::Array(Box::new(InputKind::File)));
+        assert_eq!(parse("{type: array, items: File}").name, "File[]");
+        assert_eq!(
+            parse("{type: enum, symbols: [\"#mode/fast\", slow]}").kind,
+            InputKind::Enum(vec!["fast".to_string(), "slow".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_create_job_template() {
+        let workflow = Path::new("tests/test_data/hello_world/workflows/main/main.cwl");
+        let job_folder = Path::new("tests/test_data/hello_world");
+        let template = create_job_template(workflow, false, job_folder).unwrap();
+        assert!(template.contains("# type: File\n# default: {\"class\":\"File\",\"location\":\"data/population.csv\"}\npopulation:\n  class: File\n  location:\n"));
+
+        let template = create_job_template(workflow, true, job_folder).unwrap();
+        assert!(template.contains("population:\n  class: File\n  location: data/population.csv\n"));
+        assert!(template.contains("speakers:\n  class: File\n  location: data/speakers_revised.csv\n"));
+    }
+
+    #[test]
+    fn test_create_job_template_enum() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tool.cwl");
+        fs::write(
+            &path,
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\noutputs: []\ninputs:\n  mode:\n    type: {type: enum, symbols: [fast, slow]}\n    default: fast\n  reads: File[]\n  threads: int?\n",
+        )
+        .unwrap();
+        let template = create_job_template(&path, false, dir.path()).unwrap();
+        assert!(template.contains("# type: enum\n# allowed values: fast, slow\n# default: \"fast\"\nmode:\n"));
+        assert!(template.contains("# type: File[]\nreads:\n- class: File\n  location:\n"));
+        assert!(template.contains("# type: int (optional)\nthreads:\n"));
+        assert!(create_job_template(&path, true, dir.path()).unwrap().contains("mode: fast\n"));
+
+        fs::write(
+            &path,
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\noutputs: []\ninputs:\n  temperature:\n    type: float\n    s:unitCode: http://purl.obolibrary.org/obo/UO_0000027\n    s:unitText: degree Celsius\n    s:minValue: -10\n    s:maxValue: 40\n",
+        )
+        .unwrap();
+        let template = create_job_template(&path, false, dir.path()).unwrap();
+        assert!(template.contains("# type: float\n# unit: degree Celsius (UO:0000027)\n# range: -10 to 40\ntemperature:\n"));
+    }
+
+    #[test]
+    fn test_read_table() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("runs.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        workbook.add_worksheet().set_name("notes").unwrap();
+        let sheet = workbook.add_worksheet().set_name("runs").unwrap();
+        sheet.write_row(0, 0, ["name", "threads", "mode"]).unwrap();
+        sheet.write_string(1, 0, "first").unwrap();
+        sheet.write_number(1, 1, 4).unwrap();
+        sheet.write_string(1, 2, "fast").unwrap();
+        sheet.write_string(3, 0, "second").unwrap();
+        workbook.save(&path).unwrap();
+
+        let mut table = read_table(&path, Some("runs")).unwrap();
+        assert_eq!(table.columns, vec!["name", "threads", "mode"]);
+        assert_eq!(table.rows, vec![vec!["first", "4", "fast"], vec!["second", "", ""]]);
+        assert_eq!(table.take_column("name").unwrap(), vec!["first", "second"]);
+        assert_eq!(table.columns, vec!["threads", "mode"]);
+        assert!(read_table(&path, Some("missing")).unwrap_err().to_string().contains("notes, runs"));
+
+        let path = dir.path().join("runs.csv");
+        fs::write(&path, "threads,mode\n\n 2 ,slow\n").unwrap();
+        assert_eq!(read_table(&path, None).unwrap().rows, vec![vec!["2", "slow"]]);
+    }
+
+    #[test]
+    fn test_create_jobs_from_table() {
+        let dir = tempdir().unwrap();
+        let document: Value = serde_yaml::from_str(
+            "inputs:\n  reads: File[]\n  label: string\n  threads: int?\n  ratio: float\n  mode: {type: enum, symbols: [fast, slow]}\n",
+        )
+        .unwrap();
+        let table = Table {
+            columns: vec!["reads".into(), "label".into(), "threads".into(), "ratio".into(), "mode".into()],
+            rows: vec![
+                vec!["a.fq; b.fq".into(), "007".into(), "4".into(), "0.5".into(), "fast".into()],
+                vec!["c.fq".into(), "x".into(), "".into(), "1".into(), "slow".into()],
+            ],
+        };
+        let jobs = create_jobs_from_table(&table, Some(&document), dir.path()).unwrap();
+        let expected: Mapping = serde_yaml::from_str(&format!(
+            "reads:\n- {{class: File, location: {0}/a.fq}}\n- {{class: File, location: {0}/b.fq}}\nlabel: '007'\nthreads: 4\nratio: 0.5\nmode: fast\n",
+            relative_path(&env::current_dir().unwrap(), dir.path())
+        ))
+        .unwrap();
+        assert_eq!(jobs[0], expected);
+        assert!(jobs[1].get("threads").is_none());
+
+        let sweep = create_sweep(&jobs);
+        assert_eq!(sweep.get("threads").unwrap(), &serde_yaml::from_str::<Value>("[4, null]").unwrap());
+        assert_eq!(sweep.get("label").unwrap(), &serde_yaml::from_str::<Value>("['007', x]").unwrap());
+
+        //untyped values are guessed
+        let jobs = create_jobs_from_table(&table, None, dir.path()).unwrap();
+        assert_eq!(jobs[0].get("label").unwrap(), &Value::from(7));
+        assert_eq!(jobs[0].get("reads").unwrap(), &Value::from("a.fq; b.fq"));
+
+        let table = Table {
+            columns: vec!["reads".into(), "unknown".into()],
+            rows: vec![],
+        };
+        assert!(create_jobs_from_table(&table, Some(&document), dir.path())
+            .unwrap_err()
+            .to_string()
+            .contains("Columns unknown do not match any input"));
+        assert_eq!(
+            get_missing_inputs(&document, dir.path(), &["reads".to_string()]),
+            vec!["label", "ratio", "mode"]
+        );
+    }
+}
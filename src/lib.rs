@@ -1,12 +1,30 @@
 pub mod cli;
 pub mod commands;
+pub mod bundle;
+pub mod container;
 pub mod cwl;
+pub mod datasets;
+pub mod diff;
+pub mod environments;
 pub mod error;
 pub mod execution;
 pub mod io;
+pub mod job;
 pub mod log;
+pub mod notebook;
+pub mod ontology;
+pub mod package;
 pub mod parser;
+pub mod random;
+pub mod report;
 pub mod repo;
+pub mod sbom;
+pub mod secrets;
+pub mod tui;
+pub mod units;
+pub mod visualize;
+pub mod wes;
+pub mod wizard;
 
 use ::log::info;
 use std::{num::NonZero, process::Command, thread};
@@ -1,8 +1,36 @@
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
 use colored::Colorize;
 use log::{Level, Log};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub static LOGGER: Logger = Logger;
 
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+/// Format messages are printed in
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum LogFormat {
+    /// Human readable messages
+    #[default]
+    Text,
+    /// One JSON object per line, e.g. for ingestion into log management systems
+    Json,
+}
+
+pub fn set_log_format(format: LogFormat) {
+    JSON_FORMAT.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn log_format() -> LogFormat {
+    if JSON_FORMAT.load(Ordering::Relaxed) {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    }
+}
+
 pub struct Logger;
 
 impl Log for Logger {
@@ -11,7 +39,17 @@ impl Log for Logger {
     }
 
     fn log(&self, record: &log::Record) {
-        eprintln!("{}{}", format_level(&record.level()), record.args());
+        match log_format() {
+            LogFormat::Text => eprintln!("{}{}", format_level(&record.level()), record.args()),
+            LogFormat::Json => eprintln!(
+                "{}",
+                json!({
+                    "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                    "level": record.level().as_str().to_lowercase(),
+                    "message": record.args().to_string(),
+                })
+            ),
+        }
     }
 
     fn flush(&self) {}
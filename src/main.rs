@@ -1,12 +1,14 @@
 use clap::{CommandFactory, Parser};
+use cwl::error::CWLError;
 use log::{error, LevelFilter};
 use s4n::{
     cli::{generate_completions, Cli, Commands},
     commands::{
-        annotate::handle_annotation_command, execute::handle_execute_commands, init::handle_init_command, sync::handle_sync, tool::{create_tool, handle_tool_commands}, workflow::handle_workflow_commands
+        annotate::handle_annotation_command, bundle::{handle_export_commands, handle_import_commands}, data::handle_data_commands, execute::handle_execute_commands, fmt::format_files, init::handle_init_command, inputs::handle_inputs_commands, package::handle_package_commands, report::handle_report_commands, sbom::create_sbom, sync::handle_sync, tool::{create_tool, handle_tool_commands}, validate::validate, workflow::handle_workflow_commands
     },
     error::{CommandError, ExitCode},
     log::LOGGER,
+    tui::run_tui,
 };
 use std::{error::Error, process::exit};
 
@@ -17,6 +19,8 @@ fn main() {
         error!("{e}");
         if let Some(cmd_err) = e.downcast_ref::<CommandError>() {
             exit(cmd_err.exit_code());
+        } else if let Some(cwl_err) = e.downcast_ref::<CWLError>() {
+            exit(cwl_err.exit_code());
         } else {
             exit(1);
         }
@@ -32,8 +36,18 @@ fn run() -> Result<(), Box<dyn Error>> {
         Commands::Tool { command } => handle_tool_commands(command),
         Commands::Run(args) => create_tool(args),
         Commands::Workflow { command } => handle_workflow_commands(command),
-        Commands::Annotate { command, tool_name } => handle_annotation_command(command, tool_name),
+        Commands::Annotate { command, tool_name, offline } => handle_annotation_command(command, tool_name, *offline),
+        Commands::Data { command } => handle_data_commands(command),
+        Commands::Inputs { command } => handle_inputs_commands(command),
         Commands::Execute { command } => handle_execute_commands(command),
+        Commands::Package { command } => handle_package_commands(command),
+        Commands::Export { command } => handle_export_commands(command),
+        Commands::Import { command } => handle_import_commands(command),
+        Commands::Report { command } => handle_report_commands(command),
+        Commands::Sbom(args) => create_sbom(args),
+        Commands::Validate(args) => validate(args),
+        Commands::Fmt(args) => format_files(args),
+        Commands::Tui => run_tui(),
         Commands::Sync => handle_sync(),
         Commands::Completions { shell } => generate_completions(*shell, &mut Cli::command()),
     }
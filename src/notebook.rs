@@ -0,0 +1,277 @@
+use crate::{
+    container::{get_python_imports, ScriptDependencies, ScriptLanguage},
+    io::get_filename_without_extension,
+};
+use cwl::{
+    clt::{Argument, Command, CommandLineTool},
+    inputs::{CommandInputParameter, CommandLineBinding},
+    requirements::{InitialWorkDirRequirement, Requirement},
+    types::{CWLType, DefaultValue, Directory, File},
+};
+use log::warn;
+use serde_json::Value;
+use std::{error::Error, fs, path::Path};
+
+/// Tag papermill uses to find the cell defining the parameters of a notebook
+const PARAMETERS_TAG: &str = "parameters";
+
+/// Packages needed to execute notebooks in addition to the ones imported by the notebook
+const PAPERMILL_PACKAGES: [&str; 2] = ["papermill", "ipykernel"];
+
+/// A variable assigned in the parameters cell of a notebook, e.g. `threshold = 0.5  # minimal score`
+#[derive(Debug, PartialEq)]
+pub struct NotebookParameter {
+    pub name: String,
+    pub value: String,
+    pub doc: Option<String>,
+}
+
+/// The code cells of a Jupyter notebook together with their tags
+#[derive(Debug)]
+pub struct Notebook {
+    pub path: String,
+    cells: Vec<(Vec<String>, String)>,
+}
+
+impl Notebook {
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("❌ Could not read notebook {path}: {e}"))?;
+        let notebook: Value = serde_json::from_str(&contents).map_err(|e| format!("❌ {path} is not a valid Jupyter notebook: {e}"))?;
+        Ok(Self::from_json(path, &notebook))
+    }
+
+    fn from_json(path: &str, notebook: &Value) -> Self {
+        let cells = notebook["cells"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|cell| cell["cell_type"] == "code")
+            .map(|cell| {
+                let tags = cell["metadata"]["tags"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|tag| tag.as_str().map(str::to_string))
+                    .collect();
+                //sources are stored either as a single string or as list of lines
+                let source = match &cell["source"] {
+                    Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect::<String>(),
+                    source => source.as_str().unwrap_or_default().to_string(),
+                };
+                (tags, source)
+            })
+            .collect();
+        Notebook {
+            path: path.to_string(),
+            cells,
+        }
+    }
+
+    /// Parameters defined in the cell tagged `parameters`, papermill injects new values for them after this cell
+    pub fn get_parameters(&self) -> Vec<NotebookParameter> {
+        let Some((_, source)) = self.cells.iter().find(|(tags, _)| tags.iter().any(|t| t == PARAMETERS_TAG)) else {
+            return vec![];
+        };
+        source.lines().filter_map(parse_parameter).collect()
+    }
+
+    /// Packages imported by the code cells and those needed to execute the notebook
+    pub fn get_dependencies(&self) -> ScriptDependencies {
+        let code = self.cells.iter().map(|(_, source)| source.as_str()).collect::<Vec<_>>().join("\n");
+        let mut packages = get_python_imports(&code);
+        packages.extend(PAPERMILL_PACKAGES.iter().map(|p| p.to_string()));
+        ScriptDependencies {
+            language: ScriptLanguage::Python,
+            packages,
+        }
+    }
+
+    /// Name of the executed copy of the notebook papermill writes
+    pub fn get_executed_name(&self) -> String {
+        let name = get_filename_without_extension(&self.path).unwrap_or_else(|| "notebook".to_string());
+        format!("{name}_executed.ipynb")
+    }
+
+    /// Creates a CommandLineTool running the notebook with papermill. Every parameter becomes an input, the values
+    /// are passed to papermill as YAML using `-y`.
+    pub fn to_tool(&self) -> CommandLineTool {
+        let base_command = Command::Multiple(vec!["papermill".to_string(), self.path.clone(), self.get_executed_name()]);
+        let mut inputs = vec![];
+        let mut values = vec![];
+        for parameter in self.get_parameters() {
+            let Some(input) = create_parameter_input(&parameter) else {
+                warn!(
+                    "Parameter {} = {} can not be expressed as CWL input, the value of the notebook is used",
+                    parameter.name, parameter.value
+                );
+                continue;
+            };
+            let value = match input.type_ {
+                CWLType::File | CWLType::Directory => format!("\"$(inputs.{}.path)\"", input.id),
+                CWLType::String => format!("\"$(inputs.{})\"", input.id),
+                _ => format!("$(inputs.{})", input.id),
+            };
+            values.push(format!("\"{}\": {value}", parameter.name));
+            inputs.push(input);
+        }
+
+        let arguments = (!values.is_empty()).then(|| {
+            vec![Argument::Binding(CommandLineBinding {
+                prefix: Some("-y".to_string()),
+                value_from: Some(format!("{{{}}}", values.join(", "))),
+                ..Default::default()
+            })]
+        });
+        CommandLineTool::default()
+            .with_base_command(base_command)
+            .with_inputs(inputs)
+            .with_arguments(arguments)
+            .with_requirements(vec![Requirement::InitialWorkDirRequirement(InitialWorkDirRequirement::from_file(
+                &self.path,
+            ))])
+    }
+}
+
+/// Parses an assignment like `name = value`, `name: int = value` or `name = value  # doc`
+fn parse_parameter(line: &str) -> Option<NotebookParameter> {
+    let (assignment, comment) = split_comment(line);
+    let (target, value) = assignment.split_once('=')?;
+    let name = target.split(':').next()?.trim();
+    let value = value.trim();
+    if name.is_empty() || value.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') || name.starts_with(|c: char| c.is_numeric()) {
+        return None;
+    }
+    Some(NotebookParameter {
+        name: name.to_string(),
+        value: value.to_string(),
+        doc: comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+    })
+}
+
+/// Splits a line of Python code at the first `#` which is not part of a string
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') => return (&line[..i], Some(&line[i + 1..])),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// Creates an input from a parameter whose value is a Python literal, other expressions are not supported
+fn create_parameter_input(parameter: &NotebookParameter) -> Option<CommandInputParameter> {
+    let value = parameter.value.as_str();
+    let (cwl_type, default) = match value {
+        "True" | "False" => (CWLType::Boolean, DefaultValue::Any(serde_yaml::Value::Bool(value == "True"))),
+        _ if value.len() >= 2 && (value.starts_with('"') && value.ends_with('"') || value.starts_with('\'') && value.ends_with('\'')) => {
+            let text = &value[1..value.len() - 1];
+            //existing paths are staged as File or Directory
+            if Path::new(text).is_file() {
+                (CWLType::File, DefaultValue::File(File::from_location(&text.to_string())))
+            } else if Path::new(text).is_dir() {
+                (CWLType::Directory, DefaultValue::Directory(Directory::from_location(&text.to_string())))
+            } else {
+                (CWLType::String, DefaultValue::Any(serde_yaml::Value::String(text.to_string())))
+            }
+        }
+        _ => match serde_yaml::from_str::<serde_yaml::Value>(value).ok()? {
+            serde_yaml::Value::Number(number) if number.is_f64() => (CWLType::Float, DefaultValue::Any(serde_yaml::Value::Number(number))),
+            serde_yaml::Value::Number(number) => (CWLType::Int, DefaultValue::Any(serde_yaml::Value::Number(number))),
+            _ => return None,
+        },
+    };
+    let mut input = CommandInputParameter::default()
+        .with_id(&parameter.name)
+        .with_type(cwl_type)
+        .with_default_value(default);
+    if let Some(doc) = &parameter.doc {
+        input = input.with_doc(doc);
+    }
+    Some(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notebook() -> Notebook {
+        Notebook::from_json(
+            "analysis.ipynb",
+            &json!({
+                "cells": [
+                    { "cell_type": "markdown", "metadata": {}, "source": ["# Analysis"] },
+                    {
+                        "cell_type": "code",
+                        "metadata": { "tags": ["parameters"] },
+                        "source": [
+                            "threshold = 0.5  # minimal score\n",
+                            "iterations: int = 10\n",
+                            "title = \"Results #1\"\n",
+                            "verbose = False\n",
+                            "columns = ['a', 'b']\n"
+                        ]
+                    },
+                    { "cell_type": "code", "metadata": {}, "source": "import pandas as pd\nimport os\n" }
+                ]
+            }),
+        )
+    }
+
+    #[test]
+    fn test_get_parameters() {
+        let parameters = notebook().get_parameters();
+        assert_eq!(parameters.len(), 5);
+        assert_eq!(
+            parameters[0],
+            NotebookParameter {
+                name: "threshold".to_string(),
+                value: "0.5".to_string(),
+                doc: Some("minimal score".to_string())
+            }
+        );
+        assert_eq!(parameters[1].name, "iterations");
+        assert_eq!(parameters[2].value, "\"Results #1\"");
+        assert_eq!(parameters[2].doc, None);
+    }
+
+    #[test]
+    fn test_notebook_to_tool() {
+        let notebook = notebook();
+        let tool = notebook.to_tool();
+        assert_eq!(
+            tool.base_command,
+            Command::Multiple(vec![
+                "papermill".to_string(),
+                "analysis.ipynb".to_string(),
+                "analysis_executed.ipynb".to_string()
+            ])
+        );
+        let types = tool.inputs.iter().map(|i| (i.id.as_str(), i.type_.clone())).collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                ("threshold", CWLType::Float),
+                ("iterations", CWLType::Int),
+                ("title", CWLType::String),
+                ("verbose", CWLType::Boolean)
+            ]
+        );
+        let Some(Argument::Binding(binding)) = tool.arguments.as_ref().and_then(|a| a.first()) else {
+            panic!("papermill parameters missing")
+        };
+        assert_eq!(
+            binding.value_from.as_deref(),
+            Some(
+                r#"{"threshold": $(inputs.threshold), "iterations": $(inputs.iterations), "title": "$(inputs.title)", "verbose": $(inputs.verbose)}"#
+            )
+        );
+
+        let dependencies = notebook.get_dependencies();
+        assert_eq!(dependencies.packages.iter().collect::<Vec<_>>(), vec!["ipykernel", "pandas", "papermill"]);
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap, env, error::Error, fs, future::Future, path::PathBuf};
+
+/// Location of the term cache relative to the user's cache directory
+pub const ONTOLOGY_CACHE: &str = "s4n/ontology.db";
+/// Search results older than this are fetched again if the terminology service is reachable
+pub const CACHE_TTL_DAYS: i64 = 30;
+
+/// A term found by the terminology service: label, ontology and IRI
+pub type Term = (String, String, String);
+
+thread_local!(static OFFLINE: RefCell<bool> = const { RefCell::new(false) });
+
+/// Sets whether terms are only taken from the cache, without querying the terminology service
+pub fn set_offline(value: bool) {
+    OFFLINE.with(|offline| *offline.borrow_mut() = value);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.with(|offline| *offline.borrow())
+}
+
+/// Cached search results of the terminology service keyed by the lowercase search term
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct OntologyCache {
+    pub searches: BTreeMap<String, CachedSearch>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CachedSearch {
+    pub fetched: String,
+    pub terms: Vec<Term>,
+}
+
+impl CachedSearch {
+    pub fn is_expired(&self) -> bool {
+        DateTime::parse_from_rfc3339(&self.fetched).map_or(true, |fetched| Utc::now() - fetched.to_utc() > Duration::days(CACHE_TTL_DAYS))
+    }
+}
+
+impl OntologyCache {
+    /// Loads the cache of the current user, a missing or unreadable cache is treated as empty
+    pub fn load() -> Self {
+        let Some(path) = get_cache_path() else {
+            return Self::default();
+        };
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = get_cache_path().ok_or("❌ Could not determine cache directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, search_term: &str) -> Option<&CachedSearch> {
+        self.searches.get(&search_term.to_lowercase())
+    }
+
+    pub fn insert(&mut self, search_term: &str, terms: Vec<Term>) {
+        let search = CachedSearch {
+            fetched: Utc::now().to_rfc3339(),
+            terms,
+        };
+        self.searches.insert(search_term.to_lowercase(), search);
+    }
+}
+
+/// Searches terms using the cache if possible. In offline mode only cached results are used, otherwise expired or
+/// missing results are fetched and stored. If fetching fails expired results are used as fallback.
+pub async fn search_terms<F, Fut>(search_term: &str, fetch: F) -> Result<Vec<Term>, Box<dyn Error>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<Term>, Box<dyn Error>>>,
+{
+    let mut cache = OntologyCache::load();
+    let cached = cache.get(search_term).cloned();
+    if is_offline() {
+        if cached.is_none() {
+            warn!("No cached terms found for '{}', annotating it without ontology", search_term);
+        }
+        return Ok(cached.map(|c| c.terms).unwrap_or_default());
+    }
+    if let Some(cached) = cached.as_ref().filter(|c| !c.is_expired()) {
+        return Ok(cached.terms.clone());
+    }
+
+    match fetch().await {
+        Ok(terms) => {
+            cache.insert(search_term, terms.clone());
+            if let Err(e) = cache.save() {
+                warn!("Could not update ontology cache: {}", e);
+            }
+            Ok(terms)
+        }
+        Err(e) => match cached {
+            Some(cached) => {
+                warn!("Could not reach terminology service ({}), using cached terms for '{}'", e, search_term);
+                Ok(cached.terms)
+            }
+            None => Err(format!("❌ Could not reach terminology service: {e}, use --offline to annotate without ontology").into()),
+        },
+    }
+}
+
+/// The cache is stored in `$XDG_CACHE_HOME`, `~/.cache` or `%LOCALAPPDATA%` on Windows
+pub fn get_cache_path() -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(cache_dir.join(ONTOLOGY_CACHE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    fn term(label: &str) -> Term {
+        (label.to_string(), "ncit".to_string(), format!("http://purl.obolibrary.org/obo/{label}"))
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_terms_cache() {
+        let dir = tempdir().unwrap();
+        let previous = env::var_os("XDG_CACHE_HOME");
+        env::set_var("XDG_CACHE_HOME", dir.path());
+
+        let terms = search_terms("Formal Search", || async { Ok(vec![term("C170397")]) }).await.unwrap();
+        assert_eq!(terms, vec![term("C170397")]);
+        assert!(dir.path().join(ONTOLOGY_CACHE).exists());
+
+        //cached results are used without fetching
+        let terms = search_terms("formal search", || async { Err("unreachable".into()) }).await.unwrap();
+        assert_eq!(terms, vec![term("C170397")]);
+
+        set_offline(true);
+        assert_eq!(search_terms("Formal Search", || async { Ok(vec![]) }).await.unwrap(), vec![term("C170397")]);
+        assert!(search_terms("Other", || async { Ok(vec![term("C1")]) }).await.unwrap().is_empty());
+        set_offline(false);
+
+        assert!(search_terms("Other", || async { Err("unreachable".into()) }).await.is_err());
+
+        match previous {
+            Some(previous) => env::set_var("XDG_CACHE_HOME", previous),
+            None => env::remove_var("XDG_CACHE_HOME"),
+        }
+    }
+
+    #[test]
+    fn test_cached_search_expired() {
+        let mut search = CachedSearch {
+            fetched: Utc::now().to_rfc3339(),
+            terms: vec![],
+        };
+        assert!(!search.is_expired());
+        search.fetched = (Utc::now() - Duration::days(CACHE_TTL_DAYS + 1)).to_rfc3339();
+        assert!(search.is_expired());
+        search.fetched = "invalid".to_string();
+        assert!(search.is_expired());
+    }
+}
@@ -0,0 +1,531 @@
+use crate::{
+    commands::fmt::find_cwl_files,
+    datasets::{load_manifest, Dataset},
+    io::get_workflows_folder,
+    job::{get_entries, load_document},
+    secrets::SecretScanner,
+    units::get_input_quantities,
+};
+use chrono::Utc;
+use cwl::{load_tool, load_workflow};
+use git2::{ObjectType, Repository};
+use log::warn;
+use reqwest::{
+    multipart::{Form, Part},
+    Client,
+};
+use serde_json::{json, Value};
+use serde_yaml::Value as YamlValue;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tokio::runtime::Builder;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+/// Name of the metadata file of an RO-Crate
+pub const RO_CRATE_METADATA: &str = "ro-crate-metadata.json";
+
+/// Environment variable holding the API token used to upload to WorkflowHub
+pub const WORKFLOWHUB_TOKEN_VAR: &str = "WORKFLOWHUB_API_TOKEN";
+
+/// Environment variable holding the access token used to create Zenodo depositions
+pub const ZENODO_TOKEN_VAR: &str = "ZENODO_ACCESS_TOKEN";
+
+/// Files of the project root which are packed alongside the workflows if they exist
+const PROJECT_FILES: [&str; 4] = ["README.md", "LICENSE", "LICENSE.md", "CITATION.cff"];
+
+/// Release of a project as Workflow RO-Crate
+///
+/// Reference: [Workflow RO-Crate](https://w3id.org/workflowhub/workflow-ro-crate/1.0)
+#[derive(Debug)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// Workflow the crate is about, relative to the project root
+    pub main_workflow: PathBuf,
+    /// All packed files relative to the project root
+    pub files: Vec<PathBuf>,
+    /// Metadata of the main workflow
+    pub annotations: Annotations,
+}
+
+/// Schema.org annotations and documentation of a workflow as added by `s4n annotate`
+#[derive(Debug, Default, PartialEq)]
+pub struct Annotations {
+    pub label: Option<String>,
+    pub doc: Option<String>,
+    pub license: Option<String>,
+    pub authors: Vec<Author>,
+}
+
+/// Person annotated as `s:author`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Author {
+    pub name: String,
+    /// Identifier of the person, usually an ORCID
+    pub identifier: Option<String>,
+}
+
+impl Annotations {
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let yaml: YamlValue = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+        let get = |value: &YamlValue, key: &str| value.get(key).and_then(YamlValue::as_str).map(str::to_string);
+        let authors = yaml
+            .get("s:author")
+            .and_then(YamlValue::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(|person| {
+                Some(Author {
+                    name: get(person, "s:name")?,
+                    identifier: get(person, "s:identifier"),
+                })
+            })
+            .collect();
+        Ok(Annotations {
+            label: get(&yaml, "label"),
+            doc: get(&yaml, "doc"),
+            license: get(&yaml, "s:license"),
+            authors,
+        })
+    }
+}
+
+impl Package {
+    /// Collects the workflows folder and common project files like the README. Without a given workflow, the project
+    /// needs to contain exactly one workflow to be used as main workflow.
+    pub fn from_project(project_dir: &Path, name: &str, version: &str, workflow: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let workflows_dir = project_dir.join(get_workflows_folder());
+        let workflows = find_cwl_files(&workflows_dir)
+            .into_iter()
+            .filter(|path| load_workflow(path).is_ok())
+            .collect::<Vec<_>>();
+        let main_workflow = match workflow {
+            Some(workflow) => {
+                let workflow = workflow.trim_end_matches(".cwl");
+                workflows
+                    .iter()
+                    .find(|path| path.file_stem().is_some_and(|stem| stem == workflow))
+                    .ok_or(format!("❌ Workflow {workflow} does not exist"))?
+            }
+            None => match workflows.as_slice() {
+                [workflow] => workflow,
+                [] => return Err("❌ Project does not contain a workflow to publish".into()),
+                _ => return Err("❌ Project contains multiple workflows, please select one using --workflow".into()),
+            },
+        };
+
+        let mut files = WalkDir::new(&workflows_dir)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .chain(PROJECT_FILES.iter().map(|f| project_dir.join(f)).filter(|p| p.is_file()))
+            .map(|path| path.strip_prefix(project_dir).map(Path::to_path_buf))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.sort();
+
+        Ok(Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            main_workflow: main_workflow.strip_prefix(project_dir)?.to_path_buf(),
+            files,
+            annotations: Annotations::from_file(main_workflow)?,
+        })
+    }
+
+    /// Creates the RO-Crate metadata describing the packed files, CWL workflows and tools are typed as
+    /// `ComputationalWorkflow` and `SoftwareSourceCode`
+    pub fn get_metadata(&self, project_dir: &Path) -> Value {
+        let mut graph = vec![
+            json!({
+                "@id": RO_CRATE_METADATA,
+                "@type": "CreativeWork",
+                "conformsTo": [
+                    { "@id": "https://w3id.org/ro/crate/1.1" },
+                    { "@id": "https://w3id.org/workflowhub/workflow-ro-crate/1.0" }
+                ],
+                "about": { "@id": "./" }
+            }),
+            json!({
+                "@id": "./",
+                "@type": "Dataset",
+                "name": self.name,
+                "version": self.version,
+                "datePublished": Utc::now().to_rfc3339(),
+                "mainEntity": { "@id": to_id(&self.main_workflow) },
+                "hasPart": self.files.iter().map(|f| json!({ "@id": to_id(f) })).collect::<Vec<_>>()
+            }),
+            json!({
+                "@id": "#cwl",
+                "@type": "ComputerLanguage",
+                "name": "Common Workflow Language",
+                "alternateName": "CWL",
+                "identifier": { "@id": "https://w3id.org/cwl/v1.2/" },
+                "url": { "@id": "https://www.commonwl.org/" }
+            }),
+        ];
+
+        let root = &mut graph[1];
+        if let Some(license) = &self.annotations.license {
+            root["license"] = json!(license);
+        }
+        if let Some(doc) = &self.annotations.doc {
+            root["description"] = json!(doc);
+        }
+        if !self.annotations.authors.is_empty() {
+            root["author"] = json!(self.annotations.authors.iter().map(|a| json!({ "@id": a.get_id() })).collect::<Vec<_>>());
+        }
+        for author in &self.annotations.authors {
+            graph.push(json!({ "@id": author.get_id(), "@type": "Person", "name": author.name }));
+        }
+
+        for file in &self.files {
+            let path = project_dir.join(file);
+            let mut entity = json!({ "@id": to_id(file), "@type": "File", "name": file.file_name().unwrap_or_default().to_string_lossy() });
+            if let Ok(workflow) = load_workflow(&path) {
+                entity["@type"] = json!(["File", "SoftwareSourceCode", "ComputationalWorkflow"]);
+                entity["programmingLanguage"] = json!({ "@id": "#cwl" });
+                if let Some(label) = workflow.label {
+                    entity["name"] = json!(label);
+                }
+                if let Some(doc) = workflow.doc {
+                    entity["description"] = json!(doc);
+                }
+                if *file == self.main_workflow {
+                    let parameters = get_formal_parameters(&path, &to_id(file));
+                    entity["input"] = json!(parameters.iter().map(|p| json!({ "@id": p["@id"] })).collect::<Vec<_>>());
+                    graph.extend(parameters);
+                }
+            } else if load_tool(&path).is_ok() {
+                entity["@type"] = json!(["File", "SoftwareSourceCode"]);
+                entity["programmingLanguage"] = json!({ "@id": "#cwl" });
+            }
+            graph.push(entity);
+        }
+
+        //datasets registered using `s4n data add` are described, so that the data the workflows were run on can be cited
+        match load_manifest(project_dir) {
+            Ok(datasets) => {
+                for (name, dataset) in &datasets {
+                    add_dataset_entity(&mut graph, project_dir, name, dataset);
+                }
+            }
+            Err(e) => warn!("Datasets are not described in the crate: {}", e),
+        }
+
+        json!({
+            "@context": "https://w3id.org/ro/crate/1.1/context",
+            "@graph": graph
+        })
+    }
+
+    /// Writes all files and the RO-Crate metadata into a zip archive. Secrets found by the given scanner are replaced in
+    /// the archived files, the project's files are left untouched.
+    pub fn write_crate(&self, project_dir: &Path, archive: &Path, redact: Option<&SecretScanner>) -> Result<(), Box<dyn Error>> {
+        let mut zip = ZipWriter::new(File::create(archive)?);
+        let options = SimpleFileOptions::default();
+        zip.start_file(RO_CRATE_METADATA, options)?;
+        zip.write_all(serde_json::to_string_pretty(&self.get_metadata(project_dir))?.as_bytes())?;
+        for file in &self.files {
+            let path = project_dir.join(file);
+            let redacted = redact.map(|scanner| scanner.redact_file(&path)).transpose()?.flatten();
+            zip.start_file(to_id(file), options)?;
+            match redacted {
+                Some(contents) => zip.write_all(contents.as_bytes())?,
+                None => zip.write_all(&fs::read(path)?)?,
+            }
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Describes the inputs of a workflow as `FormalParameter`, including their units and allowed ranges
+fn get_formal_parameters(path: &Path, workflow_id: &str) -> Vec<Value> {
+    let Ok(document) = load_document(path) else {
+        return vec![];
+    };
+    let folder = path.parent().unwrap_or(Path::new("."));
+    let quantities = get_input_quantities(&document, folder).into_iter().collect::<HashMap<_, _>>();
+    get_entries(document.get("inputs"))
+        .into_iter()
+        .map(|(id, input)| {
+            let mut parameter = json!({
+                "@id": format!("#{workflow_id}/{id}"),
+                "@type": "FormalParameter",
+                "conformsTo": { "@id": "https://bioschemas.org/profiles/FormalParameter/1.0-RELEASE" },
+                "name": id
+            });
+            if let Some(doc) = input.get("doc").and_then(YamlValue::as_str) {
+                parameter["description"] = json!(doc);
+            }
+            if let Some(quantity) = quantities.get(&id) {
+                let fields = [
+                    ("unitCode", quantity.unit_code.as_ref().map(|code| json!({ "@id": code }))),
+                    ("unitText", quantity.unit().map(|unit| json!(unit))),
+                    ("minValue", quantity.minimum.map(|min| json!(min))),
+                    ("maxValue", quantity.maximum.map(|max| json!(max))),
+                ];
+                for (key, value) in fields {
+                    if let Some(value) = value {
+                        parameter[key] = value;
+                    }
+                }
+            }
+            parameter
+        })
+        .collect()
+}
+
+/// Creates an annotated tag pointing to HEAD, fails if the tag already exists
+pub fn tag_release(repo: &Repository, tag: &str, message: &str) -> Result<(), Box<dyn Error>> {
+    if repo.refname_to_id(&format!("refs/tags/{tag}")).is_ok() {
+        return Err(format!("❌ Tag {tag} already exists").into());
+    }
+    let head = repo.head()?.peel(ObjectType::Commit)?;
+    repo.tag(tag, &head, &repo.signature()?, message, false)?;
+    Ok(())
+}
+
+/// Uploads a Workflow RO-Crate to a WorkflowHub instance as new workflow of the given project and returns the URL of
+/// the created workflow
+pub fn upload_to_workflowhub(base_url: &str, token: &str, project_id: &str, archive: &Path) -> Result<String, Box<dyn Error>> {
+    let base_url = base_url.trim_end_matches('/');
+    let file_name = archive.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let form = Form::new().text("workflow[project_ids][]", project_id.to_string()).part(
+        "ro_crate",
+        Part::bytes(fs::read(archive)?).file_name(file_name).mime_str("application/zip")?,
+    );
+
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    let (status, body) = runtime
+        .block_on(async {
+            let response = Client::new()
+                .post(format!("{base_url}/workflows"))
+                .header("Authorization", format!("Token {token}"))
+                .header("Accept", "application/json")
+                .multipart(form)
+                .send()
+                .await?;
+            let status = response.status();
+            response.text().await.map(|text| (status, text))
+        })
+        .map_err(|e| format!("❌ Could not reach WorkflowHub at {base_url}: {e}"))?;
+    if !status.is_success() {
+        return Err(format!("❌ WorkflowHub responded with {status}: {body}").into());
+    }
+
+    let id = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| {
+            v.pointer("/data/id")
+                .map(|id| id.as_str().map(str::to_string).unwrap_or_else(|| id.to_string()))
+        })
+        .ok_or("❌ Invalid response of WorkflowHub")?;
+    Ok(format!("{base_url}/workflows/{id}"))
+}
+
+impl Author {
+    /// Identifier of the person in the RO-Crate, a local one is used for persons without identifier
+    fn get_id(&self) -> String {
+        self.identifier.clone().unwrap_or_else(|| format!("#{}", self.name.replace(' ', "_")))
+    }
+}
+
+/// Creates the metadata of a Zenodo deposition of type software from the package and its annotations
+pub fn get_zenodo_metadata(package: &Package) -> Value {
+    let annotations = &package.annotations;
+    let creators = annotations
+        .authors
+        .iter()
+        .map(|author| {
+            let mut creator = json!({ "name": author.name });
+            if let Some(orcid) = author.identifier.as_deref().and_then(|id| id.strip_prefix("https://orcid.org/")) {
+                creator["orcid"] = json!(orcid);
+            }
+            creator
+        })
+        .collect::<Vec<_>>();
+    let title = annotations.label.clone().unwrap_or_else(|| package.name.clone());
+    let mut metadata = json!({
+        "upload_type": "software",
+        "title": title,
+        "description": annotations.doc.clone().unwrap_or_else(|| format!("CWL workflow {title}")),
+        "creators": creators,
+        "version": package.version,
+    });
+    if let Some(license) = &annotations.license {
+        //licenses are annotated by name or SPDX URL, Zenodo expects the identifier
+        let id = license
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(license)
+            .trim_end_matches(".html");
+        metadata["license"] = json!(id);
+    }
+    json!({ "metadata": metadata })
+}
+
+/// Creates a Zenodo deposition, uploads the files, publishes it and returns the minted DOI
+pub fn upload_to_zenodo(base_url: &str, token: &str, package: &Package, files: &[PathBuf]) -> Result<String, Box<dyn Error>> {
+    let base_url = base_url.trim_end_matches('/');
+    let client = Client::new();
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    let send = |request: reqwest::RequestBuilder| -> Result<Value, Box<dyn Error>> {
+        let (status, body) = runtime
+            .block_on(async {
+                let response = request.bearer_auth(token).send().await?;
+                let status = response.status();
+                response.text().await.map(|text| (status, text))
+            })
+            .map_err(|e| format!("❌ Could not reach Zenodo at {base_url}: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("❌ Zenodo responded with {status}: {body}").into());
+        }
+        Ok(serde_json::from_str(&body).map_err(|e| format!("❌ Invalid response of Zenodo: {e}"))?)
+    };
+
+    let deposition = send(client.post(format!("{base_url}/api/deposit/depositions")).json(&json!({})))?;
+    let id = deposition.get("id").ok_or("❌ Invalid response of Zenodo")?;
+    let bucket = deposition
+        .pointer("/links/bucket")
+        .and_then(Value::as_str)
+        .ok_or("❌ Invalid response of Zenodo")?;
+    for file in files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        send(client.put(format!("{bucket}/{name}")).body(fs::read(file)?))?;
+    }
+    send(
+        client
+            .put(format!("{base_url}/api/deposit/depositions/{id}"))
+            .json(&get_zenodo_metadata(package)),
+    )?;
+    let published = send(client.post(format!("{base_url}/api/deposit/depositions/{id}/actions/publish")))?;
+    let doi = published.get("doi").and_then(Value::as_str).ok_or("❌ Zenodo did not mint a DOI")?;
+    Ok(doi.to_string())
+}
+
+/// Describes a registered dataset identified by its location, packed files at the same location are extended
+fn add_dataset_entity(graph: &mut Vec<Value>, project_dir: &Path, name: &str, dataset: &Dataset) {
+    let id = if dataset.is_remote() {
+        dataset.location.clone()
+    } else {
+        to_id(Path::new(dataset.location.trim_start_matches("./")))
+    };
+    let index = graph.iter().position(|e| e["@id"] == id).unwrap_or_else(|| {
+        let kind = if !dataset.is_remote() && project_dir.join(&dataset.location).is_dir() {
+            "Dataset"
+        } else {
+            "File"
+        };
+        graph.push(json!({ "@id": id, "@type": kind }));
+        graph.len() - 1
+    });
+
+    let entity = &mut graph[index];
+    entity["name"] = json!(name);
+    if let Some(sha1) = dataset.checksum.as_deref().and_then(|c| c.strip_prefix("sha1$")) {
+        entity["sha1"] = json!(sha1);
+    }
+    if let Some(license) = &dataset.license {
+        entity["license"] = json!(license);
+    }
+    if let Some(doi) = &dataset.doi {
+        let doi = doi.trim_start_matches("doi:");
+        entity["identifier"] = if doi.starts_with("https://") {
+            json!(doi)
+        } else {
+            json!(format!("https://doi.org/{doi}"))
+        };
+    }
+}
+
+fn to_id(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::create_dir_all;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_package_metadata() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        create_dir_all(root.join("workflows/main")).unwrap();
+        create_dir_all(root.join("workflows/echo")).unwrap();
+        fs::write(
+            root.join("workflows/main/main.cwl"),
+            "class: Workflow
+cwlVersion: v1.2
+label: Main
+s:license: https://spdx.org/licenses/MIT
+s:author:
+- class: s:Person
+  s:identifier: https://orcid.org/0000-0002-1825-0097
+  s:name: Jane Doe
+inputs:
+  temperature:
+    type: float
+    s:unitCode: http://purl.obolibrary.org/obo/UO_0000027
+    s:maxValue: 40
+outputs: []
+steps: []
+",
+        )
+        .unwrap();
+        fs::write(
+            root.join("workflows/echo/echo.cwl"),
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs: []\noutputs: []\n",
+        )
+        .unwrap();
+        fs::write(root.join("README.md"), "# Test\n").unwrap();
+
+        let package = Package::from_project(root, "test", "1.0.0", None).unwrap();
+        assert_eq!(package.main_workflow, PathBuf::from("workflows/main/main.cwl"));
+        assert_eq!(
+            package.files,
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("workflows/echo/echo.cwl"),
+                PathBuf::from("workflows/main/main.cwl")
+            ]
+        );
+
+        let metadata = package.get_metadata(root);
+        let graph = metadata["@graph"].as_array().unwrap();
+        let find = |id: &str| graph.iter().find(|e| e["@id"] == id).unwrap();
+        assert_eq!(find("./")["mainEntity"]["@id"], "workflows/main/main.cwl");
+        assert_eq!(find("./")["version"], "1.0.0");
+        assert_eq!(find("workflows/main/main.cwl")["name"], "Main");
+        assert_eq!(find("workflows/echo/echo.cwl")["@type"], json!(["File", "SoftwareSourceCode"]));
+        assert_eq!(find("README.md")["@type"], "File");
+        assert_eq!(find("./")["license"], "https://spdx.org/licenses/MIT");
+        assert_eq!(find("https://orcid.org/0000-0002-1825-0097")["name"], "Jane Doe");
+        assert_eq!(find("workflows/main/main.cwl")["input"], json!([{ "@id": "#workflows/main/main.cwl/temperature" }]));
+        let parameter = find("#workflows/main/main.cwl/temperature");
+        assert_eq!(parameter["unitCode"]["@id"], "http://purl.obolibrary.org/obo/UO_0000027");
+        assert_eq!(parameter["unitText"], "UO:0000027");
+        assert_eq!(parameter["maxValue"], 40.0);
+
+        let zenodo = get_zenodo_metadata(&package);
+        assert_eq!(zenodo["metadata"]["license"], "MIT");
+        assert_eq!(zenodo["metadata"]["title"], "Main");
+        assert_eq!(
+            zenodo["metadata"]["creators"],
+            json!([{ "name": "Jane Doe", "orcid": "0000-0002-1825-0097" }])
+        );
+
+        assert!(Package::from_project(root, "test", "1.0.0", Some("echo")).is_err());
+    }
+}
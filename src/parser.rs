@@ -1,10 +1,13 @@
-use crate::{io::get_filename_without_extension, split_vec_at};
+use crate::{
+    io::{get_filename_without_extension, is_binary_file},
+    split_vec_at,
+};
 use cwl::{
     clt::{Argument, Command, CommandLineTool},
     inputs::{CommandInputParameter, CommandLineBinding},
     outputs::{CommandOutputBinding, CommandOutputParameter},
-    requirements::{InitialWorkDirRequirement, Requirement},
-    types::{CWLType, DefaultValue, Directory, File},
+    requirements::{InitialWorkDirRequirement, InlineJavascriptRequirement, Requirement},
+    types::{CWLType, DefaultValue, Directory, Entry, File},
 };
 use serde_yaml::Value;
 use slugify::slugify;
@@ -33,9 +36,11 @@ pub fn parse_command_line(commands: Vec<&str>, inputs: Option<Vec<&str>>) -> Com
         let updated_commands = update_commands_with_entrynames(commands.clone(), &initial_dir_req);
         let base_command_updated = get_base_command(&updated_commands.iter().map(String::as_str).collect::<Vec<_>>());
 
-        return tool
+        tool = tool
             .with_base_command(base_command_updated)
             .with_requirements(vec![Requirement::InitialWorkDirRequirement(initial_dir_req)]);
+        stage_binary_files(&mut tool, Path::new("."));
+        return tool;
     } else if !remainder.is_empty() {
         let (cmd, piped) = split_vec_at(remainder, "|");
 
@@ -70,6 +75,43 @@ pub fn parse_command_line(commands: Vec<&str>, inputs: Option<Vec<&str>>) -> Com
     tool
 }
 
+/// Binary files can not be embedded using `$include`, they are staged by a File input the listing entry references
+/// instead. Included files are looked up relative to `base`. Returns the ids of the added inputs.
+pub fn stage_binary_files(tool: &mut CommandLineTool, base: &Path) -> Vec<String> {
+    let mut added = vec![];
+    let mut new_inputs: Vec<CommandInputParameter> = vec![];
+    for requirements in [&mut tool.requirements, &mut tool.hints].into_iter().flatten() {
+        for requirement in requirements.iter_mut() {
+            let Requirement::InitialWorkDirRequirement(iwdr) = requirement else {
+                continue;
+            };
+            for listing in &mut iwdr.listing {
+                let Entry::Include(include) = &listing.entry else {
+                    continue;
+                };
+                if !is_binary_file(base.join(&include.include)) {
+                    continue;
+                }
+                let name = get_filename_without_extension(&listing.entryname).unwrap_or(listing.entryname.clone());
+                let mut id = slugify!(&name, separator = "_");
+                while tool.inputs.iter().chain(&new_inputs).any(|i| i.id == id) {
+                    id.push_str("_file");
+                }
+                new_inputs.push(
+                    CommandInputParameter::default()
+                        .with_id(&id)
+                        .with_type(CWLType::File)
+                        .with_default_value(DefaultValue::File(File::from_location(&include.include))),
+                );
+                listing.entry = Entry::Source(format!("$(inputs.{id})"));
+                added.push(id);
+            }
+        }
+    }
+    tool.inputs.extend(new_inputs);
+    added
+}
+
 fn update_commands_with_entrynames(commands: Vec<&str>, initial_work_dir: &InitialWorkDirRequirement) -> Vec<String> {
     let entry_map: HashMap<&str, &str> = initial_work_dir
         .listing
@@ -98,7 +140,10 @@ pub fn get_outputs(files: Vec<String>) -> Vec<CommandOutputParameter> {
             CommandOutputParameter::default()
                 .with_type(CWLType::File)
                 .with_id(get_filename_without_extension(f).unwrap_or(f.to_string()).as_str())
-                .with_binding(CommandOutputBinding { glob: f.clone() })
+                .with_binding(CommandOutputBinding {
+                    glob: f.clone(),
+                    ..Default::default()
+                })
         })
         .collect()
 }
@@ -274,9 +319,9 @@ pub fn post_process_cwl(tool: &mut CommandLineTool) {
 
     if processed_once {
         if let Some(requirements) = &mut tool.requirements {
-            requirements.push(Requirement::InlineJavascriptRequirement);
+            requirements.push(Requirement::InlineJavascriptRequirement(InlineJavascriptRequirement::default()));
         } else {
-            tool.requirements = Some(vec![Requirement::InlineJavascriptRequirement]);
+            tool.requirements = Some(vec![Requirement::InlineJavascriptRequirement(InlineJavascriptRequirement::default())]);
         }
     }
 }
@@ -308,11 +353,315 @@ pub fn guess_type(value: &str) -> CWLType {
     }
 }
 
+/// An option or positional argument listed in the `--help` output of a script
+#[derive(Debug, Default)]
+struct HelpEntry {
+    names: Vec<String>,
+    metavar: Option<String>,
+    is_array: bool,
+    help: String,
+}
+
+/// Creates a CommandLineTool from the `--help` output of a command, as printed by Python's argparse or click. Inputs
+/// get their types from metavars like `INTEGER` or `FILE` or from the documented default value, their prefixes from
+/// the option names and their docs from the help of each option. The description becomes the doc of the tool.
+pub fn parse_help_text(command: &[&str], help: &str) -> CommandLineTool {
+    let mut tool = parse_command_line(command.to_vec(), None);
+
+    let mut usage = String::new();
+    let mut in_usage = false;
+    let mut description = vec![];
+    let mut section: Option<String> = None;
+    let mut has_positional_section = false;
+    let mut options: Vec<HelpEntry> = vec![];
+    let mut positionals: Vec<HelpEntry> = vec![];
+    let mut entry_indent = 0;
+
+    for line in help.lines() {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        if trimmed.to_lowercase().starts_with("usage:") {
+            usage = trimmed[6..].trim().to_string();
+            in_usage = true;
+            continue;
+        }
+        //usage lines of long commands are continued on indented lines
+        if in_usage {
+            if trimmed.is_empty() {
+                in_usage = false;
+            } else {
+                usage.push(' ');
+                usage.push_str(trimmed);
+            }
+            continue;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        if indent == 0 && trimmed.ends_with(':') {
+            let name = trimmed.trim_end_matches(':').to_lowercase();
+            has_positional_section |= is_positional_section(&name);
+            section = Some(name);
+            continue;
+        }
+        let Some(section) = &section else {
+            description.push(trimmed);
+            continue;
+        };
+        //epilogs are not part of any section
+        if indent == 0 {
+            continue;
+        }
+        let (entries, starts_entry) = if is_positional_section(section) {
+            let starts_entry = positionals.is_empty() || indent <= entry_indent;
+            (&mut positionals, starts_entry)
+        } else {
+            let starts_entry = trimmed.starts_with('-') && (options.is_empty() || indent <= entry_indent);
+            (&mut options, starts_entry)
+        };
+        if starts_entry {
+            entry_indent = indent;
+            //the help of an entry is separated from its names by at least two spaces
+            let (spec, text) = trimmed.split_once("  ").map_or((trimmed, ""), |(spec, text)| (spec, text.trim()));
+            let mut entry = parse_help_spec(spec);
+            entry.help = text.to_string();
+            entries.push(entry);
+        } else if let Some(entry) = entries.last_mut() {
+            if !entry.help.is_empty() {
+                entry.help.push(' ');
+            }
+            entry.help.push_str(trimmed);
+        }
+    }
+
+    //click does not list arguments, they are only part of the usage line
+    if !has_positional_section {
+        positionals = get_usage_arguments(&usage)
+            .into_iter()
+            .map(|name| HelpEntry {
+                is_array: name.ends_with("..."),
+                names: vec![name.trim_end_matches("...").to_string()],
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    let mut inputs = vec![];
+    for entry in options {
+        if entry.names.iter().any(|name| ["-h", "--help", "--version"].contains(&name.as_str())) {
+            continue;
+        }
+        //long option names are preferred as they are more descriptive
+        let Some(prefix) = entry.names.iter().find(|name| name.starts_with("--")).or(entry.names.first()) else {
+            continue;
+        };
+        let id = slugify!(prefix.trim_start_matches('-'), separator = "_");
+        let input = create_help_input(&id, &entry).with_binding(CommandLineBinding::default().with_prefix(prefix));
+        inputs.push(input);
+    }
+    for (i, entry) in positionals.iter().enumerate() {
+        let Some(name) = entry.names.first() else {
+            continue;
+        };
+        let id = slugify!(name, separator = "_");
+        let input = create_help_input(&id, entry).with_binding(CommandLineBinding::default().with_position(i as isize + 1));
+        inputs.push(input);
+    }
+    tool.inputs = inputs;
+
+    if !description.is_empty() {
+        tool.doc = Some(description.join(" "));
+    }
+    tool
+}
+
+fn is_positional_section(section: &str) -> bool {
+    section.contains("positional") || section == "arguments"
+}
+
+/// Parses the names and metavar of an entry like `-c COUNT, --count COUNT`, `--count INTEGER` or `--shout / --no-shout`
+fn parse_help_spec(spec: &str) -> HelpEntry {
+    let mut entry = HelpEntry {
+        is_array: spec.contains("..."),
+        ..Default::default()
+    };
+    let tokens = spec.split([',', ' ']).filter(|token| !token.is_empty());
+    //the negated name of boolean switches is not needed
+    for token in tokens.take_while(|token| *token != "/") {
+        if token.starts_with('-') {
+            entry.names.push(token.to_string());
+        } else if entry.metavar.is_none() {
+            entry.metavar = Some(token.trim_matches(['[', ']', '<', '>', '.']).to_string());
+        }
+    }
+    //positional arguments only consist of their name
+    if entry.names.is_empty() {
+        entry.names.extend(entry.metavar.take());
+    }
+    entry
+}
+
+/// Returns the arguments of a usage line like `script.py [OPTIONS] INPUT FILES...` without the program and optional parts
+fn get_usage_arguments(usage: &str) -> Vec<String> {
+    let mut required = String::new();
+    let mut depth = 0;
+    for c in usage.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if depth == 0 => required.push(c),
+            _ => {}
+        }
+    }
+    let mut arguments = vec![];
+    let mut tokens = required.split_whitespace().skip(1);
+    while let Some(token) = tokens.next() {
+        if token.starts_with('-') {
+            //required options are followed by their metavar
+            tokens.next();
+        } else if !token.starts_with('{') {
+            arguments.push(token.to_string());
+        }
+    }
+    arguments
+}
+
+/// Maps metavars printed by argparse and click to CWL types
+fn get_metavar_type(metavar: &str) -> Option<CWLType> {
+    match metavar.to_uppercase().as_str() {
+        "INTEGER" | "INT" | "N" => Some(CWLType::Int),
+        "FLOAT" | "NUMBER" => Some(CWLType::Float),
+        "FILE" | "FILENAME" => Some(CWLType::File),
+        "DIRECTORY" | "DIR" => Some(CWLType::Directory),
+        "BOOLEAN" | "BOOL" => Some(CWLType::Boolean),
+        "TEXT" | "STRING" | "STR" => Some(CWLType::String),
+        _ => None,
+    }
+}
+
+fn create_help_input(id: &str, entry: &HelpEntry) -> CommandInputParameter {
+    let (doc, default) = extract_help_default(&entry.help);
+    let is_flag = entry.metavar.is_none() && entry.names.iter().any(|name| name.starts_with('-'));
+    let cwl_type = if is_flag {
+        CWLType::Boolean
+    } else {
+        entry
+            .metavar
+            .as_deref()
+            .and_then(get_metavar_type)
+            .or_else(|| default.as_deref().filter(|d| serde_yaml::from_str::<Value>(d).is_ok()).map(guess_type))
+            .unwrap_or(CWLType::String)
+    };
+
+    let mut input = CommandInputParameter::default().with_id(id);
+    if !doc.is_empty() {
+        input = input.with_doc(&doc);
+    }
+    if entry.is_array {
+        return input.with_type(CWLType::Array(Box::new(cwl_type)));
+    }
+    let default_value = match (&cwl_type, default) {
+        (CWLType::Boolean, None) if is_flag => Some(DefaultValue::Any(Value::Bool(false))),
+        (_, None) => None,
+        (CWLType::File, Some(default)) => Some(DefaultValue::File(File::from_location(&default))),
+        (CWLType::Directory, Some(default)) => Some(DefaultValue::Directory(Directory::from_location(&default))),
+        (CWLType::Boolean, Some(default)) => Some(DefaultValue::Any(Value::Bool(default.eq_ignore_ascii_case("true")))),
+        (_, Some(default)) => Some(DefaultValue::Any(serde_yaml::from_str(&default).unwrap_or(Value::String(default)))),
+    };
+    if let Some(default_value) = default_value {
+        input = input.with_default_value(default_value);
+    }
+    input.with_type(cwl_type)
+}
+
+/// Splits the default value documented as `(default: 5)` by argparse or `[default: 5]` by click from the help text
+fn extract_help_default(help: &str) -> (String, Option<String>) {
+    let mut doc = help.replace("[required]", "");
+    let mut default = None;
+    for (start, end) in [("(default: ", ')'), ("[default: ", ']')] {
+        if let Some(begin) = doc.find(start) {
+            if let Some(length) = doc[begin..].find(end) {
+                let value = doc[begin + start.len()..begin + length].trim().to_string();
+                //argparse prints `None` for options without a default
+                if value != "None" && !value.is_empty() {
+                    default = Some(value);
+                }
+                doc.replace_range(begin..=begin + length, "");
+            }
+        }
+    }
+    (doc.split_whitespace().collect::<Vec<_>>().join(" "), default)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     //test private cwl api here
     #[test]
+    pub fn test_parse_help_text_argparse() {
+        let help = "usage: script.py [-h] [-c COUNT] [--verbose] [--files [FILES ...]] input
+
+Counts words in a text.
+
+positional arguments:
+  input                 file to count the words of
+
+options:
+  -h, --help            show this help message and exit
+  -c COUNT, --count COUNT
+                        number of words to print (default: 10)
+  --verbose             print progress
+  --files [FILES ...]   additional files
+";
+        let tool = parse_help_text(&["python", "script.py"], help);
+        assert_eq!(tool.base_command, Command::Multiple(vec!["python".to_string(), "script.py".to_string()]));
+        assert_eq!(tool.doc, Some("Counts words in a text.".to_string()));
+        assert_eq!(tool.inputs.len(), 4);
+
+        let count = &tool.inputs[0];
+        assert_eq!(count.id, "count");
+        assert_eq!(count.type_, CWLType::Int);
+        assert_eq!(count.doc, Some("number of words to print".to_string()));
+        assert_eq!(count.default, Some(DefaultValue::Any(Value::from(10))));
+        assert_eq!(count.input_binding.as_ref().unwrap().prefix, Some("--count".to_string()));
+
+        assert_eq!(tool.inputs[1].type_, CWLType::Boolean);
+        assert_eq!(tool.inputs[1].default, Some(DefaultValue::Any(Value::Bool(false))));
+        assert_eq!(tool.inputs[2].type_, CWLType::Array(Box::new(CWLType::String)));
+
+        let input = &tool.inputs[3];
+        assert_eq!(input.id, "input");
+        assert_eq!(input.doc, Some("file to count the words of".to_string()));
+        assert_eq!(input.input_binding.as_ref().unwrap().position, Some(1));
+    }
+
+    #[test]
+    pub fn test_parse_help_text_click() {
+        let help = "Usage: plot.py [OPTIONS] DATA
+
+  Plots the given data.
+
+Options:
+  --title TEXT      Title of the plot  [required]
+  --dpi INTEGER     Resolution  [default: 300]
+  --out-dir DIRECTORY
+  --shout / --no-shout
+  --help            Show this message and exit.
+";
+        let tool = parse_help_text(&["python", "plot.py"], help);
+        assert_eq!(tool.doc, Some("Plots the given data.".to_string()));
+        let ids = tool.inputs.iter().map(|i| i.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["title", "dpi", "out_dir", "shout", "data"]);
+        assert_eq!(tool.inputs[0].doc, Some("Title of the plot".to_string()));
+        assert_eq!(tool.inputs[0].default, None);
+        assert_eq!(tool.inputs[1].type_, CWLType::Int);
+        assert_eq!(tool.inputs[1].default, Some(DefaultValue::Any(Value::from(300))));
+        assert_eq!(tool.inputs[2].type_, CWLType::Directory);
+        assert_eq!(tool.inputs[3].type_, CWLType::Boolean);
+        assert_eq!(tool.inputs[4].type_, CWLType::String);
+        assert_eq!(tool.inputs[4].input_binding.as_ref().unwrap().position, Some(1));
+    }
+    #[test]
     pub fn test_get_base_command() {
         let commands = ["python script.py --arg1 hello", "echo 'Hello World!'", "Rscript lol.R", ""];
         let expected = [
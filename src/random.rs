@@ -0,0 +1,78 @@
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
+use std::{cell::RefCell, io};
+use tempfile::{Builder, TempDir};
+use uuid::Uuid;
+
+/// Environment variable tools can read the seed of a run from
+pub const SEED_VAR: &str = "S4N_SEED";
+
+thread_local!(static SEEDED: RefCell<Option<(u64, StdRng)>> = const { RefCell::new(None) });
+
+/// Makes run ids, names of temporary directories and random filenames deterministic by drawing them from a generator
+/// with the given seed, `None` returns to random values
+pub fn set_seed(seed: Option<u64>) {
+    SEEDED.with(|seeded| *seeded.borrow_mut() = seed.map(|seed| (seed, StdRng::seed_from_u64(seed))));
+}
+
+/// Seeds the generator of a worker thread running the job with the given index of a run, so that names drawn by jobs
+/// do not depend on the order in which they are run
+pub fn set_job_seed(seed: Option<u64>, job: u64) {
+    let stream = seed.map(|seed| seed ^ (job + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    SEEDED.with(|seeded| *seeded.borrow_mut() = seed.zip(stream).map(|(seed, stream)| (seed, StdRng::seed_from_u64(stream))));
+}
+
+pub fn seed() -> Option<u64> {
+    SEEDED.with(|seeded| seeded.borrow().as_ref().map(|(seed, _)| *seed))
+}
+
+/// Fills the buffer from the seeded generator or randomly if no seed is set
+fn fill_bytes(bytes: &mut [u8]) {
+    SEEDED.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some((_, rng)) => rng.fill(bytes),
+        None => rand::thread_rng().fill(bytes),
+    });
+}
+
+/// Creates a version 4 UUID
+pub fn new_uuid() -> Uuid {
+    let mut bytes = [0; 16];
+    fill_bytes(&mut bytes);
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// Creates an alphanumeric string of the given length
+pub fn random_string(length: usize) -> String {
+    SEEDED.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some((_, rng)) => rng.sample_iter(&Alphanumeric).take(length).map(char::from).collect(),
+        None => rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect(),
+    })
+}
+
+/// Creates a temporary directory, which is named deterministically if a seed is set. A randomly named one is used if
+/// the directory already exists, e.g. because another run with the same seed is in progress.
+pub fn temp_dir() -> io::Result<TempDir> {
+    if seed().is_some() {
+        let name = format!(".tmp{}", random_string(6));
+        if let Ok(dir) = Builder::new().prefix(&name).rand_bytes(0).tempdir() {
+            return Ok(dir);
+        }
+    }
+    tempfile::tempdir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed() {
+        set_seed(Some(42));
+        let first = (new_uuid(), random_string(10), temp_dir().unwrap().path().to_path_buf());
+        set_seed(Some(42));
+        let second = (new_uuid(), random_string(10), temp_dir().unwrap().path().to_path_buf());
+        set_seed(None);
+        assert_eq!(first, second);
+        assert_eq!(first.0.get_version_num(), 4);
+        assert_ne!(new_uuid(), first.0);
+    }
+}
@@ -1,5 +1,18 @@
-use git2::{Commit, Error, IndexAddOption, Repository, Status, StatusOptions};
-use std::{iter, path::Path};
+use git2::{build::CheckoutBuilder, Commit, Error, IndexAddOption, Repository, Sort, Status, StatusOptions};
+use std::{
+    env, fs, iter,
+    path::{Component, Path, PathBuf},
+};
+
+/// A commit which added or changed a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRevision {
+    pub id: String,
+    /// Seconds since the Unix epoch
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+}
 
 pub fn get_modified_files(repo: &Repository) -> Vec<String> {
     let mut opts = StatusOptions::new();
@@ -53,3 +66,65 @@ fn commit_impl(repo: &Repository, message: &str, parents: &[&Commit<'_>]) -> Res
     repo.commit(Some("HEAD"), &author, &author, message, &new_tree, parents)?;
     Ok(())
 }
+
+/// Path of a file relative to the root of the repository, the file does not need to exist in the working tree
+pub fn get_repository_path(repo: &Repository, path: &Path) -> Option<PathBuf> {
+    let workdir = fs::canonicalize(repo.workdir()?).ok()?;
+    let absolute = match fs::canonicalize(path) {
+        Ok(absolute) => absolute,
+        Err(_) => fs::canonicalize(env::current_dir().ok()?)
+            .ok()?
+            .join(path.components().filter(|c| *c != Component::CurDir).collect::<PathBuf>()),
+    };
+    absolute.strip_prefix(workdir).ok().map(Path::to_path_buf)
+}
+
+/// Commits reachable from HEAD which added or changed the file at the given path relative to the repository root,
+/// newest first. Merge commits are only listed if the file differs from all of their parents.
+pub fn get_file_history(repo: &Repository, path: &Path) -> Result<Vec<FileRevision>, Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TIME)?;
+
+    let mut history = vec![];
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let Ok(entry) = commit.tree()?.get_path(path) else {
+            continue;
+        };
+        let changed = commit
+            .parents()
+            .all(|parent| parent.tree().ok().and_then(|tree| tree.get_path(path).ok()).map(|e| e.id()) != Some(entry.id()));
+        if changed {
+            history.push(FileRevision {
+                id: commit.id().to_string(),
+                time: commit.time().seconds(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+    }
+    Ok(history)
+}
+
+/// Id of the HEAD commit the file at the given path relative to the repository root belongs to, suffixed with
+/// `-dirty` if the file has uncommitted changes
+pub fn get_file_commit(repo: &Repository, path: &Path) -> Option<String> {
+    let head = repo.head().ok()?.peel_to_commit().ok()?.id().to_string();
+    let status = repo.status_file(path).unwrap_or(Status::CURRENT);
+    if status.is_empty() || status.contains(Status::IGNORED) {
+        Some(head)
+    } else {
+        Some(format!("{head}-dirty"))
+    }
+}
+
+/// Writes the files of a revision like `HEAD~2`, a branch or a commit id to the target directory without touching the
+/// working tree, index or HEAD and returns the id of the commit
+pub fn checkout_revision(repo: &Repository, revision: &str, target: &Path) -> Result<String, Error> {
+    let commit = repo.revparse_single(revision)?.peel_to_commit()?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.target_dir(target).update_index(false).force();
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+    Ok(commit.id().to_string())
+}
@@ -0,0 +1,284 @@
+use crate::{
+    sbom::{collect_components, Component, ComponentKind},
+    units::get_quantities,
+    visualize::{render_workflow, Renderer},
+};
+use cwl::{clt::Command, load_tool, load_workflow};
+use std::{error::Error, path::Path};
+
+/// Reference of the CWL standard, which is cited by every report
+const CWL_CITATION: &str = "Crusoe, M. R. et al. (2022). Methods Included: Standardizing Computational Reuse and Portability with the \
+                            Common Workflow Language. Communications of the ACM, 65(6), 54-63. https://doi.org/10.1145/3486897";
+
+/// Supplementary material for a manuscript describing a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicationReport {
+    pub name: String,
+    /// Graph of the workflow as SVG image
+    pub figure: String,
+    /// Methods paragraph generated from the annotations of the workflow and the docs of its steps
+    pub methods: String,
+    /// References cited by the methods paragraph, `[1]` refers to the first one
+    pub references: Vec<String>,
+}
+
+impl PublicationReport {
+    /// Markdown document embedding the figure from the given path
+    pub fn to_markdown(&self, figure: &str) -> String {
+        let references = self
+            .references
+            .iter()
+            .enumerate()
+            .map(|(i, reference)| format!("{}. {reference}", i + 1))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "# {0}\n\n![Workflow {0}]({figure})\n\n**Figure:** Graph of the workflow {0}. Inputs are shown in blue, outputs in green and steps \
+             running nested workflows in grey.\n\n## Methods\n\n{1}\n\n## Software\n\n{references}\n",
+            self.name, self.methods
+        )
+    }
+}
+
+/// Creates a figure, a methods paragraph and the list of software to cite for a workflow. The methods describe the steps in
+/// the order they are run using the `doc` or `label` of their tools and the software they use, followed by the units of
+/// the inputs annotated using `annotate input`.
+pub fn create_publication_report(path: &Path) -> Result<PublicationReport, Box<dyn Error>> {
+    let workflow = load_workflow(path)?;
+    let folder = path.parent().unwrap_or(Path::new("."));
+    let name = workflow
+        .label
+        .clone()
+        .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+    let components = collect_components(path)?;
+
+    let mut references = vec![
+        CWL_CITATION.to_string(),
+        format!(
+            "SciWIn client (s4n), version {}. {}",
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_REPOSITORY")
+        ),
+    ];
+    let mut cited: Vec<&Component> = vec![];
+
+    let mut sentences = vec![format!(
+        "The analysis was implemented as workflow \"{name}\" using the Common Workflow Language (CWL) [1]."
+    )];
+    if let Some(doc) = &workflow.doc {
+        sentences.push(to_sentence(doc.split("\n\n").next().unwrap_or_default()));
+    }
+    let step_count = match workflow.steps.len() {
+        1 => "one step".to_string(),
+        n => format!("{n} steps"),
+    };
+    sentences.push(format!(
+        "It consists of {step_count}, which were executed using the SciWIn client (s4n) [2]."
+    ));
+
+    //steps are described in the order they are run, the order of the file is used if it contains cycles
+    let order = workflow
+        .sort_steps()
+        .unwrap_or_else(|_| workflow.steps.iter().map(|s| s.id.clone()).collect());
+    for step in order.iter().filter_map(|id| workflow.steps.iter().find(|s| s.id == *id)) {
+        let run = folder.join(&step.run);
+        let tool_name = run.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let description = match (load_tool(&run), load_workflow(&run)) {
+            (Ok(tool), _) => {
+                let command = match &tool.base_command {
+                    Command::Single(command) => command.clone(),
+                    Command::Multiple(command) => command.join(" "),
+                };
+                describe(tool.doc.as_deref().or(tool.label.as_deref()), &format!("runs `{command}`"))
+            }
+            (_, Ok(nested)) => describe(
+                nested.doc.as_deref().or(nested.label.as_deref()),
+                &format!("runs the nested workflow `{tool_name}`"),
+            ),
+            _ => format!("runs `{}`", step.run),
+        };
+
+        let software = components
+            .iter()
+            .filter(|component| component.used_by.contains(&tool_name))
+            .map(|component| {
+                let index = cited.iter().position(|c| *c == component).unwrap_or_else(|| {
+                    cited.push(component);
+                    cited.len() - 1
+                });
+                format!("{} [{}]", component_name(component), references.len() + index + 1)
+            })
+            .collect::<Vec<_>>();
+        let software = match software.as_slice() {
+            [] => String::new(),
+            [single] => format!(" using {single}"),
+            [rest @ .., last] => format!(" using {} and {last}", rest.join(", ")),
+        };
+        sentences.push(format!("The step `{}` {description}{software}.", step.id));
+    }
+
+    let quantities = get_quantities(path)?
+        .into_iter()
+        .map(|(id, quantity)| {
+            let unit = quantity.unit().map(|unit| format!(" in {unit}")).unwrap_or_default();
+            let range = quantity.range().map(|range| format!(" ({range})")).unwrap_or_default();
+            format!("`{id}`{unit}{range}")
+        })
+        .collect::<Vec<_>>();
+    if !quantities.is_empty() {
+        sentences.push(format!("The input parameters were given as {}.", quantities.join(", ")));
+    }
+
+    //software of nested workflows is not cited by the steps and listed afterwards
+    for component in &components {
+        if !cited.contains(&component) {
+            cited.push(component);
+        }
+    }
+    references.extend(cited.iter().map(|component| cite_component(component)));
+
+    Ok(PublicationReport {
+        name,
+        figure: render_workflow(path, Renderer::Svg)?,
+        methods: sentences.join(" "),
+        references,
+    })
+}
+
+/// Uses the first sentence of the given text starting lowercase, so it can follow the step name
+fn describe(text: Option<&str>, fallback: &str) -> String {
+    let Some(text) = text.map(str::trim).filter(|t| !t.is_empty()) else {
+        return fallback.to_string();
+    };
+    let sentence = to_sentence(text);
+    let sentence = sentence.trim_end_matches('.');
+    let mut chars = sentence.chars();
+    match (chars.next(), chars.next()) {
+        //acronyms like PCA are kept
+        (Some(first), Some(second)) if !second.is_uppercase() => first.to_lowercase().chain(sentence.chars().skip(1)).collect(),
+        _ => sentence.to_string(),
+    }
+}
+
+/// First sentence of a text ending with a period
+fn to_sentence(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let sentence = match text.find(". ") {
+        Some(end) => &text[..end],
+        None => text.trim_end_matches('.'),
+    };
+    format!("{sentence}.")
+}
+
+fn component_name(component: &Component) -> String {
+    match &component.version {
+        //digests of images are too long for a text
+        Some(version) if !version.starts_with("sha256:") => format!("{} {version}", component.name),
+        _ => component.name.clone(),
+    }
+}
+
+/// Citation of a component linking to its registry, the package URL is used if the registry is not known
+fn cite_component(component: &Component) -> String {
+    let kind = match component.kind {
+        ComponentKind::Container => "Container image",
+        ComponentKind::Library => "Software package",
+    };
+    let purl_type = component.purl.strip_prefix("pkg:").and_then(|p| p.split('/').next()).unwrap_or_default();
+    let name = &component.name;
+    let url = match purl_type {
+        "pypi" => format!("https://pypi.org/project/{name}/"),
+        "cran" => format!("https://cran.r-project.org/package={name}"),
+        "conda" => match component.purl.split_once("?channel=") {
+            Some((_, channel)) => format!("https://anaconda.org/{channel}/{name}"),
+            None => component.purl.clone(),
+        },
+        //official images of Docker Hub have no namespace
+        "docker" if !component.purl.contains("repository_url=") && name.contains('/') => format!("https://hub.docker.com/r/{name}"),
+        "docker" if !component.purl.contains("repository_url=") => format!("https://hub.docker.com/_/{name}"),
+        _ => component.purl.clone(),
+    };
+    format!("{}. {kind}. {url}", component_name(component))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(Some("Calculates the speakers.\nUses pandas."), "runs"),
+            "calculates the speakers"
+        );
+        assert_eq!(describe(Some("PCA of the samples"), "runs"), "PCA of the samples");
+        assert_eq!(describe(None, "runs `echo`"), "runs `echo`");
+        assert_eq!(to_sentence("Plots  the results. Then saves them"), "Plots the results.");
+    }
+
+    #[test]
+    fn test_cite_component() {
+        let component = Component {
+            name: "pandas".to_string(),
+            version: Some("2.2.3".to_string()),
+            kind: ComponentKind::Library,
+            purl: "pkg:pypi/pandas@2.2.3".to_string(),
+            digest: None,
+            used_by: vec![],
+        };
+        assert_eq!(
+            cite_component(&component),
+            "pandas 2.2.3. Software package. https://pypi.org/project/pandas/"
+        );
+        let image = Component {
+            name: "python".to_string(),
+            version: Some("3.12".to_string()),
+            kind: ComponentKind::Container,
+            purl: "pkg:docker/python@3.12".to_string(),
+            ..component
+        };
+        assert_eq!(cite_component(&image), "python 3.12. Container image. https://hub.docker.com/_/python");
+    }
+
+    #[test]
+    fn test_create_publication_report() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("clean.cwl"),
+            "class: CommandLineTool\ncwlVersion: v1.2\ndoc: Removes outliers from the measurements.\nbaseCommand: clean\nhints:\n  DockerRequirement:\n    dockerPull: python:3.12\ninputs:\n  temperature:\n    type: float\n    s:unitText: degree Celsius\n    s:minValue: 0\n    s:maxValue: 40\noutputs:\n  cleaned:\n    type: File\n    outputBinding:\n      glob: cleaned.csv\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("plot.cwl"),
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: [plot, --png]\ninputs:\n  data: File\noutputs: []\n",
+        )
+        .unwrap();
+        let path = dir.path().join("main.cwl");
+        fs::write(
+            &path,
+            "class: Workflow\ncwlVersion: v1.2\nlabel: Field trial\ndoc: |\n  Analyses the measurements of a field trial.\n\n  More details.\ninputs:\n  temp: float\noutputs: []\nsteps:\n  plot:\n    run: plot.cwl\n    in:\n      data: clean/cleaned\n    out: []\n  clean:\n    run: clean.cwl\n    in:\n      temperature: temp\n    out: [cleaned]\n",
+        )
+        .unwrap();
+
+        let report = create_publication_report(&path).unwrap();
+        assert_eq!(report.name, "Field trial");
+        assert!(report.figure.starts_with("<svg"));
+        assert!(report.methods.starts_with(
+            "The analysis was implemented as workflow \"Field trial\" using the Common Workflow Language (CWL) [1]. Analyses the measurements of a field trial. It consists of 2 steps"
+        ));
+        assert!(report
+            .methods
+            .contains("The step `clean` removes outliers from the measurements using python 3.12 [3]. The step `plot` runs `plot --png`."));
+        assert!(report
+            .methods
+            .ends_with("The input parameters were given as `temp` in degree Celsius (0 to 40)."));
+        assert_eq!(report.references.len(), 3);
+        assert!(report.references[2].ends_with("https://hub.docker.com/_/python"));
+
+        let markdown = report.to_markdown("figure.svg");
+        assert!(markdown.starts_with("# Field trial\n\n![Workflow Field trial](figure.svg)"));
+        assert!(markdown.contains("## Software\n\n1. Crusoe, M. R. et al. (2022)"));
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    container::{detect_dependencies, get_image_digest, ScriptLanguage},
+    environments::get_software_packages,
+    random::new_uuid,
+};
+use chrono::Utc;
+use cwl::{
+    clt::{Command, CommandLineTool},
+    load_tool, load_workflow,
+    requirements::{DockerRequirement, Requirement, SoftwarePackage},
+    types::Entry,
+};
+use serde_json::{json, Value};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Kind of software a tool depends on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComponentKind {
+    Container,
+    Library,
+}
+
+/// Software used by the tools of a workflow, identified by its [package URL](https://github.com/package-url/purl-spec)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub version: Option<String>,
+    pub kind: ComponentKind,
+    pub purl: String,
+    /// SHA-256 digest of container images
+    pub digest: Option<String>,
+    /// Tools depending on the component
+    pub used_by: Vec<String>,
+}
+
+/// Collects the container images, `SoftwareRequirement` packages, packages of conda environments and dependencies of
+/// scripts of all tools a workflow or tool runs. Components used by several tools are listed once.
+pub fn collect_components(file: &Path) -> Result<Vec<Component>, Box<dyn Error>> {
+    let mut tools = vec![];
+    collect_tools(file, &mut tools)?;
+
+    let mut components: Vec<Component> = vec![];
+    for path in tools {
+        let tool = load_tool(&path)?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        for mut component in get_tool_components(&tool, &path)? {
+            match components.iter_mut().find(|c| c.purl == component.purl) {
+                Some(existing) if !existing.used_by.contains(&name) => existing.used_by.push(name.clone()),
+                Some(_) => {}
+                None => {
+                    component.used_by.push(name.clone());
+                    components.push(component);
+                }
+            }
+        }
+    }
+    components.sort_by(|a, b| a.purl.cmp(&b.purl));
+    Ok(components)
+}
+
+/// Paths of all tools run by a workflow including those of nested workflows, a tool is returned as is
+fn collect_tools(file: &Path, tools: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let Ok(workflow) = load_workflow(file) else {
+        if !tools.iter().any(|t| t == file) {
+            tools.push(file.to_path_buf());
+        }
+        return Ok(());
+    };
+    let dir = file.parent().unwrap_or(Path::new("."));
+    for step in &workflow.steps {
+        collect_tools(&dir.join(&step.run), tools)?;
+    }
+    Ok(())
+}
+
+fn get_tool_components(tool: &CommandLineTool, path: &Path) -> Result<Vec<Component>, Box<dyn Error>> {
+    let tool_dir = path.parent().unwrap_or(Path::new("."));
+    let mut components = vec![];
+    for requirement in tool.requirements.iter().chain(tool.hints.iter()).flatten() {
+        match requirement {
+            Requirement::DockerRequirement(DockerRequirement::DockerPull(image))
+            | Requirement::DockerRequirement(DockerRequirement::DockerFile { docker_image_id: image, .. }) => {
+                components.push(get_image_component(image));
+            }
+            Requirement::SoftwareRequirement(software) => components.extend(software.packages.iter().map(get_package_component)),
+            Requirement::CondaEnvironment(conda) => {
+                let file = tool_dir.join(&conda.env_file);
+                let environment = fs::read_to_string(&file).map_err(|e| format!("❌ Could not read conda environment {:?}: {}", file, e))?;
+                components.extend(get_software_packages(&environment)?.iter().map(get_package_component));
+            }
+            _ => {}
+        }
+    }
+
+    //scripts are staged using the InitialWorkDirRequirement and called relative to the working directory
+    let listing = tool
+        .requirements
+        .iter()
+        .flatten()
+        .filter_map(|r| match r {
+            Requirement::InitialWorkDirRequirement(iwdr) => Some(&iwdr.listing),
+            _ => None,
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    let command = match &tool.base_command {
+        Command::Single(command) => vec![command.clone()],
+        Command::Multiple(command) => command.clone(),
+    };
+    let command = command
+        .iter()
+        .map(|arg| {
+            let source = listing.iter().find(|l| l.entryname == *arg).and_then(|l| match &l.entry {
+                Entry::Include(include) => Some(tool_dir.join(&include.include)),
+                Entry::Source(_) => None,
+            });
+            source.unwrap_or_else(|| tool_dir.join(arg)).to_string_lossy().into_owned()
+        })
+        .collect::<Vec<_>>();
+    if let Some(dependencies) = detect_dependencies(&command) {
+        components.extend(dependencies.packages.iter().map(|requirement| {
+            let (name, version) = match requirement.split_once("==") {
+                Some((name, version)) => (name.trim(), Some(version.trim().to_string())),
+                //other specifiers do not pin a single version
+                None => (requirement.split(['<', '>', '=', '!', '~', ';', '[']).next().unwrap_or_default().trim(), None),
+            };
+            let type_ = match dependencies.language {
+                ScriptLanguage::Python => "pypi",
+                ScriptLanguage::R => "cran",
+            };
+            library(name, version, type_, None)
+        }));
+    }
+    Ok(components)
+}
+
+fn get_image_component(image: &str) -> Component {
+    let (image_name, version) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest.to_string())),
+        None => match image.rsplit_once(':').filter(|(_, tag)| !tag.contains('/')) {
+            Some((name, tag)) => (name, Some(tag.to_string())),
+            None => (image, None),
+        },
+    };
+    //the first part of the name is a registry if it looks like a host
+    let (registry, name) = match image_name.split_once('/') {
+        Some((host, name)) if host.contains('.') || host.contains(':') || host == "localhost" => (Some(host), name),
+        _ => (None, image_name),
+    };
+    let mut purl = format!("pkg:docker/{name}");
+    if let Some(version) = &version {
+        purl.push_str(&format!("@{}", version.replace(':', "%3A")));
+    }
+    if let Some(registry) = registry {
+        purl.push_str(&format!("?repository_url={registry}"));
+    }
+    Component {
+        name: image_name.to_string(),
+        version,
+        kind: ComponentKind::Container,
+        purl,
+        digest: get_image_digest(image),
+        used_by: vec![],
+    }
+}
+
+/// Creates the component of a package of a `SoftwareRequirement`, its type is derived from the registry it links to
+fn get_package_component(package: &SoftwarePackage) -> Component {
+    let version = package.version.as_ref().and_then(|v| v.first()).cloned();
+    let specs = package.specs.iter().flatten().collect::<Vec<_>>();
+    if specs.iter().any(|s| s.starts_with("https://pypi.org/")) {
+        return library(&package.package, version, "pypi", None);
+    }
+    if let Some(channel) = specs
+        .iter()
+        .find_map(|s| s.strip_prefix("https://anaconda.org/"))
+        .and_then(|s| s.split('/').next())
+    {
+        return library(&package.package, version, "conda", Some(format!("channel={channel}")));
+    }
+    if specs.iter().any(|s| s.contains("cran.r-project.org")) {
+        return library(&package.package, version, "cran", None);
+    }
+    library(&package.package, version, "generic", None)
+}
+
+fn library(name: &str, version: Option<String>, type_: &str, qualifiers: Option<String>) -> Component {
+    //PyPI names are case insensitive and normalized to lowercase in package URLs
+    let purl_name = if type_ == "pypi" { name.to_lowercase().replace('_', "-") } else { name.to_string() };
+    let mut purl = format!("pkg:{type_}/{purl_name}");
+    if let Some(version) = &version {
+        purl.push_str(&format!("@{version}"));
+    }
+    if let Some(qualifiers) = qualifiers {
+        purl.push_str(&format!("?{qualifiers}"));
+    }
+    Component {
+        name: name.to_string(),
+        version,
+        kind: ComponentKind::Library,
+        purl,
+        digest: None,
+        used_by: vec![],
+    }
+}
+
+/// Creates a [CycloneDX 1.5](https://cyclonedx.org/docs/1.5/json/) SBOM of the given workflow
+pub fn to_cyclonedx(name: &str, components: &[Component]) -> Value {
+    let entries = components
+        .iter()
+        .map(|component| {
+            let mut entry = json!({
+                "type": match component.kind {
+                    ComponentKind::Container => "container",
+                    ComponentKind::Library => "library",
+                },
+                "bom-ref": component.purl,
+                "name": component.name,
+                "purl": component.purl,
+                "properties": component.used_by.iter().map(|tool| json!({ "name": "s4n:usedBy", "value": tool })).collect::<Vec<_>>(),
+            });
+            if let Some(version) = &component.version {
+                entry["version"] = json!(version);
+            }
+            if let Some(digest) = component.digest.as_ref().and_then(|d| d.strip_prefix("sha256:")) {
+                entry["hashes"] = json!([{ "alg": "SHA-256", "content": digest }]);
+            }
+            entry
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", new_uuid()),
+        "version": 1,
+        "metadata": {
+            "timestamp": Utc::now().to_rfc3339(),
+            "tools": {
+                "components": [{ "type": "application", "name": "s4n", "version": env!("CARGO_PKG_VERSION") }]
+            },
+            "component": { "type": "application", "bom-ref": name, "name": name }
+        },
+        "components": entries,
+        "dependencies": [{ "ref": name, "dependsOn": components.iter().map(|c| &c.purl).collect::<Vec<_>>() }]
+    })
+}
+
+/// Creates an [SPDX 2.3](https://spdx.github.io/spdx-spec/v2.3/) SBOM of the given workflow
+pub fn to_spdx(name: &str, components: &[Component]) -> Value {
+    let root = "SPDXRef-Workflow";
+    let mut packages = vec![json!({
+        "SPDXID": root,
+        "name": name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "primaryPackagePurpose": "APPLICATION",
+    })];
+    let mut relationships = vec![json!({ "spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": root })];
+    for (i, component) in components.iter().enumerate() {
+        let id = format!("SPDXRef-Package-{}", i + 1);
+        let mut package = json!({
+            "SPDXID": id,
+            "name": component.name,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": match component.kind {
+                ComponentKind::Container => "CONTAINER",
+                ComponentKind::Library => "LIBRARY",
+            },
+            "externalRefs": [{ "referenceCategory": "PACKAGE-MANAGER", "referenceType": "purl", "referenceLocator": component.purl }],
+            "comment": format!("Used by {}", component.used_by.join(", ")),
+        });
+        if let Some(version) = &component.version {
+            package["versionInfo"] = json!(version);
+        }
+        if let Some(digest) = component.digest.as_ref().and_then(|d| d.strip_prefix("sha256:")) {
+            package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": digest }]);
+        }
+        packages.push(package);
+        relationships.push(json!({ "spdxElementId": root, "relationshipType": "DEPENDS_ON", "relatedSpdxElement": id }));
+    }
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{name}-{}", new_uuid()),
+        "creationInfo": {
+            "created": Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "creators": [format!("Tool: s4n-{}", env!("CARGO_PKG_VERSION"))]
+        },
+        "packages": packages,
+        "relationships": relationships
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_get_image_component() {
+        let component = get_image_component("python:3.12-slim");
+        assert_eq!(component.name, "python");
+        assert_eq!(component.version, Some("3.12-slim".to_string()));
+        assert_eq!(component.purl, "pkg:docker/python@3.12-slim");
+        assert_eq!(
+            get_image_component("ghcr.io/fairagro/tool:1.0").purl,
+            "pkg:docker/fairagro/tool@1.0?repository_url=ghcr.io"
+        );
+        assert_eq!(get_image_component("rocker/r-ver@sha256:abc").purl, "pkg:docker/rocker/r-ver@sha256%3Aabc");
+        assert_eq!(get_image_component("localhost:5000/tool").purl, "pkg:docker/tool?repository_url=localhost:5000");
+    }
+
+    #[test]
+    fn test_get_package_component() {
+        let package = |name: &str, version: Option<&str>, spec: Option<&str>| SoftwarePackage {
+            package: name.to_string(),
+            version: version.map(|v| vec![v.to_string()]),
+            specs: spec.map(|s| vec![s.to_string()]),
+        };
+        assert_eq!(
+            get_package_component(&package("Scikit_Learn", Some("1.5.0"), Some("https://pypi.org/project/scikit-learn"))).purl,
+            "pkg:pypi/scikit-learn@1.5.0"
+        );
+        assert_eq!(
+            get_package_component(&package("samtools", Some("1.21"), Some("https://anaconda.org/bioconda/samtools"))).purl,
+            "pkg:conda/samtools@1.21?channel=bioconda"
+        );
+        assert_eq!(get_package_component(&package("zip", None, None)).purl, "pkg:generic/zip");
+    }
+
+    #[test]
+    fn test_collect_components() {
+        let components = collect_components(Path::new("tests/test_data/hello_world/workflows/main/main.cwl")).unwrap();
+        let purls = components.iter().map(|c| c.purl.as_str()).collect::<Vec<_>>();
+        assert_eq!(purls, vec!["pkg:pypi/matplotlib", "pkg:pypi/pandas", "pkg:pypi/scienceplots"]);
+        let pandas = &components[1];
+        assert_eq!(pandas.used_by, vec!["calculation", "plot"]);
+
+        let dir = tempdir().unwrap();
+        let tool = dir.path().join("tool.cwl");
+        fs::write(
+            &tool,
+            r"cwlVersion: v1.2
+class: CommandLineTool
+requirements:
+- class: DockerRequirement
+  dockerPull: python:3.12-slim
+- class: SoftwareRequirement
+  packages:
+  - package: numpy
+    version: ['1.26.4']
+    specs: ['https://pypi.org/project/numpy']
+inputs: []
+outputs: []
+baseCommand: [python, --version]
+",
+        )
+        .unwrap();
+        let components = collect_components(&tool).unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].kind, ComponentKind::Container);
+        assert_eq!(components[1].purl, "pkg:pypi/numpy@1.26.4");
+
+        let cyclonedx = to_cyclonedx("tool", &components);
+        assert_eq!(cyclonedx["components"][0]["type"], "container");
+        assert_eq!(cyclonedx["dependencies"][0]["dependsOn"][1], "pkg:pypi/numpy@1.26.4");
+        let spdx = to_spdx("tool", &components);
+        assert_eq!(spdx["packages"].as_array().unwrap().len(), 3);
+        assert_eq!(spdx["packages"][2]["externalRefs"][0]["referenceLocator"], "pkg:pypi/numpy@1.26.4");
+        assert_eq!(spdx["relationships"][2]["relationshipType"], "DEPENDS_ON");
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{
+    execution::runs::{get_run_key, load_runs, RunDatabase},
+    io::get_workflows_folder,
+};
+use cwl::load_workflow;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+use walkdir::WalkDir;
+
+/// Input files looked up next to a workflow when running it
+const INPUT_FILES: [&str; 3] = ["inputs.yml", "inputs.yaml", "job.yml"];
+/// Number of log lines kept for the log panel
+const MAX_LOG_LINES: usize = 1000;
+/// Prefix of the log line the runner writes when starting a step
+const STEP_START: &str = "Running step ";
+
+/// Opens the terminal user interface to browse the workflows of the project in the current directory, run them and
+/// follow their progress. It works over SSH sessions where no graphical interface is available.
+pub fn run_tui() -> Result<(), Box<dyn Error>> {
+    let mut app = App::new(env::current_dir()?)?;
+    if app.workflows.is_empty() {
+        return Err(format!("❌ No workflows found in {}", get_workflows_folder()).into());
+    }
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StepState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+struct WorkflowEntry {
+    name: String,
+    path: PathBuf,
+    steps: Vec<String>,
+}
+
+/// A workflow executed by a child process of s4n, its output is received line by line
+struct Execution {
+    workflow: usize,
+    child: Child,
+    lines: Receiver<String>,
+}
+
+struct App {
+    root: PathBuf,
+    workflows: Vec<WorkflowEntry>,
+    list: ListState,
+    runs: RunDatabase,
+    execution: Option<Execution>,
+    /// Steps of the workflow run from this session, which are shown instead of the last recorded run
+    step_states: HashMap<String, StepState>,
+    states_of: Option<usize>,
+    log: Vec<String>,
+}
+
+impl App {
+    fn new(root: PathBuf) -> Result<Self, Box<dyn Error>> {
+        let mut workflows = WalkDir::new(root.join(get_workflows_folder()))
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "cwl"))
+            .filter_map(|path| {
+                let workflow = load_workflow(&path).ok()?;
+                Some(WorkflowEntry {
+                    name: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+                    steps: workflow.steps.iter().map(|s| s.id.clone()).collect(),
+                    path,
+                })
+            })
+            .collect::<Vec<_>>();
+        workflows.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(Self {
+            runs: load_runs(&root)?,
+            root,
+            workflows,
+            list: ListState::default().with_selected(Some(0)),
+            execution: None,
+            step_states: HashMap::new(),
+            states_of: None,
+            log: vec![],
+        })
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
+        loop {
+            self.update()?;
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.cancel();
+                    return Ok(());
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                KeyCode::Enter | KeyCode::Char('r') => self.start()?,
+                KeyCode::Char('c') => self.cancel(),
+                _ => {}
+            }
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.list.selected().unwrap_or_default().min(self.workflows.len().saturating_sub(1))
+    }
+
+    /// Runs the selected workflow using the internal runner, with the first input file found next to it
+    fn start(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.execution.is_some() {
+            self.push_log("⚠️  Another workflow is still running, press c to cancel it".to_string());
+            return Ok(());
+        }
+        let index = self.selected();
+        let workflow = &self.workflows[index];
+        let file = workflow.path.strip_prefix(&self.root).unwrap_or(&workflow.path).to_path_buf();
+        let folder = workflow.path.parent().unwrap_or(Path::new("."));
+        let input_file = INPUT_FILES
+            .iter()
+            .map(|name| folder.join(name))
+            .find(|path| path.exists())
+            .map(|path| path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf());
+
+        let mut command = Command::new(env::current_exe()?);
+        command.args(["execute", "local"]).arg(&file).args(&input_file);
+        command.current_dir(&self.root).env("NO_COLOR", "1");
+        command.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null());
+        let mut child = command.spawn()?;
+
+        let (sender, receiver) = mpsc::channel();
+        forward_lines(child.stdout.take(), sender.clone());
+        forward_lines(child.stderr.take(), sender);
+
+        self.step_states = workflow.steps.iter().map(|s| (s.clone(), StepState::Pending)).collect();
+        self.log.clear();
+        let inputs = input_file.map(|f| format!(" {}", f.display())).unwrap_or_default();
+        self.push_log(format!("$ s4n execute local {}{}", file.display(), inputs));
+        self.states_of = Some(index);
+        self.execution = Some(Execution {
+            workflow: index,
+            child,
+            lines: receiver,
+        });
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
+        if let Some(mut execution) = self.execution.take() {
+            let _ = execution.child.kill();
+            let _ = execution.child.wait();
+            self.set_running_steps(StepState::Failed);
+            self.push_log("🛑 Run cancelled".to_string());
+        }
+    }
+
+    /// Collects new output of the running workflow and finishes the run once the process exited
+    fn update(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(execution) = &mut self.execution else {
+            return Ok(());
+        };
+        let lines = execution.lines.try_iter().collect::<Vec<_>>();
+        let status = execution.child.try_wait()?;
+        lines.into_iter().for_each(|line| self.handle_line(&line));
+
+        if let Some(status) = status {
+            //the process exited, so the remaining output is read until both streams are closed
+            let execution = self.execution.take().unwrap();
+            execution.lines.iter().for_each(|line| self.handle_line(&line));
+            if status.success() {
+                self.set_running_steps(StepState::Done);
+                self.push_log("✔️  Run finished successfully".to_string());
+            } else {
+                self.set_running_steps(StepState::Failed);
+                self.push_log(format!("❌ Run failed with {status}"));
+            }
+            self.runs = load_runs(&self.root)?;
+        }
+        Ok(())
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        if let Some(index) = line.find(STEP_START) {
+            let step = line[index + STEP_START.len()..].trim().to_string();
+            //nested workflows report their own steps, only those of the selected workflow are tracked
+            if self.step_states.contains_key(&step) {
+                self.set_running_steps(StepState::Done);
+                self.step_states.insert(step, StepState::Running);
+            }
+        }
+        self.push_log(line.to_string());
+    }
+
+    fn set_running_steps(&mut self, state: StepState) {
+        for value in self.step_states.values_mut().filter(|s| **s == StepState::Running) {
+            *value = state;
+        }
+    }
+
+    fn push_log(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [left, right] = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(frame.area());
+        let selected = self.selected();
+        let steps = self.workflows[selected].steps.len() as u16;
+        let [steps_area, log_area] = Layout::vertical([Constraint::Length(steps.min(12) + 2), Constraint::Min(3)]).areas(right);
+
+        let items = self
+            .workflows
+            .iter()
+            .enumerate()
+            .map(|(i, workflow)| ListItem::new(format!("{} {}", self.get_workflow_marker(i), workflow.name)))
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::bordered().title(" Workflows ").title_bottom(" ↑↓ select · r run · c cancel · q quit "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let steps = Paragraph::new(self.get_step_lines(selected)).block(Block::bordered().title(" Steps "));
+        frame.render_widget(steps, steps_area);
+
+        let visible = log_area.height.saturating_sub(2) as usize;
+        let log = self.log.iter().skip(self.log.len().saturating_sub(visible)).map(|l| Line::raw(l.as_str())).collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(log).block(Block::bordered().title(" Log ")), log_area);
+    }
+
+    fn get_workflow_marker(&self, index: usize) -> &'static str {
+        if self.execution.as_ref().is_some_and(|e| e.workflow == index) {
+            return "⏳";
+        }
+        let workflow = &self.workflows[index];
+        match self.runs.get(&get_run_key(&workflow.path, &self.root)) {
+            None => "· ",
+            Some(run) if !run.success => "❌",
+            Some(run) if run.is_stale(&workflow.path) => "⚠️ ",
+            Some(_) => "✅",
+        }
+    }
+
+    /// States of the workflow run from this session or the last recorded run of the selected one
+    fn get_step_lines(&self, index: usize) -> Vec<Line<'_>> {
+        let workflow = &self.workflows[index];
+        let last_run = self.runs.get(&get_run_key(&workflow.path, &self.root));
+        workflow
+            .steps
+            .iter()
+            .map(|step| {
+                let (state, color) = if self.states_of == Some(index) {
+                    match self.step_states.get(step).copied().unwrap_or(StepState::Pending) {
+                        StepState::Pending => ("pending".to_string(), Color::Gray),
+                        StepState::Running => ("running".to_string(), Color::Yellow),
+                        StepState::Done => ("done".to_string(), Color::Green),
+                        StepState::Failed => ("failed".to_string(), Color::Red),
+                    }
+                } else {
+                    match last_run.and_then(|run| run.steps.get(step)) {
+                        Some(step_run) if step_run.is_stale() => (format!("stale, last run {}", step_run.finished), Color::Yellow),
+                        Some(step_run) => (format!("last run {}", step_run.finished), Color::Green),
+                        None => ("never run".to_string(), Color::Gray),
+                    }
+                };
+                Line::from(vec![Span::raw(format!("{step:<24} ")), Span::styled(state, Style::default().fg(color))])
+            })
+            .collect()
+    }
+}
+
+fn forward_lines<R: Read + Send + 'static>(stream: Option<R>, sender: Sender<String>) {
+    if let Some(stream) = stream {
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::copy_dir;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tempfile::tempdir;
+
+    fn setup() -> (tempfile::TempDir, App) {
+        let dir = tempdir().unwrap();
+        copy_dir("tests/test_data", dir.path().join("workflows/test_data")).unwrap();
+        let app = App::new(dir.path().to_path_buf()).unwrap();
+        (dir, app)
+    }
+
+    #[test]
+    fn test_handle_line() {
+        let (_dir, mut app) = setup();
+        app.step_states = HashMap::from([("calc".to_string(), StepState::Pending), ("plot".to_string(), StepState::Pending)]);
+        app.handle_line("▶️  Running step calc");
+        assert_eq!(app.step_states["calc"], StepState::Running);
+        app.handle_line("▶️  Running step plot");
+        assert_eq!(app.step_states["calc"], StepState::Done);
+        assert_eq!(app.step_states["plot"], StepState::Running);
+        //steps of nested workflows are not tracked
+        app.handle_line("▶️  Running step echo");
+        assert_eq!(app.step_states["plot"], StepState::Running);
+        assert_eq!(app.log.len(), 3);
+    }
+
+    #[test]
+    fn test_draw() {
+        let (_dir, mut app) = setup();
+        assert!(app.workflows.iter().any(|w| w.name == "scatter_wf"));
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect::<String>();
+        assert!(screen.contains("Workflows"));
+        assert!(screen.contains("never run"));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::job::{find_connected, get_entries, load_document};
+use serde_yaml::Value;
+use std::{collections::HashMap, error::Error, fmt::Display, path::Path};
+
+/// IRI prefix of terms of the Units of Measurement Ontology (UO)
+pub const UO_PREFIX: &str = "http://purl.obolibrary.org/obo/UO_";
+
+/// Unit and allowed range of a numeric input, annotated as `s:unitCode`, `s:unitText`, `s:minValue` and `s:maxValue`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Quantity {
+    /// IRI of the unit, usually a term of the UO ontology
+    pub unit_code: Option<String>,
+    /// Human readable name of the unit
+    pub unit_text: Option<String>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+}
+
+impl Quantity {
+    /// Reads the annotations of an input, `None` if it has none
+    pub fn from_input(input: &Value) -> Option<Self> {
+        let text = |key: &str| input.get(key).and_then(Value::as_str).map(str::to_string);
+        let quantity = Quantity {
+            unit_code: text("s:unitCode"),
+            unit_text: text("s:unitText"),
+            minimum: input.get("s:minValue").and_then(Value::as_f64),
+            maximum: input.get("s:maxValue").and_then(Value::as_f64),
+        };
+        (quantity != Quantity::default()).then_some(quantity)
+    }
+
+    /// Name of the unit, the CURIE of the unit code is used if no text is annotated, e.g. `UO:0000027`
+    pub fn unit(&self) -> Option<String> {
+        self.unit_text.clone().or_else(|| self.unit_code.as_deref().map(to_curie))
+    }
+
+    /// Allowed range like `0 to 40`, `>= 0` or `<= 40`
+    pub fn range(&self) -> Option<String> {
+        match (self.minimum, self.maximum) {
+            (Some(min), Some(max)) => Some(format!("{min} to {max}")),
+            (Some(min), None) => Some(format!(">= {min}")),
+            (None, Some(max)) => Some(format!("<= {max}")),
+            (None, None) => None,
+        }
+    }
+
+    /// Checks a value or all items of an array against the range, values which are not numbers are ignored
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        if let Value::Sequence(items) = value {
+            return items.iter().try_for_each(|item| self.check(item));
+        }
+        let Some(number) = value.as_f64() else {
+            return Ok(());
+        };
+        let unit = self.unit().map(|unit| format!(" {unit}")).unwrap_or_default();
+        match (self.minimum, self.maximum) {
+            (Some(min), _) if number < min => Err(format!("{number}{unit} is less than the minimum of {min}{unit}")),
+            (_, Some(max)) if number > max => Err(format!("{number}{unit} is greater than the maximum of {max}{unit}")),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = [self.range(), self.unit()].into_iter().flatten().collect::<Vec<_>>();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Turns a UO term given as CURIE like `UO:0000027` or `UO_0000027` into its IRI, IRIs are returned unchanged
+pub fn to_unit_iri(unit: &str) -> Option<String> {
+    if unit.starts_with("http://") || unit.starts_with("https://") {
+        return Some(unit.to_string());
+    }
+    let number = unit.strip_prefix("UO:").or_else(|| unit.strip_prefix("UO_"))?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then(|| format!("{UO_PREFIX}{number}"))
+}
+
+/// Shortens IRIs of UO terms to CURIEs like `UO:0000027`, other IRIs are returned unchanged
+pub fn to_curie(iri: &str) -> String {
+    match iri.strip_prefix(UO_PREFIX) {
+        Some(number) => format!("UO:{number}"),
+        None => iri.to_string(),
+    }
+}
+
+/// Collects the units and ranges of the inputs of a workflow or tool. Workflow inputs without annotations use the ones
+/// of the tool inputs they are connected to.
+pub fn get_quantities(path: &Path) -> Result<Vec<(String, Quantity)>, Box<dyn Error>> {
+    let document = load_document(path)?;
+    let folder = path.parent().unwrap_or(Path::new("."));
+    Ok(get_input_quantities(&document, folder))
+}
+
+pub fn get_input_quantities(document: &Value, folder: &Path) -> Vec<(String, Quantity)> {
+    get_entries(document.get("inputs"))
+        .into_iter()
+        .filter_map(|(id, input)| {
+            let quantity = Quantity::from_input(&input).or_else(|| find_connected(document, folder, &id, &|value, _| Quantity::from_input(value)))?;
+            Some((id, quantity))
+        })
+        .collect()
+}
+
+/// Checks the values of a job against the ranges of the inputs of a workflow or tool document located in `folder`,
+/// inputs missing in the job are checked using their default. All values out of range are reported at once.
+pub fn check_job_ranges(document: &Value, folder: &Path, job: &HashMap<String, Value>) -> Result<(), Box<dyn Error>> {
+    let inputs = get_entries(document.get("inputs")).into_iter().collect::<HashMap<_, _>>();
+
+    let mut errors = vec![];
+    for (id, quantity) in get_input_quantities(document, folder) {
+        let value = job.get(&id).or_else(|| inputs.get(&id).and_then(|input| input.get("default")));
+        if let Some(Err(e)) = value.map(|value| quantity.check(value)) {
+            errors.push(format!("  - {id}: {e}"));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("❌ Inputs are out of their allowed range:\n{}", errors.join("\n")).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_quantity() {
+        let input: Value =
+            serde_yaml::from_str("{type: float, s:unitCode: 'http://purl.obolibrary.org/obo/UO_0000027', s:minValue: 0, s:maxValue: 40.5}").unwrap();
+        let quantity = Quantity::from_input(&input).unwrap();
+        assert_eq!(quantity.to_string(), "0 to 40.5 UO:0000027");
+        assert!(quantity.check(&Value::from(12)).is_ok());
+        assert_eq!(
+            quantity.check(&Value::from(-1)).unwrap_err(),
+            "-1 UO:0000027 is less than the minimum of 0 UO:0000027"
+        );
+        assert!(quantity.check(&serde_yaml::from_str("[1, 41]").unwrap()).is_err());
+        assert!(quantity.check(&Value::from("text")).is_ok());
+        assert!(Quantity::from_input(&serde_yaml::from_str("{type: int}").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_to_unit_iri() {
+        assert_eq!(to_unit_iri("UO:0000027").unwrap(), "http://purl.obolibrary.org/obo/UO_0000027");
+        assert_eq!(to_unit_iri("UO_0000027").unwrap(), "http://purl.obolibrary.org/obo/UO_0000027");
+        assert_eq!(
+            to_unit_iri("http://qudt.org/vocab/unit/DEG_C").unwrap(),
+            "http://qudt.org/vocab/unit/DEG_C"
+        );
+        assert!(to_unit_iri("degree Celsius").is_none());
+    }
+
+    #[test]
+    fn test_check_job_ranges() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("tool.cwl"),
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\noutputs: []\ninputs:\n  temperature:\n    type: float\n    default: 20\n    s:unitText: degree Celsius\n    s:maxValue: 40\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("main.cwl"),
+            "class: Workflow\ncwlVersion: v1.2\noutputs: []\ninputs:\n  temp: float\nsteps:\n  tool:\n    run: tool.cwl\n    in:\n      temperature: temp\n    out: []\n",
+        )
+        .unwrap();
+
+        let tool = load_document(&dir.path().join("tool.cwl")).unwrap();
+        assert!(check_job_ranges(&tool, dir.path(), &HashMap::new()).is_ok());
+        let job = HashMap::from([("temperature".to_string(), Value::from(55))]);
+        let error = check_job_ranges(&tool, dir.path(), &job).unwrap_err().to_string();
+        assert!(error.contains("temperature: 55 degree Celsius is greater than the maximum of 40 degree Celsius"));
+
+        let quantities = get_quantities(&dir.path().join("main.cwl")).unwrap();
+        assert_eq!(quantities[0].0, "temp");
+        assert_eq!(quantities[0].1.unit_text.as_deref(), Some("degree Celsius"));
+        let job = HashMap::from([("temp".to_string(), Value::from(55))]);
+        let workflow = load_document(&dir.path().join("main.cwl")).unwrap();
+        assert!(check_job_ranges(&workflow, dir.path(), &job).is_err());
+    }
+}
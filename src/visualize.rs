@@ -0,0 +1,589 @@
+use clap::ValueEnum;
+use cwl::{inputs::WorkflowStepInput, load_tool, load_workflow, wf::{Source, Workflow}};
+use serde_yaml::Value;
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Renderer {
+    /// Mermaid flowchart which can be embedded into Markdown
+    #[default]
+    Mermaid,
+    /// Graphviz DOT
+    Dot,
+    /// Scalable Vector Graphics image, nested workflows are shown as single steps
+    Svg,
+}
+
+/// Renders the workflow at the given path as graph. Steps running nested workflows are rendered as subgraphs.
+pub fn render_workflow<P: AsRef<Path>>(path: P, renderer: Renderer) -> Result<String, Box<dyn Error>> {
+    let path = path.as_ref();
+    let workflow = load_workflow(path)?;
+    let graph = WorkflowGraph::build(&workflow, path.parent().unwrap_or(Path::new(".")), "")?;
+    Ok(match renderer {
+        Renderer::Mermaid => graph.to_mermaid(),
+        Renderer::Dot => graph.to_dot(),
+        Renderer::Svg => graph.to_svg(),
+    })
+}
+
+/// Both ends of a connection, either a node itself or a port of a step node
+#[derive(Debug, Clone, PartialEq)]
+enum Endpoint {
+    Node(String),
+    Port(String, String),
+}
+
+#[derive(Debug)]
+struct StepNode {
+    id: String,
+    label: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    subworkflow: Option<WorkflowGraph>,
+    group: Option<String>,
+}
+
+#[derive(Debug)]
+struct WorkflowGraph {
+    prefix: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    steps: Vec<StepNode>,
+    edges: Vec<(Endpoint, Endpoint)>,
+}
+
+impl WorkflowGraph {
+    fn build(workflow: &Workflow, folder: &Path, prefix: &str) -> Result<Self, Box<dyn Error>> {
+        let mut steps = vec![];
+        for step in &workflow.steps {
+            let path = folder.join(&step.run);
+            let step_prefix = format!("{prefix}{}_", sanitize(&step.id));
+            let node = if is_workflow(&path) {
+                let subworkflow = load_workflow(&path)?;
+                StepNode {
+                    id: step.id.clone(),
+                    label: step.id.clone(),
+                    inputs: subworkflow.inputs.iter().map(|i| i.id.clone()).collect(),
+                    outputs: subworkflow.outputs.iter().map(|o| o.id.clone()).collect(),
+                    subworkflow: Some(Self::build(&subworkflow, path.parent().unwrap_or(Path::new(".")), &step_prefix)?),
+                    group: step.group.clone(),
+                }
+            } else {
+                let tool = load_tool(&path)?;
+                StepNode {
+                    id: step.id.clone(),
+                    label: step.id.clone(),
+                    inputs: tool.inputs.iter().map(|i| i.id.clone()).collect(),
+                    outputs: tool.outputs.iter().map(|o| o.id.clone()).collect(),
+                    subworkflow: None,
+                    group: step.group.clone(),
+                }
+            };
+            steps.push(node);
+        }
+
+        let mut graph = Self {
+            prefix: prefix.to_string(),
+            inputs: workflow.inputs.iter().map(|i| i.id.clone()).collect(),
+            outputs: workflow.outputs.iter().map(|o| o.id.clone()).collect(),
+            steps,
+            edges: vec![],
+        };
+
+        let mut edges = vec![];
+        for step in &workflow.steps {
+            //sorted to get a stable output
+            let mut inputs = step.in_.iter().collect::<Vec<_>>();
+            inputs.sort_by_key(|(id, _)| *id);
+            for (id, input) in inputs {
+                let sources = match input {
+                    WorkflowStepInput::String(source) => vec![source.as_str()],
+                    WorkflowStepInput::Parameter(parameter) => parameter.source.as_ref().map(Source::ids).unwrap_or_default(),
+                };
+                for from in sources.into_iter().filter_map(|s| graph.source_endpoint(s)) {
+                    edges.push((from, graph.step_input_endpoint(&step.id, id)));
+                }
+            }
+        }
+        for output in &workflow.outputs {
+            for from in output.output_source.ids().into_iter().filter_map(|s| graph.source_endpoint(s)) {
+                edges.push((from, Endpoint::Node(graph.output_id(&output.id))));
+            }
+        }
+        graph.edges = edges;
+        Ok(graph)
+    }
+
+    fn input_id(&self, id: &str) -> String {
+        format!("{}in_{}", self.prefix, sanitize(id))
+    }
+
+    fn output_id(&self, id: &str) -> String {
+        format!("{}out_{}", self.prefix, sanitize(id))
+    }
+
+    fn step_id(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, sanitize(id))
+    }
+
+    fn group_id(&self, group: &str) -> String {
+        format!("{}group_{}", self.prefix, sanitize(group))
+    }
+
+    /// Steps in their order, steps of a group are collected at the position of the group's first step
+    fn grouped_steps(&self) -> Vec<(Option<&str>, Vec<&StepNode>)> {
+        let mut groups: Vec<(Option<&str>, Vec<&StepNode>)> = vec![];
+        for step in &self.steps {
+            match step.group.as_deref() {
+                Some(group) => match groups.iter_mut().find(|(g, _)| *g == Some(group)) {
+                    Some((_, steps)) => steps.push(step),
+                    None => groups.push((Some(group), vec![step])),
+                },
+                None => groups.push((None, vec![step])),
+            }
+        }
+        groups
+    }
+
+    /// Resolves a workflow input or step output reference, outputs of nested workflows are connected to their output nodes
+    fn source_endpoint(&self, source: &str) -> Option<Endpoint> {
+        match source.split_once('/') {
+            Some((step_id, output)) => {
+                let step = self.steps.iter().find(|s| s.id == step_id)?;
+                Some(match &step.subworkflow {
+                    Some(subworkflow) => Endpoint::Node(subworkflow.output_id(output)),
+                    None => Endpoint::Port(self.step_id(step_id), output.to_string()),
+                })
+            }
+            None => Some(Endpoint::Node(self.input_id(source))),
+        }
+    }
+
+    fn step_input_endpoint(&self, step_id: &str, input: &str) -> Endpoint {
+        match self.steps.iter().find(|s| s.id == step_id).and_then(|s| s.subworkflow.as_ref()) {
+            Some(subworkflow) => Endpoint::Node(subworkflow.input_id(input)),
+            None => Endpoint::Port(self.step_id(step_id), input.to_string()),
+        }
+    }
+
+    fn to_mermaid(&self) -> String {
+        let mut lines = vec!["flowchart TB".to_string()];
+        self.write_mermaid(&mut lines, 1);
+        lines.join("\n") + "\n"
+    }
+
+    fn write_mermaid(&self, lines: &mut Vec<String>, depth: usize) {
+        let indent = "    ".repeat(depth);
+        for input in &self.inputs {
+            lines.push(format!("{indent}{}([{}])", self.input_id(input), input));
+        }
+        for (group, steps) in self.grouped_steps() {
+            match group {
+                Some(group) => {
+                    lines.push(format!("{indent}subgraph {}[\"{group}\"]", self.group_id(group)));
+                    for step in steps {
+                        self.write_mermaid_step(step, lines, depth + 1);
+                    }
+                    lines.push(format!("{indent}end"));
+                }
+                None => steps.into_iter().for_each(|step| self.write_mermaid_step(step, lines, depth)),
+            }
+        }
+        for output in &self.outputs {
+            lines.push(format!("{indent}{}([{}])", self.output_id(output), output));
+        }
+        for (from, to) in &self.edges {
+            //mermaid has no ports, their names are used as edge labels instead
+            let label = [from, to]
+                .iter()
+                .filter_map(|e| match e {
+                    Endpoint::Port(_, port) => Some(port.as_str()),
+                    Endpoint::Node(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" → ");
+            let (from, to) = (endpoint_node(from), endpoint_node(to));
+            if label.is_empty() {
+                lines.push(format!("{indent}{from} --> {to}"));
+            } else {
+                lines.push(format!("{indent}{from} -->|{label}| {to}"));
+            }
+        }
+    }
+
+    fn write_mermaid_step(&self, step: &StepNode, lines: &mut Vec<String>, depth: usize) {
+        let indent = "    ".repeat(depth);
+        match &step.subworkflow {
+            Some(subworkflow) => {
+                lines.push(format!("{indent}subgraph {}[\"{}\"]", self.step_id(&step.id), step.label));
+                subworkflow.write_mermaid(lines, depth + 1);
+                lines.push(format!("{indent}end"));
+            }
+            None => lines.push(format!("{indent}{}[\"{}\"]", self.step_id(&step.id), step.label)),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph workflow {".to_string(),
+            "    rankdir=TB;".to_string(),
+            "    node [fontname=\"Helvetica\"];".to_string(),
+        ];
+        self.write_dot(&mut lines, 1);
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn write_dot(&self, lines: &mut Vec<String>, depth: usize) {
+        let indent = "    ".repeat(depth);
+        for input in &self.inputs {
+            lines.push(format!(
+                "{indent}{} [label=\"{input}\", shape=ellipse, style=filled, fillcolor=\"#94DDF4\"];",
+                self.input_id(input)
+            ));
+        }
+        for (group, steps) in self.grouped_steps() {
+            match group {
+                Some(group) => {
+                    lines.push(format!("{indent}subgraph cluster_{} {{", self.group_id(group)));
+                    lines.push(format!("{indent}    label=\"{group}\";"));
+                    lines.push(format!("{indent}    style=dashed;"));
+                    for step in steps {
+                        self.write_dot_step(step, lines, depth + 1);
+                    }
+                    lines.push(format!("{indent}}}"));
+                }
+                None => steps.into_iter().for_each(|step| self.write_dot_step(step, lines, depth)),
+            }
+        }
+        for output in &self.outputs {
+            lines.push(format!(
+                "{indent}{} [label=\"{output}\", shape=ellipse, style=filled, fillcolor=\"#94DDA4\"];",
+                self.output_id(output)
+            ));
+        }
+        for (from, to) in &self.edges {
+            let from = match from {
+                Endpoint::Port(node, port) => format!("{node}:out_{}", sanitize(port)),
+                Endpoint::Node(node) => node.clone(),
+            };
+            let to = match to {
+                Endpoint::Port(node, port) => format!("{node}:in_{}", sanitize(port)),
+                Endpoint::Node(node) => node.clone(),
+            };
+            lines.push(format!("{indent}{from} -> {to};"));
+        }
+    }
+
+    fn write_dot_step(&self, step: &StepNode, lines: &mut Vec<String>, depth: usize) {
+        let indent = "    ".repeat(depth);
+        match &step.subworkflow {
+            Some(subworkflow) => {
+                lines.push(format!("{indent}subgraph cluster_{} {{", self.step_id(&step.id)));
+                lines.push(format!("{indent}    label=\"{}\";", step.label));
+                subworkflow.write_dot(lines, depth + 1);
+                lines.push(format!("{indent}}}"));
+            }
+            None => {
+                //record shape showing inputs on top and outputs at the bottom
+                let ports = |prefix: &str, ports: &[String]| {
+                    ports
+                        .iter()
+                        .map(|p| format!("<{prefix}_{}> {p}", sanitize(p)))
+                        .collect::<Vec<_>>()
+                        .join("|")
+                };
+                lines.push(format!(
+                    "{indent}{} [shape=record, label=\"{{{{{}}}|{}|{{{}}}}}\"];",
+                    self.step_id(&step.id),
+                    ports("in", &step.inputs),
+                    step.label,
+                    ports("out", &step.outputs)
+                ));
+            }
+        }
+    }
+
+    /// Lays out the graph top to bottom, every node is placed one row below the lowest node connected to it
+    fn to_svg(&self) -> String {
+        const HEIGHT: usize = 36;
+        const ROW: usize = 96;
+        const GAP: usize = 30;
+        const MARGIN: usize = 30;
+
+        //nodes of nested workflows are collapsed into the step running them
+        let mut collapsed = HashMap::new();
+        for step in &self.steps {
+            if let Some(subworkflow) = &step.subworkflow {
+                subworkflow.collect_nodes(&self.step_id(&step.id), &mut collapsed);
+            }
+        }
+        let node = |endpoint: &Endpoint| {
+            let id = endpoint_node(endpoint);
+            collapsed.get(id).cloned().unwrap_or_else(|| id.to_string())
+        };
+        let mut edges: Vec<(String, String)> = vec![];
+        for (from, to) in &self.edges {
+            let edge = (node(from), node(to));
+            if edge.0 != edge.1 && !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+
+        //id, label, fill color and corner radius
+        let mut nodes = vec![];
+        nodes.extend(self.inputs.iter().map(|i| (self.input_id(i), i.as_str(), "#94DDF4", HEIGHT / 2)));
+        //steps of a group are placed next to each other
+        let grouped_steps = self.grouped_steps();
+        nodes.extend(grouped_steps.iter().flat_map(|(_, steps)| steps).map(|s| {
+            let fill = if s.subworkflow.is_some() { "#EEEEEE" } else { "#FFFFFF" };
+            (self.step_id(&s.id), s.label.as_str(), fill, 4)
+        }));
+        nodes.extend(self.outputs.iter().map(|o| (self.output_id(o), o.as_str(), "#94DDA4", HEIGHT / 2)));
+
+        let mut ranks = nodes.iter().map(|(id, ..)| (id.as_str(), 0)).collect::<HashMap<_, _>>();
+        for _ in 0..nodes.len() {
+            for (from, to) in &edges {
+                let rank = ranks.get(from.as_str()).copied().unwrap_or_default() + 1;
+                if let Some(current) = ranks.get_mut(to.as_str()).filter(|current| **current < rank) {
+                    *current = rank;
+                }
+            }
+        }
+
+        let width = |label: &str| (label.chars().count() * 8 + 30).max(80);
+        let row_count = ranks.values().max().copied().unwrap_or_default() + 1;
+        let row_widths = (0..row_count)
+            .map(|row| {
+                let widths = nodes.iter().filter(|(id, ..)| ranks[id.as_str()] == row).map(|(_, label, ..)| width(label));
+                widths.clone().sum::<usize>() + GAP * widths.count().saturating_sub(1)
+            })
+            .collect::<Vec<_>>();
+        let total_width = row_widths.iter().max().copied().unwrap_or_default() + 2 * MARGIN;
+        let total_height = row_count * ROW - (ROW - HEIGHT) + 2 * MARGIN;
+
+        //center of each node's top edge
+        let mut positions = HashMap::new();
+        let mut offsets = row_widths.iter().map(|w| (total_width - w) / 2).collect::<Vec<_>>();
+        for (id, label, ..) in &nodes {
+            let row = ranks[id.as_str()];
+            positions.insert(id.as_str(), (offsets[row] + width(label) / 2, MARGIN + row * ROW));
+            offsets[row] += width(label) + GAP;
+        }
+
+        let mut lines = vec![
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{total_width}\" height=\"{total_height}\" viewBox=\"0 0 {total_width} {total_height}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"14\">"
+            ),
+            "  <defs>".to_string(),
+            "    <marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\">".to_string(),
+            "      <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#555555\"/>".to_string(),
+            "    </marker>".to_string(),
+            "  </defs>".to_string(),
+        ];
+        //groups are drawn as dashed boxes around their steps with the name on top
+        for (group, steps) in grouped_steps.iter().filter_map(|(group, steps)| Some((group.as_ref()?, steps))) {
+            let bounds = steps.iter().map(|step| {
+                let (x, y) = positions[self.step_id(&step.id).as_str()];
+                let half = width(&step.label) / 2;
+                (x - half, y, x + half, y + HEIGHT)
+            });
+            let (left, top, right, bottom) = bounds.fold((usize::MAX, usize::MAX, 0, 0), |(l, t, r, b), (x1, y1, x2, y2)| {
+                (l.min(x1), t.min(y1), r.max(x2), b.max(y2))
+            });
+            let (left, top) = (left - 10, top - 24);
+            lines.push(format!(
+                "  <rect x=\"{left}\" y=\"{top}\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"none\" stroke=\"#888888\" stroke-dasharray=\"6 4\"/>",
+                right + 10 - left,
+                bottom + 10 - top
+            ));
+            lines.push(format!(
+                "  <text x=\"{}\" y=\"{}\" font-size=\"12\" fill=\"#555555\">{}</text>",
+                left + 8,
+                top + 16,
+                escape_xml(group)
+            ));
+        }
+        for (from, to) in &edges {
+            let (Some((x1, y1)), Some((x2, y2))) = (positions.get(from.as_str()), positions.get(to.as_str())) else {
+                continue;
+            };
+            let (y1, middle) = (y1 + HEIGHT, (y1 + HEIGHT + y2) / 2);
+            lines.push(format!(
+                "  <path d=\"M {x1} {y1} C {x1} {middle}, {x2} {middle}, {x2} {y2}\" fill=\"none\" stroke=\"#555555\" marker-end=\"url(#arrow)\"/>"
+            ));
+        }
+        for (id, label, fill, radius) in &nodes {
+            let (x, y) = positions[id.as_str()];
+            let width = width(label);
+            lines.push(format!(
+                "  <rect x=\"{}\" y=\"{y}\" width=\"{width}\" height=\"{HEIGHT}\" rx=\"{radius}\" fill=\"{fill}\" stroke=\"#333333\"/>",
+                x - width / 2
+            ));
+            lines.push(format!(
+                "  <text x=\"{x}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                y + HEIGHT / 2,
+                escape_xml(label)
+            ));
+        }
+        lines.push("</svg>".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// Maps all nodes of the graph and its nested graphs to the given node
+    fn collect_nodes(&self, node: &str, nodes: &mut HashMap<String, String>) {
+        for id in self.inputs.iter().map(|i| self.input_id(i)).chain(self.outputs.iter().map(|o| self.output_id(o))) {
+            nodes.insert(id, node.to_string());
+        }
+        for step in &self.steps {
+            nodes.insert(self.step_id(&step.id), node.to_string());
+            if let Some(subworkflow) = &step.subworkflow {
+                subworkflow.collect_nodes(node, nodes);
+            }
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn endpoint_node(endpoint: &Endpoint) -> &str {
+    match endpoint {
+        Endpoint::Node(node) | Endpoint::Port(node, _) => node,
+    }
+}
+
+fn is_workflow(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_yaml::from_str::<Value>(&contents).ok())
+        .is_some_and(|document| document.get("class").and_then(|c| c.as_str()) == Some("Workflow"))
+}
+
+/// Makes ids usable as node names in both Mermaid and DOT
+fn sanitize(id: &str) -> String {
+    id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    #[test]
+    #[serial]
+    fn test_render_mermaid() {
+        let mermaid = render_workflow("tests/test_data/hello_world/workflows/main/main.cwl", Renderer::Mermaid).unwrap();
+        assert!(mermaid.starts_with("flowchart TB\n"));
+        assert!(mermaid.contains("    in_population([population])"));
+        assert!(mermaid.contains("    calculation[\"calculation\"]"));
+        assert!(mermaid.contains("    calculation -->|results → results| plot"));
+        assert!(mermaid.contains("    plot -->|results| out_out"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_dot() {
+        let dot = render_workflow("tests/test_data/hello_world/workflows/main/main.cwl", Renderer::Dot).unwrap();
+        assert!(dot.starts_with("digraph workflow {"));
+        assert!(dot.contains("calculation [shape=record, label=\"{{<in_population> population|<in_speakers> speakers}|calculation|{<out_results> results}}\"];"));
+        assert!(dot.contains("    calculation:out_results -> plot:in_results;"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_svg() {
+        let svg = render_workflow("tests/test_data/hello_world/workflows/main/main.cwl", Renderer::Svg).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains(">calculation</text>"));
+        //population and speakers are connected to calculation, calculation to plot and plot to the output
+        assert_eq!(svg.matches("marker-end=\"url(#arrow)\"").count(), 4);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_nested_workflow() {
+        let dir = tempdir().unwrap();
+        let main = fs::canonicalize("tests/test_data/hello_world/workflows/main/main.cwl").unwrap();
+        let path = dir.path().join("outer.cwl");
+        let workflow = format!(
+            r"class: Workflow
+cwlVersion: v1.2
+inputs:
+- id: pop
+  type: File
+- id: speakers
+  type: File
+outputs:
+- id: plot
+  type: File
+  outputSource: inner/out
+steps:
+- id: inner
+  in:
+    population: pop
+    speakers: speakers
+  run: '{}'
+  out:
+  - out
+",
+            main.to_string_lossy()
+        );
+        fs::write(&path, workflow).unwrap();
+
+        let mermaid = render_workflow(&path, Renderer::Mermaid).unwrap();
+        assert!(mermaid.contains("    subgraph inner[\"inner\"]\n        inner_in_population([population])"));
+        assert!(mermaid.contains("        inner_calculation -->|results → results| inner_plot"));
+        assert!(mermaid.contains("    in_pop --> inner_in_population"));
+        assert!(mermaid.contains("    inner_out_out --> out_plot"));
+
+        let dot = render_workflow(&path, Renderer::Dot).unwrap();
+        assert!(dot.contains("    subgraph cluster_inner {\n        label=\"inner\";"));
+        assert!(dot.contains("    in_pop -> inner_in_population;"));
+
+        let svg = render_workflow(&path, Renderer::Svg).unwrap();
+        assert!(svg.contains("fill=\"#EEEEEE\""));
+        assert!(!svg.contains(">calculation</text>"));
+        assert_eq!(svg.matches("marker-end=\"url(#arrow)\"").count(), 3);
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_groups() {
+        let dir = tempdir().unwrap();
+        let main = fs::read_to_string("tests/test_data/hello_world/workflows/main/main.cwl").unwrap();
+        let folder = fs::canonicalize("tests/test_data/hello_world/workflows/main").unwrap();
+        let workflow = main
+            .replace("'../", &format!("'{}/../", folder.to_string_lossy()))
+            .replace("- id: calculation\n", "- id: calculation\n  s4n:group: analysis\n");
+        let path = dir.path().join("grouped.cwl");
+        fs::write(&path, workflow).unwrap();
+
+        let workflow = load_workflow(&path).unwrap();
+        assert_eq!(workflow.steps[0].group.as_deref(), Some("analysis"));
+        assert!(workflow.steps[1].group.is_none());
+
+        let mermaid = render_workflow(&path, Renderer::Mermaid).unwrap();
+        assert!(mermaid.contains("    subgraph group_analysis[\"analysis\"]\n        calculation[\"calculation\"]\n    end\n    plot[\"plot\"]"));
+
+        let dot = render_workflow(&path, Renderer::Dot).unwrap();
+        assert!(dot.contains("    subgraph cluster_group_analysis {\n        label=\"analysis\";\n        style=dashed;\n        calculation [shape=record"));
+
+        let svg = render_workflow(&path, Renderer::Svg).unwrap();
+        assert!(svg.contains("stroke-dasharray=\"6 4\""));
+        assert!(svg.contains(">analysis</text>"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("my-step.1"), "my_step_1");
+    }
+}
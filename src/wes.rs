@@ -0,0 +1,624 @@
+use cwl::packed::{pack_workflow, MAIN_ID};
+use reqwest::{
+    multipart::{Form, Part},
+    Client, RequestBuilder,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::runtime::{Builder, Runtime};
+use walkdir::WalkDir;
+
+/// File configuring access to WES servers, relative to the project root. It contains credentials and must not be
+/// committed.
+pub const WES_CONFIG: &str = ".s4n/wes.yml";
+
+/// How often waiting checks whether it was interrupted
+const INTERRUPT_CHECK: Duration = Duration::from_millis(100);
+
+/// Requirements defined by the CWL standard, which every conformant engine supports
+const STANDARD_REQUIREMENTS: [&str; 17] = [
+    "InlineJavascriptRequirement",
+    "SchemaDefRequirement",
+    "DockerRequirement",
+    "SoftwareRequirement",
+    "InitialWorkDirRequirement",
+    "EnvVarRequirement",
+    "ShellCommandRequirement",
+    "ResourceRequirement",
+    "SubworkflowFeatureRequirement",
+    "ScatterFeatureRequirement",
+    "MultipleInputFeatureRequirement",
+    "StepInputExpressionRequirement",
+    "LoadListingRequirement",
+    "WorkReuse",
+    "NetworkAccess",
+    "InplaceUpdateRequirement",
+    "ToolTimeLimit",
+];
+
+/// Project specific configuration of WES servers, read from [`WES_CONFIG`]
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct WesConfig {
+    /// Bearer tokens keyed by the URL of the WES API they are used for
+    pub tokens: HashMap<String, String>,
+}
+
+impl WesConfig {
+    /// Loads the configuration of the given project, which is empty if there is none
+    pub fn load(project_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = project_dir.join(WES_CONFIG);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(&path)?).map_err(|e| format!("❌ Could not read {WES_CONFIG}: {e}"))?)
+    }
+
+    /// Returns the token configured for a server, URLs are compared without trailing slashes
+    pub fn token(&self, server: &str) -> Option<String> {
+        let server = server.trim_end_matches('/');
+        self.tokens.iter().find(|(url, _)| url.trim_end_matches('/') == server).map(|(_, token)| token.clone())
+    }
+}
+
+/// Client of the GA4GH Workflow Execution Service (WES) API v1.0
+///
+/// Reference: [WES API](https://ga4gh.github.io/workflow-execution-service-schemas/docs/)
+pub struct WesClient {
+    base_url: String,
+    token: Option<String>,
+    client: Client,
+    runtime: Runtime,
+    interrupted: Arc<AtomicBool>,
+}
+
+/// State of a run as reported by the WES server
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RunState {
+    Unknown,
+    Queued,
+    Initializing,
+    Running,
+    Paused,
+    Complete,
+    ExecutorError,
+    SystemError,
+    Canceled,
+    Canceling,
+    Preempted,
+}
+
+impl RunState {
+    /// Runs in a finished state do not change anymore
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            RunState::Complete | RunState::ExecutorError | RunState::SystemError | RunState::Canceled | RunState::Preempted
+        )
+    }
+}
+
+impl Display for RunState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ServiceInfo {
+    #[serde(default)]
+    workflow_type_versions: HashMap<String, WorkflowTypeVersion>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WorkflowTypeVersion {
+    #[serde(default)]
+    workflow_type_version: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RunId {
+    run_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct RunStatus {
+    state: RunState,
+}
+
+impl WesClient {
+    /// Creates a client for the WES endpoint at the given url, e.g. `https://wes.example.org/ga4gh/wes/v1`
+    pub fn new(base_url: &str, token: Option<String>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            client: Client::new(),
+            runtime: Builder::new_current_thread().enable_all().build()?,
+            interrupted: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Catches Ctrl-C from now on, so that [`WesClient::wait`] can be interrupted without terminating the process
+    pub fn catch_interrupts(&self) {
+        let interrupted = self.interrupted.clone();
+        self.runtime.spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupted.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+
+    /// Waits for the given time, returns `false` early if interrupted using Ctrl-C
+    pub fn wait(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        self.runtime.block_on(async {
+            while !self.interrupted.load(Ordering::SeqCst) {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                tokio::time::sleep(remaining.min(INTERRUPT_CHECK)).await;
+            }
+        });
+        !self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Returns the CWL versions the server can run as announced by its service info, e.g. `["v1.0", "v1.2"]`. The list
+    /// is empty if the server does not run CWL at all.
+    pub fn get_cwl_versions(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let info: ServiceInfo = self.send(self.client.get(format!("{}/service-info", self.base_url)))?;
+        let versions = info
+            .workflow_type_versions
+            .into_iter()
+            .filter(|(workflow_type, _)| workflow_type.eq_ignore_ascii_case("CWL"))
+            .flat_map(|(_, versions)| versions.workflow_type_version)
+            .map(|version| format!("v{}", version.trim_start_matches('v')))
+            .collect();
+        Ok(versions)
+    }
+
+    /// Submits a CWL document together with its inputs. Attachments are given by the name the server stores them as
+    /// and their local path, the workflow itself is attached as `workflow_name`.
+    pub fn submit_run(&self, workflow_name: &str, workflow: &str, params: &Value, attachments: &[(String, PathBuf)]) -> Result<String, Box<dyn Error>> {
+        let mut form = Form::new()
+            .text("workflow_params", params.to_string())
+            .text("workflow_type", "CWL")
+            .text("workflow_type_version", "v1.2")
+            .text("workflow_url", workflow_name.to_string())
+            .part("workflow_attachment", Part::text(workflow.to_string()).file_name(workflow_name.to_string()));
+        for (name, path) in attachments {
+            let bytes = fs::read(path).map_err(|e| format!("❌ Could not read attachment {:?}: {}", path, e))?;
+            form = form.part("workflow_attachment", Part::bytes(bytes).file_name(name.clone()));
+        }
+
+        let request = self.client.post(format!("{}/runs", self.base_url)).multipart(form);
+        let response: RunId = self.send(request)?;
+        Ok(response.run_id)
+    }
+
+    pub fn get_state(&self, run_id: &str) -> Result<RunState, Box<dyn Error>> {
+        let status: RunStatus = self.send(self.client.get(format!("{}/runs/{}/status", self.base_url, run_id)))?;
+        Ok(status.state)
+    }
+
+    /// Asks the server to cancel a run, which is finished in state `CANCELED` afterwards
+    pub fn cancel_run(&self, run_id: &str) -> Result<(), Box<dyn Error>> {
+        let _: RunId = self.send(self.client.post(format!("{}/runs/{}/cancel", self.base_url, run_id)))?;
+        Ok(())
+    }
+
+    /// Returns the full log of a run including its `outputs`
+    pub fn get_run_log(&self, run_id: &str) -> Result<Value, Box<dyn Error>> {
+        self.send(self.client.get(format!("{}/runs/{}", self.base_url, run_id)))
+    }
+
+    /// Downloads a file of a run, e.g. an output, using the credentials of the client
+    pub fn download(&self, url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+        let request = self.authorize(self.client.get(url));
+        let bytes = self.runtime.block_on(async {
+            let response = request.send().await?.error_for_status()?;
+            response.bytes().await
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    fn send<T: for<'de> Deserialize<'de>>(&self, request: RequestBuilder) -> Result<T, Box<dyn Error>> {
+        let request = self.authorize(request);
+        let body = self.runtime.block_on(async {
+            let response = request.send().await?;
+            let status = response.status();
+            response.text().await.map(|text| (status, text))
+        });
+        let (status, body) = body.map_err(|e| format!("❌ Could not reach WES server {}: {}", self.base_url, e))?;
+        if !status.is_success() {
+            //error responses contain a `msg` field
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|v| v.get("msg").and_then(|m| m.as_str()).map(str::to_string))
+                .unwrap_or(body);
+            return Err(format!("❌ WES server responded with {}: {}", status, message).into());
+        }
+        Ok(serde_json::from_str(&body).map_err(|e| format!("❌ Invalid response of WES server: {}", e))?)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+/// A packed CWL document with its inputs and the local files referenced by them, ready to be submitted
+#[derive(Debug)]
+pub struct Submission {
+    pub workflow_name: String,
+    pub workflow: Value,
+    pub params: Value,
+    pub attachments: Vec<(String, PathBuf)>,
+}
+
+impl Submission {
+    /// Packs the CWL file and collects the local files referenced by it or by the inputs of the job file
+    pub fn new(file: &Path, job_file: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let mut workflow = serde_json::to_value(pack_workflow(file)?)?;
+        let mut attachments = vec![];
+        attach_local_files(&mut workflow, file.parent().unwrap_or(Path::new(".")), &mut attachments)?;
+        let mut params = match job_file {
+            Some(job_file) => {
+                let contents = fs::read_to_string(job_file).map_err(|e| format!("Could not load File {:?}: {}", job_file, e))?;
+                serde_yaml::from_str(&contents).map_err(|e| format!("Could not read input file: {}", e))?
+            }
+            None => serde_json::json!({}),
+        };
+        let input_dir = job_file.and_then(|f| f.parent()).unwrap_or(Path::new("."));
+        attach_local_files(&mut params, input_dir, &mut attachments)?;
+        Ok(Self {
+            workflow_name: file.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            workflow,
+            params,
+            attachments,
+        })
+    }
+
+    /// Number of bytes to upload including the workflow and its inputs
+    pub fn size(&self) -> u64 {
+        let files = self.attachments.iter().map(|(_, path)| fs::metadata(path).map_or(0, |m| m.len())).sum::<u64>();
+        files + self.workflow.to_string().len() as u64 + self.params.to_string().len() as u64
+    }
+
+    /// Number of tools run by the submission, steps of subworkflows are counted individually
+    pub fn count_steps(&self) -> usize {
+        self.count_process_steps(&Value::String(MAIN_ID.to_string()))
+    }
+
+    fn count_process_steps(&self, run: &Value) -> usize {
+        let process = match run {
+            Value::String(id) => self.workflow["$graph"].as_array().and_then(|graph| graph.iter().find(|p| p["id"] == *id)),
+            process => Some(process),
+        };
+        let steps: Vec<&Value> = match process.and_then(|p| p.get("steps")) {
+            Some(Value::Array(steps)) => steps.iter().collect(),
+            Some(Value::Object(steps)) => steps.values().collect(),
+            _ => return 1,
+        };
+        steps.iter().filter_map(|step| step.get("run")).map(|run| self.count_process_steps(run)).sum()
+    }
+}
+
+/// Collects all local files referenced by File and Directory objects, which need to be attached to a run. Their
+/// locations are rewritten to the name of the attachment, which is their path relative to `base_dir`.
+pub fn attach_local_files(value: &mut Value, base_dir: &Path, attachments: &mut Vec<(String, PathBuf)>) -> Result<(), Box<dyn Error>> {
+    match value {
+        Value::Object(map) => {
+            let class = map.get("class").and_then(|c| c.as_str()).unwrap_or_default().to_string();
+            let location = map.get("location").or(map.get("path")).and_then(|l| l.as_str()).map(str::to_string);
+            if let (true, Some(location)) = (class == "File" || class == "Directory", location) {
+                let path = base_dir.join(location.trim_start_matches("file://"));
+                if !location.contains("://") || location.starts_with("file://") {
+                    if !path.exists() {
+                        return Err(format!("❌ Could not find {} {:?}", class, path).into());
+                    }
+                    let name = get_attachment_name(&path, base_dir);
+                    if class == "File" {
+                        attachments.push((name.clone(), path));
+                    } else {
+                        for entry in WalkDir::new(&path).into_iter().filter_map(Result::ok).filter(|e| e.path().is_file()) {
+                            let relative = entry.path().strip_prefix(&path)?.to_string_lossy().replace('\\', "/");
+                            attachments.push((format!("{name}/{relative}"), entry.path().to_path_buf()));
+                        }
+                    }
+                    map.remove("path");
+                    map.insert("location".to_string(), Value::String(name));
+                }
+            }
+            for value in map.values_mut() {
+                attach_local_files(value, base_dir, attachments)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                attach_local_files(item, base_dir, attachments)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns the classes of all requirements of a document that are not part of the CWL standard, e.g. `s4n:` extensions.
+/// Engines have to reject processes with requirements they do not know, unknown hints are ignored.
+pub fn get_unsupported_requirements(document: &Value) -> Vec<String> {
+    let mut classes = BTreeSet::new();
+    collect_requirements(document, &mut classes);
+    classes.into_iter().filter(|class| !STANDARD_REQUIREMENTS.contains(&class.as_str())).collect()
+}
+
+fn collect_requirements(value: &Value, classes: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            match map.get("requirements") {
+                Some(Value::Array(items)) => classes.extend(items.iter().filter_map(|r| r.get("class")?.as_str().map(str::to_string))),
+                Some(Value::Object(requirements)) => classes.extend(requirements.keys().cloned()),
+                _ => {}
+            }
+            map.values().for_each(|value| collect_requirements(value, classes));
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_requirements(item, classes)),
+        _ => {}
+    }
+}
+
+/// Files outside of the base directory are attached using their file name only
+fn get_attachment_name(path: &Path, base_dir: &Path) -> String {
+    match pathdiff::diff_paths(path, base_dir) {
+        Some(relative) if !relative.starts_with("..") => relative.to_string_lossy().replace('\\', "/"),
+        _ => path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+    }
+}
+
+/// Downloads all files of the outputs reported by the server into the output directory and points their locations
+/// to the downloaded copies. Files of directories are placed in a folder named after the directory.
+pub fn download_outputs(client: &WesClient, outputs: &mut Value, out_dir: &Path) -> Result<(), Box<dyn Error>> {
+    match outputs {
+        Value::Object(map) => {
+            let class = map.get("class").and_then(|c| c.as_str()).unwrap_or_default().to_string();
+            let location = map.get("location").and_then(|l| l.as_str()).unwrap_or_default().to_string();
+            let basename = map
+                .get("basename")
+                .and_then(|b| b.as_str())
+                .map(str::to_string)
+                .unwrap_or(location.rsplit('/').next().unwrap_or_default().to_string());
+            match class.as_str() {
+                "File" if location.starts_with("http://") || location.starts_with("https://") => {
+                    let path = out_dir.join(&basename);
+                    client.download(&location, &path)?;
+                    let path = path.to_string_lossy().into_owned();
+                    map.insert("location".to_string(), Value::String(format!("file://{path}")));
+                    map.insert("path".to_string(), Value::String(path));
+                }
+                "Directory" => {
+                    if let Some(listing) = map.get_mut("listing") {
+                        download_outputs(client, listing, &out_dir.join(&basename))?;
+                    }
+                }
+                _ => {
+                    for value in map.values_mut() {
+                        download_outputs(client, value, out_dir)?;
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                download_outputs(client, item, out_dir)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    /// Answers the given number of requests with the given JSON bodies and returns the received request heads
+    fn serve(responses: Vec<(&'static str, &'static str)>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ga4gh/wes/v1", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = vec![];
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = vec![0; 65536];
+                let mut request = String::new();
+                //read until the announced body is complete
+                loop {
+                    let n = stream.read(&mut buffer).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                    let Some(end) = request.find("\r\n\r\n") else { continue };
+                    let length = request
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                let response = format!("HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_run_state() {
+        let state: RunState = serde_json::from_str("\"EXECUTOR_ERROR\"").unwrap();
+        assert_eq!(state, RunState::ExecutorError);
+        assert!(state.is_finished());
+        assert!(!RunState::Running.is_finished());
+    }
+
+    #[test]
+    fn test_submit_and_poll_run() {
+        let (url, handle) = serve(vec![
+            ("200 OK", r#"{"run_id": "abc"}"#),
+            ("200 OK", r#"{"run_id": "abc", "state": "COMPLETE"}"#),
+            ("200 OK", r#"{"run_id": "abc", "outputs": {"out": "hello"}}"#),
+        ]);
+        let client = WesClient::new(&url, Some("secret".to_string())).unwrap();
+        let run_id = client.submit_run("main.cwl", "class: Workflow", &serde_json::json!({"name": "s4n"}), &[]).unwrap();
+        assert_eq!(run_id, "abc");
+        assert_eq!(client.get_state(&run_id).unwrap(), RunState::Complete);
+        assert_eq!(client.get_run_log(&run_id).unwrap()["outputs"]["out"], "hello");
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /ga4gh/wes/v1/runs "));
+        assert!(requests[0].contains("authorization: Bearer secret"));
+        assert!(requests[0].contains("name=\"workflow_url\"\r\n\r\nmain.cwl"));
+        assert!(requests[1].starts_with("GET /ga4gh/wes/v1/runs/abc/status "));
+    }
+
+    #[test]
+    fn test_cancel_run() {
+        let (url, handle) = serve(vec![("200 OK", r#"{"run_id": "abc"}"#)]);
+        let client = WesClient::new(&url, None).unwrap();
+        client.cancel_run("abc").unwrap();
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /ga4gh/wes/v1/runs/abc/cancel "));
+    }
+
+    #[test]
+    fn test_wait_interrupted() {
+        let client = WesClient::new("http://localhost", None).unwrap();
+        assert!(client.wait(Duration::from_millis(10)));
+
+        //interrupts end waiting right away
+        client.interrupted.store(true, Ordering::SeqCst);
+        let clock = Instant::now();
+        assert!(!client.wait(Duration::from_secs(60)));
+        assert!(clock.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_wes_config() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(WesConfig::load(dir.path()).unwrap(), WesConfig::default());
+
+        fs::create_dir(dir.path().join(".s4n")).unwrap();
+        fs::write(dir.path().join(WES_CONFIG), "tokens:\n  https://wes.example.org/ga4gh/wes/v1/: secret\n").unwrap();
+        let config = WesConfig::load(dir.path()).unwrap();
+        assert_eq!(config.token("https://wes.example.org/ga4gh/wes/v1"), Some("secret".to_string()));
+        assert_eq!(config.token("https://other.example.org/ga4gh/wes/v1"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_attach_local_files() {
+        let mut params = serde_json::json!({
+            "data": {"class": "File", "location": "test_data/input.txt"},
+            "remote": {"class": "File", "location": "https://example.org/data.csv"},
+            "files": [{"class": "File", "path": "test_data/input2.txt"}],
+            "name": "s4n"
+        });
+        let mut attachments = vec![];
+        attach_local_files(&mut params, Path::new("tests"), &mut attachments).unwrap();
+        assert_eq!(params["data"]["location"], "test_data/input.txt");
+        assert_eq!(params["files"][0]["location"], "test_data/input2.txt");
+        assert_eq!(params["remote"]["location"], "https://example.org/data.csv");
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0], ("test_data/input.txt".to_string(), PathBuf::from("tests/test_data/input.txt")));
+
+        let mut params = serde_json::json!({"data": {"class": "File", "location": "missing.txt"}});
+        assert!(attach_local_files(&mut params, Path::new("tests"), &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_get_cwl_versions() {
+        let (url, handle) = serve(vec![
+            ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["1.0", "v1.2"]}, "WDL": {}}}"#),
+            ("200 OK", r#"{"workflow_type_versions": {"NFL": {"workflow_type_version": ["22.10"]}}}"#),
+        ]);
+        let client = WesClient::new(&url, None).unwrap();
+        assert_eq!(client.get_cwl_versions().unwrap(), vec!["v1.0", "v1.2"]);
+        assert!(client.get_cwl_versions().unwrap().is_empty());
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /ga4gh/wes/v1/service-info "));
+    }
+
+    #[test]
+    fn test_submission() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs: []\noutputs: []\n";
+        let step = |run: &str| format!("  {run}:\n    run: {run}.cwl\n    in: []\n    out: []\n");
+        fs::write(dir.path().join("echo.cwl"), tool).unwrap();
+        fs::write(dir.path().join("inner.cwl"), format!("class: Workflow\ncwlVersion: v1.2\ninputs: []\noutputs: []\nsteps:\n{}", step("echo"))).unwrap();
+        let workflow = format!("class: Workflow\ncwlVersion: v1.2\ninputs: []\noutputs: []\nsteps:\n{}{}", step("echo"), step("inner"));
+        fs::write(dir.path().join("main.cwl"), workflow).unwrap();
+        fs::write(dir.path().join("inputs.yml"), "data:\n  class: File\n  location: echo.cwl\n").unwrap();
+
+        let submission = Submission::new(&dir.path().join("main.cwl"), Some(&dir.path().join("inputs.yml"))).unwrap();
+        assert_eq!(submission.workflow_name, "main.cwl");
+        //steps of subworkflows are counted individually
+        assert_eq!(submission.count_steps(), 2);
+        assert_eq!(submission.attachments, vec![("echo.cwl".to_string(), dir.path().join("echo.cwl"))]);
+        assert!(submission.size() > tool.len() as u64 + submission.workflow.to_string().len() as u64);
+
+        let submission = Submission::new(&dir.path().join("echo.cwl"), None).unwrap();
+        assert_eq!(submission.count_steps(), 1);
+    }
+
+    #[test]
+    fn test_get_unsupported_requirements() {
+        let document = serde_json::json!({
+            "cwlVersion": "v1.2",
+            "$graph": [
+                {"id": "#main", "class": "Workflow", "requirements": [{"class": "SubworkflowFeatureRequirement"}], "steps": [
+                    {"id": "#main/step", "run": "#tool.cwl", "requirements": {"s4n:RetryPolicy": {"retries": 2}}}
+                ]},
+                {"id": "#tool.cwl", "class": "CommandLineTool", "requirements": [{"class": "DockerRequirement"}, {"class": "s4n:CondaEnvironment"}],
+                 "hints": [{"class": "s4n:UnpackArchive"}]}
+            ]
+        });
+        assert_eq!(get_unsupported_requirements(&document), vec!["s4n:CondaEnvironment", "s4n:RetryPolicy"]);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let (url, handle) = serve(vec![("401 Unauthorized", r#"{"msg": "invalid token", "status_code": 401}"#)]);
+        let client = WesClient::new(&url, None).unwrap();
+        let error = client.get_state("abc").unwrap_err().to_string();
+        handle.join().unwrap();
+        assert!(error.contains("invalid token"));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{commands::tool::CreateToolArgs, container::AUTO_CONTAINER, parser::parse_command_line};
+use colored::Colorize;
+use cwl::requirements::ResourceRequirement;
+use dialoguer::{Confirm, Input, Select};
+use log::info;
+use std::{error::Error, fs, path::Path};
+
+const CONTAINER_OPTIONS: [&str; 4] = [
+    "No container",
+    "Pull an image, e.g. from Docker Hub",
+    "Build an image from a Dockerfile",
+    "Generate a Dockerfile from the script's dependencies",
+];
+
+/// Walks through everything needed to create a tool which was not given on the command line already. Resource
+/// requirements have no command line equivalent and are returned separately.
+pub fn prompt_tool_args(args: &CreateToolArgs) -> Result<(CreateToolArgs, Option<ResourceRequirement>), Box<dyn Error>> {
+    let mut args = args.clone();
+    info!("🧙 Answer the following questions to create your tool, press Enter to accept the default.");
+
+    if args.command.is_empty() {
+        let command: String = Input::new()
+            .with_prompt("Command line call, e.g. python script.py --data data.csv")
+            .interact_text()?;
+        args.command = shlex::split(&command).filter(|c| !c.is_empty()).ok_or("❌ Could not parse command line")?;
+    }
+    if args.name.is_none() {
+        let name: String = Input::new()
+            .with_prompt("Name of the tool (leave empty to derive it from the command)")
+            .allow_empty(true)
+            .interact_text()?;
+        args.name = Some(name).filter(|n| !n.is_empty());
+    }
+
+    //preview of the inputs and their types detected from the command
+    let preview = parse_command_line(args.command.iter().map(String::as_str).collect(), None);
+    if preview.inputs.is_empty() {
+        info!("🔎 No inputs detected");
+    } else {
+        info!("🔎 Detected inputs:");
+        for input in &preview.inputs {
+            let default = input.default.as_ref().map(|d| d.as_value_string()).unwrap_or_default();
+            info!("\t- {}: {} = {}", input.id.bold(), input.type_.to_string().green(), default);
+        }
+    }
+    let inputs: String = Input::new()
+        .with_prompt("Files to force as inputs (space separated, leave empty to use the detected ones)")
+        .with_initial_text(args.inputs.as_deref().unwrap_or_default().join(" "))
+        .allow_empty(true)
+        .interact_text()?;
+    args.inputs = split_list(&inputs);
+
+    let outputs: String = Input::new()
+        .with_prompt("Files to force as outputs (space separated, leave empty to detect changed files)")
+        .with_initial_text(args.outputs.as_deref().unwrap_or_default().join(" "))
+        .allow_empty(true)
+        .interact_text()?;
+    args.outputs = split_list(&outputs);
+
+    let default = match args.container_image.as_deref() {
+        None => 0,
+        Some(AUTO_CONTAINER) => 3,
+        Some(image) if image.contains("Dockerfile") => 2,
+        Some(_) => 1,
+    };
+    let container = Select::new().with_prompt("Container").items(&CONTAINER_OPTIONS).default(default).interact()?;
+    match container {
+        0 => args.container_image = None,
+        1 => args.container_image = Some(Input::new().with_prompt("Image, e.g. python:3.12").interact_text()?),
+        2 => {
+            args.container_image = Some(Input::new().with_prompt("Path to the Dockerfile").interact_text()?);
+            let tag: String = Input::new().with_prompt("Tag of the image").default("sciwin-container".to_string()).interact_text()?;
+            args.container_tag = Some(tag);
+        }
+        _ => args.container_image = Some(AUTO_CONTAINER.to_string()),
+    }
+
+    let resources = if Confirm::new().with_prompt("Add resource requirements?").default(false).interact()? {
+        let cores: String = Input::new()
+            .with_prompt("Minimum number of CPU cores (leave empty for no minimum)")
+            .allow_empty(true)
+            .interact_text()?;
+        let ram: String = Input::new()
+            .with_prompt("Minimum RAM in MiB (leave empty for no minimum)")
+            .allow_empty(true)
+            .interact_text()?;
+        Some(ResourceRequirement {
+            cores_min: parse_optional(&cores)?,
+            cores_max: None,
+            ram_min: parse_optional(&ram)?,
+            ram_max: None,
+            tmpdir_min: None,
+            tmpdir_max: None,
+            outdir_min: None,
+            outdir_max: None,
+        })
+    } else {
+        None
+    };
+
+    args.no_run = !Confirm::new()
+        .with_prompt("Run the command now to detect its outputs?")
+        .default(!args.no_run)
+        .interact()?;
+    Ok((args, resources))
+}
+
+/// Shows the changes the tool file at the given path would receive and asks whether to save them
+pub fn confirm_tool(path: &str, yaml: &str) -> Result<bool, Box<dyn Error>> {
+    let existing = if Path::new(path).exists() { fs::read_to_string(path)? } else { String::new() };
+    print_diff(path, &existing, yaml);
+    Ok(Confirm::new().with_prompt("Save tool?").default(true).interact()?)
+}
+
+/// Prints the line changes between two versions of the file at the given path
+pub fn print_diff(path: &str, old: &str, new: &str) {
+    info!("📝 Changes to {}:", path.bold());
+    for line in diff_lines(old, new) {
+        match line {
+            DiffLine::Added(text) => println!("{}", format!("+ {text}").green()),
+            DiffLine::Removed(text) => println!("{}", format!("- {text}").red()),
+            DiffLine::Unchanged(text) => println!("  {text}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DiffLine<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+    Unchanged(&'a str),
+}
+
+/// Line based diff using the longest common subsequence of both texts
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    //lengths of the common subsequences of all suffixes
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = vec![];
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Unchanged(old[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|l| DiffLine::Removed(l)));
+    diff.extend(new[j..].iter().map(|l| DiffLine::Added(l)));
+    diff
+}
+
+fn split_list(list: &str) -> Option<Vec<String>> {
+    let items = list.split_whitespace().map(String::from).collect::<Vec<_>>();
+    if items.is_empty() {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+fn parse_optional(value: &str) -> Result<Option<i32>, Box<dyn Error>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(value.parse().map_err(|_| format!("❌ {value} is not a whole number"))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "class: CommandLineTool\nbaseCommand: echo\ninputs: []\n";
+        let new = "class: CommandLineTool\nbaseCommand: cat\ninputs: []\noutputs: []\n";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Unchanged("class: CommandLineTool"),
+                DiffLine::Removed("baseCommand: echo"),
+                DiffLine::Added("baseCommand: cat"),
+                DiffLine::Unchanged("inputs: []"),
+                DiffLine::Added("outputs: []"),
+            ]
+        );
+        assert!(diff_lines("", new).iter().all(|l| matches!(l, DiffLine::Added(_))));
+    }
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(split_list("  "), None);
+        assert_eq!(split_list("a.csv  b.csv"), Some(vec!["a.csv".to_string(), "b.csv".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_optional() {
+        assert_eq!(parse_optional("").unwrap(), None);
+        assert_eq!(parse_optional(" 4 ").unwrap(), Some(4));
+        assert!(parse_optional("four").is_err());
+    }
+}
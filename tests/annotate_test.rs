@@ -1,6 +1,7 @@
 use s4n::commands::annotate::{
-    annotate, annotate_container, annotate_default, annotate_field, annotate_performer, annotate_person, annotate_process_step,
-    contains_docker_requirement, get_filename, handle_annotate_commands, parse_cwl, AnnotateCommands, AnnotateProcessArgs, PerformerArgs, PersonArgs,
+    annotate, annotate_container, annotate_default, annotate_field, annotate_from_file, annotate_performer, annotate_person, annotate_process_step,
+    annotate_s4n_namespace, contains_docker_requirement, get_filename, handle_annotate_commands, load_project_metadata, parse_cwl, AnnotateCommands,
+    AnnotateFromFileArgs, AnnotateInputArgs, AnnotateProcessArgs, PerformerArgs, PersonArgs,
 };
 use serde_yaml::Value;
 use serial_test::serial;
@@ -1137,3 +1138,138 @@ fn test_add_to_sequence() {
     assert!(result.is_ok());
     std::env::set_current_dir(current).unwrap();
 }
+
+#[test]
+#[serial]
+fn test_annotate_s4n_namespace() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    fs::write("plain.cwl", CWL_CONTENT).unwrap();
+    annotate_s4n_namespace("plain.cwl").unwrap();
+    let plain = fs::read_to_string("plain.cwl").unwrap();
+
+    fs::write(
+        "retry.cwl",
+        "class: CommandLineTool\nbaseCommand: echo\nhints:\n- class: s4n:RetryPolicy\n  maxAttempts: 3\ninputs: []\noutputs: []\n",
+    )
+    .unwrap();
+    annotate_s4n_namespace("retry.cwl").unwrap();
+    let retry = fs::read_to_string("retry.cwl").unwrap();
+    env::set_current_dir(current).unwrap();
+
+    assert!(!plain.contains("$namespaces"));
+    assert!(retry.contains("$namespaces:\n  s4n: https://github.com/fairagro/m4.4_sciwin_client#\n"));
+}
+
+#[test]
+#[serial]
+fn test_annotate_from_file() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    fs::create_dir_all("workflows/echo").unwrap();
+    fs::write("workflows/echo/echo.cwl", CWL_CONTENT).unwrap();
+    fs::write(
+        "metadata.yml",
+        r#"license: https://spdx.org/licenses/MIT
+authors:
+  - name: Jane Doe
+    id: https://orcid.org/0000-0000-0000-0000
+performers:
+  - first_name: Jane
+    last_name: Doe
+    role: Formal Search
+    role_accession: http://purl.obolibrary.org/obo/NCIT_C170397
+"#,
+    )
+    .unwrap();
+
+    let args = AnnotateFromFileArgs {
+        file: "metadata.yml".to_string(),
+    };
+    annotate_from_file(&args).unwrap();
+    let annotated = fs::read_to_string("workflows/echo/echo.cwl").unwrap();
+    let yaml = parse_cwl("workflows/echo/echo.cwl").unwrap();
+    assert_eq!(yaml["s:license"], Value::String("https://spdx.org/licenses/MIT".to_string()));
+    assert_eq!(yaml["s:author"][0]["s:name"], Value::String("Jane Doe".to_string()));
+    assert_eq!(yaml["arc:performer"][0]["arc:has role"][0]["arc:annotation value"], Value::String("Formal Search".to_string()));
+    assert_eq!(yaml["$namespaces"]["arc"], Value::String(ARC_NAMESPACE.to_string()));
+
+    //applying the same metadata again does not change anything
+    annotate_from_file(&args).unwrap();
+    assert_eq!(fs::read_to_string("workflows/echo/echo.cwl").unwrap(), annotated);
+
+    env::set_current_dir(current).unwrap();
+}
+
+#[test]
+fn test_load_project_metadata_isa_csv() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("isa.investigation.csv");
+    fs::write(
+        &file,
+        "CONTACTS\nInvestigation Person Last Name,Doe,Smith\nInvestigation Person First Name,Jane,John\nInvestigation Person Email,jane@doe.de,\n\
+         Investigation Person Roles,Formal Search,\nComment[ORCID],https://orcid.org/0000-0000-0000-0000,\n",
+    )
+    .unwrap();
+
+    let metadata = load_project_metadata(&file.to_string_lossy()).unwrap();
+    assert_eq!(metadata.authors.len(), 2);
+    assert_eq!(metadata.authors[0].name, "Jane Doe");
+    assert_eq!(metadata.authors[0].id.as_deref(), Some("https://orcid.org/0000-0000-0000-0000"));
+    assert_eq!(metadata.authors[1].mail, None);
+    assert_eq!(metadata.performers[0].role.as_deref(), Some("Formal Search"));
+    assert_eq!(metadata.performers[1].last_name, "Smith");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_annotate_input() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    fs::write(
+        "growth.cwl",
+        "class: CommandLineTool\nbaseCommand: echo\ninputs:\n  temperature: float\n  days: int\noutputs: []\n",
+    )
+    .unwrap();
+    let args = AnnotateInputArgs {
+        cwl_name: "growth".to_string(),
+        input: "temperature".to_string(),
+        unit: Some("UO:0000027".to_string()),
+        min: Some(-10.0),
+        max: Some(40.5),
+    };
+    handle_annotate_commands(&AnnotateCommands::Input(args)).await.unwrap();
+
+    let yaml = parse_cwl("growth").unwrap();
+    let input = &yaml["inputs"]["temperature"];
+    assert_eq!(input["type"], Value::from("float"));
+    assert_eq!(input["s:unitCode"], Value::from("http://purl.obolibrary.org/obo/UO_0000027"));
+    assert_eq!(input["s:minValue"], Value::from(-10));
+    assert_eq!(input["s:maxValue"], Value::from(40.5));
+    assert_eq!(yaml["$namespaces"]["s"], Value::from("https://schema.org/"));
+
+    let args = AnnotateInputArgs {
+        cwl_name: "growth".to_string(),
+        input: "days".to_string(),
+        unit: None,
+        min: Some(10.0),
+        max: Some(1.0),
+    };
+    assert!(handle_annotate_commands(&AnnotateCommands::Input(args)).await.is_err());
+    let args = AnnotateInputArgs {
+        cwl_name: "growth".to_string(),
+        input: "missing".to_string(),
+        unit: None,
+        min: Some(0.0),
+        max: None,
+    };
+    assert!(handle_annotate_commands(&AnnotateCommands::Input(args)).await.is_err());
+
+    env::set_current_dir(current).unwrap();
+}
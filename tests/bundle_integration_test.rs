@@ -0,0 +1,98 @@
+mod common;
+use common::with_temp_repository;
+use git2::Repository;
+use s4n::{
+    bundle::{BUNDLE_DIR, BUNDLE_METADATA},
+    commands::{
+        bundle::{export_bundle, import, ExportBundleArgs, ImportBundleArgs},
+        data::{add_dataset, AddDataArgs},
+        workflow::{create_workflow, CreateWorkflowArgs},
+    },
+    datasets::DATASET_MANIFEST,
+    io::create_and_write_file,
+    repo::{commit, get_modified_files, stage_all},
+};
+use serial_test::serial;
+use std::fs;
+use tempfile::tempdir;
+
+const TOOL: &str = r"#!/usr/bin/env cwl-runner
+
+cwlVersion: v1.2
+class: CommandLineTool
+
+requirements:
+- class: DockerRequirement
+  dockerPull: python:3.12-slim
+
+inputs: []
+outputs: []
+baseCommand: [python, --version]
+";
+
+#[test]
+#[serial]
+pub fn test_export_import_bundle() {
+    with_temp_repository(|dir| {
+        create_workflow(&CreateWorkflowArgs {
+            name: "main".to_string(),
+            force: false,
+        })
+        .unwrap();
+        create_and_write_file("workflows/version/version.cwl", TOOL).unwrap();
+        add_dataset(&AddDataArgs {
+            location: "data/input.txt".to_string(),
+            name: None,
+            doi: None,
+            license: None,
+            checksum: None,
+            force: false,
+            no_commit: true,
+        })
+        .unwrap();
+
+        let args = ExportBundleArgs {
+            output: Some(dir.path().join("project.tar.zst")),
+            allow_secrets: false,
+        };
+        //uncommitted changes
+        assert!(export_bundle(&args).is_err());
+
+        let repo = Repository::open(dir.path()).unwrap();
+        stage_all(&repo).unwrap();
+        commit(&repo, "Added workflow").unwrap();
+        export_bundle(&args).unwrap();
+
+        let target = tempdir().unwrap();
+        let directory = target.path().join("restored");
+        import(&ImportBundleArgs {
+            bundle: dir.path().join("project.tar.zst"),
+            directory: Some(directory.clone()),
+            no_environments: true,
+        })
+        .unwrap();
+
+        assert!(directory.join("workflows/main/main.cwl").exists());
+        assert!(directory.join(DATASET_MANIFEST).exists());
+        assert!(directory.join(BUNDLE_DIR).join("packed/main.cwl").exists());
+        let metadata = fs::read_to_string(directory.join(BUNDLE_DIR).join(BUNDLE_METADATA)).unwrap();
+        assert!(metadata.contains("python:3.12-slim"));
+        assert!(metadata.contains("\"input\""));
+
+        let restored = Repository::open(&directory).unwrap();
+        assert_eq!(
+            restored.head().unwrap().peel_to_commit().unwrap().id(),
+            repo.head().unwrap().peel_to_commit().unwrap().id()
+        );
+        //bundle contents are not part of the project
+        assert!(get_modified_files(&restored).is_empty());
+
+        //target is not empty anymore
+        assert!(import(&ImportBundleArgs {
+            bundle: dir.path().join("project.tar.zst"),
+            directory: Some(directory),
+            no_environments: true,
+        })
+        .is_err());
+    });
+}
@@ -6,7 +6,7 @@ use s4n::{
         execute::{execute_local, LocalExecuteArgs, Runner},
         init::init_s4n,
         tool::{create_tool, list_tools, CreateToolArgs, ListToolArgs},
-        workflow::{connect_workflow_nodes, create_workflow, get_workflow_status, save_workflow, ConnectWorkflowArgs, CreateWorkflowArgs},
+        workflow::{connect_workflow_nodes, create_workflow, get_workflow_status, save_workflow, ConnectWorkflowArgs, CreateWorkflowArgs, SaveWorkflowArgs, StatusWorkflowArgs},
     },
     io::copy_dir,
 };
@@ -66,6 +66,7 @@ pub fn test_cli_s4n_workflow() {
             "data/population.csv".to_string(),
         ]
         .to_vec(),
+        ..Default::default()
     })
     .expect("Could not create calculation tool");
     assert!(fs::exists("workflows/calculation/calculation.cwl").unwrap());
@@ -88,12 +89,13 @@ pub fn test_cli_s4n_workflow() {
             "results.csv".to_string(),
         ]
         .to_vec(),
+        ..Default::default()
     })
     .expect("Could not create plot tool");
     assert!(fs::exists("workflows/plot/plot.cwl").unwrap());
 
     //list tools
-    list_tools(&ListToolArgs { list_all: true }).unwrap();
+    list_tools(&ListToolArgs { list_all: true, history: None }).unwrap();
 
     //create workflow
     let name = "test_workflow".to_string();
@@ -135,7 +137,12 @@ pub fn test_cli_s4n_workflow() {
     .expect("Could not add input to output/out");
 
     //save workflow
-    save_workflow(&create_args).expect("Could not save workflow");
+    save_workflow(&SaveWorkflowArgs {
+        name: create_args.name.clone(),
+        allow_secrets: false,
+        redact_secrets: false,
+    })
+    .expect("Could not save workflow");
     let wf_path = PathBuf::from("workflows/test_workflow/test_workflow.cwl");
     assert!(fs::exists(&wf_path).unwrap());
 
@@ -152,7 +159,10 @@ pub fn test_cli_s4n_workflow() {
     assert!(workflow.has_step_output("plot/results"));
 
     //workflow status
-    get_workflow_status(&create_args).expect("Could not print status");
+    get_workflow_status(&StatusWorkflowArgs {
+        name: Some(create_args.name.clone()),
+    })
+    .expect("Could not print status");
 
     //remove outputs
     remove_file("results.csv").unwrap();
@@ -168,6 +178,7 @@ pub fn test_cli_s4n_workflow() {
         is_quiet: false,
         file: wf_path,
         args: vec!["inputs.yml".to_string()],
+        ..Default::default()
     })
     .expect("Could not execute Workflow");
 
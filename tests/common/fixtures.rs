@@ -0,0 +1,236 @@
+#![allow(dead_code)]
+//! Factories creating synthetic workflows and data on demand, so that executor tests do not need to copy files from
+//! `tests/test_data`. Created workflows only depend on `python` being available. Inputs are referenced relative to the
+//! job file, so tests should change into the temporary directory and pass relative paths like the other executor
+//! tests do.
+
+use std::{
+    fs::{self, create_dir_all},
+    path::{Path, PathBuf},
+};
+
+/// Tool appending the name of its output to the contents of its input, so the contents show which steps a file passed
+const COPY_TOOL: &str = r#"#!/usr/bin/env cwl-runner
+
+cwlVersion: v1.2
+class: CommandLineTool
+
+inputs:
+- id: file
+  type: File
+  inputBinding:
+    position: 0
+- id: name
+  type: string
+  default: out.txt
+  inputBinding:
+    position: 1
+
+outputs:
+- id: out
+  type: File
+  outputBinding:
+    glob: $(inputs.name)
+
+baseCommand:
+- python
+- -c
+- "import sys; open(sys.argv[2], 'w').write(open(sys.argv[1]).read() + sys.argv[2] + '\\n')"
+"#;
+
+/// Name of the tool file written next to the workflows
+pub const COPY_TOOL_FILE: &str = "copy.cwl";
+
+/// Paths of a workflow written by [`WorkflowFactory`] and the job file to run it with
+#[derive(Debug, Clone)]
+pub struct WorkflowFixture {
+    pub workflow: PathBuf,
+    pub job: PathBuf,
+    /// Name of the file the last step writes
+    pub output: String,
+}
+
+/// Builds workflows of `steps` steps in a row, every step reads the output of the previous one and writes
+/// `step_<n>.txt`. Scattered workflows run every step once per input file.
+#[derive(Debug, Clone)]
+pub struct WorkflowFactory {
+    name: String,
+    steps: usize,
+    scatter: bool,
+    inputs: Vec<PathBuf>,
+}
+
+impl WorkflowFactory {
+    pub fn chain(steps: usize) -> Self {
+        WorkflowFactory {
+            name: "chain".to_string(),
+            steps: steps.max(1),
+            scatter: false,
+            inputs: vec![],
+        }
+    }
+
+    /// A workflow whose steps are scattered over an array of files
+    pub fn scatter(steps: usize) -> Self {
+        WorkflowFactory {
+            name: "scatter".to_string(),
+            scatter: true,
+            ..Self::chain(steps)
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Files used as inputs, a single `input.txt` is created if none are given. Only the first file is used by
+    /// workflows which are not scattered.
+    pub fn inputs(mut self, inputs: &[PathBuf]) -> Self {
+        self.inputs = inputs.to_vec();
+        self
+    }
+
+    /// Writes the workflow, its tool and a job file to the given directory
+    pub fn write(&self, dir: &Path) -> WorkflowFixture {
+        create_dir_all(dir).expect("Could not create workflow directory");
+        fs::write(dir.join(COPY_TOOL_FILE), COPY_TOOL).expect("Could not write tool");
+
+        let mut inputs = self.inputs.clone();
+        if inputs.is_empty() {
+            let input = dir.join("input.txt");
+            fs::write(&input, "input.txt\n").expect("Could not write input");
+            inputs.push(input);
+        }
+
+        let (input_type, requirements) = if self.scatter {
+            ("File[]", "requirements:\n- class: ScatterFeatureRequirement\n\n")
+        } else {
+            ("File", "")
+        };
+        let mut workflow = format!(
+            "#!/usr/bin/env cwl-runner
+
+cwlVersion: v1.2
+class: Workflow
+
+{requirements}inputs:
+- id: file
+  type: {input_type}
+
+outputs:
+- id: out
+  type: {input_type}
+  outputSource: step_{}/out
+
+steps:
+",
+            self.steps
+        );
+        for step in 1..=self.steps {
+            let source = if step == 1 { "file".to_string() } else { format!("step_{}/out", step - 1) };
+            workflow.push_str(&format!(
+                "- id: step_{step}\n  in:\n    file: {source}\n    name:\n      default: step_{step}.txt\n  run: {COPY_TOOL_FILE}\n"
+            ));
+            if self.scatter {
+                workflow.push_str("  scatter: file\n");
+            }
+            workflow.push_str("  out:\n  - out\n");
+        }
+        let workflow_path = dir.join(format!("{}.cwl", self.name));
+        fs::write(&workflow_path, workflow).expect("Could not write workflow");
+
+        let locations = inputs
+            .iter()
+            .map(|input| {
+                let location = pathdiff::diff_paths(fs::canonicalize(input).unwrap(), fs::canonicalize(dir).unwrap()).unwrap();
+                format!("  class: File\n  location: {}\n", location.to_string_lossy().replace('\\', "/"))
+            })
+            .collect::<Vec<_>>();
+        let job = if self.scatter {
+            format!("file:\n{}", locations.iter().map(|l| format!("-{}", &l[1..])).collect::<String>())
+        } else {
+            format!("file:\n{}", locations[0])
+        };
+        let job_path = dir.join(format!("{}-job.yml", self.name));
+        fs::write(&job_path, job).expect("Could not write job");
+
+        WorkflowFixture {
+            workflow: workflow_path,
+            job: job_path,
+            output: format!("step_{}.txt", self.steps),
+        }
+    }
+}
+
+/// Builds a tree of directories filled with text files of a given size. The contents of every file start with its
+/// path relative to the root of the tree, so files can be told apart after being copied around.
+#[derive(Debug, Clone)]
+pub struct DataTreeFactory {
+    depth: usize,
+    width: usize,
+    files: usize,
+    size: usize,
+}
+
+impl Default for DataTreeFactory {
+    fn default() -> Self {
+        DataTreeFactory {
+            depth: 1,
+            width: 2,
+            files: 3,
+            size: 64,
+        }
+    }
+}
+
+impl DataTreeFactory {
+    /// Number of directory levels below the root
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Number of subdirectories of every directory
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Number of files in every directory
+    pub fn files(mut self, files: usize) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Size of every file in bytes
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Writes the tree into the given directory and returns the paths of all files sorted by name
+    pub fn write(&self, root: &Path) -> Vec<PathBuf> {
+        let mut files = vec![];
+        self.write_level(root, root, 0, &mut files);
+        files.sort();
+        files
+    }
+
+    fn write_level(&self, root: &Path, dir: &Path, level: usize, files: &mut Vec<PathBuf>) {
+        create_dir_all(dir).expect("Could not create data directory");
+        for i in 0..self.files {
+            let file = dir.join(format!("file_{i}.txt"));
+            let name = file.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            let mut contents = format!("{name}\n").repeat(self.size / (name.len() + 1) + 1);
+            contents.truncate(self.size);
+            fs::write(&file, contents).expect("Could not write data file");
+            files.push(file);
+        }
+        if level < self.depth {
+            for i in 0..self.width {
+                self.write_level(root, &dir.join(format!("dir_{i}")), level + 1, files);
+            }
+        }
+    }
+}
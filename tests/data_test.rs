@@ -0,0 +1,85 @@
+mod common;
+use common::with_temp_repository;
+use git2::Repository;
+use s4n::{
+    commands::{
+        data::{add_dataset, AddDataArgs},
+        execute::{execute_local, LocalExecuteArgs, Runner},
+    },
+    datasets::load_manifest,
+    io::create_and_write_file,
+    repo::get_modified_files,
+};
+use serial_test::serial;
+use std::{env, fs};
+
+#[test]
+#[serial]
+pub fn test_add_dataset() {
+    with_temp_repository(|dir| {
+        let args = AddDataArgs {
+            location: "data/input.txt".to_string(),
+            name: None,
+            doi: Some("10.5281/zenodo.1234".to_string()),
+            license: Some("CC-BY-4.0".to_string()),
+            checksum: None,
+            force: false,
+            no_commit: false,
+        };
+        add_dataset(&args).expect("Could not add dataset");
+
+        let manifest = load_manifest(dir.path()).unwrap();
+        let dataset = &manifest["input"];
+        assert_eq!(dataset.location, "data/input.txt");
+        assert_eq!(dataset.doi, Some("10.5281/zenodo.1234".to_string()));
+        assert!(dataset.checksum.as_ref().unwrap().starts_with("sha1$"));
+
+        //manifest is committed
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(get_modified_files(&repo).is_empty());
+
+        //adding twice fails without force
+        assert!(add_dataset(&args).is_err());
+
+        //names are folders of the dataset cache and must stay inside of it
+        let args = AddDataArgs {
+            name: Some("../../outside".to_string()),
+            ..args
+        };
+        assert!(add_dataset(&args).is_err());
+        assert!(!load_manifest(dir.path()).unwrap().contains_key("../../outside"));
+    });
+}
+
+#[test]
+#[serial]
+pub fn test_execute_with_dataset_reference() {
+    let tool = env::current_dir().unwrap().join("tests/test_data/echo.cwl");
+    with_temp_repository(|_| {
+        let args = AddDataArgs {
+            location: "data/input2.txt".to_string(),
+            name: Some("greeting".to_string()),
+            doi: None,
+            license: None,
+            checksum: None,
+            force: false,
+            no_commit: true,
+        };
+        add_dataset(&args).expect("Could not add dataset");
+        create_and_write_file("job.yml", "test:\n  class: File\n  location: dataset:greeting\n").unwrap();
+
+        let args = LocalExecuteArgs {
+            runner: Runner::Custom,
+            out_dir: None,
+            is_quiet: true,
+            file: tool,
+            args: vec!["job.yml".to_string()],
+            ..Default::default()
+        };
+        execute_local(&args).expect("Could not execute CommandLineTool");
+
+        let contents = fs::read_to_string("results.txt").unwrap();
+        let expected = fs::read_to_string("data/input2.txt").unwrap();
+        assert_eq!(contents.replace("\r\n", "\n"), expected.replace("\r\n", "\n"));
+    });
+}
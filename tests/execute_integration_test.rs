@@ -1,14 +1,25 @@
 mod common;
-use common::setup_python;
+use common::{
+    fixtures::{DataTreeFactory, WorkflowFactory},
+    setup_python,
+};
+use cwl::strict::{set_parse_mode, ParseMode};
 use s4n::{
-    commands::execute::{execute_local, LocalExecuteArgs, Runner},
+    commands::{
+        execute::{execute_local, LocalExecuteArgs, Runner},
+        workflow::{get_workflow_status, list_workflows, ListWorkflowArgs, StatusWorkflowArgs},
+    },
+    execution::{events::EVENT_LOG_FILE, runs::load_runs},
     io::copy_dir,
+    log::LogFormat,
+    repo::{get_file_history, initial_commit, stage_all},
 };
 use serial_test::serial;
 use std::{
     env, fs::{self}, iter, path::{Path, PathBuf}
 };
 use tempfile::tempdir;
+use walkdir::WalkDir;
 
 #[test]
 #[serial]
@@ -19,6 +30,7 @@ pub fn test_execute_local() {
         is_quiet: false,
         file: PathBuf::from("tests/test_data/echo.cwl"),
         args: vec![],
+        ..Default::default()
     };
 
     execute_local(&args).expect("Could not execute CommandLineTool");
@@ -47,6 +59,7 @@ pub fn test_execute_local_with_args() {
             .iter()
             .map(ToString::to_string)
             .collect::<Vec<_>>(),
+        ..Default::default()
     };
 
     execute_local(&args).expect("Could not execute CommandLineTool");
@@ -63,6 +76,30 @@ pub fn test_execute_local_with_args() {
     fs::remove_file(file).unwrap();
 }
 
+#[test]
+#[serial]
+#[cfg(not(target_os = "windows"))]
+pub fn test_execute_local_notify_command() {
+    let dir = tempdir().unwrap();
+    let status = dir.path().join("status.txt");
+    let args = LocalExecuteArgs {
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        is_quiet: true,
+        notify_command: Some(format!("echo \"$S4N_RUN_STATUS\" >> {}", status.to_string_lossy())),
+        file: PathBuf::from("tests/test_data/echo.cwl"),
+        ..Default::default()
+    };
+    execute_local(&args).unwrap();
+
+    //failed runs are notified as well
+    let failing = LocalExecuteArgs {
+        file: PathBuf::from("tests/test_data/does_not_exist.cwl"),
+        ..args
+    };
+    assert!(execute_local(&failing).is_err());
+    assert_eq!(fs::read_to_string(&status).unwrap(), "success\nfailed\n");
+}
+
 #[test]
 #[serial]
 pub fn test_execute_local_with_file() {
@@ -72,6 +109,7 @@ pub fn test_execute_local_with_file() {
         is_quiet: false,
         file: PathBuf::from("tests/test_data/echo.cwl"),
         args: iter::once(&"tests/test_data/echo-job.yml").map(ToString::to_string).collect::<Vec<_>>(),
+        ..Default::default()
     };
 
     execute_local(&args).expect("Could not execute CommandLineTool");
@@ -98,6 +136,7 @@ pub fn test_execute_local_outdir() {
         is_quiet: false,
         file: PathBuf::from("tests/test_data/echo.cwl"),
         args: vec![],
+        ..Default::default()
     };
 
     execute_local(&args).expect("Could not execute CommandLineTool");
@@ -107,6 +146,78 @@ pub fn test_execute_local_outdir() {
     fs::remove_file(file).unwrap();
 }
 
+#[test]
+#[serial]
+pub fn test_execute_local_json_events() {
+    let dir = tempdir().unwrap();
+    let args = LocalExecuteArgs {
+        runner: Runner::Custom,
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        log_format: LogFormat::Json,
+        file: PathBuf::from("tests/test_data/echo.cwl"),
+        ..Default::default()
+    };
+
+    execute_local(&args).expect("Could not execute CommandLineTool");
+
+    let events = fs::read_to_string(dir.path().join(EVENT_LOG_FILE))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let names = events.iter().map(|e| e["event"].as_str().unwrap()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["run_started", "step_started", "command_finished", "step_finished", "run_finished"]);
+    assert_eq!(events[2]["step"], "echo");
+    assert_eq!(events[2]["exit_code"], 0);
+    assert!(events[2]["resources"]["peak_memory_bytes"].is_u64());
+    assert!(events[3]["outputs"].get("results").is_some());
+    assert_eq!(events[4]["status"], "success");
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_seed() {
+    let run_id = |seed: Option<u64>| {
+        let dir = tempdir().unwrap();
+        let args = LocalExecuteArgs {
+            runner: Runner::Custom,
+            out_dir: Some(dir.path().to_string_lossy().into_owned()),
+            log_format: LogFormat::Json,
+            seed,
+            file: PathBuf::from("tests/test_data/echo.cwl"),
+            ..Default::default()
+        };
+        execute_local(&args).expect("Could not execute CommandLineTool");
+        let events = fs::read_to_string(dir.path().join(EVENT_LOG_FILE)).unwrap();
+        let event = serde_json::from_str::<serde_json::Value>(events.lines().next().unwrap()).unwrap();
+        event["run"].as_str().unwrap().to_string()
+    };
+    assert_eq!(run_id(Some(7)), run_id(Some(7)));
+    assert_ne!(run_id(Some(7)), run_id(Some(8)));
+    assert_ne!(run_id(None), run_id(None));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_stream_document() {
+    let dir = tempdir().unwrap();
+    let args = LocalExecuteArgs {
+        runner: Runner::Custom,
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        file: PathBuf::from("tests/test_data/stream.cwl#echo"),
+        ..Default::default()
+    };
+
+    execute_local(&args).expect("Could not execute CommandLineTool");
+    assert_eq!(fs::read_to_string(dir.path().join("echo.txt")).unwrap(), "Hello from a stream!\n");
+
+    let args = LocalExecuteArgs {
+        file: PathBuf::from("tests/test_data/stream.cwl"),
+        ..args
+    };
+    assert!(execute_local(&args).is_err());
+}
+
 #[test]
 #[serial]
 pub fn test_execute_local_is_quiet() {
@@ -117,6 +228,7 @@ pub fn test_execute_local_is_quiet() {
         is_quiet: true,
         file: PathBuf::from("tests/test_data/echo.cwl"),
         args: vec![],
+        ..Default::default()
     };
 
     execute_local(&args).expect("Could not execute CommandLineTool");
@@ -137,6 +249,7 @@ pub fn test_execute_local_cwltool() {
             is_quiet: false,
             file: PathBuf::from("tests/test_data/echo.cwl"),
             args: vec![],
+            ..Default::default()
         };
 
         execute_local(&args).expect("Could not execute CommandLineTool");
@@ -170,6 +283,7 @@ pub fn test_execute_local_workflow() {
         is_quiet: false,
         file: PathBuf::from(format!("{dir_str}/workflows/main/main.cwl")),
         args: vec!["inputs.yml".to_string()],
+        ..Default::default()
     };
     let result = execute_local(&args);
     println!("{result:#?}");
@@ -199,6 +313,7 @@ pub fn test_execute_local_tool_default_cwl() {
         is_quiet: true,
         file: path.clone(),
         args: vec![],
+        ..Default::default()
     };
     let args_override = LocalExecuteArgs {
         runner: Runner::Custom,
@@ -206,6 +321,7 @@ pub fn test_execute_local_tool_default_cwl() {
         is_quiet: true,
         file: path,
         args: vec!["--file1".to_string(), "tests/test_data/input.txt".to_string()],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
@@ -233,6 +349,7 @@ pub fn test_execute_local_workflow_no_steps() {
         is_quiet: true,
         file: path,
         args: vec![],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
@@ -252,6 +369,7 @@ pub fn test_execute_local_workflow_in_param() {
         is_quiet: true,
         file: path,
         args: vec!["--pop".to_string(), "tests/test_data/input.txt".to_string()],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
@@ -275,6 +393,7 @@ pub fn test_execute_local_workflow_dir_out() {
         is_quiet: true,
         file: path,
         args: vec![],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
@@ -297,6 +416,7 @@ pub fn test_execute_local_workflow_file_out() {
         is_quiet: true,
         file: path,
         args: vec![],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
@@ -317,8 +437,896 @@ pub fn test_execute_local_workflow_directory_out() {
         is_quiet: true,
         file: path,
         args: vec!["--dirname".to_string(), "test_directory".to_string()],
+        ..Default::default()
     };
 
     assert!(execute_local(&args).is_ok());
     assert!(fs::exists(out_path).unwrap());
-}
\ No newline at end of file
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_simulate() {
+    let folder = "./tests/test_data/hello_world";
+
+    let dir = tempdir().unwrap();
+    let dir_str = &dir.path().to_string_lossy();
+    copy_dir(folder, dir.path()).unwrap();
+
+    let current_dir = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    //no python environment needed as nothing gets executed
+    let args = LocalExecuteArgs {
+        file: PathBuf::from(format!("{dir_str}/workflows/main/main.cwl")),
+        args: vec!["inputs.yml".to_string()],
+        simulate: true,
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    env::set_current_dir(current_dir).unwrap();
+    assert!(result.is_ok());
+
+    //placeholder output is written
+    let path = dir.path().join("results.svg");
+    assert!(path.exists());
+    assert_eq!(fs::metadata(path).unwrap().len(), 0);
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_dry_run() {
+    let folder = "./tests/test_data/hello_world";
+
+    let dir = tempdir().unwrap();
+    let dir_str = &dir.path().to_string_lossy();
+    copy_dir(folder, dir.path()).unwrap();
+
+    let current_dir = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    let args = LocalExecuteArgs {
+        file: PathBuf::from(format!("{dir_str}/workflows/main/main.cwl")),
+        args: vec!["inputs.yml".to_string()],
+        dry_run: true,
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    env::set_current_dir(current_dir).unwrap();
+    assert!(result.is_ok());
+
+    //neither outputs nor logs are written
+    assert!(!dir.path().join("results.svg").exists());
+    assert!(!dir.path().join("logs").exists());
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_step_logs() {
+    let path = PathBuf::from("tests/test_data/mkdir_wf.cwl");
+    let dir = tempdir().unwrap();
+
+    let args = LocalExecuteArgs {
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        is_quiet: true,
+        file: path,
+        args: vec!["--dirname".to_string(), "test_directory".to_string()],
+        ..Default::default()
+    };
+
+    assert!(execute_local(&args).is_ok());
+    assert!(fs::exists(dir.path().join("logs/mkdir.out")).unwrap());
+    assert!(fs::exists(dir.path().join("logs/mkdir.err")).unwrap());
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_scatter() {
+    let dir = tempdir().unwrap();
+
+    let args = LocalExecuteArgs {
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        is_quiet: true,
+        file: PathBuf::from("tests/test_data/scatter_wf.cwl"),
+        args: vec!["tests/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+
+    let result = execute_local(&args);
+    println!("{result:#?}");
+    assert!(result.is_ok());
+
+    //every job writes into its own folder
+    for (i, input) in ["input.txt", "input2.txt"].iter().enumerate() {
+        let output = fs::read_to_string(dir.path().join(format!("echo_{i}/results.txt"))).unwrap();
+        let expected = fs::read_to_string(Path::new("tests/test_data").join(input)).unwrap();
+        assert_eq!(output.replace("\r\n", "\n"), expected.replace("\r\n", "\n"));
+    }
+    assert!(fs::exists(dir.path().join("logs/echo_1.out")).unwrap());
+}
+
+#[test]
+#[serial]
+#[cfg(not(target_os = "windows"))]
+pub fn test_execute_local_workflow_scatter_parallel() {
+    let dir = tempdir().unwrap();
+    let markers = dir.path().join("markers");
+    //every job leaves a marker and only succeeds if the other job runs at the same time
+    let script = format!(
+        "touch {0}/$0; n=0; while [ $n -lt 20 ]; do [ `ls {0} | wc -l` -ge 2 ] && exit 0; sleep 0.1; n=`expr $n + 1`; done; exit 1",
+        markers.to_string_lossy()
+    );
+    fs::write(
+        dir.path().join("wait.cwl"),
+        format!(
+            "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: [sh, -c, {}]\ninputs:\n  name:\n    type: string\n    inputBinding:\n      position: 1\noutputs: []\n",
+            serde_json::to_string(&script).unwrap()
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("main.cwl"),
+        "class: Workflow\ncwlVersion: v1.2\nrequirements:\n  ScatterFeatureRequirement: {}\ninputs:\n  names: string[]\noutputs: []\nsteps:\n  wait:\n    run: wait.cwl\n    scatter: name\n    in:\n      name: names\n    out: []\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("job.yml"), "names: [a, b]\n").unwrap();
+
+    let run = |parallel| {
+        fs::remove_dir_all(&markers).ok();
+        fs::create_dir_all(&markers).unwrap();
+        execute_local(&LocalExecuteArgs {
+            out_dir: Some(dir.path().join("out").to_string_lossy().into_owned()),
+            is_quiet: true,
+            parallel: Some(parallel),
+            file: dir.path().join("main.cwl"),
+            args: vec![dir.path().join("job.yml").to_string_lossy().into_owned()],
+            ..Default::default()
+        })
+    };
+    assert!(run(2).is_ok());
+    //a single core runs the jobs one after another
+    assert!(run(1).is_err());
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_scatter_parallel_strict() {
+    let dir = tempdir().unwrap();
+    //the tool is only loaded by the jobs of the scattered subworkflow
+    fs::write(
+        dir.path().join("echo.cwl"),
+        "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\nunknownField: true\ninputs:\n  name:\n    type: string\n    inputBinding:\n      position: 1\noutputs: []\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("sub.cwl"),
+        "class: Workflow\ncwlVersion: v1.2\ninputs:\n  name: string\noutputs: []\nsteps:\n  echo:\n    run: echo.cwl\n    in:\n      name: name\n    out: []\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("main.cwl"),
+        "class: Workflow\ncwlVersion: v1.2\nrequirements:\n  ScatterFeatureRequirement: {}\n  SubworkflowFeatureRequirement: {}\ninputs:\n  names: string[]\noutputs: []\nsteps:\n  sub:\n    run: sub.cwl\n    scatter: name\n    in:\n      name: names\n    out: []\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("job.yml"), "names: [a, b]\n").unwrap();
+
+    let run = |mode| {
+        set_parse_mode(mode);
+        let result = execute_local(&LocalExecuteArgs {
+            out_dir: Some(dir.path().join("out").to_string_lossy().into_owned()),
+            is_quiet: true,
+            parallel: Some(2),
+            file: dir.path().join("main.cwl"),
+            args: vec![dir.path().join("job.yml").to_string_lossy().into_owned()],
+            ..Default::default()
+        });
+        set_parse_mode(ParseMode::Permissive);
+        result
+    };
+    assert!(run(ParseMode::Permissive).is_ok());
+    //jobs on worker threads load documents in the parse mode of the run
+    let error = run(ParseMode::Strict).unwrap_err().to_string();
+    assert!(error.contains("unknownField"), "{error}");
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_nested() {
+    let dir = tempdir().unwrap();
+
+    let args = LocalExecuteArgs {
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        is_quiet: true,
+        file: PathBuf::from("tests/test_data/nested_wf.cwl"),
+        args: vec!["tests/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+
+    let result = execute_local(&args);
+    println!("{result:#?}");
+    assert!(result.is_ok());
+
+    //outputs of the nested workflow are placed in a folder named after its step
+    for (i, input) in ["input.txt", "input2.txt"].iter().enumerate() {
+        let output = fs::read_to_string(dir.path().join(format!("inner/echo_{i}/results.txt"))).unwrap();
+        let expected = fs::read_to_string(Path::new("tests/test_data").join(input)).unwrap();
+        assert_eq!(output.replace("\r\n", "\n"), expected.replace("\r\n", "\n"));
+    }
+    assert!(fs::exists(dir.path().join("logs/inner/echo_1.out")).unwrap());
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_records_run() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    copy_dir("tests/test_data", dir.path().join("workflows/test_data")).unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    let args = LocalExecuteArgs {
+        out_dir: Some("out".to_string()),
+        is_quiet: true,
+        file: PathBuf::from("workflows/test_data/scatter_wf.cwl"),
+        args: vec!["workflows/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    let status = get_workflow_status(&StatusWorkflowArgs { name: None });
+    //input locations are stored relative to the project root
+    let workflow_file = Path::new("workflows/test_data/scatter_wf.cwl");
+    let runs = load_runs(dir.path()).unwrap();
+    let run = runs.get("workflows/test_data/scatter_wf.cwl").cloned();
+    let was_stale = run.as_ref().map(|r| r.is_stale(workflow_file));
+    //changing an input outdates the results
+    fs::write("workflows/test_data/input.txt", "changed").unwrap();
+    let is_stale = run.as_ref().map(|r| r.is_stale(workflow_file));
+    env::set_current_dir(current).unwrap();
+
+    assert!(result.is_ok());
+    assert!(status.is_ok());
+    let run = run.unwrap();
+    assert!(run.success);
+    assert!(run.steps.contains_key("echo"));
+    assert_eq!(was_stale, Some(false));
+    assert_eq!(is_stale, Some(true));
+    assert!(dir.path().join(".s4n/.gitignore").exists());
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_revision() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    copy_dir("tests/test_data", dir.path().join("workflows/test_data")).unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    if repo.signature().is_err() {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Derp").unwrap();
+        config.set_str("user.email", "derp@google.de").unwrap();
+    }
+    stage_all(&repo).unwrap();
+    initial_commit(&repo).unwrap();
+    let head = repo.head().unwrap().target().unwrap().to_string();
+    env::set_current_dir(dir.path()).unwrap();
+
+    //the working tree version is broken, the committed one is run
+    fs::write("workflows/test_data/scatter_wf.cwl", "class: Workflow").unwrap();
+    let args = LocalExecuteArgs {
+        out_dir: Some("out".to_string()),
+        is_quiet: true,
+        file: PathBuf::from("workflows/test_data/scatter_wf.cwl@HEAD"),
+        args: vec!["workflows/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    let missing = execute_local(&LocalExecuteArgs {
+        file: PathBuf::from("workflows/test_data/missing.cwl@HEAD"),
+        ..Default::default()
+    });
+    let history = list_workflows(&ListWorkflowArgs {
+        list_all: false,
+        history: Some("workflows/test_data/scatter_wf.cwl".to_string()),
+    });
+    let runs = load_runs(dir.path()).unwrap();
+    env::set_current_dir(current).unwrap();
+
+    assert!(result.is_ok());
+    assert!(missing.is_err());
+    assert!(history.is_ok());
+    assert_eq!(runs["workflows/test_data/scatter_wf.cwl"].commit, Some(head.clone()));
+    let revisions = get_file_history(&repo, Path::new("workflows/test_data/scatter_wf.cwl")).unwrap();
+    assert_eq!(revisions.iter().map(|r| r.id.clone()).collect::<Vec<_>>(), vec![head]);
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_chain() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    let fixture = WorkflowFactory::chain(5).write(Path::new("workflows"));
+
+    let args = LocalExecuteArgs {
+        out_dir: Some("out".to_string()),
+        is_quiet: true,
+        file: fixture.workflow,
+        args: vec![fixture.job.to_string_lossy().into_owned()],
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    let output = fs::read_to_string(Path::new("out").join(&fixture.output));
+    env::set_current_dir(current).unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(output.unwrap(), "input.txt\nstep_1.txt\nstep_2.txt\nstep_3.txt\nstep_4.txt\nstep_5.txt\n");
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_scatter_data_tree() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    let files = DataTreeFactory::default().depth(2).width(2).files(1).size(32).write(Path::new("data"));
+    let fixture = WorkflowFactory::scatter(2).inputs(&files).write(Path::new("workflows"));
+
+    let args = LocalExecuteArgs {
+        out_dir: Some("out".to_string()),
+        is_quiet: true,
+        file: fixture.workflow,
+        args: vec![fixture.job.to_string_lossy().into_owned()],
+        ..Default::default()
+    };
+    let result = execute_local(&args);
+    let outputs = WalkDir::new("out")
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy() == fixture.output)
+        .count();
+    let data_left = files.iter().all(|file| file.exists());
+    env::set_current_dir(current).unwrap();
+
+    assert!(result.is_ok());
+    assert_eq!(files.len(), 7);
+    assert_eq!(outputs, files.len());
+    assert!(data_left);
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_incremental() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    copy_dir("tests/test_data", dir.path().join("workflows/test_data")).unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    let args = LocalExecuteArgs {
+        out_dir: Some("out".to_string()),
+        is_quiet: true,
+        incremental: true,
+        file: PathBuf::from("workflows/test_data/scatter_wf.cwl"),
+        args: vec!["workflows/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+    let count_entries = || fs::read_dir(".s4n/cache/steps").unwrap().filter(|e| e.as_ref().unwrap().path().is_dir()).count();
+    let mut results = vec![];
+    let mut entries = vec![];
+    for change in [None, None, Some("workflows/test_data/input2.txt")] {
+        if let Some(file) = change {
+            fs::write(file, "changed").unwrap();
+        }
+        results.push(execute_local(&args).is_ok());
+        entries.push(count_entries());
+    }
+    let output = fs::read_to_string("out/echo_1/results.txt");
+    env::set_current_dir(current).unwrap();
+
+    assert!(results.iter().all(|r| *r));
+    //unchanged jobs are reused, only the job of the modified file runs again
+    assert_eq!(entries, vec![2, 2, 3]);
+    assert_eq!(output.unwrap(), "changed");
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_provenance() {
+    let dir = tempdir().unwrap();
+    let bundle = dir.path().join("provenance");
+
+    let args = LocalExecuteArgs {
+        out_dir: Some(dir.path().to_string_lossy().into_owned()),
+        is_quiet: true,
+        provenance: Some(bundle.clone()),
+        file: PathBuf::from("tests/test_data/scatter_wf.cwl"),
+        args: vec!["tests/test_data/scatter-job.yml".to_string()],
+        ..Default::default()
+    };
+    assert!(execute_local(&args).is_ok());
+
+    for file in [
+        "bagit.txt",
+        "manifest-sha1.txt",
+        "tagmanifest-sha1.txt",
+        "workflow/packed.cwl",
+        "workflow/primary-job.json",
+        "metadata/manifest.json",
+        "metadata/provenance/primary.cwlprov.json",
+    ] {
+        assert!(bundle.join(file).exists(), "{file} is missing");
+    }
+
+    //every scattered job is recorded together with its command
+    let prov: serde_json::Value = serde_json::from_str(&fs::read_to_string(bundle.join("metadata/provenance/primary.cwlprov.json")).unwrap()).unwrap();
+    let activities = prov["activity"].as_object().unwrap();
+    assert_eq!(activities.len(), 3);
+    assert_eq!(activities.values().filter(|a| a.get("cwlprov:command").is_some()).count(), 2);
+    assert_eq!(activities.values().filter(|a| a.get("s4n:wallTimeMs").is_some()).count(), 2);
+
+    //input files are part of the payload
+    let manifest = fs::read_to_string(bundle.join("manifest-sha1.txt")).unwrap();
+    let checksum = s4n::io::get_file_checksum("tests/test_data/input.txt").unwrap();
+    assert!(manifest.contains(&format!("data/{}/{checksum}", &checksum[..2])));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_local_cached() {
+    let cache = tempdir().unwrap();
+    let run = |out_dir: &Path| {
+        let args = LocalExecuteArgs {
+            out_dir: Some(out_dir.to_string_lossy().into_owned()),
+            is_quiet: true,
+            cache_dir: Some(cache.path().to_path_buf()),
+            file: PathBuf::from("tests/test_data/scatter_wf.cwl"),
+            args: vec!["tests/test_data/scatter-job.yml".to_string()],
+            ..Default::default()
+        };
+        execute_local(&args)
+    };
+
+    let dir = tempdir().unwrap();
+    assert!(run(dir.path()).is_ok());
+    let entries = fs::read_dir(cache.path()).unwrap().count();
+    assert_eq!(entries, 2);
+
+    //modify cached result to see whether it is used
+    let entry = fs::read_dir(cache.path()).unwrap().next().unwrap().unwrap().path();
+    fs::write(entry.join("files/results.txt"), "cached").unwrap();
+
+    let dir = tempdir().unwrap();
+    assert!(run(dir.path()).is_ok());
+    let outputs = (0..2)
+        .map(|i| fs::read_to_string(dir.path().join(format!("echo_{i}/results.txt"))).unwrap())
+        .collect::<Vec<_>>();
+    assert!(outputs.contains(&"cached".to_string()));
+    assert_eq!(fs::read_dir(cache.path()).unwrap().count(), entries);
+}
+
+/// Tool failing until it was run `fail_times` times, the attempts are counted in the given file. `after` only orders
+/// steps.
+const FLAKY_TOOL: &str = r#"cwlVersion: v1.2
+class: CommandLineTool
+
+inputs:
+- id: counter
+  type: string
+  inputBinding:
+    position: 0
+- id: fail_times
+  type: int
+  default: 2
+  inputBinding:
+    position: 1
+- id: after
+  type: Any
+
+outputs:
+- id: out
+  type: File
+  outputBinding:
+    glob: out.txt
+
+baseCommand:
+- python
+- -c
+- "import os, sys; n = int(open(sys.argv[1]).read()) + 1 if os.path.exists(sys.argv[1]) else 1; open(sys.argv[1], 'w').write(str(n)); open('out.txt', 'w').write(str(n)); sys.exit(0 if n > int(sys.argv[2]) else 1)"
+"#;
+
+const RETRY_WORKFLOW: &str = r#"cwlVersion: v1.2
+class: Workflow
+
+inputs:
+- id: counter
+  type: string
+- id: broken_counter
+  type: string
+
+outputs:
+- id: flaky_out
+  type: File
+  outputSource: flaky/out
+- id: after_out
+  type: File
+  outputSource: after_broken/out
+
+steps:
+- id: flaky
+  in:
+    counter: counter
+  run: flaky.cwl
+  out:
+  - out
+- id: broken
+  in:
+    counter: broken_counter
+    fail_times:
+      default: 100
+    after: flaky/out
+  run: flaky.cwl
+  out:
+  - out
+- id: after_broken
+  in:
+    counter: broken/out
+  run: flaky.cwl
+  out:
+  - out
+"#;
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_retries() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    fs::write("flaky.cwl", FLAKY_TOOL).unwrap();
+    fs::write("retry.cwl", RETRY_WORKFLOW).unwrap();
+    let counter = dir.path().join("counter").to_string_lossy().into_owned();
+    let broken_counter = dir.path().join("broken_counter").to_string_lossy().into_owned();
+    let job = format!("counter: {counter}\nbroken_counter: {broken_counter}\n");
+    fs::write("job.yml", &job).unwrap();
+    fs::write(
+        "job_continue.yml",
+        format!("{job}cwl:requirements:\n- class: s4n:RetryPolicy\n  maxAttempts: 3\n  onError: continue\n"),
+    )
+    .unwrap();
+
+    let run = |job: &str, retries: Option<u32>| {
+        let _ = fs::remove_file(&counter);
+        let _ = fs::remove_file(&broken_counter);
+        execute_local(&LocalExecuteArgs {
+            out_dir: Some("out".to_string()),
+            is_quiet: true,
+            retries,
+            file: PathBuf::from("retry.cwl"),
+            args: vec![job.to_string()],
+            ..Default::default()
+        })
+    };
+    //the flaky step fails twice before succeeding, the broken one always fails
+    let without_retries = run("job.yml", None);
+    let attempts_without_retries = fs::read_to_string(&counter).unwrap();
+    let with_retries = run("job.yml", Some(2));
+    let attempts_with_retries = fs::read_to_string(&counter).unwrap();
+    let continued = run("job_continue.yml", None);
+    let flaky_out = fs::read_to_string("out/out.txt");
+    let broken_attempts = fs::read_to_string(&broken_counter).unwrap();
+    let cwd = env::current_dir().unwrap();
+    env::set_current_dir(current).unwrap();
+
+    assert!(without_retries.is_err());
+    assert_eq!(attempts_without_retries, "1");
+    //flaky step succeeds, the broken one stops the run
+    assert!(with_retries.is_err());
+    assert_eq!(attempts_with_retries, "3");
+    //policy of the job file continues after the broken step and skips the step depending on it
+    assert!(continued.is_ok());
+    assert_eq!(flaky_out.unwrap(), "3");
+    assert_eq!(broken_attempts, "3");
+    assert_eq!(cwd, dir.path());
+}
+
+const CONDITIONAL_TOOL: &str = r#"cwlVersion: v1.2
+class: CommandLineTool
+
+inputs:
+- id: message
+  type: Any
+  inputBinding:
+    position: 0
+- id: name
+  type: string
+  inputBinding:
+    position: 1
+- id: run
+  type: Any
+
+outputs:
+- id: out
+  type: File
+  outputBinding:
+    glob: $(inputs.name).txt
+
+baseCommand:
+- python
+- -c
+- "import os, sys; open(sys.argv[2] + '.txt', 'w').write(os.path.basename(sys.argv[1]))"
+"#;
+
+const AFTER_TOOL: &str = r#"cwlVersion: v1.2
+class: CommandLineTool
+
+inputs:
+- id: file
+  type: File
+  inputBinding:
+    position: 0
+
+outputs:
+- id: out
+  type: File
+  outputBinding:
+    glob: after.txt
+
+baseCommand:
+- python
+- -c
+- "import os, sys; open('after.txt', 'w').write(os.path.basename(sys.argv[1]))"
+"#;
+
+const CONDITIONAL_WORKFLOW: &str = r#"cwlVersion: v1.2
+class: Workflow
+
+inputs:
+- id: run_first
+  type: boolean
+- id: keep
+  type: Any
+
+outputs:
+- id: first_out
+  type: Any
+  outputSource: first/out
+- id: after_out
+  type: File
+  outputSource: after/out
+- id: kept
+  type: Any
+  outputSource: scattered/out
+  pickValue: all_non_null
+
+requirements:
+- class: ScatterFeatureRequirement
+
+steps:
+- id: first
+  in:
+    message:
+      default: first
+    name:
+      default: first
+    run: run_first
+  run: conditional.cwl
+  when: $(inputs.run)
+  out:
+  - out
+- id: after
+  in:
+    file:
+      source: first/out
+      default:
+        class: File
+        location: fallback.txt
+  run: after.cwl
+  out:
+  - out
+- id: scattered
+  in:
+    message:
+      default: scattered
+    name:
+      source: names
+    run: keep
+  scatter: [name, run]
+  run: conditional.cwl
+  when: $(inputs.run)
+  out:
+  - out
+"#;
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_conditional() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    fs::write("conditional.cwl", CONDITIONAL_TOOL).unwrap();
+    fs::write("after.cwl", AFTER_TOOL).unwrap();
+    fs::write("fallback.txt", "").unwrap();
+    fs::write("workflow.cwl", CONDITIONAL_WORKFLOW.replace("      source: names", "      default: [a, b, c]")).unwrap();
+
+    let run = |run_first: bool, out_dir: &str| {
+        fs::write("job.yml", format!("run_first: {run_first}\nkeep: [true, false, true]\n")).unwrap();
+        execute_local(&LocalExecuteArgs {
+            out_dir: Some(out_dir.to_string()),
+            is_quiet: true,
+            file: PathBuf::from("workflow.cwl"),
+            args: vec!["job.yml".to_string()],
+            ..Default::default()
+        })
+    };
+    let executed = run(true, "executed");
+    let skipped = run(false, "skipped");
+    let contents = |path: &str| fs::read_to_string(path).unwrap_or_default();
+    let (first, after, skipped_first, skipped_after) = (
+        contents("executed/first.txt"),
+        contents("executed/after.txt"),
+        Path::new("skipped/first.txt").exists(),
+        contents("skipped/after.txt"),
+    );
+    let kept = ["a", "b", "c"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Path::new("skipped").join(format!("scattered_{i}/{name}.txt")).exists())
+        .collect::<Vec<_>>();
+    env::set_current_dir(current).unwrap();
+
+    assert!(executed.is_ok(), "{:?}", executed);
+    assert_eq!(first, "first");
+    assert_eq!(after, "first.txt");
+    //skipped step has null outputs, the step default is used instead
+    assert!(skipped.is_ok());
+    assert!(!skipped_first);
+    assert_eq!(skipped_after, "fallback.txt");
+    //jobs are skipped individually
+    assert_eq!(kept, vec![true, false, true]);
+}
+
+const GATHER_TOOL: &str = r#"cwlVersion: v1.2
+class: CommandLineTool
+
+inputs:
+- id: files
+  type: File[]
+  inputBinding:
+    position: 0
+
+outputs:
+- id: out
+  type: File
+  outputBinding:
+    glob: gathered.txt
+
+baseCommand:
+- python
+- -c
+- "import os, sys; open('gathered.txt', 'w').write(' '.join(os.path.basename(f) for f in sys.argv[1:]))"
+"#;
+
+const FAN_IN_WORKFLOW: &str = r#"cwlVersion: v1.2
+class: Workflow
+
+inputs:
+- id: run_a
+  type: boolean
+
+outputs:
+- id: gathered
+  type: File
+  outputSource: gather/out
+- id: first
+  type: File
+  outputSource: [a/out, b/out]
+  pickValue: first_non_null
+- id: all
+  type: File[]
+  outputSource: [a/out, b/out]
+  linkMerge: merge_flattened
+  pickValue: all_non_null
+
+requirements:
+- class: MultipleInputFeatureRequirement
+
+steps:
+- id: a
+  in:
+    message:
+      default: a
+    name:
+      default: a
+    run: run_a
+  run: conditional.cwl
+  when: $(inputs.run)
+  out:
+  - out
+- id: b
+  in:
+    message:
+      default: b
+    name:
+      default: b
+  run: conditional.cwl
+  out:
+  - out
+- id: gather
+  in:
+    files:
+      source: [a/out, b/out]
+      pickValue: all_non_null
+  run: gather.cwl
+  out:
+  - out
+"#;
+
+#[test]
+#[serial]
+pub fn test_execute_local_workflow_fan_in() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    fs::write("conditional.cwl", CONDITIONAL_TOOL).unwrap();
+    fs::write("gather.cwl", GATHER_TOOL).unwrap();
+    fs::write("workflow.cwl", FAN_IN_WORKFLOW).unwrap();
+
+    let run = |run_a: bool, out_dir: &str| {
+        execute_local(&LocalExecuteArgs {
+            out_dir: Some(out_dir.to_string()),
+            is_quiet: true,
+            file: PathBuf::from("workflow.cwl"),
+            args: vec!["--run_a".to_string(), run_a.to_string()],
+            ..Default::default()
+        })
+    };
+    let both = run(true, "both");
+    let only_b = run(false, "only_b");
+    let contents = |path: &str| fs::read_to_string(path).unwrap_or_default();
+    let (gathered_both, gathered_b, a_skipped) = (contents("both/gathered.txt"), contents("only_b/gathered.txt"), !Path::new("only_b/a.txt").exists());
+    env::set_current_dir(current).unwrap();
+
+    assert!(both.is_ok(), "{:?}", both);
+    assert!(only_b.is_ok(), "{:?}", only_b);
+    //values of both sources are merged, the null output of the skipped step is removed
+    assert_eq!(gathered_both, "a.txt b.txt");
+    assert_eq!(gathered_b, "b.txt");
+    assert!(a_skipped);
+}
+
+const RANGE_TOOL: &str = r#"#!/usr/bin/env cwl-runner
+cwlVersion: v1.2
+class: CommandLineTool
+baseCommand: echo
+inputs:
+  temperature:
+    type: float
+    inputBinding:
+      position: 0
+    s:unitText: degree Celsius
+    s:minValue: -10
+    s:maxValue: 40
+outputs: []
+"#;
+
+#[test]
+#[serial]
+pub fn test_execute_local_out_of_range() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    fs::write("range.cwl", RANGE_TOOL).unwrap();
+
+    let run = |temperature: &str| {
+        execute_local(&LocalExecuteArgs {
+            is_quiet: true,
+            file: PathBuf::from("range.cwl"),
+            args: vec!["--temperature".to_string(), temperature.to_string()],
+            ..Default::default()
+        })
+    };
+    let valid = run("21.5");
+    let invalid = run("55");
+    env::set_current_dir(current).unwrap();
+
+    assert!(valid.is_ok(), "{:?}", valid);
+    let error = invalid.unwrap_err().to_string();
+    assert!(error.contains("temperature: 55 degree Celsius is greater than the maximum of 40 degree Celsius"), "{error}");
+}
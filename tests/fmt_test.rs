@@ -0,0 +1,27 @@
+use s4n::commands::fmt::{find_cwl_files, format_files, FmtArgs};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+pub fn test_format_files() {
+    let dir = tempdir().unwrap();
+    let folder = dir.path().join("workflows/echo");
+    fs::create_dir_all(&folder).unwrap();
+    fs::create_dir_all(dir.path().join(".s4n")).unwrap();
+    fs::copy("tests/test_data/echo.cwl", dir.path().join(".s4n/echo.cwl")).unwrap();
+    let file = folder.join("echo.cwl");
+    fs::write(&file, "inputs: []\noutputs: []\nbaseCommand: [\"echo\", 'Hello']\nclass:   CommandLineTool\ncwlVersion: v1.2\n").unwrap();
+
+    let files = find_cwl_files(dir.path());
+    assert_eq!(files, vec![file.clone()]);
+
+    let args = FmtArgs { files, check: true };
+    assert!(format_files(&args).is_err());
+
+    format_files(&FmtArgs { check: false, ..args }).unwrap();
+    let formatted = fs::read_to_string(&file).unwrap();
+    assert!(formatted.starts_with("#!/usr/bin/env cwl-runner\n\ncwlVersion: v1.2\nclass: CommandLineTool\n"));
+    assert!(formatted.contains("baseCommand:\n- echo\n- Hello\n"));
+
+    format_files(&FmtArgs { files: vec![file], check: true }).unwrap();
+}
@@ -1,9 +1,13 @@
 mod common;
 use calamine::{open_workbook, Reader, Xlsx};
 use common::check_git_user;
-use s4n::commands::init::{create_arc_folder_structure, create_investigation_excel_file, create_minimal_folder_structure, init_git_repo, init_s4n, is_git_repo};
+use s4n::commands::init::{
+    create_arc_folder_structure, create_investigation_excel_file, create_minimal_folder_structure, import_arc, init_git_repo, init_s4n, is_git_repo,
+    PROJECT_FILE,
+};
+use s4n::repo::{initial_commit, stage_all};
 use serial_test::serial;
-use std::{env, path::PathBuf};
+use std::{env, fs, path::PathBuf};
 use tempfile::{tempdir, Builder, NamedTempFile};
 
 #[test]
@@ -252,3 +256,56 @@ fn test_is_not_git_repo() {
     // assert that it is not a git repo
     assert!(!result, "Expected directory to not be a git repo");
 }
+
+#[test]
+#[serial]
+fn test_import_arc() {
+    let temp_dir = tempdir().unwrap();
+    let base = temp_dir.path();
+    check_git_user().unwrap();
+
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let rows = [
+        ["Investigation Identifier", "my-arc"],
+        ["Investigation Title", "My \"first\" ARC"],
+        ["Investigation Person Last Name", "Doe"],
+        ["Investigation Person First Name", "Jane"],
+        ["Comment[ORCID]", "https://orcid.org/0000-0000-0000-0000"],
+    ];
+    for (i, row) in rows.iter().enumerate() {
+        sheet.write_row(i as u32, 0, row.iter().copied()).unwrap();
+    }
+    workbook.save(base.join("isa.investigation.xlsx")).unwrap();
+    fs::create_dir_all(base.join("workflows/fastqc")).unwrap();
+    fs::create_dir_all(base.join("runs/fastqc")).unwrap();
+    fs::create_dir_all(base.join("assays/measurement")).unwrap();
+    fs::write(base.join("workflows/fastqc/workflow.cwl"), "class: CommandLineTool\nbaseCommand: fastqc\ninputs: []\noutputs: []\n").unwrap();
+    fs::write(
+        base.join("runs/fastqc/run.cwl"),
+        "class: Workflow\ninputs: []\noutputs: []\nsteps:\n- id: fastqc\n  run: ../../workflows/fastqc/workflow.cwl\n  in: {}\n  out: []\n",
+    )
+    .unwrap();
+
+    //existing history is kept
+    let repo = init_git_repo(base.to_str()).unwrap();
+    stage_all(&repo).unwrap();
+    initial_commit(&repo).unwrap();
+
+    import_arc(base.to_str()).unwrap();
+
+    let project = fs::read_to_string(base.join(PROJECT_FILE)).unwrap();
+    assert!(project.contains("name = \"my-arc\""));
+    assert!(project.contains("title = \"My \\\"first\\\" ARC\""));
+    assert!(project.contains("[tools]\n\"fastqc\" = \"workflows/fastqc/workflow.cwl\""));
+    assert!(project.contains("[workflows]\n\"fastqc\" = \"runs/fastqc/run.cwl\""));
+
+    let tool = fs::read_to_string(base.join("workflows/fastqc/workflow.cwl")).unwrap();
+    assert!(tool.contains("s:name: Jane Doe"));
+    assert!(tool.contains("arc:first name: Jane"));
+
+    let mut revwalk = repo.revwalk().unwrap();
+    revwalk.push_head().unwrap();
+    assert_eq!(revwalk.count(), 2);
+    assert!(repo.statuses(None).unwrap().is_empty());
+}
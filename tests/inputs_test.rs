@@ -0,0 +1,55 @@
+use s4n::commands::inputs::{create_jobs_from_file, FromTableArgs};
+use serial_test::serial;
+use std::{env, fs, path::PathBuf};
+use tempfile::tempdir;
+
+#[test]
+#[serial]
+pub fn test_create_jobs_from_table() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    fs::create_dir_all("data").unwrap();
+    fs::write("data/a.csv", "a").unwrap();
+    fs::write(
+        "tool.cwl",
+        "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: cat\noutputs: []\ninputs:\n  file: File\n  threads: int\n",
+    )
+    .unwrap();
+    fs::write("runs.csv", "run,file,threads\nFirst Run,data/a.csv,4\nsecond,data/a.csv,8\n").unwrap();
+
+    let args = FromTableArgs {
+        table: PathBuf::from("runs.csv"),
+        sheet: None,
+        workflow: Some("tool.cwl".to_string()),
+        name_column: Some("run".to_string()),
+        outdir: PathBuf::from("jobs"),
+        sweep: false,
+        force: false,
+    };
+    create_jobs_from_file(&args).unwrap();
+    let job = fs::read_to_string("jobs/first_run.yml").unwrap();
+    assert_eq!(job, "file:\n  class: File\n  location: ../data/a.csv\nthreads: 4\n");
+    assert!(fs::read_to_string("jobs/second.yml").unwrap().contains("threads: 8\n"));
+
+    //existing jobs are kept unless forced
+    assert!(create_jobs_from_file(&args).is_err());
+    create_jobs_from_file(&FromTableArgs { force: true, ..args }).unwrap();
+
+    create_jobs_from_file(&FromTableArgs {
+        table: PathBuf::from("runs.csv"),
+        sheet: None,
+        workflow: None,
+        name_column: Some("run".to_string()),
+        outdir: PathBuf::from("sweep"),
+        sweep: true,
+        force: false,
+    })
+    .unwrap();
+    let sweep = fs::read_to_string("sweep/runs.yml").unwrap();
+    assert!(sweep.contains("threads:\n- 4\n- 8\n"));
+    assert!(sweep.contains("file:\n- class: File\n  location: ../data/a.csv\n"));
+
+    env::set_current_dir(current).unwrap();
+}
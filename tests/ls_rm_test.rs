@@ -64,6 +64,7 @@ pub fn tool_remove_test() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd_create = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd_create).is_ok());
@@ -101,6 +102,7 @@ pub fn tool_remove_test_extension() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd_create = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd_create).is_ok());
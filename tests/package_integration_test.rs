@@ -0,0 +1,80 @@
+mod common;
+use common::check_git_user;
+use git2::Repository;
+use s4n::{
+    commands::{
+        init::init_s4n,
+        package::{publish, PublishArgs},
+        workflow::{create_workflow, CreateWorkflowArgs},
+    },
+    package::RO_CRATE_METADATA,
+    repo::{commit, stage_all},
+};
+use serde_json::Value;
+use serial_test::serial;
+use std::{env, fs, fs::File, io::Read, path::PathBuf};
+use tempfile::tempdir;
+
+#[test]
+#[serial]
+pub fn test_publish_package() {
+    check_git_user().unwrap();
+
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    init_s4n(None, false).unwrap();
+    create_workflow(&CreateWorkflowArgs {
+        name: "main".to_string(),
+        force: false,
+    })
+    .unwrap();
+    fs::create_dir("data").unwrap();
+    fs::write("data/population.csv", "country,population\n").unwrap();
+    fs::write(
+        "datasets.yml",
+        "population:\n  location: data/population.csv\n  checksum: sha1$5f4ba4d1c4b2fa6d1f1e7b4fb8ef0c1c3b64a0f5\n  license: https://spdx.org/licenses/CC-BY-4.0\nclimate:\n  location: https://example.org/climate.nc\n  doi: 10.5281/zenodo.1234567\n",
+    )
+    .unwrap();
+    let args = PublishArgs {
+        version: "1.0.0".to_string(),
+        workflow: None,
+        output: None,
+        no_tag: false,
+        workflowhub: None,
+        project_id: None,
+        zenodo: None,
+        allow_secrets: false,
+        redact_secrets: false,
+    };
+    //uncommitted workflow
+    assert!(publish(&args).is_err());
+
+    let repo = Repository::open(dir.path()).unwrap();
+    stage_all(&repo).unwrap();
+    commit(&repo, "Added workflow").unwrap();
+    publish(&args).unwrap();
+
+    let name = dir.path().file_name().unwrap().to_string_lossy();
+    let archive = PathBuf::from(format!("{name}-1.0.0.crate.zip"));
+    let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+    let mut metadata = String::new();
+    zip.by_name(RO_CRATE_METADATA).unwrap().read_to_string(&mut metadata).unwrap();
+    let metadata: Value = serde_json::from_str(&metadata).unwrap();
+    let graph = metadata["@graph"].as_array().unwrap();
+    let find = |id: &str| graph.iter().find(|e| e["@id"] == id).unwrap();
+    //registered datasets are described as contextual entities
+    let population = find("data/population.csv");
+    assert_eq!(population["name"], "population");
+    assert_eq!(population["sha1"], "5f4ba4d1c4b2fa6d1f1e7b4fb8ef0c1c3b64a0f5");
+    assert_eq!(population["license"], "https://spdx.org/licenses/CC-BY-4.0");
+    assert_eq!(find("https://example.org/climate.nc")["identifier"], "https://doi.org/10.5281/zenodo.1234567");
+    assert!(zip.by_name("workflows/main/main.cwl").is_ok());
+    assert!(repo.refname_to_id("refs/tags/v1.0.0").is_ok());
+
+    //tag already exists
+    assert!(publish(&args).is_err());
+
+    env::set_current_dir(current).unwrap();
+}
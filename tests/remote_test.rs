@@ -0,0 +1,227 @@
+use s4n::commands::execute::{execute_remote, RemoteExecuteArgs};
+use serial_test::serial;
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+use tempfile::tempdir;
+
+/// Requests received by the fake server
+type Requests = Arc<Mutex<Vec<String>>>;
+
+/// Starts a fake WES server answering requests by their request line, e.g. `GET /ga4gh/wes/v1/runs/abc/status`
+fn serve(respond: fn(&str) -> (&'static str, &'static str)) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/ga4gh/wes/v1", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let received = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buffer = vec![0; 65536];
+            let mut request = String::new();
+            //read until the announced body is complete
+            loop {
+                let n = stream.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                let Some(end) = request.find("\r\n\r\n") else { continue };
+                let length = request
+                    .lines()
+                    .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break;
+                }
+            }
+            let line = request.lines().next().unwrap_or_default().rsplit_once(' ').map(|(l, _)| l).unwrap_or_default();
+            let (status, body) = respond(line);
+            let response = format!("HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+            //requests are recorded before answering, so they are known once the client got its response
+            received.lock().unwrap().push(request);
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url, requests)
+}
+
+fn write_workflow(dir: &Path) {
+    fs::write(
+        dir.join("echo.cwl"),
+        "class: CommandLineTool\ncwlVersion: v1.2\nbaseCommand: echo\ninputs:\n  message:\n    type: string\n    inputBinding:\n      position: 1\noutputs: []\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.cwl"),
+        "class: Workflow\ncwlVersion: v1.2\ninputs:\n  message: string\noutputs: []\nsteps:\n  echo:\n    run: echo.cwl\n    in:\n      message: message\n    out: []\n",
+    )
+    .unwrap();
+    fs::write(dir.join("inputs.yml"), "message: hello\n").unwrap();
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_timeout() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.2"]}}}"#),
+        "POST /ga4gh/wes/v1/runs" | "POST /ga4gh/wes/v1/runs/abc/cancel" => ("200 OK", r#"{"run_id": "abc"}"#),
+        _ => ("200 OK", r#"{"run_id": "abc", "state": "RUNNING"}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        interval: 1,
+        timeout: Some(1),
+        file: dir.path().join("main.cwl"),
+        input_file: Some(dir.path().join("inputs.yml")),
+        yes: true,
+        ..Default::default()
+    });
+    assert!(result.unwrap_err().to_string().contains("did not finish within 1 seconds"));
+    //the run is canceled on the server
+    let requests = requests.lock().unwrap();
+    assert!(requests.last().unwrap().starts_with("POST /ga4gh/wes/v1/runs/abc/cancel "));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_project_token() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.2"]}}}"#),
+        "POST /ga4gh/wes/v1/runs" => ("200 OK", r#"{"run_id": "abc"}"#),
+        "GET /ga4gh/wes/v1/runs/abc/status" => ("200 OK", r#"{"run_id": "abc", "state": "COMPLETE"}"#),
+        _ => ("200 OK", r#"{"run_id": "abc", "outputs": {}}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+    fs::create_dir(dir.path().join(".s4n")).unwrap();
+    fs::write(dir.path().join(".s4n/wes.yml"), format!("tokens:\n  {url}: project-secret\n")).unwrap();
+
+    //tokens given by the environment take precedence
+    env::remove_var("WES_TOKEN");
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        out_dir: Some("out".to_string()),
+        file: "main.cwl".into(),
+        input_file: Some("inputs.yml".into()),
+        yes: true,
+        ..Default::default()
+    });
+    env::set_current_dir(current).unwrap();
+    assert!(result.is_ok(), "{result:?}");
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().all(|r| r.contains("authorization: Bearer project-secret")));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_unsupported_version() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.0"]}}}"#),
+        _ => ("200 OK", r#"{"run_id": "abc"}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        file: dir.path().join("main.cwl"),
+        input_file: Some(dir.path().join("inputs.yml")),
+        yes: true,
+        ..Default::default()
+    });
+    assert!(result.unwrap_err().to_string().contains("supports CWL v1.0 only"));
+    //nothing is uploaded
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().all(|r| r.starts_with("GET /ga4gh/wes/v1/service-info ")));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_unsupported_requirement() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.2"]}}}"#),
+        _ => ("200 OK", r#"{"run_id": "abc"}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+    let tool = fs::read_to_string(dir.path().join("echo.cwl")).unwrap();
+    fs::write(
+        dir.path().join("echo.cwl"),
+        format!("{tool}requirements:\n- class: s4n:CondaEnvironment\n  envFile: environment.yml\n"),
+    )
+    .unwrap();
+
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        file: dir.path().join("main.cwl"),
+        input_file: Some(dir.path().join("inputs.yml")),
+        yes: true,
+        ..Default::default()
+    });
+    assert!(result.unwrap_err().to_string().contains("requires s4n:CondaEnvironment"));
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().all(|r| r.starts_with("GET /ga4gh/wes/v1/service-info ")));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_commandlinetool() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.2"]}}}"#),
+        "POST /ga4gh/wes/v1/runs" => ("200 OK", r#"{"run_id": "abc"}"#),
+        "GET /ga4gh/wes/v1/runs/abc/status" => ("200 OK", r#"{"run_id": "abc", "state": "COMPLETE"}"#),
+        _ => ("200 OK", r#"{"run_id": "abc", "outputs": {}}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        out_dir: Some(dir.path().join("out").to_string_lossy().into_owned()),
+        file: dir.path().join("echo.cwl"),
+        input_file: Some(dir.path().join("inputs.yml")),
+        yes: true,
+        ..Default::default()
+    });
+    assert!(result.is_ok(), "{result:?}");
+    //the tool is submitted as packed document with itself as entrypoint
+    let requests = requests.lock().unwrap();
+    let submission = requests.iter().find(|r| r.starts_with("POST /ga4gh/wes/v1/runs ")).unwrap();
+    assert!(submission.contains(r#""$graph":[{"#));
+    assert!(submission.contains(r#""class":"CommandLineTool""#));
+    assert!(submission.contains(r##""id":"#main""##));
+    assert!(submission.contains("echo.cwl"));
+}
+
+#[test]
+#[serial]
+pub fn test_execute_remote_requires_confirmation() {
+    let (url, requests) = serve(|line| match line {
+        "GET /ga4gh/wes/v1/service-info" => ("200 OK", r#"{"workflow_type_versions": {"CWL": {"workflow_type_version": ["v1.2"]}}}"#),
+        _ => ("200 OK", r#"{"run_id": "abc"}"#),
+    });
+    let dir = tempdir().unwrap();
+    write_workflow(dir.path());
+
+    //tests do not run in a terminal, so there is no one to confirm the run
+    let result = execute_remote(&RemoteExecuteArgs {
+        server: url,
+        file: dir.path().join("main.cwl"),
+        input_file: Some(dir.path().join("inputs.yml")),
+        ..Default::default()
+    });
+    assert!(result.unwrap_err().to_string().contains("use --yes"));
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().all(|r| r.starts_with("GET /ga4gh/wes/v1/service-info ")));
+}
@@ -4,6 +4,7 @@ use cwl::{clt::CommandLineTool, types::DefaultValue};
 use s4n::execution::runner::{run_command, run_commandlinetool};
 use serial_test::serial;
 use std::{collections::HashMap, fs};
+use tempfile::tempdir;
 
 #[test]
 #[serial]
@@ -126,6 +127,75 @@ message:
     });
 }
 
+#[test]
+#[serial]
+pub fn test_run_command_binary_stdout() {
+    with_temp_repository(|dir| {
+        let cwl = r#"
+cwlVersion: v1.2
+class: CommandLineTool
+
+inputs: []
+baseCommand: [python, -c, "import sys; sys.stdout.buffer.write(bytes(range(256)))"]
+
+stdout: output.bin
+
+outputs:
+- id: output
+  type: stdout
+"#;
+        let tool: CommandLineTool = serde_yaml::from_str(cwl).expect("Tool parsing failed");
+        assert!(run_command(&tool, None).is_ok());
+
+        //bytes must not be altered by utf-8 conversion
+        let contents = fs::read(dir.path().join("output.bin")).expect("Could not read output");
+        assert_eq!(contents, (0..=255).collect::<Vec<u8>>());
+    });
+}
+
+#[test]
+#[serial]
+pub fn test_run_commandlinetool_binary_listing() {
+    let dir = tempdir().unwrap();
+    let model = dir.path().join("model.rds");
+    fs::write(&model, [0u8, 159, 146, 150, 255]).unwrap();
+    let cwl = format!(
+        r"
+cwlVersion: v1.2
+class: CommandLineTool
+
+requirements:
+- class: InitialWorkDirRequirement
+  listing:
+  - entryname: model.rds
+    entry: $(inputs.model)
+
+inputs:
+- id: model
+  type: File
+  default:
+    class: File
+    location: {}
+
+outputs:
+- id: copy
+  type: File
+  outputBinding:
+    glob: copy.rds
+
+baseCommand: [cp, model.rds, copy.rds]
+",
+        model.to_string_lossy()
+    );
+
+    let mut tool: CommandLineTool = serde_yaml::from_str(&cwl).expect("Tool parsing failed");
+    let out_dir = tempdir().unwrap();
+    let result = run_commandlinetool(&mut tool, None, None, Some(out_dir.path().to_string_lossy().into_owned()));
+    result.unwrap();
+    //binary files are staged as they are instead of being inlined as text
+    assert_eq!(fs::read(out_dir.path().join("copy.rds")).unwrap(), vec![0u8, 159, 146, 150, 255]);
+}
+
 #[test]
 #[serial]
 pub fn test_run_commandlinetool() {
@@ -172,3 +242,90 @@ baseCommand:
         Err(e) => eprintln!("{e:?}"),
     }
 }
+
+#[test]
+#[serial]
+#[cfg(feature = "javascript")]
+pub fn test_run_commandlinetool_javascript() {
+    use cwl::types::OutputItem;
+
+    with_temp_repository(|dir| {
+        let cwl = r#"
+#!/usr/bin/env cwl-runner
+
+cwlVersion: v1.2
+class: CommandLineTool
+
+requirements:
+- class: InlineJavascriptRequirement
+  expressionLib:
+  - "function shout(text) { return text.toUpperCase() + '!'; }"
+
+inputs:
+- id: name
+  type: string
+  default: hello
+
+outputs:
+- id: greeting
+  type: File
+  outputBinding:
+    glob: $(inputs.name + ".txt")
+- id: length
+  type: int
+  outputBinding:
+    glob: hello.txt
+    loadContents: true
+    outputEval: ${ return self[0].contents.trim().length; }
+
+baseCommand: echo
+arguments:
+- $(shout(inputs.name))
+
+stdout: $(inputs.name + ".txt")
+"#;
+        let mut tool: CommandLineTool = serde_yaml::from_str(cwl).expect("Tool parsing failed");
+        let outputs = run_commandlinetool(&mut tool, None, None, None).expect("Tool execution failed");
+
+        let contents = fs::read_to_string(dir.path().join("hello.txt")).expect("Could not read output");
+        assert_eq!(contents.trim(), "HELLO!");
+        assert_eq!(outputs["length"], OutputItem::OutputString("6".to_string()));
+    });
+}
+
+#[test]
+#[serial]
+pub fn test_run_commandlinetool_unpack_archive() {
+    let cwl = r"
+#!/usr/bin/env cwl-runner
+
+cwlVersion: v1.2
+class: CommandLineTool
+
+hints:
+  s4n:UnpackArchive:
+    outputs: [data]
+    keepArchive: true
+
+inputs: []
+
+outputs:
+- id: data
+  type: Directory
+  outputBinding:
+    glob: data.tar.gz
+
+baseCommand:
+- sh
+- -c
+- echo Hello Archive > hello.txt && tar -czf data.tar.gz hello.txt
+";
+    let dir = tempdir().unwrap();
+    let mut tool: CommandLineTool = serde_yaml::from_str(cwl).expect("Tool parsing failed");
+    let result = run_commandlinetool(&mut tool, None, None, Some(dir.path().to_string_lossy().into_owned()));
+    assert!(result.is_ok(), "{result:?}");
+
+    let contents = fs::read_to_string(dir.path().join("data/hello.txt")).unwrap();
+    assert_eq!(contents.trim(), "Hello Archive");
+    assert!(dir.path().join("data.tar.gz").exists());
+}
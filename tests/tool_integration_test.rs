@@ -3,16 +3,19 @@ use common::{os_path, with_temp_repository};
 use cwl::{
     clt::{Argument, CommandLineTool},
     requirements::{DockerRequirement, Requirement},
-    types::Entry,
+    types::{DefaultValue, Entry},
     load_tool,
 };
 use git2::Repository;
 use s4n::{
-    commands::tool::{create_tool, handle_tool_commands, CreateToolArgs, ToolCommands},
-    repo::get_modified_files,
+    commands::tool::{create_tool, handle_tool_commands, CreateToolArgs, MigrateToolArgs, ToolCommands},
+    repo::{commit, get_modified_files, stage_all},
 };
 use serial_test::serial;
-use std::{fs::read_to_string, path::Path};
+use std::{
+    fs::{self, read_to_string},
+    path::Path,
+};
 
 #[test]
 #[serial]
@@ -34,6 +37,7 @@ pub fn tool_create_test() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -73,6 +77,7 @@ pub fn tool_create_test_is_raw() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -105,6 +110,7 @@ pub fn tool_create_test_no_commit() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -143,6 +149,7 @@ pub fn tool_create_test_no_run() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -154,6 +161,91 @@ pub fn tool_create_test_no_run() {
     });
 }
 
+#[test]
+#[serial]
+pub fn tool_create_test_secrets() {
+    with_temp_repository(|dir| {
+        let args = CreateToolArgs {
+            no_run: true,
+            command: vec![
+                "python".to_string(),
+                "scripts/echo.py".to_string(),
+                "--test".to_string(),
+                "data/input.txt".to_string(),
+                "--api-token".to_string(),
+                "s3cr3t-value".to_string(),
+            ],
+            ..Default::default()
+        };
+        let path = dir.path().join("workflows/echo/echo.cwl");
+        assert!(create_tool(&args).is_err());
+        assert!(!path.exists());
+
+        create_tool(&CreateToolArgs {
+            redact_secrets: true,
+            ..args.clone()
+        })
+        .unwrap();
+        let contents = read_to_string(&path).unwrap();
+        assert!(contents.contains("REDACTED"));
+        assert!(!contents.contains("s3cr3t-value"));
+        let repo = Repository::open(dir.path()).unwrap();
+        let message = repo.head().unwrap().peel_to_commit().unwrap().message().unwrap().to_string();
+        assert!(!message.contains("s3cr3t-value"));
+
+        fs::remove_file(&path).unwrap();
+        stage_all(&repo).unwrap();
+        commit(&repo, "Removed tool").unwrap();
+        create_tool(&CreateToolArgs { allow_secrets: true, ..args }).unwrap();
+        assert!(read_to_string(&path).unwrap().contains("s3cr3t-value"));
+    });
+}
+
+#[test]
+#[serial]
+pub fn tool_create_test_from_help() {
+    with_temp_repository(|dir| {
+        let tool_create_args = CreateToolArgs {
+            from_help: true,
+            outputs: Some(vec!["results.txt".to_string()]),
+            command: vec!["python".to_string(), "scripts/echo.py".to_string()],
+            ..Default::default()
+        };
+        assert!(handle_tool_commands(&ToolCommands::Create(tool_create_args)).is_ok());
+        //the script is not run
+        assert!(!dir.path().join("results.txt").exists());
+
+        let tool = load_tool("workflows/echo/echo.cwl").unwrap();
+        assert_eq!(tool.inputs.len(), 1);
+        assert_eq!(tool.inputs[0].id, "test");
+        assert_eq!(tool.inputs[0].doc, Some("test argument pointing to txt file".to_string()));
+        assert_eq!(tool.inputs[0].input_binding.as_ref().unwrap().prefix, Some("--test".to_string()));
+        assert_eq!(tool.outputs[0].id, "results");
+    });
+}
+
+#[test]
+#[serial]
+pub fn tool_create_test_from_notebook() {
+    with_temp_repository(|dir| {
+        let tool_create_args = CreateToolArgs {
+            from_notebook: Some("scripts/analysis.ipynb".to_string()),
+            no_run: true,
+            ..Default::default()
+        };
+        assert!(handle_tool_commands(&ToolCommands::Create(tool_create_args)).is_ok());
+        assert!(dir.path().join("workflows/analysis/Dockerfile").exists());
+
+        let tool = load_tool("workflows/analysis/analysis.cwl").unwrap();
+        let ids = tool.inputs.iter().map(|i| i.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["input_file", "min_length"]);
+        assert!(matches!(&tool.inputs[0].default, Some(DefaultValue::File(file)) if file.location == "../../data/input.txt"));
+        assert_eq!(tool.inputs[0].doc, Some("text to count the lines of".to_string()));
+        assert_eq!(tool.outputs[0].id, "analysis_executed");
+        assert!(tool.hints.iter().flatten().any(|h| matches!(h, Requirement::DockerRequirement(_))));
+    });
+}
+
 #[test]
 #[serial]
 pub fn tool_create_test_is_clean() {
@@ -174,6 +266,7 @@ pub fn tool_create_test_is_clean() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -206,6 +299,7 @@ pub fn tool_create_test_container_image() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -250,6 +344,7 @@ pub fn tool_create_test_dockerfile() {
                 "--test".to_string(),
                 "data/input.txt".to_string(),
             ],
+            ..Default::default()
         };
         let cmd = ToolCommands::Create(tool_create_args);
         assert!(handle_tool_commands(&cmd).is_ok());
@@ -279,6 +374,56 @@ pub fn tool_create_test_dockerfile() {
     });
 }
 
+#[test]
+#[serial]
+pub fn tool_create_test_generated_dockerfile() {
+    with_temp_repository(|dir| {
+        let tool_create_args = CreateToolArgs {
+            name: None,
+            container_image: Some("auto".to_string()),
+            container_tag: None,
+            is_raw: false,
+            no_commit: false,
+            no_run: false,
+            is_clean: false,
+            inputs: None,
+            outputs: None,
+            command: vec![
+                "python".to_string(),
+                "scripts/echo.py".to_string(),
+                "--test".to_string(),
+                "data/input.txt".to_string(),
+            ],
+            ..Default::default()
+        };
+        let cmd = ToolCommands::Create(tool_create_args);
+        assert!(handle_tool_commands(&cmd).is_ok());
+
+        let cwl_file = dir.path().join(Path::new("workflows/echo/echo.cwl"));
+        let cwl_contents = read_to_string(cwl_file).expect("Could not read CWL File");
+        let cwl: CommandLineTool = serde_yaml::from_str(&cwl_contents).expect("Could not convert CWL");
+
+        let hints = cwl.hints.expect("No hints found!");
+        if let Requirement::DockerRequirement(DockerRequirement::DockerFile {
+            docker_file,
+            docker_image_id,
+        }) = &hints[0]
+        {
+            assert_eq!(*docker_file, Entry::from_file("Dockerfile")); //generated next to the CWL file
+            assert_eq!(*docker_image_id, "sciwin-echo".to_string());
+        } else {
+            panic!("Hint is not a Dockerfile");
+        }
+
+        let dockerfile = read_to_string(dir.path().join("workflows/echo/Dockerfile")).expect("Could not read Dockerfile");
+        assert!(dockerfile.starts_with("FROM python"));
+
+        //no uncommitted left?
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(get_modified_files(&repo).is_empty());
+    });
+}
+
 #[test]
 #[serial]
 pub fn test_tool_magic_outputs() {
@@ -295,6 +440,7 @@ pub fn test_tool_magic_outputs() {
             inputs: None,
             outputs: None,
             command: shlex::split(str).unwrap(),
+            ..Default::default()
         };
 
         assert!(create_tool(&args).is_ok());
@@ -321,6 +467,7 @@ pub fn test_tool_magic_stdout() {
             inputs: None,
             outputs: None,
             command: shlex::split(str).unwrap(),
+            ..Default::default()
         };
 
         assert!(create_tool(&args).is_ok());
@@ -346,6 +493,7 @@ pub fn test_tool_magic_arguments() {
             inputs: None,
             outputs: None,
             command: shlex::split(str).unwrap(),
+            ..Default::default()
         };
 
         assert!(create_tool(&args).is_ok());
@@ -358,3 +506,116 @@ pub fn test_tool_magic_arguments() {
         }
     });
 }
+
+#[test]
+#[serial]
+#[cfg(unix)]
+pub fn tool_env_capture_test() {
+    use s4n::commands::tool::{CaptureEnvArgs, ToolEnvCommands};
+    use std::{env, fs, os::unix::fs::PermissionsExt};
+
+    with_temp_repository(|dir| {
+        let tool_create_args = CreateToolArgs {
+            command: vec!["python".to_string(), "scripts/echo.py".to_string(), "--test".to_string(), "data/input.txt".to_string()],
+            ..Default::default()
+        };
+        assert!(create_tool(&tool_create_args).is_ok());
+
+        //fake conda printing a fixed environment
+        let bin = tempfile::tempdir().unwrap();
+        let conda = bin.path().join("conda");
+        fs::write(
+            &conda,
+            "#!/bin/sh\nprintf 'name: base\\nchannels:\\n  - conda-forge\\ndependencies:\\n  - numpy=1.26.4\\nprefix: /opt/conda\\n'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&conda, fs::Permissions::from_mode(0o755)).unwrap();
+        env::set_var("MAMBA_EXE", &conda);
+        let result = handle_tool_commands(&ToolCommands::Env {
+            command: ToolEnvCommands::Capture(CaptureEnvArgs {
+                tool_name: "echo".to_string(),
+                no_commit: false,
+            }),
+        });
+        env::remove_var("MAMBA_EXE");
+        assert!(result.is_ok());
+
+        let environment = read_to_string(dir.path().join("workflows/echo/environment.yml")).unwrap();
+        assert!(environment.contains("name: echo"));
+        assert!(!environment.contains("prefix"));
+
+        let tool = load_tool(dir.path().join("workflows/echo/echo.cwl")).unwrap();
+        let hints = tool.hints.expect("No hints found!");
+        assert!(hints.iter().any(|h| matches!(h, Requirement::SoftwareRequirement(r) if r.packages[0].package == "numpy")));
+        assert!(hints.iter().any(|h| matches!(h, Requirement::CondaEnvironment(c) if c.env_file == "environment.yml")));
+
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(get_modified_files(&repo).is_empty());
+    });
+}
+
+#[test]
+#[serial]
+pub fn tool_create_test_binary_input() {
+    with_temp_repository(|dir| {
+        fs::write(dir.path().join("data/model.bin"), [0u8, 159, 146, 150, 255]).unwrap();
+        let repo = Repository::open(dir.path()).unwrap();
+        stage_all(&repo).unwrap();
+        commit(&repo, "Add model").unwrap();
+        let tool_create_args = CreateToolArgs {
+            inputs: Some(vec!["data/model.bin".to_string()]),
+            no_run: true,
+            command: vec!["python".to_string(), "scripts/echo.py".to_string()],
+            ..Default::default()
+        };
+        assert!(handle_tool_commands(&ToolCommands::Create(tool_create_args)).is_ok());
+
+        let tool = load_tool("workflows/echo/echo.cwl").unwrap();
+        let input = tool.inputs.iter().find(|i| i.id == "model").unwrap();
+        assert!(matches!(&input.default, Some(DefaultValue::File(file)) if file.location == "../../data/model.bin"));
+        let Some(Requirement::InitialWorkDirRequirement(iwdr)) = &tool.requirements.as_ref().unwrap().first() else {
+            panic!("InitialWorkDirRequirement missing")
+        };
+        let entries = iwdr.listing.iter().map(|l| l.entry.clone()).collect::<Vec<_>>();
+        assert!(entries.contains(&Entry::Source("$(inputs.model)".to_string())));
+        //text files are still included
+        assert!(entries.iter().any(|e| matches!(e, Entry::Include(_))));
+    });
+}
+
+#[test]
+#[serial]
+pub fn tool_migrate_test() {
+    with_temp_repository(|dir| {
+        fs::create_dir_all(dir.path().join("workflows/model")).unwrap();
+        fs::write(dir.path().join("workflows/model/model.rds"), [0u8, 159, 146, 150, 255]).unwrap();
+        let cwl = "class: CommandLineTool
+cwlVersion: v1.2
+s:author: Jane Doe
+requirements:
+- class: InitialWorkDirRequirement
+  listing:
+  - entryname: model.rds
+    entry:
+      $include: model.rds
+inputs: []
+outputs: []
+baseCommand: [Rscript, -e, 'readRDS(\"model.rds\")']
+";
+        fs::write(dir.path().join("workflows/model/model.cwl"), cwl).unwrap();
+
+        let args = MigrateToolArgs {
+            tool_name: None,
+            no_commit: false,
+        };
+        assert!(handle_tool_commands(&ToolCommands::Migrate(args)).is_ok());
+
+        let contents = fs::read_to_string(dir.path().join("workflows/model/model.cwl")).unwrap();
+        assert!(contents.contains("s:author: Jane Doe"));
+        assert!(contents.contains("entry: $(inputs.model)"));
+        let tool = load_tool("workflows/model/model.cwl").unwrap();
+        assert!(matches!(&tool.inputs[0].default, Some(DefaultValue::File(file)) if file.location == "model.rds"));
+        let repo = Repository::open(dir.path()).unwrap();
+        assert!(!get_modified_files(&repo).iter().any(|f| f.ends_with("model.cwl")));
+    });
+}
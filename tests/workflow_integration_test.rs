@@ -1,20 +1,24 @@
 mod common;
 use assert_cmd::Command;
 use common::check_git_user;
-use cwl::load_workflow;
+use cwl::{inputs::WorkflowStepInput, load_workflow, requirements::Requirement};
 use predicates::prelude::*;
 use s4n::{
     commands::{
         init::init_s4n,
         workflow::{
-            connect_workflow_nodes, create_workflow, disconnect_workflow_nodes, list_workflows, remove_workflow, ConnectWorkflowArgs,
-            CreateWorkflowArgs, ListWorkflowArgs, RemoveWorkflowArgs,
+            collect_garbage, compose_workflow, connect_workflow_nodes, create_workflow, disconnect_workflow_nodes, find_orphans, list_workflows, pack,
+            remove_workflow, ComposeWorkflowArgs, ConnectWorkflowArgs, CreateWorkflowArgs, GcWorkflowArgs, ListWorkflowArgs, PackWorkflowArgs,
+            RemoveWorkflowArgs, GC_ARCHIVE,
         },
     },
     io::create_and_write_file,
 };
 use serial_test::serial;
-use std::{env, fs, path::Path};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 use tempfile::tempdir;
 
 #[test]
@@ -56,7 +60,7 @@ pub fn test_remove_workflow() {
     let target = "workflows/test/test.cwl";
     assert!(fs::exists(target).unwrap());
 
-    list_workflows(&ListWorkflowArgs { list_all: true }).unwrap();
+    list_workflows(&ListWorkflowArgs { list_all: true, history: None }).unwrap();
 
     remove_workflow(&RemoveWorkflowArgs {
         rm_workflow: vec![target.to_string()],
@@ -177,6 +181,147 @@ pub fn test_workflow() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[serial]
+pub fn test_connect_workflow_selectors() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    create_and_write_file("workflows/calculation/calculation.cwl", CALCULATION_FILE).unwrap();
+    create_and_write_file("workflows/plot/plot.cwl", PLOT_FILE).unwrap();
+    create_workflow(&CreateWorkflowArgs {
+        name: "test".to_string(),
+        force: false,
+    })
+    .unwrap();
+
+    let connect = |from: &str, to: &str| {
+        connect_workflow_nodes(&ConnectWorkflowArgs {
+            name: "test".to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    };
+    connect("@inputs/speakers", "calculation/speakers").unwrap();
+    connect("calculation/results[0]", "plot/results").unwrap();
+    connect("@inputs/speakers", "@outputs/speakers").unwrap();
+
+    let error = connect("calculation/result", "plot/results").unwrap_err();
+    assert!(error.to_string().contains("available outputs are: results"));
+    let error = connect("@inputs/speakers", "plot/result").unwrap_err();
+    assert!(error.to_string().contains("available inputs are: results"));
+    assert!(connect("@outputs/speakers", "plot/results").is_err());
+
+    let workflow = load_workflow("workflows/test/test.cwl").unwrap();
+    let step = workflow.get_step("plot").unwrap();
+    let WorkflowStepInput::Parameter(input) = &step.in_["results"] else {
+        panic!("selected item is not connected using valueFrom");
+    };
+    assert_eq!(input.source, Some("calculation/results".into()));
+    assert_eq!(input.value_from.as_deref(), Some("$(self[0])"));
+    assert!(workflow
+        .requirements
+        .iter()
+        .flatten()
+        .any(|r| matches!(r, Requirement::StepInputExpressionRequirement)));
+    assert!(workflow.outputs.iter().any(|o| o.id == "speakers" && o.output_source == "speakers"));
+
+    disconnect_workflow_nodes(&ConnectWorkflowArgs {
+        name: "test".to_string(),
+        from: "@inputs/speakers".to_string(),
+        to: "@outputs/speakers".to_string(),
+    })
+    .unwrap();
+    assert!(!load_workflow("workflows/test/test.cwl").unwrap().has_output("speakers"));
+
+    env::set_current_dir(current).unwrap();
+}
+
+#[test]
+#[serial]
+pub fn test_compose_workflow() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    create_and_write_file("workflows/calculation/calculation.cwl", CALCULATION_FILE).unwrap();
+    create_and_write_file("workflows/plot/plot.cwl", PLOT_FILE).unwrap();
+
+    let args = ComposeWorkflowArgs {
+        name: "main".to_string(),
+        steps: vec!["calculation".to_string(), "plot".to_string()],
+        auto_connect: true,
+        force: false,
+    };
+    compose_workflow(&args).unwrap();
+
+    let workflow = load_workflow("workflows/main/main.cwl").unwrap();
+    assert!(workflow.has_step("calculation"));
+    assert!(workflow.has_step("plot"));
+    assert!(workflow.has_input("population"));
+    assert!(workflow.has_input("speakers"));
+    assert!(!workflow.has_input("results"));
+    assert!(workflow.has_step_input("calculation/results"));
+    assert_eq!(workflow.outputs.len(), 1);
+    assert_eq!(workflow.outputs[0].output_source, "plot/results");
+
+    //existing workflows are only replaced with force
+    assert!(compose_workflow(&args).is_err());
+    assert!(compose_workflow(&ComposeWorkflowArgs { force: true, ..args }).is_ok());
+
+    env::set_current_dir(current).unwrap();
+}
+
+#[test]
+#[serial]
+pub fn test_collect_garbage() {
+    let dir = tempdir().unwrap();
+    let current = env::current_dir().unwrap();
+    env::set_current_dir(dir.path()).unwrap();
+
+    create_and_write_file("workflows/calculation/calculation.cwl", CALCULATION_FILE).unwrap();
+    create_and_write_file("workflows/plot/plot.cwl", PLOT_FILE).unwrap();
+    compose_workflow(&ComposeWorkflowArgs {
+        name: "main".to_string(),
+        steps: vec!["calculation".to_string(), "plot".to_string()],
+        auto_connect: true,
+        force: false,
+    })
+    .unwrap();
+    //steps of workflows are in use, even if they are not at the usual location
+    create_and_write_file("workflows/main/variants/plot_v2.cwl", PLOT_FILE).unwrap();
+    let mut workflow = fs::read_to_string("workflows/main/main.cwl").unwrap();
+    workflow = workflow.replace("run: ../plot/plot.cwl", "run: variants/plot_v2.cwl");
+    fs::write("workflows/main/main.cwl", workflow).unwrap();
+
+    create_and_write_file("workflows/calculation/calculation_old.cwl", CALCULATION_FILE).unwrap();
+    create_and_write_file("workflows/leftover/data.csv", "a,b").unwrap();
+
+    let orphans = find_orphans(Path::new("workflows/")).unwrap();
+    assert_eq!(
+        orphans,
+        vec![
+            PathBuf::from("workflows/calculation/calculation_old.cwl"),
+            PathBuf::from("workflows/leftover")
+        ]
+    );
+
+    collect_garbage(&GcWorkflowArgs {
+        delete: false,
+        archive: true,
+    })
+    .unwrap();
+    assert!(orphans.iter().all(|o| !o.exists()));
+    assert!(Path::new("workflows/plot/plot.cwl").exists());
+    assert!(Path::new("workflows/main/variants/plot_v2.cwl").exists());
+    let archives = fs::read_dir(GC_ARCHIVE).unwrap().filter_map(Result::ok).filter(|e| e.path().extension().is_some_and(|e| e == "gz"));
+    assert_eq!(archives.count(), 1);
+    assert!(find_orphans(Path::new("workflows/")).unwrap().is_empty());
+
+    env::set_current_dir(current).unwrap();
+}
+
 const CALCULATION_FILE: &str = r"#!/usr/bin/env cwl-runner
 
 cwlVersion: v1.2
@@ -247,3 +392,45 @@ baseCommand:
 - python
 - plot.py
 ";
+
+#[test]
+#[serial]
+pub fn test_diff_workflow() {
+    let output = Command::cargo_bin("s4n")
+        .unwrap()
+        .args(["workflow", "diff", "tests/test_data/test-wf.cwl", "tests/test_data/test-wf_features.cwl", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let diff: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(diff["steps"][0]["kind"], "added");
+    assert_eq!(diff["steps"][0]["id"], "default");
+    assert_eq!(diff["outputs"][0]["new"], "File ← default/out");
+    assert_eq!(diff["inputs"].as_array().map(Vec::len), Some(0));
+
+    Command::cargo_bin("s4n")
+        .unwrap()
+        .args(["workflow", "diff", "tests/test_data/test-wf.cwl", "tests/test_data/test-wf.cwl"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No structural changes"));
+}
+
+#[test]
+#[serial]
+pub fn test_pack_workflow() {
+    let dir = tempdir().unwrap();
+    let output = dir.path().join("packed.cwl");
+    let args = PackWorkflowArgs {
+        workflow: "tests/test_data/hello_world/workflows/main/main.cwl".to_string(),
+        output: Some(output.to_string_lossy().into_owned()),
+    };
+    pack(&args).expect("Could not pack workflow");
+
+    let contents = fs::read_to_string(output).unwrap();
+    let packed: cwl::packed::PackedCWL = serde_yaml::from_str(&contents).unwrap();
+    assert_eq!(packed.graph.len(), 3);
+    assert!(packed.get_process("#main").is_some());
+}